@@ -0,0 +1,45 @@
+//! Compares updating a variable through arithmetic (whose string form is only
+//! built once it is read) against formatting its value on every update.
+
+use conch_runtime::env::{
+    ArithVariableEnvironment, DefaultEnvArc, DefaultEnvConfigArc, TokioFileDescManagerEnv,
+    VariableEnvironment,
+};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::sync::Arc;
+
+fn new_env() -> DefaultEnvArc {
+    let mut cfg = DefaultEnvConfigArc::new().expect("failed to create env cfg");
+    cfg.file_desc_manager_env = TokioFileDescManagerEnv::new();
+    DefaultEnvArc::with_config(cfg)
+}
+
+fn arith_var(c: &mut Criterion) {
+    let mut env = new_env();
+    let name = Arc::new("i".to_owned());
+
+    c.bench_function("increment formatted", |b| {
+        b.iter(|| {
+            let val = env.arith_var(black_box(&name)) + 1;
+            env.set_var(name.clone(), Arc::new(val.to_string()));
+        })
+    });
+
+    c.bench_function("increment arith", |b| {
+        b.iter(|| {
+            let val = env.arith_var(black_box(&name)) + 1;
+            env.set_arith_var(name.clone(), val);
+        })
+    });
+
+    c.bench_function("increment arith then read", |b| {
+        b.iter(|| {
+            let val = env.arith_var(black_box(&name)) + 1;
+            env.set_arith_var(name.clone(), val);
+            black_box(env.var(&name));
+        })
+    });
+}
+
+criterion_group!(benches, arith_var);
+criterion_main!(benches);
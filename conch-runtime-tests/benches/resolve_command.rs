@@ -0,0 +1,64 @@
+//! Compares resolving command names through `Env`'s cache against looking
+//! them up within the function and builtin environments on every invocation.
+
+use conch_parser::ast::builder::ArcBuilder;
+use conch_runtime::env::{
+    BuiltinEnvironment, DefaultEnvArc, DefaultEnvConfigArc, FunctionEnvironment,
+    ResolveCommandEnvironment, ResolvedCommand, TokioFileDescManagerEnv,
+};
+use conch_runtime::spawn::Session;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::sync::Arc;
+
+fn new_env() -> DefaultEnvArc {
+    let mut cfg = DefaultEnvConfigArc::new().expect("failed to create env cfg");
+    cfg.file_desc_manager_env = TokioFileDescManagerEnv::new();
+
+    let mut session = Session::new(DefaultEnvArc::with_config(cfg));
+    let batch = session
+        .parse::<ArcBuilder>("greet() { :; }")
+        .expect("failed to parse script");
+
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    rt.block_on(session.execute(batch)).unwrap();
+    session.into_env()
+}
+
+fn uncached(
+    env: &DefaultEnvArc,
+    name: &Arc<String>,
+) -> ResolvedCommand<
+    <DefaultEnvArc as FunctionEnvironment>::Fn,
+    <DefaultEnvArc as BuiltinEnvironment>::Builtin,
+> {
+    if let Some(func) = env.function(name) {
+        ResolvedCommand::Function(func.clone())
+    } else if let Some(builtin) = env.builtin(name) {
+        ResolvedCommand::Builtin(builtin)
+    } else {
+        ResolvedCommand::External
+    }
+}
+
+fn resolve_command(c: &mut Criterion) {
+    let mut env = new_env();
+
+    for &(kind, name) in &[
+        ("function", "greet"),
+        ("builtin", "wait"),
+        ("external", "ls"),
+    ] {
+        let name = Arc::new(name.to_owned());
+
+        c.bench_function(&format!("resolve {} uncached", kind), |b| {
+            b.iter(|| uncached(&env, black_box(&name)))
+        });
+
+        c.bench_function(&format!("resolve {} cached", kind), |b| {
+            b.iter(|| env.resolve_command(black_box(&name)))
+        });
+    }
+}
+
+criterion_group!(benches, resolve_command);
+criterion_main!(benches);
@@ -0,0 +1,264 @@
+//! A differential conformance runner which executes a script both through
+//! `conch-runtime` and the system shell, and compares their observable behavior.
+//!
+//! ```no_run
+//! use conch_runtime_tests::conformance::ConformanceRunner;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! ConformanceRunner::new()
+//!     .check("x=foo; echo \"$x\" bar")
+//!     .await
+//!     .unwrap();
+//! # }
+//! ```
+
+use conch_parser::ast::builder::ArcBuilder;
+use conch_parser::lexer::Lexer;
+use conch_parser::parse::Parser;
+use conch_runtime::env::{
+    AsyncIoEnvironment, DefaultEnvArc, DefaultEnvConfigArc, FileDescEnvironment, FileDescOpener,
+    ReportErrorEnvironment,
+};
+use conch_runtime::io::Permissions;
+use conch_runtime::spawn::sequence;
+use conch_runtime::{ExitStatus, EXIT_ERROR, STDERR_FILENO, STDIN_FILENO, STDOUT_FILENO};
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use thiserror::Error;
+
+#[cfg(unix)]
+const DEV_NULL: &str = "/dev/null";
+#[cfg(windows)]
+const DEV_NULL: &str = "NUL";
+
+/// The observable outcome of running a script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptOutput {
+    /// Everything the script wrote to its standard output.
+    pub stdout: Vec<u8>,
+    /// Everything the script wrote to its standard error.
+    pub stderr: Vec<u8>,
+    /// The exit status of the script.
+    pub status: ExitStatus,
+}
+
+/// Selects which parts of a `ScriptOutput` must match for a script to conform.
+///
+/// Shells rarely agree on the exact wording of their diagnostics, so comparing
+/// standard error is only useful for scripts which write to it explicitly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compare {
+    /// Whether standard output must match.
+    pub stdout: bool,
+    /// Whether standard error must match.
+    pub stderr: bool,
+    /// Whether the exit status must match.
+    pub status: bool,
+}
+
+impl Default for Compare {
+    fn default() -> Self {
+        Self {
+            stdout: true,
+            stderr: true,
+            status: true,
+        }
+    }
+}
+
+impl Compare {
+    fn matches(&self, runtime: &ScriptOutput, system: &ScriptOutput) -> bool {
+        (!self.stdout || runtime.stdout == system.stdout)
+            && (!self.stderr || runtime.stderr == system.stderr)
+            && (!self.status || runtime.status == system.status)
+    }
+}
+
+/// The outputs of a script which did not behave the same in both shells.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    /// The script which was run.
+    pub script: String,
+    /// The output of the script when run through `conch-runtime`.
+    pub runtime: ScriptOutput,
+    /// The output of the script when run through the system shell.
+    pub system: ScriptOutput,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(fmt, "script: {:?}", self.script)?;
+
+        let (runtime, system) = (&self.runtime, &self.system);
+        if runtime.stdout != system.stdout {
+            writeln!(
+                fmt,
+                "stdout (runtime): {:?}",
+                String::from_utf8_lossy(&runtime.stdout)
+            )?;
+            writeln!(
+                fmt,
+                "stdout (system):  {:?}",
+                String::from_utf8_lossy(&system.stdout)
+            )?;
+        }
+        if runtime.stderr != system.stderr {
+            writeln!(
+                fmt,
+                "stderr (runtime): {:?}",
+                String::from_utf8_lossy(&runtime.stderr)
+            )?;
+            writeln!(
+                fmt,
+                "stderr (system):  {:?}",
+                String::from_utf8_lossy(&system.stderr)
+            )?;
+        }
+        if runtime.status != system.status {
+            writeln!(fmt, "status (runtime): {:?}", runtime.status)?;
+            writeln!(fmt, "status (system):  {:?}", system.status)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// An error which can arise while checking the conformance of a script.
+#[derive(Debug, Error)]
+pub enum ConformanceError {
+    /// The script could not be parsed.
+    #[error("failed to parse script: {0}")]
+    Parse(String),
+    /// Either shell could not be run.
+    #[error("failed to run script: {0}")]
+    Io(#[from] io::Error),
+    /// The script did not behave the same in both shells.
+    #[error("script does not conform to the system shell:\n{0}")]
+    Mismatch(Box<Mismatch>),
+}
+
+/// Runs scripts both through `conch-runtime` and the system shell
+/// (i.e. `/bin/sh` by default) and compares their outputs.
+///
+/// Both shells run in the current working directory of the process,
+/// inherit its environment variables, and read their standard input
+/// from the equivalent of `/dev/null`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConformanceRunner {
+    shell: PathBuf,
+    compare: Compare,
+}
+
+impl Default for ConformanceRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConformanceRunner {
+    /// Creates a new runner which compares against `/bin/sh`.
+    pub fn new() -> Self {
+        Self::with_shell("/bin/sh")
+    }
+
+    /// Creates a new runner which compares against the specified shell,
+    /// which is invoked as `shell -c script`.
+    pub fn with_shell<P: Into<PathBuf>>(shell: P) -> Self {
+        Self {
+            shell: shell.into(),
+            compare: Compare::default(),
+        }
+    }
+
+    /// Changes which parts of the outputs must match.
+    pub fn compare(mut self, compare: Compare) -> Self {
+        self.compare = compare;
+        self
+    }
+
+    /// Runs a script through `conch-runtime`.
+    ///
+    /// Any fatal errors are reported to the script's standard error, after which
+    /// the script exits with `EXIT_ERROR`.
+    pub async fn run_runtime(&self, script: &str) -> Result<ScriptOutput, ConformanceError> {
+        let parser = Parser::with_builder(Lexer::new(script.chars()), ArcBuilder::new());
+        let cmds = parser
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| ConformanceError::Parse(e.to_string()))?;
+
+        let mut env = DefaultEnvArc::with_config(DefaultEnvConfigArc {
+            interactive: false,
+            ..DefaultEnvConfigArc::new()?
+        });
+
+        let stdin = env.open_path(DEV_NULL.as_ref(), std::fs::OpenOptions::new().read(true))?;
+        let stdout = env.open_pipe()?;
+        let stderr = env.open_pipe()?;
+
+        env.set_file_desc(STDIN_FILENO, stdin.into(), Permissions::Read);
+        env.set_file_desc(STDOUT_FILENO, stdout.writer.into(), Permissions::Write);
+        env.set_file_desc(STDERR_FILENO, stderr.writer.into(), Permissions::Write);
+
+        // Drain both pipes in the background so that a chatty script
+        // cannot block on a full pipe while we wait for it to exit
+        let read_stdout = tokio::spawn(env.read_all(stdout.reader.into()));
+        let read_stderr = tokio::spawn(env.read_all(stderr.reader.into()));
+
+        let status = match sequence(cmds, &mut env).await {
+            Ok(future) => future.await,
+            Err(e) => {
+                env.report_error(&e).await;
+                EXIT_ERROR
+            }
+        };
+
+        // Close our copies of the pipe writers so the readers can reach EOF
+        drop(env);
+
+        let join = |result: Result<io::Result<Vec<u8>>, _>| {
+            result.unwrap_or_else(|e| Err(io::Error::new(io::ErrorKind::Other, e)))
+        };
+
+        Ok(ScriptOutput {
+            stdout: join(read_stdout.await)?,
+            stderr: join(read_stderr.await)?,
+            status,
+        })
+    }
+
+    /// Runs a script through the system shell.
+    pub fn run_system(&self, script: &str) -> Result<ScriptOutput, ConformanceError> {
+        let output = Command::new(&self.shell)
+            .arg("-c")
+            .arg(script)
+            .stdin(Stdio::null())
+            .output()?;
+
+        Ok(ScriptOutput {
+            stdout: output.stdout,
+            stderr: output.stderr,
+            status: output.status.into(),
+        })
+    }
+
+    /// Runs a script through both shells, returning an error if
+    /// any of the compared outputs differ.
+    pub async fn check(&self, script: &str) -> Result<(), ConformanceError> {
+        let runtime = self.run_runtime(script).await?;
+        let system = self.run_system(script)?;
+
+        if self.compare.matches(&runtime, &system) {
+            Ok(())
+        } else {
+            Err(ConformanceError::Mismatch(Box::new(Mismatch {
+                script: script.to_owned(),
+                runtime,
+                system,
+            })))
+        }
+    }
+}
@@ -1,3 +1,12 @@
 //! Integ tests are separated into their own "crate" so that they can depend on
 //! extra features, without obscuring what the crate actually depends on during development.
 //! (Cargo has the habit of adding the test features in with regular build tests.)
+//!
+//! The crate also hosts reusable utilities for integration testing, such as the
+//! `conformance` module for validating the runtime's behavior against the system shell.
+
+#![deny(missing_debug_implementations)]
+#![deny(missing_docs)]
+#![deny(rust_2018_idioms)]
+
+pub mod conformance;
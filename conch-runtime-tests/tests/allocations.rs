@@ -0,0 +1,76 @@
+#![deny(rust_2018_idioms)]
+
+//! Checks which rely on counting allocations, kept in their own test binary
+//! so the counting allocator is not installed for any other tests.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+mod support;
+pub use self::support::*;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Counts the allocations made by the current thread.
+struct CountingAlloc;
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+#[tokio::test]
+async fn ready_status_does_not_allocate_for_common_statuses() {
+    const ITERATIONS: usize = 10_000;
+
+    let before = allocations();
+    for _ in 0..ITERATIONS {
+        assert_eq!(ready_status(EXIT_SUCCESS).await, EXIT_SUCCESS);
+        assert_eq!(ready_status(EXIT_ERROR).await, EXIT_ERROR);
+    }
+    assert_eq!(allocations() - before, 0);
+}
+
+/// A microbenchmark for spawning trivial builtins in a tight loop, which can be
+/// run via `cargo test --release --test allocations -- --ignored --nocapture`.
+#[tokio::test]
+#[ignore]
+async fn trivial_builtin_loop_microbenchmark() {
+    use conch_parser::ast::builder::ArcBuilder;
+    use std::time::Instant;
+
+    const ITERATIONS: usize = 10_000;
+
+    let mut session = Session::new(new_env_with_no_fds());
+    let script = ":; true; false\n".repeat(ITERATIONS);
+    let batch = session.parse::<ArcBuilder>(&script).unwrap();
+
+    let before = allocations();
+    let start = Instant::now();
+    session.execute(batch).await.unwrap();
+    let elapsed = start.elapsed();
+    let allocs = allocations() - before;
+
+    println!(
+        "{} iterations: {:?} ({:?}/iteration), {} allocations/iteration",
+        ITERATIONS,
+        elapsed,
+        elapsed / ITERATIONS as u32,
+        allocs / ITERATIONS,
+    );
+}
@@ -131,3 +131,48 @@ async fn ast_smoke() {
 
     assert_eq!(exit, cmd.spawn(&mut new_env()).await.unwrap().await);
 }
+
+#[tokio::test]
+async fn test_and_or_should_notify_observer_of_completed_and_skipped_commands() {
+    let exit = ExitStatus::Code(42);
+    let mut events = Vec::new();
+
+    let mut env = new_env();
+    let future = and_or_list_with_observer(
+        mock_status(EXIT_ERROR),
+        vec![
+            AndOr::And(mock_panic("first cmd should not run")),
+            AndOr::Or(mock_status(EXIT_SUCCESS)),
+            AndOr::Or(mock_panic("third cmd should not run")),
+            AndOr::And(mock_status(exit)),
+        ],
+        &mut env,
+        |event| events.push(event),
+    )
+    .await
+    .expect("list failed");
+    drop(env);
+
+    assert_eq!(exit, future.await);
+    assert_eq!(
+        events,
+        vec![
+            AndOrEvent::Completed {
+                index: 0,
+                status: EXIT_ERROR
+            },
+            AndOrEvent::Skipped {
+                index: 1,
+                status: EXIT_ERROR
+            },
+            AndOrEvent::Completed {
+                index: 2,
+                status: EXIT_SUCCESS
+            },
+            AndOrEvent::Skipped {
+                index: 3,
+                status: EXIT_SUCCESS
+            },
+        ]
+    );
+}
@@ -40,7 +40,7 @@ async fn test_eval_arith() {
     assert_eq!(env.var(&var), Some(&var_value.to_string()));
 
     assert_eq!(UnaryPlus(lit(5)).eval(env), Ok(5));
-    assert_eq!(UnaryPlus(lit(-5)).eval(env), Ok(5));
+    assert_eq!(UnaryPlus(lit(-5)).eval(env), Ok(-5));
 
     assert_eq!(UnaryMinus(lit(5)).eval(env), Ok(-5));
     assert_eq!(UnaryMinus(lit(-5)).eval(env), Ok(5));
@@ -148,3 +148,52 @@ async fn test_eval_arith() {
     assert_eq!(env.var("x").map(|s| &**s), Some("6"));
     assert_eq!(env.var("y").map(|s| &**s), Some("9"));
 }
+
+#[tokio::test]
+async fn test_eval_arith_overflow() {
+    use conch_parser::ast::Arithmetic::*;
+    use std::isize::{MAX, MIN};
+
+    fn lit(i: isize) -> Box<Arithmetic<String>> {
+        Box::new(Literal(i))
+    }
+
+    let env = &mut VarEnv::new();
+    let overflow = Err(ExpansionError::Overflow);
+
+    assert_eq!(Add(lit(MAX), lit(1)).eval(env), overflow);
+    assert_eq!(Sub(lit(MIN), lit(1)).eval(env), overflow);
+    assert_eq!(Mult(lit(MAX), lit(2)).eval(env), overflow);
+    assert_eq!(Div(lit(MIN), lit(-1)).eval(env), overflow);
+    assert_eq!(Modulo(lit(MIN), lit(-1)).eval(env), overflow);
+    assert_eq!(Pow(lit(2), lit(200)).eval(env), overflow);
+    assert_eq!(Pow(lit(2), lit(MAX)).eval(env), overflow);
+    assert_eq!(Pow(lit(1), lit(200)).eval(env), Ok(1));
+    assert_eq!(UnaryMinus(lit(MIN)).eval(env), overflow);
+    assert_eq!(UnaryPlus(lit(MIN)).eval(env), Ok(MIN));
+    assert_eq!(UnaryPlus(lit(-5)).eval(env), Ok(-5));
+    assert_eq!(ShiftLeft(lit(1), lit(-1)).eval(env), overflow);
+    assert_eq!(ShiftLeft(lit(1), lit(1000)).eval(env), overflow);
+    assert_eq!(ShiftRight(lit(1), lit(-1)).eval(env), overflow);
+    assert_eq!(Add(lit(MAX - 1), lit(1)).eval(env), Ok(MAX));
+
+    // Variables are left untouched if an assignment overflows
+    let var = "var".to_owned();
+    env.set_var(var.clone(), MAX.to_string());
+    assert_eq!(PreIncr(var.clone()).eval(env), overflow);
+    assert_eq!(PostIncr(var.clone()).eval(env), overflow);
+    assert_eq!(
+        Assign(
+            var.clone(),
+            Box::new(Add(Box::new(Var(var.clone())), lit(1)))
+        )
+        .eval(env),
+        overflow
+    );
+    assert_eq!(env.var(&var), Some(&MAX.to_string()));
+
+    env.set_var(var.clone(), MIN.to_string());
+    assert_eq!(PreDecr(var.clone()).eval(env), overflow);
+    assert_eq!(PostDecr(var.clone()).eval(env), overflow);
+    assert_eq!(env.var(&var), Some(&MIN.to_string()));
+}
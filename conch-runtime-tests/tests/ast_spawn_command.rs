@@ -21,12 +21,22 @@ async fn list() {
 #[tokio::test]
 async fn job() {
     let exit = ExitStatus::Code(42);
-    // FIXME: Currently unimplemented
-    Job(mock_status(exit))
-        .spawn(&mut new_env())
-        .await
-        .err()
-        .unwrap();
+    let mut env = new_env();
+    assert_eq!(env.last_job(), None);
+
+    // Jobs run in the background and report success right away
+    let status = Job(mock_status(exit)).spawn(&mut env).await.unwrap().await;
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(env.last_status(), EXIT_SUCCESS);
+
+    let id = env.last_job().expect("job was not tracked");
+    assert_eq!(env.job_ids(), vec![id]);
+    assert_eq!(env.wait_job(id).unwrap().await, exit);
+
+    // Jobs can only be waited for once
+    assert_eq!(env.job_ids(), Vec::<JobId>::new());
+    assert!(env.wait_job(id).is_none());
+    assert_eq!(env.last_job(), Some(id));
 }
 
 #[tokio::test]
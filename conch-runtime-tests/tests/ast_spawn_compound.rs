@@ -1,6 +1,8 @@
 #![deny(rust_2018_idioms)]
 
-use conch_parser::ast::{CompoundCommandKind, GuardBodyPair, PatternBodyPair};
+use conch_parser::ast::{CompoundCommand, CompoundCommandKind, GuardBodyPair, PatternBodyPair};
+use conch_runtime::io::Permissions;
+use futures_util::poll;
 use std::sync::Arc;
 
 mod support;
@@ -79,3 +81,32 @@ async fn loop_should_bail_on_empty_commands() {
     let cmd: Kind = CompoundCommandKind::Until(gbp);
     assert_eq!(EXIT_SUCCESS, cmd.spawn(&mut new_env()).await.unwrap().await);
 }
+
+#[tokio::test]
+async fn cancelling_if_mid_guard_restores_redirects() {
+    let mut env = new_env();
+    let fd = 5;
+    assert_eq!(env.file_desc(fd), None);
+
+    let cmd = CompoundCommand {
+        kind: Kind::If {
+            conditionals: vec![GuardBodyPair {
+                guard: vec![mock_status(EXIT_SUCCESS), mock_pending()],
+                body: vec![mock_panic("must not run")],
+            }],
+            else_branch: None,
+        },
+        io: vec![mock_redirect(RedirectAction::Open(
+            fd,
+            dev_null(&mut env),
+            Permissions::Write,
+        ))],
+    };
+
+    {
+        let mut future = cmd.spawn(&mut env);
+        assert!(poll!(&mut future).is_pending());
+    }
+
+    assert_eq!(env.file_desc(fd), None);
+}
@@ -1,10 +1,13 @@
 #![deny(rust_2018_idioms)]
 
-use conch_runtime::env::{AsyncIoEnvironment, TokioAsyncIoEnv};
-use conch_runtime::io::{FileDesc, Pipe};
+use conch_runtime::env::{read_exact_timeout, read_until, AsyncIoEnvironment, TokioAsyncIoEnv};
+use conch_runtime::io::{AsyncFileDesc, FileDesc, Pipe};
 use futures_util::future::try_join3;
 use std::borrow::Cow;
 use std::fs::File;
+use std::io::{ErrorKind, Write};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
 #[macro_use]
 pub mod support;
@@ -68,3 +71,88 @@ async fn file() {
             .expect("second read failed")
     );
 }
+
+#[tokio::test]
+async fn read_async_incrementally() {
+    let pipe = Pipe::new().expect("failed to create pipe");
+    let mut env = TokioAsyncIoEnv::new();
+
+    (&pipe.writer)
+        .write_all(b"first\nsecond\nthird")
+        .expect("write failed");
+    drop(pipe.writer);
+
+    let mut reader = env.read_async(pipe.reader).expect("read_async failed");
+
+    let mut buf = Vec::new();
+    assert_eq!(read_until(&mut reader, b'\n', &mut buf).await.unwrap(), 6);
+    assert_eq!(buf, b"first\n");
+
+    buf.clear();
+    assert_eq!(read_until(&mut reader, b'\n', &mut buf).await.unwrap(), 7);
+    assert_eq!(buf, b"second\n");
+
+    buf.clear();
+    assert_eq!(read_until(&mut reader, b'\n', &mut buf).await.unwrap(), 5);
+    assert_eq!(buf, b"third");
+
+    buf.clear();
+    assert_eq!(read_until(&mut reader, b'\n', &mut buf).await.unwrap(), 0);
+    assert!(buf.is_empty());
+}
+
+#[tokio::test]
+async fn read_exact_with_timeout() {
+    let pipe = Pipe::new().expect("failed to create pipe");
+    let mut env = TokioAsyncIoEnv::new();
+
+    (&pipe.writer).write_all(b"hello").expect("write failed");
+
+    let mut reader = env.read_async(pipe.reader).expect("read_async failed");
+    let timeout = Duration::from_millis(100);
+
+    let mut buf = Vec::new();
+    read_exact_timeout(&mut reader, 3, &mut buf, timeout)
+        .await
+        .expect("read failed");
+    assert_eq!(buf, b"hel");
+
+    // Partial reads are retained if the timeout expires
+    let err = read_exact_timeout(&mut reader, 3, &mut buf, timeout)
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::TimedOut);
+    assert_eq!(buf, b"hello");
+
+    drop(pipe.writer);
+    let err = read_exact_timeout(&mut reader, 1, &mut buf, timeout)
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    assert_eq!(buf, b"hello");
+}
+
+#[tokio::test]
+async fn async_file_desc_adapters() {
+    let pipe = Pipe::new().expect("failed to create pipe");
+    let msg = "hello async world!";
+
+    let mut writer = AsyncFileDesc::new(pipe.writer);
+    let mut reader = AsyncFileDesc::new(pipe.reader);
+
+    let write = async move {
+        writer.write_all(msg.as_bytes()).await?;
+        writer.shutdown().await
+    };
+
+    let read = async move {
+        let mut data = String::new();
+        reader.read_to_string(&mut data).await?;
+        Ok(data)
+    };
+
+    let ((), data) = futures_util::future::try_join(write, read)
+        .await
+        .expect("futures failed");
+    assert_eq!(data, msg);
+}
@@ -0,0 +1,83 @@
+#![deny(rust_2018_idioms)]
+
+use std::fs;
+use std::sync::Arc;
+
+#[macro_use]
+mod support;
+pub use self::support::*;
+
+fn has_function(session: &Session<DefaultEnvArc>, name: &str) -> bool {
+    session.env().has_function(&Arc::new(name.to_owned()))
+}
+
+#[tokio::test]
+async fn functions_are_loaded_on_first_call() {
+    let lib = mktmp!();
+    fs::write(
+        lib.path().join("greet.sh"),
+        "greet() { greeting=\"hello $1\"; helper; }\nhelper() { helped=yes; }\n",
+    )
+    .unwrap();
+    fs::write(lib.path().join("count"), "count() { count=$#; }\n").unwrap();
+    fs::write(lib.path().join(".hidden"), "hidden() { :; }\n").unwrap();
+    fs::create_dir(lib.path().join("subdir")).unwrap();
+
+    let mut env = new_env_with_no_fds();
+    let names = autoload_functions(lib.path(), &mut env).unwrap();
+    assert_eq!(names, vec!["count", "greet"]);
+
+    let mut session = Session::new(env);
+    assert!(has_function(&session, "greet"));
+    assert!(has_function(&session, "count"));
+    assert!(!has_function(&session, "helper"));
+    assert!(!has_function(&session, "hidden"));
+
+    run(&mut session, "greet world; status=$?\n").await.unwrap();
+    assert_eq!(var(&session, "greeting"), Some("hello world".to_owned()));
+    assert_eq!(var(&session, "helped"), Some("yes".to_owned()));
+    assert_eq!(var(&session, "status"), Some("0".to_owned()));
+    assert!(has_function(&session, "helper"));
+
+    // Once loaded, the file is no longer needed
+    fs::remove_file(lib.path().join("greet.sh")).unwrap();
+    run(&mut session, "greet again\n").await.unwrap();
+    assert_eq!(var(&session, "greeting"), Some("hello again".to_owned()));
+
+    run(&mut session, "count a b c\n").await.unwrap();
+    assert_eq!(var(&session, "count"), Some("3".to_owned()));
+}
+
+#[tokio::test]
+async fn files_which_do_not_define_their_function_are_not_found() {
+    let lib = mktmp!();
+    fs::write(lib.path().join("missing"), "other() { :; }\n").unwrap();
+    fs::write(lib.path().join("broken"), "broken() {\n").unwrap();
+
+    let mut env = new_env_with_no_fds();
+    autoload_functions(lib.path(), &mut env).unwrap();
+
+    let mut session = Session::new(env);
+    run(&mut session, "missing; a=$?; broken; b=$?\n")
+        .await
+        .unwrap();
+    assert_eq!(var(&session, "a"), Some("127".to_owned()));
+    assert_eq!(var(&session, "b"), Some("1".to_owned()));
+
+    // The placeholders remain in place
+    assert!(has_function(&session, "missing"));
+    assert!(has_function(&session, "broken"));
+    assert!(has_function(&session, "other"));
+
+    // Fixing the file will load the function on its next call
+    fs::write(lib.path().join("missing"), "missing() { found=yes; }\n").unwrap();
+    run(&mut session, "missing\n").await.unwrap();
+    assert_eq!(var(&session, "found"), Some("yes".to_owned()));
+}
+
+#[tokio::test]
+async fn missing_directory_is_an_error() {
+    let lib = mktmp!();
+    let mut env = new_env_with_no_fds();
+    assert!(autoload_functions(lib.path().join("missing"), &mut env).is_err());
+}
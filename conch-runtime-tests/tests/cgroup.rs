@@ -0,0 +1,104 @@
+#![deny(rust_2018_idioms)]
+#![cfg(all(target_os = "linux", feature = "cgroups"))]
+
+use std::env::current_dir;
+use std::ffi::OsStr;
+use std::fs;
+
+mod support;
+pub use self::support::*;
+
+// NB: a real cgroup hierarchy is not necessarily available (or writable) where
+// the tests run, so a regular directory is used to stand in for the cgroup.
+fn fake_cgroup(tempdir: &tempfile::TempDir) -> Cgroup {
+    fs::write(tempdir.path().join("cgroup.procs"), "").unwrap();
+    Cgroup::open(tempdir.path()).unwrap()
+}
+
+#[test]
+fn open_requires_an_existing_cgroup() {
+    let tempdir = mktmp!();
+    assert!(Cgroup::open(tempdir.path()).is_err());
+
+    let cgroup = fake_cgroup(&tempdir);
+    assert_eq!(cgroup.path(), tempdir.path());
+    assert!(!cgroup.is_transient());
+
+    drop(cgroup);
+    assert!(tempdir.path().exists());
+}
+
+#[test]
+fn transient_cgroups_are_unique_and_removed_on_drop() {
+    let tempdir = mktmp!();
+
+    let first = Cgroup::create_transient(tempdir.path()).unwrap();
+    let second = Cgroup::create_transient(tempdir.path()).unwrap();
+    assert!(first.is_transient());
+    assert_ne!(first.path(), second.path());
+    assert_eq!(first.path().parent(), Some(tempdir.path()));
+    assert!(first.path().is_dir());
+
+    let path = first.path().to_owned();
+    drop(first);
+    assert!(!path.exists());
+    assert!(second.path().exists());
+}
+
+#[test]
+fn set_limits_only_writes_provided_limits() {
+    let tempdir = mktmp!();
+    let cgroup = fake_cgroup(&tempdir);
+    let read = |file: &str| fs::read_to_string(tempdir.path().join(file)).ok();
+
+    cgroup
+        .set_limits(&CgroupLimits {
+            memory_max: Some(1024),
+            cpu_max: Some((50_000, 100_000)),
+            pids_max: None,
+        })
+        .unwrap();
+
+    assert_eq!(read("memory.max"), Some("1024".to_owned()));
+    assert_eq!(read("cpu.max"), Some("50000 100000".to_owned()));
+    assert_eq!(read("pids.max"), None);
+
+    cgroup.add_process(42).unwrap();
+    assert_eq!(read("cgroup.procs"), Some("42".to_owned()));
+}
+
+#[tokio::test]
+async fn spawned_executables_join_cgroup_before_running() {
+    let tempdir = mktmp!();
+    let env = TokioExecEnv::new().with_cgroup(fake_cgroup(&tempdir));
+    assert_eq!(env.cgroup().map(Cgroup::path), Some(tempdir.path()));
+
+    let bin_path = bin_path("env");
+    let cur_dir = current_dir().expect("failed to get current_dir");
+    let data = ExecutableData::builder(OsStr::new(&bin_path), &cur_dir).build();
+
+    let child = env.spawn_executable(data).expect("spawn failed");
+    assert!(child.await.success());
+
+    let procs = fs::read_to_string(tempdir.path().join("cgroup.procs")).unwrap();
+    assert_eq!(procs, "0");
+}
+
+#[tokio::test]
+async fn missing_cgroup_fails_to_spawn() {
+    let tempdir = mktmp!();
+    let env = TokioExecEnv::new().with_cgroup(fake_cgroup(&tempdir));
+    fs::remove_file(tempdir.path().join("cgroup.procs")).unwrap();
+
+    let bin_path = bin_path("env");
+    let cur_dir = current_dir().expect("failed to get current_dir");
+    let data = ExecutableData::builder(OsStr::new(&bin_path), &cur_dir).build();
+
+    match env.spawn_executable(data) {
+        Err(CommandError::Io(_, Some(path))) => {
+            assert_eq!(path, tempdir.path().display().to_string())
+        }
+        Err(e) => panic!("unexpected error: {}", e),
+        Ok(_) => panic!("unexpected success"),
+    }
+}
@@ -0,0 +1,98 @@
+#![deny(rust_2018_idioms)]
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+mod support;
+pub use self::support::*;
+
+#[test]
+fn manual_clock_should_only_advance_when_told() {
+    let start = UNIX_EPOCH + Duration::from_secs(1_000);
+    let clock = ManualClock::new(start);
+    let monotonic_start = clock.monotonic_now();
+
+    assert_eq!(clock.now(), start);
+    assert_eq!(clock.monotonic_now(), monotonic_start);
+
+    clock.clone().advance(Duration::from_secs(5));
+    assert_eq!(clock.now(), start + Duration::from_secs(5));
+    assert_eq!(
+        clock.monotonic_now(),
+        monotonic_start + Duration::from_secs(5)
+    );
+
+    clock.set_now(UNIX_EPOCH);
+    assert_eq!(clock.now(), UNIX_EPOCH);
+    assert_eq!(
+        clock.monotonic_now(),
+        monotonic_start + Duration::from_secs(5)
+    );
+}
+
+#[test]
+fn clock_handles_should_compare_by_identity() {
+    let clock = Clock::new(ManualClock::new(UNIX_EPOCH));
+
+    assert_eq!(Clock::system(), Clock::default());
+    assert!(Clock::system().is_system());
+    assert!(!clock.is_system());
+    assert_eq!(clock, clock.clone());
+    assert_ne!(clock, Clock::system());
+    assert_ne!(clock, Clock::new(ManualClock::new(UNIX_EPOCH)));
+}
+
+#[test]
+fn system_clock_should_tell_the_current_time() {
+    let before = SystemTime::now();
+    let now = Clock::system().now();
+    let after = SystemTime::now();
+
+    assert!(before <= now && now <= after);
+}
+
+#[tokio::test]
+async fn env_should_use_configured_clock() {
+    let manual = ManualClock::new(UNIX_EPOCH);
+    let env = Env::with_config(EnvConfig {
+        clock: Clock::new(manual.clone()),
+        ..DefaultEnvConfigArc::new().unwrap()
+    });
+    let monotonic_start = env.monotonic_now();
+
+    manual.advance(Duration::from_secs(3));
+    assert_eq!(env.now(), UNIX_EPOCH + Duration::from_secs(3));
+    assert_eq!(
+        env.monotonic_now(),
+        monotonic_start + Duration::from_secs(3)
+    );
+
+    // Sub environments share the same clock
+    let sub = env.sub_env();
+    manual.advance(Duration::from_secs(1));
+    assert_eq!(sub.now(), UNIX_EPOCH + Duration::from_secs(4));
+}
+
+#[test]
+fn execution_reporter_should_measure_durations_with_its_clock() {
+    let manual = ManualClock::new(UNIX_EPOCH);
+    let reporter = ExecutionReporter::with_clock(Clock::new(manual.clone()));
+
+    let outer = reporter.start_command(vec!["outer".to_owned()], vec![]);
+    manual.advance(Duration::from_millis(10));
+
+    let inner = outer
+        .nested_reporter()
+        .start_command(vec!["inner".to_owned()], vec![]);
+    manual.advance(Duration::from_millis(5));
+    inner.finish(EXIT_SUCCESS);
+
+    manual.advance(Duration::from_millis(1));
+    outer.finish(EXIT_ERROR);
+
+    let report = reporter.report();
+    assert_eq!(report.commands.len(), 1);
+
+    let outer = &report.commands[0];
+    assert_eq!(outer.duration, Some(Duration::from_millis(16)));
+    assert_eq!(outer.children[0].duration, Some(Duration::from_millis(5)));
+}
@@ -0,0 +1,177 @@
+#![deny(rust_2018_idioms)]
+#![cfg(unix)]
+
+use conch_runtime::env::{
+    complete_command, complete_path, CommandCompletion, CommandCompletionKind, PathCompletion,
+};
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::sync::Arc;
+
+#[macro_use]
+mod support;
+pub use self::support::*;
+
+fn create_file(path: &Path, mode: u32) {
+    fs::write(path, "").unwrap();
+    fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap();
+}
+
+#[tokio::test]
+async fn should_list_functions_builtins_and_executables() {
+    let tempdir = mktmp!();
+    let first = tempdir.path().join("first");
+    let second = tempdir.path().join("second");
+    fs::create_dir(&first).unwrap();
+    fs::create_dir(&second).unwrap();
+
+    create_file(&first.join("c-exec"), 0o755);
+    create_file(&first.join("c-not-exec"), 0o644);
+    create_file(&first.join("cd"), 0o755);
+    create_file(&first.join("d-exec"), 0o755);
+    create_file(&second.join("c-exec"), 0o755);
+    create_file(&second.join("c-second"), 0o755);
+    fs::create_dir(second.join("c-dir")).unwrap();
+
+    let mut env = Env::with_config(
+        DefaultEnvConfigArc::new()
+            .unwrap()
+            .change_fn_error::<MockErr>(),
+    );
+    let path = std::env::join_paths(&[&first, &second]).unwrap();
+    env.set_var(name("PATH"), name(path.to_str().unwrap()));
+    env.set_function(name("c-fn"), Arc::new(mock_status(EXIT_SUCCESS)));
+    env.set_function(name("conch-info"), Arc::new(mock_status(EXIT_SUCCESS)));
+
+    let completion = |name: &str, kind| CommandCompletion {
+        name: name.to_owned(),
+        kind,
+    };
+
+    assert_eq!(
+        complete_command("c", &env),
+        vec![
+            completion(
+                "c-exec",
+                CommandCompletionKind::Executable(first.join("c-exec"))
+            ),
+            completion("c-fn", CommandCompletionKind::Function),
+            completion(
+                "c-second",
+                CommandCompletionKind::Executable(second.join("c-second"))
+            ),
+            completion("cd", CommandCompletionKind::Builtin),
+            completion("command", CommandCompletionKind::Builtin),
+            completion("conch-info", CommandCompletionKind::Function),
+            completion("continue", CommandCompletionKind::Builtin),
+        ]
+    );
+
+    assert_eq!(complete_command("nothing", &env), vec![]);
+    assert_eq!(complete_command("first/c", &env), vec![]);
+    assert_eq!(complete_command("", &env).len(), 35);
+}
+
+#[tokio::test]
+async fn should_resolve_relative_path_entries_against_working_dir() {
+    let tempdir = mktmp!();
+    let bin = tempdir.path().join("bin");
+    fs::create_dir(&bin).unwrap();
+    create_file(&bin.join("relative-exec"), 0o755);
+
+    let mut env = new_env();
+    env.set_var(name("PATH"), name("bin"));
+    env.change_working_dir(std::borrow::Cow::Borrowed(tempdir.path()))
+        .unwrap();
+
+    assert_eq!(
+        complete_command("rel", &env),
+        vec![CommandCompletion {
+            name: "relative-exec".to_owned(),
+            kind: CommandCompletionKind::Executable(
+                tempdir
+                    .path()
+                    .canonicalize()
+                    .unwrap()
+                    .join("bin")
+                    .join("relative-exec")
+            ),
+        }]
+    );
+}
+
+fn path(path: &str, escaped: &str, is_dir: bool) -> PathCompletion {
+    PathCompletion {
+        path: path.to_owned(),
+        escaped: escaped.to_owned(),
+        is_dir,
+    }
+}
+
+#[tokio::test]
+async fn should_complete_paths_relative_to_working_dir() {
+    let tempdir = mktmp!();
+    let sub = tempdir.path().join("sub");
+    fs::create_dir(&sub).unwrap();
+    create_file(&tempdir.path().join("a*b"), 0o644);
+    create_file(&tempdir.path().join("a-file"), 0o644);
+    create_file(&tempdir.path().join(".hidden"), 0o644);
+    create_file(&sub.join("nested[1]"), 0o644);
+
+    let mut env = new_env();
+    env.change_working_dir(std::borrow::Cow::Borrowed(tempdir.path()))
+        .unwrap();
+
+    assert_eq!(
+        complete_path("", &env),
+        vec![
+            path("a*b", "a\\*b", false),
+            path("a-file", "a-file", false),
+            path("sub/", "sub/", true),
+        ]
+    );
+    assert_eq!(
+        complete_path("a", &env),
+        vec![path("a*b", "a\\*b", false), path("a-file", "a-file", false)]
+    );
+    assert_eq!(
+        complete_path("sub/n", &env),
+        vec![path("sub/nested[1]", "sub/nested\\[1\\]", false)]
+    );
+    assert_eq!(complete_path("missing/", &env), vec![]);
+}
+
+#[tokio::test]
+async fn should_only_complete_hidden_paths_if_prefix_starts_with_dot() {
+    let tempdir = mktmp!();
+    create_file(&tempdir.path().join(".hidden"), 0o644);
+    create_file(&tempdir.path().join("visible"), 0o644);
+
+    let mut env = new_env();
+    env.change_working_dir(std::borrow::Cow::Borrowed(tempdir.path()))
+        .unwrap();
+
+    assert_eq!(
+        complete_path("", &env),
+        vec![path("visible", "visible", false)]
+    );
+    assert_eq!(
+        complete_path(".h", &env),
+        vec![path(".hidden", ".hidden", false)]
+    );
+}
+
+#[tokio::test]
+async fn should_complete_absolute_paths() {
+    let tempdir = mktmp!();
+    create_file(&tempdir.path().join("file"), 0o644);
+
+    let prefix = format!("{}/f", tempdir.path().display());
+    let expected = format!("{}/file", tempdir.path().display());
+
+    assert_eq!(
+        complete_path(&prefix, &new_env()),
+        vec![path(&expected, &expected, false)]
+    );
+}
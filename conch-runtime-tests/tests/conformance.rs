@@ -0,0 +1,65 @@
+#![deny(rust_2018_idioms)]
+#![cfg(unix)]
+
+use conch_runtime::ExitStatus;
+use conch_runtime_tests::conformance::{Compare, ConformanceError, ConformanceRunner};
+
+#[tokio::test]
+async fn should_conform_to_system_shell() {
+    let runner = ConformanceRunner::new();
+
+    let scripts = &[
+        "echo foo bar",
+        "x='a  b'; echo $x \"$x\"",
+        "false; echo $?",
+        "false",
+        "echo oops >&2; echo ok",
+        "for x in a b c; do echo $x; done",
+        "case foo in f*) echo yes;; *) echo no;; esac",
+        "f() { echo \"$1-$#\"; }; f a b",
+        "v=abcabc; echo ${v%b*} ${v##*b} ${#v}",
+    ];
+
+    for script in scripts {
+        if let Err(e) = runner.check(script).await {
+            panic!("{}", e);
+        }
+    }
+}
+
+#[tokio::test]
+async fn should_not_block_on_large_outputs() {
+    let script = "seq 1 20000; seq 1 20000 >&2";
+    ConformanceRunner::new().check(script).await.unwrap();
+}
+
+#[tokio::test]
+async fn should_report_mismatches() {
+    let runner = ConformanceRunner::with_shell("false");
+
+    match runner.check("echo foo").await {
+        Err(ConformanceError::Mismatch(mismatch)) => {
+            assert_eq!(mismatch.script, "echo foo");
+            assert_eq!(mismatch.runtime.stdout, b"foo\n");
+            assert_eq!(mismatch.runtime.status, ExitStatus::Code(0));
+            assert!(mismatch.system.stdout.is_empty());
+            assert_eq!(mismatch.system.status, ExitStatus::Code(1));
+        }
+        result => panic!("unexpected result: {:?}", result),
+    }
+
+    let runner = runner.compare(Compare {
+        stdout: false,
+        stderr: true,
+        status: false,
+    });
+    runner.check("echo foo").await.unwrap();
+}
+
+#[tokio::test]
+async fn should_report_parse_errors() {
+    match ConformanceRunner::new().check("echo (").await {
+        Err(ConformanceError::Parse(_)) => {}
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
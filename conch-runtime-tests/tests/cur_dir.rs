@@ -1,7 +1,9 @@
 #![deny(rust_2018_idioms)]
 
 use std::borrow::Cow;
+use std::io;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
 #[macro_use]
 mod support;
@@ -65,3 +67,70 @@ async fn change_cur_dir_should_accept_relative_paths() {
     expected.pop();
     assert_eq!(env.current_working_dir(), expected);
 }
+
+#[tokio::test]
+async fn change_cur_dir_can_be_vetoed() {
+    let tempdir = mktmp!();
+    let allowed = tempdir.path().join("allowed");
+    let denied = tempdir.path().join("denied");
+    std::fs::create_dir(&allowed).unwrap();
+    std::fs::create_dir(&denied).unwrap();
+
+    let mut env = VirtualWorkingDirEnv::new(tempdir.path()).unwrap();
+    let sandbox = allowed.clone();
+    env.add_change_validator(move |_, new| {
+        if new.starts_with(&sandbox) {
+            Ok(())
+        } else {
+            Err(io::Error::new(io::ErrorKind::PermissionDenied, "sandboxed"))
+        }
+    });
+
+    env.change_working_dir(Cow::Borrowed(&allowed))
+        .expect("change_working_dir failed");
+    assert_eq!(env.current_working_dir(), allowed);
+
+    let err = env
+        .change_working_dir(Cow::Borrowed(Path::new("../denied")))
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    assert_eq!(env.current_working_dir(), allowed);
+
+    // Subshells are subject to the same restrictions
+    let mut sub_env = env.sub_env();
+    assert!(sub_env.change_working_dir(Cow::Borrowed(&denied)).is_err());
+    assert_eq!(sub_env.current_working_dir(), allowed);
+}
+
+#[tokio::test]
+async fn change_cur_dir_notifies_watchers() {
+    let tempdir = mktmp!();
+    let nested = tempdir.path().join("nested");
+    std::fs::create_dir(&nested).unwrap();
+
+    let changes = Arc::new(Mutex::new(Vec::new()));
+    let mut env = VirtualWorkingDirEnv::new(tempdir.path()).unwrap();
+
+    let changes_clone = changes.clone();
+    env.watch(move |old, new| {
+        let change = (old.to_path_buf(), new.to_path_buf());
+        changes_clone.lock().unwrap().push(change);
+    });
+
+    env.change_working_dir(Cow::Borrowed(Path::new("nested")))
+        .expect("change_working_dir failed");
+    assert!(env
+        .change_working_dir(Cow::Borrowed(Path::new("missing")))
+        .is_err());
+
+    // Changes in subshells are not visible to the parent
+    let mut sub_env = env.sub_env();
+    sub_env
+        .change_working_dir(Cow::Borrowed(tempdir.path()))
+        .expect("change_working_dir failed");
+
+    assert_eq!(
+        *changes.lock().unwrap(),
+        vec![(tempdir.path().to_path_buf(), nested)]
+    );
+}
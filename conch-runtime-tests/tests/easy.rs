@@ -0,0 +1,74 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::easy::{Shell, ShellError};
+use conch_runtime::{ExitStatus, EXIT_ERROR, EXIT_SUCCESS};
+use std::fs;
+
+#[tokio::test]
+async fn should_run_scripts_against_the_same_env() {
+    let mut shell = Shell::new().unwrap();
+    shell.set_var("greeting", "hello");
+
+    let status = shell
+        .run_str("greet() { msg=\"$greeting $1\"; }\ngreet world")
+        .await
+        .unwrap();
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(shell.var("msg"), Some("hello world"));
+
+    assert_eq!(shell.run_str("false").await.unwrap(), EXIT_ERROR);
+    assert_eq!(shell.run_str("status=$?").await.unwrap(), EXIT_SUCCESS);
+    assert_eq!(shell.var("status"), Some("1"));
+
+    shell.unset_var("msg");
+    assert_eq!(shell.var("msg"), None);
+}
+
+#[tokio::test]
+async fn should_capture_stdout() {
+    let tempdir = tempfile::tempdir().unwrap();
+    fs::write(tempdir.path().join("file"), "from a file\n").unwrap();
+
+    let mut shell = Shell::new().unwrap();
+    let stdout = shell.stdout_capture();
+    shell.set_var("DIR", tempdir.path().to_str().unwrap());
+
+    shell.run_str("echo one; printf '%s\\n' two").await.unwrap();
+    assert_eq!(stdout.take_string(), "one\ntwo\n");
+
+    // Output from executables is captured as well
+    shell.run_str("cat \"$DIR/file\"").await.unwrap();
+    assert_eq!(stdout.take_string(), "from a file\n");
+
+    shell.stop_stdout_capture();
+    shell.run_str("echo three >/dev/null").await.unwrap();
+    assert_eq!(stdout.take(), Vec::<u8>::new());
+}
+
+#[tokio::test]
+async fn should_return_exit_status() {
+    let mut shell = Shell::new().unwrap();
+    let status = shell.run_str("x=before; exit 4; x=after").await.unwrap();
+
+    assert_eq!(status, ExitStatus::Code(4));
+    assert_eq!(shell.var("x"), Some("before"));
+}
+
+#[tokio::test]
+async fn should_not_run_anything_if_script_is_invalid() {
+    let mut shell = Shell::new().unwrap();
+
+    match shell.run_str("x=1; if true; then").await {
+        Err(ShellError::Parse(_)) => {}
+        result => panic!("unexpected result: {:?}", result),
+    }
+
+    match shell.run_str("x=1; )").await {
+        Err(ShellError::Parse(_)) => {}
+        result => panic!("unexpected result: {:?}", result),
+    }
+
+    assert_eq!(shell.var("x"), None);
+    assert_eq!(shell.run_str("x=2").await.unwrap(), EXIT_SUCCESS);
+    assert_eq!(shell.var("x"), Some("2"));
+}
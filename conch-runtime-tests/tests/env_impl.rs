@@ -1,6 +1,7 @@
 #![deny(rust_2018_idioms)]
 
 use std::borrow::Cow;
+use std::sync::Arc;
 
 #[macro_use]
 pub mod support;
@@ -45,3 +46,114 @@ async fn sets_pwd_and_oldpwd_env_vars() {
         Some(&*env.current_working_dir().to_string_lossy())
     );
 }
+
+#[test]
+fn getopts_state_is_scoped_to_fn_frames_and_resettable() {
+    let mut env = DefaultEnv::<String>::new().unwrap();
+    let state = GetoptsState {
+        optind: 2,
+        char_offset: 1,
+    };
+
+    env.set_var("OPTIND".to_owned(), "2".to_owned());
+    env.set_var("OPTARG".to_owned(), "arg".to_owned());
+    env.set_getopts_state(Some(state));
+
+    env.push_fn_frame();
+    assert_eq!(env.getopts_state(), None);
+    env.set_getopts_state(Some(GetoptsState::new(3)));
+    env.pop_fn_frame();
+    assert_eq!(env.getopts_state(), Some(state));
+
+    env.reset_getopts();
+    assert_eq!(env.getopts_state(), None);
+    assert_eq!(env.var("OPTIND").map(|s| &**s), Some("1"));
+    assert_eq!(env.var("OPTARG"), None);
+}
+
+#[tokio::test]
+async fn resolved_commands_are_invalidated_when_functions_change() {
+    use conch_parser::ast::builder::ArcBuilder;
+    use std::sync::Arc;
+
+    fn resolve(env: &mut DefaultEnvArc, name: &str) -> &'static str {
+        match env.resolve_command(&Arc::new(name.to_owned())) {
+            ResolvedCommand::Function(_) => "function",
+            ResolvedCommand::Builtin(_) => "builtin",
+            ResolvedCommand::External => "external",
+        }
+    }
+
+    async fn run(env: DefaultEnvArc, script: &str) -> DefaultEnvArc {
+        let mut session = Session::new(env);
+        let batch = session.parse::<ArcBuilder>(script).unwrap();
+        session.execute(batch).await.unwrap();
+        session.into_env()
+    }
+
+    let mut env = new_env_with_no_fds();
+    assert_eq!(resolve(&mut env, "echo"), "builtin");
+    assert_eq!(resolve(&mut env, "greet"), "external");
+
+    // Resolving again hits the cache
+    assert_eq!(resolve(&mut env, "echo"), "builtin");
+    assert_eq!(resolve(&mut env, "greet"), "external");
+
+    let mut env = run(env, "echo() { :; }; greet() { :; }").await;
+    assert_eq!(resolve(&mut env, "echo"), "function");
+    assert_eq!(resolve(&mut env, "greet"), "function");
+
+    let mut env = run(env, "unset -f echo greet").await;
+    assert_eq!(resolve(&mut env, "echo"), "builtin");
+    assert_eq!(resolve(&mut env, "greet"), "external");
+
+    // Sub-environments see the same definitions
+    let mut env = run(env, "greet() { :; }").await;
+    let mut sub = env.sub_env();
+    assert_eq!(resolve(&mut sub, "greet"), "function");
+    assert_eq!(resolve(&mut env, "greet"), "function");
+}
+
+#[test]
+fn hardened_config_has_conservative_defaults() {
+    let cfg = DefaultEnvConfigArc::hardened().unwrap();
+
+    assert!(!cfg.interactive);
+    assert!(cfg.options.errexit);
+    assert!(cfg.options.nounset);
+    assert!(cfg.options.pipefail);
+    assert!(cfg.options.fatal_special_builtin_errors);
+
+    let fd_usage = cfg.file_desc_manager_env.fd_usage().clone();
+    assert_eq!(fd_usage.limit(), Some(256));
+
+    let env = DefaultEnvArc::with_config(cfg);
+    assert_eq!(env.var(&Arc::new("PATH".to_owned())), None);
+
+    #[cfg(unix)]
+    {
+        let core = env.resource_limit(Resource::CoreFileSize).unwrap();
+        assert_eq!(core.soft, Some(0));
+        assert_eq!(core.hard, Some(0));
+
+        let open_files = env.resource_limit(Resource::OpenFiles).unwrap();
+        assert!(open_files.soft.unwrap() <= 1024);
+        assert!(open_files.hard.unwrap() <= 1024);
+
+        let processes = env.resource_limit(Resource::Processes).unwrap();
+        assert!(processes.soft.unwrap() <= 4096);
+        assert!(processes.hard.unwrap() <= 4096);
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "spawn-policy"))]
+#[tokio::test]
+async fn hardened_config_prevents_gaining_privileges() {
+    let mut session = Session::new(DefaultEnvArc::with_config(
+        DefaultEnvConfigArc::hardened().unwrap(),
+    ));
+
+    let script = "status=$(/bin/grep NoNewPrivs /proc/self/status)";
+    assert_eq!(run(&mut session, script).await, Ok(EXIT_SUCCESS));
+    assert_eq!(var(&session, "status"), Some("NoNewPrivs:\t1".to_owned()));
+}
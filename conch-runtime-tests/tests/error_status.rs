@@ -0,0 +1,164 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::{EXIT_CMD_NOT_EXECUTABLE, EXIT_CMD_NOT_FOUND};
+use std::error::Error;
+use std::io::{Error as IoError, ErrorKind};
+
+mod support;
+pub use self::support::*;
+
+fn not_found() -> IoError {
+    std::fs::File::open("/conch-runtime/definitely/does/not/exist").unwrap_err()
+}
+
+fn status(err: RuntimeError) -> ExitStatus {
+    exit_status_for_error(&err as &(dyn Error + 'static))
+}
+
+#[test]
+fn describe_io_error_should_omit_os_error_codes() {
+    let err = not_found();
+    assert!(err.raw_os_error().is_some());
+    assert!(err.to_string().contains("os error"));
+
+    let description = describe_io_error(&err);
+    assert!(!description.contains("os error"));
+    assert!(err.to_string().starts_with(&description));
+
+    let custom = IoError::new(ErrorKind::Other, "custom message");
+    assert_eq!(describe_io_error(&custom), "custom message");
+}
+
+#[test]
+fn io_errors_should_be_displayed_after_path() {
+    let description = describe_io_error(&not_found());
+
+    assert_eq!(
+        RedirectionError::Io(not_found(), Some("foo".to_owned())).to_string(),
+        format!("foo: {}", description)
+    );
+    assert_eq!(
+        CommandError::Io(not_found(), Some("foo".to_owned())).to_string(),
+        format!("foo: {}", description)
+    );
+    assert_eq!(
+        RuntimeError::Io(not_found(), Some("foo".to_owned())).to_string(),
+        format!("foo: {}", description)
+    );
+    assert_eq!(RuntimeError::Io(not_found(), None).to_string(), description);
+}
+
+#[test]
+fn missing_commands_with_paths_should_not_be_reported_as_not_found() {
+    assert_eq!(
+        CommandError::NotFound("foo".to_owned()).to_string(),
+        "foo: command not found"
+    );
+    assert_eq!(
+        CommandError::NotFound("./foo".to_owned()).to_string(),
+        "./foo: No such file or directory"
+    );
+}
+
+#[test]
+fn command_errors_should_map_to_conventional_statuses() {
+    let io = |kind| CommandError::Io(IoError::from(kind), Some("foo".to_owned()));
+
+    assert_eq!(
+        CommandError::NotFound("foo".to_owned()).exit_status(),
+        EXIT_CMD_NOT_FOUND
+    );
+    assert_eq!(
+        CommandError::NotExecutable("foo".to_owned()).exit_status(),
+        EXIT_CMD_NOT_EXECUTABLE
+    );
+    assert_eq!(io(ErrorKind::NotFound).exit_status(), EXIT_CMD_NOT_FOUND);
+    assert_eq!(
+        io(ErrorKind::PermissionDenied).exit_status(),
+        EXIT_CMD_NOT_EXECUTABLE
+    );
+    assert_eq!(io(ErrorKind::Other).exit_status(), EXIT_ERROR);
+    assert_eq!(
+        CommandError::SpecialBuiltin("shift".to_owned(), ExitStatus::Code(2)).exit_status(),
+        ExitStatus::Code(2)
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn is_a_directory_should_map_to_not_executable() {
+    let err = IoError::from_raw_os_error(21 /* EISDIR */);
+    assert_eq!(
+        CommandError::Io(err, None).exit_status(),
+        EXIT_CMD_NOT_EXECUTABLE
+    );
+}
+
+#[test]
+fn exit_status_for_error_should_search_sources() {
+    let denied = || IoError::from(ErrorKind::PermissionDenied);
+
+    assert_eq!(
+        status(CommandError::Io(denied(), None).into()),
+        EXIT_CMD_NOT_EXECUTABLE
+    );
+    assert_eq!(
+        status(CommandError::NotFound("foo".to_owned()).into()),
+        EXIT_CMD_NOT_FOUND
+    );
+
+    // Redirection failures always exit with a generic error,
+    // regardless of the underlying cause
+    assert_eq!(
+        status(RedirectionError::Io(denied(), None).into()),
+        EXIT_ERROR
+    );
+    assert_eq!(
+        status(RedirectionError::Io(not_found(), None).into()),
+        EXIT_ERROR
+    );
+    assert_eq!(status(RuntimeError::Io(denied(), None)), EXIT_ERROR);
+    assert_eq!(status(ExpansionError::DivideByZero.into()), EXIT_ERROR);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn executing_a_directory_should_exit_with_not_executable() {
+    struct ExecDir(String);
+
+    #[async_trait::async_trait]
+    impl<E> Spawn<E> for ExecDir
+    where
+        E: ?Sized + Send + ExecutableEnvironment + WorkingDirectoryEnvironment,
+    {
+        type Error = RuntimeError;
+
+        async fn spawn(&self, env: &mut E) -> Result<BoxFuture<'static, ExitStatus>, Self::Error> {
+            let data = ExecutableData {
+                name: std::ffi::OsStr::new(&self.0),
+                path: None,
+                args: &[],
+                env_vars: &[],
+                current_dir: &env.current_working_dir().to_path_buf(),
+                stdin: ExecutableStdio::Null,
+                stdout: ExecutableStdio::Null,
+                stderr: ExecutableStdio::Null,
+                name_tainted: false,
+                args_tainted: &[],
+            };
+
+            Ok(env.spawn_executable(data)?)
+        }
+    }
+
+    let tempdir = mktmp!();
+    let cmd = ExecDir(tempdir.path().display().to_string());
+
+    let mut env = new_env_with_no_fds();
+    let status = swallow_non_fatal_errors(&cmd, &mut env)
+        .await
+        .unwrap()
+        .await;
+
+    assert_eq!(status, EXIT_CMD_NOT_EXECUTABLE);
+}
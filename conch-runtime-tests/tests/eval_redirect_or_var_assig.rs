@@ -1,6 +1,7 @@
 #![deny(rust_2018_idioms)]
 
 use conch_runtime::io::{FileDesc, Permissions};
+use conch_runtime::Fd;
 use std::sync::Arc;
 
 mod support;
@@ -232,3 +233,106 @@ async fn should_propagate_errors_and_restore_redirects_and_vars() {
         assert_eq!(env.var(&key), None);
     }
 }
+
+async fn eval_staged(
+    vars: Vec<MockRedirectOrVarAssig>,
+    env: &mut DefaultEnvArc,
+) -> Result<
+    EnvChangeSet<Arc<String>, Arc<String>, Arc<FileDesc>>,
+    EvalRedirectOrVarAssigError<MockErr, MockErr>,
+> {
+    let mut restorer = EnvRestorer::new(env);
+    eval_redirects_or_var_assignments_staged(Some(true), vars.into_iter(), &mut restorer).await
+}
+
+#[tokio::test]
+async fn staged_changes_are_only_applied_on_request() {
+    let mut env = new_env_with_no_fds();
+
+    let key = Arc::new("key".to_owned());
+    let val = Arc::new("val".to_owned());
+    let fdes = dev_null(&mut env);
+
+    let changes = eval_staged(
+        vec![
+            RedirectOrVarAssig::Redirect(mock_redirect(RedirectAction::Open(
+                1,
+                fdes.clone(),
+                Permissions::Write,
+            ))),
+            RedirectOrVarAssig::VarAssig(
+                key.clone(),
+                Some(mock_word_fields(Fields::Single((*val).clone()))),
+            ),
+            RedirectOrVarAssig::Redirect(mock_redirect(RedirectAction::Close(2))),
+        ],
+        &mut env,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(changes.len(), 3);
+    assert_eq!(env.file_desc(1), None);
+    assert_eq!(env.var(&key), None);
+
+    let mut restorer = EnvRestorer::new(&mut env);
+    changes.apply(&mut restorer);
+
+    assert_eq!(
+        restorer.get().file_desc(1),
+        Some((&fdes, Permissions::Write))
+    );
+    assert_eq!(restorer.get().exported_var(&key), Some((&val, true)));
+    assert_eq!(restorer.redirected_fds(), vec![1, 2]);
+
+    drop(restorer);
+    assert_eq!(env.file_desc(1), None);
+    assert_eq!(env.var(&key), None);
+}
+
+#[tokio::test]
+async fn staged_changes_are_undone_on_error_or_cancellation() {
+    let mut env = new_env_with_no_fds();
+    let key = Arc::new("key".to_owned());
+
+    let vars = |env: &mut DefaultEnvArc, last| {
+        vec![
+            RedirectOrVarAssig::Redirect(mock_redirect(RedirectAction::Open(
+                1,
+                dev_null(env),
+                Permissions::Write,
+            ))),
+            RedirectOrVarAssig::VarAssig(
+                key.clone(),
+                Some(mock_word_fields(Fields::Single("val".to_owned()))),
+            ),
+            last,
+        ]
+    };
+
+    let vars_with_error = vars(
+        &mut env,
+        RedirectOrVarAssig::VarAssig(key.clone(), Some(mock_word_error(false))),
+    );
+    assert_eq!(
+        eval_staged(vars_with_error, &mut env).await.unwrap_err(),
+        EvalRedirectOrVarAssigError::VarAssig(MockErr::Fatal(false))
+    );
+    assert_eq!(env.file_desc(1), None);
+    assert_eq!(env.var(&key), None);
+
+    let vars_pending = vars(
+        &mut env,
+        RedirectOrVarAssig::VarAssig(key.clone(), Some(mock_word_pending())),
+    );
+    let mut restorer = EnvRestorer::new(&mut env);
+    let future =
+        eval_redirects_or_var_assignments_staged(None, vars_pending.into_iter(), &mut restorer);
+    assert!(future.boxed().now_or_never().is_none());
+
+    // NB: the restorer itself should not be left with anything to undo
+    assert_eq!(restorer.frame_depth(), 0);
+    assert_eq!(restorer.redirected_fds(), Vec::<Fd>::new());
+    assert_eq!(restorer.get().file_desc(1), None);
+    assert_eq!(restorer.get().var(&key), None);
+}
@@ -8,6 +8,10 @@ pub use self::support::*;
 
 type SimpleWord = ast::SimpleWord<String, MockParam, MockWord>;
 
+fn new_string_env() -> DefaultEnv<String> {
+    DefaultEnv::new().expect("failed to create env")
+}
+
 async fn assert_eval_equals_single(word: SimpleWord, expected: String) {
     assert_eval_equals_fields(word, Fields::Single(expected)).await;
 }
@@ -19,7 +23,7 @@ async fn assert_eval_equals_fields(word: SimpleWord, fields: Fields<String>) {
         split_fields_further: true,
     };
 
-    let mut env = VarEnv::<String, String>::new();
+    let mut env = new_string_env();
     let future = word
         .eval_with_config(&mut env, cfg)
         .await
@@ -58,7 +62,7 @@ async fn test_lone_tilde_expansion() {
     };
 
     let home_value = "foo bar".to_owned();
-    let mut env = VarEnv::new();
+    let mut env = new_string_env();
     env.set_var("HOME".to_owned(), home_value.clone());
 
     let word: SimpleWord = Tilde;
@@ -71,6 +75,30 @@ async fn test_lone_tilde_expansion() {
     assert_eq!(Fields::Single(home_value), future.await);
 }
 
+#[tokio::test]
+async fn test_lone_tilde_expansion_uses_pinned_home_dir() {
+    let cfg = WordEvalConfig {
+        tilde_expansion: TildeExpansion::First,
+        split_fields_further: true,
+    };
+
+    let home_value = "/pinned/home".to_owned();
+    let mut env = Env::with_config(EnvConfig {
+        home_dir: Some(home_value.clone()),
+        ..DefaultEnvConfig::<String>::new().expect("failed to create env config")
+    });
+    env.set_var("HOME".to_owned(), "/var/home".to_owned());
+
+    let word: SimpleWord = Tilde;
+    let future = word
+        .eval_with_config(&mut env, cfg)
+        .await
+        .expect("eval failed");
+    drop(env);
+
+    assert_eq!(Fields::Single(home_value), future.await);
+}
+
 #[tokio::test]
 async fn test_subst() {
     let fields = Fields::Single("foo".to_owned());
@@ -84,7 +112,7 @@ async fn test_subst_error() {
         split_fields_further: false,
     };
 
-    let mut env = VarEnv::<String, String>::new();
+    let mut env = new_string_env();
     let word: SimpleWord = Subst(mock_word_error(true));
 
     assert_eq!(
@@ -115,7 +143,7 @@ async fn test_param_splitting() {
         // Specific fields here aren't too important
         let fields = Fields::Split(vec!["~".to_owned(), "foo".to_owned()]);
 
-        let mut env = VarEnv::<String, String>::new();
+        let mut env = new_string_env();
         let word: SimpleWord = Param(MockParam::Split(split, fields.clone()));
         let future = word
             .eval_with_config(&mut env, cfg)
@@ -126,3 +154,29 @@ async fn test_param_splitting() {
         assert_eq!(fields, future.await);
     }
 }
+
+#[tokio::test]
+async fn test_param_unset_with_nounset() {
+    let cfg = WordEvalConfig {
+        tilde_expansion: TildeExpansion::None,
+        split_fields_further: false,
+    };
+
+    let mut env = new_string_env();
+    env.options_mut().nounset = true;
+
+    let word: SimpleWord = Param(MockParam::Fields(None));
+    let err = ExpansionError::UnsetParameter("MockParam".to_owned());
+    assert_eq!(
+        Some(MockErr::ExpansionError(err)),
+        word.eval_with_config(&mut env, cfg).await.err()
+    );
+
+    // Null parameters are still considered set
+    let word: SimpleWord = Param(MockParam::Fields(Some(Fields::Zero)));
+    let future = word
+        .eval_with_config(&mut env, cfg)
+        .await
+        .expect("eval failed");
+    assert_eq!(Fields::Zero, future.await);
+}
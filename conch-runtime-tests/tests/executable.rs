@@ -5,6 +5,7 @@ use futures_util::future::{join4, FutureExt};
 use std::borrow::Cow;
 use std::env::current_dir;
 use std::ffi::OsStr;
+use std::path::Path;
 
 mod support;
 pub use self::support::*;
@@ -24,12 +25,15 @@ async fn spawn_executable_with_io() {
 
     let data = ExecutableData {
         name: OsStr::new(&bin_path),
+        path: None,
         args: &[],
         env_vars: &[],
         current_dir: &current_dir().expect("failed to get current_dir"),
-        stdin: Some(pipe_in.reader.try_unwrap().expect("unwrap failed")),
-        stdout: Some(pipe_out.writer.try_unwrap().expect("unwrap failed")),
-        stderr: Some(pipe_err.writer.try_unwrap().expect("unwrap failed")),
+        stdin: ExecutableStdio::Piped(pipe_in.reader.try_unwrap().expect("unwrap failed")),
+        stdout: ExecutableStdio::Piped(pipe_out.writer.try_unwrap().expect("unwrap failed")),
+        stderr: ExecutableStdio::Piped(pipe_err.writer.try_unwrap().expect("unwrap failed")),
+        name_tainted: false,
+        args_tainted: &[],
     };
 
     let pipe_in_writer = pipe_in.writer;
@@ -68,18 +72,15 @@ async fn env_vars_set_from_data_without_inheriting_from_process() {
     let pipe_out = io_env.open_pipe().unwrap();
 
     let bin_path = bin_path("env");
-    let data = ExecutableData {
-        name: OsStr::new(&bin_path),
-        args: &[],
-        env_vars: &[
-            (OsStr::new("foo"), OsStr::new("bar")),
-            (OsStr::new("PATH"), OsStr::new("qux")),
-        ],
-        current_dir: &current_dir().expect("failed to get current_dir"),
-        stdin: None,
-        stdout: Some(pipe_out.writer.try_unwrap().expect("unwrap failed")),
-        stderr: None,
-    };
+    let cur_dir = current_dir().expect("failed to get current_dir");
+    let env_vars = [
+        (OsStr::new("foo"), OsStr::new("bar")),
+        (OsStr::new("PATH"), OsStr::new("qux")),
+    ];
+    let data = ExecutableData::builder(OsStr::new(&bin_path), &cur_dir)
+        .env_vars(&env_vars)
+        .stdout(pipe_out.writer.try_unwrap().expect("unwrap failed"))
+        .build();
 
     let child = env.spawn_executable(data).expect("spawn failed");
     let stdout = io_env.read_all(pipe_out.reader);
@@ -97,6 +98,66 @@ async fn env_vars_set_from_data_without_inheriting_from_process() {
     assert!(child.await.success());
 }
 
+#[cfg(unix)]
+#[tokio::test]
+async fn spawn_executable_with_usage_collects_resource_usage() {
+    let env = TokioExecEnv::new();
+
+    let bin_path = bin_path("env");
+    let cur_dir = current_dir().expect("failed to get current_dir");
+    let data = ExecutableData::builder(OsStr::new(&bin_path), &cur_dir).build();
+
+    let child = env.spawn_executable_with_usage(data).expect("spawn failed");
+    drop(env);
+
+    let (status, usage) = child.await;
+    assert!(status.success());
+
+    let usage = usage.expect("no resource usage");
+    assert!(usage.max_rss > 0);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn spawn_executable_with_usage_kills_and_reaps_dropped_children() {
+    use std::time::{Duration, Instant};
+
+    let env = TokioExecEnv::new();
+    let cur_dir = current_dir().expect("failed to get current_dir");
+    let args = [OsStr::new("-c"), OsStr::new("while :; do :; done")];
+    let data = ExecutableData::builder(OsStr::new("/bin/sh"), &cur_dir)
+        .args(&args)
+        .build();
+
+    let started = Instant::now();
+    let child = env.spawn_executable_with_usage(data).expect("spawn failed");
+    let timeout = tokio::time::timeout(Duration::from_millis(50), child).await;
+    assert!(timeout.is_err());
+
+    // NB: the child is killed (and reaped in the background) once the future is dropped
+    assert!(started.elapsed() < Duration::from_secs(10));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn execution_report_includes_resource_usage_of_executables() {
+    use conch_parser::ast::builder::ArcBuilder;
+
+    let reporter = ExecutionReporter::new();
+    let mut env = new_env_with_no_fds();
+    env.set_execution_reporter(Some(reporter.clone()));
+
+    let mut session = Session::new(env);
+    let script = format!("{}; true\n", bin_path("env").display());
+    let batch = session.parse::<ArcBuilder>(&script).unwrap();
+    assert_eq!(session.execute(batch).await.unwrap(), EXIT_SUCCESS);
+
+    let report = reporter.report();
+    assert_eq!(report.commands.len(), 2);
+    assert!(report.commands[0].resource_usage.is_some());
+    assert_eq!(report.commands[1].resource_usage, None);
+}
+
 #[tokio::test]
 async fn remote_spawn_smoke() {
     let env = TokioExecEnv::new();
@@ -105,12 +166,15 @@ async fn remote_spawn_smoke() {
 
     let data = ExecutableData {
         name: OsStr::new(&bin_path),
+        path: None,
         args: &[],
         env_vars: &[],
         current_dir: &current_dir().expect("failed to get current_dir"),
-        stdin: None,
-        stdout: None,
-        stderr: None,
+        stdin: ExecutableStdio::Null,
+        stdout: ExecutableStdio::Null,
+        stderr: ExecutableStdio::Null,
+        name_tainted: false,
+        args_tainted: &[],
     };
 
     // Spawning when not running in a task is the same as spawning
@@ -128,15 +192,11 @@ async fn defines_empty_path_env_var_if_not_provided_by_caller() {
     let pipe_out = io_env.open_pipe().unwrap();
 
     let bin_path = bin_path("env");
-    let data = ExecutableData {
-        name: OsStr::new(&bin_path),
-        args: &[],
-        env_vars: &[],
-        current_dir: &current_dir().expect("failed to get current_dir"),
-        stdin: None,
-        stdout: Some(pipe_out.writer.try_unwrap().expect("unwrap failed")),
-        stderr: None,
-    };
+    let cur_dir = current_dir().expect("failed to get current_dir");
+    let data = ExecutableData::builder(OsStr::new(&bin_path), &cur_dir)
+        .stdin(ExecutableStdio::Inherit)
+        .stdout(pipe_out.writer.try_unwrap().expect("unwrap failed"))
+        .build();
 
     let child = env.spawn_executable(data).expect("child failed");
     let stdout = io_env.read_all(pipe_out.reader);
@@ -147,3 +207,270 @@ async fn defines_empty_path_env_var_if_not_provided_by_caller() {
     assert_eq!(b"PATH=\n", &*stdout.await.expect("read failed"));
     assert!(child.await.success());
 }
+
+#[test]
+fn builder_defaults_to_null_stdio_without_args_or_env_vars() {
+    let name = OsStr::new("name");
+    let cur_dir = current_dir().expect("failed to get current_dir");
+    let args = [OsStr::new("arg")];
+
+    assert_eq!(
+        ExecutableData::builder(name, &cur_dir).build(),
+        ExecutableData {
+            name,
+            path: None,
+            args: &[],
+            env_vars: &[],
+            current_dir: &cur_dir,
+            stdin: ExecutableStdio::Null,
+            stdout: ExecutableStdio::Null,
+            stderr: ExecutableStdio::Null,
+            name_tainted: false,
+            args_tainted: &[],
+        }
+    );
+
+    assert_eq!(
+        ExecutableData::builder(name, &cur_dir)
+            .path(&cur_dir)
+            .args(&args)
+            .stdin(ExecutableStdio::Inherit)
+            .stderr(None)
+            .build(),
+        ExecutableData {
+            name,
+            path: Some(&cur_dir),
+            args: &args,
+            env_vars: &[],
+            current_dir: &cur_dir,
+            stdin: ExecutableStdio::Inherit,
+            stdout: ExecutableStdio::Null,
+            stderr: ExecutableStdio::Null,
+            name_tainted: false,
+            args_tainted: &[],
+        }
+    );
+}
+
+#[test]
+fn isolation_is_disabled_by_default() {
+    let isolation = SpawnIsolation::default();
+    assert!(!isolation.is_enabled());
+    assert_eq!(*TokioExecEnv::new().isolation(), isolation);
+
+    let isolation = SpawnIsolation {
+        unshare_net: true,
+        ..SpawnIsolation::default()
+    };
+    assert!(isolation.is_enabled());
+    assert_eq!(
+        *TokioExecEnv::with_isolation(isolation.clone()).isolation(),
+        isolation
+    );
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn chroot_should_hide_executables_outside_of_jail() {
+    let jail = mktmp!();
+    let env = TokioExecEnv::with_isolation(SpawnIsolation {
+        chroot: Some(jail.path().to_owned()),
+        ..SpawnIsolation::default()
+    });
+
+    // The executable does not exist within the (empty) jail, and unprivileged
+    // users are not permitted to chroot at all. Either way it should not run.
+    let bin_path = bin_path("env");
+    let data = ExecutableData::builder(OsStr::new(&bin_path), Path::new("/")).build();
+    match env.spawn_executable(data) {
+        Err(CommandError::NotFound(_)) | Err(CommandError::Io(_, _)) => {}
+        Err(e) => panic!("unexpected error: {}", e),
+        Ok(_) => panic!("unexpectedly spawned executable outside of jail"),
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn unsharing_namespaces_should_spawn_or_fail_gracefully() {
+    let env = TokioExecEnv::with_isolation(SpawnIsolation {
+        unshare_mount: true,
+        unshare_net: true,
+        unshare_pid: true,
+        chroot: None,
+    });
+
+    let bin_path = bin_path("env");
+    let cur_dir = current_dir().expect("failed to get current_dir");
+    let data = ExecutableData::builder(OsStr::new(&bin_path), &cur_dir).build();
+
+    // Creating namespaces requires privileges the tests may not be running with
+    match env.spawn_executable(data) {
+        Ok(child) => assert_eq!(child.await, EXIT_SUCCESS),
+        Err(CommandError::Io(_, Some(name))) => assert_eq!(name, bin_path.display().to_string()),
+        Err(e) => panic!("unexpected error: {}", e),
+    }
+}
+
+#[cfg(all(unix, feature = "spawn-policy"))]
+mod spawn_policy {
+    use super::*;
+    use std::io;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[derive(Debug, Default)]
+    struct MockPolicy {
+        prepared: AtomicUsize,
+        fail_prepare: bool,
+        fail_install: bool,
+    }
+
+    impl SpawnPolicy for MockPolicy {
+        fn prepare(&self, data: &ExecutableData<'_>) -> io::Result<PolicyInstaller> {
+            assert!(!data.name.is_empty());
+            self.prepared.fetch_add(1, Ordering::SeqCst);
+
+            if self.fail_prepare {
+                return Err(io::Error::from(io::ErrorKind::InvalidInput));
+            }
+
+            let fail_install = self.fail_install;
+            Ok(Box::new(move || {
+                if fail_install {
+                    Err(io::Error::from(io::ErrorKind::PermissionDenied))
+                } else {
+                    Ok(())
+                }
+            }))
+        }
+    }
+
+    async fn spawn(
+        policy: MockPolicy,
+        name: &OsStr,
+    ) -> (Arc<MockPolicy>, Result<ExitStatus, CommandError>) {
+        let policy = Arc::new(policy);
+        let env = TokioExecEnv::new().with_spawn_policy(policy.clone());
+
+        let cur_dir = current_dir().expect("failed to get current_dir");
+        let data = ExecutableData::builder(name, &cur_dir).build();
+        let result = match env.spawn_executable(data) {
+            Ok(child) => Ok(child.await),
+            Err(e) => Err(e),
+        };
+
+        (policy, result)
+    }
+
+    #[tokio::test]
+    async fn policy_is_prepared_for_every_spawned_executable() {
+        let bin_path = bin_path("env");
+        let (policy, result) = spawn(MockPolicy::default(), OsStr::new(&bin_path)).await;
+
+        assert_eq!(result, Ok(EXIT_SUCCESS));
+        assert_eq!(policy.prepared.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn failing_to_prepare_or_install_policy_is_reported() {
+        let bin_path = bin_path("env");
+        let name = bin_path.display().to_string();
+
+        let policy = MockPolicy {
+            fail_prepare: true,
+            ..MockPolicy::default()
+        };
+        match spawn(policy, OsStr::new(&bin_path)).await.1 {
+            Err(CommandError::SpawnPolicy(ref e, ref n)) => {
+                assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+                assert_eq!(*n, name);
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        let policy = MockPolicy {
+            fail_install: true,
+            ..MockPolicy::default()
+        };
+        let err = match spawn(policy, OsStr::new(&bin_path)).await.1 {
+            Err(e @ CommandError::SpawnPolicy(_, _)) => e,
+            result => panic!("unexpected result: {:?}", result),
+        };
+        assert_eq!(err.exit_status(), conch_runtime::EXIT_CMD_NOT_EXECUTABLE);
+        assert!(err
+            .to_string()
+            .starts_with(&format!("{}: failed to apply spawn policy", name)));
+    }
+
+    #[tokio::test]
+    async fn missing_executables_are_not_reported_as_policy_failures() {
+        let (policy, result) =
+            spawn(MockPolicy::default(), OsStr::new("conch-runtime-missing")).await;
+
+        assert_eq!(
+            result,
+            Err(CommandError::NotFound("conch-runtime-missing".to_owned()))
+        );
+        assert_eq!(policy.prepared.load(Ordering::SeqCst), 1);
+    }
+}
+
+#[cfg(windows)]
+mod job_object {
+    use super::*;
+    use conch_runtime::io::FileDescWrapper;
+
+    #[tokio::test]
+    async fn spawned_executables_run_within_job() {
+        let job = JobObject::new().expect("failed to create job");
+        job.set_limits(&JobLimits {
+            job_memory: Some(512 * 1024 * 1024),
+            process_memory: None,
+            cpu_rate_percent: Some(50),
+        })
+        .expect("failed to set limits");
+
+        let env = TokioExecEnv::new().with_job_object(job);
+        assert!(env.job_object().is_some());
+
+        let bin_path = bin_path("env");
+        let cur_dir = current_dir().expect("failed to get current_dir");
+        let data = ExecutableData::builder(OsStr::new(&bin_path), &cur_dir).build();
+
+        let child = env.spawn_executable(data).expect("spawn failed");
+        assert!(child.await.success());
+    }
+
+    #[tokio::test]
+    async fn terminating_job_kills_spawned_executables() {
+        let env = TokioExecEnv::new().with_job_object(JobObject::new().unwrap());
+        let mut io_env = TokioFileDescManagerEnv::new();
+
+        // NB: the child will block until its stdin is closed
+        let pipe = io_env.open_pipe().unwrap();
+        let bin_path = bin_path("cat-dup");
+        let cur_dir = current_dir().expect("failed to get current_dir");
+        let data = ExecutableData::builder(OsStr::new(&bin_path), &cur_dir)
+            .stdin(pipe.reader.try_unwrap().expect("unwrap failed"))
+            .build();
+
+        let child = env.spawn_executable(data).expect("spawn failed");
+        env.job_object().unwrap().terminate(42).unwrap();
+
+        assert_eq!(child.await, ExitStatus::Code(42));
+        drop(pipe.writer);
+    }
+
+    #[test]
+    fn invalid_cpu_rates_are_rejected() {
+        let job = JobObject::new().unwrap();
+
+        for &rate in &[0, 101] {
+            let limits = JobLimits {
+                cpu_rate_percent: Some(rate),
+                ..JobLimits::default()
+            };
+            assert!(job.set_limits(&limits).is_err());
+        }
+    }
+}
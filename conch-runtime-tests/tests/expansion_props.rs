@@ -0,0 +1,255 @@
+#![deny(rust_2018_idioms)]
+#![cfg(feature = "fuzz")]
+
+//! Property based tests which cross-check the expansion semantics of the runtime
+//! against a (naive) reference implementation, as well as against `dash` whenever
+//! it is installed and the inputs are ASCII only (`dash` is not multibyte aware).
+//!
+//! Run via `cargo test -p conch-runtime-tests --features fuzz`.
+
+use conch_runtime::eval::{
+    alternative, default, len, remove_largest_prefix, remove_largest_suffix,
+    remove_smallest_prefix, remove_smallest_suffix, Fields, TildeExpansion,
+};
+use futures_util::future::FutureExt;
+use proptest::prelude::*;
+use std::process::Command;
+
+mod support;
+pub use self::support::*;
+
+const VALUE: &str = "[ab: \t\né]{0,10}";
+const PATTERN: &str = "[ab*?é]{0,4}";
+const IFS_VALUE: &str = "[ :\t\né]{0,3}";
+
+/// Runs `script` with `dash`, exporting any set `vars`, and returns its stdout,
+/// or `None` if `dash` is unavailable or any of the inputs are not ASCII.
+fn dash(script: &str, vars: &[(&str, Option<&str>)]) -> Option<Vec<u8>> {
+    if !vars.iter().all(|(_, val)| val.map_or(true, str::is_ascii)) {
+        return None;
+    }
+
+    let mut cmd = Command::new("dash");
+    cmd.arg("-c").arg(script).env_clear();
+    for (name, val) in vars {
+        if let Some(val) = val {
+            cmd.env(name, val);
+        }
+    }
+
+    let output = cmd.output().ok()?;
+    assert!(output.status.success(), "dash failed: {:?}", output);
+    Some(output.stdout)
+}
+
+fn eval_word<F>(future: F) -> String
+where
+    F: std::future::Future<Output = Result<Fields<String>, MockErr>>,
+{
+    future
+        .now_or_never()
+        .expect("mock words should evaluate immediately")
+        .expect("mock words should not error")
+        .into_iter()
+        .collect()
+}
+
+fn param(val: &Option<String>) -> MockParam {
+    MockParam::Fields(val.clone().map(Fields::Single))
+}
+
+fn word(val: &str) -> Option<MockWord> {
+    Some(mock_word_fields(Fields::Single(val.to_owned())))
+}
+
+fn glob_matches(pat: &[char], s: &[char]) -> bool {
+    match pat.split_first() {
+        None => s.is_empty(),
+        Some(('*', rest)) => (0..=s.len()).any(|i| glob_matches(rest, &s[i..])),
+        Some(('?', rest)) => !s.is_empty() && glob_matches(rest, &s[1..]),
+        Some((c, rest)) => s.first() == Some(c) && glob_matches(rest, &s[1..]),
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Remove {
+    SmallestSuffix,
+    LargestSuffix,
+    SmallestPrefix,
+    LargestPrefix,
+}
+
+impl Remove {
+    fn operator(self) -> &'static str {
+        match self {
+            Remove::SmallestSuffix => "%",
+            Remove::LargestSuffix => "%%",
+            Remove::SmallestPrefix => "#",
+            Remove::LargestPrefix => "##",
+        }
+    }
+
+    fn reference(self, val: &str, pat: &str) -> String {
+        let val = val.chars().collect::<Vec<_>>();
+        let pat = pat.chars().collect::<Vec<_>>();
+
+        let mut splits = (0..=val.len()).collect::<Vec<_>>();
+        if let Remove::SmallestSuffix | Remove::LargestPrefix = self {
+            splits.reverse();
+        }
+
+        let (head, tail) = match self {
+            Remove::SmallestSuffix | Remove::LargestSuffix => splits
+                .into_iter()
+                .find(|&i| glob_matches(&pat, &val[i..]))
+                .map(|i| (0, i)),
+            Remove::SmallestPrefix | Remove::LargestPrefix => splits
+                .into_iter()
+                .find(|&i| glob_matches(&pat, &val[..i]))
+                .map(|i| (i, val.len())),
+        }
+        .unwrap_or((0, val.len()));
+
+        val[head..tail].iter().collect()
+    }
+
+    fn eval(self, val: &Option<String>, pat: &str) -> String {
+        let param = param(val);
+        let env = &mut ();
+
+        eval_word(match self {
+            Remove::SmallestSuffix => remove_smallest_suffix(&param, word(pat), env).boxed(),
+            Remove::LargestSuffix => remove_largest_suffix(&param, word(pat), env).boxed(),
+            Remove::SmallestPrefix => remove_smallest_prefix(&param, word(pat), env).boxed(),
+            Remove::LargestPrefix => remove_largest_prefix(&param, word(pat), env).boxed(),
+        })
+    }
+}
+
+fn split_reference(val: &str, ifs: &str) -> Vec<String> {
+    // A null IFS leaves the value as is, even if it is empty
+    if ifs.is_empty() {
+        return vec![val.to_owned()];
+    }
+
+    let val = val.chars().collect::<Vec<_>>();
+
+    let is_ifs = |c: char| ifs.contains(c);
+    let is_ifs_ws = |c: char| is_ifs(c) && c.is_whitespace();
+
+    let mut fields = Vec::new();
+    let mut i = 0;
+    while i < val.len() && is_ifs_ws(val[i]) {
+        i += 1;
+    }
+
+    while i < val.len() {
+        let start = i;
+        while i < val.len() && !is_ifs(val[i]) {
+            i += 1;
+        }
+        fields.push(val[start..i].iter().collect());
+
+        // A delimiter is any IFS whitespace, optionally surrounding
+        // a single non-whitespace IFS character.
+        while i < val.len() && is_ifs_ws(val[i]) {
+            i += 1;
+        }
+        if i < val.len() && is_ifs(val[i]) {
+            i += 1;
+            while i < val.len() && is_ifs_ws(val[i]) {
+                i += 1;
+            }
+        }
+    }
+
+    fields
+}
+
+proptest! {
+    #[test]
+    fn field_splitting(val in VALUE, ifs in proptest::option::of(IFS_VALUE)) {
+        let mut env = VarEnv::<String, String>::new();
+        if let Some(ref ifs) = ifs {
+            env.set_var("IFS".to_owned(), ifs.clone());
+        }
+
+        let actual = Fields::Single(val.clone()).split(&env).into_iter().collect::<Vec<_>>();
+        let expected = split_reference(&val, ifs.as_deref().unwrap_or(" \t\n"));
+        prop_assert_eq!(&actual, &expected);
+
+        let script = r#"set -f; if [ -n "${I+x}" ]; then IFS=$I; else unset IFS; fi; set -- $V; for f; do printf '%s\0' "$f"; done"#;
+        // Unlike the runtime, the shell discards an empty field even if IFS is null
+        let keeps_empty = val.is_empty() && ifs.as_deref() == Some("");
+        let dash = if keeps_empty {
+            None
+        } else {
+            dash(script, &[("V", Some(&val)), ("I", ifs.as_deref())])
+        };
+
+        if let Some(out) = dash {
+            let out = String::from_utf8(out).unwrap();
+            let dash = out.split_terminator('\0').map(String::from).collect::<Vec<_>>();
+            prop_assert_eq!(&actual, &dash);
+        }
+    }
+
+    #[test]
+    fn pattern_removal(
+        val in proptest::option::of(VALUE),
+        pat in PATTERN,
+        op in prop_oneof![
+            Just(Remove::SmallestSuffix),
+            Just(Remove::LargestSuffix),
+            Just(Remove::SmallestPrefix),
+            Just(Remove::LargestPrefix),
+        ],
+    ) {
+        let actual = op.eval(&val, &pat);
+        let expected = val.as_ref().map_or_else(String::new, |val| op.reference(val, &pat));
+        prop_assert_eq!(&actual, &expected);
+
+        let script = format!(r#"printf '%s' "${{V{}$P}}""#, op.operator());
+        if let Some(out) = dash(&script, &[("V", val.as_deref()), ("P", Some(&pat))]) {
+            prop_assert_eq!(actual.as_bytes(), &*out);
+        }
+    }
+
+    #[test]
+    fn default_alternative_and_len(
+        val in proptest::option::of(VALUE),
+        alt in VALUE,
+        strict in any::<bool>(),
+    ) {
+        let param = param(&val);
+        let env = &mut ();
+        let cfg = TildeExpansion::None;
+
+        let is_present = match val {
+            Some(ref val) => !strict || !val.is_empty(),
+            None => false,
+        };
+
+        let actual_default = eval_word(default(strict, &param, word(&alt), env, cfg));
+        let expected_default = if is_present { val.clone().unwrap() } else { alt.clone() };
+        prop_assert_eq!(&actual_default, &expected_default);
+
+        let actual_alternative = eval_word(alternative(strict, &param, word(&alt), env, cfg));
+        let expected_alternative = if is_present { alt.clone() } else { String::new() };
+        prop_assert_eq!(&actual_alternative, &expected_alternative);
+
+        let actual_len = len(&param, env);
+        let expected_len = val.as_ref().map_or(0, |val| val.chars().count()).to_string();
+        prop_assert_eq!(&actual_len, &expected_len);
+
+        let colon = if strict { ":" } else { "" };
+        let script = format!(
+            r#"printf '%s\0%s\0%s' "${{V{0}-$A}}" "${{V{0}+$A}}" "${{#V}}""#,
+            colon
+        );
+        if let Some(out) = dash(&script, &[("V", val.as_deref()), ("A", Some(&alt))]) {
+            let expected = format!("{}\0{}\0{}", actual_default, actual_alternative, actual_len);
+            prop_assert_eq!(expected.as_bytes(), &*out);
+        }
+    }
+}
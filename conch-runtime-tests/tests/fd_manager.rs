@@ -1,6 +1,9 @@
 #![deny(rust_2018_idioms)]
 
-use conch_runtime::env::{AsyncIoEnvironment, FileDescOpener, TokioFileDescManagerEnv};
+use conch_runtime::env::{
+    AsyncIoEnvironment, FileDescOpener, SubEnvironment, TokioFileDescManagerEnv,
+};
+use conch_runtime::error::FdLimitExceededError;
 use futures_util::future::try_join3;
 use std::borrow::Cow;
 
@@ -27,3 +30,51 @@ async fn fd_manager() {
     assert_eq!(read_msg, msg.as_bytes());
     assert_eq!(read_msg_best_effort, msg.as_bytes());
 }
+
+#[tokio::test]
+async fn fd_usage_tracks_open_fds() {
+    let mut env = TokioFileDescManagerEnv::new();
+    let usage = env.fd_usage().clone();
+    assert_eq!(usage.open_fds(), 0);
+
+    let pipe = env.open_pipe().expect("failed to create pipe");
+    assert_eq!(usage.open_fds(), 2);
+
+    let mut sub_env = env.sub_env();
+    let sub_pipe = sub_env.open_pipe().expect("failed to create pipe");
+    assert_eq!(usage.open_fds(), 4);
+
+    drop(sub_pipe);
+    drop(sub_env);
+    assert_eq!(usage.open_fds(), 2);
+
+    // Handles which are still referenced elsewhere are still open
+    let reader = pipe.reader.clone();
+    drop(pipe);
+    assert_eq!(usage.open_fds(), 1);
+
+    drop(reader);
+    assert_eq!(usage.open_fds(), 0);
+}
+
+#[tokio::test]
+async fn fd_usage_limit() {
+    let mut env = TokioFileDescManagerEnv::new();
+    env.fd_usage().set_limit(Some(3));
+    assert_eq!(env.fd_usage().limit(), Some(3));
+
+    let pipe = env.open_pipe().expect("failed to create pipe");
+    let err = env.open_pipe().unwrap_err();
+    let inner = err
+        .get_ref()
+        .and_then(|e| e.downcast_ref::<FdLimitExceededError>());
+    assert_eq!(inner, Some(&FdLimitExceededError { limit: 3 }));
+    assert_eq!(env.fd_usage().open_fds(), 2);
+
+    drop(pipe);
+    let _pipe = env.open_pipe().expect("failed to create pipe");
+
+    env.fd_usage().set_limit(None);
+    let _pipe = env.open_pipe().expect("failed to create pipe");
+    assert_eq!(env.fd_usage().open_fds(), 4);
+}
@@ -1,7 +1,11 @@
 #![deny(rust_2018_idioms)]
 use conch_runtime;
 
-use conch_runtime::env::{FileDescOpener, Pipe};
+use conch_runtime::env::{
+    FileDescAuditEnvironment, FileDescEnvironment, FileDescOpener, Pipe, TokioFileDescManagerEnv,
+};
+use conch_runtime::io::Permissions;
+use conch_runtime::Fd;
 use std::fs::OpenOptions;
 use std::io::{Read, Write};
 use std::path::PathBuf;
@@ -59,3 +63,45 @@ async fn smoke_pipe() {
     guard.join().unwrap();
     assert_eq!(msg, read);
 }
+
+#[tokio::test]
+async fn cloexec_is_set_by_default_and_controllable() {
+    let tempdir = mktmp!();
+    let file_path = tempdir.path().join("out");
+
+    let mut opts = OpenOptions::new();
+    opts.write(true).create(true);
+
+    let mut opener = FileDescOpenerEnv::new();
+
+    let fd = opener.open_path(&file_path, &opts).unwrap();
+    assert!(fd.is_cloexec().unwrap());
+    let fd = opener
+        .open_path_with_cloexec(&file_path, &opts, false)
+        .unwrap();
+    assert!(!fd.is_cloexec().unwrap());
+
+    let pipe = opener.open_pipe().unwrap();
+    assert!(pipe.reader.is_cloexec().unwrap());
+    assert!(pipe.writer.is_cloexec().unwrap());
+    let pipe = opener.open_pipe_with_cloexec(false).unwrap();
+    assert!(!pipe.reader.is_cloexec().unwrap());
+    assert!(!pipe.writer.is_cloexec().unwrap());
+}
+
+#[tokio::test]
+async fn audit_inheritable_fds() {
+    let mut env = TokioFileDescManagerEnv::with_process_stdio().unwrap();
+    assert_eq!(env.inheritable_fds().unwrap(), Vec::<Fd>::new());
+
+    let leaked = env.open_pipe_with_cloexec(false).unwrap();
+    let pipe = env.open_pipe().unwrap();
+    env.set_file_desc(3, pipe.reader, Permissions::Read);
+    env.set_file_desc(6, leaked.writer, Permissions::Write);
+    env.set_file_desc(5, leaked.reader, Permissions::Read);
+
+    assert_eq!(env.inheritable_fds().unwrap(), vec![5, 6]);
+
+    env.close_file_desc(5);
+    assert_eq!(env.inheritable_fds().unwrap(), vec![6]);
+}
@@ -1,6 +1,7 @@
 #![deny(rust_2018_idioms)]
 
 use conch_runtime::env::{UnsetVariableEnvironment, VarEnv, VariableEnvironment};
+use conch_runtime::eval::join_fields;
 use conch_runtime::eval::Fields::*;
 
 #[tokio::test]
@@ -63,7 +64,27 @@ async fn test_fields_join_with_ifs() {
     assert_eq!(Single("foo".to_owned()).join_with_ifs(&env), "foo");
     assert_eq!(At(strs.clone()).join_with_ifs(&env), "foo  bar");
     assert_eq!(Star(strs.clone()).join_with_ifs(&env), "foo  bar");
-    assert_eq!(Split(strs).join_with_ifs(&env), "foo  bar");
+    assert_eq!(Split(strs.clone()).join_with_ifs(&env), "foo  bar");
+
+    // Multi-byte first IFS character
+    env.set_var(ifs, "\u{1F4A9}\u{e9}".to_owned());
+    assert_eq!(Star(strs).join_with_ifs(&env), "foo\u{1F4A9}\u{1F4A9}bar");
+}
+
+#[tokio::test]
+async fn test_join_fields() {
+    let fields = ["foo", "", "bar"];
+
+    assert_eq!(join_fields(&fields, None), "foo  bar");
+    assert_eq!(join_fields(&fields, Some("")), "foobar");
+    assert_eq!(join_fields(&fields, Some(":x")), "foo::bar");
+    assert_eq!(join_fields(&fields, Some("\u{e9}:")), "foo\u{e9}\u{e9}bar");
+    assert_eq!(
+        join_fields(&fields, Some("\u{1F4A9}")),
+        "foo\u{1F4A9}\u{1F4A9}bar"
+    );
+    assert_eq!(join_fields(&["foo"], Some("\u{e9}")), "foo");
+    assert_eq!(join_fields(Vec::<String>::new(), Some("\u{e9}")), "");
 }
 
 #[tokio::test]
@@ -157,3 +178,35 @@ async fn test_no_splitting_if_ifs_blank() {
     let fields = Single(" \t\nfoo \t\nbar \t\n".to_owned());
     assert_eq!(fields.clone().split(&env), fields);
 }
+
+#[tokio::test]
+async fn test_splitting_whitespace_around_non_whitespace_ifs_is_a_single_delimiter() {
+    let mut env = VarEnv::new();
+    env.set_var("IFS".to_owned(), ": ".to_owned());
+
+    assert_eq!(
+        Single("foo : bar :: baz".to_owned()).split(&env),
+        Split(vec!(
+            "foo".to_owned(),
+            "bar".to_owned(),
+            "".to_owned(),
+            "baz".to_owned(),
+        ))
+    );
+}
+
+#[tokio::test]
+async fn test_splitting_multibyte_chars() {
+    let mut env = VarEnv::new();
+    env.set_var("IFS".to_owned(), "\u{e9} ".to_owned());
+
+    assert_eq!(
+        Single("\u{1F4A9} foo\u{e9}\u{e9}bar".to_owned()).split(&env),
+        Split(vec!(
+            "\u{1F4A9}".to_owned(),
+            "foo".to_owned(),
+            "".to_owned(),
+            "bar".to_owned(),
+        ))
+    );
+}
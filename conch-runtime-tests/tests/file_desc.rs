@@ -1,7 +1,7 @@
 #![deny(rust_2018_idioms)]
 use conch_runtime;
 
-use conch_runtime::io::{FileDesc, Pipe};
+use conch_runtime::io::{copy_fd, FileDesc, Pipe};
 use std::fs::File;
 use std::io::{Read, Write};
 use std::path::PathBuf;
@@ -71,3 +71,47 @@ async fn test_file_desc_seeking() {
 
     assert_eq!(read, "***???!!!");
 }
+
+#[tokio::test]
+async fn test_copy_fd() {
+    let msg = "copied message\n".repeat(10_000);
+
+    let tempdir = mktmp!();
+    let src_path = tempdir.path().join("src");
+    let dst_path = tempdir.path().join("dst");
+    std::fs::write(&src_path, &msg).unwrap();
+
+    // File to file
+    let src = FileDesc::from(File::open(&src_path).unwrap());
+    let dst = FileDesc::from(File::create(&dst_path).unwrap());
+    assert_eq!(copy_fd(&src, &dst).unwrap(), msg.len() as u64);
+    drop(dst);
+    assert_eq!(std::fs::read_to_string(&dst_path).unwrap(), msg);
+
+    // File to pipe
+    let Pipe { mut reader, writer } = Pipe::new().unwrap();
+    let src = FileDesc::from(File::open(&src_path).unwrap());
+    let guard = thread::spawn(move || copy_fd(&src, &writer).unwrap());
+
+    let mut read = String::new();
+    reader.read_to_string(&mut read).unwrap();
+    assert_eq!(guard.join().unwrap(), msg.len() as u64);
+    assert_eq!(read, msg);
+
+    // Pipe to file, starting from a partially consumed pipe
+    let Pipe { mut reader, writer } = Pipe::new().unwrap();
+    let msg_copy = msg.clone();
+    let guard = thread::spawn(move || {
+        let mut writer = writer;
+        writer.write_all(msg_copy.as_bytes()).unwrap();
+    });
+
+    let mut skipped = [0u8; 3];
+    reader.read_exact(&mut skipped).unwrap();
+
+    let dst = FileDesc::from(File::create(&dst_path).unwrap());
+    assert_eq!(copy_fd(&reader, &dst).unwrap(), msg.len() as u64 - 3);
+    guard.join().unwrap();
+    drop(dst);
+    assert_eq!(std::fs::read_to_string(&dst_path).unwrap(), &msg[3..]);
+}
@@ -0,0 +1,204 @@
+#![deny(rust_2018_idioms)]
+
+use conch_parser::ast::builder::ArcBuilder;
+use conch_runtime::io::FileDesc;
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+#[macro_use]
+mod support;
+pub use self::support::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Record {
+    path: PathBuf,
+    flags: Option<OpenFlags>,
+    cloexec: bool,
+    opened: bool,
+}
+
+/// An auditor which records all opens, and refuses to truncate existing files.
+#[derive(Debug, Default)]
+struct NoClobberAuditor {
+    records: Mutex<Vec<Record>>,
+}
+
+impl FileOpenAuditor for NoClobberAuditor {
+    fn check_open(&self, path: &Path, flags: Option<OpenFlags>) -> io::Result<()> {
+        match flags {
+            Some(flags) if flags.truncate && path.exists() => Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "cannot overwrite existing file",
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn record_open(&self, event: &FileOpenEvent<'_>) {
+        self.records.lock().unwrap().push(Record {
+            path: event.path.to_owned(),
+            flags: event.flags,
+            cloexec: event.cloexec,
+            opened: event.result.is_ok(),
+        });
+    }
+}
+
+fn write_flags() -> OpenFlags {
+    OpenFlags {
+        write: true,
+        create: true,
+        truncate: true,
+        ..OpenFlags::default()
+    }
+}
+
+#[test]
+fn open_flags_should_mirror_permissions() {
+    use conch_runtime::io::Permissions;
+
+    assert_eq!(
+        OpenFlags::from(Permissions::Read),
+        OpenFlags {
+            read: true,
+            ..OpenFlags::default()
+        }
+    );
+    assert_eq!(OpenFlags::from(Permissions::Write), write_flags());
+    assert_eq!(
+        OpenFlags::from(Permissions::ReadWrite),
+        OpenFlags {
+            read: true,
+            write: true,
+            create: true,
+            ..OpenFlags::default()
+        }
+    );
+}
+
+#[tokio::test]
+async fn should_report_opened_paths_and_descriptors() {
+    struct FdAuditor(Mutex<Vec<String>>);
+
+    impl FileOpenAuditor for FdAuditor {
+        fn record_open(&self, event: &FileOpenEvent<'_>) {
+            let fdes: &FileDesc = event.result.expect("open failed");
+            assert!(fdes.is_cloexec().unwrap());
+            self.0
+                .lock()
+                .unwrap()
+                .push(event.path.file_name().unwrap().to_str().unwrap().to_owned());
+        }
+    }
+
+    let tempdir = mktmp!();
+    let auditor = Arc::new(FdAuditor(Mutex::new(Vec::new())));
+    let mut opener = AuditFileDescOpenerEnv::new(FileDescOpenerEnv::new(), auditor.clone());
+
+    opener
+        .open_path_with_flags(&tempdir.path().join("foo"), write_flags(), true)
+        .unwrap();
+    opener
+        .open_path(
+            &tempdir.path().join("bar"),
+            OpenOptions::new().write(true).create(true),
+        )
+        .unwrap();
+    opener.open_pipe().unwrap();
+
+    assert_eq!(*auditor.0.lock().unwrap(), vec!["foo", "bar"]);
+}
+
+#[tokio::test]
+async fn sub_envs_should_share_auditor() {
+    let tempdir = mktmp!();
+    let auditor = Arc::new(NoClobberAuditor::default());
+    let opener = AuditFileDescOpenerEnv::new(FileDescOpenerEnv::new(), auditor.clone());
+
+    let mut sub = opener.sub_env();
+    assert_eq!(opener, sub);
+
+    let path = tempdir.path().join("out");
+    sub.open_path(&path, OpenOptions::new().write(true).create(true))
+        .unwrap();
+
+    assert_eq!(
+        *auditor.records.lock().unwrap(),
+        vec![Record {
+            path,
+            flags: None,
+            cloexec: true,
+            opened: true,
+        }]
+    );
+}
+
+#[tokio::test]
+async fn redirects_should_be_audited_and_vetoable() {
+    let tempdir = mktmp!();
+    let auditor = Arc::new(NoClobberAuditor::default());
+
+    let fd_env = FileDescManagerEnv::new(
+        AuditFileDescOpenerEnv::new(
+            ArcFileDescOpenerEnv::new(FileDescOpenerEnv::new()),
+            auditor.clone(),
+        ),
+        FileDescEnv::<Arc<FileDesc>>::new(),
+        ArcUnwrappingAsyncIoEnv::new(TokioAsyncIoEnv::new()),
+    );
+
+    let cfg = DefaultEnvConfigArc::new()
+        .unwrap()
+        .change_file_desc_manager_env(fd_env);
+    let mut session = Session::new(Env::with_config(cfg));
+    session
+        .env_mut()
+        .change_working_dir(tempdir.path().into())
+        .unwrap();
+
+    let batch = session
+        .parse::<ArcBuilder>("echo foo > out; a=$?; echo bar >> out; b=$?; echo baz > out; c=$?\n")
+        .unwrap();
+    session.execute(batch).await.unwrap();
+
+    let var = |name: &str| session.env().var(&Arc::new(name.to_owned())).cloned();
+    assert_eq!(var("a"), Some(Arc::new("0".to_owned())));
+    assert_eq!(var("b"), Some(Arc::new("0".to_owned())));
+    assert_ne!(var("c"), Some(Arc::new("0".to_owned())));
+    assert_eq!(
+        fs::read_to_string(tempdir.path().join("out")).unwrap(),
+        "foo\nbar\n"
+    );
+
+    let path = tempdir.path().join("out");
+    let append = OpenFlags {
+        append: true,
+        ..OpenFlags::default()
+    };
+    let records = auditor.records.lock().unwrap();
+    assert_eq!(
+        *records,
+        vec![
+            Record {
+                path: path.clone(),
+                flags: Some(write_flags()),
+                cloexec: true,
+                opened: true,
+            },
+            Record {
+                path: path.clone(),
+                flags: Some(append),
+                cloexec: true,
+                opened: true,
+            },
+            Record {
+                path,
+                flags: Some(write_flags()),
+                cloexec: true,
+                opened: false,
+            },
+        ]
+    );
+}
@@ -1,5 +1,8 @@
 #![deny(rust_2018_idioms)]
 
+use conch_runtime::io::Permissions;
+use futures_util::future::FutureExt;
+use futures_util::stream::StreamExt;
 use std::collections::VecDeque;
 use std::sync::Arc;
 
@@ -172,3 +175,108 @@ async fn should_propagate_fatal_errors_in_body() {
     let for_cmd = for_with_args(name, vars_raw, &fatal, env);
     assert_eq!(Some(MockErr::Fatal(true)), for_cmd.await.err());
 }
+
+#[tokio::test]
+async fn should_trace_each_iteration_if_xtrace_enabled() {
+    let mut env = new_env();
+    env.options_mut().xtrace = true;
+
+    let pipe = env.open_pipe().expect("failed to open pipe");
+    env.set_file_desc(
+        conch_runtime::STDERR_FILENO,
+        pipe.writer,
+        Permissions::Write,
+    );
+    let stderr = env.read_all(pipe.reader);
+
+    let args = vec![Arc::new("foo".to_owned()), Arc::new("bar".to_owned())];
+    let future = for_with_args(
+        Arc::new("x".to_owned()),
+        args,
+        mock_status(EXIT_SUCCESS),
+        &mut env,
+    );
+
+    assert_eq!(EXIT_SUCCESS, future.await.unwrap().await);
+    drop(env);
+
+    assert_eq!(
+        stderr.await.unwrap(),
+        &b"+ for x in foo bar\n+ for x in foo bar\n"[..]
+    );
+}
+
+#[tokio::test]
+async fn should_run_lazily_over_streams_and_words() {
+    let mut env = new_env();
+
+    let result_var = Arc::new(RESULT_VAR.to_owned());
+    let name = Arc::new(VAR.to_owned());
+
+    let args =
+        futures_util::stream::iter(vec![Arc::new("foo".to_owned()), Arc::new("bar".to_owned())]);
+    let for_cmd = for_stream(name.clone(), args, MockCmd2, &mut env);
+    assert_eq!(MOCK_EXIT, for_cmd.await.unwrap().await);
+    assert_eq!("foobar", &**env.var(&result_var).unwrap());
+
+    env.unset_var(&result_var);
+    let words = vec![
+        mock_word_fields(Fields::Split(vec!["foo".to_owned(), "bar".to_owned()])),
+        mock_word_fields(Fields::Zero),
+        mock_word_fields(Fields::Single("baz".to_owned())),
+    ];
+    let for_cmd = for_loop_lazy(name.clone(), words, MockCmd2, &mut env);
+    assert_eq!(MOCK_EXIT, for_cmd.await.unwrap().await);
+    assert_eq!("foobarbaz", &**env.var(&result_var).unwrap());
+
+    let should_not_run = mock_panic("must not run");
+    let for_cmd = for_stream(
+        name.clone(),
+        futures_util::stream::empty(),
+        &should_not_run,
+        &mut env,
+    );
+    assert_eq!(EXIT_SUCCESS, for_cmd.await.unwrap().await);
+
+    let for_cmd = for_loop_lazy(name, Vec::<MockWord>::new(), &should_not_run, &mut env);
+    assert_eq!(EXIT_SUCCESS, for_cmd.await.unwrap().await);
+}
+
+#[tokio::test]
+async fn should_stop_lazy_loops_on_errors() {
+    let env = &mut new_env();
+    let name = Arc::new("name".to_owned());
+
+    let words = vec![
+        mock_word_fields(Fields::Single("foo".to_owned())),
+        mock_word_error(true),
+        mock_word_panic("must not evaluate"),
+    ];
+    let for_cmd = for_loop_lazy(name.clone(), words, mock_status(EXIT_SUCCESS), env);
+    assert_eq!(Some(MockErr::Fatal(true)), for_cmd.await.err());
+
+    let args = futures_util::stream::iter(vec![Arc::new("foo".to_owned())]).chain(
+        futures_util::stream::once(async { panic!("must not poll") }),
+    );
+    let for_cmd = for_stream(name, args, mock_error(true), env);
+    assert_eq!(Some(MockErr::Fatal(true)), for_cmd.await.err());
+}
+
+#[tokio::test]
+async fn should_cancel_streaming_loop_when_dropped() {
+    let mut env = new_env();
+
+    let result_var = Arc::new(RESULT_VAR.to_owned());
+    let name = Arc::new(VAR.to_owned());
+
+    // The stream never finishes after yielding its first item
+    let args = futures_util::stream::iter(vec![Arc::new("foo".to_owned())])
+        .chain(futures_util::stream::pending());
+
+    let for_cmd = for_stream(name, args, MockCmd2, &mut env);
+    assert!(for_cmd.now_or_never().is_none());
+
+    // The first iteration ran, and the environment is usable again after cancellation
+    assert_eq!("foo", &**env.var(&result_var).unwrap());
+    assert_eq!(MOCK_EXIT, env.last_status());
+}
@@ -194,3 +194,23 @@ async fn test_env_run_function_nested_calls_do_not_destroy_upper_args() {
     assert_eq!(depth.load(Ordering::SeqCst), 0);
     assert_eq!(env.is_fn_running(), false);
 }
+
+#[tokio::test]
+async fn should_restore_args_and_frame_if_cancelled() {
+    let mut env = new_test_env();
+
+    let fn_name = "fn_name".to_owned();
+    env.set_function(fn_name.clone(), mock_wrapper(mock_pending()));
+
+    let args = VecDeque::from(vec!["foo".to_owned(), "bar".to_owned()]);
+    env.set_args(Arc::new(args.clone()));
+
+    {
+        let future = function(&fn_name, VecDeque::from(vec!["qux".to_owned()]), &mut env);
+        futures_util::pin_mut!(future);
+        assert!(futures_util::poll!(future).is_pending());
+    }
+
+    assert_eq!(env.args(), Vec::from(args));
+    assert_eq!(env.is_fn_running(), false);
+}
@@ -0,0 +1,96 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::env::{AsyncIoEnvironment, TokioAsyncIoEnv};
+use conch_runtime::io::{LineReader, Pipe};
+use std::io::Write;
+use std::time::Duration;
+
+mod support;
+pub use self::support::*;
+
+#[tokio::test]
+async fn reads_lines_until_eof() {
+    let mut reader = LineReader::new(&b"foo\nbar\n\nbaz"[..]);
+
+    assert_eq!(reader.read_line().await.unwrap(), Some(b"foo".to_vec()));
+    assert_eq!(reader.read_line().await.unwrap(), Some(b"bar".to_vec()));
+    assert_eq!(reader.read_line().await.unwrap(), Some(Vec::new()));
+    assert!(!reader.is_eof());
+    assert_eq!(reader.read_line().await.unwrap(), Some(b"baz".to_vec()));
+    assert!(reader.is_eof());
+    assert_eq!(reader.read_line().await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn buffered_reads_yield_same_lines() {
+    for &capacity in &[1, 2, 3, 512, 100_000] {
+        let mut reader = LineReader::with_capacity(&b"foo\nbar\nbaz"[..], capacity);
+
+        assert_eq!(reader.read_line().await.unwrap(), Some(b"foo".to_vec()));
+        assert_eq!(reader.read_line().await.unwrap(), Some(b"bar".to_vec()));
+        assert_eq!(reader.read_line().await.unwrap(), Some(b"baz".to_vec()));
+        assert_eq!(reader.read_line().await.unwrap(), None);
+    }
+}
+
+#[tokio::test]
+async fn custom_delimiter() {
+    let mut reader = LineReader::new(&b"foo\nbar:baz:"[..]);
+    reader.set_delimiter(b':');
+    assert_eq!(reader.delimiter(), b':');
+
+    assert_eq!(
+        reader.read_line().await.unwrap(),
+        Some(b"foo\nbar".to_vec())
+    );
+    assert_eq!(reader.read_line().await.unwrap(), Some(b"baz".to_vec()));
+    assert_eq!(reader.read_line().await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn max_chars() {
+    let mut reader = LineReader::new("h\u{e9}llo\nworld".as_bytes());
+
+    let line = reader.read_line_max_chars(3).await.unwrap().unwrap();
+    assert_eq!(line, "h\u{e9}l".as_bytes());
+    assert_eq!(
+        reader.read_line_max_chars(5).await.unwrap(),
+        Some(b"lo".to_vec())
+    );
+    assert_eq!(
+        reader.read_line_max_chars(0).await.unwrap(),
+        Some(Vec::new())
+    );
+    assert_eq!(reader.read_line().await.unwrap(), Some(b"world".to_vec()));
+}
+
+#[tokio::test]
+async fn does_not_consume_past_end_of_line() {
+    let input = &b"foo\nbar\n"[..];
+    let mut reader = LineReader::new(input);
+
+    assert_eq!(reader.read_line().await.unwrap(), Some(b"foo".to_vec()));
+    assert_eq!(reader.into_inner(), b"bar\n");
+}
+
+#[tokio::test]
+async fn partial_lines_are_retained_across_cancellation() {
+    let pipe = Pipe::new().expect("failed to create pipe");
+    let mut env = TokioAsyncIoEnv::new();
+    let reader = env.read_async(pipe.reader).expect("read_async failed");
+    let mut reader = LineReader::new(reader);
+    let timeout = Duration::from_millis(100);
+
+    (&pipe.writer).write_all(b"hel").expect("write failed");
+    let result = tokio::time::timeout(timeout, reader.read_line()).await;
+    assert!(result.is_err());
+    assert_eq!(reader.partial_line(), b"hel");
+
+    (&pipe.writer).write_all(b"lo\nwor").expect("write failed");
+    drop(pipe.writer);
+
+    assert_eq!(reader.read_line().await.unwrap(), Some(b"hello".to_vec()));
+    assert_eq!(reader.read_line().await.unwrap(), Some(b"wor".to_vec()));
+    assert!(reader.is_eof());
+    assert!(reader.partial_line().is_empty());
+}
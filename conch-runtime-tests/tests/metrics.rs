@@ -0,0 +1,121 @@
+#![deny(rust_2018_idioms)]
+#![cfg(feature = "metrics")]
+
+use conch_runtime::env::metrics::{self, install_metrics, MetricsEnvironment, MetricsSink};
+use std::collections::HashMap;
+use std::fs;
+use std::sync::{Arc, Mutex};
+
+#[macro_use]
+mod support;
+pub use self::support::*;
+
+/// Records counters by their name and `command` label (if any), and
+/// the number of observations made for each histogram.
+#[derive(Debug, Default)]
+struct Recorder {
+    counters: Mutex<HashMap<(&'static str, String), u64>>,
+    histograms: Mutex<HashMap<(&'static str, String), usize>>,
+}
+
+fn key(name: &'static str, labels: &[(&'static str, &str)]) -> (&'static str, String) {
+    let command = labels
+        .iter()
+        .find(|(label, _)| *label == "command")
+        .map(|(_, value)| (*value).to_owned())
+        .unwrap_or_default();
+
+    (name, command)
+}
+
+impl Recorder {
+    fn counter(&self, name: &'static str, command: &str) -> u64 {
+        let key = (name, command.to_owned());
+        self.counters
+            .lock()
+            .unwrap()
+            .get(&key)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    fn histogram(&self, name: &'static str, command: &str) -> usize {
+        let key = (name, command.to_owned());
+        self.histograms
+            .lock()
+            .unwrap()
+            .get(&key)
+            .copied()
+            .unwrap_or(0)
+    }
+}
+
+impl MetricsSink for Recorder {
+    fn increment_counter(&self, name: &'static str, labels: &[(&'static str, &str)], value: u64) {
+        *self
+            .counters
+            .lock()
+            .unwrap()
+            .entry(key(name, labels))
+            .or_insert(0) += value;
+    }
+
+    fn record_histogram(&self, name: &'static str, labels: &[(&'static str, &str)], value: f64) {
+        assert!(value >= 0.0);
+        *self
+            .histograms
+            .lock()
+            .unwrap()
+            .entry(key(name, labels))
+            .or_insert(0) += 1;
+    }
+}
+
+#[tokio::test]
+async fn should_record_command_metrics() {
+    let tempdir = mktmp!();
+    let out = tempdir.path().join("out");
+
+    let recorder = Arc::new(Recorder::default());
+    let mut env = new_env_with_no_fds();
+    install_metrics(recorder.clone(), &mut env);
+    assert!(env.metrics_sink().is_some());
+
+    let script = format!(
+        "echo hello >{0}; false; false; echo bye >>{0}\n",
+        out.display()
+    );
+
+    let mut session = Session::new(env);
+    run(&mut session, &script).await.unwrap();
+
+    assert_eq!(fs::read_to_string(&out).unwrap(), "hello\nbye\n");
+
+    assert_eq!(recorder.counter(metrics::COMMANDS_EXECUTED, "echo"), 2);
+    assert_eq!(recorder.counter(metrics::COMMANDS_EXECUTED, "false"), 2);
+    assert_eq!(recorder.counter(metrics::COMMAND_FAILURES, "echo"), 0);
+    assert_eq!(recorder.counter(metrics::COMMAND_FAILURES, "false"), 2);
+    assert_eq!(
+        recorder.histogram(metrics::SPAWN_LATENCY_SECONDS, "echo"),
+        2
+    );
+    assert_eq!(
+        recorder.histogram(metrics::SPAWN_LATENCY_SECONDS, "false"),
+        2
+    );
+    assert_eq!(recorder.counter(metrics::SHELL_OUTPUT_BYTES, ""), 10);
+}
+
+#[tokio::test]
+async fn sub_envs_should_inherit_sink() {
+    let recorder = Arc::new(Recorder::default());
+    let mut env = new_env_with_no_fds();
+    env.set_metrics_sink(Some(recorder.clone()));
+
+    let mut sub_env = env.sub_env();
+    assert!(sub_env.metrics_sink().is_some());
+
+    sub_env.set_metrics_sink(None);
+    assert!(sub_env.metrics_sink().is_none());
+    assert!(env.metrics_sink().is_some());
+}
@@ -29,12 +29,24 @@ impl MockFileAndVarEnv {
 impl FileDescOpener for MockFileAndVarEnv {
     type OpenedFileHandle = Arc<FileDesc>;
 
-    fn open_path(&mut self, path: &Path, opts: &OpenOptions) -> io::Result<Self::OpenedFileHandle> {
-        opts.open(&path).map(FileDesc::from).map(Arc::new)
+    fn open_path_with_cloexec(
+        &mut self,
+        path: &Path,
+        opts: &OpenOptions,
+        cloexec: bool,
+    ) -> io::Result<Self::OpenedFileHandle> {
+        let fd = opts.open(&path).map(FileDesc::from)?;
+        fd.set_cloexec(cloexec)?;
+        Ok(Arc::new(fd))
     }
 
-    fn open_pipe(&mut self) -> io::Result<Pipe<Self::OpenedFileHandle>> {
+    fn open_pipe_with_cloexec(
+        &mut self,
+        cloexec: bool,
+    ) -> io::Result<Pipe<Self::OpenedFileHandle>> {
         let pipe = ::conch_runtime::io::Pipe::new()?;
+        pipe.reader.set_cloexec(cloexec)?;
+        pipe.writer.set_cloexec(cloexec)?;
         Ok(Pipe {
             reader: Arc::new(pipe.reader),
             writer: Arc::new(pipe.writer),
@@ -49,6 +61,10 @@ impl AsyncIoEnvironment for MockFileAndVarEnv {
         unimplemented!()
     }
 
+    fn read_async(&mut self, _: Self::IoHandle) -> io::Result<BoxAsyncRead> {
+        unimplemented!()
+    }
+
     /// Asynchronously write `data` into the specified handle.
     fn write_all<'a>(
         &mut self,
@@ -0,0 +1,122 @@
+#![deny(rust_2018_idioms)]
+
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Barrier;
+
+mod support;
+pub use self::support::*;
+
+#[tokio::test]
+async fn should_collect_statuses_in_order() {
+    let exit = ExitStatus::Code(42);
+    let cmds = vec![
+        mock_status(EXIT_SUCCESS),
+        mock_status(exit),
+        mock_error(false),
+        mock_error(true),
+    ];
+
+    let status = parallel(ParallelAggregate::default(), cmds, &new_env()).await;
+    assert_eq!(
+        status.statuses(),
+        &[EXIT_SUCCESS, exit, EXIT_ERROR, EXIT_ERROR]
+    );
+    assert_eq!(status.aggregate(), ParallelAggregate::FirstFailure);
+    assert_eq!(status.status(), exit);
+}
+
+#[tokio::test]
+async fn should_aggregate_statuses() {
+    let exit = ExitStatus::Code(42);
+
+    let run = |aggregate, cmds: Vec<MockCmd>| async move {
+        parallel(aggregate, cmds, &new_env()).await.status()
+    };
+
+    let all_ok = || vec![mock_status(EXIT_SUCCESS), mock_status(EXIT_SUCCESS)];
+    let some_ok = || vec![mock_status(exit), mock_status(EXIT_SUCCESS)];
+    let none_ok = || vec![mock_status(exit), mock_status(EXIT_ERROR)];
+
+    assert_eq!(
+        run(ParallelAggregate::FirstFailure, all_ok()).await,
+        EXIT_SUCCESS
+    );
+    assert_eq!(run(ParallelAggregate::FirstFailure, some_ok()).await, exit);
+    assert_eq!(
+        run(ParallelAggregate::FirstFailure, vec![]).await,
+        EXIT_SUCCESS
+    );
+
+    assert_eq!(run(ParallelAggregate::Last, some_ok()).await, EXIT_SUCCESS);
+    assert_eq!(run(ParallelAggregate::Last, none_ok()).await, EXIT_ERROR);
+    assert_eq!(run(ParallelAggregate::Last, vec![]).await, EXIT_SUCCESS);
+
+    assert_eq!(
+        run(ParallelAggregate::AnySuccess, some_ok()).await,
+        EXIT_SUCCESS
+    );
+    assert_eq!(
+        run(ParallelAggregate::AnySuccess, none_ok()).await,
+        EXIT_ERROR
+    );
+    assert_eq!(
+        run(ParallelAggregate::AnySuccess, vec![]).await,
+        EXIT_SUCCESS
+    );
+}
+
+#[tokio::test]
+async fn should_run_commands_concurrently() {
+    #[derive(Clone)]
+    struct MockBarrier(Arc<Barrier>);
+
+    #[async_trait::async_trait]
+    impl<E: ?Sized + Send> Spawn<E> for MockBarrier {
+        type Error = MockErr;
+
+        async fn spawn(&self, _: &mut E) -> Result<BoxFuture<'static, ExitStatus>, Self::Error> {
+            let barrier = self.0.clone();
+            Ok(Box::pin(async move {
+                barrier.wait().await;
+                EXIT_SUCCESS
+            }))
+        }
+    }
+
+    // Each command can only complete once all others are running
+    let barrier = MockBarrier(Arc::new(Barrier::new(3)));
+    let cmds = vec![barrier.clone(), barrier.clone(), barrier];
+
+    let future = parallel(ParallelAggregate::default(), cmds, &new_env());
+    let status = tokio::time::timeout(Duration::from_secs(10), future)
+        .await
+        .expect("commands did not run concurrently");
+
+    assert_eq!(status.statuses(), &[EXIT_SUCCESS; 3]);
+}
+
+#[tokio::test]
+async fn should_isolate_side_effects_in_sub_environments() {
+    #[derive(Clone)]
+    struct MockSetVar;
+
+    #[async_trait::async_trait]
+    impl Spawn<DefaultEnvArc> for MockSetVar {
+        type Error = MockErr;
+
+        async fn spawn(
+            &self,
+            env: &mut DefaultEnvArc,
+        ) -> Result<BoxFuture<'static, ExitStatus>, Self::Error> {
+            env.set_var(Arc::new("key".to_owned()), Arc::new("val".to_owned()));
+            Ok(Box::pin(async { EXIT_SUCCESS }))
+        }
+    }
+
+    let env = new_env();
+    let status = parallel(ParallelAggregate::default(), vec![MockSetVar], &env).await;
+
+    assert_eq!(status.status(), EXIT_SUCCESS);
+    assert_eq!(env.var(&Arc::new("key".to_owned())), None);
+}
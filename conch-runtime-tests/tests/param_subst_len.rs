@@ -19,6 +19,14 @@ async fn zero() {
     assert_len(0, MockParam::Fields(Some(Fields::Zero)));
 }
 
+#[tokio::test]
+async fn single_counts_chars() {
+    assert_len(
+        4,
+        MockParam::Fields(Some(Fields::Single("a\u{e9}\u{1F4A9}b".to_owned()))),
+    );
+}
+
 #[tokio::test]
 async fn at() {
     let fields = vec!["foo".into(), "bar".into()];
@@ -51,6 +51,16 @@ async fn should_evaluate_appropriately() {
     eval(&param, None).await.unwrap();
 }
 
+#[tokio::test]
+async fn should_treat_consecutive_stars_as_one() {
+    let param = MockParam::Fields(Some(Fields::Single("abab".to_owned())));
+    let word = mock_word_fields(Fields::Single("b**".to_owned()));
+    assert_eq!(
+        eval(&param, word).await,
+        Ok(Fields::Single("aba".to_owned()))
+    );
+}
+
 #[tokio::test]
 async fn should_propagate_errors_from_word_if_applicable() {
     let must_not_run = mock_word_panic("should not run");
@@ -2,10 +2,11 @@
 
 use conch_parser::ast::Parameter::*;
 use conch_runtime::env::{
-    ArgsEnv, ArgumentsEnvironment, Env, EnvConfig, LastStatusEnvironment, VariableEnvironment,
+    ArgsEnv, ArgumentsEnvironment, Env, EnvConfig, JobEnvironment, LastStatusEnvironment,
+    ShellOptionsEnvironment, VariableEnvironment,
 };
 use conch_runtime::eval::{Fields, ParamEval};
-use conch_runtime::ExitStatus;
+use conch_runtime::{ExitStatus, EXIT_SUCCESS};
 
 #[tokio::test]
 async fn test_eval_parameter_with_set_vars() {
@@ -38,9 +39,19 @@ async fn test_eval_parameter_with_set_vars() {
         Some(Fields::Single(getpid().to_string()))
     );
 
-    // FIXME: test these
-    //assert_eq!(Dash.eval(false, &env), ...);
-    //assert_eq!(Bang.eval(false, &env), ...);
+    assert_eq!(Dash.eval(false, &env), Some(Fields::Single(String::new())));
+    env.options_mut().errexit = true;
+    env.options_mut().xtrace = true;
+    env.set_interactive(true);
+    assert_eq!(
+        Dash.eval(false, &env),
+        Some(Fields::Single("exi".to_owned()))
+    );
+
+    // Nothing is reported until a job has been spawned
+    assert_eq!(Bang.eval(false, &env), None);
+    let id = env.spawn_job(Box::pin(async { EXIT_SUCCESS }));
+    assert_eq!(Bang.eval(false, &env), Some(Fields::Single(id.to_string())));
 
     // Before anything is run it should be considered a success
     assert_eq!(
@@ -93,9 +104,8 @@ async fn test_eval_parameter_with_unset_vars() {
     assert_eq!(At.eval(false, &env), Some(Fields::Zero));
     assert_eq!(Star.eval(false, &env), Some(Fields::Zero));
 
-    // FIXME: test these
-    //assert_eq!(Dash.eval(false, &env), ...);
-    //assert_eq!(Bang.eval(false, &env), ...);
+    assert_eq!(Dash.eval(false, &env), Some(Fields::Single(String::new())));
+    assert_eq!(Bang.eval(false, &env), None);
 
     assert_eq!(
         Pound.eval(false, &env),
@@ -243,9 +253,15 @@ async fn test_eval_parameter_splitting_with_custom_ifs() {
     );
     assert_eq!(Var("var3".to_owned()).eval(true, &env), Some(Fields::Zero));
 
-    // FIXME: test these
-    //assert_eq!(Dash.eval(false, &env), ...);
-    //assert_eq!(Bang.eval(false, &env), ...);
+    env.options_mut().nounset = true;
+    assert_eq!(Dash.eval(true, &env), Some(Fields::Single("u".to_owned())));
+
+    // NB: the id of the tenth job contains an IFS character
+    for _ in 0..10 {
+        env.spawn_job(Box::pin(async { EXIT_SUCCESS }));
+    }
+    assert_eq!(env.last_job(), Some(10));
+    assert_eq!(Bang.eval(true, &env), Some(Fields::Single("1".to_owned())));
 
     assert_eq!(
         Question.eval(true, &env),
@@ -282,9 +298,12 @@ async fn test_eval_parameter_splitting_with_custom_ifs() {
         Some(Fields::Single(val3))
     );
 
-    // FIXME: test these
-    //assert_eq!(Dash.eval(false, &env), ...);
-    //assert_eq!(Bang.eval(false, &env), ...);
+    assert_eq!(Dash.eval(false, &env), Some(Fields::Single("u".to_owned())));
+
+    assert_eq!(
+        Bang.eval(false, &env),
+        Some(Fields::Single("10".to_owned()))
+    );
 
     assert_eq!(
         Question.eval(false, &env),
@@ -0,0 +1,57 @@
+#![deny(rust_2018_idioms)]
+
+use std::borrow::Cow;
+
+mod support;
+pub use self::support::*;
+
+#[test]
+fn should_not_quote_safe_words() {
+    for word in &["foo", "foo-bar_1.2", "/usr/bin/env", "a=b", "x,y:z@host%+"] {
+        assert_eq!(quote_for_shell(word), Cow::Borrowed(*word));
+    }
+}
+
+#[test]
+fn should_single_quote_special_words() {
+    assert_eq!(quote_for_shell(""), "''");
+    assert_eq!(quote_for_shell("foo bar"), "'foo bar'");
+    assert_eq!(quote_for_shell("$HOME"), "'$HOME'");
+    assert_eq!(quote_for_shell("~"), "'~'");
+    assert_eq!(quote_for_shell("a\nb"), "'a\nb'");
+    assert_eq!(quote_for_shell("it's"), "'it'\\''s'");
+    assert_eq!(quote_for_shell("héllo"), "'héllo'");
+}
+
+#[test]
+fn should_quote_arbitrary_bytes() {
+    assert_eq!(quote_bytes_for_shell(b"foo"), &b"foo"[..]);
+    assert_eq!(quote_bytes_for_shell(b"\xff'"), &b"'\xff'\\'''"[..]);
+}
+
+#[tokio::test]
+async fn quoted_words_should_round_trip_through_the_shell() {
+    let values = [
+        "",
+        "foo bar",
+        "it's",
+        "'",
+        "\\",
+        "$(echo oops) `echo oops` $((1 + 1)) ${x:-y}",
+        "*?[a]~ #!&|;<>(){}\"",
+        "multi\nline\ttabs",
+    ];
+
+    let mut session = Session::new(new_env());
+
+    for value in &values {
+        let script = format!("x={}\n", quote_for_shell(value));
+        run(&mut session, &script).await.unwrap();
+        assert_eq!(
+            var(&session, "x"),
+            Some(value.to_string()),
+            "script: {}",
+            script
+        );
+    }
+}
@@ -0,0 +1,85 @@
+#![deny(rust_2018_idioms)]
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+mod support;
+pub use self::support::*;
+
+/// A "generator" which counts upwards from zero.
+#[derive(Debug, Default)]
+struct MockRng(AtomicU32);
+
+impl RandomEnvironment for MockRng {
+    fn next_u32(&self) -> u32 {
+        self.0.fetch_add(1, Ordering::SeqCst)
+    }
+}
+
+fn take(rng: &dyn RandomEnvironment, n: usize) -> Vec<u32> {
+    (0..n).map(|_| rng.next_u32()).collect()
+}
+
+#[test]
+fn fast_rng_should_be_deterministic_for_a_seed() {
+    let first = take(&FastRng::with_seed(42), 10);
+
+    assert_eq!(first, take(&FastRng::with_seed(42), 10));
+    assert_ne!(first, take(&FastRng::with_seed(43), 10));
+    assert_ne!(first[0], first[1]);
+}
+
+#[test]
+fn fast_rng_should_be_seeded_differently_by_os() {
+    assert_ne!(
+        take(&FastRng::from_os_seed(), 10),
+        take(&FastRng::from_os_seed(), 10)
+    );
+}
+
+#[test]
+fn next_below_should_stay_within_bound() {
+    let rng = FastRng::with_seed(7);
+
+    for _ in 0..1_000 {
+        assert!(rng.next_below(32_768) < 32_768);
+        assert!(rng.next_below(1) == 0);
+    }
+    assert_eq!(rng.next_below(0), 0);
+
+    assert_eq!(MockRng::default().next_below(u32::max_value()), 0);
+}
+
+#[test]
+fn rng_handles_should_compare_by_identity() {
+    let rng = Rng::with_seed(1);
+
+    assert_eq!(rng, rng.clone());
+    assert_ne!(rng, Rng::with_seed(1));
+}
+
+#[tokio::test]
+async fn env_and_sub_envs_should_share_configured_rng() {
+    let env = Env::with_config(EnvConfig {
+        rng: Rng::new(MockRng::default()),
+        ..DefaultEnvConfigArc::new().unwrap()
+    });
+
+    assert_eq!(env.next_u32(), 0);
+    assert_eq!(env.next_u32(), 1);
+
+    let sub = env.sub_env();
+    assert_eq!(sub.next_u32(), 2);
+    assert_eq!(env.next_u32(), 3);
+}
+
+#[tokio::test]
+async fn seeded_envs_should_produce_the_same_sequence() {
+    let new_env = || {
+        Env::with_config(EnvConfig {
+            rng: Rng::with_seed(1234),
+            ..DefaultEnvConfigArc::new().unwrap()
+        })
+    };
+
+    assert_eq!(take(&new_env(), 10), take(&new_env(), 10));
+}
@@ -285,6 +285,13 @@ async fn eval_heredoc() {
     }
 }
 
+#[tokio::test]
+async fn redirect_action_fd() {
+    assert_eq!(RedirectAction::<()>::Close(3).fd(), 3);
+    assert_eq!(RedirectAction::Open(4, (), Permissions::Read).fd(), 4);
+    assert_eq!(RedirectAction::<()>::HereDoc(5, vec![]).fd(), 5);
+}
+
 #[tokio::test]
 async fn apply_redirect_action() {
     let mut env = new_env_with_no_fds();
@@ -324,6 +331,30 @@ async fn apply_redirect_action() {
     assert_eq!(read, msg.as_bytes());
 }
 
+#[tokio::test]
+async fn heredoc_bodies_are_fed_to_command_stdin() {
+    let big = "a".repeat(256 * 1024);
+    let script = format!(
+        concat!(
+            "y=world\n",
+            "read expanded <<EOF\nhello $y\nEOF\n",
+            "read literal <<'EOF'\nhello $y\nEOF\n",
+            "count=$(wc -c <<EOF\n{}\nEOF\n)\n",
+        ),
+        big
+    );
+
+    let mut session = Session::new(new_env_with_no_fds());
+    assert_eq!(run(&mut session, &script).await, Ok(EXIT_SUCCESS));
+
+    assert_eq!(var(&session, "expanded"), Some("hello world".to_owned()));
+    assert_eq!(var(&session, "literal"), Some("hello $y".to_owned()));
+    // NB: the body is larger than a pipe's buffer, so it must be written
+    // asynchronously while the command is consuming it.
+    let count = var(&session, "count").map(|count| count.trim().to_owned());
+    assert_eq!(count, Some((big.len() + 1).to_string()));
+}
+
 #[tokio::test]
 async fn should_split_word_fields_if_interactive_and_expand_first_tilde() {
     for &interactive in &[true, false] {
@@ -0,0 +1,61 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::io::{FileDescWrapper, Pipe};
+use conch_runtime::RefCounted;
+use std::io::{self, Read, Write};
+use std::rc::Rc;
+use std::sync::Arc;
+
+mod support;
+pub use self::support::*;
+
+fn should_not_duplicate(_: &String) -> io::Result<String> {
+    panic!("must not duplicate uniquely referenced values")
+}
+
+#[tokio::test]
+async fn unwrap_or_duplicate_only_copies_shared_values() {
+    let rc = Rc::new("foo".to_owned());
+    assert_eq!(Rc::try_unwrap(rc.clone()).err(), Some(rc.clone()));
+    assert_eq!(
+        RefCounted::unwrap_or_duplicate(rc, should_not_duplicate).unwrap(),
+        "foo"
+    );
+
+    let arc = Arc::new("foo".to_owned());
+    let shared = arc.clone();
+    let copy = RefCounted::unwrap_or_duplicate(arc, |s: &String| Ok(format!("{}-copy", s)));
+    assert_eq!(copy.unwrap(), "foo-copy");
+    assert_eq!(
+        RefCounted::unwrap_or_duplicate(shared, should_not_duplicate).unwrap(),
+        "foo"
+    );
+
+    let arc = Arc::new(5);
+    let _shared = arc.clone();
+    let err = RefCounted::unwrap_or_duplicate(arc, |_| {
+        Err(io::Error::new(io::ErrorKind::Other, "dup failed"))
+    });
+    assert_eq!(err.unwrap_err().kind(), io::ErrorKind::Other);
+}
+
+#[tokio::test]
+async fn shared_file_desc_wrappers_are_duplicated() {
+    let Pipe { reader, writer } = Pipe::new().unwrap();
+
+    let writer = Arc::new(writer);
+    let shared = writer.clone();
+
+    let mut dup = writer.try_unwrap().unwrap();
+    dup.write_all(b"foo").unwrap();
+    drop(dup);
+
+    let mut unique = shared.try_unwrap().unwrap();
+    unique.write_all(b"bar").unwrap();
+    drop(unique);
+
+    let mut buf = String::new();
+    let mut reader = reader;
+    reader.read_to_string(&mut buf).unwrap();
+    assert_eq!(buf, "foobar");
+}
@@ -0,0 +1,193 @@
+#![deny(rust_2018_idioms)]
+
+use conch_parser::ast::builder::ArcBuilder;
+use conch_runtime::env::remote::*;
+use conch_runtime::io::{FileDesc, FileDescWrapper};
+use conch_runtime::{EXIT_CMD_NOT_EXECUTABLE, EXIT_CMD_NOT_FOUND};
+use std::env::current_dir;
+use std::ffi::{OsStr, OsString};
+use std::sync::{Arc, Mutex};
+
+mod support;
+pub use self::support::*;
+
+/// An agent which records all requests instead of executing them.
+#[derive(Debug, Default)]
+struct RecordingAgent {
+    requests: Mutex<Vec<RemoteExecRequest>>,
+}
+
+impl RemoteAgent for RecordingAgent {
+    fn execute(
+        &self,
+        request: RemoteExecRequest,
+        _stdio: RemoteStdio,
+    ) -> BoxFuture<'static, RemoteExecResponse> {
+        let response = match request.name.to_str() {
+            Some("missing") => RemoteExecResponse::NotFound,
+            _ => RemoteExecResponse::Exited(ExitStatus::Code(request.args.len() as i32)),
+        };
+
+        self.requests.lock().unwrap().push(request);
+        Box::pin(async move { response })
+    }
+}
+
+/// An agent which "remotely" executes requests on the local machine.
+struct LoopbackAgent;
+
+impl RemoteAgent for LoopbackAgent {
+    fn execute(
+        &self,
+        request: RemoteExecRequest,
+        stdio: RemoteStdio,
+    ) -> BoxFuture<'static, RemoteExecResponse> {
+        fn join(kind: RemoteStdioKind, fdes: Option<FileDesc>) -> ExecutableStdio {
+            match kind {
+                RemoteStdioKind::Inherit => ExecutableStdio::Inherit,
+                RemoteStdioKind::Null => ExecutableStdio::Null,
+                RemoteStdioKind::Piped => fdes.into(),
+            }
+        }
+
+        let args = request.args.iter().map(|a| &**a).collect::<Vec<_>>();
+        let env_vars = request
+            .env_vars
+            .iter()
+            .map(|(k, v)| (&**k, &**v))
+            .collect::<Vec<_>>();
+
+        let data = ExecutableData::builder(&request.name, &request.current_dir)
+            .args(&args)
+            .env_vars(&env_vars)
+            .stdin(join(request.stdin, stdio.stdin))
+            .stdout(join(request.stdout, stdio.stdout))
+            .stderr(join(request.stderr, stdio.stderr))
+            .build();
+
+        match TokioExecEnv::new().spawn_executable(data) {
+            Ok(child) => Box::pin(async move { RemoteExecResponse::Exited(child.await) }),
+            Err(CommandError::NotFound(_)) => Box::pin(async { RemoteExecResponse::NotFound }),
+            Err(e) => {
+                let msg = e.to_string();
+                Box::pin(async move { RemoteExecResponse::Failed(msg) })
+            }
+        }
+    }
+}
+
+#[test]
+fn responses_should_map_to_conventional_statuses() {
+    let exited = RemoteExecResponse::Exited(ExitStatus::Signal(9));
+    assert_eq!(exited.exit_status(), ExitStatus::Signal(9));
+    assert_eq!(
+        RemoteExecResponse::NotFound.exit_status(),
+        EXIT_CMD_NOT_FOUND
+    );
+    assert_eq!(
+        RemoteExecResponse::NotExecutable.exit_status(),
+        EXIT_CMD_NOT_EXECUTABLE
+    );
+    assert_eq!(
+        RemoteExecResponse::Failed("oops".to_owned()).exit_status(),
+        EXIT_ERROR
+    );
+}
+
+#[tokio::test]
+async fn into_request_should_own_data_and_split_off_piped_streams() {
+    let mut io_env = TokioFileDescManagerEnv::new();
+    let pipe = io_env.open_pipe().unwrap();
+    let writer = pipe.writer.try_unwrap().expect("unwrap failed");
+
+    let cur_dir = current_dir().unwrap();
+    let args = [OsStr::new("a"), OsStr::new("b")];
+    let env_vars = [(OsStr::new("k"), OsStr::new("v"))];
+    let data = ExecutableData::builder(OsStr::new("foo"), &cur_dir)
+        .args(&args)
+        .env_vars(&env_vars)
+        .stdin(ExecutableStdio::Inherit)
+        .stdout(writer)
+        .build();
+
+    let (request, stdio) = into_request(data);
+
+    assert_eq!(
+        request,
+        RemoteExecRequest {
+            name: OsString::from("foo"),
+            args: vec![OsString::from("a"), OsString::from("b")],
+            env_vars: vec![(OsString::from("k"), OsString::from("v"))],
+            current_dir: cur_dir,
+            stdin: RemoteStdioKind::Inherit,
+            stdout: RemoteStdioKind::Piped,
+            stderr: RemoteStdioKind::Null,
+        }
+    );
+    assert!(stdio.stdin.is_none());
+    assert!(stdio.stdout.is_some());
+    assert!(stdio.stderr.is_none());
+}
+
+#[tokio::test]
+async fn shell_logic_should_run_locally_and_commands_remotely() {
+    let agent = Arc::new(RecordingAgent::default());
+    let cfg = DefaultEnvConfigArc::new()
+        .unwrap()
+        .change_exec_env(RemoteExecEnv::new(agent.clone()));
+
+    let mut session = Session::new(Env::with_config(EnvConfig {
+        file_desc_manager_env: TokioFileDescManagerEnv::new(),
+        ..cfg
+    }));
+
+    let batch = session
+        .parse::<ArcBuilder>("x=bar\nREMOTE_VAR=$x foo a \"$x\" c; s=$?\nmissing; t=$?\n")
+        .unwrap();
+    session.execute(batch).await.unwrap();
+
+    let requests = agent.requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+
+    let foo = &requests[0];
+    assert_eq!(foo.name, "foo");
+    assert_eq!(foo.args, vec!["a", "bar", "c"]);
+    assert!(foo
+        .env_vars
+        .contains(&(OsString::from("REMOTE_VAR"), OsString::from("bar"))));
+    assert_eq!(foo.current_dir, current_dir().unwrap());
+    assert_eq!(requests[1].name, "missing");
+
+    let var = |name: &str| session.env().var(&Arc::new(name.to_owned())).cloned();
+    assert_eq!(var("s"), Some(Arc::new("3".to_owned())));
+    assert_eq!(var("t"), Some(Arc::new("127".to_owned())));
+}
+
+#[tokio::test]
+async fn loopback_agent_should_pipe_streams_to_remote_process() {
+    let env = RemoteExecEnv::new(LoopbackAgent);
+    let mut io_env = TokioFileDescManagerEnv::new();
+    let pipe = io_env.open_pipe().unwrap();
+
+    let bin_path = bin_path("env");
+    let cur_dir = current_dir().unwrap();
+    let env_vars = [(OsStr::new("foo"), OsStr::new("bar"))];
+    let data = ExecutableData::builder(OsStr::new(&bin_path), &cur_dir)
+        .env_vars(&env_vars)
+        .stdout(pipe.writer.try_unwrap().expect("unwrap failed"))
+        .build();
+
+    let child = env.spawn_executable(data).expect("spawn failed");
+    let out = io_env.read_all(pipe.reader);
+    drop(io_env);
+
+    let (status, out) = futures_util::future::join(child, out).await;
+    let out = String::from_utf8(out.unwrap()).unwrap();
+
+    assert_eq!(status, EXIT_SUCCESS);
+    assert!(out.lines().any(|line| line == "foo=bar"), "{}", out);
+
+    let missing = ExecutableData::builder(OsStr::new("conch-runtime-missing"), &cur_dir).build();
+    let status = env.spawn_executable(missing).unwrap().await;
+    assert_eq!(status, EXIT_CMD_NOT_FOUND);
+}
@@ -1,5 +1,6 @@
 #![deny(rust_2018_idioms)]
 
+use conch_runtime::error::ErrorContext;
 use conch_runtime::io::Permissions;
 use conch_runtime::STDERR_FILENO;
 
@@ -54,3 +55,54 @@ async fn closed_fd() {
     env.close_file_desc(STDERR_FILENO);
     env.report_error(&MockErr).await;
 }
+
+#[tokio::test]
+async fn reports_innermost_context_first() {
+    let mut env = DefaultEnv::<String>::new().expect("failed to create env");
+
+    let pipe = env.open_pipe().expect("failed to open pipe");
+    env.set_file_desc(STDERR_FILENO, pipe.writer, Permissions::Write);
+
+    env.push_error_context(ErrorContext::CommandSubstitution);
+    env.push_error_context(ErrorContext::Function("f".to_owned()));
+
+    // Sub environments inherit the context of their parent
+    let mut sub_env = env.sub_env();
+    env.pop_error_context();
+    env.pop_error_context();
+
+    let reader = env.read_all(pipe.reader);
+    let report = sub_env.report_error(&MockErr);
+    let name = env.name().clone();
+    drop(env);
+    drop(sub_env);
+
+    report.await;
+    let msg = reader.await.expect("read failed");
+    let expected = format!(
+        "{}: in function f, in command substitution: {}\n",
+        name, MockErr
+    );
+    assert_eq!(msg, expected.as_bytes());
+}
+
+#[tokio::test]
+async fn reports_command_text_after_context() {
+    let mut env = DefaultEnv::<String>::new().expect("failed to create env");
+
+    let pipe = env.open_pipe().expect("failed to open pipe");
+    env.set_file_desc(STDERR_FILENO, pipe.writer, Permissions::Write);
+
+    env.push_error_context(ErrorContext::Function("f".to_owned()));
+    assert_eq!(env.set_command_text(Some("echo foo".to_owned())), None);
+
+    let reader = env.read_all(pipe.reader);
+    let report = env.report_error(&MockErr);
+    let name = env.name().clone();
+    drop(env);
+
+    report.await;
+    let msg = reader.await.expect("read failed");
+    let expected = format!("{}: in function f: echo foo: {}\n", name, MockErr);
+    assert_eq!(msg, expected.as_bytes());
+}
@@ -0,0 +1,103 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::spawn::{retry, RetryPolicy};
+use std::time::Duration;
+
+mod support;
+pub use self::support::*;
+
+async fn run_retry(
+    statuses: Vec<MockCmd>,
+    policy: RetryPolicy,
+) -> (Result<ExitStatus, MockErr>, usize, DefaultEnvArc) {
+    let mut env = new_env();
+    env.set_var(name("var"), name("original"));
+
+    let mut attempts = 0;
+    let ret = {
+        let mut restorer = EnvRestorer::new(&mut env);
+        retry(
+            |restorer: &mut EnvRestorer<'_, DefaultEnvArc>| {
+                assert_eq!(restorer.var(&name("var")), Some(&name("original")));
+                restorer.set_var(name("var"), name("local"));
+
+                let cmd = statuses[attempts].clone();
+                attempts += 1;
+                cmd
+            },
+            policy,
+            &mut restorer,
+        )
+        .await
+    };
+
+    (ret, attempts, env)
+}
+
+#[tokio::test]
+async fn should_retry_until_success() {
+    let statuses = vec![
+        mock_status(ExitStatus::Code(3)),
+        mock_status(ExitStatus::Code(4)),
+        mock_status(EXIT_SUCCESS),
+        mock_panic("must not run"),
+    ];
+
+    let (ret, attempts, env) =
+        run_retry(statuses, RetryPolicy::new(5, Duration::from_millis(1))).await;
+    assert_eq!(ret, Ok(EXIT_SUCCESS));
+    assert_eq!(attempts, 3);
+    assert_eq!(env.var(&name("var")), Some(&name("original")));
+}
+
+#[tokio::test]
+async fn should_stop_after_last_attempt() {
+    let statuses = vec![
+        mock_status(ExitStatus::Code(3)),
+        mock_status(ExitStatus::Code(4)),
+        mock_panic("must not run"),
+    ];
+
+    let (ret, attempts, env) =
+        run_retry(statuses, RetryPolicy::new(2, Duration::from_secs(0))).await;
+    assert_eq!(ret, Ok(ExitStatus::Code(4)));
+    assert_eq!(attempts, 2);
+    assert_eq!(env.var(&name("var")), Some(&name("original")));
+
+    let statuses = vec![mock_status(ExitStatus::Code(3)), mock_panic("must not run")];
+    let (ret, attempts, _) = run_retry(statuses, RetryPolicy::new(0, Duration::from_secs(0))).await;
+    assert_eq!(ret, Ok(ExitStatus::Code(3)));
+    assert_eq!(attempts, 1);
+}
+
+#[tokio::test]
+async fn should_propagate_errors_without_retrying() {
+    let statuses = vec![
+        mock_status(ExitStatus::Code(3)),
+        mock_error(false),
+        mock_panic("must not run"),
+    ];
+
+    let (ret, attempts, env) =
+        run_retry(statuses, RetryPolicy::new(5, Duration::from_secs(0))).await;
+    assert_eq!(ret, Err(MockErr::Fatal(false)));
+    assert_eq!(attempts, 2);
+    assert_eq!(env.var(&name("var")), Some(&name("original")));
+}
+
+#[test]
+fn delay_should_back_off_up_to_max_delay() {
+    let mut policy = RetryPolicy::new(10, Duration::from_millis(100));
+    assert_eq!(policy.delay_before(1), Duration::from_millis(100));
+    assert_eq!(policy.delay_before(5), Duration::from_millis(100));
+
+    policy.backoff_factor = 2;
+    assert_eq!(policy.delay_before(1), Duration::from_millis(100));
+    assert_eq!(policy.delay_before(2), Duration::from_millis(200));
+    assert_eq!(policy.delay_before(4), Duration::from_millis(800));
+
+    policy.max_delay = Some(Duration::from_millis(500));
+    assert_eq!(policy.delay_before(3), Duration::from_millis(400));
+    assert_eq!(policy.delay_before(4), Duration::from_millis(500));
+    assert_eq!(policy.delay_before(200), Duration::from_millis(500));
+}
@@ -0,0 +1,141 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::env::{
+    ChangeWorkingDirectoryEnvironment, LastStatusEnv, LastStatusEnvironment, LastStatusRestorer,
+    Restorer, VirtualWorkingDirEnv, WorkingDirRestorer, WorkingDirectoryEnvironment,
+};
+use conch_runtime::{ExitStatus, EXIT_ERROR, EXIT_SUCCESS};
+use futures_util::future::{pending, FutureExt};
+use std::borrow::Cow;
+use std::fs;
+use std::path::Path;
+
+#[macro_use]
+mod support;
+pub use self::support::*;
+
+#[test]
+fn last_status_restored_on_drop() {
+    let mut env = LastStatusEnv::new();
+
+    {
+        let mut restorer = LastStatusRestorer::with_status(&mut env, EXIT_ERROR);
+        assert_eq!(restorer.last_status(), EXIT_ERROR);
+
+        restorer.set_last_status(ExitStatus::Code(42));
+        assert_eq!(restorer.get().last_status(), ExitStatus::Code(42));
+    }
+
+    assert_eq!(env.last_status(), EXIT_SUCCESS);
+}
+
+#[test]
+fn last_status_restore_and_clear_on_demand() {
+    let mut env = LastStatusEnv::new();
+
+    let mut restorer = LastStatusRestorer::new(&mut env);
+    restorer.set_last_status(EXIT_ERROR);
+    restorer.restore_last_status();
+    assert_eq!(restorer.last_status(), EXIT_SUCCESS);
+
+    // The original status should be backed up again on the next change
+    restorer.set_last_status(ExitStatus::Code(42));
+    restorer.set_last_status(EXIT_ERROR);
+    restorer.restore_last_status();
+    assert_eq!(restorer.last_status(), EXIT_SUCCESS);
+
+    restorer.set_last_status(EXIT_ERROR);
+    restorer.clear_last_status();
+    drop(restorer);
+    assert_eq!(env.last_status(), EXIT_ERROR);
+}
+
+#[test]
+fn last_status_restored_if_future_cancelled() {
+    let mut env = LastStatusEnv::new();
+
+    let future = async {
+        let _restorer = LastStatusRestorer::with_status(&mut env, EXIT_ERROR);
+        pending::<()>().await;
+    };
+    assert_eq!(future.now_or_never(), None);
+
+    assert_eq!(env.last_status(), EXIT_SUCCESS);
+}
+
+#[test]
+fn working_dir_restored_on_drop() {
+    let tempdir = mktmp!();
+    let sub = tempdir.path().join("sub");
+    fs::create_dir(&sub).unwrap();
+
+    let mut env = VirtualWorkingDirEnv::new(tempdir.path()).unwrap();
+
+    {
+        let mut restorer =
+            WorkingDirRestorer::with_working_dir(&mut env, Cow::Borrowed(Path::new("sub")))
+                .unwrap();
+        assert_eq!(restorer.current_working_dir(), sub);
+
+        restorer
+            .change_working_dir(Cow::Borrowed(Path::new("..")))
+            .unwrap();
+        restorer
+            .change_working_dir(Cow::Borrowed(Path::new("sub")))
+            .unwrap();
+        assert_eq!(restorer.get().current_working_dir(), sub);
+    }
+
+    assert_eq!(env.current_working_dir(), tempdir.path());
+}
+
+#[test]
+fn working_dir_unchanged_if_override_fails() {
+    let tempdir = mktmp!();
+    let mut env = VirtualWorkingDirEnv::new(tempdir.path()).unwrap();
+
+    let missing = Cow::Borrowed(Path::new("missing"));
+    assert!(WorkingDirRestorer::with_working_dir(&mut env, missing).is_err());
+    assert_eq!(env.current_working_dir(), tempdir.path());
+}
+
+#[test]
+fn working_dir_restore_and_clear_on_demand() {
+    let tempdir = mktmp!();
+    let sub = tempdir.path().join("sub");
+    fs::create_dir(&sub).unwrap();
+
+    let mut env = VirtualWorkingDirEnv::new(tempdir.path()).unwrap();
+
+    let mut restorer = WorkingDirRestorer::new(&mut env);
+    restorer
+        .change_working_dir(Cow::Borrowed(Path::new("sub")))
+        .unwrap();
+    restorer.restore_working_dir().unwrap();
+    assert_eq!(restorer.current_working_dir(), tempdir.path());
+
+    restorer
+        .change_working_dir(Cow::Borrowed(Path::new("sub")))
+        .unwrap();
+    restorer.clear_working_dir();
+    drop(restorer);
+    assert_eq!(env.current_working_dir(), sub);
+}
+
+#[test]
+fn working_dir_restored_if_future_cancelled() {
+    let tempdir = mktmp!();
+    fs::create_dir(tempdir.path().join("sub")).unwrap();
+
+    let mut env = VirtualWorkingDirEnv::new(tempdir.path()).unwrap();
+
+    let future = async {
+        let _restorer =
+            WorkingDirRestorer::with_working_dir(&mut env, Cow::Borrowed(Path::new("sub")))
+                .unwrap();
+        pending::<()>().await;
+    };
+    assert_eq!(future.now_or_never(), None);
+
+    assert_eq!(env.current_working_dir(), tempdir.path());
+}
@@ -1,8 +1,8 @@
 #![deny(rust_2018_idioms)]
 
 use conch_runtime::env::{
-    EnvRestorer, ExportedVariableEnvironment, Restorer, UnsetVariableEnvironment, VarEnvRestorer,
-    VariableEnvironment,
+    EnvRestorer, ExportedVariableEnvironment, Restorer, ScopedFrame, ScopedRestorer,
+    UnsetVariableEnvironment, VarEnvRestorer, VariableEnvironment,
 };
 
 mod mock_env;
@@ -148,3 +148,135 @@ fn clear_persists_changes() {
 
     assert_eq!(env, current);
 }
+
+#[test]
+fn restore_batch_changes() {
+    let mut env = MockFileAndVarEnv::new();
+    env.set_exported_var("key_exported", "val_exported", true);
+    env.set_var("key_existing", "val_existing");
+
+    let env_original = env.clone();
+    let mut restorer = EnvRestorer::new(&mut env);
+
+    restorer.set_vars(vec![
+        ("key_existing", "some other value"),
+        ("key_originally_unset", "some new value"),
+    ]);
+    restorer.extend_exported(vec![
+        ("key_exported", "some other exported value", false),
+        ("key_existing", "yet another value", true),
+    ]);
+
+    assert_ne!(env_original, *restorer.get());
+    restorer.restore_vars();
+    assert_eq!(env_original, *restorer.get());
+}
+
+#[test]
+fn nested_frames_restore_independently() {
+    let key = "key";
+    let key_inner = "key_inner";
+
+    let mut env = MockFileAndVarEnv::new();
+    env.set_var(key, "original");
+
+    let env_original = env.clone();
+    let mut restorer = EnvRestorer::new(&mut env);
+    restorer.set_var(key, "outer");
+    let env_outer = restorer.get().clone();
+
+    restorer.push_frame();
+    assert_eq!(restorer.frame_depth(), 1);
+    restorer.set_var(key, "inner");
+    restorer.set_var(key_inner, "inner");
+
+    // Restoring only applies to the innermost frame
+    restorer.restore_vars();
+    assert_eq!(env_outer, *restorer.get());
+
+    restorer.set_var(key, "inner");
+    restorer.pop_frame();
+    assert_eq!(restorer.frame_depth(), 0);
+    assert_eq!(env_outer, *restorer.get());
+
+    restorer.pop_frame();
+    assert_eq!(env_original, *restorer.get());
+}
+
+#[test]
+fn clearing_inner_frame_keeps_outer_backups() {
+    let key = "key";
+    let key_inner = "key_inner";
+
+    let mut env = MockFileAndVarEnv::new();
+    env.set_var(key, "original");
+
+    let env_original = env.clone();
+    let mut restorer = EnvRestorer::new(&mut env);
+    restorer.set_var(key, "outer");
+
+    restorer.push_frame();
+    restorer.set_var(key, "inner");
+    restorer.set_var(key_inner, "inner");
+    restorer.clear_vars();
+    restorer.pop_frame();
+
+    assert_eq!(restorer.var(&key), Some(&"inner"));
+    assert_eq!(restorer.var(&key_inner), Some(&"inner"));
+
+    // Dropping restores any active frames, but the outer frame
+    // is unaware of the variable the inner frame committed to
+    restorer.push_frame();
+    drop(restorer);
+
+    assert_eq!(env.var(&key), env_original.var(&key));
+    assert_eq!(env.var(&key_inner), Some(&"inner"));
+}
+
+#[test]
+fn scoped_frame_pops_its_frame_on_drop() {
+    let key = "key";
+
+    let mut env = MockFileAndVarEnv::new();
+    env.set_var(key, "original");
+
+    let mut restorer = EnvRestorer::new(&mut env);
+    restorer.set_var(key, "outer");
+    let env_outer = restorer.get().clone();
+
+    {
+        let mut frame = ScopedFrame::new(&mut restorer);
+        assert_eq!(frame.frame_depth(), 1);
+        frame.set_var(key, "inner");
+
+        // Balanced nested frames are fine
+        let mut nested = ScopedFrame::new(&mut *frame);
+        nested.set_var(key, "nested");
+    }
+
+    assert_eq!(restorer.frame_depth(), 0);
+    assert_eq!(env_outer, *restorer.get());
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "restorer frames were left unbalanced")]
+fn scoped_frame_panics_if_nested_code_pops_its_frame() {
+    let mut env = MockFileAndVarEnv::new();
+    let mut restorer = EnvRestorer::new(&mut env);
+
+    let mut frame = ScopedFrame::new(&mut restorer);
+    frame.set_var("key", "val");
+    frame.pop_frame();
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "restorer frames were left unbalanced")]
+fn scoped_frame_panics_if_nested_code_leaks_a_frame() {
+    let mut env = MockFileAndVarEnv::new();
+    let mut restorer = EnvRestorer::new(&mut env);
+
+    let mut frame = ScopedFrame::new(&mut restorer);
+    frame.push_frame();
+}
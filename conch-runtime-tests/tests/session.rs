@@ -0,0 +1,187 @@
+#![deny(rust_2018_idioms)]
+
+use conch_parser::ast::builder::ArcBuilder;
+use conch_parser::parse::ParseError;
+use std::time::Duration;
+
+mod support;
+pub use self::support::*;
+
+fn new_session() -> Session<DefaultEnvArc> {
+    Session::new(new_env_with_no_fds())
+}
+
+#[tokio::test]
+async fn should_execute_batches_against_the_same_env() {
+    let mut session = new_session();
+
+    let batch = session.parse::<ArcBuilder>("x=1; false\n").unwrap();
+    assert_eq!(batch.len(), 2);
+    assert_eq!(session.execute(batch).await, Ok(EXIT_ERROR));
+    assert_eq!(session.env().last_status(), EXIT_ERROR);
+
+    assert_eq!(run(&mut session, "y=\"$x $?\"\n").await, Ok(EXIT_SUCCESS));
+    assert_eq!(var(&session, "y"), Some("1 1".to_owned()));
+}
+
+#[tokio::test]
+async fn should_hold_on_to_input_which_ends_mid_command() {
+    let mut session = new_session();
+    assert!(!session.is_incomplete());
+
+    let batch = session.parse::<ArcBuilder>("x=1\nif true; then\n").unwrap();
+    assert_eq!(batch.len(), 1);
+    assert!(session.is_incomplete());
+    assert_eq!(session.pending_input(), "if true; then\n");
+    session.execute(batch).await.unwrap();
+    assert_eq!(var(&session, "x"), Some("1".to_owned()));
+
+    let batch = session.parse::<ArcBuilder>("  y=2\n").unwrap();
+    assert!(batch.is_empty());
+    assert!(session.is_incomplete());
+
+    let batch = session.parse::<ArcBuilder>("fi; z=3\n").unwrap();
+    assert_eq!(batch.len(), 2);
+    assert!(!session.is_incomplete());
+    assert_eq!(session.pending_input(), "");
+
+    session.execute(batch).await.unwrap();
+    assert_eq!(var(&session, "y"), Some("2".to_owned()));
+    assert_eq!(var(&session, "z"), Some("3".to_owned()));
+}
+
+#[tokio::test]
+async fn should_consider_unterminated_structures_incomplete() {
+    let cases = [
+        "echo 'abc\n",
+        "echo \"abc\n",
+        "echo $(foo\n",
+        "foo &&\n",
+        "foo |\n",
+        "for x in a b\n",
+        "while true\n",
+        "case x in\n",
+        "foo() {\n",
+    ];
+
+    for case in &cases {
+        let mut session = new_session();
+        let batch = session.parse::<ArcBuilder>(case).unwrap();
+
+        assert!(batch.is_empty(), "{:?}", case);
+        assert!(session.is_incomplete(), "{:?}", case);
+        assert_eq!(session.pending_input(), *case);
+    }
+}
+
+#[tokio::test]
+async fn should_discard_input_with_syntax_errors() {
+    let mut session = new_session();
+
+    session.parse::<ArcBuilder>("if true; then\n").unwrap();
+    match session.parse::<ArcBuilder>("echo )\n") {
+        Err(ParseError::Unexpected(_, _)) => {}
+        result => panic!("unexpected result: {:?}", result),
+    }
+
+    assert!(!session.is_incomplete());
+    assert!(session.parse::<ArcBuilder>("echo\n").unwrap().len() == 1);
+}
+
+#[tokio::test]
+async fn should_discard_pending_input_on_request() {
+    let mut session = new_session();
+
+    session.parse::<ArcBuilder>("while true; do\n").unwrap();
+    assert_eq!(session.discard_pending_input(), "while true; do\n");
+    assert!(!session.is_incomplete());
+
+    let batch = session.parse::<ArcBuilder>("x=1\n").unwrap();
+    assert_eq!(batch.len(), 1);
+}
+
+#[tokio::test]
+async fn should_return_fatal_errors_and_remain_usable() {
+    let mut session = new_session();
+
+    match run(&mut session, "x=${unset_var:?oops}; y=1\n").await {
+        Err(RuntimeError::Expansion(ExpansionError::EmptyParameter(_, _))) => {}
+        result => panic!("unexpected result: {:?}", result),
+    }
+    assert_eq!(var(&session, "y"), None);
+
+    assert_eq!(run(&mut session, "y=2\n").await, Ok(EXIT_SUCCESS));
+    assert_eq!(var(&session, "y"), Some("2".to_owned()));
+}
+
+#[tokio::test]
+async fn interactive_mode_can_be_switched_at_run_time() {
+    let mut session = new_session();
+    assert!(!session.is_interactive());
+
+    assert!(run(&mut session, "x=$((1/0)); y=1\n").await.is_err());
+    assert_eq!(var(&session, "y"), None);
+
+    session.env_mut().options_mut().fatal_special_builtin_errors = true;
+    session.set_interactive(true);
+    assert!(session.is_interactive());
+    assert!(session.env().sub_env().is_interactive());
+    assert!(!session.env().options().fatal_special_builtin_errors);
+
+    // Fatal errors are reported and the rest of the batch still runs
+    assert_eq!(
+        run(&mut session, "x=$((1/0)); status=$?; y=1\n").await,
+        Ok(EXIT_SUCCESS)
+    );
+    assert_eq!(var(&session, "status"), Some("1".to_owned()));
+    assert_eq!(var(&session, "y"), Some("1".to_owned()));
+
+    session.set_interactive(false);
+    assert!(!session.is_interactive());
+    assert!(!session.env().options().fatal_special_builtin_errors);
+
+    assert!(run(&mut session, "x=$((1/0)); z=1\n").await.is_err());
+    assert_eq!(var(&session, "z"), None);
+}
+
+#[tokio::test]
+async fn should_dump_state_of_cancelled_batches() {
+    let mut session = new_session();
+
+    let batch = session
+        .parse::<ArcBuilder>("true &\nx=1\nsleep 5\ny=1\n")
+        .unwrap();
+    let result = tokio::time::timeout(Duration::from_millis(200), session.execute(batch)).await;
+    assert!(result.is_err(), "batch should not have completed");
+
+    let state = session.dump_state();
+    assert_eq!(
+        state,
+        SessionState {
+            interactive: false,
+            executing: Some(2),
+            pending_input_len: 0,
+            last_status: EXIT_SUCCESS,
+            jobs: vec![1],
+        }
+    );
+    assert_eq!(
+        state.to_string(),
+        "non-interactive session executing command #2, last status: exit code: 0, \
+         pending input: 0 bytes, jobs: [1]"
+    );
+    assert_eq!(var(&session, "x"), Some("1".to_owned()));
+    assert_eq!(var(&session, "y"), None);
+
+    run(&mut session, "wait\n").await.unwrap();
+    session.parse::<ArcBuilder>("if true; then\n").unwrap();
+
+    let state = session.dump_state();
+    assert_eq!(state.executing, None);
+    assert_eq!(state.jobs, Vec::<JobId>::new());
+    assert_eq!(
+        state.to_string(),
+        "idle non-interactive session, last status: exit code: 0, \
+         pending input: 14 bytes, jobs: []"
+    );
+}
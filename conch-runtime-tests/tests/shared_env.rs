@@ -0,0 +1,88 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::env::{SharedEnv, SubEnvironment};
+use std::sync::Arc;
+
+mod support;
+pub use self::support::*;
+
+#[tokio::test(threaded_scheduler)]
+async fn should_share_state_between_concurrent_tasks() {
+    let env = SharedEnv::new(new_env_with_no_fds());
+    let key = name("count");
+    env.lock().await.set_var(key.clone(), name("0"));
+
+    let tasks = (0..10)
+        .map(|_| {
+            let env = env.clone();
+            let key = key.clone();
+
+            tokio::spawn(async move {
+                let mut env = env.lock().await;
+                let count = env.var(&key).unwrap().parse::<usize>().unwrap();
+                let () = tokio::task::yield_now().await;
+                env.set_var(key, Arc::new((count + 1).to_string()));
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for task in tasks {
+        task.await.unwrap();
+    }
+
+    assert_eq!(env.lock().await.var(&key), Some(&name("10")));
+}
+
+#[tokio::test]
+async fn should_expose_env_traits_through_guard() {
+    let env = SharedEnv::new(new_env_with_no_fds());
+    env.lock()
+        .await
+        .set_args(Arc::new(vec![name("a"), name("b")].into()));
+
+    {
+        let mut guard = env.lock().await;
+        let args = vec!["1".to_owned()];
+        let exit = spawn::builtin::shift(args, &mut *guard).await.await;
+        assert_eq!(exit, EXIT_SUCCESS);
+    }
+
+    assert_eq!(env.lock().await.args(), vec![name("b")]);
+}
+
+#[tokio::test]
+async fn should_only_allow_one_lock_at_a_time() {
+    let env = SharedEnv::new(new_env_with_no_fds());
+    let other = env.clone();
+    assert!(env.ptr_eq(&other));
+    assert!(!env.ptr_eq(&SharedEnv::new(new_env_with_no_fds())));
+
+    let guard = env.lock().await;
+    assert!(other.try_lock().is_none());
+    drop(guard);
+    assert!(other.try_lock().is_some());
+}
+
+#[tokio::test]
+async fn sub_env_should_not_affect_shared_env() {
+    let env = SharedEnv::new(new_env_with_no_fds());
+    let key = name("key");
+    env.lock().await.set_var(key.clone(), name("shared"));
+
+    let mut sub_env = env.sub_env().await;
+    assert_eq!(sub_env.var(&key), Some(&name("shared")));
+    sub_env.set_var(key.clone(), name("sub"));
+
+    assert_eq!(env.lock().await.var(&key), Some(&name("shared")));
+    assert_eq!(sub_env.sub_env().var(&key), Some(&name("sub")));
+}
+
+#[tokio::test]
+async fn try_unwrap_should_succeed_only_for_the_last_handle() {
+    let env = SharedEnv::new(new_env_with_no_fds());
+    let other = env.clone();
+
+    let env = env.try_unwrap().unwrap_err();
+    drop(other);
+    assert!(env.try_unwrap().is_ok());
+}
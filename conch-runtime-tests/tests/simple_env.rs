@@ -0,0 +1,99 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::io::Permissions;
+use conch_runtime::{STDIN_FILENO, STDOUT_FILENO};
+use std::io::Write;
+use std::path::Path;
+use tokio::io::AsyncReadExt;
+
+#[macro_use]
+mod support;
+pub use self::support::*;
+
+/// A third-party builtin which only relies on the simple interface.
+async fn upcase(env: &mut dyn SimpleEnvironment) -> ExitStatus {
+    let mut input = String::new();
+    let read = match env.read_async(STDIN_FILENO) {
+        Ok(mut reader) => reader.read_to_string(&mut input).await,
+        Err(e) => Err(e),
+    };
+
+    if read.is_err() {
+        return EXIT_ERROR;
+    }
+
+    let prefix = env.var("PREFIX").unwrap_or_default().to_owned();
+    let output = format!("{}{}", prefix, input.to_uppercase());
+    env.set_var("LAST_UPCASE".to_owned(), output.clone());
+
+    match env.write_all(STDOUT_FILENO, output.into_bytes()).await {
+        Ok(()) => EXIT_SUCCESS,
+        Err(_) => EXIT_ERROR,
+    }
+}
+
+#[tokio::test]
+async fn builtin_against_simple_env() {
+    let mut env = new_env_with_no_fds();
+
+    let input = env.open_pipe().expect("pipe failed");
+    let output = env.open_pipe().expect("pipe failed");
+    env.set_file_desc(STDIN_FILENO, input.reader, Permissions::Read);
+    env.set_file_desc(STDOUT_FILENO, output.writer, Permissions::Write);
+    env.set_var("PREFIX".to_owned().into(), "> ".to_owned().into());
+
+    (&*input.writer).write_all(b"hello").expect("write failed");
+    drop(input.writer);
+
+    let read_output = tokio::spawn(env.read_all(output.reader));
+
+    let exit = upcase(&mut SimpleEnvAdapter::new(&mut env)).await;
+    env.close_file_desc(STDOUT_FILENO);
+
+    assert_eq!(exit, EXIT_SUCCESS);
+    assert_eq!(read_output.await.unwrap().unwrap(), b"> HELLO");
+    assert_eq!(
+        env.var(&"LAST_UPCASE".to_owned()).map(|v| &***v),
+        Some("> HELLO")
+    );
+}
+
+#[tokio::test]
+async fn simple_env_adapter() {
+    let tempdir = mktmp!();
+
+    let mut env = new_env_with_no_fds();
+    let null = dev_null(&mut env);
+    env.set_file_desc(STDIN_FILENO, null, Permissions::Read);
+
+    let mut simple = SimpleEnvAdapter::new(&mut env);
+    let simple: &mut dyn SimpleEnvironment = &mut simple;
+
+    simple.set_var("var".to_owned(), "value".to_owned());
+    assert_eq!(simple.var("var"), Some("value"));
+    assert!(!simple.env_vars().contains(&("var", "value")));
+    simple.unset_var("var");
+    assert_eq!(simple.var("var"), None);
+
+    simple.set_last_status(ExitStatus::Code(42));
+    assert_eq!(simple.last_status(), ExitStatus::Code(42));
+
+    simple
+        .change_working_dir(tempdir.path())
+        .expect("change_working_dir failed");
+    assert_eq!(simple.current_working_dir(), tempdir.path());
+    assert!(simple.change_working_dir(Path::new("missing")).is_err());
+
+    assert_eq!(
+        simple.file_desc_permissions(STDIN_FILENO),
+        Some(Permissions::Read)
+    );
+    assert!(simple
+        .write_all(STDIN_FILENO, b"foo".to_vec())
+        .await
+        .is_err());
+    assert!(simple.read_async(STDOUT_FILENO).is_err());
+
+    simple.close_file_desc(STDIN_FILENO);
+    assert_eq!(simple.file_desc_permissions(STDIN_FILENO), None);
+}
@@ -0,0 +1,127 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::io::Permissions;
+use conch_runtime::{STDIN_FILENO, STDOUT_FILENO};
+
+mod support;
+pub use self::support::*;
+
+#[tokio::test]
+async fn background_stdin_is_null() {
+    let mut env = new_env_with_no_fds();
+
+    let pipe = env.open_pipe().unwrap();
+    env.set_file_desc(STDIN_FILENO, pipe.reader, Permissions::Read);
+    env.set_file_desc(STDOUT_FILENO, pipe.writer.clone(), Permissions::Write);
+
+    let mut background_env = env.sub_env();
+    redirect_background_stdin(&mut background_env).unwrap();
+
+    let (stdin, perms) = background_env.file_desc(STDIN_FILENO).unwrap();
+    assert_eq!(perms, Permissions::Read);
+
+    // Reading should immediately hit EOF instead of waiting on the shell's stdin
+    let stdin = stdin.clone();
+    assert_eq!(
+        background_env.read_all(stdin).await.unwrap(),
+        Vec::<u8>::new()
+    );
+
+    // Other descriptors, and the parent environment, are left untouched
+    let (stdout, _) = background_env.file_desc(STDOUT_FILENO).unwrap();
+    assert_eq!(*stdout, pipe.writer);
+    assert_ne!(
+        env.file_desc(STDIN_FILENO).unwrap().0,
+        background_env.file_desc(STDIN_FILENO).unwrap().0
+    );
+}
+
+#[tokio::test]
+async fn bang_tracks_the_last_background_job() {
+    use conch_parser::ast::builder::ArcBuilder;
+    use std::sync::Arc;
+
+    let mut session = Session::new(new_env_with_no_fds());
+    let batch = session
+        .parse::<ArcBuilder>(concat!(
+            "before=$!\n",
+            "true & first=$!\n",
+            "sub=$(echo $!)\n",
+            "(false &); after_sub=$!\n",
+            "false & second=$!\n",
+            "wait\n",
+        ))
+        .unwrap();
+    session.execute(batch).await.unwrap();
+
+    let var = |name: &str| {
+        session
+            .env()
+            .var(&Arc::new(name.to_owned()))
+            .map(|val| (**val).clone())
+    };
+
+    let first = var("first").unwrap();
+    assert_eq!(var("before"), Some(String::new()));
+    assert_eq!(var("sub"), Some(first.clone()));
+    // Jobs spawned by subshells are not visible to the parent
+    assert_eq!(var("after_sub"), Some(first.clone()));
+    assert_ne!(var("second"), Some(first));
+    assert_eq!(
+        var("second"),
+        session.env().last_job().map(|id| id.to_string())
+    );
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn background_jobs_track_resource_usage() {
+    use conch_parser::ast::builder::ArcBuilder;
+
+    let mut session = Session::new(new_env_with_no_fds());
+    let script = format!("{} >/dev/null &\n", bin_path("env").display());
+    let batch = session.parse::<ArcBuilder>(&script).unwrap();
+    session.execute(batch).await.unwrap();
+
+    let id = session.env().last_job().expect("no job spawned");
+    session.env().any_job_done().expect("job not running").await;
+
+    let usage = session.env().job_usage(id).expect("no job usage");
+    assert!(usage.max_rss > 0);
+
+    let status = session.env_mut().wait_job(id).unwrap().await;
+    assert!(status.success());
+    assert_eq!(session.env().job_usage(id), None);
+}
+
+#[tokio::test]
+async fn completed_jobs_are_forgotten_beyond_child_max() {
+    let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+
+    let mut env = JobEnv::new();
+    let running = env.spawn_job(Box::pin(async move {
+        rx.await.unwrap();
+        EXIT_SUCCESS
+    }));
+
+    for _ in 0..JobEnv::CHILD_MAX + 10 {
+        env.spawn_job(Box::pin(async { EXIT_SUCCESS }));
+    }
+    assert_eq!(env.job_ids().len(), JobEnv::CHILD_MAX + 11);
+
+    // Let the completed jobs run to completion
+    for _ in 0..10 {
+        let () = tokio::task::yield_now().await;
+    }
+
+    let last = env.spawn_job(Box::pin(async { EXIT_ERROR }));
+    let ids = env.job_ids();
+    assert_eq!(ids.len(), JobEnv::CHILD_MAX);
+    assert_eq!(ids.first(), Some(&running));
+    assert_eq!(ids.last(), Some(&last));
+    assert_eq!(env.wait_job(running + 1).map(drop), None);
+
+    tx.send(()).unwrap();
+    assert_eq!(env.wait_job(running).unwrap().await, EXIT_SUCCESS);
+    assert_eq!(env.wait_job(last).unwrap().await, EXIT_ERROR);
+}
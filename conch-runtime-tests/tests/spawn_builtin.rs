@@ -1,8 +1,9 @@
 #![deny(rust_2018_idioms)]
 
 use conch_runtime::io::Permissions;
-use conch_runtime::STDOUT_FILENO;
+use conch_runtime::{STDIN_FILENO, STDOUT_FILENO};
 use futures_util::future::join;
+use std::io::Write;
 use std::sync::Arc;
 
 mod support;
@@ -104,6 +105,20 @@ async fn builtin_smoke_pwd() {
     );
 }
 
+#[tokio::test]
+async fn builtin_smoke_read() {
+    let output = run_builtin_with_prep("read", &["var"], |env| {
+        let pipe = env.open_pipe().expect("pipe failed");
+        (&*pipe.writer).write_all(b"foo\n").expect("write failed");
+        env.set_file_desc(STDIN_FILENO, pipe.reader, Permissions::Read);
+    })
+    .await;
+
+    assert_eq!(output.exit, EXIT_SUCCESS);
+    assert_eq!(output.out, "");
+    assert_eq!(output.env.var(&String::from("var")), Some(&rc("foo")));
+}
+
 #[tokio::test]
 async fn builtin_smoke_shift() {
     let mut args = vec![
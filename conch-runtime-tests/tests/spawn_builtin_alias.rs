@@ -0,0 +1,80 @@
+#![deny(rust_2018_idioms)]
+
+mod support;
+pub use self::support::*;
+
+#[tokio::test]
+async fn should_define_and_print_aliases() {
+    let mut session = Session::new(new_env_with_no_fds());
+
+    run(
+        &mut session,
+        concat!(
+            "alias ll='ls -l' la=ls; set_status=$?\n",
+            "one=$(alias ll); all=$(alias)\n",
+            "alias missing ll >/dev/null 2>&1; missing_status=$?\n",
+            "alias 'bad name=x' 2>/dev/null; invalid_status=$?\n",
+        ),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(session.env().alias("ll"), Some("ls -l"));
+    assert_eq!(session.env().alias("la"), Some("ls"));
+    assert_eq!(session.env().alias("bad name"), None);
+    assert_eq!(var(&session, "set_status"), Some("0".to_owned()));
+    assert_eq!(var(&session, "one"), Some("alias ll='ls -l'".to_owned()));
+    assert_eq!(
+        var(&session, "all"),
+        Some("alias la=ls\nalias ll='ls -l'".to_owned())
+    );
+    assert_eq!(var(&session, "missing_status"), Some("1".to_owned()));
+    assert_eq!(var(&session, "invalid_status"), Some("1".to_owned()));
+}
+
+#[tokio::test]
+async fn should_remove_aliases() {
+    let mut session = Session::new(new_env_with_no_fds());
+
+    run(
+        &mut session,
+        concat!(
+            "alias a=1 b=2 c=3\n",
+            "unalias a; status=$?\n",
+            "unalias a 2>/dev/null; missing_status=$?\n",
+            "unalias 2>/dev/null; usage_status=$?\n",
+            "left=$(alias)\n",
+            "unalias -a; none=$(alias)\n",
+        ),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(var(&session, "status"), Some("0".to_owned()));
+    assert_eq!(var(&session, "missing_status"), Some("1".to_owned()));
+    assert_eq!(var(&session, "usage_status"), Some("1".to_owned()));
+    assert_eq!(
+        var(&session, "left"),
+        Some("alias b=2\nalias c=3".to_owned())
+    );
+    assert_eq!(var(&session, "none"), Some("".to_owned()));
+    assert!(session.env().aliases().is_empty());
+}
+
+#[tokio::test]
+async fn subshells_should_inherit_but_not_leak_aliases() {
+    let mut session = Session::new(new_env_with_no_fds());
+
+    run(
+        &mut session,
+        "alias outer=1; inner=$(alias outer; alias sub=2; alias sub)\n",
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        var(&session, "inner"),
+        Some("alias outer=1\nalias sub=2".to_owned())
+    );
+    assert_eq!(session.env().alias("sub"), None);
+}
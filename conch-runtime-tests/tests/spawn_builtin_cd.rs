@@ -4,6 +4,7 @@ use conch_runtime::io::Permissions;
 use futures_util::future::join3;
 use std::borrow::Cow;
 use std::fs;
+use std::io;
 #[cfg(unix)]
 use std::os::unix::fs::symlink as symlink_dir;
 #[cfg(windows)]
@@ -316,3 +317,35 @@ async fn dash_unset_old_pwd_is_error() {
     assert_eq!(result.initial_cwd, result.final_cwd);
     assert!(result.err.ends_with(": OLDPWD not set\n"));
 }
+
+#[tokio::test]
+async fn vetoed_change_is_reported_as_error() {
+    let tempdir = mktmp!();
+
+    let mut cfg = DefaultEnvConfigArc::new().expect("failed to create env cfg");
+    cfg.file_desc_manager_env = TokioFileDescManagerEnv::new();
+    cfg.working_dir_env.add_change_validator(|_, _| {
+        Err(io::Error::new(io::ErrorKind::PermissionDenied, "sandboxed"))
+    });
+    let mut env = DefaultEnvArc::with_config(cfg);
+
+    let pipe_err = env.open_pipe().expect("err pipe failed");
+    env.set_file_desc(
+        conch_runtime::STDERR_FILENO,
+        pipe_err.writer,
+        Permissions::Write,
+    );
+    let read_to_end_err = tokio::spawn(env.read_all(pipe_err.reader));
+
+    let initial_cwd = env.current_working_dir().to_path_buf();
+    let args = vec![tempdir.path().to_string_lossy().into_owned()];
+    let exit = cd(args, &mut env).await.await;
+    env.close_file_desc(conch_runtime::STDERR_FILENO);
+
+    let err = read_to_end_err.await.unwrap().unwrap();
+    let err = String::from_utf8(err).expect("err invalid utf8");
+
+    assert_eq!(exit, EXIT_ERROR);
+    assert_eq!(env.current_working_dir(), initial_cwd);
+    assert!(err.ends_with(": sandboxed\n"), "{}", err);
+}
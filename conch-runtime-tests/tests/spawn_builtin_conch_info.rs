@@ -0,0 +1,91 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::io::Permissions;
+use conch_runtime::{STDERR_FILENO, STDOUT_FILENO};
+use std::fs::{File, OpenOptions};
+
+#[macro_use]
+mod support;
+pub use self::support::spawn::builtin::conch_info;
+pub use self::support::*;
+
+#[tokio::test]
+async fn runtime_info_should_describe_open_fds_and_counters() {
+    let tempdir = mktmp!();
+    let file_path = tempdir.path().join("file");
+    File::create(&file_path).unwrap();
+
+    let mut cfg = DefaultEnvConfigArc::new().expect("failed to create env cfg");
+    cfg.file_desc_manager_env = TokioFileDescManagerEnv::new();
+    cfg.file_desc_manager_env.fd_usage().set_limit(Some(42));
+    let mut env = DefaultEnvArc::with_config(cfg);
+
+    let file = env
+        .open_path(&file_path, OpenOptions::new().read(true))
+        .unwrap();
+    let pipe = env.open_pipe().unwrap();
+    env.set_file_desc(5, file, Permissions::Read);
+    env.set_file_desc(STDOUT_FILENO, pipe.writer, Permissions::Write);
+
+    let info = env.runtime_info().unwrap();
+    assert!(!info.version.is_empty());
+    assert_eq!(info.open_fds, Some(3));
+    assert_eq!(info.fd_limit, Some(42));
+    assert_eq!(
+        info.fds,
+        vec![
+            FdInfo {
+                fd: STDOUT_FILENO,
+                permissions: Permissions::Write,
+                cloexec: true,
+                io_backend: if cfg!(unix) {
+                    IoBackend::Evented
+                } else {
+                    IoBackend::ThreadPool
+                },
+            },
+            FdInfo {
+                fd: 5,
+                permissions: Permissions::Read,
+                cloexec: true,
+                io_backend: IoBackend::ThreadPool,
+            },
+        ]
+    );
+
+    drop(pipe.reader);
+}
+
+#[tokio::test]
+async fn should_print_runtime_info() {
+    let mut env = new_env_with_no_fds();
+
+    let pipe = env.open_pipe().expect("failed to open pipe");
+    env.set_file_desc(STDOUT_FILENO, pipe.writer, Permissions::Write);
+    let stdout = env.read_all(pipe.reader);
+    let expected = env.runtime_info().unwrap().to_string();
+
+    let exit = conch_info(Vec::<String>::new(), &mut env).await.await;
+    drop(env);
+
+    assert_eq!(exit, EXIT_SUCCESS);
+    let stdout = String::from_utf8(stdout.await.unwrap()).unwrap();
+    assert_eq!(stdout, expected);
+    assert!(stdout.starts_with("conch-runtime "));
+    assert!(stdout.contains("fd 1: Write, "));
+}
+
+#[tokio::test]
+async fn should_reject_operands() {
+    let mut env = new_env_with_no_fds();
+
+    let pipe = env.open_pipe().expect("failed to open pipe");
+    env.set_file_desc(STDERR_FILENO, pipe.writer, Permissions::Write);
+    let stderr = env.read_all(pipe.reader);
+
+    let exit = conch_info(vec!["foo".to_owned()], &mut env).await.await;
+    drop(env);
+
+    assert_eq!(exit, EXIT_ERROR);
+    assert!(!stderr.await.unwrap().is_empty());
+}
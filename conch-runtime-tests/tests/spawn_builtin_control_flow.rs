@@ -0,0 +1,175 @@
+#![deny(rust_2018_idioms)]
+
+mod support;
+pub use self::support::*;
+
+async fn run_with_flow(script: &str) -> (Session<DefaultEnvArc>, Option<ControlFlow>) {
+    let mut session = Session::new(new_env_with_no_fds());
+    let flow = match run(&mut session, script).await {
+        Ok(_) => None,
+        Err(e) => Some(control_flow_for_error(&e).expect("unexpected error")),
+    };
+
+    (session, flow)
+}
+
+#[tokio::test]
+async fn return_exits_function_with_status() {
+    let (session, flow) = run_with_flow(concat!(
+        "f() { x=before; return 5; x=after; }\n",
+        "f; status=$?\n",
+        "g() { for i in 1 2 3; do while true; do return $i; done; done; }\n",
+        "g; loop_status=$?\n",
+        "h() { false; return; }\n",
+        "h; default_status=$?\n",
+        "k() { return 300; }\n",
+        "k; truncated_status=$?\n",
+    ))
+    .await;
+
+    assert_eq!(flow, None);
+    assert_eq!(var(&session, "x"), Some("before".to_owned()));
+    assert_eq!(var(&session, "status"), Some("5".to_owned()));
+    assert_eq!(var(&session, "loop_status"), Some("1".to_owned()));
+    assert_eq!(var(&session, "default_status"), Some("1".to_owned()));
+    assert_eq!(var(&session, "truncated_status"), Some("44".to_owned()));
+}
+
+#[tokio::test]
+async fn return_only_unwinds_innermost_function() {
+    let (session, flow) = run_with_flow(concat!(
+        "inner() { return 3; }\n",
+        "outer() { inner; inner_status=$?; return 4; }\n",
+        "outer; outer_status=$?\n",
+    ))
+    .await;
+
+    assert_eq!(flow, None);
+    assert_eq!(var(&session, "inner_status"), Some("3".to_owned()));
+    assert_eq!(var(&session, "outer_status"), Some("4".to_owned()));
+}
+
+#[tokio::test]
+async fn return_outside_function_is_an_error() {
+    let (session, flow) = run_with_flow("return 5; status=$?").await;
+
+    assert_eq!(flow, None);
+    assert_eq!(var(&session, "status"), Some("1".to_owned()));
+}
+
+#[tokio::test]
+async fn exit_stops_the_script() {
+    let (session, flow) = run_with_flow("x=before; exit 7; x=after").await;
+
+    assert_eq!(flow, Some(ControlFlow::Exit(ExitStatus::Code(7))));
+    assert_eq!(var(&session, "x"), Some("before".to_owned()));
+    assert_eq!(session.env().last_status(), ExitStatus::Code(7));
+
+    let (session, flow) = run_with_flow("f() { exit 3; }; f; x=after").await;
+    assert_eq!(flow, Some(ControlFlow::Exit(ExitStatus::Code(3))));
+    assert_eq!(var(&session, "x"), None);
+
+    let (session, flow) = run_with_flow("false; exit").await;
+    assert_eq!(flow, Some(ControlFlow::Exit(EXIT_ERROR)));
+    assert_eq!(session.env().last_status(), EXIT_ERROR);
+}
+
+#[tokio::test]
+async fn exit_only_ends_subshells() {
+    let (session, flow) = run_with_flow(concat!(
+        "(exit 4; x=after); subshell_status=$?\n",
+        "sub=$(echo out; exit 5; echo more); subst_status=$?\n",
+        "exit 6 | true; pipeline_status=$?\n",
+    ))
+    .await;
+
+    assert_eq!(flow, None);
+    assert_eq!(var(&session, "x"), None);
+    assert_eq!(var(&session, "subshell_status"), Some("4".to_owned()));
+    assert_eq!(var(&session, "sub"), Some("out".to_owned()));
+    assert_eq!(var(&session, "subst_status"), Some("5".to_owned()));
+    assert_eq!(var(&session, "pipeline_status"), Some("0".to_owned()));
+}
+
+#[tokio::test]
+async fn non_numeric_arguments_are_reported() {
+    let (session, flow) = run_with_flow("exit foo; status=$?").await;
+    assert_eq!(flow, None);
+    assert_eq!(var(&session, "status"), Some("1".to_owned()));
+
+    let (session, flow) = run_with_flow("f() { return 1 2; }; f; status=$?").await;
+    assert_eq!(flow, None);
+    assert_eq!(var(&session, "status"), Some("1".to_owned()));
+}
+
+#[tokio::test]
+async fn break_exits_loops() {
+    let (session, flow) = run_with_flow(concat!(
+        "while true; do x=${x}a; break; x=never; done; while_status=$?\n",
+        "for i in 1 2 3; do y=${y}$i; if [ $i = 2 ]; then false; break; fi; done; for_status=$?\n",
+        "until break; do z=never; done\n",
+        "for i in 1 2; do for j in 1 2; do nested=${nested}$i$j; break 2; done; done\n",
+        "for i in 1 2; do for j in 1 2; do clamped=${clamped}$i$j; break 5; done; done\n",
+    ))
+    .await;
+
+    assert_eq!(flow, None);
+    assert_eq!(var(&session, "x"), Some("a".to_owned()));
+    assert_eq!(var(&session, "while_status"), Some("0".to_owned()));
+    assert_eq!(var(&session, "y"), Some("12".to_owned()));
+    assert_eq!(var(&session, "for_status"), Some("0".to_owned()));
+    assert_eq!(var(&session, "z"), None);
+    assert_eq!(var(&session, "nested"), Some("11".to_owned()));
+    assert_eq!(var(&session, "clamped"), Some("11".to_owned()));
+    assert_eq!(session.env().loop_depth(), 0);
+}
+
+#[tokio::test]
+async fn continue_skips_to_next_iteration() {
+    let (session, flow) = run_with_flow(concat!(
+        "for i in 1 2 3; do [ $i = 2 ] && continue; x=${x}$i; done\n",
+        "for i in 1 2; do for j in 1 2; do y=${y}$i$j; continue 2; y=never; done; y=never; done\n",
+        "n=; while [ \"$n\" != aaa ]; do n=${n}a; continue; done\n",
+        "for i in 1 2; do false; continue; done; status=$?\n",
+    ))
+    .await;
+
+    assert_eq!(flow, None);
+    assert_eq!(var(&session, "x"), Some("13".to_owned()));
+    assert_eq!(var(&session, "y"), Some("1121".to_owned()));
+    assert_eq!(var(&session, "n"), Some("aaa".to_owned()));
+    assert_eq!(var(&session, "status"), Some("0".to_owned()));
+}
+
+#[tokio::test]
+async fn break_and_continue_outside_loops_are_reported() {
+    let (session, flow) = run_with_flow(concat!(
+        "break; break_status=$?\n",
+        "continue; continue_status=$?\n",
+        "for i in 1 2; do (break); x=${x}$i; done\n",
+        "for i in 1; do break 0; done; zero_status=$?\n",
+        "for i in 1; do continue foo; done; numeric_status=$?\n",
+    ))
+    .await;
+
+    assert_eq!(flow, None);
+    assert_eq!(var(&session, "break_status"), Some("0".to_owned()));
+    assert_eq!(var(&session, "continue_status"), Some("0".to_owned()));
+    assert_eq!(var(&session, "x"), Some("12".to_owned()));
+    assert_eq!(var(&session, "zero_status"), Some("1".to_owned()));
+    assert_eq!(var(&session, "numeric_status"), Some("1".to_owned()));
+}
+
+#[tokio::test]
+async fn functions_can_break_out_of_calling_loops() {
+    let (session, flow) = run_with_flow(concat!(
+        "f() { break; }\n",
+        "for i in 1 2 3; do x=${x}$i; f; done\n",
+        "f; status=$?\n",
+    ))
+    .await;
+
+    assert_eq!(flow, None);
+    assert_eq!(var(&session, "x"), Some("1".to_owned()));
+    assert_eq!(var(&session, "status"), Some("0".to_owned()));
+}
@@ -0,0 +1,164 @@
+#![deny(rust_2018_idioms)]
+
+use std::convert::Infallible;
+use std::fs;
+use std::io;
+use std::sync::{Arc, Mutex};
+
+#[macro_use]
+mod support;
+pub use self::support::spawn::builtin::exec;
+pub use self::support::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Replaced {
+    name: String,
+    args: Vec<String>,
+    env_vars: Vec<(String, String)>,
+    tainted: bool,
+}
+
+/// An executable environment which records any process replacements instead
+/// of performing them, and pretends that any executables named `missing` do
+/// not exist.
+#[derive(Debug, Default, Clone)]
+struct RecordingExecEnv {
+    replaced: Arc<Mutex<Vec<Replaced>>>,
+}
+
+impl SubEnvironment for RecordingExecEnv {
+    fn sub_env(&self) -> Self {
+        self.clone()
+    }
+}
+
+impl ExecutableEnvironment for RecordingExecEnv {
+    fn spawn_executable(
+        &self,
+        _data: ExecutableData<'_>,
+    ) -> Result<BoxFuture<'static, ExitStatus>, CommandError> {
+        panic!("unexpected spawn")
+    }
+}
+
+impl ProcessTimesEnvironment for RecordingExecEnv {
+    fn process_times(&self) -> io::Result<ProcessTimes> {
+        unimplemented!()
+    }
+}
+
+impl ResourceLimitEnvironment for RecordingExecEnv {
+    fn resource_limit(&self, _resource: Resource) -> io::Result<ResourceLimit> {
+        unimplemented!()
+    }
+
+    fn set_resource_limit(&mut self, _resource: Resource, _limit: ResourceLimit) -> io::Result<()> {
+        unimplemented!()
+    }
+}
+
+impl ExecReplaceEnvironment for RecordingExecEnv {
+    fn exec_replace(
+        &self,
+        data: ExecutableData<'_>,
+    ) -> Result<BoxFuture<'static, Infallible>, CommandError> {
+        let name = data.name.to_str().unwrap().to_owned();
+        if name == "missing" {
+            return Err(CommandError::NotFound(name));
+        }
+
+        let to_string = |s: &std::ffi::OsStr| s.to_str().unwrap().to_owned();
+        self.replaced.lock().unwrap().push(Replaced {
+            name,
+            args: data.args.iter().map(|&arg| to_string(arg)).collect(),
+            env_vars: data
+                .env_vars
+                .iter()
+                .map(|&(key, val)| (to_string(key), to_string(val)))
+                .collect(),
+            tainted: data.is_tainted(),
+        });
+
+        Ok(Box::pin(pending()))
+    }
+}
+
+async fn run_in_tempdir(script: &str) -> (Session<DefaultEnvArc>, tempfile::TempDir) {
+    let tempdir = mktmp!();
+    let mut env = new_env_with_no_fds();
+    env.change_working_dir(tempdir.path().into()).unwrap();
+
+    let mut session = Session::new(env);
+    run(&mut session, script).await.unwrap();
+
+    (session, tempdir)
+}
+
+#[tokio::test]
+async fn redirects_without_command_remain_in_effect() {
+    let (session, tempdir) = run_in_tempdir(concat!(
+        "exec >out 3>three; status=$?\n",
+        "echo first; echo second\n",
+        "echo third >&3\n",
+        "{ exec 4>four; echo fourth >&4; } >grouped\n",
+        "echo fifth\n",
+        "echo sixth >&4\n",
+    ))
+    .await;
+
+    assert_eq!(var(&session, "status"), Some("0".to_owned()));
+
+    let read = |name| fs::read_to_string(tempdir.path().join(name)).unwrap();
+    assert_eq!(read("out"), "first\nsecond\nfifth\n");
+    assert_eq!(read("three"), "third\n");
+    assert_eq!(read("four"), "fourth\nsixth\n");
+    assert_eq!(read("grouped"), "");
+}
+
+#[tokio::test]
+async fn replaces_process_with_command() {
+    let exec_env = RecordingExecEnv::default();
+    let cfg = DefaultEnvConfigArc::new()
+        .unwrap()
+        .change_exec_env(exec_env.clone());
+
+    let mut env = Env::with_config(EnvConfig {
+        file_desc_manager_env: TokioFileDescManagerEnv::new(),
+        ..cfg
+    });
+    env.set_exported_var(
+        Arc::new("exported".to_owned()),
+        Arc::new("yes".to_owned()),
+        true,
+    );
+    env.set_var(Arc::new("local".to_owned()), Arc::new("no".to_owned()));
+
+    let args = vec!["--", "cmd", "-a", "arg"];
+    let args = args.into_iter().map(str::to_owned).collect::<Vec<_>>();
+    let future = exec(args, &mut EnvRestorer::new(&mut env)).await;
+
+    let replaced = exec_env.replaced.lock().unwrap().clone();
+    assert_eq!(replaced.len(), 1);
+    assert_eq!(replaced[0].name, "cmd");
+    assert_eq!(replaced[0].args, vec!["-a".to_owned(), "arg".to_owned()]);
+    assert!(replaced[0].tainted);
+
+    let env_vars = &replaced[0].env_vars;
+    assert!(env_vars.contains(&("exported".to_owned(), "yes".to_owned())));
+    assert!(env_vars.iter().all(|(key, _)| key != "local"));
+
+    // The current process should never resume once replaced
+    assert_eq!(poll_immediate(future).await, None);
+
+    let args = vec!["missing".to_owned()];
+    let status = exec(args, &mut EnvRestorer::new(&mut env)).await.await;
+    assert_eq!(status, ExitStatus::Code(127));
+    assert_eq!(exec_env.replaced.lock().unwrap().len(), 1);
+}
+
+async fn poll_immediate<F: Future + Unpin>(future: F) -> Option<F::Output> {
+    match select(future, ready(())).await {
+        Either::Left((output, _)) => Some(output),
+        Either::Right(_) => None,
+    }
+}
@@ -0,0 +1,122 @@
+#![deny(rust_2018_idioms)]
+#![cfg(unix)]
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+#[macro_use]
+mod support;
+pub use self::support::*;
+
+fn create_script(dir: &Path, output: &str) {
+    fs::create_dir(dir).unwrap();
+
+    let path = dir.join("tool");
+    fs::write(&path, format!("#!/bin/sh\necho {}\n", output)).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+fn new_session(tempdir: &Path) -> Session<DefaultEnvArc> {
+    let one = tempdir.join("one");
+    let two = tempdir.join("two");
+    create_script(&one, "one");
+    create_script(&two, "two");
+
+    let mut env = new_env_with_no_fds();
+    env.set_var(name("ONE"), name(one.to_str().unwrap()));
+    env.set_var(name("TWO"), name(two.to_str().unwrap()));
+    Session::new(env)
+}
+
+#[tokio::test]
+async fn should_remember_commands_until_path_changes() {
+    let tempdir = mktmp!();
+    let one = tempdir.path().join("one").join("tool");
+    let mut session = new_session(tempdir.path());
+
+    run(
+        &mut session,
+        concat!(
+            "PATH=$ONE; hash tool; status=$?\n",
+            "hashed=$(hash); out=$(tool)\n",
+            "PATH=$TWO; rehashed=$(hash); rehashed_out=$(tool)\n",
+            "hash tool missing ./tool; missing_status=$?\n",
+        ),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(var(&session, "status"), Some("0".to_owned()));
+    assert_eq!(var(&session, "hashed"), Some(one.display().to_string()));
+    assert_eq!(var(&session, "out"), Some("one".to_owned()));
+    assert_eq!(var(&session, "rehashed"), Some(String::new()));
+    assert_eq!(var(&session, "rehashed_out"), Some("two".to_owned()));
+    assert_eq!(var(&session, "missing_status"), Some("1".to_owned()));
+}
+
+#[tokio::test]
+async fn should_track_path_changes_within_sub_environments() {
+    let tempdir = mktmp!();
+    let one = tempdir.path().join("one").join("tool");
+    let mut session = new_session(tempdir.path());
+
+    run(
+        &mut session,
+        concat!(
+            "PATH=$ONE; hash tool\n",
+            "sub_out=$(PATH=$TWO; tool); sub_hashed=$(hash)\n",
+            "PATH=$PATH; kept=$(hash); out=$(tool)\n",
+        ),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(var(&session, "sub_out"), Some("two".to_owned()));
+    assert_eq!(var(&session, "sub_hashed"), Some(one.display().to_string()));
+    // NB: assigning the same value does not count as a change
+    assert_eq!(var(&session, "kept"), Some(one.display().to_string()));
+    assert_eq!(var(&session, "out"), Some("one".to_owned()));
+}
+
+#[tokio::test]
+async fn should_only_forget_commands_on_request_with_manual_rehash() {
+    let tempdir = mktmp!();
+    let one = tempdir.path().join("one").join("tool");
+    let mut session = new_session(tempdir.path());
+    session.env_mut().options_mut().manual_rehash = true;
+
+    run(
+        &mut session,
+        concat!(
+            "PATH=$ONE; hash tool; PATH=$TWO\n",
+            "hashed=$(hash); out=$(tool)\n",
+            "hash -r; forgotten=$(hash); forgotten_out=$(tool)\n",
+        ),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(var(&session, "hashed"), Some(one.display().to_string()));
+    assert_eq!(var(&session, "out"), Some("one".to_owned()));
+    assert_eq!(var(&session, "forgotten"), Some(String::new()));
+    assert_eq!(var(&session, "forgotten_out"), Some("two".to_owned()));
+}
+
+#[tokio::test]
+async fn should_not_remember_commands_found_in_relative_directories() {
+    let tempdir = mktmp!();
+    let mut session = new_session(tempdir.path());
+
+    run(
+        &mut session,
+        "cd $ONE; PATH=.; hash tool; status=$?; hashed=$(hash); out=$(tool)\n",
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(var(&session, "status"), Some("0".to_owned()));
+    assert_eq!(var(&session, "hashed"), Some(String::new()));
+    assert_eq!(var(&session, "out"), Some("one".to_owned()));
+    assert!(session.env().hashed_commands().is_empty());
+}
@@ -0,0 +1,98 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::io::Permissions;
+use conch_runtime::spawn::builtin::{report_usage_err, BuiltinArgs, UsageError};
+use conch_runtime::STDERR_FILENO;
+
+mod support;
+pub use self::support::*;
+
+fn parse(args: &[&str], optstring: &str) -> Result<BuiltinArgs, UsageError> {
+    BuiltinArgs::parse(args.iter().map(|&s| s.to_owned()), optstring)
+}
+
+#[tokio::test]
+async fn should_parse_flags_values_and_operands() {
+    let args = parse(&["-ab", "-tfoo", "-t", "bar", "-n5", "x", "-c"], "abct:n:").unwrap();
+
+    assert!(args.is_present('a'));
+    assert!(args.is_present('b'));
+    assert!(!args.is_present('c'));
+    assert_eq!(args.value('t'), Some("bar"));
+    assert_eq!(args.value('a'), None);
+    assert_eq!(args.parse_value::<usize>('n'), Ok(Some(5)));
+    assert_eq!(args.parse_value::<usize>('x'), Ok(None));
+    assert_eq!(args.operands(), &["x".to_owned(), "-c".to_owned()][..]);
+}
+
+#[tokio::test]
+async fn should_stop_parsing_options_at_double_dash_or_single_dash() {
+    let args = parse(&["-a", "--", "-b"], "ab").unwrap();
+    assert!(args.is_present('a'));
+    assert!(!args.is_present('b'));
+    assert_eq!(args.into_operands(), vec!["-b".to_owned()]);
+
+    let args = parse(&["-", "-a"], "ab").unwrap();
+    assert!(!args.is_present('a'));
+    assert_eq!(args.into_operands(), vec!["-".to_owned(), "-a".to_owned()]);
+}
+
+#[tokio::test]
+async fn should_track_which_exclusive_option_was_last() {
+    assert_eq!(
+        parse(&["-LPL"], "LP").unwrap().last_of(&['L', 'P']),
+        Some('L')
+    );
+    assert_eq!(
+        parse(&["-L", "-P"], "LP").unwrap().last_of(&['L', 'P']),
+        Some('P')
+    );
+    assert_eq!(parse(&[], "LP").unwrap().last_of(&['L', 'P']), None);
+}
+
+#[tokio::test]
+async fn should_report_usage_errors() {
+    assert_eq!(
+        parse(&["-x"], "ab").unwrap_err(),
+        UsageError::InvalidOption('x')
+    );
+    assert_eq!(
+        parse(&["-:"], "a:").unwrap_err(),
+        UsageError::InvalidOption(':')
+    );
+    assert_eq!(
+        parse(&["-at"], "at:").unwrap_err(),
+        UsageError::MissingValue('t')
+    );
+    assert_eq!(
+        parse(&["-n", "foo"], "n:")
+            .unwrap()
+            .parse_value::<usize>('n'),
+        Err(UsageError::InvalidValue('n', "foo".to_owned()))
+    );
+
+    let args = parse(&["a", "b"], "").unwrap();
+    assert_eq!(args.check_max_operands(2), Ok(()));
+    assert_eq!(args.check_max_operands(1), Err(UsageError::TooManyOperands));
+}
+
+#[tokio::test]
+async fn should_print_usage_to_stderr() {
+    let mut env = new_env_with_no_fds();
+
+    let pipe = env.open_pipe().expect("failed to open pipe");
+    env.set_file_desc(STDERR_FILENO, pipe.writer, Permissions::Write);
+    let stderr = env.read_all(pipe.reader);
+
+    let err = UsageError::InvalidOption('x');
+    let exit = report_usage_err("foo", "[-a] [name]", &mut env, err)
+        .await
+        .await;
+    drop(env);
+
+    assert_eq!(exit, EXIT_ERROR);
+    assert_eq!(
+        String::from_utf8(stderr.await.unwrap()).unwrap(),
+        "foo: -x: invalid option\nfoo: usage: foo [-a] [name]\n"
+    );
+}
@@ -0,0 +1,129 @@
+#![deny(rust_2018_idioms)]
+use conch_runtime::io::Permissions;
+
+mod support;
+pub use self::support::spawn::builtin::printf;
+pub use self::support::*;
+
+async fn run_printf(args: &[&str]) -> (String, ExitStatus) {
+    let mut env = new_env_with_no_fds();
+
+    let pipe = env.open_pipe().expect("pipe failed");
+    env.set_file_desc(
+        conch_runtime::STDOUT_FILENO,
+        pipe.writer,
+        Permissions::Write,
+    );
+
+    let args = args.iter().map(|&s| s.to_owned()).collect::<Vec<_>>();
+
+    let read_to_end = tokio::spawn(env.read_all(pipe.reader));
+    let exit = tokio::spawn(async move {
+        let future = printf(args, &mut env).await;
+        drop(env);
+        future.await
+    });
+
+    let (output, exit) = join(read_to_end, exit).await;
+    let output = String::from_utf8(output.unwrap().unwrap()).expect("invalid utf8");
+    (output, exit.unwrap())
+}
+
+async fn run_ok(args: &[&str]) -> String {
+    let (output, exit) = run_printf(args).await;
+    assert_eq!(exit, EXIT_SUCCESS);
+    output
+}
+
+#[tokio::test]
+async fn smoke() {
+    assert_eq!(run_ok(&["hello\\n"]).await, "hello\n");
+    assert_eq!(run_ok(&["%s-%s\\n", "foo", "bar"]).await, "foo-bar\n");
+    assert_eq!(run_ok(&["--", "-%s", "x"]).await, "-x");
+    assert_eq!(run_ok(&["100%%"]).await, "100%");
+}
+
+#[tokio::test]
+async fn conversions() {
+    assert_eq!(run_ok(&["%d %i", "42", "-7"]).await, "42 -7");
+    assert_eq!(run_ok(&["%u", "-1"]).await, "18446744073709551615");
+    assert_eq!(run_ok(&["%x %X %o", "255", "255", "8"]).await, "ff FF 10");
+    assert_eq!(run_ok(&["%#x %#o", "255", "8"]).await, "0xff 010");
+    assert_eq!(run_ok(&["%d %d %d", "0x1f", "017", "'A"]).await, "31 15 65");
+    assert_eq!(run_ok(&["%c%c", "hello", "world"]).await, "hw");
+    assert_eq!(run_ok(&["%b", "a\\tb\\0101"]).await, "a\tbA");
+    assert_eq!(run_ok(&["%q", "it's"]).await, "'it'\\''s'");
+}
+
+#[tokio::test]
+async fn width_precision_and_flags() {
+    assert_eq!(run_ok(&["[%5s]", "ab"]).await, "[   ab]");
+    assert_eq!(run_ok(&["[%-5s]", "ab"]).await, "[ab   ]");
+    assert_eq!(run_ok(&["[%.2s]", "abcd"]).await, "[ab]");
+    assert_eq!(run_ok(&["[%05d]", "-42"]).await, "[-0042]");
+    assert_eq!(run_ok(&["[%+d] [% d]", "5", "5"]).await, "[+5] [ 5]");
+    assert_eq!(run_ok(&["[%.3d]", "7"]).await, "[007]");
+    assert_eq!(run_ok(&["[%*s]", "4", "x"]).await, "[   x]");
+    assert_eq!(run_ok(&["[%-*.*s]", "4", "1", "xyz"]).await, "[x   ]");
+}
+
+#[tokio::test]
+async fn escapes() {
+    assert_eq!(run_ok(&["a\\tb\\\\c\\101\\n"]).await, "a\tb\\cA\n");
+    assert_eq!(run_ok(&["\\q\\"]).await, "\\q\\");
+    assert_eq!(run_ok(&["%s\\c%s", "a", "b"]).await, "a");
+    assert_eq!(run_ok(&["%b%s", "x\\cy", "z"]).await, "x");
+}
+
+#[tokio::test]
+async fn format_is_reused_until_arguments_are_consumed() {
+    assert_eq!(run_ok(&["%s\\n", "a", "b", "c"]).await, "a\nb\nc\n");
+    assert_eq!(run_ok(&["%s=%d;", "a", "1", "b"]).await, "a=1;b=0;");
+    assert_eq!(run_ok(&["none\\n", "a", "b"]).await, "none\n");
+    assert_eq!(run_ok(&["[%s|%d]"]).await, "[|0]");
+}
+
+#[tokio::test]
+async fn errors() {
+    let (output, exit) = run_printf(&["%d|%d", "12abc", "3"]).await;
+    assert_eq!(output, "0|3");
+    assert_eq!(exit, EXIT_ERROR);
+
+    let (output, exit) = run_printf(&["a%zb", "x"]).await;
+    assert_eq!(output, "a");
+    assert_eq!(exit, EXIT_ERROR);
+
+    let (output, exit) = run_printf(&["a%"]).await;
+    assert_eq!(output, "a");
+    assert_eq!(exit, EXIT_ERROR);
+
+    let (output, exit) = run_printf(&[]).await;
+    assert_eq!(output, "");
+    assert_eq!(exit, EXIT_ERROR);
+}
+
+#[tokio::test]
+async fn out_of_range_widths_and_precisions() {
+    let (output, exit) = run_printf(&["[%99999999999999999999s]", "x"]).await;
+    assert_eq!(output, "[x]");
+    assert_eq!(exit, EXIT_ERROR);
+
+    let (output, exit) = run_printf(&["[%.2147483648d]", "1"]).await;
+    assert_eq!(output, "[1]");
+    assert_eq!(exit, EXIT_ERROR);
+
+    let (output, exit) = run_printf(&["[%*d]", "9223372036854775807", "1"]).await;
+    assert_eq!(output, "[1]");
+    assert_eq!(exit, EXIT_ERROR);
+
+    let (output, exit) = run_printf(&["[%*s]", "-2147483648", "x"]).await;
+    assert_eq!(output, "[x]");
+    assert_eq!(exit, EXIT_ERROR);
+
+    let (output, exit) = run_printf(&["[%.*s]", "4294967296", "x"]).await;
+    assert_eq!(output, "[]");
+    assert_eq!(exit, EXIT_ERROR);
+
+    // Negative precisions are still taken as if they were omitted
+    assert_eq!(run_ok(&["[%.*s]", "-1", "xyz"]).await, "[xyz]");
+}
@@ -0,0 +1,240 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::io::Permissions;
+use conch_runtime::{ExitStatus, STDIN_FILENO};
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
+
+mod support;
+pub use self::support::spawn::builtin::read;
+pub use self::support::*;
+
+async fn run_read(input: Option<&[u8]>, args: &[&str]) -> (ExitStatus, DefaultEnvArc) {
+    let mut env = new_env_with_no_fds();
+
+    let pipe = env.open_pipe().expect("pipe failed");
+    env.set_file_desc(STDIN_FILENO, pipe.reader, Permissions::Read);
+
+    // Keep the writer open if there is no input so reads will block
+    let _writer = match input {
+        Some(input) => {
+            (&*pipe.writer).write_all(input).expect("write failed");
+            drop(pipe.writer);
+            None
+        }
+        None => Some(pipe.writer),
+    };
+
+    let args = args.iter().map(|&s| s.to_owned()).collect::<Vec<_>>();
+    let exit = read(args, &mut env).await.await;
+
+    (exit, env)
+}
+
+fn var<'a>(env: &'a DefaultEnvArc, name: &str) -> Option<&'a str> {
+    env.var(&name.to_owned()).map(|v| v.as_str())
+}
+
+#[tokio::test]
+async fn reads_line_into_reply_by_default() {
+    let (exit, env) = run_read(Some(b"foo bar\nbaz\n"), &[]).await;
+    assert_eq!(exit, EXIT_SUCCESS);
+    assert_eq!(var(&env, "REPLY"), Some("foo bar"));
+}
+
+#[tokio::test]
+async fn reads_line_into_named_var() {
+    let (exit, env) = run_read(Some(b"foo bar\nbaz\n"), &["var"]).await;
+    assert_eq!(exit, EXIT_SUCCESS);
+    assert_eq!(var(&env, "var"), Some("foo bar"));
+    assert_eq!(var(&env, "REPLY"), None);
+}
+
+#[tokio::test]
+async fn does_not_consume_input_past_end_of_line() {
+    let mut env = new_env_with_no_fds();
+
+    let pipe = env.open_pipe().expect("pipe failed");
+    env.set_file_desc(STDIN_FILENO, pipe.reader, Permissions::Read);
+    (&*pipe.writer)
+        .write_all(b"first\nsecond\n")
+        .expect("write failed");
+    drop(pipe.writer);
+
+    let exit = read(vec!["a".to_owned()], &mut env).await.await;
+    assert_eq!(exit, EXIT_SUCCESS);
+    let exit = read(vec!["b".to_owned()], &mut env).await.await;
+    assert_eq!(exit, EXIT_SUCCESS);
+
+    assert_eq!(var(&env, "a"), Some("first"));
+    assert_eq!(var(&env, "b"), Some("second"));
+}
+
+#[tokio::test]
+async fn eof_before_newline_assigns_partial_input_and_fails() {
+    let (exit, env) = run_read(Some(b"foo"), &["var"]).await;
+    assert_eq!(exit, EXIT_ERROR);
+    assert_eq!(var(&env, "var"), Some("foo"));
+}
+
+#[tokio::test]
+async fn nchars_stops_after_reading_enough_chars() {
+    let (exit, env) = run_read(Some("h\u{e9}llo\n".as_bytes()), &["-n", "3", "var"]).await;
+    assert_eq!(exit, EXIT_SUCCESS);
+    assert_eq!(var(&env, "var"), Some("h\u{e9}l"));
+}
+
+#[tokio::test]
+async fn nchars_stops_at_newline() {
+    let (exit, env) = run_read(Some(b"ab\ncd\n"), &["-n", "5", "var"]).await;
+    assert_eq!(exit, EXIT_SUCCESS);
+    assert_eq!(var(&env, "var"), Some("ab"));
+}
+
+#[tokio::test]
+async fn timeout_exits_with_status_greater_than_128() {
+    let (exit, env) = run_read(None, &["-t", "0.1", "var"]).await;
+    match exit {
+        ExitStatus::Code(code) => assert!(code > 128),
+        ExitStatus::Signal(_) => panic!("unexpected exit: {:?}", exit),
+    }
+    assert_eq!(var(&env, "var"), Some(""));
+}
+
+#[tokio::test]
+async fn timeout_does_not_fire_if_input_available() {
+    let (exit, env) = run_read(Some(b"foo\n"), &["-t", "5", "var"]).await;
+    assert_eq!(exit, EXIT_SUCCESS);
+    assert_eq!(var(&env, "var"), Some("foo"));
+}
+
+#[tokio::test]
+async fn timeout_retains_partial_input() {
+    let mut env = new_env_with_no_fds();
+
+    let pipe = env.open_pipe().expect("pipe failed");
+    env.set_file_desc(STDIN_FILENO, pipe.reader, Permissions::Read);
+    let writer = pipe.writer;
+
+    let write = tokio::spawn(async move {
+        (&*writer).write_all(b"par").expect("write failed");
+        tokio::time::delay_for(Duration::from_secs(1)).await;
+        drop(writer);
+    });
+
+    let args = vec!["-t".to_owned(), "0.2".to_owned(), "var".to_owned()];
+    let exit = read(args, &mut env).await.await;
+    write.await.unwrap();
+
+    assert_ne!(exit, EXIT_SUCCESS);
+    assert_ne!(exit, EXIT_ERROR);
+    assert_eq!(var(&env, "var"), Some("par"));
+}
+
+#[tokio::test]
+async fn invalid_args_are_reported() {
+    for args in &[
+        &["-t", "foo"][..],
+        &["-t", "-1"],
+        &["-n", "bar"],
+        &["-d", "\u{e9}"],
+    ] {
+        let mut env = new_env_with_no_fds();
+        let exit = read(args.iter().map(|&s| Arc::new(s.to_owned())), &mut env)
+            .await
+            .await;
+        assert_eq!(exit, EXIT_ERROR);
+    }
+}
+
+#[tokio::test]
+async fn closed_stdin_is_an_error() {
+    let mut env = new_env_with_no_fds();
+    let exit = read(Vec::<String>::new(), &mut env).await.await;
+    assert_eq!(exit, EXIT_ERROR);
+}
+
+#[tokio::test]
+async fn custom_delimiter() {
+    let (exit, env) = run_read(Some(b"foo\nbar:baz"), &["-d", ":", "var"]).await;
+    assert_eq!(exit, EXIT_SUCCESS);
+    assert_eq!(var(&env, "var"), Some("foo\nbar"));
+
+    let (exit, env) = run_read(Some(b"foo\nbar\0baz"), &["-d", "", "var"]).await;
+    assert_eq!(exit, EXIT_SUCCESS);
+    assert_eq!(var(&env, "var"), Some("foo\nbar"));
+
+    // Only the first character of the delimiter is used
+    let (exit, env) = run_read(Some(b"foo:bar;baz"), &["-d", ";:", "var"]).await;
+    assert_eq!(exit, EXIT_SUCCESS);
+    assert_eq!(var(&env, "var"), Some("foo:bar"));
+}
+
+#[tokio::test]
+async fn multi_byte_delimiter_is_an_error() {
+    let (exit, env) = run_read(Some("foo\u{e9}bar".as_bytes()), &["-d", "\u{e9}", "var"]).await;
+    assert_eq!(exit, EXIT_ERROR);
+    assert_eq!(var(&env, "var"), None);
+}
+
+#[tokio::test]
+async fn splits_fields_across_names_via_ifs() {
+    let (exit, env) = run_read(Some(b"  foo   bar  baz qux  \n"), &["a", "b", "c"]).await;
+    assert_eq!(exit, EXIT_SUCCESS);
+    assert_eq!(var(&env, "a"), Some("foo"));
+    assert_eq!(var(&env, "b"), Some("bar"));
+    assert_eq!(var(&env, "c"), Some("baz qux"));
+
+    let (exit, env) = run_read(Some(b"foo\n"), &["a", "b", "c"]).await;
+    assert_eq!(exit, EXIT_SUCCESS);
+    assert_eq!(var(&env, "a"), Some("foo"));
+    assert_eq!(var(&env, "b"), Some(""));
+    assert_eq!(var(&env, "c"), Some(""));
+
+    // Only a single name strips surrounding whitespace, $REPLY does not
+    let (_, env) = run_read(Some(b"  foo bar  \n"), &["a"]).await;
+    assert_eq!(var(&env, "a"), Some("foo bar"));
+    let (_, env) = run_read(Some(b"  foo bar  \n"), &[]).await;
+    assert_eq!(var(&env, "REPLY"), Some("  foo bar  "));
+}
+
+#[tokio::test]
+async fn non_whitespace_ifs_delimits_empty_fields() {
+    let mut env = new_env_with_no_fds();
+    env.set_var(Arc::new("IFS".to_owned()), Arc::new(": ".to_owned()));
+
+    let pipe = env.open_pipe().expect("pipe failed");
+    env.set_file_desc(STDIN_FILENO, pipe.reader, Permissions::Read);
+    (&*pipe.writer)
+        .write_all(b"a::b : c:d e\n")
+        .expect("write failed");
+    drop(pipe.writer);
+
+    let args = vec!["w", "x", "y", "z"];
+    let exit = read(args.into_iter().map(String::from), &mut env)
+        .await
+        .await;
+    assert_eq!(exit, EXIT_SUCCESS);
+    assert_eq!(var(&env, "w"), Some("a"));
+    assert_eq!(var(&env, "x"), Some(""));
+    assert_eq!(var(&env, "y"), Some("b"));
+    assert_eq!(var(&env, "z"), Some("c:d e"));
+}
+
+#[tokio::test]
+async fn backslashes_escape_unless_raw() {
+    let input = b"foo\\ bar baz\\\\ qux\\\nnext line\n";
+
+    let (exit, env) = run_read(Some(input), &["a", "b", "c"]).await;
+    assert_eq!(exit, EXIT_SUCCESS);
+    assert_eq!(var(&env, "a"), Some("foo bar"));
+    assert_eq!(var(&env, "b"), Some("baz\\"));
+    assert_eq!(var(&env, "c"), Some("quxnext line"));
+
+    let (exit, env) = run_read(Some(input), &["-r", "a", "b", "c"]).await;
+    assert_eq!(exit, EXIT_SUCCESS);
+    assert_eq!(var(&env, "a"), Some("foo\\"));
+    assert_eq!(var(&env, "b"), Some("bar"));
+    assert_eq!(var(&env, "c"), Some("baz\\\\ qux\\"));
+}
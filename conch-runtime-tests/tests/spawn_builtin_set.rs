@@ -0,0 +1,328 @@
+#![deny(rust_2018_idioms)]
+
+use std::fs;
+use std::sync::Arc;
+
+#[macro_use]
+mod support;
+pub use self::support::spawn::builtin::set;
+pub use self::support::*;
+
+fn args(env: &DefaultEnvArc) -> Vec<String> {
+    env.args().iter().map(|arg| (**arg).clone()).collect()
+}
+
+fn errexit() -> Result<ExitStatus, RuntimeError> {
+    Err(RuntimeError::Command(CommandError::ErrExit(EXIT_ERROR)))
+}
+
+async fn set_with_args(env: &mut DefaultEnvArc, args: &[&str]) -> ExitStatus {
+    let args = args.iter().map(|&s| s.to_owned()).collect::<Vec<_>>();
+    set(args, env).await.await
+}
+
+#[tokio::test]
+async fn enable_and_disable_options() {
+    let mut env = new_env_with_no_fds();
+
+    assert_eq!(
+        set_with_args(&mut env, &["-eu", "-o", "pipefail"]).await,
+        EXIT_SUCCESS
+    );
+    assert!(env.options().errexit);
+    assert!(env.options().nounset);
+    assert!(env.options().pipefail);
+    assert!(!env.options().xtrace);
+
+    assert_eq!(
+        set_with_args(&mut env, &["+e", "+o", "pipefail", "-x"]).await,
+        EXIT_SUCCESS
+    );
+    assert!(!env.options().errexit);
+    assert!(env.options().nounset);
+    assert!(!env.options().pipefail);
+    assert!(env.options().xtrace);
+
+    // A lone `-` only disables xtrace
+    assert_eq!(set_with_args(&mut env, &["-"]).await, EXIT_SUCCESS);
+    assert!(env.options().nounset);
+    assert!(!env.options().xtrace);
+}
+
+#[tokio::test]
+async fn operands_set_positional_parameters() {
+    let mut env = new_env_with_no_fds();
+
+    assert_eq!(
+        set_with_args(&mut env, &["a", "-b", "c"]).await,
+        EXIT_SUCCESS
+    );
+    assert_eq!(args(&env), vec!["a", "-b", "c"]);
+
+    assert_eq!(set_with_args(&mut env, &["-u", "x"]).await, EXIT_SUCCESS);
+    assert!(env.options().nounset);
+    assert_eq!(args(&env), vec!["x"]);
+
+    // Options alone leave the parameters intact
+    assert_eq!(set_with_args(&mut env, &["+u"]).await, EXIT_SUCCESS);
+    assert_eq!(args(&env), vec!["x"]);
+
+    assert_eq!(set_with_args(&mut env, &["--", "-e"]).await, EXIT_SUCCESS);
+    assert!(!env.options().errexit);
+    assert_eq!(args(&env), vec!["-e"]);
+
+    assert_eq!(set_with_args(&mut env, &["--"]).await, EXIT_SUCCESS);
+    assert!(args(&env).is_empty());
+}
+
+#[tokio::test]
+async fn invalid_options_change_nothing() {
+    let mut env = new_env_with_no_fds();
+
+    assert_eq!(
+        set_with_args(&mut env, &["-e", "-k", "a"]).await,
+        EXIT_ERROR
+    );
+    assert_eq!(
+        set_with_args(&mut env, &["-e", "-o", "bogus", "a"]).await,
+        EXIT_ERROR
+    );
+    assert_eq!(*env.options(), ShellOptions::default());
+    assert!(args(&env).is_empty());
+}
+
+#[tokio::test]
+async fn print_options() {
+    let tempdir = mktmp!();
+    let mut env = new_env_with_no_fds();
+    env.change_working_dir(tempdir.path().into()).unwrap();
+    let mut session = Session::new(env);
+
+    run(
+        &mut session,
+        "set -u -o pipefail; set -o > human; set +o > reinput\n",
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        fs::read_to_string(tempdir.path().join("human")).unwrap(),
+        concat!(
+            "allexport       off\n",
+            "errexit         off\n",
+            "nounset         on\n",
+            "pipefail        on\n",
+            "xtrace          off\n",
+        )
+    );
+    assert_eq!(
+        fs::read_to_string(tempdir.path().join("reinput")).unwrap(),
+        concat!(
+            "set +o allexport\n",
+            "set +o errexit\n",
+            "set -o nounset\n",
+            "set -o pipefail\n",
+            "set +o xtrace\n",
+        )
+    );
+
+    run(&mut session, "flags=$-\n").await.unwrap();
+    assert_eq!(var(&session, "flags"), Some("u".to_owned()));
+}
+
+#[tokio::test]
+async fn errexit_aborts_on_failed_commands() {
+    let mut session = Session::new(new_env_with_no_fds());
+    run(&mut session, "set -e\n").await.unwrap();
+
+    assert_eq!(run(&mut session, "false; ran=yes\n").await, errexit());
+    assert_eq!(
+        run(&mut session, "true && false; ran=yes\n").await,
+        errexit()
+    );
+    assert_eq!(
+        run(&mut session, "false | false; ran=yes\n").await,
+        errexit()
+    );
+    assert_eq!(run(&mut session, "(false); ran=yes\n").await, errexit());
+    assert_eq!(
+        run(&mut session, "f() { false; ran=yes; }; f; ran=yes\n").await,
+        errexit()
+    );
+    assert_eq!(
+        run(&mut session, "if true; then false; fi; ran=yes\n").await,
+        errexit()
+    );
+    assert_eq!(var(&session, "ran"), None);
+
+    // The option remains set until it is explicitly disabled
+    assert!(session.env().options().errexit);
+    run(&mut session, "set +e; false; ran=yes\n").await.unwrap();
+    assert_eq!(var(&session, "ran"), Some("yes".to_owned()));
+}
+
+#[tokio::test]
+async fn errexit_is_ignored_in_conditions() {
+    let mut session = Session::new(new_env_with_no_fds());
+
+    let script = concat!(
+        "set -e\n",
+        "if false; then :; elif false; then :; fi\n",
+        "while false; do :; done\n",
+        "until true; do :; done\n",
+        "false && true\n",
+        "false || true\n",
+        "! true\n",
+        "if ! false; then :; fi\n",
+        "f() { false; true; }; if f; then :; fi\n",
+        "false &\n",
+        "ran=yes\n",
+    );
+
+    assert_eq!(run(&mut session, script).await, Ok(EXIT_SUCCESS));
+    assert_eq!(var(&session, "ran"), Some("yes".to_owned()));
+    // Conditions do not permanently disable the option
+    assert!(session.env().options().errexit);
+}
+
+#[tokio::test]
+async fn errexit_applies_within_background_jobs() {
+    let mut session = Session::new(new_env_with_no_fds());
+
+    let script = concat!(
+        "out=$(set -e; { false; echo after; } & wait; echo done)\n",
+        "set -e\n",
+        "{ false; ran=yes; } & wait $! || status=$?\n",
+    );
+
+    assert_eq!(run(&mut session, script).await, Ok(EXIT_SUCCESS));
+    assert_eq!(var(&session, "out"), Some("done".to_owned()));
+    assert_eq!(var(&session, "status"), Some("1".to_owned()));
+}
+
+#[tokio::test]
+async fn errexit_changes_within_conditions_are_retained() {
+    let mut session = Session::new(new_env_with_no_fds());
+    assert_eq!(
+        run(&mut session, "if set -e; then :; fi; false; ran=yes\n").await,
+        errexit()
+    );
+    assert_eq!(var(&session, "ran"), None);
+
+    let mut session = Session::new(new_env_with_no_fds());
+    assert_eq!(
+        run(
+            &mut session,
+            "set -e; while set +e; do break; done; false; ran=yes\n"
+        )
+        .await,
+        Ok(EXIT_SUCCESS)
+    );
+    assert_eq!(var(&session, "ran"), Some("yes".to_owned()));
+
+    let mut session = Session::new(new_env_with_no_fds());
+    assert_eq!(
+        run(&mut session, "set -e; set +e || :; false; ran=yes\n").await,
+        Ok(EXIT_SUCCESS)
+    );
+    assert_eq!(var(&session, "ran"), Some("yes".to_owned()));
+
+    let mut session = Session::new(new_env_with_no_fds());
+    assert_eq!(
+        run(&mut session, "! set -e; false; ran=yes\n").await,
+        errexit()
+    );
+    assert_eq!(var(&session, "ran"), None);
+}
+
+#[tokio::test]
+async fn errexit_is_reported_as_set_within_conditions() {
+    let mut session = Session::new(new_env_with_no_fds());
+
+    let script = concat!(
+        "set -e\n",
+        "if flags=$-; then :; fi\n",
+        "true && inner=$-\n",
+    );
+
+    assert_eq!(run(&mut session, script).await, Ok(EXIT_SUCCESS));
+    assert_eq!(var(&session, "flags"), Some("e".to_owned()));
+    assert_eq!(var(&session, "inner"), Some("e".to_owned()));
+}
+
+#[tokio::test]
+async fn pipefail_reports_last_failed_command() {
+    let mut session = Session::new(new_env_with_no_fds());
+
+    let script = concat!(
+        "false | true; a=$?\n",
+        "set -o pipefail\n",
+        "false | true; b=$?\n",
+        "true | true; c=$?\n",
+        "! false | true; d=$?\n",
+    );
+
+    run(&mut session, script).await.unwrap();
+    assert_eq!(var(&session, "a"), Some("0".to_owned()));
+    assert_eq!(var(&session, "b"), Some("1".to_owned()));
+    assert_eq!(var(&session, "c"), Some("0".to_owned()));
+    assert_eq!(var(&session, "d"), Some("0".to_owned()));
+}
+
+#[tokio::test]
+async fn nounset_rejects_unset_parameters() {
+    let mut session = Session::new(new_env_with_no_fds());
+
+    let script = concat!(
+        "set -u\n",
+        "empty=\n",
+        "a=\"$empty\" b=${unset:-default} c=${unset+alt} d=\"$@$*\"\n",
+    );
+    run(&mut session, script).await.unwrap();
+    assert_eq!(var(&session, "a"), Some("".to_owned()));
+    assert_eq!(var(&session, "b"), Some("default".to_owned()));
+    assert_eq!(var(&session, "c"), Some("".to_owned()));
+    assert_eq!(var(&session, "d"), Some("".to_owned()));
+
+    for script in &["x=$unset\n", "x=${#unset}\n", "x=${unset%.txt}\n", "x=$1\n"] {
+        match run(&mut session, script).await {
+            Err(RuntimeError::Expansion(ExpansionError::UnsetParameter(_))) => {}
+            result => panic!("unexpected result for {:?}: {:?}", script, result),
+        }
+        assert_eq!(var(&session, "x"), None);
+    }
+}
+
+#[tokio::test]
+async fn allexport_exports_assigned_variables() {
+    let mut session = Session::new(new_env_with_no_fds());
+    let exported = |session: &Session<DefaultEnvArc>, name: &str| {
+        session
+            .env()
+            .exported_var(&Arc::new(name.to_owned()))
+            .map(|(_, exported)| exported)
+    };
+
+    run(
+        &mut session,
+        concat!(
+            "before=1; existing=1\n",
+            "set -a; after=1; for loop in x; do :; done; : $((arith = 5))\n",
+            "existing=2 :\n",
+            "set +a; later=1\n",
+        ),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(exported(&session, "before"), Some(false));
+    assert_eq!(exported(&session, "after"), Some(true));
+    assert_eq!(exported(&session, "loop"), Some(true));
+    assert_eq!(exported(&session, "arith"), Some(true));
+    assert_eq!(var(&session, "arith"), Some("5".to_owned()));
+    assert_eq!(exported(&session, "later"), Some(false));
+
+    // NB: temporary assignments restore the previous exported status
+    assert_eq!(var(&session, "existing"), Some("1".to_owned()));
+    assert_eq!(exported(&session, "existing"), Some(false));
+}
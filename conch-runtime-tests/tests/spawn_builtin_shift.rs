@@ -1,5 +1,7 @@
 #![deny(rust_2018_idioms)]
 
+use conch_runtime::io::Permissions;
+use conch_runtime::STDERR_FILENO;
 use std::sync::Arc;
 
 mod support;
@@ -69,3 +71,22 @@ async fn shift_multiple_arg_does_nothing_and_exit_with_error() {
     let args = &["a", "b"];
     run_shift(args, &["1", "2"], args, EXIT_ERROR).await;
 }
+
+#[tokio::test]
+async fn shift_large_arg_reports_error() {
+    let mut env = new_env_with_no_fds();
+    env.set_args(Arc::new(vec![Arc::new("a".to_owned())].into()));
+
+    let pipe = env.open_pipe().expect("failed to open pipe");
+    env.set_file_desc(STDERR_FILENO, pipe.writer, Permissions::Write);
+    let stderr = env.read_all(pipe.reader);
+
+    let exit = shift(vec!["2".to_owned()], &mut env).await.await;
+    drop(env);
+
+    assert_eq!(exit, EXIT_ERROR);
+    assert_eq!(
+        String::from_utf8(stderr.await.unwrap()).unwrap(),
+        "shift: 2: shift count out of range\n"
+    );
+}
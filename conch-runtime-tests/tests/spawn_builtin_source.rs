@@ -0,0 +1,172 @@
+#![deny(rust_2018_idioms)]
+
+use futures_core::future::BoxFuture;
+use std::fs;
+use std::path::Path;
+
+#[macro_use]
+mod support;
+pub use self::support::*;
+
+async fn run_in_dir(dir: &Path, script: &str) -> (Session<DefaultEnvArc>, Option<ControlFlow>) {
+    run_with_env(new_env_with_no_fds(), dir, script).await
+}
+
+async fn run_with_env(
+    mut env: DefaultEnvArc,
+    dir: &Path,
+    script: &str,
+) -> (Session<DefaultEnvArc>, Option<ControlFlow>) {
+    env.set_var(name("DIR"), name(dir.to_str().unwrap()));
+
+    let mut session = Session::new(env);
+    let flow = match run(&mut session, script).await {
+        Ok(_) => None,
+        Err(e) => Some(control_flow_for_error(&e).expect("unexpected error")),
+    };
+
+    (session, flow)
+}
+
+#[tokio::test]
+async fn should_run_file_in_current_environment() {
+    let tempdir = mktmp!();
+    fs::write(
+        tempdir.path().join("lib.sh"),
+        "x=set; greet() { greeting=\"hello $1\"; }; args=\"$#:$*\"\n",
+    )
+    .unwrap();
+
+    let (session, flow) = run_in_dir(
+        tempdir.path(),
+        concat!(
+            "set -- outer\n",
+            ". $DIR/lib.sh; status=$?; greet world\n",
+            "source $DIR/lib.sh a b; after=\"$#:$*\"\n",
+        ),
+    )
+    .await;
+
+    assert_eq!(flow, None);
+    assert_eq!(var(&session, "x"), Some("set".to_owned()));
+    assert_eq!(var(&session, "status"), Some("0".to_owned()));
+    assert_eq!(var(&session, "greeting"), Some("hello world".to_owned()));
+    assert_eq!(var(&session, "args"), Some("2:a b".to_owned()));
+    assert_eq!(var(&session, "after"), Some("1:outer".to_owned()));
+}
+
+#[tokio::test]
+async fn should_search_path_for_file() {
+    let tempdir = mktmp!();
+    fs::write(tempdir.path().join("lib.sh"), "x=found\n").unwrap();
+
+    let (session, _) = run_in_dir(
+        tempdir.path(),
+        concat!(
+            "PATH=$DIR; . lib.sh\n",
+            "PATH=/nonexistent; . lib.sh; missing_status=$?\n",
+            ". $DIR/missing.sh; unreadable_status=$?\n",
+        ),
+    )
+    .await;
+
+    assert_eq!(var(&session, "x"), Some("found".to_owned()));
+    assert_eq!(var(&session, "missing_status"), Some("1".to_owned()));
+    assert_eq!(var(&session, "unreadable_status"), Some("1".to_owned()));
+}
+
+#[tokio::test]
+async fn should_return_from_file_and_propagate_exit() {
+    let tempdir = mktmp!();
+    fs::write(
+        tempdir.path().join("ret.sh"),
+        "x=before; return 4; x=after\n",
+    )
+    .unwrap();
+    fs::write(tempdir.path().join("exit.sh"), "exit 6\n").unwrap();
+
+    let (session, flow) = run_in_dir(
+        tempdir.path(),
+        concat!(". $DIR/ret.sh; status=$?\n", ". $DIR/exit.sh; y=after\n",),
+    )
+    .await;
+
+    assert_eq!(flow, Some(ControlFlow::Exit(ExitStatus::Code(6))));
+    assert_eq!(var(&session, "x"), Some("before".to_owned()));
+    assert_eq!(var(&session, "status"), Some("4".to_owned()));
+    assert_eq!(var(&session, "y"), None);
+}
+
+#[tokio::test]
+async fn should_run_file_with_installed_script_runner() {
+    fn uppercase<'a>(
+        source: &'a str,
+        env: &'a mut DefaultEnvArc,
+    ) -> BoxFuture<'a, Result<ExitStatus, ControlFlow>> {
+        Box::pin(async move {
+            env.set_var(name("sourced"), name(&source.trim().to_uppercase()));
+            Ok(ExitStatus::Code(3))
+        })
+    }
+
+    let tempdir = mktmp!();
+    fs::write(tempdir.path().join("lib.sh"), "not a script\n").unwrap();
+
+    let mut env = new_env_with_no_fds();
+    env.set_script_runner(ScriptRunner::from_fn(uppercase));
+
+    let (session, flow) = run_with_env(env, tempdir.path(), ". $DIR/lib.sh; status=$?\n").await;
+
+    assert_eq!(flow, None);
+    assert_eq!(var(&session, "sourced"), Some("NOT A SCRIPT".to_owned()));
+    assert_eq!(var(&session, "status"), Some("3".to_owned()));
+}
+
+#[tokio::test]
+async fn should_resolve_nested_files_relative_to_including_file() {
+    let tempdir = mktmp!();
+    let lib = tempdir.path().join("lib");
+    fs::create_dir_all(lib.join("util")).unwrap();
+    fs::write(
+        lib.join("main.sh"),
+        concat!(
+            "outer=$BASH_SOURCE\n",
+            ". util/helper.sh\n",
+            ". ./sibling.sh\n",
+            "after=$BASH_SOURCE\n",
+        ),
+    )
+    .unwrap();
+    fs::write(lib.join("sibling.sh"), "sibling=$BASH_SOURCE\n").unwrap();
+    fs::write(lib.join("util/helper.sh"), ". helper2.sh\n").unwrap();
+    fs::write(lib.join("util/helper2.sh"), "inner=$BASH_SOURCE\n").unwrap();
+
+    // NB: the working directory is not where the scripts live
+    let (session, flow) = run_in_dir(
+        tempdir.path(),
+        "BASH_SOURCE=orig; PATH=/nonexistent; . $DIR/lib/main.sh\n",
+    )
+    .await;
+
+    let path = |p: &Path| Some(p.to_str().unwrap().to_owned());
+
+    assert_eq!(flow, None);
+    assert_eq!(var(&session, "outer"), path(&lib.join("main.sh")));
+    assert_eq!(var(&session, "inner"), path(&lib.join("util/helper2.sh")));
+    assert_eq!(var(&session, "sibling"), path(&lib.join("./sibling.sh")));
+    assert_eq!(var(&session, "after"), path(&lib.join("main.sh")));
+    assert_eq!(var(&session, "BASH_SOURCE"), Some("orig".to_owned()));
+    assert!(session.env().source_stack().is_empty());
+}
+
+#[tokio::test]
+async fn should_unset_bash_source_after_sourcing() {
+    let tempdir = mktmp!();
+    fs::write(tempdir.path().join("lib.sh"), "return 2\n").unwrap();
+
+    let (session, flow) = run_in_dir(tempdir.path(), ". $DIR/lib.sh\n").await;
+
+    assert_eq!(flow, None);
+    assert_eq!(var(&session, "BASH_SOURCE"), None);
+    assert!(session.env().source_stack().is_empty());
+}
@@ -0,0 +1,148 @@
+#![deny(rust_2018_idioms)]
+#![cfg(unix)]
+
+use std::borrow::Cow;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::UnixListener;
+use std::path::Path;
+
+#[macro_use]
+mod support;
+pub use self::support::*;
+
+async fn status(script: &str) -> String {
+    status_in(&std::env::current_dir().unwrap(), script).await
+}
+
+/// Runs `script` within `dir`, and returns the status of its last command.
+async fn status_in(dir: &Path, script: &str) -> String {
+    let mut env = new_env_with_no_fds();
+    env.change_working_dir(Cow::Borrowed(dir)).unwrap();
+
+    let mut session = Session::new(env);
+    run(&mut session, &format!("{}; status=$?\n", script))
+        .await
+        .unwrap();
+    var(&session, "status").unwrap()
+}
+
+#[tokio::test]
+async fn should_compare_strings() {
+    assert_eq!(status("test foo = foo").await, "0");
+    assert_eq!(status("test foo = bar").await, "1");
+    assert_eq!(status("test foo != bar").await, "0");
+    assert_eq!(status("[ -n foo ]").await, "0");
+    assert_eq!(status("[ -n '' ]").await, "1");
+    assert_eq!(status("[ -z '' ]").await, "0");
+    assert_eq!(status("[ foo ]").await, "0");
+    assert_eq!(status("[ '' ]").await, "1");
+    assert_eq!(status("[ ]").await, "1");
+}
+
+#[tokio::test]
+async fn should_compare_integers() {
+    assert_eq!(status("test 5 -eq 5").await, "0");
+    assert_eq!(status("test 5 -ne 5").await, "1");
+    assert_eq!(status("test -3 -lt 2").await, "0");
+    assert_eq!(status("test 2 -le 2").await, "0");
+    assert_eq!(status("test 2 -gt 3").await, "1");
+    assert_eq!(status("test ' 3 ' -ge 3").await, "0");
+    assert_eq!(status("test foo -eq 3").await, "2");
+}
+
+#[tokio::test]
+async fn should_disambiguate_by_number_of_arguments() {
+    assert_eq!(status("test -n").await, "0");
+    assert_eq!(status("test !").await, "0");
+    assert_eq!(status("test ! ''").await, "0");
+    assert_eq!(status("test ! -z foo").await, "0");
+    assert_eq!(status("test '(' foo ')'").await, "0");
+    assert_eq!(status("test = = =").await, "0");
+    assert_eq!(status("test ! foo = foo").await, "1");
+    assert_eq!(status("test '(' -z foo ')'").await, "1");
+    assert_eq!(status("test foo bar").await, "2");
+    assert_eq!(status("test foo bar baz").await, "2");
+}
+
+#[tokio::test]
+async fn should_evaluate_full_grammar() {
+    assert_eq!(status("test foo -a '' -o bar").await, "0");
+    assert_eq!(status("test foo -a '(' '' -o '' ')'").await, "1");
+    assert_eq!(status("test ! foo = bar -a 1 -lt 2").await, "0");
+    assert_eq!(status("test ! '(' foo = foo -o x = y ')'").await, "1");
+    assert_eq!(status("test -n foo -a -z ''").await, "0");
+    assert_eq!(status("test '(' foo = foo").await, "2");
+    assert_eq!(status("test foo = foo bar baz").await, "2");
+}
+
+#[tokio::test]
+async fn should_require_closing_bracket() {
+    assert_eq!(status("[ foo = foo").await, "2");
+    assert_eq!(status("[ foo = foo ] ]").await, "2");
+}
+
+#[tokio::test]
+async fn should_test_files_relative_to_working_dir() {
+    let tempdir = mktmp!();
+    let dir = tempdir.path();
+
+    fs::write(dir.join("empty"), "").unwrap();
+    fs::write(dir.join("full"), "data").unwrap();
+    fs::write(dir.join("script"), "#!/bin/sh\n").unwrap();
+    fs::set_permissions(dir.join("script"), fs::Permissions::from_mode(0o755)).unwrap();
+    fs::create_dir(dir.join("sub")).unwrap();
+    std::os::unix::fs::symlink(dir.join("full"), dir.join("link")).unwrap();
+
+    assert_eq!(status_in(dir, "test -e empty").await, "0");
+    assert_eq!(status_in(dir, "test -e missing").await, "1");
+    assert_eq!(status_in(dir, "test -f full").await, "0");
+    assert_eq!(status_in(dir, "test -f sub").await, "1");
+    assert_eq!(status_in(dir, "test -d sub").await, "0");
+    assert_eq!(status_in(dir, "test -d full").await, "1");
+    assert_eq!(status_in(dir, "test -s full").await, "0");
+    assert_eq!(status_in(dir, "test -s empty").await, "1");
+    assert_eq!(status_in(dir, "test -h link").await, "0");
+    assert_eq!(status_in(dir, "test -L full").await, "1");
+    assert_eq!(status_in(dir, "test -r full").await, "0");
+    assert_eq!(status_in(dir, "test -w full").await, "0");
+    assert_eq!(status_in(dir, "test -x script").await, "0");
+    assert_eq!(status_in(dir, "test -x full").await, "1");
+    assert_eq!(status_in(dir, "test -e ''").await, "1");
+}
+
+#[tokio::test]
+async fn should_test_file_types_and_mode_bits() {
+    let tempdir = mktmp!();
+    let dir = tempdir.path();
+
+    fs::write(dir.join("plain"), "").unwrap();
+    fs::write(dir.join("setuid"), "").unwrap();
+    fs::set_permissions(dir.join("setuid"), fs::Permissions::from_mode(0o4755)).unwrap();
+    fs::write(dir.join("setgid"), "").unwrap();
+    fs::set_permissions(dir.join("setgid"), fs::Permissions::from_mode(0o2755)).unwrap();
+    let _listener = UnixListener::bind(dir.join("socket")).unwrap();
+
+    assert_eq!(status_in(dir, "mkfifo fifo && test -p fifo").await, "0");
+    assert_eq!(status_in(dir, "test -p plain").await, "1");
+    assert_eq!(status_in(dir, "test -S socket").await, "0");
+    assert_eq!(status_in(dir, "test -S plain").await, "1");
+    assert_eq!(status_in(dir, "test -c /dev/null").await, "0");
+    assert_eq!(status_in(dir, "test -c plain").await, "1");
+    assert_eq!(status_in(dir, "test -b /dev/null").await, "1");
+    assert_eq!(status_in(dir, "test -b missing").await, "1");
+    assert_eq!(status_in(dir, "test -u setuid").await, "0");
+    assert_eq!(status_in(dir, "test -u setgid").await, "1");
+    assert_eq!(status_in(dir, "test -g setgid").await, "0");
+    assert_eq!(status_in(dir, "test -g plain").await, "1");
+}
+
+#[tokio::test]
+async fn should_test_if_fds_are_terminals() {
+    // NB: the environment has no file descriptors open
+    assert_eq!(status("[ -t 0 ]").await, "1");
+    assert_eq!(status("exec 3</dev/null; [ -t 3 ]").await, "1");
+    assert_eq!(status("[ -t 99999 ]").await, "1");
+    assert_eq!(status("[ -t foo ]").await, "2");
+    assert_eq!(status("[ -t ]").await, "0");
+}
@@ -0,0 +1,132 @@
+#![deny(rust_2018_idioms)]
+#![cfg(unix)]
+
+use conch_runtime::easy::Shell;
+use conch_runtime::env::{
+    Clock, DefaultEnvArc, DefaultEnvConfigArc, ManualClock, NextSpawn, SpawnContext,
+    SpawnMiddleware, SpawnMiddlewareEnvironment,
+};
+use conch_runtime::{ExitStatus, EXIT_ERROR, EXIT_SUCCESS};
+use futures_util::future::BoxFuture;
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+const BUSY_LOOP: &str = "sh -c 'i=0; while [ $i -lt 20000 ]; do i=$((i+1)); done'";
+
+fn parse_times(output: &str) -> Vec<(&str, Duration)> {
+    output
+        .lines()
+        .map(|line| {
+            let mut parts = line.splitn(2, ' ');
+            let name = parts.next().unwrap();
+            let secs = parts.next().expect("missing time").parse::<f64>().unwrap();
+            (name, Duration::from_secs_f64(secs))
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn should_report_times_of_command_to_shell_stderr() {
+    let mut shell = Shell::new().unwrap();
+    let stdout = shell.stdout_capture();
+
+    let script = format!("{{ time -p {} 2>/dev/null; }} 2>&1", BUSY_LOOP);
+    assert_eq!(shell.run_str(&script).await.unwrap(), EXIT_SUCCESS);
+
+    let output = stdout.take_string();
+    let times = parse_times(&output);
+    let names = times.iter().map(|&(name, _)| name).collect::<Vec<_>>();
+    assert_eq!(names, ["real", "user", "sys"], "{}", output);
+    assert!(times[0].1 > Duration::from_secs(0), "{}", output);
+    assert!(
+        times[1].1 + times[2].1 > Duration::from_secs(0),
+        "{}",
+        output
+    );
+}
+
+#[tokio::test]
+async fn should_time_executables_run_by_functions() {
+    let mut shell = Shell::new().unwrap();
+    let stdout = shell.stdout_capture();
+
+    let script = format!("f() {{ {}; }}\n{{ time f; }} 2>&1", BUSY_LOOP);
+    assert_eq!(shell.run_str(&script).await.unwrap(), EXIT_SUCCESS);
+
+    let output = stdout.take_string();
+    let times = parse_times(&output);
+    assert_eq!(times.len(), 3, "{}", output);
+    assert!(
+        times[1].1 + times[2].1 > Duration::from_secs(0),
+        "{}",
+        output
+    );
+}
+
+/// Advances a manual clock while each command runs.
+struct Advance(ManualClock, Duration);
+
+impl SpawnMiddleware for Advance {
+    fn wrap<'a>(&'a self, _cmd: &'a SpawnContext, next: NextSpawn<'a>) -> NextSpawn<'a> {
+        Box::pin(async move {
+            let future = next.await;
+            let clock = self.0.clone();
+            let duration = self.1;
+            let future: BoxFuture<'static, ExitStatus> = Box::pin(async move {
+                let status = future.await;
+                clock.advance(duration);
+                status
+            });
+            future
+        })
+    }
+}
+
+#[tokio::test]
+async fn should_measure_real_time_with_env_clock() {
+    let clock = ManualClock::new(UNIX_EPOCH);
+    let mut env = DefaultEnvArc::with_config(DefaultEnvConfigArc {
+        clock: Clock::new(clock.clone()),
+        ..DefaultEnvConfigArc::new().unwrap()
+    });
+    env.add_spawn_middleware(Arc::new(Advance(clock, Duration::from_millis(1500))));
+
+    let mut shell = Shell::with_env(env);
+    let stdout = shell.stdout_capture();
+
+    assert_eq!(
+        shell.run_str("{ time -p true; } 2>&1").await.unwrap(),
+        EXIT_SUCCESS
+    );
+
+    let output = stdout.take_string();
+    assert_eq!(output.lines().next(), Some("real 1.50"), "{}", output);
+}
+
+#[tokio::test]
+async fn should_propagate_status_of_timed_command() {
+    let mut shell = Shell::new().unwrap();
+    let script = "{ time sh -c 'exit 42'; } 2>/dev/null";
+    assert_eq!(shell.run_str(script).await.unwrap(), ExitStatus::Code(42));
+}
+
+#[tokio::test]
+async fn should_report_zero_times_without_command() {
+    let mut shell = Shell::new().unwrap();
+    let stdout = shell.stdout_capture();
+
+    assert_eq!(
+        shell.run_str("{ time -p; } 2>&1").await.unwrap(),
+        EXIT_SUCCESS
+    );
+    assert_eq!(stdout.take_string(), "real 0.00\nuser 0.00\nsys 0.00\n");
+}
+
+#[tokio::test]
+async fn should_reject_unknown_options() {
+    let mut shell = Shell::new().unwrap();
+    assert_eq!(
+        shell.run_str("time -x 2>/dev/null").await.unwrap(),
+        EXIT_ERROR
+    );
+}
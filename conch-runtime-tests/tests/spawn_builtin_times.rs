@@ -0,0 +1,49 @@
+#![deny(rust_2018_idioms)]
+#![cfg(unix)]
+
+use conch_runtime::easy::Shell;
+use conch_runtime::env::ProcessTimesEnvironment;
+use conch_runtime::{EXIT_ERROR, EXIT_SUCCESS};
+use std::time::Duration;
+
+fn parse_time(time: &str) -> Duration {
+    let time = time.strip_suffix('s').expect("missing seconds suffix");
+    let mut parts = time.splitn(2, 'm');
+    let mins = parts.next().unwrap().parse::<u64>().unwrap();
+    let secs = parts.next().unwrap().parse::<f64>().unwrap();
+    Duration::from_secs(mins * 60) + Duration::from_secs_f64(secs)
+}
+
+#[tokio::test]
+async fn should_print_shell_and_children_times() {
+    let mut shell = Shell::new().unwrap();
+    let stdout = shell.stdout_capture();
+
+    let before = shell.env().process_times().unwrap();
+    let script = "sh -c 'i=0; while [ $i -lt 20000 ]; do i=$((i+1)); done'; times";
+    assert_eq!(shell.run_str(script).await.unwrap(), EXIT_SUCCESS);
+    let after = shell.env().process_times().unwrap();
+
+    let output = stdout.take_string();
+    let times = output
+        .split_whitespace()
+        .map(parse_time)
+        .collect::<Vec<_>>();
+    assert_eq!(output.lines().count(), 2, "{}", output);
+    assert_eq!(times.len(), 4, "{}", output);
+
+    // NB: times are truncated to milliseconds
+    let ms = Duration::from_millis(1);
+    let children = times[2] + times[3];
+    assert!(children + ms >= before.children_user_time + before.children_system_time);
+    assert!(children <= after.children_user_time + after.children_system_time);
+    assert!(after.children_user_time + after.children_system_time > Duration::from_secs(0));
+    assert!(times[0] <= after.user_time);
+    assert!(times[1] <= after.system_time);
+}
+
+#[tokio::test]
+async fn should_not_accept_operands() {
+    let mut shell = Shell::new().unwrap();
+    assert_eq!(shell.run_str("times foo").await.unwrap(), EXIT_ERROR);
+}
@@ -0,0 +1,194 @@
+#![deny(rust_2018_idioms)]
+
+use std::fs;
+
+#[macro_use]
+mod support;
+pub use self::support::spawn::builtin::trap;
+pub use self::support::*;
+
+async fn trap_with_args(args: &[&str]) -> (DefaultEnvArc, ExitStatus) {
+    let mut env = new_env_with_no_fds();
+    let args = args.iter().map(|&s| s.to_owned()).collect::<Vec<_>>();
+    let status = trap(args, &mut env).await.await;
+    (env, status)
+}
+
+#[test]
+fn signal_parsing() {
+    assert_eq!("INT".parse(), Ok(Signal::Int));
+    assert_eq!("SIGINT".parse(), Ok(Signal::Int));
+    assert_eq!("2".parse(), Ok(Signal::Int));
+    assert_eq!("EXIT".parse(), Ok(Signal::Exit));
+    assert_eq!("0".parse(), Ok(Signal::Exit));
+    assert_eq!("15".parse(), Ok(Signal::Term));
+    assert_eq!(
+        "int".parse::<Signal>(),
+        Err(UnknownSignalError("int".to_owned()))
+    );
+    assert_eq!(
+        "SIGEXIT0".parse::<Signal>(),
+        Err(UnknownSignalError("SIGEXIT0".to_owned()))
+    );
+    assert_eq!(
+        "99".parse::<Signal>(),
+        Err(UnknownSignalError("99".to_owned()))
+    );
+
+    for &signal in Signal::ALL {
+        assert_eq!(signal.to_string().parse(), Ok(signal));
+        assert_eq!(signal.number().to_string().parse(), Ok(signal));
+    }
+}
+
+#[tokio::test]
+async fn set_ignore_and_reset_traps() {
+    let (mut env, status) = trap_with_args(&["echo hi", "INT", "SIGTERM", "0"]).await;
+    assert_eq!(status, EXIT_SUCCESS);
+    let action = TrapAction::Command("echo hi".to_owned());
+    assert_eq!(*env.trap(Signal::Int), action);
+    assert_eq!(*env.trap(Signal::Term), action);
+    assert_eq!(*env.trap(Signal::Exit), action);
+    assert_eq!(*env.trap(Signal::Hup), TrapAction::Default);
+
+    let args = vec!["".to_owned(), "HUP".to_owned()];
+    assert_eq!(trap(args, &mut env).await.await, EXIT_SUCCESS);
+    assert_eq!(*env.trap(Signal::Hup), TrapAction::Ignore);
+
+    let args = vec!["-".to_owned(), "INT".to_owned(), "HUP".to_owned()];
+    assert_eq!(trap(args, &mut env).await.await, EXIT_SUCCESS);
+    assert_eq!(*env.trap(Signal::Int), TrapAction::Default);
+    assert_eq!(*env.trap(Signal::Hup), TrapAction::Default);
+
+    // A numeric first operand resets all conditions
+    let args = vec!["15".to_owned(), "EXIT".to_owned()];
+    assert_eq!(trap(args, &mut env).await.await, EXIT_SUCCESS);
+    assert_eq!(*env.trap(Signal::Term), TrapAction::Default);
+    assert_eq!(*env.trap(Signal::Exit), TrapAction::Default);
+}
+
+#[tokio::test]
+async fn invalid_conditions_are_reported_but_others_are_set() {
+    let (env, status) = trap_with_args(&["echo hi", "FOO", "INT"]).await;
+    assert_eq!(status, EXIT_ERROR);
+    assert_eq!(
+        *env.trap(Signal::Int),
+        TrapAction::Command("echo hi".to_owned())
+    );
+}
+
+#[tokio::test]
+async fn action_without_conditions_is_a_usage_error() {
+    let (env, status) = trap_with_args(&["echo hi"]).await;
+    assert_eq!(status, EXIT_ERROR);
+    for &signal in Signal::ALL {
+        assert_eq!(*env.trap(signal), TrapAction::Default);
+    }
+
+    let (_, status) = trap_with_args(&["-x", "INT"]).await;
+    assert_eq!(status, EXIT_ERROR);
+}
+
+#[tokio::test]
+async fn trap_without_operands_prints_traps() {
+    let tempdir = mktmp!();
+    let mut env = new_env_with_no_fds();
+    env.change_working_dir(tempdir.path().into()).unwrap();
+
+    let mut session = Session::new(env);
+    run(
+        &mut session,
+        concat!(
+            "trap \"echo 'bye now'\" EXIT\n",
+            "trap '' INT\n",
+            "trap 'echo term' TERM QUIT\n",
+            "trap 'echo hup' HUP; trap - HUP\n",
+            "trap > out\n",
+        ),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        fs::read_to_string(tempdir.path().join("out")).unwrap(),
+        concat!(
+            "trap -- 'echo '\\''bye now'\\''' EXIT\n",
+            "trap -- '' INT\n",
+            "trap -- 'echo term' QUIT\n",
+            "trap -- 'echo term' TERM\n",
+        )
+    );
+}
+
+#[tokio::test]
+async fn non_ignored_traps_are_reset_in_subshells() {
+    let mut session = Session::new(new_env());
+    run(
+        &mut session,
+        "trap 'echo int' INT; trap '' TERM; out=$(trap)\n",
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(var(&session, "out"), Some("trap -- '' TERM".to_owned()));
+
+    let sub = session.env().sub_env();
+    assert_eq!(*sub.trap(Signal::Int), TrapAction::Default);
+    assert_eq!(*sub.trap(Signal::Term), TrapAction::Ignore);
+    assert_ne!(sub.signal_sender(), session.env().signal_sender());
+}
+
+#[tokio::test]
+async fn session_runs_pending_traps_between_commands() {
+    let mut session = Session::new(new_env_with_no_fds());
+    run(&mut session, "trap 'caught=$((caught+1)); false' INT\n")
+        .await
+        .unwrap();
+
+    let sender = session.env().signal_sender();
+    sender.send(Signal::Int);
+    sender.send(Signal::Hup);
+    sender.send(Signal::Int);
+    assert_eq!(var(&session, "caught"), None);
+
+    run(&mut session, "true\nstatus=$?\n").await.unwrap();
+    assert_eq!(var(&session, "caught"), Some("2".to_owned()));
+    // The trap action does not clobber the last status
+    assert_eq!(var(&session, "status"), Some("0".to_owned()));
+
+    // Signals are only handled once
+    run(&mut session, "true\n").await.unwrap();
+    assert_eq!(var(&session, "caught"), Some("2".to_owned()));
+
+    // Ignored signals are discarded
+    run(&mut session, "trap '' INT\n").await.unwrap();
+    sender.send(Signal::Int);
+    run(&mut session, "true\n").await.unwrap();
+    assert_eq!(var(&session, "caught"), Some("2".to_owned()));
+}
+
+#[tokio::test]
+async fn exit_trap_runs_once() {
+    let mut session = Session::new(new_env_with_no_fds());
+    run(&mut session, "trap 'exited=$((exited+1))' EXIT\n")
+        .await
+        .unwrap();
+    assert_eq!(var(&session, "exited"), None);
+
+    session.run_exit_trap().await;
+    assert_eq!(var(&session, "exited"), Some("1".to_owned()));
+    assert_eq!(*session.env().trap(Signal::Exit), TrapAction::Default);
+
+    session.run_exit_trap().await;
+    assert_eq!(var(&session, "exited"), Some("1".to_owned()));
+}
+
+#[tokio::test]
+async fn trap_action_parse_errors_are_reported() {
+    let mut env = new_env_with_no_fds();
+    env.set_trap(Signal::Int, TrapAction::Command("foo; )".to_owned()));
+    env.set_last_status(ExitStatus::Code(42));
+
+    run_trap(Signal::Int, &mut env).await;
+    assert_eq!(env.last_status(), ExitStatus::Code(42));
+}
@@ -1,6 +1,9 @@
 #![deny(rust_2018_idioms)]
 
+use std::sync::Arc;
+
 mod support;
+pub use self::support::env::builtin::*;
 pub use self::support::spawn::builtin::{colon, false_cmd, true_cmd};
 pub use self::support::*;
 
@@ -18,3 +21,18 @@ fn false_smoke() {
 fn true_smoke() {
     assert_eq!(EXIT_SUCCESS, true_cmd());
 }
+
+#[test]
+fn trivial_builtins_have_a_fixed_status() {
+    let env = new_env_with_no_fds();
+    let trivial_status = |name: &str| {
+        let builtin = env.builtin(&Arc::new(name.to_owned())).unwrap();
+        BuiltinUtility::<'_, Vec<Arc<String>>, EnvRestorer<'_, DefaultEnvArc>, DefaultEnvArc>::trivial_status(&builtin)
+    };
+
+    assert_eq!(trivial_status(":"), Some(EXIT_SUCCESS));
+    assert_eq!(trivial_status("true"), Some(EXIT_SUCCESS));
+    assert_eq!(trivial_status("false"), Some(EXIT_ERROR));
+    assert_eq!(trivial_status("echo"), None);
+    assert_eq!(trivial_status("shift"), None);
+}
@@ -0,0 +1,118 @@
+#![deny(rust_2018_idioms)]
+#![cfg(unix)]
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+
+#[macro_use]
+mod support;
+pub use self::support::*;
+
+fn new_session() -> (Session<DefaultEnvArc>, tempfile::TempDir) {
+    let tempdir = mktmp!();
+    let path = tempdir.path().join("tool");
+    fs::write(&path, "#!/bin/sh\n").unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let mut env = new_env_with_no_fds();
+    env.set_var(name("DIR"), name(tempdir.path().to_str().unwrap()));
+
+    (Session::new(env), tempdir)
+}
+
+async fn run_in_tempdir(script: &str) -> (Session<DefaultEnvArc>, tempfile::TempDir) {
+    let (mut session, tempdir) = new_session();
+    run(&mut session, script).await.unwrap();
+    (session, tempdir)
+}
+
+#[tokio::test]
+async fn type_describes_each_kind_of_command() {
+    let (session, tempdir) = run_in_tempdir(concat!(
+        "f() { :; }; PATH=$DIR\n",
+        "keyword=$(type while); function=$(type f); builtin=$(type cd)\n",
+        "external=$(type tool); relative=$(cd $DIR && type ./tool)\n",
+        "type f missing; status=$?\n",
+    ))
+    .await;
+
+    let tool = tempdir.path().join("tool");
+    assert_eq!(
+        var(&session, "keyword"),
+        Some("while is a shell keyword".to_owned())
+    );
+    assert_eq!(
+        var(&session, "function"),
+        Some("f is a shell function".to_owned())
+    );
+    assert_eq!(
+        var(&session, "builtin"),
+        Some("cd is a shell builtin".to_owned())
+    );
+    assert_eq!(
+        var(&session, "external"),
+        Some(format!("tool is {}", tool.display()))
+    );
+    assert_eq!(
+        var(&session, "relative"),
+        Some("./tool is ./tool".to_owned())
+    );
+    assert_eq!(var(&session, "status"), Some("1".to_owned()));
+}
+
+#[tokio::test]
+async fn command_describes_commands() {
+    let (session, tempdir) = run_in_tempdir(concat!(
+        "f() { :; }; PATH=$DIR\n",
+        "names=$(command -v if f type tool missing); names_status=$?\n",
+        "verbose=$(command -V f); found_status=$?\n",
+        "command -V missing; missing_status=$?\n",
+    ))
+    .await;
+
+    let tool = tempdir.path().join("tool");
+    assert_eq!(
+        var(&session, "names"),
+        Some(format!("if\nf\ntype\n{}", tool.display()))
+    );
+    assert_eq!(var(&session, "names_status"), Some("1".to_owned()));
+    assert_eq!(
+        var(&session, "verbose"),
+        Some("f is a shell function".to_owned())
+    );
+    assert_eq!(var(&session, "found_status"), Some("0".to_owned()));
+    assert_eq!(var(&session, "missing_status"), Some("1".to_owned()));
+}
+
+#[tokio::test]
+async fn command_executes_commands_bypassing_functions() {
+    let (session, _tempdir) = run_in_tempdir(concat!(
+        "true() { called=true; return 3; }; tool() { called=tool; }; PATH=$DIR\n",
+        "command true; builtin_status=$?\n",
+        "command -- true; dashes_status=$?\n",
+        "command command true; nested_status=$?\n",
+        "command tool; external_status=$?\n",
+        "command; empty_status=$?\n",
+        "true; function_status=$?\n",
+    ))
+    .await;
+
+    assert_eq!(var(&session, "builtin_status"), Some("0".to_owned()));
+    assert_eq!(var(&session, "dashes_status"), Some("0".to_owned()));
+    assert_eq!(var(&session, "nested_status"), Some("0".to_owned()));
+    assert_eq!(var(&session, "external_status"), Some("0".to_owned()));
+    assert_eq!(var(&session, "empty_status"), Some("0".to_owned()));
+    assert_eq!(var(&session, "function_status"), Some("3".to_owned()));
+    assert_eq!(var(&session, "called"), Some("true".to_owned()));
+}
+
+#[tokio::test]
+async fn command_prevents_special_builtin_errors_from_being_fatal() {
+    let (mut session, _tempdir) = new_session();
+    session.env_mut().options_mut().fatal_special_builtin_errors = true;
+
+    run(&mut session, "command shift 5; status=$?")
+        .await
+        .unwrap();
+    assert_eq!(var(&session, "status"), Some("1".to_owned()));
+}
@@ -0,0 +1,101 @@
+#![deny(rust_2018_idioms)]
+#![cfg(unix)]
+
+use conch_runtime::easy::Shell;
+use conch_runtime::env::{Resource, ResourceLimit, ResourceLimitEnvironment};
+use conch_runtime::{EXIT_ERROR, EXIT_SUCCESS};
+
+async fn run(shell: &mut Shell, script: &str) -> String {
+    let stdout = shell.stdout_capture();
+    assert_eq!(shell.run_str(script).await.unwrap(), EXIT_SUCCESS);
+    stdout.take_string()
+}
+
+#[tokio::test]
+async fn should_apply_limits_to_spawned_executables() {
+    let mut shell = Shell::new().unwrap();
+
+    let output = run(
+        &mut shell,
+        "ulimit -n 64; ulimit -n; ulimit -H -n; sh -c 'ulimit -n'",
+    )
+    .await;
+    assert_eq!(output, "64\n64\n64\n");
+
+    let limit = shell.env().resource_limit(Resource::OpenFiles).unwrap();
+    assert_eq!(
+        limit,
+        ResourceLimit {
+            soft: Some(64),
+            hard: Some(64),
+        }
+    );
+
+    let output = run(
+        &mut shell,
+        "ulimit -S -n 32; sh -c 'ulimit -S -n; ulimit -H -n'",
+    )
+    .await;
+    assert_eq!(output, "32\n64\n");
+}
+
+#[tokio::test]
+async fn should_convert_units() {
+    let mut shell = Shell::new().unwrap();
+
+    run(&mut shell, "ulimit -f 8; ulimit -S -v 1048576").await;
+    let env = shell.env();
+    assert_eq!(
+        env.resource_limit(Resource::FileSize).unwrap().soft,
+        Some(8 * 512)
+    );
+    assert_eq!(
+        env.resource_limit(Resource::VirtualMemory).unwrap().soft,
+        Some(1024 * 1024 * 1024)
+    );
+
+    let output = run(&mut shell, "ulimit; ulimit -a").await;
+    let mut lines = output.lines();
+    assert_eq!(lines.next(), Some("8"));
+
+    let all = lines.collect::<Vec<_>>();
+    assert_eq!(all.len(), Resource::ALL.len());
+    assert!(all
+        .iter()
+        .any(|l| l.starts_with("file size") && l.ends_with("(blocks, -f) 8")));
+    assert!(all.iter().any(|l| l.contains("(-n) ")));
+}
+
+#[tokio::test]
+async fn subshells_should_not_leak_limits() {
+    let mut shell = Shell::new().unwrap();
+
+    let output = run(
+        &mut shell,
+        "ulimit -n 64; (ulimit -n 32; ulimit -n); ulimit -n",
+    )
+    .await;
+    assert_eq!(output, "32\n64\n");
+}
+
+#[tokio::test]
+async fn invalid_limits_should_be_rejected() {
+    let mut shell = Shell::new().unwrap();
+    run(&mut shell, "ulimit -n 100").await;
+
+    for script in &[
+        "ulimit -n nope",
+        "ulimit -n -- -5",
+        "ulimit -S -n 200",
+        "ulimit -S -n unlimited",
+        "ulimit -n 1 2",
+        "ulimit -a 5",
+        "ulimit -x",
+    ] {
+        let status = shell.run_str(script).await.unwrap();
+        assert_eq!(status, EXIT_ERROR, "{}", script);
+    }
+
+    let output = run(&mut shell, "ulimit -H -n; ulimit -S -n").await;
+    assert_eq!(output, "100\n100\n");
+}
@@ -0,0 +1,90 @@
+#![deny(rust_2018_idioms)]
+
+mod support;
+pub use self::support::spawn::builtin::unset;
+pub use self::support::*;
+
+async fn unset_with_args(env: &mut DefaultEnvArc, args: &[&str]) -> ExitStatus {
+    let args = args.iter().map(|&s| s.to_owned()).collect::<Vec<_>>();
+    unset(args, env).await.await
+}
+
+async fn define_functions(env: DefaultEnvArc, script: &str) -> DefaultEnvArc {
+    let mut session = Session::new(env);
+    run(&mut session, script).await.unwrap();
+    session.into_env()
+}
+
+#[tokio::test]
+async fn unsets_variables_by_default() {
+    let mut env = new_env_with_no_fds();
+    env.set_var(name("foo"), name("1"));
+    env.set_var(name("bar"), name("2"));
+    env.set_var(name("baz"), name("3"));
+    let mut env = define_functions(env, "foo() { :; }").await;
+
+    assert_eq!(
+        unset_with_args(&mut env, &["foo", "bar", "missing"]).await,
+        EXIT_SUCCESS
+    );
+    assert_eq!(env.var(&name("foo")), None);
+    assert_eq!(env.var(&name("bar")), None);
+    assert_eq!(env.var(&name("baz")), Some(&name("3")));
+    assert!(env.has_function(&name("foo")));
+
+    assert_eq!(
+        unset_with_args(&mut env, &["-v", "baz"]).await,
+        EXIT_SUCCESS
+    );
+    assert_eq!(env.var(&name("baz")), None);
+}
+
+#[tokio::test]
+async fn unsets_functions_with_flag() {
+    let mut env = new_env_with_no_fds();
+    env.set_var(name("foo"), name("1"));
+    let mut env = define_functions(env, "foo() { :; }; bar() { :; }").await;
+
+    assert_eq!(
+        unset_with_args(&mut env, &["-f", "foo", "missing"]).await,
+        EXIT_SUCCESS
+    );
+    assert!(!env.has_function(&name("foo")));
+    assert!(env.has_function(&name("bar")));
+    assert_eq!(env.var(&name("foo")), Some(&name("1")));
+
+    // The last flag wins
+    assert_eq!(
+        unset_with_args(&mut env, &["-v", "-f", "bar"]).await,
+        EXIT_SUCCESS
+    );
+    assert!(!env.has_function(&name("bar")));
+    assert_eq!(
+        unset_with_args(&mut env, &["-fv", "foo"]).await,
+        EXIT_SUCCESS
+    );
+    assert_eq!(env.var(&name("foo")), None);
+}
+
+#[tokio::test]
+async fn readonly_variables_are_reported_and_left_alone() {
+    let mut env = new_env_with_no_fds();
+    env.set_var(name("ro"), name("1"));
+    env.set_var(name("rw"), name("2"));
+    env.set_readonly(name("ro"));
+
+    assert_eq!(unset_with_args(&mut env, &["ro", "rw"]).await, EXIT_ERROR);
+    assert_eq!(env.var(&name("ro")), Some(&name("1")));
+    assert_eq!(env.var(&name("rw")), None);
+
+    // Functions do not share the same namespace
+    let mut env = define_functions(env, "ro() { :; }").await;
+    assert_eq!(unset_with_args(&mut env, &["-f", "ro"]).await, EXIT_SUCCESS);
+    assert!(!env.has_function(&name("ro")));
+}
+
+#[tokio::test]
+async fn invalid_flags_are_usage_errors() {
+    let mut env = new_env_with_no_fds();
+    assert_eq!(unset_with_args(&mut env, &["-x", "foo"]).await, EXIT_ERROR);
+}
@@ -0,0 +1,104 @@
+#![deny(rust_2018_idioms)]
+
+use std::fs;
+
+#[macro_use]
+mod support;
+pub use self::support::spawn::builtin::wait;
+pub use self::support::*;
+
+#[tokio::test]
+async fn wait_without_operands_waits_for_all_jobs() {
+    let tempdir = mktmp!();
+    let mut env = new_env_with_no_fds();
+    env.change_working_dir(tempdir.path().into()).unwrap();
+
+    let mut session = Session::new(env);
+    run(
+        &mut session,
+        "echo foo > foo & echo bar > bar & false & wait; status=$?\n",
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(var(&session, "status"), Some("0".to_owned()));
+    assert_eq!(session.env().job_ids(), Vec::<JobId>::new());
+    assert_eq!(
+        fs::read_to_string(tempdir.path().join("foo")).unwrap(),
+        "foo\n"
+    );
+    assert_eq!(
+        fs::read_to_string(tempdir.path().join("bar")).unwrap(),
+        "bar\n"
+    );
+}
+
+#[tokio::test]
+async fn wait_reports_status_of_last_operand() {
+    let mut session = Session::new(new_env_with_no_fds());
+    run(
+        &mut session,
+        concat!(
+            "false & f=$!; true & t=$!\n",
+            "wait $t $f; last_false=$?\n",
+            "false & f=$!; true & t=$!\n",
+            "wait $f %$t; last_true=$?\n",
+        ),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(var(&session, "last_false"), Some("1".to_owned()));
+    assert_eq!(var(&session, "last_true"), Some("0".to_owned()));
+    assert_eq!(session.env().job_ids(), Vec::<JobId>::new());
+}
+
+#[tokio::test]
+async fn wait_for_unknown_job_exits_with_127() {
+    let mut session = Session::new(new_env_with_no_fds());
+    run(
+        &mut session,
+        concat!(
+            "false & f=$!\n",
+            "wait $f; first=$?\n",
+            "wait $f; again=$?\n",
+            "true & t=$!\n",
+            "wait $t 9999; unknown_last=$?\n",
+        ),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(var(&session, "first"), Some("1".to_owned()));
+    assert_eq!(var(&session, "again"), Some("127".to_owned()));
+    assert_eq!(var(&session, "unknown_last"), Some("127".to_owned()));
+}
+
+#[tokio::test]
+async fn jobs_are_not_inherited_by_subshells() {
+    let mut session = Session::new(new_env_with_no_fds());
+    run(
+        &mut session,
+        "true & t=$!; (wait $t); sub=$?; wait $t; parent=$?\n",
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(var(&session, "sub"), Some("127".to_owned()));
+    assert_eq!(var(&session, "parent"), Some("0".to_owned()));
+}
+
+#[tokio::test]
+async fn wait_with_invalid_operand_is_an_error() {
+    let mut env = new_env_with_no_fds();
+    let id = env.spawn_job(Box::pin(async { EXIT_SUCCESS }));
+
+    let args = vec![id.to_string(), "foo".to_owned()];
+    assert_eq!(wait(args, &mut env).await.await, EXIT_ERROR);
+
+    // No jobs are waited for if any operand is invalid
+    assert_eq!(env.job_ids(), vec![id]);
+
+    let args = vec!["-x".to_owned()];
+    assert_eq!(wait(args, &mut env).await.await, EXIT_ERROR);
+}
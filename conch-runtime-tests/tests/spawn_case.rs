@@ -1,5 +1,7 @@
 #![deny(rust_2018_idioms)]
 
+use conch_runtime::io::Permissions;
+
 mod support;
 pub use self::support::*;
 
@@ -191,3 +193,168 @@ async fn should_propagate_fatal_errors() {
         .await
     );
 }
+
+async fn run_with_terminators(
+    word: MockWord,
+    arms: Vec<(
+        PatternBodyPair<Vec<MockWord>, Vec<MockCmd>>,
+        CaseArmTerminator,
+    )>,
+) -> Result<ExitStatus, MockErr> {
+    let mut env = new_env();
+    Ok(case_with_terminators(
+        word,
+        arms.iter().map(|(pbp, terminator)| {
+            let pbp = PatternBodyPair {
+                patterns: &*pbp.patterns,
+                body: sequence_slice(&pbp.body),
+            };
+
+            (pbp, *terminator)
+        }),
+        &mut env,
+    )
+    .await?
+    .await)
+}
+
+#[tokio::test]
+async fn should_run_next_body_without_matching_on_fall_through() {
+    let word = mock_word_fields(Fields::Single("foo".to_owned()));
+    let exit = ExitStatus::Code(42);
+
+    assert_eq!(
+        Ok(exit),
+        run_with_terminators(
+            word.clone(),
+            vec![
+                (
+                    PatternBodyPair {
+                        patterns: vec![word.clone()],
+                        body: vec![mock_status(ExitStatus::Code(5))],
+                    },
+                    CaseArmTerminator::FallThrough,
+                ),
+                (
+                    PatternBodyPair {
+                        patterns: vec![mock_word_panic("word must not run")],
+                        body: vec![mock_status(exit)],
+                    },
+                    CaseArmTerminator::Break,
+                ),
+                (
+                    PatternBodyPair {
+                        patterns: vec![word.clone()],
+                        body: vec![mock_panic("must not run")],
+                    },
+                    CaseArmTerminator::Break,
+                ),
+            ],
+        )
+        .await
+    );
+}
+
+#[tokio::test]
+async fn should_resume_matching_on_continue() {
+    let word = mock_word_fields(Fields::Single("foo".to_owned()));
+    let exit = ExitStatus::Code(42);
+
+    assert_eq!(
+        Ok(exit),
+        run_with_terminators(
+            word.clone(),
+            vec![
+                (
+                    PatternBodyPair {
+                        patterns: vec![word.clone()],
+                        body: vec![mock_status(exit)],
+                    },
+                    CaseArmTerminator::Continue,
+                ),
+                (
+                    PatternBodyPair {
+                        patterns: vec![mock_word_fields(Fields::Single("bar".to_owned()))],
+                        body: vec![mock_panic("must not run")], // No match
+                    },
+                    CaseArmTerminator::FallThrough,
+                ),
+            ],
+        )
+        .await
+    );
+
+    assert_eq!(
+        Ok(EXIT_SUCCESS),
+        run_with_terminators(
+            word.clone(),
+            vec![
+                (
+                    PatternBodyPair {
+                        patterns: vec![word.clone()],
+                        body: vec![mock_status(exit)],
+                    },
+                    CaseArmTerminator::Continue,
+                ),
+                (
+                    PatternBodyPair {
+                        patterns: vec![word.clone()],
+                        body: vec![mock_status(EXIT_SUCCESS)],
+                    },
+                    CaseArmTerminator::FallThrough,
+                ),
+            ],
+        )
+        .await
+    );
+}
+
+#[tokio::test]
+async fn should_trace_evaluated_word_if_xtrace_enabled() {
+    let mut env = new_env();
+    env.options_mut().xtrace = true;
+
+    let pipe = env.open_pipe().expect("failed to open pipe");
+    env.set_file_desc(
+        conch_runtime::STDERR_FILENO,
+        pipe.writer,
+        Permissions::Write,
+    );
+    let stderr = env.read_all(pipe.reader);
+
+    let future = case::<_, _, MockWord, MockCmd, _>(
+        mock_word_fields(Fields::Single("foo".to_owned())),
+        vec![].into_iter(),
+        &mut env,
+    );
+
+    assert_eq!(EXIT_SUCCESS, future.await.unwrap().await);
+    drop(env);
+
+    assert_eq!(stderr.await.unwrap(), b"+ case foo in\n");
+}
+
+#[tokio::test]
+async fn should_quote_traced_word_with_special_chars() {
+    let mut env = new_env();
+    env.options_mut().xtrace = true;
+
+    let pipe = env.open_pipe().expect("failed to open pipe");
+    env.set_file_desc(
+        conch_runtime::STDERR_FILENO,
+        pipe.writer,
+        Permissions::Write,
+    );
+    let stderr = env.read_all(pipe.reader);
+
+    let future = case::<_, _, MockWord, MockCmd, _>(
+        mock_word_fields(Fields::Single("foo bar's".to_owned())),
+        vec![].into_iter(),
+        &mut env,
+    );
+
+    assert_eq!(EXIT_SUCCESS, future.await.unwrap().await);
+    drop(env);
+
+    assert_eq!(stderr.await.unwrap(), &b"+ case 'foo bar'\\''s' in\n"[..]);
+}
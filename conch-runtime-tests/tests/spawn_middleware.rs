@@ -0,0 +1,152 @@
+#![deny(rust_2018_idioms)]
+
+use futures_util::future::BoxFuture;
+use std::sync::{Arc, Mutex};
+
+mod support;
+pub use self::support::*;
+
+type Log = Arc<Mutex<Vec<String>>>;
+
+/// Records when each command is spawned and when it completes.
+struct Record {
+    name: &'static str,
+    log: Log,
+}
+
+impl SpawnMiddleware for Record {
+    fn wrap<'a>(&'a self, cmd: &'a SpawnContext, next: NextSpawn<'a>) -> NextSpawn<'a> {
+        Box::pin(async move {
+            let argv = cmd.argv.join(" ");
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("{} spawn {}", self.name, argv));
+
+            let future = next.await;
+
+            let name = self.name;
+            let log = self.log.clone();
+            let future: BoxFuture<'static, ExitStatus> = Box::pin(async move {
+                let status = future.await;
+                log.lock()
+                    .unwrap()
+                    .push(format!("{} done {} {:?}", name, argv, status));
+                status
+            });
+
+            future
+        })
+    }
+}
+
+/// Prevents any commands with a particular name from running.
+struct Deny(&'static str);
+
+impl SpawnMiddleware for Deny {
+    fn wrap<'a>(&'a self, cmd: &'a SpawnContext, next: NextSpawn<'a>) -> NextSpawn<'a> {
+        if cmd.argv[0] == self.0 {
+            Box::pin(async { ready_status(ExitStatus::Code(42)) })
+        } else {
+            next
+        }
+    }
+}
+
+fn new_log() -> Log {
+    Arc::new(Mutex::new(Vec::new()))
+}
+
+#[tokio::test]
+async fn middleware_wraps_every_simple_command_in_registration_order() {
+    let log = new_log();
+    let mut env = new_env_with_no_fds();
+    env.add_spawn_middleware(Arc::new(Record {
+        name: "outer",
+        log: log.clone(),
+    }));
+    env.add_spawn_middleware(Arc::new(Record {
+        name: "inner",
+        log: log.clone(),
+    }));
+    assert_eq!(env.spawn_middleware().len(), 2);
+
+    let mut session = Session::new(env);
+    run(&mut session, "x=5\ntrue $x; false\n").await.unwrap();
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        vec![
+            "outer spawn true 5",
+            "inner spawn true 5",
+            "inner done true 5 Code(0)",
+            "outer done true 5 Code(0)",
+            "outer spawn false",
+            "inner spawn false",
+            "inner done false Code(1)",
+            "outer done false Code(1)",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn middleware_wraps_functions_and_their_commands() {
+    let log = new_log();
+    let mut env = new_env_with_no_fds();
+    env.add_spawn_middleware(Arc::new(Record {
+        name: "mw",
+        log: log.clone(),
+    }));
+
+    let mut session = Session::new(env);
+    run(&mut session, "f() { true; }\nf arg\n").await.unwrap();
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        vec![
+            "mw spawn f arg",
+            "mw spawn true",
+            "mw done true Code(0)",
+            "mw done f arg Code(0)",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn middleware_can_prevent_commands_from_running() {
+    let mut env = new_env_with_no_fds();
+    env.add_spawn_middleware(Arc::new(Deny("f")));
+
+    let mut session = Session::new(env);
+    run(
+        &mut session,
+        "f() { ran=yes; }\nf; status=$?\ng() { other=yes; }\ng\n",
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(var(&session, "ran"), None);
+    assert_eq!(var(&session, "status"), Some("42".to_owned()));
+    assert_eq!(var(&session, "other"), Some("yes".to_owned()));
+}
+
+#[tokio::test]
+async fn middleware_is_inherited_by_subshells() {
+    let log = new_log();
+    let mut env = new_env_with_no_fds();
+    env.add_spawn_middleware(Arc::new(Record {
+        name: "mw",
+        log: log.clone(),
+    }));
+
+    let sub = env.sub_env();
+    assert_eq!(sub.spawn_middleware().len(), 1);
+
+    let mut session = Session::new(env);
+    run(&mut session, "(true)\n").await.unwrap();
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        vec!["mw spawn true", "mw done true Code(0)"]
+    );
+}
@@ -248,3 +248,152 @@ async fn pipeline_io_smoke() {
     check_pipe(first_writer, second_reader);
     check_pipe(second_writer, third_reader);
 }
+
+#[tokio::test]
+async fn reports_status_of_every_member() {
+    let exit = ExitStatus::Code(42);
+
+    for &invert in &[false, true] {
+        let mut env = new_env_with_no_fds();
+        let future = pipeline_with_status(
+            invert,
+            mock_status(exit),
+            vec![mock_error(false), mock_status(EXIT_SUCCESS)],
+            &mut env,
+        )
+        .await
+        .unwrap();
+        drop(env);
+
+        let status = future.await;
+        assert_eq!(status.statuses(), &[exit, EXIT_ERROR, EXIT_SUCCESS]);
+        assert_eq!(status.last_status(), EXIT_SUCCESS);
+        assert_eq!(status.is_inverted(), invert);
+
+        let expected = if invert { EXIT_ERROR } else { EXIT_SUCCESS };
+        assert_eq!(status.status(), expected);
+    }
+}
+
+#[tokio::test]
+async fn last_member_finishing_first_still_reports_its_status() {
+    // Takes a while to spawn, so the last command finishes well before it
+    struct SlowSpawn(ExitStatus);
+
+    #[async_trait::async_trait]
+    impl<E: ?Sized + Send> Spawn<E> for SlowSpawn {
+        type Error = MockErr;
+
+        async fn spawn(&self, _: &mut E) -> Result<BoxFuture<'static, ExitStatus>, Self::Error> {
+            tokio::time::delay_for(std::time::Duration::from_millis(10)).await;
+
+            let status = self.0;
+            Ok(Box::pin(async move { status }))
+        }
+    }
+
+    let first = ExitStatus::Code(42);
+    let last = ExitStatus::Code(3);
+
+    let mut env = new_env_with_no_fds();
+    let future = pipeline_with_status(
+        true,
+        Box::new(SlowSpawn(first)) as Box<dyn Spawn<_, Error = MockErr> + Send + Sync>,
+        vec![Box::new(mock_status(last)) as Box<_>],
+        &mut env,
+    )
+    .await
+    .unwrap();
+    drop(env);
+
+    let status = future.await;
+    assert_eq!(status.statuses(), &[first, last]);
+    assert_eq!(status.last_status(), last);
+    assert_eq!(status.status(), EXIT_SUCCESS);
+}
+
+fn new_env_with_fd_limit(limit: usize) -> (DefaultEnvArc, FdUsageTracker) {
+    let mut cfg = DefaultEnvConfigArc::new().expect("failed to create env cfg");
+    cfg.file_desc_manager_env = TokioFileDescManagerEnv::new();
+
+    let usage = cfg.file_desc_manager_env.fd_usage().clone();
+    usage.set_limit(Some(limit));
+
+    (DefaultEnvArc::with_config(cfg), usage)
+}
+
+fn is_too_many_open_files<T>(result: Result<T, MockErr>) -> bool {
+    match result {
+        Err(MockErr::CommandError(e)) => matches!(*e, CommandError::TooManyOpenFiles(_)),
+        _ => false,
+    }
+}
+
+#[tokio::test]
+async fn too_many_open_files_closes_any_created_pipes() {
+    // Enough for two pipes, but not three
+    let (mut env, usage) = new_env_with_fd_limit(5);
+
+    let cmds = vec![
+        mock_status(EXIT_SUCCESS),
+        mock_status(EXIT_SUCCESS),
+        mock_status(EXIT_SUCCESS),
+    ];
+    let result = pipeline(false, mock_status(EXIT_SUCCESS), cmds, &mut env).await;
+    assert!(is_too_many_open_files(result));
+    assert_eq!(usage.open_fds(), 0);
+
+    let cmds = vec![mock_status(EXIT_SUCCESS), mock_status(EXIT_ERROR)];
+    let future = pipeline(false, mock_status(EXIT_SUCCESS), cmds, &mut env);
+    assert_eq!(future.await.unwrap().await, EXIT_ERROR);
+    assert_eq!(usage.open_fds(), 0);
+}
+
+#[tokio::test]
+async fn too_many_open_files_retries_once_a_job_completes_if_requested() {
+    let (mut env, usage) = new_env_with_fd_limit(2);
+
+    let spawn_job = |env: &mut DefaultEnvArc| {
+        let pipe = env.open_pipe().expect("failed to open pipe");
+        env.spawn_job(Box::pin(async move {
+            tokio::time::delay_for(std::time::Duration::from_millis(50)).await;
+            drop(pipe);
+            EXIT_SUCCESS
+        }));
+    };
+
+    let exit = ExitStatus::Code(42);
+
+    spawn_job(&mut env);
+    let result = pipeline(
+        false,
+        mock_status(EXIT_SUCCESS),
+        vec![mock_status(exit)],
+        &mut env,
+    )
+    .await;
+    assert!(is_too_many_open_files(result));
+
+    env.options_mut().reap_jobs_on_fd_exhaustion = true;
+    let future = pipeline(
+        false,
+        mock_status(EXIT_SUCCESS),
+        vec![mock_status(exit)],
+        &mut env,
+    );
+    assert_eq!(future.await.unwrap().await, exit);
+
+    // Nothing to wait for if no jobs are running
+    let pipe = env.open_pipe().expect("failed to open pipe");
+    let result = pipeline(
+        false,
+        mock_status(EXIT_SUCCESS),
+        vec![mock_status(exit)],
+        &mut env,
+    )
+    .await;
+    assert!(is_too_many_open_files(result));
+
+    drop(pipe);
+    assert_eq!(usage.open_fds(), 0);
+}
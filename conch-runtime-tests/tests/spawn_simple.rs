@@ -1,10 +1,13 @@
 #![deny(rust_2018_idioms)]
 
-use conch_runtime::env::builtin::{Builtin as RealBuiltin, BuiltinEnvironment, BuiltinUtility};
-use conch_runtime::env::FileDescEnvironment;
+use conch_runtime::env::builtin::{
+    Builtin as RealBuiltin, BuiltinEnv, BuiltinEnvironment, BuiltinUtility,
+};
+use conch_runtime::env::{EnvRestorer, FileDescEnvironment, Restorer, ScopedRestorer};
 use conch_runtime::eval::RedirectAction;
 use conch_runtime::io::Permissions;
-use conch_runtime::spawn::simple_command;
+use conch_runtime::spawn::{simple_command, simple_command_with_restorer};
+use conch_runtime::EXIT_CMD_NOT_FOUND;
 use std::sync::Arc;
 
 mod support;
@@ -142,6 +145,168 @@ async fn function_smoke() {
     assert_eq!(env.var(&key), None);
 }
 
+#[tokio::test]
+async fn function_sees_rendered_command_text() {
+    #[derive(Debug, Clone, Copy)]
+    struct MockFn;
+
+    #[async_trait::async_trait]
+    impl<E> Spawn<E> for MockFn
+    where
+        E: ?Sized + Send + Sync + CommandTextEnvironment,
+    {
+        type Error = MockErr;
+
+        async fn spawn(&self, env: &mut E) -> Result<BoxFuture<'static, ExitStatus>, Self::Error> {
+            assert_eq!(env.command_text(), Some("fn_name foo bar"));
+            Ok(Box::pin(async { EXIT_SUCCESS }))
+        }
+    }
+
+    let mut env = new_test_env();
+    env.set_function(Arc::new("fn_name".to_owned()), Arc::new(MockFn));
+    env.set_command_text(Some("outer".to_owned()));
+
+    let future = simple_command::<MockRedirect<_>, Arc<String>, _, _, _, _, _>(
+        vec![].into_iter(),
+        vec![
+            RedirectOrCmdWord::CmdWord(mock_word_fields(Fields::Single("fn_name".to_owned()))),
+            RedirectOrCmdWord::CmdWord(mock_word_fields(Fields::Split(vec![
+                "foo".to_owned(),
+                "bar".to_owned(),
+            ]))),
+        ]
+        .into_iter(),
+        &mut env,
+    );
+
+    assert_eq!(EXIT_SUCCESS, future.await.unwrap().await);
+    assert_eq!(env.command_text(), Some("outer"));
+}
+
+#[tokio::test]
+async fn should_record_execution_report() {
+    #[derive(Debug, Clone, Copy)]
+    struct MockFn;
+
+    #[async_trait::async_trait]
+    impl<E> Spawn<E> for MockFn
+    where
+        E: ?Sized + Send + Sync + ExecutionReportEnvironment,
+    {
+        type Error = MockErr;
+
+        async fn spawn(&self, env: &mut E) -> Result<BoxFuture<'static, ExitStatus>, Self::Error> {
+            let reporter = env.execution_reporter().expect("no reporter");
+            reporter
+                .start_command(vec!["inner".to_owned()], vec![])
+                .finish(EXIT_ERROR);
+            Ok(Box::pin(async { EXIT_SUCCESS }))
+        }
+    }
+
+    let reporter = ExecutionReporter::new();
+    let mut env: TestEnv = Env::with_config(EnvConfig {
+        execution_reporter: Some(reporter.clone()),
+        ..new_test_env_config!()
+    });
+    env.set_function(Arc::new("fn_name".to_owned()), Arc::new(MockFn));
+
+    let future = simple_command::<MockRedirect<_>, Arc<String>, _, _, _, _, _>(
+        vec![].into_iter(),
+        vec![
+            RedirectOrCmdWord::CmdWord(mock_word_fields(Fields::Single("fn_name".to_owned()))),
+            RedirectOrCmdWord::CmdWord(mock_word_fields(Fields::Single("foo".to_owned()))),
+        ]
+        .into_iter(),
+        &mut env,
+    );
+    assert_eq!(EXIT_SUCCESS, future.await.unwrap().await);
+
+    let future = simple_command::<MockRedirect<_>, Arc<String>, _, _, _, _, _>(
+        vec![].into_iter(),
+        vec![
+            RedirectOrCmdWord::CmdWord(mock_word_fields(Fields::Single(
+                "missing_command".to_owned(),
+            ))),
+            RedirectOrCmdWord::Redirect(mock_redirect(RedirectAction::Close(5))),
+        ]
+        .into_iter(),
+        &mut env,
+    );
+    assert_eq!(EXIT_CMD_NOT_FOUND, future.await.unwrap().await);
+
+    let report = reporter.report();
+    assert_eq!(report.commands.len(), 2);
+
+    let func = &report.commands[0];
+    assert_eq!(func.argv, vec!["fn_name", "foo"]);
+    assert_eq!(func.status, Some(EXIT_SUCCESS));
+    assert!(func.duration.is_some());
+    assert_eq!(func.children.len(), 1);
+    assert_eq!(func.children[0].argv, vec!["inner"]);
+    assert_eq!(func.children[0].status, Some(EXIT_ERROR));
+
+    let missing = &report.commands[1];
+    assert_eq!(missing.argv, vec!["missing_command"]);
+    assert_eq!(missing.status, Some(EXIT_CMD_NOT_FOUND));
+    assert_eq!(
+        missing.redirects,
+        vec![RedirectReport {
+            fd: 5,
+            permissions: None,
+        }]
+    );
+    assert_eq!(missing.resource_usage, None);
+    assert!(missing.children.is_empty());
+
+    // The function's reporter should not leak into the environment
+    assert_eq!(env.execution_reporter(), Some(&reporter));
+}
+
+#[tokio::test]
+async fn should_trace_expanded_words_to_original_stderr() {
+    let mut env: TestEnv = Env::with_config(EnvConfig {
+        options: ShellOptions {
+            xtrace: true,
+            ..ShellOptions::default()
+        },
+        ..new_test_env_config!()
+    });
+
+    let pipe = env.open_pipe().expect("failed to open pipe");
+    env.set_file_desc(
+        conch_runtime::STDERR_FILENO,
+        pipe.writer,
+        Permissions::Write,
+    );
+    let stderr = env.read_all(pipe.reader);
+
+    env.set_var(Arc::new("LVL".to_owned()), Arc::new("1".to_owned()));
+    env.set_var(Arc::new("PS4".to_owned()), Arc::new("${LVL}> ".to_owned()));
+
+    let future = simple_command::<MockRedirect<_>, Arc<String>, _, _, _, _, _>(
+        vec![].into_iter(),
+        vec![
+            RedirectOrCmdWord::CmdWord(mock_word_fields(Fields::Single(
+                "missing_command".to_owned(),
+            ))),
+            RedirectOrCmdWord::Redirect(mock_redirect(RedirectAction::Close(2))),
+            RedirectOrCmdWord::CmdWord(mock_word_fields(Fields::Split(vec![
+                "foo".to_owned(),
+                "bar".to_owned(),
+            ]))),
+        ]
+        .into_iter(),
+        &mut env,
+    );
+
+    assert_eq!(EXIT_CMD_NOT_FOUND, future.await.unwrap().await);
+    drop(env);
+
+    assert_eq!(stderr.await.unwrap(), b"1> missing_command foo bar\n");
+}
+
 #[tokio::test]
 async fn should_set_executable_cwd_same_as_env() {
     let mut env = new_test_env();
@@ -297,6 +462,44 @@ async fn command_with_no_words_should_open_and_restore_redirects_and_assign_vars
     );
 }
 
+#[tokio::test]
+async fn should_not_restore_changes_already_held_by_restorer() {
+    let mut env = new_test_env();
+
+    let key = Arc::new("key".to_owned());
+    let key_assigned = Arc::new("key_assigned".to_owned());
+    let val_outer = Arc::new("outer".to_owned());
+    let val_assigned = Arc::new("assigned".to_owned());
+
+    let mut restorer = EnvRestorer::new(&mut env);
+    restorer.set_var(key.clone(), val_outer.clone());
+
+    let future = simple_command_with_restorer::<MockRedirect<_>, _, _, _, _, _, _, _>(
+        vec![
+            RedirectOrVarAssig::VarAssig(
+                key.clone(),
+                Some(mock_word_fields(Fields::Single("inner".to_owned()))),
+            ),
+            RedirectOrVarAssig::VarAssig(
+                key_assigned.clone(),
+                Some(mock_word_fields(Fields::Single((*val_assigned).clone()))),
+            ),
+        ]
+        .into_iter(),
+        vec![].into_iter(),
+        &mut restorer,
+    );
+
+    assert_eq!(EXIT_SUCCESS, future.await.unwrap().await);
+    assert_eq!(restorer.frame_depth(), 0);
+    assert_eq!(restorer.get().var(&key_assigned), Some(&val_assigned));
+
+    // The outer change is still pending restoration by the caller
+    drop(restorer);
+    assert_eq!(env.var(&key), None);
+    assert_eq!(env.var(&key_assigned), Some(&val_assigned));
+}
+
 #[tokio::test]
 async fn should_propagate_errors_and_restore_redirects_without_assigning_vars() {
     let mut env = new_test_env();
@@ -542,3 +745,39 @@ async fn should_pass_restorers_to_builtin_utility_without_restore() {
     assert_ne!(None, env.file_desc(42));
     assert_ne!(None, env.var(&key));
 }
+
+#[tokio::test]
+async fn special_builtin_errors_should_be_fatal_only_if_configured() {
+    type RealBuiltinEnvTestEnv = TestEnvWithBuiltin<BuiltinEnv<Arc<String>>>;
+
+    async fn run_shift(fatal: bool) -> Result<ExitStatus, MockErr> {
+        let cfg = new_test_env_config!().change_builtin_env(BuiltinEnv::new());
+        let mut env: RealBuiltinEnvTestEnv = Env::with_config(EnvConfig {
+            options: ShellOptions {
+                fatal_special_builtin_errors: fatal,
+                ..ShellOptions::default()
+            },
+            ..cfg
+        });
+
+        let words = vec!["shift", "5"]
+            .into_iter()
+            .map(|w| RedirectOrCmdWord::CmdWord(mock_word_fields(Fields::Single(w.to_owned()))));
+
+        let future = simple_command::<MockRedirect<_>, String, _, _, _, _, _>(
+            vec![].into_iter(),
+            words,
+            &mut env,
+        );
+
+        Ok(future.await?.await)
+    }
+
+    assert_eq!(run_shift(false).await, Ok(EXIT_ERROR));
+
+    let err = CommandError::SpecialBuiltin("shift".to_owned(), EXIT_ERROR);
+    assert_eq!(
+        run_shift(true).await,
+        Err(MockErr::CommandError(Arc::new(err)))
+    );
+}
@@ -37,3 +37,101 @@ async fn should_terminate_on_fatal_errors_but_swallow_them() {
     let cmds = &[mock_error(true), mock_panic("should not run")];
     assert_eq!(EXIT_ERROR, subshell(sequence_slice(cmds), &new_env()).await);
 }
+
+#[tokio::test]
+async fn should_report_errors_in_subshell_context() {
+    let mut env = new_env_with_no_fds();
+    let pipe = env.open_pipe().expect("failed to open pipe");
+    env.set_file_desc(
+        conch_runtime::STDERR_FILENO,
+        pipe.writer,
+        conch_runtime::io::Permissions::Write,
+    );
+
+    let reader = env.read_all(pipe.reader);
+    let cmds = vec![mock_error(false)];
+    let exit = subshell(sequence_slice(&cmds), &env).await;
+    drop(env);
+
+    assert_eq!(exit, EXIT_ERROR);
+    let msg = String::from_utf8(reader.await.unwrap()).unwrap();
+    assert!(msg.contains(": in subshell: "), "{}", msg);
+}
+
+#[derive(Clone)]
+struct MockChangeVars;
+
+#[async_trait::async_trait]
+impl Spawn<DefaultEnvArc> for MockChangeVars {
+    type Error = MockErr;
+
+    async fn spawn(
+        &self,
+        env: &mut DefaultEnvArc,
+    ) -> Result<BoxFuture<'static, ExitStatus>, Self::Error> {
+        env.set_exported_var(name("changed"), name("new"), true);
+        env.set_var(name("ignored"), name("new"));
+        env.unset_var(&name("unset"));
+        Ok(Box::pin(async { ExitStatus::Code(42) }))
+    }
+}
+
+fn new_env_with_vars() -> DefaultEnvArc {
+    let mut env = new_env();
+    for var in &["changed", "ignored", "unset"] {
+        env.set_exported_var(name(var), name("old"), false);
+    }
+    env
+}
+
+#[tokio::test]
+async fn should_merge_chosen_vars_back_into_parent() {
+    let mut env = new_env_with_vars();
+    let merge = MergeVars::new(vec![name("changed"), name("unset"), name("missing")]);
+
+    let exit = subshell_with_merge(MockChangeVars, &mut env, merge).await;
+    assert_eq!(exit, ExitStatus::Code(42));
+
+    assert_eq!(
+        env.exported_var(&name("changed")),
+        Some((&name("new"), true))
+    );
+    assert_eq!(env.var(&name("ignored")), Some(&name("old")));
+    assert_eq!(env.var(&name("unset")), None);
+    assert_eq!(env.var(&name("missing")), None);
+}
+
+#[tokio::test]
+async fn should_merge_via_closure() {
+    let mut env = new_env_with_vars();
+
+    let exit = subshell_with_merge(
+        MockChangeVars,
+        &mut env,
+        |parent: &mut DefaultEnvArc, sub: &DefaultEnvArc| {
+            let val = sub.var(&name("ignored")).cloned().unwrap();
+            parent.set_var(name("captured"), val);
+        },
+    )
+    .await;
+    assert_eq!(exit, ExitStatus::Code(42));
+
+    assert_eq!(env.var(&name("captured")), Some(&name("new")));
+    assert_eq!(env.var(&name("changed")), Some(&name("old")));
+    assert_eq!(env.var(&name("ignored")), Some(&name("old")));
+    assert_eq!(env.var(&name("unset")), Some(&name("old")));
+}
+
+#[tokio::test]
+async fn should_merge_even_if_subshell_errors() {
+    let mut env = new_env();
+    let mut merged = false;
+
+    let exit = subshell_with_merge(mock_error(false), &mut env, |_: &mut _, _: &_| {
+        merged = true;
+    })
+    .await;
+
+    assert_eq!(exit, EXIT_ERROR);
+    assert!(merged);
+}
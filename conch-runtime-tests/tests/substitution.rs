@@ -1,7 +1,7 @@
 #![deny(rust_2018_idioms)]
 use conch_runtime;
 
-use conch_runtime::spawn::substitution;
+use conch_runtime::spawn::{substitution, substitution_bytes};
 
 mod support;
 pub use self::support::*;
@@ -75,3 +75,28 @@ async fn should_trim_trailing_newlines() {
     )
     .await;
 }
+
+#[tokio::test]
+async fn should_lossily_convert_invalid_utf8() {
+    test(
+        "hello \u{FFFD}world",
+        vec![MockOutCmd::OutBytes(b"hello \xffworld\n")],
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn should_preserve_raw_bytes() {
+    let env = new_env();
+    let cmds = vec![
+        MockOutCmd::OutBytes(b"\x00\xff\xfe"),
+        MockOutCmd::Out("hello\r\n\n"),
+    ];
+    let future = substitution_bytes(sequence_slice(&cmds), &env);
+    drop(env);
+
+    assert_eq!(
+        &b"\x00\xff\xfehello"[..],
+        &*future.await.expect("future failed")
+    );
+}
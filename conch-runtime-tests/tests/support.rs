@@ -1,5 +1,6 @@
 #![deny(rust_2018_idioms)]
 
+use conch_parser::ast::builder::ArcBuilder;
 use conch_runtime::error::IsFatalError;
 use conch_runtime::STDOUT_FILENO;
 use std::fs::OpenOptions;
@@ -14,6 +15,7 @@ pub use conch_runtime::error::*;
 pub use conch_runtime::eval::*;
 pub use conch_runtime::path::*;
 pub use conch_runtime::spawn::{self, *};
+#[allow(unused_imports)]
 pub use conch_runtime::{ExitStatus, EXIT_ERROR, EXIT_SUCCESS};
 pub use futures_core::future::*;
 pub use futures_util::future::*;
@@ -127,6 +129,7 @@ pub enum MockCmd {
     Status(ExitStatus),
     Error(MockErr),
     Panic(&'static str),
+    Pending,
 }
 
 pub fn mock_status(status: ExitStatus) -> MockCmd {
@@ -141,6 +144,13 @@ pub fn mock_panic(msg: &'static str) -> MockCmd {
     MockCmd::Panic(msg)
 }
 
+/// A command which never finishes spawning, useful for cancellation tests.
+pub fn mock_pending() -> MockCmd {
+    MockCmd::Pending
+}
+
+impl CancelSafe for MockCmd {}
+
 #[async_trait::async_trait]
 impl<E: ?Sized + Send> Spawn<E> for MockCmd {
     type Error = MockErr;
@@ -150,6 +160,7 @@ impl<E: ?Sized + Send> Spawn<E> for MockCmd {
             MockCmd::Status(s) => Ok(Box::pin(async move { s })),
             MockCmd::Error(ref e) => Err(e.clone()),
             MockCmd::Panic(msg) => panic!("{}", msg),
+            MockCmd::Pending => pending().await,
         }
     }
 }
@@ -174,6 +185,11 @@ pub fn mock_word_panic(msg: &'static str) -> MockWord {
     MockWord::Panic(msg)
 }
 
+/// A word which never finishes evaluating, useful for cancellation tests.
+pub fn mock_word_pending() -> MockWord {
+    MockWord::Pending
+}
+
 #[must_use]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum MockWord {
@@ -181,6 +197,7 @@ pub enum MockWord {
     Error(MockErr),
     AssertCfg(WordEvalConfig, Option<Fields<String>>),
     Panic(&'static str),
+    Pending,
 }
 
 #[async_trait::async_trait]
@@ -205,6 +222,7 @@ where
             MockWord::AssertCfg(_, f) => f.clone().unwrap_or(Fields::Zero),
             MockWord::Error(e) => return Err(e.clone()),
             MockWord::Panic(msg) => panic!("{}", msg),
+            MockWord::Pending => pending().await,
         };
 
         Ok(Box::pin(async move { fields }))
@@ -248,6 +266,7 @@ impl<E: ?Sized> ParamEval<E> for MockParam {
 #[derive(Debug, Clone)]
 pub enum MockOutCmd {
     Out(&'static str),
+    OutBytes(&'static [u8]),
     Cmd(MockCmd),
 }
 
@@ -263,7 +282,8 @@ where
     async fn spawn(&self, env: &mut E) -> Result<BoxFuture<'static, ExitStatus>, Self::Error> {
         match *self {
             MockOutCmd::Cmd(ref cmd) => cmd.spawn(env).await,
-            MockOutCmd::Out(ref msg) => {
+            MockOutCmd::Out(msg) => MockOutCmd::OutBytes(msg.as_bytes()).spawn(env).await,
+            MockOutCmd::OutBytes(msg) => {
                 let fd = env
                     .file_desc(STDOUT_FILENO)
                     .expect("failed to get stdout")
@@ -271,7 +291,7 @@ where
                     .clone()
                     .into();
 
-                env.write_all(fd, msg.as_bytes().into())
+                env.write_all(fd, msg.into())
                     .await
                     .expect("failed to write all");
 
@@ -323,6 +343,42 @@ pub fn new_env_with_no_fds() -> DefaultEnvArc {
     DefaultEnvArc::with_config(cfg)
 }
 
+/// Creates a (shell) name or value, e.g. of a variable or function.
+#[allow(dead_code)]
+pub fn name(s: &str) -> Arc<String> {
+    Arc::new(s.to_owned())
+}
+
+/// Parses and executes a complete script within the session.
+#[allow(dead_code)]
+pub async fn run<E>(session: &mut Session<E>, script: &str) -> Result<ExitStatus, RuntimeError>
+where
+    E: Send
+        + IsInteractiveEnvironment
+        + LastStatusEnvironment
+        + ParseScriptEnvironment
+        + ReportErrorEnvironment
+        + ScriptRunnerEnvironment
+        + SignalEnvironment,
+    E::Command: Send + Sync + Spawn<E>,
+    <E::Command as Spawn<E>>::Error: IsFatalError,
+    <ArcBuilder as conch_parser::ast::builder::Builder>::Command: Spawn<E, Error = RuntimeError>,
+{
+    let batch = session
+        .parse::<ArcBuilder>(script)
+        .expect("failed to parse script");
+    session.execute(batch).await
+}
+
+/// Gets the value of a variable within the session's environment, if it is set.
+#[allow(dead_code)]
+pub fn var(session: &Session<DefaultEnvArc>, name: &str) -> Option<String> {
+    session
+        .env()
+        .var(&Arc::new(name.to_owned()))
+        .map(|val| (**val).clone())
+}
+
 pub fn bin_path(s: &str) -> ::std::path::PathBuf {
     let mut me = ::std::env::current_exe().unwrap();
     me.pop();
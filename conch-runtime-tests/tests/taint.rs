@@ -0,0 +1,271 @@
+#![deny(rust_2018_idioms)]
+
+use conch_parser::ast;
+use std::convert::Infallible;
+use std::io;
+use std::sync::{Arc, Mutex};
+
+#[macro_use]
+mod support;
+pub use self::support::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Spawned {
+    name: (String, bool),
+    args: Vec<(String, bool)>,
+    tainted: bool,
+}
+
+/// An executable environment which records all commands instead of running them.
+#[derive(Debug, Default, Clone)]
+struct RecordingExecEnv {
+    spawned: Arc<Mutex<Vec<Spawned>>>,
+}
+
+impl SubEnvironment for RecordingExecEnv {
+    fn sub_env(&self) -> Self {
+        self.clone()
+    }
+}
+
+impl ExecutableEnvironment for RecordingExecEnv {
+    fn spawn_executable(
+        &self,
+        data: ExecutableData<'_>,
+    ) -> Result<BoxFuture<'static, ExitStatus>, CommandError> {
+        let args = data
+            .args
+            .iter()
+            .enumerate()
+            .map(|(i, arg)| (arg.to_str().unwrap().to_owned(), data.is_arg_tainted(i)))
+            .collect();
+
+        self.spawned.lock().unwrap().push(Spawned {
+            name: (data.name.to_str().unwrap().to_owned(), data.name_tainted),
+            args,
+            tainted: data.is_tainted(),
+        });
+
+        Ok(Box::pin(async { EXIT_SUCCESS }))
+    }
+}
+
+impl ProcessTimesEnvironment for RecordingExecEnv {
+    fn process_times(&self) -> io::Result<ProcessTimes> {
+        unimplemented!()
+    }
+}
+
+impl ResourceLimitEnvironment for RecordingExecEnv {
+    fn resource_limit(&self, _resource: Resource) -> io::Result<ResourceLimit> {
+        unimplemented!()
+    }
+
+    fn set_resource_limit(&mut self, _resource: Resource, _limit: ResourceLimit) -> io::Result<()> {
+        unimplemented!()
+    }
+}
+
+impl ExecReplaceEnvironment for RecordingExecEnv {
+    fn exec_replace(
+        &self,
+        data: ExecutableData<'_>,
+    ) -> Result<BoxFuture<'static, Infallible>, CommandError> {
+        Err(CommandError::NotFound(
+            data.name.to_str().unwrap().to_owned(),
+        ))
+    }
+}
+
+fn taint_vars<E>(env: &mut E)
+where
+    E: ?Sized + TaintEnvironment<VarName = Arc<String>, Var = Arc<String>>,
+{
+    env.set_var(
+        Arc::new("untrusted".to_owned()),
+        Arc::new("evil".to_owned()),
+    );
+    env.set_var(Arc::new("safe".to_owned()), Arc::new("good".to_owned()));
+    env.set_tainted(Arc::new("untrusted".to_owned()), true);
+}
+
+async fn spawned_by(script: &str) -> Vec<Spawned> {
+    let exec_env = RecordingExecEnv::default();
+    let cfg = DefaultEnvConfigArc::new()
+        .unwrap()
+        .change_exec_env(exec_env.clone());
+
+    let mut env = Env::with_config(EnvConfig {
+        file_desc_manager_env: TokioFileDescManagerEnv::new(),
+        ..cfg
+    });
+    taint_vars(&mut env);
+
+    let mut session = Session::new(env);
+    run(&mut session, script).await.unwrap();
+
+    let spawned = exec_env.spawned.lock().unwrap();
+    spawned.clone()
+}
+
+fn arg(value: &str, tainted: bool) -> (String, bool) {
+    (value.to_owned(), tainted)
+}
+
+#[test]
+fn tainted_fields_should_track_taint_per_field() {
+    let mut fields = TaintedFields::new();
+    assert!(!fields.is_tainted());
+
+    fields.extend(vec!["a", "b"], false);
+    fields.extend(vec!["c"], true);
+    fields.extend(Vec::new(), true);
+
+    assert_eq!(fields.len(), 3);
+    assert!(fields.is_tainted());
+    assert!(!fields.is_field_tainted(1));
+    assert!(fields.is_field_tainted(2));
+    assert!(!fields.is_field_tainted(3));
+    assert_eq!(fields.taint(), &[false, false, true]);
+    assert_eq!(fields.tainted_fields().collect::<Vec<_>>(), vec![&"c"]);
+    assert_eq!(fields.into_fields(), vec!["a", "b", "c"]);
+}
+
+#[tokio::test]
+async fn eval_with_taint_should_detect_tainted_expansions() {
+    type SimpleWord = ast::SimpleWord<String, ast::Parameter<String>, MockWord>;
+
+    let mut env = DefaultEnv::<String>::new().unwrap();
+    env.set_var("untrusted".to_owned(), "evil".to_owned());
+    env.set_var("safe".to_owned(), "good".to_owned());
+    env.set_tainted("untrusted".to_owned(), true);
+
+    let param = |name: &str| SimpleWord::Param(ast::Parameter::Var(name.to_owned()));
+
+    let safe = eval_with_taint(param("safe"), &mut env).await.unwrap();
+    assert!(!safe.is_tainted());
+    assert_eq!(safe.fields(), &["good".to_owned()]);
+
+    let untrusted = eval_with_taint(param("untrusted"), &mut env).await.unwrap();
+    assert!(untrusted.is_tainted());
+    assert_eq!(untrusted.fields(), &["evil".to_owned()]);
+
+    let literal = eval_with_taint(SimpleWord::Literal("lit".to_owned()), &mut env)
+        .await
+        .unwrap();
+    assert!(!literal.is_tainted());
+}
+
+#[tokio::test]
+async fn should_report_tainted_argv_to_executables() {
+    let spawned = spawned_by(concat!(
+        "cmd $safe \"pre${untrusted}post\" $(echo $untrusted) lit\n",
+        "$untrusted $safe\n",
+        "cmd ${#safe} ${untrusted:+x}\n",
+        "cmd $safe\n",
+    ))
+    .await;
+
+    assert_eq!(
+        spawned,
+        vec![
+            Spawned {
+                name: arg("cmd", false),
+                args: vec![
+                    arg("good", false),
+                    arg("preevilpost", true),
+                    arg("evil", true),
+                    arg("lit", false),
+                ],
+                tainted: true,
+            },
+            Spawned {
+                name: arg("evil", true),
+                args: vec![arg("good", false)],
+                tainted: true,
+            },
+            Spawned {
+                name: arg("cmd", false),
+                args: vec![arg("4", false), arg("x", true)],
+                tainted: true,
+            },
+            Spawned {
+                name: arg("cmd", false),
+                args: vec![arg("good", false)],
+                tainted: false,
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn should_propagate_taint_through_assignments_and_read() {
+    let spawned = spawned_by(concat!(
+        "y=$untrusted; z=$safe\n",
+        "cmd $y $z\n",
+        "read r <<EOF\n$untrusted\nEOF\n",
+        "read s <<EOF\n$safe\nEOF\n",
+        "cmd $r $s\n",
+    ))
+    .await;
+
+    assert_eq!(
+        spawned,
+        vec![
+            Spawned {
+                name: arg("cmd", false),
+                args: vec![arg("evil", true), arg("good", false)],
+                tainted: true,
+            },
+            Spawned {
+                name: arg("cmd", false),
+                args: vec![arg("evil", true), arg("good", false)],
+                tainted: true,
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn should_consider_scripts_sourced_from_tainted_paths_tainted() {
+    let tempdir = mktmp!();
+    let lib = tempdir.path().join("lib.sh");
+    std::fs::write(&lib, "cmd $safe\n").unwrap();
+
+    let spawned = spawned_by(&format!(
+        "lib='{}'\n. \"$lib${{untrusted:+}}\"\n. \"$lib\"\n",
+        lib.display()
+    ))
+    .await;
+
+    assert_eq!(
+        spawned,
+        vec![
+            Spawned {
+                name: arg("cmd", true),
+                args: vec![arg("good", true)],
+                tainted: true,
+            },
+            Spawned {
+                name: arg("cmd", false),
+                args: vec![arg("good", false)],
+                tainted: false,
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn background_jobs_should_not_record_expansions_for_the_shell() {
+    let mut env = new_env_with_no_fds();
+    taint_vars(&mut env);
+
+    let tracker = env.taint_tracker().clone();
+    let mut session = Session::new(env);
+    run(&mut session, "x=$untrusted & wait\ny=$untrusted")
+        .await
+        .unwrap();
+
+    assert_eq!(tracker.expansions(), 1);
+    assert!(session.env().is_tainted(&name("y")));
+}
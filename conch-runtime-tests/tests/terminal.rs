@@ -0,0 +1,70 @@
+#![deny(rust_2018_idioms)]
+
+use conch_runtime::io::{Permissions, TerminalMode, WindowSize};
+use conch_runtime::{STDIN_FILENO, STDOUT_FILENO};
+
+mod support;
+pub use self::support::*;
+
+#[test]
+fn raw_and_cooked_modes() {
+    assert_eq!(
+        TerminalMode::cooked(),
+        TerminalMode {
+            canonical: true,
+            echo: true,
+        }
+    );
+    assert_eq!(
+        TerminalMode::raw(),
+        TerminalMode {
+            canonical: false,
+            echo: false,
+        }
+    );
+}
+
+#[tokio::test]
+async fn pipes_and_files_are_not_terminals() {
+    let mut env = new_env_with_no_fds();
+
+    let pipe = env.open_pipe().unwrap();
+    let null = dev_null(&mut env);
+    env.set_file_desc(STDIN_FILENO, pipe.reader, Permissions::Read);
+    env.set_file_desc(STDOUT_FILENO, null, Permissions::Write);
+
+    for &fd in &[STDIN_FILENO, STDOUT_FILENO] {
+        assert!(!env.is_terminal(fd));
+        assert!(env.terminal_mode(fd).is_err());
+        assert!(env.set_terminal_mode(fd, TerminalMode::raw()).is_err());
+        assert!(env.window_size(fd).is_err());
+    }
+}
+
+#[tokio::test]
+async fn closed_fds_are_not_terminals() {
+    let mut env = new_env_with_no_fds();
+
+    assert!(!env.is_terminal(STDIN_FILENO));
+    assert!(env.terminal_mode(STDIN_FILENO).is_err());
+    assert!(env
+        .set_terminal_mode(STDIN_FILENO, TerminalMode::cooked())
+        .is_err());
+    assert!(env.window_size(STDIN_FILENO).is_err());
+}
+
+#[tokio::test]
+async fn window_size_vars_untouched_if_not_a_terminal() {
+    let mut env = new_env_with_no_fds();
+    let columns = "COLUMNS".to_owned();
+    let lines = "LINES".to_owned();
+
+    env.set_var(columns.clone().into(), "42".to_owned().into());
+    env.unset_var(&lines.clone().into());
+
+    let ret: Result<WindowSize, _> = update_window_size_vars(&mut env, STDOUT_FILENO);
+    assert!(ret.is_err());
+
+    assert_eq!(env.var(&columns).map(|v| &***v), Some("42"));
+    assert_eq!(env.var(&lines), None);
+}
@@ -3,6 +3,7 @@
 use conch_parser::ast;
 use std::sync::Arc;
 
+#[macro_use]
 mod support;
 pub use self::support::*;
 
@@ -33,3 +34,65 @@ async fn smoke() {
 
     assert_eq!(EXIT_SUCCESS, future.await);
 }
+
+#[tokio::test]
+async fn spawn_top_level_runs_commands() {
+    use conch_parser::ast::builder::ArcBuilder;
+    use conch_runtime::spawn::ast_impl::spawn_top_level;
+
+    let mut session = Session::new(new_env_with_no_fds());
+    let cmds = session
+        .parse::<ArcBuilder>("x=foo; false\ny=\"$x $?\"\n")
+        .unwrap();
+    let mut env = session.env().clone();
+
+    let mut status = EXIT_SUCCESS;
+    for cmd in &cmds {
+        status = spawn_top_level(cmd, &mut env).await.unwrap().await;
+        env.set_last_status(status);
+    }
+
+    assert_eq!(status, EXIT_SUCCESS);
+    assert_eq!(
+        env.var(&Arc::new("y".to_owned())),
+        Some(&Arc::new("foo 1".to_owned()))
+    );
+}
+
+#[tokio::test]
+async fn commands_without_names_complete_with_last_substitution_status() {
+    use conch_parser::ast::builder::ArcBuilder;
+
+    let tempdir = mktmp!();
+    let mut env = new_env_with_no_fds();
+    env.change_working_dir(tempdir.path().into()).unwrap();
+
+    let mut session = Session::new(env);
+    let cmds = session
+        .parse::<ArcBuilder>(concat!(
+            "x=$(false); a=$?\n",
+            "false; x=foo; b=$?\n",
+            "x=$(true) y=$(false); c=$?\n",
+            "x=$(false) y=$(true); d=$?\n",
+            "x=$(false) true; e=$?\n",
+            ">\"$(echo out; false)\"; f=$?\n",
+        ))
+        .unwrap();
+
+    session.execute(cmds).await.unwrap();
+
+    let var = |name: &str| {
+        session
+            .env()
+            .var(&Arc::new(name.to_owned()))
+            .map(|val| (**val).clone())
+    };
+
+    assert_eq!(var("a"), Some("1".to_owned()));
+    assert_eq!(var("b"), Some("0".to_owned()));
+    assert_eq!(var("c"), Some("1".to_owned()));
+    assert_eq!(var("d"), Some("0".to_owned()));
+    assert_eq!(var("e"), Some("0".to_owned()));
+    assert_eq!(var("f"), Some("1".to_owned()));
+    assert!(tempdir.path().join("out").exists());
+}
@@ -0,0 +1,106 @@
+#![deny(rust_2018_idioms)]
+
+use conch_parser::ast::builder::ArcBuilder;
+use conch_runtime::io::Permissions;
+use conch_runtime::validate::{validate, ValidationIssue};
+use std::sync::Arc;
+
+mod support;
+pub use self::support::*;
+
+fn validate_script(env: &DefaultEnvArc, script: &str) -> Vec<ValidationIssue> {
+    let mut session = Session::new(());
+    let cmds = session.parse::<ArcBuilder>(script).unwrap();
+    validate(&cmds, env)
+}
+
+#[tokio::test]
+async fn valid_script_has_no_issues() {
+    let mut env = new_env_with_no_fds();
+    env.options_mut().nounset = true;
+    env.set_var(Arc::new("set".to_owned()), Arc::new("value".to_owned()));
+
+    let script = concat!(
+        "f() { echo \"$set\" \"${unset:-default}\" \"${#set}\"; }\n",
+        "for i in a b; do f \"$i\"; done\n",
+        "if true; then read -r line; fi; echo \"$line\"\n",
+        "{ echo hello >&3; } 3>/dev/null\n",
+        "x=$((y = 5)); echo $x $y\n",
+        "./some/script\n",
+        "$cmd arg\n",
+    );
+
+    assert_eq!(validate_script(&env, script), vec![]);
+}
+
+#[tokio::test]
+async fn reports_unknown_commands() {
+    let env = new_env_with_no_fds();
+
+    let script = concat!(
+        "echo start\n",
+        "if true; then not_a_command_abc; fi\n",
+        "echo $(also_not_a_command_xyz) | cat\n",
+        "f; f() { :; }\n",
+    );
+
+    assert_eq!(
+        validate_script(&env, script),
+        vec![
+            ValidationIssue::UnknownCommand("not_a_command_abc".to_owned()),
+            ValidationIssue::UnknownCommand("also_not_a_command_xyz".to_owned()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn reports_unset_parameters_under_nounset() {
+    let mut env = new_env_with_no_fds();
+
+    let script = "echo $a ${#b} ${c%.txt} ${d:-default}; e=$f\n";
+    assert_eq!(validate_script(&env, script), vec![]);
+
+    env.options_mut().nounset = true;
+    assert_eq!(
+        validate_script(&env, script),
+        vec![
+            ValidationIssue::UnsetParameter("a".to_owned()),
+            ValidationIssue::UnsetParameter("b".to_owned()),
+            ValidationIssue::UnsetParameter("c".to_owned()),
+            ValidationIssue::UnsetParameter("f".to_owned()),
+        ]
+    );
+
+    // Assigning the variable anywhere in the script may avoid the issue
+    assert_eq!(validate_script(&env, "echo $a; read a\n"), vec![]);
+
+    // Any command which could do anything may also avoid the issue
+    assert_eq!(validate_script(&env, "$cmd; echo $a\n"), vec![]);
+}
+
+#[tokio::test]
+async fn reports_redirects_to_closed_file_descriptors() {
+    let mut env = new_env_with_no_fds();
+
+    let script = "echo hello >&1 2>&5; cat <&0 <&-\n";
+    assert_eq!(
+        validate_script(&env, script),
+        vec![
+            ValidationIssue::UnwritableFileDesc(1),
+            ValidationIssue::UnwritableFileDesc(5),
+            ValidationIssue::UnreadableFileDesc(0),
+        ]
+    );
+
+    let pipe = env.open_pipe().unwrap();
+    env.set_file_desc(0, pipe.writer.into(), Permissions::Write);
+    env.set_file_desc(1, pipe.reader.into(), Permissions::Read);
+
+    assert_eq!(
+        validate_script(&env, "echo >&1; cat <&0; cat 5<file 4<&5\n"),
+        vec![
+            ValidationIssue::UnwritableFileDesc(1),
+            ValidationIssue::UnreadableFileDesc(0),
+        ]
+    );
+}
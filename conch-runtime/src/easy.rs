@@ -0,0 +1,204 @@
+//! A simplified interface for running shell scripts, which hides the generic
+//! environment (and AST) machinery behind sane defaults.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), conch_runtime::easy::ShellError> {
+//! use conch_runtime::easy::Shell;
+//!
+//! let mut shell = Shell::new()?;
+//! let stdout = shell.stdout_capture();
+//!
+//! shell.set_var("NAME", "world");
+//! let status = shell.run_str("echo \"hello $NAME\"").await?;
+//!
+//! assert!(status.success());
+//! assert_eq!(stdout.take_string(), "hello world\n");
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::env::{
+    AsyncIoEnvironment, ControlFlow, DefaultEnvArc, FileDescEnvironment, FileDescOpener, Pipe,
+    UnsetVariableEnvironment, VariableEnvironment,
+};
+use crate::error::{control_flow_for_error, RuntimeError};
+use crate::io::Permissions;
+use crate::spawn::Session;
+use crate::{ExitStatus, Spawn, STDOUT_FILENO};
+use conch_parser::ast::builder::ArcBuilder;
+use conch_parser::parse::ParseError;
+use std::io;
+use std::mem;
+use std::sync::{Arc, Mutex};
+use void::Void;
+
+/// An error which may arise while running a script via a `Shell`.
+#[derive(Debug, thiserror::Error)]
+pub enum ShellError {
+    /// The script could not be parsed.
+    #[error(transparent)]
+    Parse(#[from] ParseError<Void>),
+    /// A (fatal) error occurred while executing the script.
+    #[error(transparent)]
+    Runtime(#[from] RuntimeError),
+    /// An I/O error occurred while setting up the shell, or capturing its output.
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// A shell which executes scripts against a single (default) environment.
+///
+/// Any changes a script makes to the environment (e.g. variables, functions,
+/// or the working directory) persist across runs, just like in an ordinary
+/// shell. The shell starts off with the environment variables and working
+/// directory of the current process, and is not interactive.
+#[derive(Debug)]
+pub struct Shell {
+    session: Session<DefaultEnvArc>,
+    stdout: Option<StdoutCapture>,
+}
+
+impl Shell {
+    /// Creates a new shell which uses the standard I/O of the current process.
+    pub fn new() -> io::Result<Self> {
+        DefaultEnvArc::new().map(Self::with_env)
+    }
+
+    /// Creates a new shell which executes scripts against the provided environment.
+    pub fn with_env(env: DefaultEnvArc) -> Self {
+        Self {
+            session: Session::new(env),
+            stdout: None,
+        }
+    }
+
+    /// Get a reference to the shell's environment.
+    pub fn env(&self) -> &DefaultEnvArc {
+        self.session.env()
+    }
+
+    /// Get a mutable reference to the shell's environment.
+    pub fn env_mut(&mut self) -> &mut DefaultEnvArc {
+        self.session.env_mut()
+    }
+
+    /// Get the value of a shell variable, if it is set.
+    pub fn var(&self, name: &str) -> Option<&str> {
+        self.env().var(&name.to_owned()).map(|val| val.as_str())
+    }
+
+    /// Sets the value of a shell variable.
+    pub fn set_var(&mut self, name: &str, value: &str) {
+        let name = Arc::new(name.to_owned());
+        let value = Arc::new(value.to_owned());
+        self.env_mut().set_var(name, value);
+    }
+
+    /// Unsets a shell variable.
+    pub fn unset_var(&mut self, name: &str) {
+        self.env_mut().unset_var(&Arc::new(name.to_owned()));
+    }
+
+    /// Starts capturing the standard output of any scripts the shell runs
+    /// from now on (instead of writing it to the shell's standard output),
+    /// and returns a handle to the captured output.
+    ///
+    /// Note that each run waits until all writers of the captured output have
+    /// closed it, just like a command substitution does. Thus any background
+    /// commands (or file descriptors duplicated via `exec`) which still hold on
+    /// to the shell's standard output will delay the run's completion.
+    pub fn stdout_capture(&mut self) -> StdoutCapture {
+        self.stdout
+            .get_or_insert_with(StdoutCapture::default)
+            .clone()
+    }
+
+    /// Stops capturing the standard output of the shell.
+    ///
+    /// Any output already captured remains available via any existing handles.
+    pub fn stop_stdout_capture(&mut self) {
+        self.stdout = None;
+    }
+
+    /// Parses and executes a script, returning the status of the last command.
+    ///
+    /// If the script requests to `exit`, its status is returned. The script is
+    /// parsed in its entirety before any of its commands are executed, thus
+    /// nothing is executed if it contains a syntax error.
+    pub async fn run_str(&mut self, script: &str) -> Result<ExitStatus, ShellError> {
+        // NB: the script should be complete, so don't hold on to partial input
+        self.session.discard_pending_input();
+        let batch = self.session.parse::<ArcBuilder>(script)?;
+        if self.session.is_incomplete() {
+            self.session.discard_pending_input();
+            return Err(ShellError::Parse(ParseError::UnexpectedEOF));
+        }
+
+        let capture = match self.stdout.clone() {
+            Some(capture) => capture,
+            None => return Ok(execute(&mut self.session, batch).await?),
+        };
+
+        let env = self.session.env_mut();
+        let Pipe { reader, writer } = env.open_pipe()?;
+        let prev_stdout = env
+            .file_desc(STDOUT_FILENO)
+            .map(|(handle, perms)| (handle.clone(), perms));
+        env.set_file_desc(STDOUT_FILENO, writer, Permissions::Write);
+
+        let output = env.read_all(reader);
+        let session = &mut self.session;
+        let run = async move {
+            let result = execute(session, batch).await;
+
+            // NB: drop our handle to the writer so the output can reach its end
+            let env = session.env_mut();
+            match prev_stdout {
+                Some((handle, perms)) => env.set_file_desc(STDOUT_FILENO, handle, perms),
+                None => env.close_file_desc(STDOUT_FILENO),
+            }
+
+            result
+        };
+
+        let (output, result) = futures_util::join!(output, run);
+        capture.buf.lock().unwrap().extend_from_slice(&output?);
+        Ok(result?)
+    }
+}
+
+async fn execute<I>(
+    session: &mut Session<DefaultEnvArc>,
+    batch: I,
+) -> Result<ExitStatus, RuntimeError>
+where
+    I: IntoIterator,
+    I::Item: Spawn<DefaultEnvArc, Error = RuntimeError>,
+{
+    match session.execute(batch).await {
+        Ok(status) => Ok(status),
+        Err(e) => match control_flow_for_error(&e) {
+            Some(ControlFlow::Exit(status)) => Ok(status),
+            _ => Err(e),
+        },
+    }
+}
+
+/// A handle to the standard output captured by a `Shell` (see `Shell::stdout_capture`).
+#[derive(Debug, Default, Clone)]
+pub struct StdoutCapture {
+    buf: Arc<Mutex<Vec<u8>>>,
+}
+
+impl StdoutCapture {
+    /// Takes all output captured so far, leaving the capture empty.
+    pub fn take(&self) -> Vec<u8> {
+        mem::take(&mut *self.buf.lock().unwrap())
+    }
+
+    /// Takes all output captured so far (lossily converted to UTF-8),
+    /// leaving the capture empty.
+    pub fn take_string(&self) -> String {
+        String::from_utf8_lossy(&self.take()).into_owned()
+    }
+}
@@ -1,50 +1,138 @@
 //! This module defines various interfaces and implementations of shell environments.
 //! See the documentation around `Env` or `DefaultEnv` to get started.
 
+use crate::error::ErrorContext;
 use futures_core::future::BoxFuture;
 use std::error::Error;
 
+mod alias;
 mod args;
 mod async_io;
 pub mod builtin;
+#[cfg(all(target_os = "linux", feature = "cgroups"))]
+mod cgroup;
+mod change_set;
+mod clock;
+mod completion;
 mod cur_dir;
+mod diagnostics;
 mod env_impl;
+mod exec_report;
 mod executable;
 mod fd;
 mod fd_manager;
 mod fd_opener;
 mod func;
+mod getopts;
+mod home_dir;
+mod jobs;
 mod last_status;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+mod middleware;
+mod options;
+mod parse;
+mod process_times;
+mod random;
+pub mod remote;
+mod resolve;
+mod resource_limit;
 mod restorer;
+mod search;
+mod shared;
+mod signal;
+mod simple;
+#[cfg(all(unix, feature = "spawn-policy"))]
+mod spawn_policy;
 mod string_wrapper;
+mod taint;
+mod terminal;
+mod usage;
 mod var;
 
+pub use self::alias::{AliasEnv, AliasEnvironment};
 pub use self::args::{
-    ArgsEnv, ArgumentsEnvironment, SetArgumentsEnvironment, ShiftArgumentsEnvironment,
+    ArgsEnv, ArgumentsEnvironment, ModifyArgumentsEnvironment, SetArgumentsEnvironment,
+    ShiftArgumentsEnvironment,
+};
+pub use self::async_io::{
+    read_exact_timeout, read_until, ArcUnwrappingAsyncIoEnv, AsyncIoEnvironment, BoxAsyncRead,
+    TokioAsyncIoEnv,
 };
-pub use self::async_io::{ArcUnwrappingAsyncIoEnv, AsyncIoEnvironment, TokioAsyncIoEnv};
 pub use self::builtin::{Builtin, BuiltinEnvironment};
+#[cfg(all(target_os = "linux", feature = "cgroups"))]
+pub use self::cgroup::{Cgroup, CgroupLimits};
+pub use self::change_set::EnvChangeSet;
+pub use self::clock::{Clock, ClockEnvironment, ManualClock};
+pub use self::completion::{
+    complete_command, complete_path, CommandCompletion, CommandCompletionKind, PathCompletion,
+};
 pub use self::cur_dir::{
     ChangeWorkingDirectoryEnvironment, VirtualWorkingDirEnv, WorkingDirectoryEnvironment,
 };
+pub use self::diagnostics::{FdInfo, IoBackend, RuntimeInfo, RuntimeInfoEnvironment};
 pub use self::env_impl::{
     DefaultEnv, DefaultEnvArc, DefaultEnvConfig, DefaultEnvConfigArc, Env, EnvConfig,
 };
-pub use self::executable::{ExecutableData, ExecutableEnvironment, TokioExecEnv};
-pub use self::fd::{FileDescEnv, FileDescEnvironment};
+pub use self::exec_report::{
+    CommandReport, CommandReportHandle, ExecutionReport, ExecutionReportEnvironment,
+    ExecutionReporter, RedirectReport,
+};
+pub use self::executable::{
+    ExecReplaceEnvironment, ExecutableData, ExecutableDataBuilder, ExecutableEnvironment,
+    ExecutableStdio, ResourceUsage, SpawnIsolation, TokioExecEnv,
+};
+pub use self::fd::{FileDescAuditEnvironment, FileDescEnv, FileDescEnvironment};
 pub use self::fd_manager::{
     FileDescManagerEnv, FileDescManagerEnvironment, TokioFileDescManagerEnv,
 };
-pub use self::fd_opener::{ArcFileDescOpenerEnv, FileDescOpener, FileDescOpenerEnv, Pipe};
+pub use self::fd_opener::{
+    ArcFileDescOpenerEnv, AuditFileDescOpenerEnv, FdUsageTracker, FileDescOpener,
+    FileDescOpenerEnv, FileOpenAuditor, FileOpenEvent, OpenFlags, Pipe,
+};
 pub use self::func::{
-    FnEnv, FnFrameEnv, FunctionEnvironment, FunctionFrameEnvironment, UnsetFunctionEnvironment,
+    ControlFlow, ControlFlowEnvironment, FnEnv, FnFrameEnv, FunctionEnvironment,
+    FunctionFrameEnvironment, UnsetFunctionEnvironment,
+};
+pub use self::getopts::{GetoptsEnv, GetoptsEnvironment, GetoptsState};
+pub use self::home_dir::HomeDirEnvironment;
+pub use self::jobs::{JobEnv, JobEnvironment, JobId};
+pub use self::last_status::{LastStatusEnv, LastStatusEnvironment, SubstitutionStatusEnvironment};
+pub use self::middleware::{NextSpawn, SpawnContext, SpawnMiddleware, SpawnMiddlewareEnvironment};
+pub use self::options::{ShellOptions, ShellOptionsEnvironment};
+pub use self::parse::{
+    ParseScriptEnvironment, RunScriptFn, ScriptParseError, ScriptRunner, ScriptRunnerEnvironment,
+    SourceStackEnvironment,
+};
+pub use self::process_times::{ProcessTimes, ProcessTimesEnvironment};
+pub use self::random::{FastRng, RandomEnvironment, Rng};
+pub use self::resolve::{ResolveCommandEnvironment, ResolvedCommand};
+pub use self::resource_limit::{Resource, ResourceLimit, ResourceLimitEnvironment};
+pub use self::restorer::{
+    EnvRestorer, LastStatusRestorer, RedirectEnvRestorer, Restorer, ScopedFrame, ScopedRestorer,
+    VarEnvRestorer, WorkingDirRestorer,
 };
-pub use self::last_status::{LastStatusEnv, LastStatusEnvironment};
-pub use self::restorer::{EnvRestorer, RedirectEnvRestorer, Restorer, VarEnvRestorer};
+pub(crate) use self::search::is_executable;
+pub use self::search::CommandSearchEnvironment;
+pub use self::shared::{SharedEnv, SharedEnvGuard};
+pub use self::signal::{
+    Signal, SignalEnv, SignalEnvironment, SignalSender, TrapAction, UnknownSignalError,
+};
+pub use self::simple::{SimpleEnvAdapter, SimpleEnvironment};
+#[cfg(all(target_os = "linux", feature = "spawn-policy"))]
+pub use self::spawn_policy::NoNewPrivilegesPolicy;
+#[cfg(all(unix, feature = "spawn-policy"))]
+pub use self::spawn_policy::{PolicyInstaller, SpawnPolicy};
 pub use self::string_wrapper::StringWrapper;
+pub use self::taint::{TaintEnvironment, TaintTracker};
+pub use self::terminal::{update_window_size_vars, TerminalEnvironment};
+pub use self::usage::{ResourceUsageEnvironment, ResourceUsageTracker};
 pub use self::var::{
-    ExportedVariableEnvironment, UnsetVariableEnvironment, VarEnv, VariableEnvironment,
+    ArithVariableEnvironment, ExportedVariableEnvironment, ReadonlyVariableEnvironment,
+    UnsetVariableEnvironment, VarChange, VarEnv, VariableEnvironment, WatchVariableEnvironment,
 };
+#[cfg(windows)]
+pub use crate::sys::job::{JobLimits, JobObject};
 
 /// An interface for checking if the current environment is an interactive one.
 pub trait IsInteractiveEnvironment {
@@ -58,6 +146,50 @@ impl<'a, T: ?Sized + IsInteractiveEnvironment> IsInteractiveEnvironment for &'a
     }
 }
 
+/// An interface for tracking a displayable rendering of the currently executing
+/// command (e.g. `echo foo bar`), so that diagnostics can describe what was running
+/// even though the runtime has no notion of the original source text.
+pub trait CommandTextEnvironment {
+    /// Get the rendering of the currently executing command, if one was registered.
+    fn command_text(&self) -> Option<&str>;
+
+    /// Register the rendering of the currently executing command, returning the
+    /// previous rendering so that it can be restored once the command completes.
+    fn set_command_text(&mut self, text: Option<String>) -> Option<String>;
+}
+
+impl<'a, T: ?Sized + CommandTextEnvironment> CommandTextEnvironment for &'a mut T {
+    fn command_text(&self) -> Option<&str> {
+        (**self).command_text()
+    }
+
+    fn set_command_text(&mut self, text: Option<String>) -> Option<String> {
+        (**self).set_command_text(text)
+    }
+}
+
+/// A marker trait for environments which provide all of the pluggable services
+/// the runtime relies on while spawning commands: telling time, generating random
+/// numbers, tracking resource usage and tainted variables, and reporting executions.
+pub trait CapabilityEnvironment:
+    ClockEnvironment
+    + ExecutionReportEnvironment
+    + RandomEnvironment
+    + ResourceUsageEnvironment
+    + TaintEnvironment
+{
+}
+
+impl<T> CapabilityEnvironment for T where
+    T: ?Sized
+        + ClockEnvironment
+        + ExecutionReportEnvironment
+        + RandomEnvironment
+        + ResourceUsageEnvironment
+        + TaintEnvironment
+{
+}
+
 /// An interface for reporting arbitrary errors.
 pub trait ReportErrorEnvironment {
     /// Reports any `Error` as appropriate, e.g. print to stderr.
@@ -65,6 +197,16 @@ pub trait ReportErrorEnvironment {
         &mut self,
         fail: &'a (dyn Error + Sync + Send + 'static),
     ) -> BoxFuture<'a, ()>;
+
+    /// Denote that any subsequently reported errors occur within the specified
+    /// context (e.g. while executing a function), until the context is popped.
+    ///
+    /// Implementations may use this to describe where a reported error
+    /// originated, but are free to ignore it (which is the default behavior).
+    fn push_error_context(&mut self, _context: ErrorContext) {}
+
+    /// Denote that the most recently pushed error context has been exited.
+    fn pop_error_context(&mut self) {}
 }
 
 impl<'b, T: ?Sized + ReportErrorEnvironment> ReportErrorEnvironment for &'b mut T {
@@ -74,6 +216,14 @@ impl<'b, T: ?Sized + ReportErrorEnvironment> ReportErrorEnvironment for &'b mut
     ) -> BoxFuture<'a, ()> {
         (**self).report_error(fail)
     }
+
+    fn push_error_context(&mut self, context: ErrorContext) {
+        (**self).push_error_context(context)
+    }
+
+    fn pop_error_context(&mut self) {
+        (**self).pop_error_context()
+    }
 }
 
 /// An interface for all environments that can produce another environment,
@@ -0,0 +1,97 @@
+use crate::env::SubEnvironment;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// An interface for managing alias definitions, e.g. via the `alias` builtin.
+///
+/// Although aliases are substituted while parsing (rather than executing)
+/// commands, their definitions are owned by the environment since they are
+/// changed by executing commands. Any parser which supports aliases should
+/// consult `alias` when encountering the name of a simple command.
+pub trait AliasEnvironment {
+    /// Get the value of an alias, if it is defined.
+    fn alias(&self, name: &str) -> Option<&str>;
+    /// Get the definitions of all aliases, ordered by their names.
+    fn aliases(&self) -> Vec<(&str, &str)>;
+    /// Defines an alias, replacing any previous definition of the same name.
+    fn set_alias(&mut self, name: String, value: String);
+    /// Removes the definition of an alias, returning its previous value, if any.
+    fn unalias(&mut self, name: &str) -> Option<String>;
+    /// Removes the definitions of all aliases.
+    fn unalias_all(&mut self);
+}
+
+impl<'a, T: ?Sized + AliasEnvironment> AliasEnvironment for &'a mut T {
+    fn alias(&self, name: &str) -> Option<&str> {
+        (**self).alias(name)
+    }
+
+    fn aliases(&self) -> Vec<(&str, &str)> {
+        (**self).aliases()
+    }
+
+    fn set_alias(&mut self, name: String, value: String) {
+        (**self).set_alias(name, value)
+    }
+
+    fn unalias(&mut self, name: &str) -> Option<String> {
+        (**self).unalias(name)
+    }
+
+    fn unalias_all(&mut self) {
+        (**self).unalias_all()
+    }
+}
+
+/// An `AliasEnvironment` implementation which stores definitions in memory.
+///
+/// Definitions are shared (copy-on-write) with any sub-environments, which
+/// inherit them like subshells would.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AliasEnv {
+    aliases: Arc<BTreeMap<String, String>>,
+}
+
+impl AliasEnv {
+    /// Create a new environment instance without any aliases.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl AliasEnvironment for AliasEnv {
+    fn alias(&self, name: &str) -> Option<&str> {
+        self.aliases.get(name).map(String::as_str)
+    }
+
+    fn aliases(&self) -> Vec<(&str, &str)> {
+        self.aliases
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect()
+    }
+
+    fn set_alias(&mut self, name: String, value: String) {
+        Arc::make_mut(&mut self.aliases).insert(name, value);
+    }
+
+    fn unalias(&mut self, name: &str) -> Option<String> {
+        if self.aliases.contains_key(name) {
+            Arc::make_mut(&mut self.aliases).remove(name)
+        } else {
+            None
+        }
+    }
+
+    fn unalias_all(&mut self) {
+        if !self.aliases.is_empty() {
+            self.aliases = Arc::default();
+        }
+    }
+}
+
+impl SubEnvironment for AliasEnv {
+    fn sub_env(&self) -> Self {
+        self.clone()
+    }
+}
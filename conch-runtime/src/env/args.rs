@@ -1,6 +1,7 @@
 use crate::env::SubEnvironment;
 use std::borrow::Cow;
 use std::collections::VecDeque;
+use std::ops::{Bound, RangeBounds};
 use std::sync::Arc;
 
 /// An interface for getting shell and function arguments.
@@ -11,7 +12,8 @@ pub trait ArgumentsEnvironment {
     /// Get the name of the shell.
     fn name(&self) -> &Self::Arg;
     /// Get an argument at any index. Arguments are 1-indexed since the shell variable `$0`
-    /// refers to the shell's name. Thus the first real argument starts at index 1.
+    /// refers to the shell's name (or any override of `$0`). Thus the first real argument
+    /// starts at index 1.
     fn arg(&self, idx: usize) -> Option<&Self::Arg>;
     /// Get the number of current arguments, NOT including the shell name.
     fn args_len(&self) -> usize;
@@ -75,6 +77,40 @@ impl<'a, T: ?Sized + SetArgumentsEnvironment> SetArgumentsEnvironment for &'a mu
     }
 }
 
+/// An interface for modifying the current shell and function arguments in place.
+pub trait ModifyArgumentsEnvironment: ArgumentsEnvironment {
+    /// Overrides the value of `$0` without affecting the shell's name.
+    ///
+    /// Passing in `None` removes any existing override such that `$0`
+    /// will refer to the shell's name once again.
+    fn set_arg0(&mut self, arg0: Option<Self::Arg>);
+
+    /// Replaces the positional arguments within `range` with the contents of
+    /// `replace_with`. The range is 0-indexed, i.e. index 0 refers to `$1`, and
+    /// it is clamped to the number of current arguments.
+    ///
+    /// If the range is empty and there is nothing to insert, then no change to
+    /// the positional parameters should be made.
+    fn splice_args<R, I>(&mut self, range: R, replace_with: I)
+    where
+        R: RangeBounds<usize>,
+        I: IntoIterator<Item = Self::Arg>;
+}
+
+impl<'a, T: ?Sized + ModifyArgumentsEnvironment> ModifyArgumentsEnvironment for &'a mut T {
+    fn set_arg0(&mut self, arg0: Option<Self::Arg>) {
+        (**self).set_arg0(arg0)
+    }
+
+    fn splice_args<R, I>(&mut self, range: R, replace_with: I)
+    where
+        R: RangeBounds<usize>,
+        I: IntoIterator<Item = Self::Arg>,
+    {
+        (**self).splice_args(range, replace_with)
+    }
+}
+
 /// An interface for shifting positional shell and function arguments.
 pub trait ShiftArgumentsEnvironment {
     /// Shift parameters such that the positional parameter `n` will hold
@@ -82,11 +118,14 @@ pub trait ShiftArgumentsEnvironment {
     ///
     /// If `amt == 0`, then no change to the positional parameters
     /// should be made.
-    fn shift_args(&mut self, amt: usize);
+    ///
+    /// Returns the number of positional parameters which were actually shifted,
+    /// which is less than `amt` if there were not enough parameters available.
+    fn shift_args(&mut self, amt: usize) -> usize;
 }
 
 impl<'a, T: ?Sized + ShiftArgumentsEnvironment> ShiftArgumentsEnvironment for &'a mut T {
-    fn shift_args(&mut self, amt: usize) {
+    fn shift_args(&mut self, amt: usize) -> usize {
         (**self).shift_args(amt)
     }
 }
@@ -95,6 +134,7 @@ impl<'a, T: ?Sized + ShiftArgumentsEnvironment> ShiftArgumentsEnvironment for &'
 #[derive(Debug, PartialEq, Eq)]
 pub struct ArgsEnv<T> {
     name: Arc<T>,
+    arg0: Option<Arc<T>>,
     args: Arc<VecDeque<T>>,
 }
 
@@ -121,6 +161,7 @@ impl<T> ArgsEnv<T> {
     pub fn with_name(name: T) -> Self {
         ArgsEnv {
             name: name.into(),
+            arg0: None,
             args: Arc::new(VecDeque::new()),
         }
     }
@@ -130,6 +171,7 @@ impl<T> ArgsEnv<T> {
     pub fn with_name_and_args<I: IntoIterator<Item = T>>(name: T, args: I) -> Self {
         ArgsEnv {
             name: name.into(),
+            arg0: None,
             args: Arc::new(args.into_iter().collect()),
         }
     }
@@ -145,6 +187,7 @@ impl<T> Clone for ArgsEnv<T> {
     fn clone(&self) -> Self {
         ArgsEnv {
             name: self.name.clone(),
+            arg0: self.arg0.clone(),
             args: self.args.clone(),
         }
     }
@@ -165,7 +208,7 @@ impl<T: Clone> ArgumentsEnvironment for ArgsEnv<T> {
 
     fn arg(&self, idx: usize) -> Option<&Self::Arg> {
         if idx == 0 {
-            Some(self.name())
+            Some(self.arg0.as_ref().unwrap_or(&self.name))
         } else {
             self.args.get(idx - 1)
         }
@@ -192,30 +235,72 @@ impl<T: Clone> SetArgumentsEnvironment for ArgsEnv<T> {
     }
 }
 
+impl<T: Clone> ModifyArgumentsEnvironment for ArgsEnv<T> {
+    fn set_arg0(&mut self, arg0: Option<Self::Arg>) {
+        self.arg0 = arg0.map(Arc::new);
+    }
+
+    fn splice_args<R, I>(&mut self, range: R, replace_with: I)
+    where
+        R: RangeBounds<usize>,
+        I: IntoIterator<Item = Self::Arg>,
+    {
+        let len = self.args.len();
+        let start = match range.start_bound() {
+            Bound::Included(&n) => n,
+            Bound::Excluded(&n) => n.saturating_add(1),
+            Bound::Unbounded => 0,
+        }
+        .min(len);
+        let end = match range.end_bound() {
+            Bound::Included(&n) => n.saturating_add(1),
+            Bound::Excluded(&n) => n,
+            Bound::Unbounded => len,
+        }
+        .min(len)
+        .max(start);
+
+        let mut replace_with = replace_with.into_iter().peekable();
+        if start == end && replace_with.peek().is_none() {
+            // Avoid copying arguments we might be sharing if nothing will change
+            return;
+        }
+
+        let args = Arc::make_mut(&mut self.args);
+        let tail = args.split_off(end);
+        args.truncate(start);
+        args.extend(replace_with);
+        args.extend(tail);
+    }
+}
+
 impl<T: Clone> ShiftArgumentsEnvironment for ArgsEnv<T> {
-    fn shift_args(&mut self, amt: usize) {
+    fn shift_args(&mut self, amt: usize) -> usize {
         if amt == 0 {
-            return;
+            return 0;
         }
 
-        if amt >= self.args.len() {
+        let len = self.args.len();
+        if amt >= len {
             // Keep around the already allocated memory if we're the only owner.
             if let Some(args) = Arc::get_mut(&mut self.args) {
                 args.clear();
-                return;
+                return len;
             }
 
             // Otherwise just pretend we no longer have any arguments
             self.args = Arc::new(VecDeque::new());
+            return len;
         }
 
         if let Some(args) = Arc::get_mut(&mut self.args) {
             args.drain(0..amt);
-            return;
+            return amt;
         }
 
         // Since we're not the only owner we're forced to copy everything over.
         self.args = Arc::new(self.args.iter().skip(amt).cloned().collect());
+        amt
     }
 }
 
@@ -289,7 +374,7 @@ mod tests {
         let mut env = ArgsEnv::with_name_and_args("shell", vec!["1", "2", "3", "4", "5", "6"]);
         let _copy = env.sub_env();
 
-        env.shift_args(0);
+        assert_eq!(env.shift_args(0), 0);
         assert_eq!(env.args(), vec!("1", "2", "3", "4", "5", "6"));
         assert!(env.name.get_mut().is_none()); // No needless clone here
 
@@ -299,10 +384,72 @@ mod tests {
         env.shift_args(1);
         assert_eq!(env.args(), vec!("3", "4", "5", "6"));
 
-        env.shift_args(2);
+        assert_eq!(env.shift_args(2), 2);
         assert_eq!(env.args(), vec!("5", "6"));
 
-        env.shift_args(100);
+        assert_eq!(env.shift_args(100), 2);
         assert_eq!(env.args(), Vec::<&str>::new());
     }
+
+    #[test]
+    fn test_shift_all_shared_args() {
+        let mut env = ArgsEnv::with_name_and_args("shell", vec!["1", "2", "3"]);
+        let copy = env.sub_env();
+
+        assert_eq!(env.shift_args(3), 3);
+        assert_eq!(env.args(), Vec::<&str>::new());
+        assert_eq!(copy.args(), vec!("1", "2", "3"));
+    }
+
+    #[test]
+    fn test_set_arg0() {
+        let mut env = ArgsEnv::with_name_and_args("shell", vec!["1"]);
+
+        env.set_arg0(Some("script"));
+        assert_eq!(env.name(), &"shell");
+        assert_eq!(env.arg(0), Some(&"script"));
+        assert_eq!(env.arg(1), Some(&"1"));
+
+        // The override should be inherited by sub environments
+        let mut sub = env.sub_env();
+        assert_eq!(sub.arg(0), Some(&"script"));
+
+        sub.set_arg0(None);
+        assert_eq!(sub.arg(0), Some(&"shell"));
+        assert_eq!(env.arg(0), Some(&"script"));
+    }
+
+    #[test]
+    fn test_splice_args() {
+        let mut env = ArgsEnv::with_name_and_args("shell", vec!["1", "2", "3", "4"]);
+
+        env.splice_args(1..3, vec!["a", "b", "c"]);
+        assert_eq!(env.args(), vec!("1", "a", "b", "c", "4"));
+
+        env.splice_args(..1, None);
+        assert_eq!(env.args(), vec!("a", "b", "c", "4"));
+
+        env.splice_args(2.., vec!["z"]);
+        assert_eq!(env.args(), vec!("a", "b", "z"));
+
+        // Out of range bounds are clamped
+        env.splice_args(10..=20, vec!["end"]);
+        assert_eq!(env.args(), vec!("a", "b", "z", "end"));
+
+        env.splice_args(.., None);
+        assert_eq!(env.args(), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_splice_args_no_needless_clone() {
+        let mut env = ArgsEnv::with_name_and_args("shell", vec!["1", "2"]);
+        let copy = env.sub_env();
+
+        env.splice_args(1..1, None);
+        assert!(env.args.get_mut().is_none());
+
+        env.splice_args(0..1, vec!["one"]);
+        assert_eq!(env.args(), vec!("one", "2"));
+        assert_eq!(copy.args(), vec!("1", "2"));
+    }
 }
@@ -1,13 +1,20 @@
+use ::tokio::io::AsyncRead;
 use futures_core::future::BoxFuture;
 use std::borrow::Cow;
 use std::io;
+use std::pin::Pin;
 
+mod read;
 mod tokio;
 mod unwrapper;
 
+pub use self::read::{read_exact_timeout, read_until};
 pub use self::tokio::TokioAsyncIoEnv;
 pub use self::unwrapper::ArcUnwrappingAsyncIoEnv;
 
+/// A reader which asynchronously yields data from a file handle as it becomes available.
+pub type BoxAsyncRead = Pin<Box<dyn AsyncRead + Send>>;
+
 /// An interface for performing async operations on file handles.
 pub trait AsyncIoEnvironment {
     /// The underlying handle (e.g. `FileDesc`) with which to perform the async I/O.
@@ -16,6 +23,12 @@ pub trait AsyncIoEnvironment {
     /// Asynchronously read *all* data from the specified handle.
     fn read_all(&mut self, fd: Self::IoHandle) -> BoxFuture<'static, io::Result<Vec<u8>>>;
 
+    /// Creates a reader which can incrementally consume data from the specified handle.
+    ///
+    /// Unlike `read_all`, callers can stop reading at any point (e.g. once a
+    /// full line has been read) without having to wait for the handle to reach EOF.
+    fn read_async(&mut self, fd: Self::IoHandle) -> io::Result<BoxAsyncRead>;
+
     /// Asynchronously write `data` into the specified handle.
     fn write_all<'a>(
         &mut self,
@@ -40,6 +53,10 @@ where
         (**self).read_all(fd)
     }
 
+    fn read_async(&mut self, fd: Self::IoHandle) -> io::Result<BoxAsyncRead> {
+        (**self).read_async(fd)
+    }
+
     fn write_all<'a>(
         &mut self,
         fd: Self::IoHandle,
@@ -0,0 +1,75 @@
+use std::cmp;
+use std::io;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Reads bytes from `reader` and appends them to `buf` until the `delim`
+/// byte (which is also appended) or EOF is reached. Returns the number of
+/// bytes which were read.
+///
+/// Data is consumed one byte at a time so that nothing past the delimiter
+/// is read from the underlying handle, leaving it available to anyone else
+/// reading from it afterwards (e.g. the next command in a script).
+///
+/// The returned future is cancellation safe: each byte is appended to `buf`
+/// as soon as it is read, thus if the future is dropped before completing
+/// (e.g. due to a timeout), `buf` will hold everything consumed so far.
+pub async fn read_until<R>(reader: &mut R, delim: u8, buf: &mut Vec<u8>) -> io::Result<usize>
+where
+    R: ?Sized + AsyncRead + Unpin,
+{
+    let mut read = 0;
+
+    loop {
+        let mut byte = [0];
+        if reader.read(&mut byte).await? == 0 {
+            return Ok(read);
+        }
+
+        buf.push(byte[0]);
+        read += 1;
+
+        if byte[0] == delim {
+            return Ok(read);
+        }
+    }
+}
+
+/// Reads exactly `n` bytes from `reader` and appends them to `buf`,
+/// giving up if they could not be read within the specified `timeout`.
+///
+/// An error of kind `TimedOut` is returned if the timeout expires, and an error
+/// of kind `UnexpectedEof` is returned if EOF is reached before reading `n` bytes.
+/// In either case, any data which was read before the error is retained in `buf`.
+pub async fn read_exact_timeout<R>(
+    reader: &mut R,
+    n: usize,
+    buf: &mut Vec<u8>,
+    timeout: Duration,
+) -> io::Result<()>
+where
+    R: ?Sized + AsyncRead + Unpin,
+{
+    let read_future = async {
+        let mut remaining = n;
+        let mut chunk = [0; 512];
+
+        while remaining > 0 {
+            let len = cmp::min(remaining, chunk.len());
+            match reader.read(&mut chunk[..len]).await? {
+                0 => return Err(io::Error::from(io::ErrorKind::UnexpectedEof)),
+                amt => {
+                    buf.extend_from_slice(&chunk[..amt]);
+                    remaining -= amt;
+                }
+            }
+        }
+
+        Ok(())
+    };
+
+    match tokio::time::timeout(timeout, read_future).await {
+        Ok(result) => result,
+        Err(_) => Err(io::Error::from(io::ErrorKind::TimedOut)),
+    }
+}
@@ -1,5 +1,5 @@
-use crate::env::{AsyncIoEnvironment, SubEnvironment};
-use crate::io::FileDesc;
+use crate::env::{AsyncIoEnvironment, BoxAsyncRead, SubEnvironment};
+use crate::io::{AsyncFileDesc, FileDesc};
 use futures_core::future::BoxFuture;
 use std::borrow::Cow;
 use std::io;
@@ -24,74 +24,12 @@ impl SubEnvironment for TokioAsyncIoEnv {
     }
 }
 
-enum AsyncIo {
-    /// An evented file descriptor registered with tokio.
-    #[cfg(unix)]
-    PollEvented(tokio::io::PollEvented<FileDesc>),
-    /// Evented IO not supported, use a blocking operation
-    File(tokio::fs::File),
-}
-
-impl AsyncIo {
-    fn new(fd: FileDesc) -> Self {
-        match Self::try_as_evented(&fd) {
-            Some(io) => io,
-            None => AsyncIo::File(tokio::fs::File::from_std(convert_to_file(fd))),
-        }
-    }
-
-    #[cfg(not(unix))]
-    fn try_as_evented(_: &FileDesc) -> Option<Self> {
-        None
-    }
-
-    #[cfg(unix)]
-    fn try_as_evented(fd: &FileDesc) -> Option<Self> {
-        use crate::sys::cvt_r;
-        use std::mem;
-        use std::os::unix::io::AsRawFd;
-
-        #[cfg(not(linux))]
-        fn get_mode(fd: &FileDesc) -> io::Result<libc::mode_t> {
-            unsafe {
-                let mut stat: libc::stat = mem::zeroed();
-                cvt_r(|| libc::fstat(fd.as_raw_fd(), &mut stat)).map(|_| stat.st_mode)
-            }
-        }
-
-        #[cfg(linux)]
-        fn get_mode(fd: &FileDesc) -> Result<libc::mode_t> {
-            unsafe {
-                let mut stat: libc::stat64 = mem::zeroed();
-                cvt_r(|| libc::fstat64(fd.as_raw_fd(), &mut stat)).map(|_| stat.st_mode)
-            }
-        }
-
-        let supports_evented_io = get_mode(&fd)
-            .map(|mode| mode & libc::S_IFMT == libc::S_IFREG)
-            .map(|is_regular_file| !is_regular_file);
-
-        match supports_evented_io {
-            Ok(true) => fd
-                .duplicate()
-                .and_then(|mut fd| {
-                    fd.set_nonblock(true)?;
-                    tokio::io::PollEvented::new(fd)
-                })
-                .map(AsyncIo::PollEvented)
-                .ok(),
-
-            _ => None,
-        }
-    }
-}
-
 async fn do_write_all(fd: FileDesc, data: Cow<'_, [u8]>) -> io::Result<()> {
-    match AsyncIo::new(fd) {
-        #[cfg(unix)]
-        AsyncIo::PollEvented(mut fd) => fd.write_all(&*data).await,
-        AsyncIo::File(mut fd) => fd.write_all(&*data).await,
-    }
+    let mut fd = AsyncFileDesc::new(fd);
+    fd.write_all(&*data).await?;
+    // NB: writes to regular files are performed in the background,
+    // so make sure they have actually completed before returning
+    fd.flush().await
 }
 
 impl AsyncIoEnvironment for TokioAsyncIoEnv {
@@ -100,17 +38,15 @@ impl AsyncIoEnvironment for TokioAsyncIoEnv {
     fn read_all(&mut self, fd: Self::IoHandle) -> BoxFuture<'static, io::Result<Vec<u8>>> {
         Box::pin(async {
             let mut data = Vec::new();
-
-            let _read = match AsyncIo::new(fd) {
-                #[cfg(unix)]
-                AsyncIo::PollEvented(mut fd) => fd.read_to_end(&mut data).await?,
-                AsyncIo::File(mut fd) => fd.read_to_end(&mut data).await?,
-            };
-
+            AsyncFileDesc::new(fd).read_to_end(&mut data).await?;
             Ok(data)
         })
     }
 
+    fn read_async(&mut self, fd: Self::IoHandle) -> io::Result<BoxAsyncRead> {
+        Ok(Box::pin(AsyncFileDesc::new(fd)))
+    }
+
     fn write_all<'a>(
         &mut self,
         fd: Self::IoHandle,
@@ -125,17 +61,3 @@ impl AsyncIoEnvironment for TokioAsyncIoEnv {
         });
     }
 }
-
-#[cfg(unix)]
-fn convert_to_file(fd: FileDesc) -> std::fs::File {
-    use std::os::unix::io::{FromRawFd, IntoRawFd};
-
-    unsafe { FromRawFd::from_raw_fd(fd.into_raw_fd()) }
-}
-
-#[cfg(windows)]
-fn convert_to_file(fd: FileDesc) -> std::fs::File {
-    use std::os::windows::io::{FromRawHandle, IntoRawHandle};
-
-    unsafe { FromRawHandle::from_raw_handle(fd.into_raw_handle()) }
-}
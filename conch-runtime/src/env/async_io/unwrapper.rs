@@ -1,4 +1,4 @@
-use crate::env::{AsyncIoEnvironment, SubEnvironment};
+use crate::env::{AsyncIoEnvironment, BoxAsyncRead, SubEnvironment};
 use crate::io::{FileDesc, FileDescWrapper};
 use futures_core::future::BoxFuture;
 use std::borrow::Cow;
@@ -43,6 +43,10 @@ where
         }
     }
 
+    fn read_async(&mut self, fd: Self::IoHandle) -> io::Result<BoxAsyncRead> {
+        self.async_io.read_async(fd.try_unwrap()?)
+    }
+
     fn write_all<'a>(
         &mut self,
         fd: Self::IoHandle,
@@ -2,14 +2,21 @@
 //! and provides a default implementations.
 
 use crate::env::{
-    ArgumentsEnvironment, AsyncIoEnvironment, ChangeWorkingDirectoryEnvironment,
-    FileDescEnvironment, RedirectEnvRestorer, ShiftArgumentsEnvironment, StringWrapper,
-    SubEnvironment, VarEnvRestorer, VariableEnvironment,
+    AliasEnvironment, ArgumentsEnvironment, AsyncIoEnvironment, ChangeWorkingDirectoryEnvironment,
+    CommandSearchEnvironment, ControlFlowEnvironment, ExecReplaceEnvironment, FileDescEnvironment,
+    FunctionEnvironment, FunctionFrameEnvironment, HomeDirEnvironment, JobEnvironment,
+    LastStatusEnvironment, ProcessTimesEnvironment, ReadonlyVariableEnvironment,
+    RedirectEnvRestorer, ResourceLimitEnvironment, RuntimeInfoEnvironment, ScriptRunnerEnvironment,
+    SetArgumentsEnvironment, ShellOptionsEnvironment, ShiftArgumentsEnvironment, SignalEnvironment,
+    SourceStackEnvironment, StringWrapper, SubEnvironment, TaintEnvironment, TerminalEnvironment,
+    UnsetFunctionEnvironment, UnsetVariableEnvironment, VarEnvRestorer,
 };
-use crate::spawn::builtin;
+use crate::io::FileDescWrapper;
+use crate::spawn::{builtin, ready_status};
 use crate::ExitStatus;
 use futures_core::future::BoxFuture;
 use std::borrow::Borrow;
+use std::collections::VecDeque;
 use std::fmt;
 use std::marker::PhantomData;
 
@@ -46,6 +53,52 @@ where
         'life1: 'async_trait,
         Self: 'async_trait,
         A: 'async_trait;
+
+    /// Indicates whether this is one of the POSIX "special" builtin utilities
+    /// (e.g. `:` or `shift`), whose errors may abort a non-interactive shell.
+    ///
+    /// Defaults to `false`.
+    fn is_special(&self) -> bool {
+        false
+    }
+
+    /// Indicates whether this builtin has no effects and always exits with
+    /// the same status regardless of its arguments (e.g. `true` or `:`).
+    ///
+    /// Such builtins are not spawned at all: their local redirects and variables
+    /// are restored, and their status is returned immediately.
+    ///
+    /// Defaults to `None`.
+    fn trivial_status(&self) -> Option<ExitStatus> {
+        None
+    }
+
+    /// Indicates whether this builtin, when invoked with a command name and no
+    /// options, executes that command in its place while bypassing any shell
+    /// functions of the same name (e.g. `command`).
+    ///
+    /// Such builtins are not spawned at all when given a command name: the command
+    /// is resolved and executed as if the builtin's name were not there, except
+    /// that only builtins or executables are considered.
+    ///
+    /// Defaults to `false`.
+    fn bypasses_functions(&self) -> bool {
+        false
+    }
+
+    /// Indicates whether this builtin, when invoked with a command name, executes
+    /// that command in its place and reports the time and resources it used once
+    /// it completes (e.g. `time`).
+    ///
+    /// Such builtins are not spawned at all when given a command name: the command
+    /// is resolved and executed as if the builtin's name were not there, while the
+    /// resources used by any executables it spawns are recorded by a nested
+    /// `ResourceUsageTracker`.
+    ///
+    /// Defaults to `false`.
+    fn times_command(&self) -> bool {
+        false
+    }
 }
 
 impl<'a, A, R, E, T> BuiltinUtility<'a, A, R, E> for &'_ T
@@ -67,6 +120,22 @@ where
     {
         (**self).spawn_builtin(args, restorer)
     }
+
+    fn is_special(&self) -> bool {
+        (**self).is_special()
+    }
+
+    fn trivial_status(&self) -> Option<ExitStatus> {
+        (**self).trivial_status()
+    }
+
+    fn bypasses_functions(&self) -> bool {
+        (**self).bypasses_functions()
+    }
+
+    fn times_command(&self) -> bool {
+        (**self).times_command()
+    }
 }
 
 /// An interface for getting shell builtin utilities.
@@ -78,6 +147,13 @@ pub trait BuiltinEnvironment {
 
     /// Lookup and get a particular builtin by its name.
     fn builtin(&self, name: &Self::BuiltinName) -> Option<Self::Builtin>;
+
+    /// List the names of all available builtins, e.g. for completing command names.
+    ///
+    /// Defaults to an empty list for implementations which cannot enumerate their builtins.
+    fn builtin_names(&self) -> Vec<Self::BuiltinName> {
+        Vec::new()
+    }
 }
 
 impl<'a, T: ?Sized + BuiltinEnvironment> BuiltinEnvironment for &'a T {
@@ -87,17 +163,57 @@ impl<'a, T: ?Sized + BuiltinEnvironment> BuiltinEnvironment for &'a T {
     fn builtin(&self, name: &Self::BuiltinName) -> Option<Self::Builtin> {
         (**self).builtin(name)
     }
+
+    fn builtin_names(&self) -> Vec<Self::BuiltinName> {
+        (**self).builtin_names()
+    }
+}
+
+impl<'a, T: ?Sized + BuiltinEnvironment> BuiltinEnvironment for &'a mut T {
+    type BuiltinName = T::BuiltinName;
+    type Builtin = T::Builtin;
+
+    fn builtin(&self, name: &Self::BuiltinName) -> Option<Self::Builtin> {
+        (**self).builtin(name)
+    }
+
+    fn builtin_names(&self) -> Vec<Self::BuiltinName> {
+        (**self).builtin_names()
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum BuiltinKind {
+    Alias,
+    Bracket,
+    Break,
     Cd,
     Colon,
+    Command,
+    ConchInfo,
+    Continue,
     Echo,
+    Exec,
+    Exit,
     False,
+    Hash,
+    Printf,
     Pwd,
+    Read,
+    Return,
+    Set,
     Shift,
+    Source,
+    Test,
+    Time,
+    Times,
+    Trap,
     True,
+    Type,
+    Ulimit,
+    Unalias,
+    Unset,
+    Wait,
 }
 
 /// Represents a shell builtin utility managed by a `BuiltinEnv` instance.
@@ -152,18 +268,45 @@ impl<T> SubEnvironment for BuiltinEnv<T> {
     }
 }
 
-fn lookup_builtin(name: &str) -> Option<BuiltinKind> {
-    match name {
-        "cd" => Some(BuiltinKind::Cd),
-        ":" => Some(BuiltinKind::Colon),
-        "echo" => Some(BuiltinKind::Echo),
-        "false" => Some(BuiltinKind::False),
-        "pwd" => Some(BuiltinKind::Pwd),
-        "shift" => Some(BuiltinKind::Shift),
-        "true" => Some(BuiltinKind::True),
+const BUILTINS: &[(&str, BuiltinKind)] = &[
+    ("[", BuiltinKind::Bracket),
+    ("alias", BuiltinKind::Alias),
+    ("break", BuiltinKind::Break),
+    ("cd", BuiltinKind::Cd),
+    (":", BuiltinKind::Colon),
+    (".", BuiltinKind::Source),
+    ("command", BuiltinKind::Command),
+    ("conch-info", BuiltinKind::ConchInfo),
+    ("continue", BuiltinKind::Continue),
+    ("echo", BuiltinKind::Echo),
+    ("exec", BuiltinKind::Exec),
+    ("exit", BuiltinKind::Exit),
+    ("false", BuiltinKind::False),
+    ("hash", BuiltinKind::Hash),
+    ("printf", BuiltinKind::Printf),
+    ("pwd", BuiltinKind::Pwd),
+    ("read", BuiltinKind::Read),
+    ("return", BuiltinKind::Return),
+    ("set", BuiltinKind::Set),
+    ("shift", BuiltinKind::Shift),
+    ("source", BuiltinKind::Source),
+    ("test", BuiltinKind::Test),
+    ("time", BuiltinKind::Time),
+    ("times", BuiltinKind::Times),
+    ("trap", BuiltinKind::Trap),
+    ("true", BuiltinKind::True),
+    ("type", BuiltinKind::Type),
+    ("ulimit", BuiltinKind::Ulimit),
+    ("unalias", BuiltinKind::Unalias),
+    ("unset", BuiltinKind::Unset),
+    ("wait", BuiltinKind::Wait),
+];
 
-        _ => None,
-    }
+fn lookup_builtin(name: &str) -> Option<BuiltinKind> {
+    BUILTINS
+        .iter()
+        .find(|&&(builtin_name, _)| builtin_name == name)
+        .map(|&(_, kind)| kind)
 }
 
 impl<T> BuiltinEnvironment for BuiltinEnv<T>
@@ -176,6 +319,13 @@ where
     fn builtin(&self, name: &Self::BuiltinName) -> Option<Self::Builtin> {
         lookup_builtin(name.as_str()).map(|kind| Builtin { kind })
     }
+
+    fn builtin_names(&self) -> Vec<Self::BuiltinName> {
+        BUILTINS
+            .iter()
+            .map(|&(name, _)| T::from(name.to_owned()))
+            .collect()
+    }
 }
 
 impl<'a, A, R, E> BuiltinUtility<'a, A, R, E> for Builtin
@@ -188,13 +338,37 @@ where
         + ?Sized
         + Send
         + Sync
+        + AliasEnvironment
         + AsyncIoEnvironment
         + ArgumentsEnvironment
+        + BuiltinEnvironment<BuiltinName = <E as FunctionEnvironment>::FnName>
         + ChangeWorkingDirectoryEnvironment
+        + CommandSearchEnvironment
+        + ControlFlowEnvironment
+        + ExecReplaceEnvironment
         + FileDescEnvironment
-        + VariableEnvironment
-        + ShiftArgumentsEnvironment,
-    E::FileHandle: Clone,
+        + FunctionFrameEnvironment
+        + HomeDirEnvironment
+        + JobEnvironment
+        + LastStatusEnvironment
+        + ProcessTimesEnvironment
+        + ReadonlyVariableEnvironment
+        + ResourceLimitEnvironment
+        + RuntimeInfoEnvironment
+        + ScriptRunnerEnvironment
+        + SetArgumentsEnvironment
+        + ShellOptionsEnvironment
+        + ShiftArgumentsEnvironment
+        + SignalEnvironment
+        + SourceStackEnvironment
+        + TaintEnvironment
+        + TerminalEnvironment
+        + UnsetFunctionEnvironment
+        + UnsetVariableEnvironment,
+    E::Arg: Send + From<String>,
+    E::Args: Send + From<VecDeque<E::Arg>>,
+    E::FileHandle: Clone + FileDescWrapper,
+    E::FnName: From<String>,
     E::IoHandle: Send + From<E::FileHandle>,
     E::Var: Borrow<String> + From<String>,
     E::VarName: Borrow<String> + From<String>,
@@ -216,14 +390,37 @@ where
             let env = restorer.get_mut();
 
             let ret = match kind {
+                BuiltinKind::Alias => builtin::alias(args, env).await,
+                BuiltinKind::Bracket => builtin::bracket(args, env).await,
+                BuiltinKind::Break => builtin::break_cmd(args, env).await,
                 BuiltinKind::Cd => builtin::cd(args, env).await,
+                BuiltinKind::Command => builtin::command(args, env).await,
+                BuiltinKind::ConchInfo => builtin::conch_info(args, env).await,
+                BuiltinKind::Continue => builtin::continue_cmd(args, env).await,
                 BuiltinKind::Echo => builtin::echo(args, env).await,
+                BuiltinKind::Exec => builtin::exec(args, restorer).await,
+                BuiltinKind::Exit => builtin::exit(args, env).await,
+                BuiltinKind::Hash => builtin::hash(args, env).await,
+                BuiltinKind::Printf => builtin::printf(args, env).await,
                 BuiltinKind::Pwd => builtin::pwd(args, env).await,
+                BuiltinKind::Read => builtin::read(args, env).await,
+                BuiltinKind::Return => builtin::return_cmd(args, env).await,
+                BuiltinKind::Set => builtin::set(args, env).await,
                 BuiltinKind::Shift => builtin::shift(args, env).await,
+                BuiltinKind::Source => builtin::source(args, env).await,
+                BuiltinKind::Test => builtin::test_cmd(args, env).await,
+                BuiltinKind::Time => builtin::time(args, env).await,
+                BuiltinKind::Times => builtin::times(args, env).await,
+                BuiltinKind::Trap => builtin::trap(args, env).await,
+                BuiltinKind::Type => builtin::type_cmd(args, env).await,
+                BuiltinKind::Ulimit => builtin::ulimit(args, env).await,
+                BuiltinKind::Unalias => builtin::unalias(args, env).await,
+                BuiltinKind::Unset => builtin::unset(args, env).await,
+                BuiltinKind::Wait => builtin::wait(args, env).await,
 
-                BuiltinKind::Colon => Box::pin(async { builtin::colon() }),
-                BuiltinKind::False => Box::pin(async { builtin::false_cmd() }),
-                BuiltinKind::True => Box::pin(async { builtin::true_cmd() }),
+                BuiltinKind::Colon => ready_status(builtin::colon()),
+                BuiltinKind::False => ready_status(builtin::false_cmd()),
+                BuiltinKind::True => ready_status(builtin::true_cmd()),
             };
 
             restorer.restore_vars();
@@ -232,4 +429,84 @@ where
             ret
         })
     }
+
+    fn is_special(&self) -> bool {
+        match self.kind {
+            BuiltinKind::Break
+            | BuiltinKind::Colon
+            | BuiltinKind::Continue
+            | BuiltinKind::Exec
+            | BuiltinKind::Exit
+            | BuiltinKind::Return
+            | BuiltinKind::Set
+            | BuiltinKind::Shift
+            | BuiltinKind::Source
+            | BuiltinKind::Times
+            | BuiltinKind::Trap
+            | BuiltinKind::Unset => true,
+
+            BuiltinKind::Alias
+            | BuiltinKind::Bracket
+            | BuiltinKind::Cd
+            | BuiltinKind::Command
+            | BuiltinKind::ConchInfo
+            | BuiltinKind::Echo
+            | BuiltinKind::False
+            | BuiltinKind::Hash
+            | BuiltinKind::Printf
+            | BuiltinKind::Pwd
+            | BuiltinKind::Read
+            | BuiltinKind::Test
+            | BuiltinKind::Time
+            | BuiltinKind::True
+            | BuiltinKind::Type
+            | BuiltinKind::Ulimit
+            | BuiltinKind::Unalias
+            | BuiltinKind::Wait => false,
+        }
+    }
+
+    fn trivial_status(&self) -> Option<ExitStatus> {
+        match self.kind {
+            BuiltinKind::Colon => Some(builtin::colon()),
+            BuiltinKind::False => Some(builtin::false_cmd()),
+            BuiltinKind::True => Some(builtin::true_cmd()),
+
+            BuiltinKind::Alias
+            | BuiltinKind::Bracket
+            | BuiltinKind::Break
+            | BuiltinKind::Cd
+            | BuiltinKind::Command
+            | BuiltinKind::ConchInfo
+            | BuiltinKind::Continue
+            | BuiltinKind::Echo
+            | BuiltinKind::Exec
+            | BuiltinKind::Exit
+            | BuiltinKind::Hash
+            | BuiltinKind::Printf
+            | BuiltinKind::Pwd
+            | BuiltinKind::Read
+            | BuiltinKind::Return
+            | BuiltinKind::Set
+            | BuiltinKind::Shift
+            | BuiltinKind::Source
+            | BuiltinKind::Test
+            | BuiltinKind::Time
+            | BuiltinKind::Times
+            | BuiltinKind::Trap
+            | BuiltinKind::Type
+            | BuiltinKind::Ulimit
+            | BuiltinKind::Unalias
+            | BuiltinKind::Unset
+            | BuiltinKind::Wait => None,
+        }
+    }
+
+    fn bypasses_functions(&self) -> bool {
+        self.kind == BuiltinKind::Command
+    }
+
+    fn times_command(&self) -> bool {
+        self.kind == BuiltinKind::Time
+    }
 }
@@ -0,0 +1,151 @@
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::os::unix::io::AsRawFd;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The file which lists (and accepts) the processes within a cgroup.
+const PROCS: &str = "cgroup.procs";
+
+/// Resource limits which can be applied to all processes within a `Cgroup`.
+///
+/// Any limits which are left unset are not modified.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CgroupLimits {
+    /// The maximum amount of memory (in bytes) all processes may use combined
+    /// (written to `memory.max`).
+    pub memory_max: Option<u64>,
+    /// The maximum CPU bandwidth all processes may use combined, as the
+    /// amount of time (in microseconds) they may run within each period
+    /// (written to `cpu.max` as `(quota, period)`).
+    pub cpu_max: Option<(u64, u64)>,
+    /// The maximum number of processes which may exist at once
+    /// (written to `pids.max`).
+    pub pids_max: Option<u64>,
+}
+
+/// A (Linux) control group v2 which spawned executables can be placed in,
+/// allowing an embedder to cap the resources used by any commands a script runs.
+///
+/// A cgroup can either be provided by the caller (e.g. one delegated to the
+/// embedder by the init system), or created as a transient child of one, in
+/// which case it is removed again once dropped (as long as it is empty by then).
+#[derive(Debug, PartialEq, Eq)]
+pub struct Cgroup {
+    path: PathBuf,
+    transient: bool,
+}
+
+impl Cgroup {
+    /// Use an existing cgroup, located at `path` within the cgroup v2 hierarchy
+    /// (e.g. `/sys/fs/cgroup/my-service`).
+    pub fn open<P: Into<PathBuf>>(path: P) -> io::Result<Self> {
+        let path = path.into();
+        fs::metadata(path.join(PROCS))?;
+
+        Ok(Self {
+            path,
+            transient: false,
+        })
+    }
+
+    /// Create a new cgroup with a unique name as a child of the `parent` cgroup.
+    ///
+    /// The cgroup is removed once dropped (unless it still contains processes).
+    pub fn create_transient<P: AsRef<Path>>(parent: P) -> io::Result<Self> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let name = format!(
+            "conch-runtime-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+
+        let path = parent.as_ref().join(name);
+        fs::create_dir(&path)?;
+
+        Ok(Self {
+            path,
+            transient: true,
+        })
+    }
+
+    /// Get the path to the cgroup within the cgroup hierarchy.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Checks if the cgroup was created by (and will be removed by) this handle.
+    pub fn is_transient(&self) -> bool {
+        self.transient
+    }
+
+    /// Apply any of the provided limits to the cgroup.
+    ///
+    /// Note that the corresponding controllers must be enabled (by the parent
+    /// cgroup) for any limits to be applied.
+    pub fn set_limits(&self, limits: &CgroupLimits) -> io::Result<()> {
+        if let Some(max) = limits.memory_max {
+            self.write("memory.max", &max.to_string())?;
+        }
+
+        if let Some((quota, period)) = limits.cpu_max {
+            self.write("cpu.max", &format!("{} {}", quota, period))?;
+        }
+
+        if let Some(max) = limits.pids_max {
+            self.write("pids.max", &max.to_string())?;
+        }
+
+        Ok(())
+    }
+
+    /// Move the process with the specified ID into the cgroup.
+    pub fn add_process(&self, pid: u32) -> io::Result<()> {
+        self.write(PROCS, &pid.to_string())
+    }
+
+    fn write(&self, file: &str, contents: &str) -> io::Result<()> {
+        fs::write(self.path.join(file), contents)
+    }
+
+    /// Registers a hook on the command which moves the child process into the cgroup
+    /// before it executes, so that any descendants it spawns are also accounted for.
+    pub(crate) fn attach(&self, cmd: &mut Command) -> io::Result<()> {
+        let procs = OpenOptions::new().write(true).open(self.path.join(PROCS))?;
+
+        let pre_exec = move || write_self(&procs);
+
+        // Safety: the hook only writes to an already opened file,
+        // which is safe to do between forking and exec-ing the child.
+        unsafe {
+            cmd.pre_exec(pre_exec);
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for Cgroup {
+    fn drop(&mut self) {
+        if self.transient {
+            let _ = fs::remove_dir(&self.path);
+        }
+    }
+}
+
+/// Moves the current process into the cgroup whose `cgroup.procs` file is provided.
+fn write_self(procs: &File) -> io::Result<()> {
+    // NB: writing a PID of zero denotes the writing process itself.
+    // Also avoid going through `Write` here, since it could allocate.
+    let pid = b"0";
+    let ret = unsafe { libc::write(procs.as_raw_fd(), pid.as_ptr().cast(), pid.len()) };
+
+    if ret == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
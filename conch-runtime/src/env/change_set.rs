@@ -0,0 +1,117 @@
+use crate::env::{ExportedVariableEnvironment, FileDescEnvironment, TaintEnvironment};
+use crate::io::Permissions;
+use crate::Fd;
+
+/// A single staged modification of an environment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum EnvChange<N, V, H> {
+    Var(N, V, bool),
+    FileDesc(Fd, Option<(H, Permissions)>),
+}
+
+/// A buffer of variable assignments and file descriptor changes which have
+/// been staged, but not yet applied to any environment.
+///
+/// Staging changes allows for evaluating a group of them (e.g. the redirects
+/// and assignments at the start of a simple command) without leaving the
+/// environment partially modified if evaluation fails or is cancelled part
+/// way through: the changes can be applied all at once (in the order they
+/// were staged) only after all of them have been successfully evaluated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvChangeSet<N, V, H> {
+    changes: Vec<EnvChange<N, V, H>>,
+    /// The names of any assigned variables whose values were derived from tainted input.
+    tainted: Vec<N>,
+}
+
+impl<N, V, H> Default for EnvChangeSet<N, V, H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N, V, H> EnvChangeSet<N, V, H> {
+    /// Create a new, empty, change set.
+    pub fn new() -> Self {
+        Self {
+            changes: Vec::new(),
+            tainted: Vec::new(),
+        }
+    }
+
+    /// Create a new, empty, change set with capacity for at
+    /// least `capacity` changes.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            changes: Vec::with_capacity(capacity),
+            tainted: Vec::new(),
+        }
+    }
+
+    /// Returns the number of staged changes.
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    /// Returns `true` if no changes have been staged.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Stages setting the value of a variable along with its exported status.
+    pub fn set_exported_var(&mut self, name: N, val: V, exported: bool) {
+        self.changes.push(EnvChange::Var(name, val, exported));
+    }
+
+    /// Stages marking a variable as tainted (see `TaintEnvironment`), e.g.
+    /// because the value assigned to it was derived from tainted input.
+    pub fn set_tainted(&mut self, name: N) {
+        self.tainted.push(name);
+    }
+
+    /// Stages setting the handle and permissions of a file descriptor.
+    pub fn set_file_desc(&mut self, fd: Fd, handle: H, perms: Permissions) {
+        self.changes
+            .push(EnvChange::FileDesc(fd, Some((handle, perms))));
+    }
+
+    /// Stages closing a file descriptor.
+    pub fn close_file_desc(&mut self, fd: Fd) {
+        self.changes.push(EnvChange::FileDesc(fd, None));
+    }
+
+    /// Applies all staged changes to an environment, in the order they were staged.
+    ///
+    /// Passing in a restorer (e.g. `EnvRestorer`) allows for the changes
+    /// to be restored later, just like any other modification made through it.
+    pub fn apply<E>(self, env: &mut E)
+    where
+        E: ?Sized
+            + ExportedVariableEnvironment<VarName = N, Var = V>
+            + FileDescEnvironment<FileHandle = H>,
+    {
+        for change in self.changes {
+            match change {
+                EnvChange::Var(name, val, exported) => env.set_exported_var(name, val, exported),
+                EnvChange::FileDesc(fd, Some((handle, perms))) => {
+                    env.set_file_desc(fd, handle, perms)
+                }
+                EnvChange::FileDesc(fd, None) => env.close_file_desc(fd),
+            }
+        }
+    }
+
+    /// Marks any variables staged via `set_tainted` as tainted in an environment.
+    ///
+    /// Since variables remain tainted even after they are reassigned, this
+    /// should be applied to the environment itself, rather than a restorer.
+    pub fn apply_taint<E>(&self, env: &mut E)
+    where
+        E: ?Sized + TaintEnvironment<VarName = N>,
+        N: Clone,
+    {
+        for name in &self.tainted {
+            env.set_tainted(name.clone(), true);
+        }
+    }
+}
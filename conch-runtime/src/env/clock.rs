@@ -0,0 +1,161 @@
+use std::fmt;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant, SystemTime};
+
+/// An interface for telling time, so that anything time dependent can be
+/// tested deterministically, or otherwise virtualized by the embedder.
+pub trait ClockEnvironment {
+    /// Get the current wall-clock time.
+    fn now(&self) -> SystemTime;
+
+    /// Get the current time of a monotonically nondecreasing clock,
+    /// suitable for measuring elapsed durations.
+    fn monotonic_now(&self) -> Instant;
+}
+
+impl<'a, T: ?Sized + ClockEnvironment> ClockEnvironment for &'a T {
+    fn now(&self) -> SystemTime {
+        (**self).now()
+    }
+
+    fn monotonic_now(&self) -> Instant {
+        (**self).monotonic_now()
+    }
+}
+
+impl<'a, T: ?Sized + ClockEnvironment> ClockEnvironment for &'a mut T {
+    fn now(&self) -> SystemTime {
+        (**self).now()
+    }
+
+    fn monotonic_now(&self) -> Instant {
+        (**self).monotonic_now()
+    }
+}
+
+/// A cheaply clonable handle to the clock used by an environment.
+///
+/// By default the system clock is used, but any other `ClockEnvironment`
+/// implementation (such as a `ManualClock`) can be provided instead. Two handles
+/// are considered equal if they both use the system clock, or share the same
+/// clock instance.
+#[derive(Default, Clone)]
+pub struct Clock {
+    /// The custom clock to use, or `None` for the system clock.
+    inner: Option<Arc<dyn ClockEnvironment + Send + Sync>>,
+}
+
+impl Clock {
+    /// Creates a handle which uses the system clock.
+    pub fn system() -> Self {
+        Self::default()
+    }
+
+    /// Creates a handle which uses the provided clock.
+    pub fn new<C>(clock: C) -> Self
+    where
+        C: 'static + ClockEnvironment + Send + Sync,
+    {
+        Self {
+            inner: Some(Arc::new(clock)),
+        }
+    }
+
+    /// Checks if the handle uses the system clock.
+    pub fn is_system(&self) -> bool {
+        self.inner.is_none()
+    }
+}
+
+impl ClockEnvironment for Clock {
+    fn now(&self) -> SystemTime {
+        match self.inner {
+            Some(ref clock) => clock.now(),
+            None => SystemTime::now(),
+        }
+    }
+
+    fn monotonic_now(&self) -> Instant {
+        match self.inner {
+            Some(ref clock) => clock.monotonic_now(),
+            None => Instant::now(),
+        }
+    }
+}
+
+impl PartialEq for Clock {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.inner, &other.inner) {
+            (None, None) => true,
+            (Some(a), Some(b)) => Arc::as_ptr(a) as *const u8 == Arc::as_ptr(b) as *const u8,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Clock {}
+
+impl fmt::Debug for Clock {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct(stringify!(Clock))
+            .field("system", &self.is_system())
+            .finish()
+    }
+}
+
+/// A clock which only advances when explicitly told to, e.g. for testing.
+///
+/// Any clones of this clock share (and can advance) the same time.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    inner: Arc<Mutex<ManualTime>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ManualTime {
+    now: SystemTime,
+    monotonic_now: Instant,
+}
+
+impl ManualClock {
+    /// Creates a clock whose wall-clock time starts out at `now`.
+    pub fn new(now: SystemTime) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(ManualTime {
+                now,
+                monotonic_now: Instant::now(),
+            })),
+        }
+    }
+
+    fn time(&self) -> ManualTime {
+        *self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Moves both the wall-clock and monotonic time forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        let mut time = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        time.now += duration;
+        time.monotonic_now += duration;
+    }
+
+    /// Changes the wall-clock time, e.g. as if the system time was adjusted.
+    ///
+    /// The monotonic time remains unaffected.
+    pub fn set_now(&self, now: SystemTime) {
+        self.inner
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .now = now;
+    }
+}
+
+impl ClockEnvironment for ManualClock {
+    fn now(&self) -> SystemTime {
+        self.time().now
+    }
+
+    fn monotonic_now(&self) -> Instant {
+        self.time().monotonic_now
+    }
+}
@@ -0,0 +1,206 @@
+use crate::env::search::is_executable;
+use crate::env::{
+    BuiltinEnvironment, FunctionEnvironment, StringWrapper, VariableEnvironment,
+    WorkingDirectoryEnvironment,
+};
+use std::borrow::{Borrow, Cow};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+lazy_static::lazy_static! {
+    static ref PATH: String = String::from("PATH");
+}
+
+/// What a completed command name would resolve to if it were executed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandCompletionKind {
+    /// A function defined in the environment.
+    Function,
+    /// A builtin utility provided by the environment.
+    Builtin,
+    /// An executable found in one of the `$PATH` directories.
+    Executable(PathBuf),
+}
+
+/// A candidate for completing a command name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandCompletion {
+    /// The full name of the command.
+    pub name: String,
+    /// What the command would resolve to if it were executed.
+    pub kind: CommandCompletionKind,
+}
+
+/// Lists all commands whose names start with `prefix`, e.g. for providing
+/// tab-completion of command names in an interactive shell.
+///
+/// Commands are resolved the same way the runtime would resolve them if they
+/// were executed: functions shadow builtins, which in turn shadow executables
+/// found in the `$PATH` directories (where earlier directories take precedence).
+/// Thus each name is listed only once, and the results are sorted by name.
+///
+/// Any prefix containing a path separator is not looked up via `$PATH` (and
+/// would not name a function or builtin either), so no results are produced.
+///
+/// Note that listing the `$PATH` directories performs blocking I/O.
+pub fn complete_command<E>(prefix: &str, env: &E) -> Vec<CommandCompletion>
+where
+    E: ?Sized
+        + BuiltinEnvironment
+        + FunctionEnvironment
+        + VariableEnvironment
+        + WorkingDirectoryEnvironment,
+    E::BuiltinName: StringWrapper,
+    E::FnName: StringWrapper,
+    E::VarName: Borrow<String>,
+    E::Var: Borrow<String>,
+{
+    if prefix.contains(std::path::is_separator) {
+        return Vec::new();
+    }
+
+    let mut completions = BTreeMap::new();
+
+    for name in env.function_names() {
+        if name.as_str().starts_with(prefix) {
+            completions.insert(name.as_str().to_owned(), CommandCompletionKind::Function);
+        }
+    }
+
+    for name in env.builtin_names() {
+        if name.as_str().starts_with(prefix) {
+            completions
+                .entry(name.into_owned())
+                .or_insert(CommandCompletionKind::Builtin);
+        }
+    }
+
+    if let Some(path) = env.var(&PATH) {
+        for dir in std::env::split_paths(path.borrow().as_str()) {
+            let dir = env.path_relative_to_working_dir(Cow::Owned(dir));
+
+            for (name, path) in list_executables(&dir, prefix) {
+                completions
+                    .entry(name)
+                    .or_insert(CommandCompletionKind::Executable(path));
+            }
+        }
+    }
+
+    completions
+        .into_iter()
+        .map(|(name, kind)| CommandCompletion { name, kind })
+        .collect()
+}
+
+/// Lists all executables within `dir` whose names start with `prefix`,
+/// silently ignoring anything which cannot be read.
+fn list_executables(dir: &Path, prefix: &str) -> Vec<(String, PathBuf)> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            if !name.starts_with(prefix) {
+                return None;
+            }
+
+            let path = entry.path();
+            // NB: follow any symlinks to their targets
+            let metadata = fs::metadata(&path).ok()?;
+            if metadata.is_file() && is_executable(&metadata) {
+                Some((name, path))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// A candidate for completing a path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathCompletion {
+    /// The completed path, which starts with the original prefix.
+    ///
+    /// Directories end with a trailing path separator.
+    pub path: String,
+    /// The completed path with any glob metacharacters (i.e. `*`, `?`, `[`, `]`, and
+    /// on Unix systems `\`) escaped with a backslash, so it is treated literally by
+    /// pathname expansion.
+    pub escaped: String,
+    /// Whether the path refers to a directory.
+    pub is_dir: bool,
+}
+
+/// Lists all paths which start with `prefix`, e.g. for providing
+/// tab-completion of file names in an interactive shell.
+///
+/// Relative prefixes are resolved against the environment's working directory,
+/// not the working directory of the current process. Much like pathname expansion,
+/// hidden entries (i.e. whose names start with `.`) are only listed if the last
+/// component of the prefix also starts with a `.`. The results are sorted by path.
+///
+/// Note that listing the directory performs blocking I/O.
+pub fn complete_path<E>(prefix: &str, env: &E) -> Vec<PathCompletion>
+where
+    E: ?Sized + WorkingDirectoryEnvironment,
+{
+    let (dir, name_prefix) = match prefix.rfind(std::path::is_separator) {
+        Some(idx) => prefix.split_at(idx + 1),
+        None => ("", prefix),
+    };
+
+    let dir_path = if dir.is_empty() { "." } else { dir };
+    let dir_path = env.path_relative_to_working_dir(Cow::Borrowed(Path::new(dir_path)));
+
+    let entries = match fs::read_dir(&dir_path) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let show_hidden = name_prefix.starts_with('.');
+    let mut completions = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            if !name.starts_with(name_prefix) || (name.starts_with('.') && !show_hidden) {
+                return None;
+            }
+
+            // NB: follow any symlinks to their targets
+            let is_dir = fs::metadata(entry.path()).is_ok_and(|m| m.is_dir());
+
+            let mut path = format!("{}{}", dir, name);
+            if is_dir {
+                path.push(std::path::MAIN_SEPARATOR);
+            }
+
+            Some(PathCompletion {
+                escaped: escape_glob(&path),
+                path,
+                is_dir,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    completions.sort_by(|a, b| a.path.cmp(&b.path));
+    completions
+}
+
+fn escape_glob(path: &str) -> String {
+    let mut escaped = String::with_capacity(path.len());
+    for c in path.chars() {
+        // NB: backslashes are path separators on Windows
+        let is_meta = matches!(c, '*' | '?' | '[' | ']') || (cfg!(unix) && c == '\\');
+        if is_meta {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
@@ -2,7 +2,9 @@ use crate::env::SubEnvironment;
 use crate::path::NormalizedPath;
 use std::borrow::Cow;
 use std::env;
+use std::fmt;
 use std::io;
+use std::mem;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
@@ -53,14 +55,21 @@ where
     }
 }
 
+type DirValidator = Arc<dyn Fn(&Path, &Path) -> io::Result<()> + Send + Sync>;
+type DirWatcher = Arc<dyn Fn(&Path, &Path) + Send + Sync>;
+
 /// An environment module for keeping track of the current working directory.
 ///
 /// This is a "virtual" implementation because changing the working directory
 /// through this environment will not affect the working directory of the
 /// entire process.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Clone)]
 pub struct VirtualWorkingDirEnv {
     cwd: Arc<NormalizedPath>,
+    /// Hooks which may veto a directory change before it happens.
+    validators: Vec<DirValidator>,
+    /// Hooks which are notified after the directory has changed.
+    watchers: Vec<DirWatcher>,
 }
 
 impl VirtualWorkingDirEnv {
@@ -80,6 +89,8 @@ impl VirtualWorkingDirEnv {
             if normalized.is_dir() {
                 Ok(Self {
                     cwd: Arc::new(normalized),
+                    validators: Vec::new(),
+                    watchers: Vec::new(),
                 })
             } else {
                 let msg = format!("not a directory: {}", normalized.display());
@@ -96,8 +107,54 @@ impl VirtualWorkingDirEnv {
     pub fn with_process_working_dir() -> io::Result<Self> {
         env::current_dir().and_then(Self::with_path_buf)
     }
+
+    /// Registers a hook which is invoked with the current and new working
+    /// directories before any change is made, and which can veto the change
+    /// by returning an error (e.g. to sandbox a script to some directory).
+    ///
+    /// Any error will be propagated to the caller of `change_working_dir`
+    /// (e.g. it will be reported as the `cd` builtin's error message).
+    /// Validators are inherited by all sub-environments, so that subshells
+    /// are held to the same restrictions.
+    pub fn add_change_validator<F>(&mut self, validator: F)
+    where
+        F: Fn(&Path, &Path) -> io::Result<()> + Send + Sync + 'static,
+    {
+        self.validators.push(Arc::new(validator));
+    }
+
+    /// Registers a callback which is invoked with the previous and new working
+    /// directories whenever the working directory has been changed.
+    ///
+    /// Watchers are shared with any clones of this environment, but are *not*
+    /// inherited by sub-environments, since changes there are not visible to
+    /// the parent.
+    pub fn watch<F>(&mut self, watcher: F)
+    where
+        F: Fn(&Path, &Path) + Send + Sync + 'static,
+    {
+        self.watchers.push(Arc::new(watcher));
+    }
+}
+
+impl fmt::Debug for VirtualWorkingDirEnv {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct(stringify!(VirtualWorkingDirEnv))
+            .field("cwd", &self.cwd)
+            .field("validators", &self.validators.len())
+            .field("watchers", &self.watchers.len())
+            .finish()
+    }
 }
 
+impl PartialEq for VirtualWorkingDirEnv {
+    fn eq(&self, other: &Self) -> bool {
+        self.cwd == other.cwd
+    }
+}
+
+impl Eq for VirtualWorkingDirEnv {}
+
 impl WorkingDirectoryEnvironment for VirtualWorkingDirEnv {
     fn path_relative_to_working_dir<'a>(&self, path: Cow<'a, Path>) -> Cow<'a, Path> {
         if path.is_absolute() {
@@ -125,7 +182,15 @@ impl ChangeWorkingDirectoryEnvironment for VirtualWorkingDirEnv {
         new_cwd.join_normalized_logial(&path);
 
         if new_cwd.is_dir() {
-            self.cwd = Arc::new(new_cwd);
+            for validator in &self.validators {
+                validator(&self.cwd, &new_cwd)?;
+            }
+
+            let old_cwd = mem::replace(&mut self.cwd, Arc::new(new_cwd));
+            for watcher in &self.watchers {
+                watcher(&old_cwd, &self.cwd);
+            }
+
             Ok(())
         } else {
             let msg = format!("not a directory: {}", new_cwd.display());
@@ -136,6 +201,10 @@ impl ChangeWorkingDirectoryEnvironment for VirtualWorkingDirEnv {
 
 impl SubEnvironment for VirtualWorkingDirEnv {
     fn sub_env(&self) -> Self {
-        self.clone()
+        Self {
+            cwd: self.cwd.clone(),
+            validators: self.validators.clone(),
+            watchers: Vec::new(),
+        }
     }
 }
@@ -0,0 +1,132 @@
+use crate::io::Permissions;
+use crate::Fd;
+use std::fmt;
+use std::io;
+
+/// Describes how asynchronous I/O is performed on a file descriptor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IoBackend {
+    /// The descriptor is registered with the event loop (e.g. pipes on Unix systems).
+    Evented,
+    /// Operations on the descriptor block a background thread (e.g. regular files).
+    ThreadPool,
+}
+
+impl fmt::Display for IoBackend {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            IoBackend::Evented => fmt.write_str("evented"),
+            IoBackend::ThreadPool => fmt.write_str("thread pool"),
+        }
+    }
+}
+
+/// Describes an open shell file descriptor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FdInfo {
+    /// The shell file descriptor.
+    pub fd: Fd,
+    /// The permissions the descriptor was opened with.
+    pub permissions: Permissions,
+    /// Whether the underlying OS descriptor has its close-on-exec flag set.
+    pub cloexec: bool,
+    /// How asynchronous I/O is performed on the descriptor.
+    pub io_backend: IoBackend,
+}
+
+/// A snapshot of the capabilities and state of the runtime,
+/// useful for diagnosing misbehaving environment configurations.
+///
+/// The `Display` implementation renders a human readable report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeInfo {
+    /// The version of the `conch-runtime` crate.
+    pub version: &'static str,
+    /// The optional features and platform specific capabilities compiled in.
+    pub capabilities: Vec<&'static str>,
+    /// All open shell file descriptors, in ascending order.
+    pub fds: Vec<FdInfo>,
+    /// The number of file descriptors opened by the environment which are still open, if tracked.
+    pub open_fds: Option<usize>,
+    /// The limit on the number of open file descriptors, if any.
+    pub fd_limit: Option<usize>,
+}
+
+impl Default for RuntimeInfo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RuntimeInfo {
+    /// Creates a snapshot with the capabilities of the runtime, but without
+    /// any information about a particular environment.
+    pub fn new() -> Self {
+        let mut capabilities = Vec::new();
+
+        if cfg!(unix) {
+            capabilities.push("unix");
+            capabilities.push("evented-io");
+        }
+        if cfg!(windows) {
+            capabilities.push("windows");
+        }
+        if cfg!(target_os = "linux") {
+            capabilities.push("splice");
+        }
+        if cfg!(feature = "conch-parser") {
+            capabilities.push("conch-parser");
+        }
+        if cfg!(feature = "serde") {
+            capabilities.push("serde");
+        }
+
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            capabilities,
+            fds: Vec::new(),
+            open_fds: None,
+            fd_limit: None,
+        }
+    }
+}
+
+impl fmt::Display for RuntimeInfo {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(fmt, "conch-runtime {}", self.version)?;
+        writeln!(fmt, "capabilities: {}", self.capabilities.join(", "))?;
+
+        if let Some(open_fds) = self.open_fds {
+            write!(fmt, "open fds: {}", open_fds)?;
+            match self.fd_limit {
+                Some(limit) => writeln!(fmt, " (limit: {})", limit)?,
+                None => writeln!(fmt, " (no limit)")?,
+            }
+        }
+
+        for info in &self.fds {
+            writeln!(
+                fmt,
+                "fd {}: {}, {}{}",
+                info.fd,
+                info.permissions,
+                info.io_backend,
+                if info.cloexec { "" } else { ", inheritable" }
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// An interface for inspecting the capabilities and state of an environment.
+pub trait RuntimeInfoEnvironment {
+    /// Takes a snapshot of the runtime's capabilities and the environment's state.
+    fn runtime_info(&self) -> io::Result<RuntimeInfo>;
+}
+
+impl<'a, T: ?Sized + RuntimeInfoEnvironment> RuntimeInfoEnvironment for &'a mut T {
+    fn runtime_info(&self) -> io::Result<RuntimeInfo> {
+        (**self).runtime_info()
+    }
+}
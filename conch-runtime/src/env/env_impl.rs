@@ -2,29 +2,55 @@
 // FIXME: consumers still have all the pieces so they can make their own environment and swap out pieces there
 // FIXME: downside is any unit tests which want a mock env, will need to basically do the same
 use crate::env::builtin::{BuiltinEnv, BuiltinEnvironment};
+#[cfg(feature = "metrics")]
+use crate::env::metrics::{self, MetricsEnvironment, MetricsSink};
+use crate::env::resolve::CommandCache;
+use crate::env::search::CommandSearchCache;
+use crate::env::terminal::not_a_terminal;
+#[cfg(all(target_os = "linux", feature = "spawn-policy"))]
+use crate::env::NoNewPrivilegesPolicy;
 use crate::env::{
-    ArgsEnv, ArgumentsEnvironment, AsyncIoEnvironment, ChangeWorkingDirectoryEnvironment,
-    ExecutableData, ExecutableEnvironment, ExportedVariableEnvironment, FileDescEnvironment,
-    FileDescOpener, FnEnv, FnFrameEnv, FunctionEnvironment, FunctionFrameEnvironment,
-    IsInteractiveEnvironment, LastStatusEnv, LastStatusEnvironment, Pipe, ReportErrorEnvironment,
-    SetArgumentsEnvironment, ShiftArgumentsEnvironment, StringWrapper, SubEnvironment,
-    TokioExecEnv, TokioFileDescManagerEnv, UnsetFunctionEnvironment, UnsetVariableEnvironment,
-    VarEnv, VariableEnvironment, VirtualWorkingDirEnv, WorkingDirectoryEnvironment,
+    AliasEnv, AliasEnvironment, ArgsEnv, ArgumentsEnvironment, ArithVariableEnvironment,
+    AsyncIoEnvironment, BoxAsyncRead, ChangeWorkingDirectoryEnvironment, Clock, ClockEnvironment,
+    CommandSearchEnvironment, CommandTextEnvironment, ControlFlow, ControlFlowEnvironment,
+    ExecReplaceEnvironment, ExecutableData, ExecutableEnvironment, ExecutionReportEnvironment,
+    ExecutionReporter, ExportedVariableEnvironment, FileDescAuditEnvironment, FileDescEnvironment,
+    FileDescOpener, FnEnv, FnFrameEnv, FunctionEnvironment, FunctionFrameEnvironment, GetoptsEnv,
+    GetoptsEnvironment, GetoptsState, HomeDirEnvironment, IsInteractiveEnvironment, JobEnv,
+    JobEnvironment, JobId, LastStatusEnv, LastStatusEnvironment, ModifyArgumentsEnvironment,
+    OpenFlags, ParseScriptEnvironment, Pipe, ProcessTimes, ProcessTimesEnvironment,
+    RandomEnvironment, ReadonlyVariableEnvironment, ReportErrorEnvironment,
+    ResolveCommandEnvironment, ResolvedCommand, Resource, ResourceLimit, ResourceLimitEnvironment,
+    ResourceUsage, ResourceUsageEnvironment, ResourceUsageTracker, Rng, RuntimeInfo,
+    RuntimeInfoEnvironment, ScriptParseError, ScriptRunner, ScriptRunnerEnvironment,
+    SetArgumentsEnvironment, ShellOptions, ShellOptionsEnvironment, ShiftArgumentsEnvironment,
+    Signal, SignalEnv, SignalEnvironment, SignalSender, SourceStackEnvironment, SpawnMiddleware,
+    SpawnMiddlewareEnvironment, StringWrapper, SubEnvironment, SubstitutionStatusEnvironment,
+    TaintEnvironment, TaintTracker, TerminalEnvironment, TokioExecEnv, TokioFileDescManagerEnv,
+    TrapAction, UnsetFunctionEnvironment, UnsetVariableEnvironment, VarEnv, VariableEnvironment,
+    VirtualWorkingDirEnv, WatchVariableEnvironment, WorkingDirectoryEnvironment,
 };
-use crate::error::{CommandError, RuntimeError};
-use crate::io::Permissions;
+use crate::error::{CommandError, ErrorContext, RuntimeError};
+use crate::io::{FileDesc, Permissions, TerminalMode, WindowSize};
 use crate::{ExitStatus, Fd, Spawn, IFS_DEFAULT, STDERR_FILENO};
 use futures_core::future::BoxFuture;
 use std::borrow::{Borrow, Cow};
-use std::convert::From;
+use std::convert::{From, Infallible};
 use std::error::Error;
 use std::fmt;
 use std::fs::OpenOptions;
 use std::hash::Hash;
 use std::io;
 use std::marker::PhantomData;
-use std::path::Path;
+use std::mem;
+use std::ops::RangeBounds;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Instant, SystemTime};
+
+lazy_static::lazy_static! {
+    static ref PATH: String = String::from("PATH");
+}
 
 /// A struct for configuring a new `Env` instance.
 ///
@@ -50,17 +76,29 @@ pub struct EnvConfig<A, FM, L, V, EX, WD, B, N, ERR> {
     pub args_env: A,
     /// An implementation of `FileDescManagerEnvironment`.
     pub file_desc_manager_env: FM,
-    /// An implementation of `LastStatusEnvironment`.
+    /// An implementation of `LastStatusEnvironment` and possibly `SubstitutionStatusEnvironment`.
     pub last_status_env: L,
-    /// An implementation of `VariableEnvironment`, `UnsetVariableEnvironment`, and
+    /// An implementation of `VariableEnvironment`, `UnsetVariableEnvironment`,
+    /// `ReadonlyVariableEnvironment`, and
     /// `ExportedVariableEnvironment`.
     pub var_env: V,
-    /// An implementation of `ExecutableEnvironment`.
+    /// An implementation of `ExecutableEnvironment` and possibly `ExecReplaceEnvironment`.
     pub exec_env: EX,
     /// An implementation of `WorkingDirectoryEnvironment`.
     pub working_dir_env: WD,
     /// An implementation of `BuiltinEnvironment`.
     pub builtin_env: B,
+    /// A fixed home directory to use (e.g. for tilde expansions) instead of
+    /// the value of `$HOME`.
+    pub home_dir: Option<String>,
+    /// A reporter for recording a structured report of all executed commands.
+    pub execution_reporter: Option<ExecutionReporter>,
+    /// The clock used for telling time.
+    pub clock: Clock,
+    /// The generator used for producing random numbers.
+    pub rng: Rng,
+    /// The shell options the environment starts out with.
+    pub options: ShellOptions,
     /// A marker to indicate the type used for function names.
     pub fn_name: PhantomData<N>,
     /// A marker to indicate the type used for function errors.
@@ -79,6 +117,11 @@ impl<A, FM, L, V, EX, WD, B, N, ERR> EnvConfig<A, FM, L, V, EX, WD, B, N, ERR> {
             exec_env: self.exec_env,
             working_dir_env: self.working_dir_env,
             builtin_env: self.builtin_env,
+            home_dir: self.home_dir,
+            execution_reporter: self.execution_reporter,
+            clock: self.clock,
+            rng: self.rng,
+            options: self.options,
             fn_name: self.fn_name,
             fn_error: self.fn_error,
         }
@@ -98,6 +141,11 @@ impl<A, FM, L, V, EX, WD, B, N, ERR> EnvConfig<A, FM, L, V, EX, WD, B, N, ERR> {
             exec_env: self.exec_env,
             working_dir_env: self.working_dir_env,
             builtin_env: self.builtin_env,
+            home_dir: self.home_dir,
+            execution_reporter: self.execution_reporter,
+            clock: self.clock,
+            rng: self.rng,
+            options: self.options,
             fn_name: self.fn_name,
             fn_error: self.fn_error,
         }
@@ -117,6 +165,11 @@ impl<A, FM, L, V, EX, WD, B, N, ERR> EnvConfig<A, FM, L, V, EX, WD, B, N, ERR> {
             exec_env: self.exec_env,
             working_dir_env: self.working_dir_env,
             builtin_env: self.builtin_env,
+            home_dir: self.home_dir,
+            execution_reporter: self.execution_reporter,
+            clock: self.clock,
+            rng: self.rng,
+            options: self.options,
             fn_name: self.fn_name,
             fn_error: self.fn_error,
         }
@@ -133,6 +186,11 @@ impl<A, FM, L, V, EX, WD, B, N, ERR> EnvConfig<A, FM, L, V, EX, WD, B, N, ERR> {
             exec_env: self.exec_env,
             working_dir_env: self.working_dir_env,
             builtin_env: self.builtin_env,
+            home_dir: self.home_dir,
+            execution_reporter: self.execution_reporter,
+            clock: self.clock,
+            rng: self.rng,
+            options: self.options,
             fn_name: self.fn_name,
             fn_error: self.fn_error,
         }
@@ -149,6 +207,11 @@ impl<A, FM, L, V, EX, WD, B, N, ERR> EnvConfig<A, FM, L, V, EX, WD, B, N, ERR> {
             exec_env,
             working_dir_env: self.working_dir_env,
             builtin_env: self.builtin_env,
+            home_dir: self.home_dir,
+            execution_reporter: self.execution_reporter,
+            clock: self.clock,
+            rng: self.rng,
+            options: self.options,
             fn_name: self.fn_name,
             fn_error: self.fn_error,
         }
@@ -168,6 +231,11 @@ impl<A, FM, L, V, EX, WD, B, N, ERR> EnvConfig<A, FM, L, V, EX, WD, B, N, ERR> {
             exec_env: self.exec_env,
             working_dir_env,
             builtin_env: self.builtin_env,
+            home_dir: self.home_dir,
+            execution_reporter: self.execution_reporter,
+            clock: self.clock,
+            rng: self.rng,
+            options: self.options,
             fn_name: self.fn_name,
             fn_error: self.fn_error,
         }
@@ -187,6 +255,11 @@ impl<A, FM, L, V, EX, WD, B, N, ERR> EnvConfig<A, FM, L, V, EX, WD, B, N, ERR> {
             exec_env: self.exec_env,
             working_dir_env: self.working_dir_env,
             builtin_env,
+            home_dir: self.home_dir,
+            execution_reporter: self.execution_reporter,
+            clock: self.clock,
+            rng: self.rng,
+            options: self.options,
             fn_name: self.fn_name,
             fn_error: self.fn_error,
         }
@@ -203,6 +276,11 @@ impl<A, FM, L, V, EX, WD, B, N, ERR> EnvConfig<A, FM, L, V, EX, WD, B, N, ERR> {
             exec_env: self.exec_env,
             working_dir_env: self.working_dir_env,
             builtin_env: self.builtin_env,
+            home_dir: self.home_dir,
+            execution_reporter: self.execution_reporter,
+            clock: self.clock,
+            rng: self.rng,
+            options: self.options,
             fn_name: PhantomData,
             fn_error: self.fn_error,
         }
@@ -219,6 +297,11 @@ impl<A, FM, L, V, EX, WD, B, N, ERR> EnvConfig<A, FM, L, V, EX, WD, B, N, ERR> {
             exec_env: self.exec_env,
             working_dir_env: self.working_dir_env,
             builtin_env: self.builtin_env,
+            home_dir: self.home_dir,
+            execution_reporter: self.execution_reporter,
+            clock: self.clock,
+            rng: self.rng,
+            options: self.options,
             fn_name: self.fn_name,
             fn_error: PhantomData,
         }
@@ -262,28 +345,148 @@ where
             exec_env: TokioExecEnv::new(),
             working_dir_env: VirtualWorkingDirEnv::with_process_working_dir()?,
             builtin_env: BuiltinEnv::new(),
+            home_dir: None,
+            execution_reporter: None,
+            clock: Clock::system(),
+            rng: Rng::default(),
+            options: ShellOptions::default(),
             fn_name: PhantomData,
             fn_error: PhantomData,
         })
     }
+
+    /// Creates a new `DefaultEnvConfig` with conservative defaults, suitable
+    /// for security-sensitive embedders executing untrusted scripts.
+    ///
+    /// Unlike `DefaultEnvConfig::new`, the configuration:
+    ///
+    /// * does not inherit any environment variables from the current process
+    /// (thus `$PATH`, if any, must be explicitly provided by the caller),
+    /// * enables the `errexit`, `nounset`, `pipefail`, and
+    /// `fatal_special_builtin_errors` shell options,
+    /// * limits the number of file descriptors the shell itself may open
+    /// at once (e.g. via redirects or substitutions) to 256,
+    /// * disables core dumps of, and limits the number of files which can be
+    /// opened and processes which can be run by, any spawned executables (Unix only),
+    /// * prevents spawned executables from gaining new privileges, e.g. via
+    /// setuid binaries (Linux only, with the `spawn-policy` feature enabled).
+    ///
+    /// The configuration is *not* a sandbox, and notably does not protect against:
+    ///
+    /// * anything a restricted shell would forbid: scripts may still `cd`
+    /// anywhere, modify `$PATH`, run executables by an absolute path, redirect
+    /// output to (and thus overwrite) any file the embedding process can write,
+    /// source arbitrary files, and shadow builtins with functions,
+    /// * `exec` with a command, which still terminates the embedding process
+    /// once the command exits (propagating its status),
+    /// * exhausting the memory of the embedding process: there is no cap on the
+    /// size of expanded words, fields, or variable values (e.g. `x=$x$x` in a loop),
+    /// * exhausting the CPU of the embedding process, as there are no time limits
+    /// on evaluating the script itself (e.g. `while :; do :; done`),
+    /// * running external executables at all (there is no `noexec` guard), or
+    /// bounding their CPU time or memory, since only the limits above are capped,
+    /// * anything an executable may do with the privileges it starts out with,
+    /// e.g. accessing the file system or network (see `SpawnIsolation`).
+    ///
+    /// The resource limits are only applied to spawned executables, not to the
+    /// embedding process, and the `Processes` limit is shared by every process of
+    /// the same user. Embedders which need any of the protections above should run
+    /// the shell in a separate, externally sandboxed process.
+    ///
+    /// Any further restrictions, such as other resource limits, can be layered
+    /// on top by modifying the `exec_env` instance. Note that attaching another
+    /// `SpawnPolicy` replaces the bundled one.
+    pub fn hardened() -> io::Result<Self> {
+        let mut cfg = Self::new()?;
+        cfg.var_env = VarEnv::new();
+        cfg.options.errexit = true;
+        cfg.options.nounset = true;
+        cfg.options.pipefail = true;
+        cfg.options.fatal_special_builtin_errors = true;
+
+        cfg.file_desc_manager_env
+            .fd_usage()
+            .set_limit(Some(HARDENED_OPEN_FDS));
+
+        #[cfg(all(target_os = "linux", feature = "spawn-policy"))]
+        {
+            cfg.exec_env = cfg.exec_env.with_spawn_policy(NoNewPrivilegesPolicy);
+        }
+
+        #[cfg(unix)]
+        {
+            let exec_env = &mut cfg.exec_env;
+            for &(resource, max) in HARDENED_RESOURCE_LIMITS {
+                let limit = exec_env.resource_limit(resource)?;
+                exec_env.set_resource_limit(resource, limit.capped(max))?;
+            }
+        }
+
+        Ok(cfg)
+    }
 }
 
+/// The maximum number of file descriptors the shell may open through an
+/// environment created via `DefaultEnvConfig::hardened`.
+const HARDENED_OPEN_FDS: usize = 256;
+
+/// The resource limits (in bytes or a count) applied by `DefaultEnvConfig::hardened`.
+#[cfg(unix)]
+const HARDENED_RESOURCE_LIMITS: &[(Resource, u64)] = &[
+    (Resource::CoreFileSize, 0),
+    (Resource::OpenFiles, 1024),
+    // NB: this limit applies to all processes of the user, not just the
+    // descendants of the shell, so leave plenty of room for everything else
+    #[cfg(not(any(target_os = "solaris", target_os = "illumos")))]
+    (Resource::Processes, 4096),
+];
+
 /// A shell environment implementation which delegates work to other
 /// environment implementations.
 pub struct Env<A, FM, L, V, EX, WD, B, N: Eq + Hash, ERR> {
     /// If the shell is running in interactive mode
     interactive: bool,
     args_env: A,
+    alias_env: AliasEnv,
     file_desc_manager_env: FM,
     #[allow(clippy::type_complexity)]
     fn_env:
         FnEnv<N, Arc<dyn Spawn<Env<A, FM, L, V, EX, WD, B, N, ERR>, Error = ERR> + Send + Sync>>,
+    #[allow(clippy::type_complexity)]
+    command_cache: CommandCache<
+        N,
+        Arc<dyn Spawn<Env<A, FM, L, V, EX, WD, B, N, ERR>, Error = ERR> + Send + Sync>,
+    >,
+    command_search: CommandSearchCache,
     fn_frame_env: FnFrameEnv,
+    getopts_env: GetoptsEnv,
+    job_env: JobEnv,
+    signal_env: SignalEnv,
+    /// The contexts (outermost first) in which any errors are currently reported.
+    error_context: Vec<ErrorContext>,
+    /// A rendering of the currently executing command, if any.
+    command_text: Option<String>,
     last_status_env: L,
     var_env: V,
     exec_env: EX,
     working_dir_env: WD,
     builtin_env: B,
+    /// A fixed home directory to use instead of `$HOME`.
+    home_dir: Option<String>,
+    execution_reporter: Option<ExecutionReporter>,
+    usage_tracker: Option<ResourceUsageTracker>,
+    clock: Clock,
+    rng: Rng,
+    options: ShellOptions,
+    /// How many times the `errexit` option is currently being ignored.
+    errexit_ignored: usize,
+    spawn_middleware: Vec<Arc<dyn SpawnMiddleware>>,
+    #[allow(clippy::type_complexity)]
+    script_runner: Option<ScriptRunner<Env<A, FM, L, V, EX, WD, B, N, ERR>>>,
+    /// The files (outermost first) which are currently being sourced.
+    source_stack: Vec<Arc<Path>>,
+    #[cfg(feature = "metrics")]
+    metrics_sink: Option<Arc<dyn MetricsSink>>,
 }
 
 impl<A, FM, L, V, EX, WD, B, N, ERR> Env<A, FM, L, V, EX, WD, B, N, ERR>
@@ -307,25 +510,48 @@ where
     /// arbitrarily large), `Arc` is your friend.
     pub fn with_config(cfg: EnvConfig<A, FM, L, V, EX, WD, B, N, ERR>) -> Self
     where
-        V: ExportedVariableEnvironment,
-        V::VarName: From<String>,
+        V: ExportedVariableEnvironment + WatchVariableEnvironment,
+        V::VarName: Borrow<String> + From<String>,
         V::Var: Borrow<String> + From<String> + Clone,
         WD: WorkingDirectoryEnvironment,
     {
+        let mut var_env = cfg.var_env;
+        let command_search = CommandSearchCache::watching(&mut var_env);
+
         let mut env = Env {
             interactive: cfg.interactive,
             args_env: cfg.args_env,
+            alias_env: AliasEnv::new(),
             fn_env: FnEnv::new(),
+            command_cache: CommandCache::new(),
+            command_search,
             fn_frame_env: FnFrameEnv::new(),
+            getopts_env: GetoptsEnv::new(),
+            job_env: JobEnv::new(),
+            signal_env: SignalEnv::new(),
+            error_context: Vec::new(),
+            command_text: None,
             file_desc_manager_env: cfg.file_desc_manager_env,
             last_status_env: cfg.last_status_env,
-            var_env: cfg.var_env,
+            var_env,
             exec_env: cfg.exec_env,
             working_dir_env: cfg.working_dir_env,
             builtin_env: cfg.builtin_env,
+            home_dir: cfg.home_dir,
+            execution_reporter: cfg.execution_reporter,
+            usage_tracker: None,
+            clock: cfg.clock,
+            rng: cfg.rng,
+            options: cfg.options,
+            errexit_ignored: 0,
+            spawn_middleware: Vec::new(),
+            script_runner: None,
+            source_stack: Vec::new(),
+            #[cfg(feature = "metrics")]
+            metrics_sink: None,
         };
 
-        let sh_lvl = "SHLVL".to_owned().into();
+        let sh_lvl = "SHLVL".to_owned();
         let level = env
             .var(&sh_lvl)
             .and_then(|lvl| lvl.borrow().parse::<isize>().ok().map(|l| l + 1))
@@ -339,9 +565,11 @@ where
             .into_owned()
             .into();
 
-        env.set_exported_var(sh_lvl, level, true);
-        env.set_exported_var("PWD".to_owned().into(), cwd.clone(), true);
-        env.set_exported_var("OLDPWD".to_owned().into(), cwd, true);
+        env.extend_exported(vec![
+            (sh_lvl.into(), level, true),
+            ("PWD".to_owned().into(), cwd.clone(), true),
+            ("OLDPWD".to_owned().into(), cwd, true),
+        ]);
         env.set_var("IFS".to_owned().into(), IFS_DEFAULT.to_owned().into());
         env
     }
@@ -362,14 +590,34 @@ where
         Env {
             interactive: self.interactive,
             args_env: self.args_env.clone(),
+            alias_env: self.alias_env.clone(),
             file_desc_manager_env: self.file_desc_manager_env.clone(),
             fn_env: self.fn_env.clone(),
+            command_cache: CommandCache::new(),
+            command_search: self.command_search.clone(),
             fn_frame_env: self.fn_frame_env,
+            getopts_env: self.getopts_env.clone(),
+            job_env: self.job_env.clone(),
+            signal_env: self.signal_env.clone(),
+            error_context: self.error_context.clone(),
+            command_text: self.command_text.clone(),
             last_status_env: self.last_status_env.clone(),
             var_env: self.var_env.clone(),
             exec_env: self.exec_env.clone(),
             working_dir_env: self.working_dir_env.clone(),
             builtin_env: self.builtin_env.clone(),
+            home_dir: self.home_dir.clone(),
+            execution_reporter: self.execution_reporter.clone(),
+            usage_tracker: self.usage_tracker.clone(),
+            clock: self.clock.clone(),
+            rng: self.rng.clone(),
+            options: self.options,
+            errexit_ignored: self.errexit_ignored,
+            spawn_middleware: self.spawn_middleware.clone(),
+            script_runner: self.script_runner,
+            source_stack: self.source_stack.clone(),
+            #[cfg(feature = "metrics")]
+            metrics_sink: self.metrics_sink.clone(),
         }
     }
 }
@@ -389,18 +637,39 @@ where
         use std::collections::BTreeSet;
         let fn_names: BTreeSet<_> = self.fn_env.fn_names().collect();
 
-        fmt.debug_struct(stringify!(Env))
-            .field("interactive", &self.interactive)
+        let mut fmt = fmt.debug_struct(stringify!(Env));
+        fmt.field("interactive", &self.interactive)
             .field("args_env", &self.args_env)
+            .field("alias_env", &self.alias_env)
             .field("file_desc_manager_env", &self.file_desc_manager_env)
             .field("functions", &fn_names)
+            .field("hashed_commands", &self.command_search)
             .field("fn_frame_env", &self.fn_frame_env)
+            .field("getopts_env", &self.getopts_env)
+            .field("job_env", &self.job_env)
+            .field("signal_env", &self.signal_env)
+            .field("error_context", &self.error_context)
+            .field("command_text", &self.command_text)
             .field("last_status_env", &self.last_status_env)
             .field("var_env", &self.var_env)
             .field("exec_env", &self.exec_env)
             .field("working_dir_env", &self.working_dir_env)
             .field("builtin_env", &self.builtin_env)
-            .finish()
+            .field("home_dir", &self.home_dir)
+            .field("execution_reporter", &self.execution_reporter)
+            .field("usage_tracker", &self.usage_tracker)
+            .field("clock", &self.clock)
+            .field("rng", &self.rng)
+            .field("options", &self.options)
+            .field("errexit_ignored", &self.errexit_ignored)
+            .field("spawn_middleware", &self.spawn_middleware.len())
+            .field("script_runner", &self.script_runner)
+            .field("source_stack", &self.source_stack);
+
+        #[cfg(feature = "metrics")]
+        fmt.field("metrics_sink", &self.metrics_sink.is_some());
+
+        fmt.finish()
     }
 }
 
@@ -408,8 +677,8 @@ impl<A, FM, L, V, EX, WD, B, N, ERR> From<EnvConfig<A, FM, L, V, EX, WD, B, N, E
     for Env<A, FM, L, V, EX, WD, B, N, ERR>
 where
     N: Hash + Eq,
-    V: ExportedVariableEnvironment,
-    V::VarName: From<String>,
+    V: ExportedVariableEnvironment + WatchVariableEnvironment,
+    V::VarName: Borrow<String> + From<String>,
     V::Var: Borrow<String> + From<String> + Clone,
     WD: WorkingDirectoryEnvironment,
 {
@@ -433,28 +702,78 @@ where
     A: SubEnvironment,
     FM: SubEnvironment,
     L: SubEnvironment,
-    V: SubEnvironment,
+    V: SubEnvironment + WatchVariableEnvironment,
+    V::VarName: Borrow<String>,
     B: SubEnvironment,
     N: Hash + Eq,
     EX: SubEnvironment,
     WD: SubEnvironment,
 {
     fn sub_env(&self) -> Self {
+        let mut var_env = self.var_env.sub_env();
+        let mut command_search = self.command_search.clone();
+        command_search.watch(&mut var_env);
+
         Env {
             interactive: self.is_interactive(),
             args_env: self.args_env.sub_env(),
+            alias_env: self.alias_env.sub_env(),
             file_desc_manager_env: self.file_desc_manager_env.sub_env(),
             fn_env: self.fn_env.sub_env(),
+            command_cache: CommandCache::new(),
+            command_search,
             fn_frame_env: self.fn_frame_env.sub_env(),
+            getopts_env: self.getopts_env.sub_env(),
+            job_env: self.job_env.sub_env(),
+            signal_env: self.signal_env.sub_env(),
+            error_context: self.error_context.clone(),
+            command_text: self.command_text.clone(),
             last_status_env: self.last_status_env.sub_env(),
-            var_env: self.var_env.sub_env(),
+            var_env,
             exec_env: self.exec_env.sub_env(),
             working_dir_env: self.working_dir_env.sub_env(),
             builtin_env: self.builtin_env.sub_env(),
+            home_dir: self.home_dir.clone(),
+            execution_reporter: self.execution_reporter.clone(),
+            usage_tracker: self.usage_tracker.clone(),
+            clock: self.clock.clone(),
+            rng: self.rng.clone(),
+            options: self.options,
+            errexit_ignored: self.errexit_ignored,
+            spawn_middleware: self.spawn_middleware.clone(),
+            script_runner: self.script_runner,
+            source_stack: self.source_stack.clone(),
+            #[cfg(feature = "metrics")]
+            metrics_sink: self.metrics_sink.clone(),
         }
     }
 }
 
+impl<A, FM, L, V, EX, WD, B, N, ERR> AliasEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    N: Hash + Eq,
+{
+    fn alias(&self, name: &str) -> Option<&str> {
+        self.alias_env.alias(name)
+    }
+
+    fn aliases(&self) -> Vec<(&str, &str)> {
+        self.alias_env.aliases()
+    }
+
+    fn set_alias(&mut self, name: String, value: String) {
+        self.alias_env.set_alias(name, value)
+    }
+
+    fn unalias(&mut self, name: &str) -> Option<String> {
+        self.alias_env.unalias(name)
+    }
+
+    fn unalias_all(&mut self) {
+        self.alias_env.unalias_all()
+    }
+}
+
 impl<A, FM, L, V, EX, WD, B, N, ERR> ArgumentsEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
 where
     A: ArgumentsEnvironment,
@@ -492,13 +811,33 @@ where
     }
 }
 
+impl<A, FM, L, V, EX, WD, B, N, ERR> ModifyArgumentsEnvironment
+    for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    A: ModifyArgumentsEnvironment,
+    A::Arg: Clone,
+    N: Hash + Eq,
+{
+    fn set_arg0(&mut self, arg0: Option<Self::Arg>) {
+        self.args_env.set_arg0(arg0)
+    }
+
+    fn splice_args<R, I>(&mut self, range: R, replace_with: I)
+    where
+        R: RangeBounds<usize>,
+        I: IntoIterator<Item = Self::Arg>,
+    {
+        self.args_env.splice_args(range, replace_with)
+    }
+}
+
 impl<A, FM, L, V, EX, WD, B, N, ERR> ShiftArgumentsEnvironment
     for Env<A, FM, L, V, EX, WD, B, N, ERR>
 where
     A: ShiftArgumentsEnvironment,
     N: Hash + Eq,
 {
-    fn shift_args(&mut self, amt: usize) {
+    fn shift_args(&mut self, amt: usize) -> usize {
         self.args_env.shift_args(amt)
     }
 }
@@ -514,11 +853,29 @@ where
         self.file_desc_manager_env.read_all(fd)
     }
 
+    fn read_async(&mut self, fd: Self::IoHandle) -> io::Result<BoxAsyncRead> {
+        self.file_desc_manager_env.read_async(fd)
+    }
+
     fn write_all<'a>(
         &mut self,
         fd: Self::IoHandle,
         data: Cow<'a, [u8]>,
     ) -> BoxFuture<'a, io::Result<()>> {
+        #[cfg(feature = "metrics")]
+        {
+            if let Some(sink) = self.metrics_sink.clone() {
+                let len = data.len() as u64;
+                let future = self.file_desc_manager_env.write_all(fd, data);
+
+                return Box::pin(async move {
+                    future.await?;
+                    sink.increment_counter(metrics::SHELL_OUTPUT_BYTES, &[], len);
+                    Ok(())
+                });
+            }
+        }
+
         self.file_desc_manager_env.write_all(fd, data)
     }
 
@@ -547,6 +904,27 @@ where
     }
 }
 
+impl<A, FM, L, V, EX, WD, B, N, ERR> FileDescAuditEnvironment
+    for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    FM: FileDescAuditEnvironment,
+    N: Hash + Eq,
+{
+    fn inheritable_fds(&self) -> io::Result<Vec<Fd>> {
+        self.file_desc_manager_env.inheritable_fds()
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> RuntimeInfoEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    FM: RuntimeInfoEnvironment,
+    N: Hash + Eq,
+{
+    fn runtime_info(&self) -> io::Result<RuntimeInfo> {
+        self.file_desc_manager_env.runtime_info()
+    }
+}
+
 impl<A, FM, L, V, EX, WD, B, N, ERR> FileDescOpener for Env<A, FM, L, V, EX, WD, B, N, ERR>
 where
     FM: FileDescOpener,
@@ -554,12 +932,68 @@ where
 {
     type OpenedFileHandle = FM::OpenedFileHandle;
 
-    fn open_path(&mut self, path: &Path, opts: &OpenOptions) -> io::Result<Self::OpenedFileHandle> {
-        self.file_desc_manager_env.open_path(path, opts)
+    fn open_path_with_cloexec(
+        &mut self,
+        path: &Path,
+        opts: &OpenOptions,
+        cloexec: bool,
+    ) -> io::Result<Self::OpenedFileHandle> {
+        self.file_desc_manager_env
+            .open_path_with_cloexec(path, opts, cloexec)
+    }
+
+    fn open_path_with_flags(
+        &mut self,
+        path: &Path,
+        flags: OpenFlags,
+        cloexec: bool,
+    ) -> io::Result<Self::OpenedFileHandle> {
+        self.file_desc_manager_env
+            .open_path_with_flags(path, flags, cloexec)
+    }
+
+    fn open_pipe_with_cloexec(
+        &mut self,
+        cloexec: bool,
+    ) -> io::Result<Pipe<Self::OpenedFileHandle>> {
+        self.file_desc_manager_env.open_pipe_with_cloexec(cloexec)
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    FM: FileDescEnvironment,
+    FM::FileHandle: Borrow<FileDesc>,
+    N: Hash + Eq,
+{
+    fn terminal_file_desc(&self, fd: Fd) -> io::Result<&FileDesc> {
+        self.file_desc(fd)
+            .map(|(handle, _)| handle.borrow())
+            .filter(|fdes| fdes.is_terminal())
+            .ok_or_else(|| not_a_terminal(fd))
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> TerminalEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    FM: FileDescEnvironment,
+    FM::FileHandle: Borrow<FileDesc>,
+    N: Hash + Eq,
+{
+    fn is_terminal(&self, fd: Fd) -> bool {
+        self.terminal_file_desc(fd).is_ok()
+    }
+
+    fn terminal_mode(&self, fd: Fd) -> io::Result<TerminalMode> {
+        self.terminal_file_desc(fd)?.terminal_mode()
+    }
+
+    fn set_terminal_mode(&mut self, fd: Fd, mode: TerminalMode) -> io::Result<()> {
+        self.terminal_file_desc(fd)?.set_terminal_mode(mode)
     }
 
-    fn open_pipe(&mut self) -> io::Result<Pipe<Self::OpenedFileHandle>> {
-        self.file_desc_manager_env.open_pipe()
+    fn window_size(&self, fd: Fd) -> io::Result<WindowSize> {
+        self.terminal_file_desc(fd)?.window_size()
     }
 }
 
@@ -581,13 +1015,160 @@ where
             _ => return Box::pin(async {}),
         };
 
-        let data = format!("{}: {}\n", self.name(), fail).into_bytes();
+        let mut prefix = vec![self.name().to_string()];
+
+        if !self.error_context.is_empty() {
+            // NB: report the innermost context first, e.g.
+            // "in function f, in command substitution: ..."
+            let context = self
+                .error_context
+                .iter()
+                .rev()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            prefix.push(context);
+        }
+
+        if let Some(ref text) = self.command_text {
+            prefix.push(text.clone());
+        }
+
+        let data = format!("{}: {}\n", prefix.join(": "), fail).into_bytes();
         let future = self.write_all(fd.into(), Cow::Owned(data));
 
         Box::pin(async move {
             let _ = future.await;
         })
     }
+
+    fn push_error_context(&mut self, context: ErrorContext) {
+        self.error_context.push(context);
+    }
+
+    fn pop_error_context(&mut self) {
+        self.error_context.pop();
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> HomeDirEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    V: HomeDirEnvironment,
+    N: Hash + Eq,
+{
+    fn home_dir(&self) -> Option<Cow<'_, str>> {
+        match self.home_dir {
+            Some(ref home) => Some(Cow::Borrowed(home)),
+            None => self.var_env.home_dir(),
+        }
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> ExecutionReportEnvironment
+    for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    N: Hash + Eq,
+{
+    fn execution_reporter(&self) -> Option<&ExecutionReporter> {
+        self.execution_reporter.as_ref()
+    }
+
+    fn set_execution_reporter(
+        &mut self,
+        reporter: Option<ExecutionReporter>,
+    ) -> Option<ExecutionReporter> {
+        mem::replace(&mut self.execution_reporter, reporter)
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> ResourceUsageEnvironment
+    for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    N: Hash + Eq,
+{
+    fn usage_tracker(&self) -> Option<&ResourceUsageTracker> {
+        self.usage_tracker.as_ref()
+    }
+
+    fn set_usage_tracker(
+        &mut self,
+        tracker: Option<ResourceUsageTracker>,
+    ) -> Option<ResourceUsageTracker> {
+        mem::replace(&mut self.usage_tracker, tracker)
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> ClockEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    N: Hash + Eq,
+{
+    fn now(&self) -> SystemTime {
+        self.clock.now()
+    }
+
+    fn monotonic_now(&self) -> Instant {
+        self.clock.monotonic_now()
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> RandomEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    N: Hash + Eq,
+{
+    fn next_u32(&self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_below(&self, bound: u32) -> u32 {
+        self.rng.next_below(bound)
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> ShellOptionsEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    N: Hash + Eq,
+{
+    fn options(&self) -> &ShellOptions {
+        &self.options
+    }
+
+    fn options_mut(&mut self) -> &mut ShellOptions {
+        &mut self.options
+    }
+
+    fn is_errexit_enforced(&self) -> bool {
+        self.options.errexit && self.errexit_ignored == 0
+    }
+
+    fn begin_ignoring_errexit(&mut self) {
+        self.errexit_ignored += 1;
+    }
+
+    fn end_ignoring_errexit(&mut self) {
+        debug_assert!(self.errexit_ignored > 0, "errexit was not being ignored");
+        self.errexit_ignored = self.errexit_ignored.saturating_sub(1);
+    }
+
+    fn set_interactive(&mut self, interactive: bool) {
+        self.interactive = interactive;
+        if interactive {
+            self.options.fatal_special_builtin_errors = false;
+        }
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> CommandTextEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    N: Hash + Eq,
+{
+    fn command_text(&self) -> Option<&str> {
+        self.command_text.as_deref()
+    }
+
+    fn set_command_text(&mut self, text: Option<String>) -> Option<String> {
+        mem::replace(&mut self.command_text, text)
+    }
 }
 
 impl<A, FM, L, V, EX, WD, B, N, ERR> FunctionEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
@@ -602,12 +1183,17 @@ where
     }
 
     fn set_function(&mut self, name: Self::FnName, func: Self::Fn) {
+        self.command_cache.invalidate(&name);
         self.fn_env.set_function(name, func);
     }
 
     fn has_function(&self, name: &Self::FnName) -> bool {
         self.fn_env.has_function(name)
     }
+
+    fn function_names(&self) -> Vec<&Self::FnName> {
+        self.fn_env.function_names()
+    }
 }
 
 impl<A, FM, L, V, EX, WD, B, N, ERR> UnsetFunctionEnvironment
@@ -616,6 +1202,7 @@ where
     N: Hash + Eq + Clone,
 {
     fn unset_function(&mut self, name: &Self::FnName) {
+        self.command_cache.invalidate(name);
         self.fn_env.unset_function(name);
     }
 }
@@ -626,11 +1213,13 @@ where
     N: Hash + Eq + Clone,
 {
     fn push_fn_frame(&mut self) {
-        self.fn_frame_env.push_fn_frame()
+        self.fn_frame_env.push_fn_frame();
+        self.getopts_env.push_fn_frame();
     }
 
     fn pop_fn_frame(&mut self) {
-        self.fn_frame_env.pop_fn_frame()
+        self.fn_frame_env.pop_fn_frame();
+        self.getopts_env.pop_fn_frame();
     }
 
     fn is_fn_running(&self) -> bool {
@@ -638,6 +1227,209 @@ where
     }
 }
 
+impl<A, FM, L, V, EX, WD, B, N, ERR> ControlFlowEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    N: Hash + Eq,
+{
+    fn request_control_flow(&mut self, flow: ControlFlow) {
+        self.fn_frame_env.request_control_flow(flow);
+    }
+
+    fn take_control_flow(&mut self) -> Option<ControlFlow> {
+        self.fn_frame_env.take_control_flow()
+    }
+
+    fn push_loop_frame(&mut self) {
+        self.fn_frame_env.push_loop_frame();
+    }
+
+    fn pop_loop_frame(&mut self) {
+        self.fn_frame_env.pop_loop_frame();
+    }
+
+    fn loop_depth(&self) -> usize {
+        self.fn_frame_env.loop_depth()
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> GetoptsEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    V: ExportedVariableEnvironment + UnsetVariableEnvironment,
+    V::VarName: From<String>,
+    V::Var: From<String>,
+    N: Hash + Eq,
+{
+    fn getopts_state(&self) -> Option<GetoptsState> {
+        self.getopts_env.getopts_state()
+    }
+
+    fn set_getopts_state(&mut self, state: Option<GetoptsState>) {
+        self.getopts_env.set_getopts_state(state)
+    }
+
+    fn reset_getopts(&mut self) {
+        self.getopts_env.reset_getopts();
+        self.var_env
+            .set_var("OPTIND".to_owned().into(), "1".to_owned().into());
+        self.var_env.unset_var(&"OPTARG".to_owned().into());
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> JobEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    N: Hash + Eq,
+{
+    fn spawn_job(&mut self, job: BoxFuture<'static, ExitStatus>) -> JobId {
+        self.job_env.spawn_job(job)
+    }
+
+    fn last_job(&self) -> Option<JobId> {
+        self.job_env.last_job()
+    }
+
+    fn job_ids(&self) -> Vec<JobId> {
+        self.job_env.job_ids()
+    }
+
+    fn wait_job(&mut self, id: JobId) -> Option<BoxFuture<'static, ExitStatus>> {
+        self.job_env.wait_job(id)
+    }
+
+    fn any_job_done(&self) -> Option<BoxFuture<'static, ()>> {
+        self.job_env.any_job_done()
+    }
+
+    fn spawn_job_with_usage(
+        &mut self,
+        job: BoxFuture<'static, ExitStatus>,
+        usage: ResourceUsageTracker,
+    ) -> JobId {
+        self.job_env.spawn_job_with_usage(job, usage)
+    }
+
+    fn job_usage(&self, id: JobId) -> Option<ResourceUsage> {
+        self.job_env.job_usage(id)
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> SpawnMiddlewareEnvironment
+    for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    N: Hash + Eq,
+{
+    fn spawn_middleware(&self) -> &[Arc<dyn SpawnMiddleware>] {
+        &self.spawn_middleware
+    }
+
+    fn add_spawn_middleware(&mut self, middleware: Arc<dyn SpawnMiddleware>) {
+        self.spawn_middleware.push(middleware);
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl<A, FM, L, V, EX, WD, B, N, ERR> MetricsEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    N: Hash + Eq,
+{
+    fn metrics_sink(&self) -> Option<&Arc<dyn MetricsSink>> {
+        self.metrics_sink.as_ref()
+    }
+
+    fn set_metrics_sink(&mut self, sink: Option<Arc<dyn MetricsSink>>) {
+        self.metrics_sink = sink;
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> ScriptRunnerEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    N: Hash + Eq,
+{
+    fn script_runner(&self) -> Option<ScriptRunner<Self>> {
+        self.script_runner
+    }
+
+    fn set_script_runner(&mut self, runner: ScriptRunner<Self>) {
+        self.script_runner = Some(runner);
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> SourceStackEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    N: Hash + Eq,
+{
+    fn source_stack(&self) -> &[Arc<Path>] {
+        &self.source_stack
+    }
+
+    fn push_source(&mut self, path: Arc<Path>) {
+        self.source_stack.push(path);
+    }
+
+    fn pop_source(&mut self) -> Option<Arc<Path>> {
+        self.source_stack.pop()
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> SignalEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    N: Hash + Eq,
+{
+    fn trap(&self, signal: Signal) -> &TrapAction {
+        self.signal_env.trap(signal)
+    }
+
+    fn set_trap(&mut self, signal: Signal, action: TrapAction) {
+        self.signal_env.set_trap(signal, action)
+    }
+
+    fn signal_sender(&self) -> SignalSender {
+        self.signal_env.signal_sender()
+    }
+
+    fn take_pending_signals(&mut self) -> Vec<Signal> {
+        self.signal_env.take_pending_signals()
+    }
+}
+
+/// Parses scripts into the same (atomic) AST which `conch-parser`'s
+/// `AtomicDefaultBuilder` produces.
+#[cfg(feature = "conch-parser")]
+impl<A, FM, L, V, EX, WD, B, N, ERR> ParseScriptEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    N: Hash + Eq + From<String>,
+{
+    type Command = conch_parser::ast::AtomicTopLevelCommand<N>;
+
+    fn parse_script(&self, source: &str) -> Result<Vec<Self::Command>, ScriptParseError> {
+        use conch_parser::ast::builder::AtomicDefaultBuilder;
+        use conch_parser::lexer::Lexer;
+        use conch_parser::parse::Parser;
+
+        let lexer = Lexer::new(source.chars());
+        let mut parser = Parser::with_builder(lexer, AtomicDefaultBuilder::new());
+
+        let mut cmds = Vec::new();
+        while let Some(cmd) = parser.complete_command()? {
+            cmds.push(cmd);
+        }
+
+        Ok(cmds)
+    }
+}
+
+/// Parsing scripts is not supported without the `conch-parser` feature.
+#[cfg(not(feature = "conch-parser"))]
+impl<A, FM, L, V, EX, WD, B, N, ERR> ParseScriptEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    N: Hash + Eq,
+{
+    type Command = void::Void;
+
+    fn parse_script(&self, _source: &str) -> Result<Vec<Self::Command>, ScriptParseError> {
+        Err("parsing scripts requires the `conch-parser` feature".into())
+    }
+}
+
 impl<A, FM, L, V, EX, WD, B, N, ERR> LastStatusEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
 where
     L: LastStatusEnvironment,
@@ -652,9 +1444,26 @@ where
     }
 }
 
+impl<A, FM, L, V, EX, WD, B, N, ERR> SubstitutionStatusEnvironment
+    for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    L: SubstitutionStatusEnvironment,
+    N: Hash + Eq,
+{
+    fn last_substitution_status(&self) -> Option<ExitStatus> {
+        self.last_status_env.last_substitution_status()
+    }
+
+    fn set_last_substitution_status(&mut self, status: Option<ExitStatus>) {
+        self.last_status_env.set_last_substitution_status(status);
+    }
+}
+
+/// While the `allexport` option is set, any variables which are set
+/// are marked as exported.
 impl<A, FM, L, V, EX, WD, B, N, ERR> VariableEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
 where
-    V: VariableEnvironment,
+    V: ExportedVariableEnvironment,
     N: Hash + Eq,
 {
     type VarName = V::VarName;
@@ -669,7 +1478,23 @@ where
     }
 
     fn set_var(&mut self, name: Self::VarName, val: Self::Var) {
-        self.var_env.set_var(name, val);
+        if self.options.allexport {
+            self.var_env.set_exported_var(name, val, true);
+        } else {
+            self.var_env.set_var(name, val);
+        }
+    }
+
+    fn set_vars<I>(&mut self, vars: I)
+    where
+        I: IntoIterator<Item = (Self::VarName, Self::Var)>,
+    {
+        if self.options.allexport {
+            let vars = vars.into_iter().map(|(name, val)| (name, val, true));
+            self.var_env.extend_exported(vars);
+        } else {
+            self.var_env.set_vars(vars);
+        }
     }
 
     fn env_vars(&self) -> Cow<'_, [(&Self::VarName, &Self::Var)]> {
@@ -677,6 +1502,33 @@ where
     }
 }
 
+/// As with `VariableEnvironment`, any variables which are assigned
+/// while the `allexport` option is set are marked as exported.
+impl<A, FM, L, V, EX, WD, B, N, ERR> ArithVariableEnvironment
+    for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    V: ArithVariableEnvironment + ExportedVariableEnvironment,
+    V::Var: From<String>,
+    N: Hash + Eq,
+{
+    fn arith_var<Q: ?Sized>(&self, name: &Q) -> isize
+    where
+        Self::VarName: Borrow<Q>,
+        Q: Hash + Eq,
+    {
+        self.var_env.arith_var(name)
+    }
+
+    fn set_arith_var(&mut self, name: Self::VarName, val: isize) {
+        if self.options.allexport {
+            self.var_env
+                .set_exported_var(name, val.to_string().into(), true);
+        } else {
+            self.var_env.set_arith_var(name, val);
+        }
+    }
+}
+
 impl<A, FM, L, V, EX, WD, B, N, ERR> ExportedVariableEnvironment
     for Env<A, FM, L, V, EX, WD, B, N, ERR>
 where
@@ -690,12 +1542,19 @@ where
     fn set_exported_var(&mut self, name: Self::VarName, val: Self::Var, exported: bool) {
         self.var_env.set_exported_var(name, val, exported)
     }
+
+    fn extend_exported<I>(&mut self, vars: I)
+    where
+        I: IntoIterator<Item = (Self::VarName, Self::Var, bool)>,
+    {
+        self.var_env.extend_exported(vars)
+    }
 }
 
 impl<A, FM, L, V, EX, WD, B, N, ERR> UnsetVariableEnvironment
     for Env<A, FM, L, V, EX, WD, B, N, ERR>
 where
-    V: UnsetVariableEnvironment,
+    V: ExportedVariableEnvironment + UnsetVariableEnvironment,
     N: Hash + Eq,
 {
     fn unset_var(&mut self, name: &V::VarName) {
@@ -703,6 +1562,43 @@ where
     }
 }
 
+impl<A, FM, L, V, EX, WD, B, N, ERR> ReadonlyVariableEnvironment
+    for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    V: ExportedVariableEnvironment + ReadonlyVariableEnvironment,
+    N: Hash + Eq,
+{
+    fn is_readonly(&self, name: &V::VarName) -> bool {
+        self.var_env.is_readonly(name)
+    }
+
+    fn set_readonly(&mut self, name: V::VarName) {
+        self.var_env.set_readonly(name)
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> TaintEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    V: ExportedVariableEnvironment + TaintEnvironment,
+    N: Hash + Eq,
+{
+    fn is_tainted(&self, name: &Self::VarName) -> bool {
+        self.var_env.is_tainted(name)
+    }
+
+    fn set_tainted(&mut self, name: Self::VarName, tainted: bool) {
+        self.var_env.set_tainted(name, tainted)
+    }
+
+    fn taint_tracker(&self) -> &TaintTracker {
+        self.var_env.taint_tracker()
+    }
+
+    fn set_taint_tracker(&mut self, tracker: TaintTracker) -> TaintTracker {
+        self.var_env.set_taint_tracker(tracker)
+    }
+}
+
 impl<A, FM, L, V, EX, WD, B, N, ERR> ExecutableEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
 where
     N: Hash + Eq,
@@ -714,6 +1610,51 @@ where
     ) -> Result<BoxFuture<'static, ExitStatus>, CommandError> {
         self.exec_env.spawn_executable(data)
     }
+
+    fn spawn_executable_with_usage(
+        &self,
+        data: ExecutableData<'_>,
+    ) -> Result<BoxFuture<'static, (ExitStatus, Option<ResourceUsage>)>, CommandError> {
+        self.exec_env.spawn_executable_with_usage(data)
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> ExecReplaceEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    N: Hash + Eq,
+    EX: ExecReplaceEnvironment,
+{
+    fn exec_replace(
+        &self,
+        data: ExecutableData<'_>,
+    ) -> Result<BoxFuture<'static, Infallible>, CommandError> {
+        self.exec_env.exec_replace(data)
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> ProcessTimesEnvironment for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    N: Hash + Eq,
+    EX: ProcessTimesEnvironment,
+{
+    fn process_times(&self) -> io::Result<ProcessTimes> {
+        self.exec_env.process_times()
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> ResourceLimitEnvironment
+    for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    N: Hash + Eq,
+    EX: ResourceLimitEnvironment,
+{
+    fn resource_limit(&self, resource: Resource) -> io::Result<ResourceLimit> {
+        self.exec_env.resource_limit(resource)
+    }
+
+    fn set_resource_limit(&mut self, resource: Resource, limit: ResourceLimit) -> io::Result<()> {
+        self.exec_env.set_resource_limit(resource, limit)
+    }
 }
 
 impl<A, FM, L, V, EX, WD, B, N, ERR> WorkingDirectoryEnvironment
@@ -735,7 +1676,7 @@ impl<A, FM, L, V, EX, WD, B, N, ERR> ChangeWorkingDirectoryEnvironment
     for Env<A, FM, L, V, EX, WD, B, N, ERR>
 where
     N: Hash + Eq,
-    V: VariableEnvironment,
+    V: ExportedVariableEnvironment,
     V::VarName: From<String>,
     V::Var: From<String>,
     WD: WorkingDirectoryEnvironment,
@@ -774,6 +1715,63 @@ where
     fn builtin(&self, name: &Self::BuiltinName) -> Option<Self::Builtin> {
         self.builtin_env.builtin(name)
     }
+
+    fn builtin_names(&self) -> Vec<Self::BuiltinName> {
+        self.builtin_env.builtin_names()
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> ResolveCommandEnvironment
+    for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    N: Hash + Eq + Clone,
+    B: BuiltinEnvironment<BuiltinName = N>,
+    B::Builtin: 'static + Send + Sync + Clone,
+{
+    fn resolve_command(&mut self, name: &Self::FnName) -> ResolvedCommand<Self::Fn, Self::Builtin> {
+        let fn_env = &self.fn_env;
+        let builtin_env = &self.builtin_env;
+
+        self.command_cache.resolve(name, || {
+            if let Some(func) = fn_env.function(name) {
+                ResolvedCommand::Function(func.clone())
+            } else if let Some(builtin) = builtin_env.builtin(name) {
+                ResolvedCommand::Builtin(builtin)
+            } else {
+                ResolvedCommand::External
+            }
+        })
+    }
+}
+
+impl<A, FM, L, V, EX, WD, B, N, ERR> CommandSearchEnvironment
+    for Env<A, FM, L, V, EX, WD, B, N, ERR>
+where
+    V: ExportedVariableEnvironment,
+    V::VarName: Borrow<String>,
+    V::Var: Borrow<String>,
+    WD: WorkingDirectoryEnvironment,
+    N: Hash + Eq,
+{
+    fn search_command(&mut self, name: &str) -> Option<PathBuf> {
+        let path = self.var_env.var(&*PATH).map(|path| path.borrow().as_str());
+        let working_dir_env = &self.working_dir_env;
+
+        self.command_search
+            .search(name, path, self.options.manual_rehash, |dir| {
+                working_dir_env
+                    .path_relative_to_working_dir(Cow::Owned(dir))
+                    .into_owned()
+            })
+    }
+
+    fn hashed_commands(&self) -> Vec<(String, PathBuf)> {
+        self.command_search.entries(self.options.manual_rehash)
+    }
+
+    fn forget_hashed_commands(&mut self) {
+        self.command_search.clear();
+    }
 }
 
 /// A default environment configured with provided (non-atomic) implementations.
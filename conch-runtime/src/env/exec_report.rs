@@ -0,0 +1,238 @@
+use crate::env::{Clock, ClockEnvironment, ResourceUsage};
+use crate::io::Permissions;
+use crate::{ExitStatus, Fd};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// An interface for accessing the reporter which records the commands executed
+/// by an environment, if any.
+pub trait ExecutionReportEnvironment {
+    /// Get the reporter which should record any newly executed commands, if any.
+    fn execution_reporter(&self) -> Option<&ExecutionReporter>;
+
+    /// Change the reporter which should record any newly executed commands,
+    /// returning the previous one so that it can be restored later.
+    fn set_execution_reporter(
+        &mut self,
+        reporter: Option<ExecutionReporter>,
+    ) -> Option<ExecutionReporter>;
+}
+
+impl<'a, T: ?Sized + ExecutionReportEnvironment> ExecutionReportEnvironment for &'a mut T {
+    fn execution_reporter(&self) -> Option<&ExecutionReporter> {
+        (**self).execution_reporter()
+    }
+
+    fn set_execution_reporter(
+        &mut self,
+        reporter: Option<ExecutionReporter>,
+    ) -> Option<ExecutionReporter> {
+        (**self).set_execution_reporter(reporter)
+    }
+}
+
+/// A structured report of all commands executed by an environment
+/// (and any of its sub-environments).
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ExecutionReport {
+    /// The outermost commands, in the order they started executing.
+    pub commands: Vec<CommandReport>,
+}
+
+/// A record of a single executed command.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandReport {
+    /// The fully expanded command name and its arguments.
+    pub argv: Vec<String>,
+    /// Any redirects applied to the command.
+    pub redirects: Vec<RedirectReport>,
+    /// The exit status of the command, or `None` if the command did not
+    /// complete (e.g. it could not be spawned, or it was cancelled).
+    pub status: Option<ExitStatus>,
+    /// How long the command took to complete, or `None` if it did not complete.
+    pub duration: Option<Duration>,
+    /// The resources used by the command if it was an executable, and they
+    /// could be determined (see `ExecutableEnvironment::spawn_executable_with_usage`).
+    pub resource_usage: Option<ResourceUsage>,
+    /// Any commands executed on behalf of this one, e.g. within a function body.
+    pub children: Vec<CommandReport>,
+}
+
+/// A record of a redirect applied to an executed command.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RedirectReport {
+    /// The redirected file descriptor.
+    pub fd: Fd,
+    /// The permissions of the file descriptor as seen by the command,
+    /// or `None` if it was closed.
+    pub permissions: Option<Permissions>,
+}
+
+/// A handle for accumulating an `ExecutionReport` of any executed commands.
+///
+/// Any clones of this handle (and sub-environments of the environment it belongs to)
+/// record into the same report, which can be retrieved at any point (e.g. once
+/// a script has finished running) via `ExecutionReporter::report`. Two reporters
+/// are considered equal if they record into the same report at the same level of nesting.
+#[derive(Default, Debug, Clone)]
+pub struct ExecutionReporter {
+    inner: Arc<Mutex<ReportTree>>,
+    /// The command within which any newly started commands are nested, if any.
+    parent: Option<usize>,
+    /// The clock used for measuring the duration of commands.
+    clock: Clock,
+}
+
+impl PartialEq for ExecutionReporter {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner) && self.parent == other.parent
+    }
+}
+
+impl Eq for ExecutionReporter {}
+
+#[derive(Default, Debug)]
+struct ReportTree {
+    roots: Vec<usize>,
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug)]
+struct Node {
+    argv: Vec<String>,
+    redirects: Vec<RedirectReport>,
+    status: Option<ExitStatus>,
+    duration: Option<Duration>,
+    resource_usage: Option<ResourceUsage>,
+    children: Vec<usize>,
+}
+
+impl ReportTree {
+    fn command_report(&self, id: usize) -> CommandReport {
+        let node = &self.nodes[id];
+
+        CommandReport {
+            argv: node.argv.clone(),
+            redirects: node.redirects.clone(),
+            status: node.status,
+            duration: node.duration,
+            resource_usage: node.resource_usage,
+            children: node
+                .children
+                .iter()
+                .map(|&child| self.command_report(child))
+                .collect(),
+        }
+    }
+}
+
+impl ExecutionReporter {
+    /// Creates a new reporter which has yet to record any commands.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new reporter which measures the duration of commands using
+    /// the provided clock, instead of the system clock.
+    pub fn with_clock(clock: Clock) -> Self {
+        Self {
+            clock,
+            ..Self::default()
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ReportTree> {
+        lock(&self.inner)
+    }
+
+    /// Records that a command has started executing, returning a handle
+    /// through which its completion should be recorded.
+    pub fn start_command(
+        &self,
+        argv: Vec<String>,
+        redirects: Vec<RedirectReport>,
+    ) -> CommandReportHandle {
+        let mut tree = self.lock();
+
+        let id = tree.nodes.len();
+        tree.nodes.push(Node {
+            argv,
+            redirects,
+            status: None,
+            duration: None,
+            resource_usage: None,
+            children: Vec::new(),
+        });
+
+        match self.parent {
+            Some(parent) => tree.nodes[parent].children.push(id),
+            None => tree.roots.push(id),
+        }
+
+        CommandReportHandle {
+            inner: self.inner.clone(),
+            id,
+            started: self.clock.monotonic_now(),
+            clock: self.clock.clone(),
+        }
+    }
+
+    /// Returns a snapshot of all commands recorded so far.
+    pub fn report(&self) -> ExecutionReport {
+        let tree = self.lock();
+
+        ExecutionReport {
+            commands: tree
+                .roots
+                .iter()
+                .map(|&root| tree.command_report(root))
+                .collect(),
+        }
+    }
+}
+
+/// A handle to a command recorded by an `ExecutionReporter` which has yet to complete.
+#[derive(Debug)]
+pub struct CommandReportHandle {
+    inner: Arc<Mutex<ReportTree>>,
+    id: usize,
+    started: Instant,
+    clock: Clock,
+}
+
+impl CommandReportHandle {
+    /// Returns a reporter which records any commands it starts as children of this one.
+    pub fn nested_reporter(&self) -> ExecutionReporter {
+        ExecutionReporter {
+            inner: self.inner.clone(),
+            parent: Some(self.id),
+            clock: self.clock.clone(),
+        }
+    }
+
+    /// Records that the command has completed with the provided status.
+    pub fn finish(self, status: ExitStatus) {
+        self.finish_with_usage(status, None)
+    }
+
+    /// Records that the command has completed with the provided status,
+    /// along with the resources it used (if known).
+    pub fn finish_with_usage(self, status: ExitStatus, resource_usage: Option<ResourceUsage>) {
+        let duration = self
+            .clock
+            .monotonic_now()
+            .saturating_duration_since(self.started);
+        let node = &mut lock(&self.inner).nodes[self.id];
+
+        node.status = Some(status);
+        node.duration = Some(duration);
+        node.resource_usage = resource_usage;
+    }
+}
+
+fn lock(inner: &Mutex<ReportTree>) -> MutexGuard<'_, ReportTree> {
+    inner.lock().unwrap_or_else(PoisonError::into_inner)
+}
@@ -1,19 +1,50 @@
-use crate::env::SubEnvironment;
+#[cfg(all(unix, feature = "spawn-policy"))]
+use crate::env::spawn_policy::PolicyFailures;
+#[cfg(all(target_os = "linux", feature = "cgroups"))]
+use crate::env::Cgroup;
+#[cfg(all(unix, feature = "spawn-policy"))]
+use crate::env::SpawnPolicy;
+use crate::env::{
+    ProcessTimes, ProcessTimesEnvironment, Resource, ResourceLimit, ResourceLimitEnvironment,
+    SubEnvironment,
+};
 use crate::error::CommandError;
 use crate::io::FileDesc;
+#[cfg(windows)]
+use crate::sys::job::JobObject;
 use crate::{ExitStatus, EXIT_ERROR};
 use futures_core::future::BoxFuture;
+use std::convert::Infallible;
 use std::ffi::OsStr;
-use std::io::{Error as IoError, ErrorKind as IoErrorKind};
-use std::path::Path;
-use std::process::Stdio;
-use tokio::process::Command;
+use std::fmt;
+use std::io::{self, Error as IoError, ErrorKind as IoErrorKind};
+use std::path::{Path, PathBuf};
+use std::process::{self, Stdio};
+#[cfg(any(
+    windows,
+    all(unix, feature = "spawn-policy"),
+    all(target_os = "linux", feature = "cgroups")
+))]
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::process::{Child, Command};
+#[cfg(unix)]
+use tokio::signal::unix::{signal, Signal, SignalKind};
+
+/// The offset added to the number of a signal which terminated an executable,
+/// when its status is propagated as an exit code.
+const EXIT_SIGNAL_OFFSET: i32 = 128;
 
 /// Any data required to execute a child process.
 #[derive(Debug, PartialEq, Eq)]
 pub struct ExecutableData<'a> {
     /// The name/path to the executable.
     pub name: &'a OsStr,
+    /// The location of the executable if it has already been resolved (e.g. via
+    /// `CommandSearchEnvironment`), in which case it should be executed instead
+    /// of looking up `name` (which should still be used as the executable's
+    /// first argument on platforms which support it).
+    pub path: Option<&'a Path>,
     /// Arguments to be provided to the executable.
     pub args: &'a [&'a OsStr],
     /// Any environment variables that should be passed to the executable.
@@ -22,15 +53,162 @@ pub struct ExecutableData<'a> {
     pub env_vars: &'a [(&'a OsStr, &'a OsStr)],
     /// The current working directory the executable should start out with.
     pub current_dir: &'a Path,
-    /// The executable's standard input will be redirected to this descriptor
-    /// or the equivalent of `/dev/null` if not specified.
-    pub stdin: Option<FileDesc>,
-    /// The executable's standard output will be redirected to this descriptor
-    /// or the equivalent of `/dev/null` if not specified.
-    pub stdout: Option<FileDesc>,
-    /// The executable's standard error will be redirected to this descriptor
-    /// or the equivalent of `/dev/null` if not specified.
-    pub stderr: Option<FileDesc>,
+    /// How the executable's standard input should be set up.
+    pub stdin: ExecutableStdio,
+    /// How the executable's standard output should be set up.
+    pub stdout: ExecutableStdio,
+    /// How the executable's standard error should be set up.
+    pub stderr: ExecutableStdio,
+    /// Indicates if the name of the executable was derived from the values
+    /// of any tainted variables (see `TaintEnvironment`).
+    pub name_tainted: bool,
+    /// Indicates which of the `args` were derived from the values of any
+    /// tainted variables (any missing entries are considered untainted).
+    pub args_tainted: &'a [bool],
+}
+
+impl<'a> ExecutableData<'a> {
+    /// Start building the data for executing `name` within `current_dir`.
+    ///
+    /// Unless specified otherwise, the executable will receive no arguments and
+    /// no environment variables, and all of its standard streams will be null.
+    pub fn builder(name: &'a OsStr, current_dir: &'a Path) -> ExecutableDataBuilder<'a> {
+        ExecutableDataBuilder {
+            data: ExecutableData {
+                name,
+                path: None,
+                args: &[],
+                env_vars: &[],
+                current_dir,
+                stdin: ExecutableStdio::Null,
+                stdout: ExecutableStdio::Null,
+                stderr: ExecutableStdio::Null,
+                name_tainted: false,
+                args_tainted: &[],
+            },
+        }
+    }
+
+    /// Checks if the name or any of the arguments of the executable were
+    /// derived from the values of any tainted variables.
+    pub fn is_tainted(&self) -> bool {
+        self.name_tainted || self.args_tainted.contains(&true)
+    }
+
+    /// Checks if the argument at the specified index was derived from
+    /// the values of any tainted variables.
+    pub fn is_arg_tainted(&self, index: usize) -> bool {
+        self.args_tainted.get(index).copied().unwrap_or(false)
+    }
+}
+
+/// A builder for `ExecutableData`, created via `ExecutableData::builder`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ExecutableDataBuilder<'a> {
+    data: ExecutableData<'a>,
+}
+
+impl<'a> ExecutableDataBuilder<'a> {
+    /// Set the already resolved location of the executable.
+    pub fn path(mut self, path: &'a Path) -> Self {
+        self.data.path = Some(path);
+        self
+    }
+
+    /// Set the arguments to be provided to the executable.
+    pub fn args(mut self, args: &'a [&'a OsStr]) -> Self {
+        self.data.args = args;
+        self
+    }
+
+    /// Set the (only) environment variables to be provided to the executable.
+    pub fn env_vars(mut self, env_vars: &'a [(&'a OsStr, &'a OsStr)]) -> Self {
+        self.data.env_vars = env_vars;
+        self
+    }
+
+    /// Set up the executable's standard input.
+    pub fn stdin<T: Into<ExecutableStdio>>(mut self, stdin: T) -> Self {
+        self.data.stdin = stdin.into();
+        self
+    }
+
+    /// Set up the executable's standard output.
+    pub fn stdout<T: Into<ExecutableStdio>>(mut self, stdout: T) -> Self {
+        self.data.stdout = stdout.into();
+        self
+    }
+
+    /// Set up the executable's standard error.
+    pub fn stderr<T: Into<ExecutableStdio>>(mut self, stderr: T) -> Self {
+        self.data.stderr = stderr.into();
+        self
+    }
+
+    /// Mark the name of the executable as tainted (or not).
+    pub fn name_tainted(mut self, tainted: bool) -> Self {
+        self.data.name_tainted = tainted;
+        self
+    }
+
+    /// Set which of the arguments are tainted.
+    pub fn args_tainted(mut self, tainted: &'a [bool]) -> Self {
+        self.data.args_tainted = tainted;
+        self
+    }
+
+    /// Finish building the data.
+    pub fn build(self) -> ExecutableData<'a> {
+        self.data
+    }
+}
+
+/// Describes how a standard stream of an executable should be set up.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub enum ExecutableStdio {
+    /// Inherit the corresponding stream of the *current process* (rather
+    /// than that of the shell environment).
+    Inherit,
+    /// Redirect the stream to the equivalent of `/dev/null`.
+    #[default]
+    Null,
+    /// Redirect the stream to the provided descriptor.
+    Piped(FileDesc),
+}
+
+impl From<FileDesc> for ExecutableStdio {
+    fn from(fdes: FileDesc) -> Self {
+        ExecutableStdio::Piped(fdes)
+    }
+}
+
+impl From<Option<FileDesc>> for ExecutableStdio {
+    fn from(fdes: Option<FileDesc>) -> Self {
+        fdes.map_or(ExecutableStdio::Null, ExecutableStdio::Piped)
+    }
+}
+
+impl From<ExecutableStdio> for Stdio {
+    fn from(stdio: ExecutableStdio) -> Self {
+        match stdio {
+            ExecutableStdio::Inherit => Stdio::inherit(),
+            ExecutableStdio::Null => Stdio::null(),
+            ExecutableStdio::Piped(fdes) => fdes.into(),
+        }
+    }
+}
+
+/// The resources used by an executable which has exited (including those used
+/// by any of its descendants which it waited for), as reported by the OS.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ResourceUsage {
+    /// The maximum resident set size of the executable, in bytes.
+    pub max_rss: u64,
+    /// The CPU time the executable spent executing in user mode.
+    pub user_time: Duration,
+    /// The CPU time the executable spent executing in kernel mode.
+    pub system_time: Duration,
 }
 
 /// An interface for asynchronously spawning executables.
@@ -40,6 +218,19 @@ pub trait ExecutableEnvironment {
         &self,
         data: ExecutableData<'_>,
     ) -> Result<BoxFuture<'static, ExitStatus>, CommandError>;
+
+    /// Attempt to spawn the executable command exactly like `spawn_executable`,
+    /// but resolve with the resources it used (if they can be determined)
+    /// along with its exit status.
+    ///
+    /// By default, no resource usage is collected.
+    fn spawn_executable_with_usage(
+        &self,
+        data: ExecutableData<'_>,
+    ) -> Result<BoxFuture<'static, (ExitStatus, Option<ResourceUsage>)>, CommandError> {
+        let child = self.spawn_executable(data)?;
+        Ok(Box::pin(async move { (child.await, None) }))
+    }
 }
 
 impl<'a, T: ExecutableEnvironment> ExecutableEnvironment for &'a T {
@@ -49,13 +240,102 @@ impl<'a, T: ExecutableEnvironment> ExecutableEnvironment for &'a T {
     ) -> Result<BoxFuture<'static, ExitStatus>, CommandError> {
         (**self).spawn_executable(data)
     }
+
+    fn spawn_executable_with_usage(
+        &self,
+        data: ExecutableData<'_>,
+    ) -> Result<BoxFuture<'static, (ExitStatus, Option<ResourceUsage>)>, CommandError> {
+        (**self).spawn_executable_with_usage(data)
+    }
+}
+
+/// An interface for replacing the current process with an executable,
+/// e.g. for the `exec` builtin.
+pub trait ExecReplaceEnvironment {
+    /// Attempt to replace the current process with the executable command.
+    ///
+    /// If successful, control never returns to the current process. Platforms
+    /// which are unable to replace a process may instead spawn the executable
+    /// and return a future which exits the current process with the same status
+    /// as the executable once it completes (and thus never resolves).
+    fn exec_replace(
+        &self,
+        data: ExecutableData<'_>,
+    ) -> Result<BoxFuture<'static, Infallible>, CommandError>;
+}
+
+impl<'a, T: ExecReplaceEnvironment> ExecReplaceEnvironment for &'a T {
+    fn exec_replace(
+        &self,
+        data: ExecutableData<'_>,
+    ) -> Result<BoxFuture<'static, Infallible>, CommandError> {
+        (**self).exec_replace(data)
+    }
+}
+
+/// Opt-in isolation options for spawned executables, e.g. for embedders
+/// which wish to sandbox any commands executed by a script.
+///
+/// All options are disabled by default. Note that applying most of them requires
+/// elevated privileges (or an appropriately configured user namespace), and any
+/// failures to apply them will fail to spawn the executable.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SpawnIsolation {
+    /// Spawn the executable in a new mount namespace (Linux only).
+    pub unshare_mount: bool,
+    /// Spawn the executable in a new network namespace (Linux only).
+    pub unshare_net: bool,
+    /// Spawn any children of the executable in a new PID namespace (Linux only).
+    ///
+    /// Note that the executable itself retains its PID, but its first child
+    /// will become the init process of the new namespace.
+    pub unshare_pid: bool,
+    /// Change the root directory of the executable to the provided path (Unix only).
+    ///
+    /// The executable's working directory (as well as its name, if it is a path)
+    /// will then be resolved relative to the new root.
+    pub chroot: Option<PathBuf>,
+}
+
+impl SpawnIsolation {
+    /// Checks if any isolation options are enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.unshare_mount || self.unshare_net || self.unshare_pid || self.chroot.is_some()
+    }
 }
 
 /// An `ExecutableEnvironment` implementation that uses `tokio`
 /// to monitor when child processes have exited.
-#[derive(Clone, Debug, Default)]
-#[allow(missing_copy_implementations)]
-pub struct TokioExecEnv(());
+#[derive(Clone, Default)]
+pub struct TokioExecEnv {
+    isolation: SpawnIsolation,
+    #[cfg(all(unix, feature = "spawn-policy"))]
+    policy: Option<Arc<dyn SpawnPolicy + Send + Sync>>,
+    #[cfg(windows)]
+    job: Option<Arc<JobObject>>,
+    #[cfg(all(target_os = "linux", feature = "cgroups"))]
+    cgroup: Option<Arc<Cgroup>>,
+    #[cfg(unix)]
+    resource_limits: Vec<(Resource, ResourceLimit)>,
+}
+
+impl fmt::Debug for TokioExecEnv {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut fmt = fmt.debug_struct(stringify!(TokioExecEnv));
+        fmt.field("isolation", &self.isolation);
+
+        #[cfg(all(unix, feature = "spawn-policy"))]
+        fmt.field("has_policy", &self.policy.is_some());
+        #[cfg(windows)]
+        fmt.field("job", &self.job);
+        #[cfg(all(target_os = "linux", feature = "cgroups"))]
+        fmt.field("cgroup", &self.cgroup);
+        #[cfg(unix)]
+        fmt.field("resource_limits", &self.resource_limits);
+
+        fmt.finish()
+    }
+}
 
 impl SubEnvironment for TokioExecEnv {
     fn sub_env(&self) -> Self {
@@ -66,26 +346,224 @@ impl SubEnvironment for TokioExecEnv {
 impl TokioExecEnv {
     /// Construct a new environment.
     pub fn new() -> Self {
-        Self(())
+        Self::with_isolation(SpawnIsolation::default())
+    }
+
+    /// Construct a new environment which applies the provided isolation
+    /// options to all spawned executables.
+    pub fn with_isolation(isolation: SpawnIsolation) -> Self {
+        Self {
+            isolation,
+            #[cfg(all(unix, feature = "spawn-policy"))]
+            policy: None,
+            #[cfg(windows)]
+            job: None,
+            #[cfg(all(target_os = "linux", feature = "cgroups"))]
+            cgroup: None,
+            #[cfg(unix)]
+            resource_limits: Vec::new(),
+        }
+    }
+
+    /// Get the isolation options applied to all spawned executables.
+    pub fn isolation(&self) -> &SpawnIsolation {
+        &self.isolation
+    }
+
+    /// Attach a policy which will be installed within every spawned executable
+    /// (after any isolation options have been applied).
+    #[cfg(all(unix, feature = "spawn-policy"))]
+    pub fn with_spawn_policy<P>(mut self, policy: P) -> Self
+    where
+        P: 'static + SpawnPolicy + Send + Sync,
+    {
+        self.policy = Some(Arc::new(policy));
+        self
+    }
+
+    /// Add every spawned executable (and thus all of its descendants) to the
+    /// provided job, so that entire process trees can be terminated (or have
+    /// their resources limited) together.
+    ///
+    /// The job is shared by all sub-environments, and all processes within it
+    /// are terminated once the last environment sharing it has been dropped.
+    ///
+    /// Note that processes are added to the job right after they have been
+    /// spawned, thus any descendants they manage to spawn beforehand will not
+    /// be part of the job.
+    #[cfg(windows)]
+    pub fn with_job_object(mut self, job: JobObject) -> Self {
+        self.job = Some(Arc::new(job));
+        self
+    }
+
+    /// Get the job which all spawned executables are added to, if any.
+    #[cfg(windows)]
+    pub fn job_object(&self) -> Option<&JobObject> {
+        self.job.as_deref()
+    }
+
+    /// Place every spawned executable (and thus all of its descendants) into
+    /// the provided cgroup, so that their resource usage can be limited together.
+    ///
+    /// The cgroup is shared by all sub-environments, and executables are moved
+    /// into it before they start running.
+    #[cfg(all(target_os = "linux", feature = "cgroups"))]
+    pub fn with_cgroup(mut self, cgroup: Cgroup) -> Self {
+        self.cgroup = Some(Arc::new(cgroup));
+        self
+    }
+
+    /// Get the cgroup which all spawned executables are placed into, if any.
+    #[cfg(all(target_os = "linux", feature = "cgroups"))]
+    pub fn cgroup(&self) -> Option<&Cgroup> {
+        self.cgroup.as_deref()
+    }
+}
+
+/// Resource limits are only supported on Unix systems, where they are applied
+/// to spawned executables (via `setrlimit(2)`) before they start running. Any
+/// changes are retained by the environment (and inherited by sub-environments)
+/// rather than being applied to the current process.
+impl ResourceLimitEnvironment for TokioExecEnv {
+    #[cfg(unix)]
+    fn resource_limit(&self, resource: Resource) -> io::Result<ResourceLimit> {
+        let limit = self
+            .resource_limits
+            .iter()
+            .find(|&&(r, _)| r == resource)
+            .map(|&(_, limit)| limit);
+
+        match limit {
+            Some(limit) => Ok(limit),
+            None => crate::sys::rlimit::get_rlimit(resource),
+        }
+    }
+
+    #[cfg(unix)]
+    fn set_resource_limit(&mut self, resource: Resource, limit: ResourceLimit) -> io::Result<()> {
+        if !limit.is_valid() {
+            let msg = "the soft limit cannot exceed the hard limit";
+            return Err(IoError::new(IoErrorKind::InvalidInput, msg));
+        }
+
+        let current = self.resource_limit(resource)?;
+        let is_raised = match (current.hard, limit.hard) {
+            (None, _) => false,
+            (Some(_), None) => true,
+            (Some(current), Some(hard)) => hard > current,
+        };
+
+        // NB: only a privileged process may raise its hard limits
+        if is_raised && unsafe { libc::geteuid() } != 0 {
+            let msg = "the hard limit cannot be raised";
+            return Err(IoError::new(IoErrorKind::PermissionDenied, msg));
+        }
+
+        match self
+            .resource_limits
+            .iter_mut()
+            .find(|(r, _)| *r == resource)
+        {
+            Some((_, existing)) => *existing = limit,
+            None => self.resource_limits.push((resource, limit)),
+        }
+
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn resource_limit(&self, _resource: Resource) -> io::Result<ResourceLimit> {
+        Err(unsupported_resource_limits())
     }
+
+    #[cfg(not(unix))]
+    fn set_resource_limit(&mut self, _resource: Resource, _limit: ResourceLimit) -> io::Result<()> {
+        Err(unsupported_resource_limits())
+    }
+}
+
+#[cfg(not(unix))]
+fn unsupported_resource_limits() -> IoError {
+    let msg = "resource limits are not supported on this platform";
+    IoError::new(IoErrorKind::Other, msg)
 }
 
+/// Process times are only reported on Unix systems (via `getrusage(2)`), and
+/// are those of the current process as a whole. Thus the times of children
+/// spawned (and reaped) by any other environments are included as well.
+impl ProcessTimesEnvironment for TokioExecEnv {
+    #[cfg(unix)]
+    fn process_times(&self) -> io::Result<ProcessTimes> {
+        crate::sys::rusage::process_times()
+    }
+
+    #[cfg(not(unix))]
+    fn process_times(&self) -> io::Result<ProcessTimes> {
+        let msg = "process times are not supported on this platform";
+        Err(IoError::new(IoErrorKind::Other, msg))
+    }
+}
+
+/// Resource usage is only collected on Unix systems, where children whose usage
+/// is collected are reaped (via `wait4(2)`) whenever a `SIGCHLD` is delivered,
+/// instead of by tokio. If such a future is dropped before its child exits, the
+/// child is killed and reaped on a blocking thread.
 impl ExecutableEnvironment for TokioExecEnv {
     fn spawn_executable(
         &self,
         data: ExecutableData<'_>,
     ) -> Result<BoxFuture<'static, ExitStatus>, CommandError> {
-        let stdio = |fdes: Option<FileDesc>| fdes.map(Into::into).unwrap_or_else(Stdio::null);
+        let prepared = self.prepare(data)?;
+        self.spawn_prepared(prepared)
+    }
 
+    #[cfg(unix)]
+    fn spawn_executable_with_usage(
+        &self,
+        data: ExecutableData<'_>,
+    ) -> Result<BoxFuture<'static, (ExitStatus, Option<ResourceUsage>)>, CommandError> {
+        let prepared = self.prepare(data)?;
+
+        // NB: listen for SIGCHLD before spawning so that the child's exit cannot be missed
+        let sigchld = signal(SignalKind::child())
+            .map_err(|err| CommandError::Io(err, Some(prepared.name.clone())))?;
+
+        // NB: the child is killed (if cancelled) and reaped by `wait_with_usage` instead
+        let (child, _) = prepared.spawn(|mut cmd| cmd.spawn())?;
+        Ok(Box::pin(wait_with_usage(child, sigchld)))
+    }
+}
+
+/// A command which has been fully set up by `TokioExecEnv`, but not yet spawned.
+struct PreparedCommand {
+    cmd: process::Command,
+    name: String,
+    #[cfg(all(unix, feature = "spawn-policy"))]
+    policy_failures: Option<PolicyFailures>,
+}
+
+impl TokioExecEnv {
+    fn prepare(&self, data: ExecutableData<'_>) -> Result<PreparedCommand, CommandError> {
         let name = data.name;
-        let mut cmd = Command::new(&name);
+
+        #[cfg(all(unix, feature = "spawn-policy"))]
+        let installer = match self.policy {
+            Some(ref policy) => Some(policy.prepare(&data).map_err(|err| {
+                CommandError::SpawnPolicy(err, name.to_string_lossy().into_owned())
+            })?),
+            None => None,
+        };
+
+        // NB: a location resolved outside of the new root directory is meaningless
+        let resolved = self.isolation.chroot.is_none();
+        let mut cmd = new_command(&data, resolved);
         cmd.args(data.args)
-            .kill_on_drop(true) // Ensure we clean up any dropped handles
             .env_clear() // Ensure we don't inherit from the process
             .current_dir(&data.current_dir)
-            .stdin(stdio(data.stdin))
-            .stdout(stdio(data.stdout))
-            .stderr(stdio(data.stderr));
+            .stdin(Stdio::from(data.stdin))
+            .stdout(Stdio::from(data.stdout))
+            .stderr(Stdio::from(data.stderr));
 
         // Ensure a PATH env var is defined, otherwise it appears that
         // things default to the PATH env var defined for the process
@@ -95,14 +573,213 @@ impl ExecutableEnvironment for TokioExecEnv {
             cmd.env(k, v);
         }
 
-        let child = cmd
-            .spawn()
-            .map_err(|err| map_io_err(err, name.to_string_lossy().into_owned()))?;
+        #[cfg(all(target_os = "linux", feature = "cgroups"))]
+        {
+            if let Some(ref cgroup) = self.cgroup {
+                cgroup.attach(&mut cmd).map_err(|err| {
+                    CommandError::Io(err, Some(cgroup.path().display().to_string()))
+                })?;
+            }
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+
+            if !self.resource_limits.is_empty() {
+                let limits = self.resource_limits.clone();
+                let pre_exec = move || {
+                    for &(resource, limit) in &limits {
+                        crate::sys::rlimit::set_rlimit(resource, limit)?;
+                    }
 
+                    Ok(())
+                };
+
+                // Safety: the hook only issues `setrlimit` calls (without allocating),
+                // which is safe to do between forking and exec-ing the child.
+                unsafe {
+                    cmd.pre_exec(pre_exec);
+                }
+            }
+        }
+
+        if self.isolation.is_enabled() {
+            isolate(&mut cmd, &self.isolation, data.current_dir)
+                .map_err(|err| CommandError::Io(err, Some(name.to_string_lossy().into_owned())))?;
+        }
+
+        #[cfg(all(unix, feature = "spawn-policy"))]
+        let policy_failures = match installer {
+            Some(installer) => {
+                Some(PolicyFailures::install(&mut cmd, installer).map_err(|err| {
+                    CommandError::SpawnPolicy(err, name.to_string_lossy().into_owned())
+                })?)
+            }
+            None => None,
+        };
+
+        Ok(PreparedCommand {
+            cmd,
+            name: name.to_string_lossy().into_owned(),
+            #[cfg(all(unix, feature = "spawn-policy"))]
+            policy_failures,
+        })
+    }
+
+    fn spawn_prepared(
+        &self,
+        prepared: PreparedCommand,
+    ) -> Result<BoxFuture<'static, ExitStatus>, CommandError> {
+        let child = self.spawn_child(prepared)?;
         Ok(Box::pin(async move {
             child.await.map(ExitStatus::from).unwrap_or(EXIT_ERROR)
         }))
     }
+
+    fn spawn_child(&self, prepared: PreparedCommand) -> Result<Child, CommandError> {
+        let (child, _name) = prepared.spawn(|cmd| {
+            Command::from(cmd)
+                .kill_on_drop(true) // Ensure we clean up any dropped handles
+                .spawn()
+        })?;
+
+        #[cfg(windows)]
+        {
+            if let Some(ref job) = self.job {
+                // NB: the child is killed once dropped if it cannot be added
+                job.assign(child.id())
+                    .map_err(|err| CommandError::Io(err, Some(_name)))?;
+            }
+        }
+
+        Ok(child)
+    }
+}
+
+impl PreparedCommand {
+    /// Spawns the command via the provided function, and returns the child
+    /// along with the command's name (for reporting any further errors).
+    fn spawn<C, F>(self, spawn: F) -> Result<(C, String), CommandError>
+    where
+        F: FnOnce(process::Command) -> io::Result<C>,
+    {
+        let PreparedCommand {
+            cmd,
+            name,
+            #[cfg(all(unix, feature = "spawn-policy"))]
+            policy_failures,
+        } = self;
+
+        match spawn(cmd) {
+            Ok(child) => Ok((child, name)),
+
+            #[cfg(all(unix, feature = "spawn-policy"))]
+            Err(err) if policy_failures.is_some_and(PolicyFailures::has_failed) => {
+                Err(CommandError::SpawnPolicy(err, name))
+            }
+
+            Err(err) => Err(map_io_err(err, name)),
+        }
+    }
+}
+
+/// Replaces the current process on Unix systems, unless any isolation options,
+/// policies, cgroups, or resource limits need to be applied to the executable, in which case (and
+/// on all other platforms) the executable is spawned as a child process whose
+/// status is propagated once it exits.
+impl ExecReplaceEnvironment for TokioExecEnv {
+    fn exec_replace(
+        &self,
+        data: ExecutableData<'_>,
+    ) -> Result<BoxFuture<'static, Infallible>, CommandError> {
+        #[cfg(unix)]
+        {
+            if !self.has_spawn_hooks() {
+                return Err(exec_in_place(data));
+            }
+        }
+
+        let child = self.spawn_executable(data)?;
+        Ok(Box::pin(async move { exit_with_status(child.await) }))
+    }
+}
+
+/// Exits the current process, propagating the status of an executable which
+/// was spawned in place of replacing the process.
+pub(crate) fn exit_with_status(status: ExitStatus) -> ! {
+    let code = match status {
+        ExitStatus::Code(code) => code,
+        ExitStatus::Signal(signal) => EXIT_SIGNAL_OFFSET + signal,
+    };
+
+    process::exit(code)
+}
+
+impl TokioExecEnv {
+    /// Checks if anything needs to be applied to executables after they are
+    /// forked, which a process replacing itself is unable to do.
+    #[cfg(unix)]
+    fn has_spawn_hooks(&self) -> bool {
+        #[cfg(feature = "spawn-policy")]
+        {
+            if self.policy.is_some() {
+                return true;
+            }
+        }
+
+        #[cfg(all(target_os = "linux", feature = "cgroups"))]
+        {
+            if self.cgroup.is_some() {
+                return true;
+            }
+        }
+
+        !self.resource_limits.is_empty() || self.isolation.is_enabled()
+    }
+}
+
+/// Replaces the current process with the executable, returning only if it fails.
+#[cfg(unix)]
+fn exec_in_place(data: ExecutableData<'_>) -> CommandError {
+    use std::os::unix::process::CommandExt;
+
+    let mut cmd = new_command(&data, true);
+    cmd.args(data.args)
+        .env_clear()
+        .current_dir(data.current_dir)
+        .stdin(Stdio::from(data.stdin))
+        .stdout(Stdio::from(data.stdout))
+        .stderr(Stdio::from(data.stderr));
+
+    // NB: see the comment in `spawn_executable`
+    cmd.env("PATH", "");
+
+    for (k, v) in data.env_vars {
+        cmd.env(k, v);
+    }
+
+    map_io_err(cmd.exec(), data.name.to_string_lossy().into_owned())
+}
+
+/// Creates a command which runs the already resolved location of the executable
+/// (if any, and if `resolved` is set) instead of looking up its name, though the
+/// name is still passed as the first argument on Unix systems.
+fn new_command(data: &ExecutableData<'_>, resolved: bool) -> process::Command {
+    match data.path.filter(|_| resolved) {
+        Some(path) => {
+            let mut cmd = process::Command::new(path);
+
+            #[cfg(unix)]
+            {
+                use std::os::unix::process::CommandExt;
+                cmd.arg0(data.name);
+            }
+
+            cmd
+        }
+        None => process::Command::new(data.name),
+    }
 }
 
 fn map_io_err(err: IoError, name: String) -> CommandError {
@@ -124,3 +801,189 @@ fn map_io_err(err: IoError, name: String) -> CommandError {
         CommandError::Io(err, Some(name))
     }
 }
+
+/// Waits for a child to exit, and reaps it via `wait4` to collect its resource usage.
+///
+/// The child is spawned without tokio's knowledge (which would otherwise reap it and
+/// discard its resource usage), so it is polled for here whenever a `SIGCHLD` is
+/// delivered. If the future is dropped before then, the child is killed and reaped.
+#[cfg(unix)]
+async fn wait_with_usage(
+    child: process::Child,
+    mut sigchld: Signal,
+) -> (ExitStatus, Option<ResourceUsage>) {
+    use crate::sys::cvt_r;
+    use crate::sys::rusage::timeval_to_duration;
+    use std::mem::MaybeUninit;
+    use std::os::unix::process::ExitStatusExt;
+
+    /// Kills and reaps the child when dropped, unless it has already been reaped.
+    struct UnreapedChild(Option<process::Child>);
+
+    impl Drop for UnreapedChild {
+        fn drop(&mut self) {
+            if let Some(mut child) = self.0.take() {
+                let _ = child.kill();
+
+                // NB: a killed child may not exit promptly (e.g. if it is stuck in an
+                // uninterruptible sleep), so avoid blocking the current thread on it
+                let reap = move || {
+                    let _ = child.wait();
+                };
+
+                match tokio::runtime::Handle::try_current() {
+                    Ok(handle) => drop(handle.spawn_blocking(reap)),
+                    Err(_) => drop(std::thread::spawn(reap)),
+                }
+            }
+        }
+    }
+
+    let pid = child.id() as libc::pid_t;
+    let mut child = UnreapedChild(Some(child));
+
+    loop {
+        let mut status = 0;
+        let mut usage = MaybeUninit::<libc::rusage>::zeroed();
+        let reaped =
+            cvt_r(|| unsafe { libc::wait4(pid, &mut status, libc::WNOHANG, usage.as_mut_ptr()) });
+
+        match reaped {
+            // The child is still running
+            Ok(0) => {}
+            Ok(_) => {
+                // NB: dropping the handle of a reaped child does not touch its pid again
+                child.0 = None;
+                let usage = unsafe { usage.assume_init() };
+
+                // NB: the maximum resident set size is reported in bytes on macOS,
+                // but in kilobytes elsewhere
+                let rss_unit = if cfg!(target_os = "macos") { 1 } else { 1024 };
+                let usage = ResourceUsage {
+                    max_rss: (usage.ru_maxrss as u64).saturating_mul(rss_unit),
+                    user_time: timeval_to_duration(usage.ru_utime),
+                    system_time: timeval_to_duration(usage.ru_stime),
+                };
+
+                let status = process::ExitStatus::from_raw(status);
+                return (ExitStatus::from(status), Some(usage));
+            }
+            Err(_) => {
+                // NB: the child can no longer be waited for (e.g. it was reaped elsewhere),
+                // so it must not be signalled since its pid may have been reused
+                child.0 = None;
+                return (EXIT_ERROR, None);
+            }
+        }
+
+        if sigchld.recv().await.is_none() {
+            return (EXIT_ERROR, None);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn isolate(
+    cmd: &mut process::Command,
+    isolation: &SpawnIsolation,
+    current_dir: &Path,
+) -> io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    fn cstr(path: &Path) -> io::Result<CString> {
+        CString::new(path.as_os_str().as_bytes())
+            .map_err(|err| IoError::new(IoErrorKind::InvalidInput, err))
+    }
+
+    let unshare_flags = unshare_flags(isolation)?;
+    let chroot = match isolation.chroot {
+        Some(ref root) => Some((cstr(root)?, cstr(current_dir)?)),
+        None => None,
+    };
+
+    let pre_exec = move || {
+        unshare(unshare_flags)?;
+
+        // NB: the working directory is changed before this hook runs,
+        // so it must be changed again to be relative to the new root.
+        if let Some((ref root, ref dir)) = chroot {
+            cvt(unsafe { libc::chroot(root.as_ptr()) })?;
+            cvt(unsafe { libc::chdir(dir.as_ptr()) })?;
+        }
+
+        Ok(())
+    };
+
+    // Safety: the hook only issues system calls which are safe
+    // to use between forking and exec-ing the child process.
+    unsafe {
+        use std::os::unix::process::CommandExt;
+        cmd.pre_exec(pre_exec);
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn cvt(ret: libc::c_int) -> io::Result<()> {
+    if ret == -1 {
+        Err(IoError::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn unshare_flags(isolation: &SpawnIsolation) -> io::Result<libc::c_int> {
+    let mut flags = 0;
+    if isolation.unshare_mount {
+        flags |= libc::CLONE_NEWNS;
+    }
+    if isolation.unshare_net {
+        flags |= libc::CLONE_NEWNET;
+    }
+    if isolation.unshare_pid {
+        flags |= libc::CLONE_NEWPID;
+    }
+
+    Ok(flags)
+}
+
+#[cfg(target_os = "linux")]
+fn unshare(flags: libc::c_int) -> io::Result<()> {
+    if flags == 0 {
+        Ok(())
+    } else {
+        cvt(unsafe { libc::unshare(flags) })
+    }
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn unshare_flags(isolation: &SpawnIsolation) -> io::Result<libc::c_int> {
+    if isolation.unshare_mount || isolation.unshare_net || isolation.unshare_pid {
+        Err(IoError::new(
+            IoErrorKind::Other,
+            "namespaces are not supported on this platform",
+        ))
+    } else {
+        Ok(0)
+    }
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn unshare(_flags: libc::c_int) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(windows)]
+fn isolate(
+    _cmd: &mut process::Command,
+    _isolation: &SpawnIsolation,
+    _current_dir: &Path,
+) -> io::Result<()> {
+    Err(IoError::new(
+        IoErrorKind::Other,
+        "process isolation is not supported on this platform",
+    ))
+}
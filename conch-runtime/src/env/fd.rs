@@ -1,6 +1,7 @@
-use crate::env::SubEnvironment;
-use crate::io::{dup_stdio, FileDesc, Permissions};
+use crate::env::{FdInfo, IoBackend, RuntimeInfo, RuntimeInfoEnvironment, SubEnvironment};
+use crate::io::{dup_stdio, supports_evented_io, FileDesc, Permissions};
 use crate::{Fd, RefCounted, STDERR_FILENO, STDIN_FILENO, STDOUT_FILENO};
+use std::borrow::Borrow;
 use std::collections::HashMap;
 use std::fmt;
 use std::io::Result;
@@ -34,6 +35,21 @@ impl<'a, T: ?Sized + FileDescEnvironment> FileDescEnvironment for &'a mut T {
     }
 }
 
+/// An interface for auditing the shell file descriptors of an environment
+/// for any which would be leaked into spawned children.
+pub trait FileDescAuditEnvironment {
+    /// Returns (in ascending order) the shell file descriptors whose underlying OS
+    /// descriptors do not have their close-on-exec flag set, and would thus be
+    /// inherited by *any* spawned child, regardless of its redirects.
+    fn inheritable_fds(&self) -> Result<Vec<Fd>>;
+}
+
+impl<'a, T: ?Sized + FileDescAuditEnvironment> FileDescAuditEnvironment for &'a mut T {
+    fn inheritable_fds(&self) -> Result<Vec<Fd>> {
+        (**self).inheritable_fds()
+    }
+}
+
 /// An environment module for setting and getting shell file descriptors.
 #[derive(PartialEq, Eq)]
 pub struct FileDescEnv<T> {
@@ -162,6 +178,47 @@ impl<T: Clone + Eq> FileDescEnvironment for FileDescEnv<T> {
     }
 }
 
+impl<T: Borrow<FileDesc>> FileDescAuditEnvironment for FileDescEnv<T> {
+    fn inheritable_fds(&self) -> Result<Vec<Fd>> {
+        let mut fds = Vec::new();
+        for (&fd, (handle, _)) in &*self.fds {
+            if !handle.borrow().is_cloexec()? {
+                fds.push(fd);
+            }
+        }
+
+        fds.sort_unstable();
+        Ok(fds)
+    }
+}
+
+impl<T: Borrow<FileDesc>> RuntimeInfoEnvironment for FileDescEnv<T> {
+    fn runtime_info(&self) -> Result<RuntimeInfo> {
+        let mut fds = Vec::with_capacity(self.fds.len());
+        for (&fd, &(ref handle, permissions)) in &*self.fds {
+            let handle = handle.borrow();
+            let io_backend = if supports_evented_io(handle) {
+                IoBackend::Evented
+            } else {
+                IoBackend::ThreadPool
+            };
+
+            fds.push(FdInfo {
+                fd,
+                permissions,
+                cloexec: handle.is_cloexec()?,
+                io_backend,
+            });
+        }
+
+        fds.sort_unstable_by_key(|info| info.fd);
+        Ok(RuntimeInfo {
+            fds,
+            ..RuntimeInfo::new()
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
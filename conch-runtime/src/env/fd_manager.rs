@@ -1,4 +1,7 @@
-use crate::env::{AsyncIoEnvironment, FileDescEnvironment, FileDescOpener, Pipe, SubEnvironment};
+use crate::env::{
+    AsyncIoEnvironment, BoxAsyncRead, FileDescAuditEnvironment, FileDescEnvironment,
+    FileDescOpener, OpenFlags, Pipe, RuntimeInfo, RuntimeInfoEnvironment, SubEnvironment,
+};
 use crate::io::Permissions;
 use crate::Fd;
 use futures_core::future::BoxFuture;
@@ -47,6 +50,13 @@ impl<O, S, A> FileDescManagerEnv<O, S, A> {
     }
 }
 
+impl<O, S, A> FileDescManagerEnv<O, S, A> {
+    /// Returns a reference to the environment used for opening file descriptors.
+    pub fn opener(&self) -> &O {
+        &self.opener
+    }
+}
+
 impl<O, S, A> SubEnvironment for FileDescManagerEnv<O, S, A>
 where
     O: SubEnvironment,
@@ -70,17 +80,56 @@ where
 {
     type OpenedFileHandle = A::IoHandle;
 
-    fn open_path(&mut self, path: &Path, opts: &OpenOptions) -> io::Result<Self::OpenedFileHandle> {
+    fn open_path_with_cloexec(
+        &mut self,
+        path: &Path,
+        opts: &OpenOptions,
+        cloexec: bool,
+    ) -> io::Result<Self::OpenedFileHandle> {
+        self.opener
+            .open_path_with_cloexec(path, opts, cloexec)
+            .map(Self::OpenedFileHandle::from)
+    }
+
+    fn open_path_with_flags(
+        &mut self,
+        path: &Path,
+        flags: OpenFlags,
+        cloexec: bool,
+    ) -> io::Result<Self::OpenedFileHandle> {
         self.opener
-            .open_path(path, opts)
+            .open_path_with_flags(path, flags, cloexec)
             .map(Self::OpenedFileHandle::from)
     }
 
-    fn open_pipe(&mut self) -> io::Result<Pipe<Self::OpenedFileHandle>> {
-        self.opener.open_pipe().map(|pipe| Pipe {
-            reader: pipe.reader.into(),
-            writer: pipe.writer.into(),
-        })
+    fn open_pipe_with_cloexec(
+        &mut self,
+        cloexec: bool,
+    ) -> io::Result<Pipe<Self::OpenedFileHandle>> {
+        self.opener
+            .open_pipe_with_cloexec(cloexec)
+            .map(|pipe| Pipe {
+                reader: pipe.reader.into(),
+                writer: pipe.writer.into(),
+            })
+    }
+}
+
+impl<O, S, A> FileDescAuditEnvironment for FileDescManagerEnv<O, S, A>
+where
+    S: FileDescAuditEnvironment,
+{
+    fn inheritable_fds(&self) -> io::Result<Vec<Fd>> {
+        self.storer.inheritable_fds()
+    }
+}
+
+impl<O, S, A> RuntimeInfoEnvironment for FileDescManagerEnv<O, S, A>
+where
+    S: RuntimeInfoEnvironment,
+{
+    fn runtime_info(&self) -> io::Result<RuntimeInfo> {
+        self.storer.runtime_info()
     }
 }
 
@@ -113,6 +162,10 @@ where
         self.async_env.read_all(fd)
     }
 
+    fn read_async(&mut self, fd: Self::IoHandle) -> io::Result<BoxAsyncRead> {
+        self.async_env.read_async(fd)
+    }
+
     fn write_all<'a>(
         &mut self,
         fd: Self::IoHandle,
@@ -1,6 +1,7 @@
 use crate::env::{
-    ArcFileDescOpenerEnv, ArcUnwrappingAsyncIoEnv, AsyncIoEnvironment, FileDescEnv,
-    FileDescEnvironment, FileDescManagerEnv, FileDescOpener, FileDescOpenerEnv, Pipe,
+    ArcFileDescOpenerEnv, ArcUnwrappingAsyncIoEnv, AsyncIoEnvironment, BoxAsyncRead,
+    FdUsageTracker, FileDescAuditEnvironment, FileDescEnv, FileDescEnvironment, FileDescManagerEnv,
+    FileDescOpener, FileDescOpenerEnv, OpenFlags, Pipe, RuntimeInfo, RuntimeInfoEnvironment,
     SubEnvironment, TokioAsyncIoEnv,
 };
 use crate::io::{FileDesc, Permissions};
@@ -50,6 +51,15 @@ impl TokioFileDescManagerEnv {
     pub fn with_process_stdio() -> io::Result<Self> {
         Ok(Self::with_fd_env(FileDescEnv::with_process_stdio()?))
     }
+
+    /// Returns a handle for monitoring (and limiting) the file descriptors
+    /// opened through this environment, e.g. by redirects or substitutions.
+    ///
+    /// The handle is shared with all sub-environments, and can be retained
+    /// even after this environment has been moved into an `Env`.
+    pub fn fd_usage(&self) -> &FdUsageTracker {
+        self.inner.opener().fd_usage()
+    }
 }
 
 impl SubEnvironment for TokioFileDescManagerEnv {
@@ -63,12 +73,46 @@ impl SubEnvironment for TokioFileDescManagerEnv {
 impl FileDescOpener for TokioFileDescManagerEnv {
     type OpenedFileHandle = Arc<FileDesc>;
 
-    fn open_path(&mut self, path: &Path, opts: &OpenOptions) -> io::Result<Self::OpenedFileHandle> {
-        self.inner.open_path(path, opts)
+    fn open_path_with_cloexec(
+        &mut self,
+        path: &Path,
+        opts: &OpenOptions,
+        cloexec: bool,
+    ) -> io::Result<Self::OpenedFileHandle> {
+        self.inner.open_path_with_cloexec(path, opts, cloexec)
+    }
+
+    fn open_path_with_flags(
+        &mut self,
+        path: &Path,
+        flags: OpenFlags,
+        cloexec: bool,
+    ) -> io::Result<Self::OpenedFileHandle> {
+        self.inner.open_path_with_flags(path, flags, cloexec)
     }
 
-    fn open_pipe(&mut self) -> io::Result<Pipe<Self::OpenedFileHandle>> {
-        self.inner.open_pipe()
+    fn open_pipe_with_cloexec(
+        &mut self,
+        cloexec: bool,
+    ) -> io::Result<Pipe<Self::OpenedFileHandle>> {
+        self.inner.open_pipe_with_cloexec(cloexec)
+    }
+}
+
+impl FileDescAuditEnvironment for TokioFileDescManagerEnv {
+    fn inheritable_fds(&self) -> io::Result<Vec<Fd>> {
+        self.inner.inheritable_fds()
+    }
+}
+
+impl RuntimeInfoEnvironment for TokioFileDescManagerEnv {
+    fn runtime_info(&self) -> io::Result<RuntimeInfo> {
+        let usage = self.fd_usage();
+        Ok(RuntimeInfo {
+            open_fds: Some(usage.open_fds()),
+            fd_limit: usage.limit(),
+            ..self.inner.runtime_info()?
+        })
     }
 }
 
@@ -95,6 +139,10 @@ impl AsyncIoEnvironment for TokioFileDescManagerEnv {
         self.inner.read_all(fd)
     }
 
+    fn read_async(&mut self, fd: Self::IoHandle) -> io::Result<BoxAsyncRead> {
+        self.inner.read_async(fd)
+    }
+
     fn write_all<'a>(
         &mut self,
         fd: Self::IoHandle,
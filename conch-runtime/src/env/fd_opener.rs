@@ -1,9 +1,13 @@
 use crate::env::SubEnvironment;
-use crate::io::{FileDesc, Pipe as OsPipe};
+use crate::error::FdLimitExceededError;
+use crate::io::{FileDesc, Permissions, Pipe as OsPipe};
+use std::any::Any;
+use std::borrow::Borrow;
+use std::fmt;
 use std::fs::OpenOptions;
 use std::io;
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, Weak};
 
 /// A pipe reader/writer pair created by a `FileDescOpener`.
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -14,26 +18,137 @@ pub struct Pipe<T> {
     pub writer: T,
 }
 
+/// An inspectable description of how a path should be opened,
+/// mirroring the options of `OpenOptions`.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpenFlags {
+    /// Open the file for reading.
+    pub read: bool,
+    /// Open the file for writing.
+    pub write: bool,
+    /// Open the file in append mode (which implies writing).
+    pub append: bool,
+    /// Truncate the file to zero length if it already exists.
+    pub truncate: bool,
+    /// Create the file if it does not already exist.
+    pub create: bool,
+    /// Create the file, failing if it already exists.
+    pub create_new: bool,
+}
+
+impl From<OpenFlags> for OpenOptions {
+    fn from(flags: OpenFlags) -> Self {
+        let mut options = OpenOptions::new();
+        options
+            .read(flags.read)
+            .write(flags.write)
+            .append(flags.append)
+            .truncate(flags.truncate)
+            .create(flags.create)
+            .create_new(flags.create_new);
+        options
+    }
+}
+
+impl From<Permissions> for OpenFlags {
+    fn from(perms: Permissions) -> Self {
+        match perms {
+            Permissions::Read => OpenFlags {
+                read: true,
+                ..OpenFlags::default()
+            },
+            Permissions::Write => OpenFlags {
+                write: true,
+                create: true,
+                truncate: true,
+                ..OpenFlags::default()
+            },
+            Permissions::ReadWrite => OpenFlags {
+                read: true,
+                write: true,
+                create: true,
+                ..OpenFlags::default()
+            },
+        }
+    }
+}
+
 /// An interface for opening file descriptors as some handle representation.
+///
+/// Unless requested otherwise, any opened file descriptors have their close-on-exec
+/// flag set, so that they are not accidentally leaked into spawned children
+/// (redirected descriptors are explicitly handed to children regardless).
 pub trait FileDescOpener {
     /// A type which represents an opened file descriptor.
     type OpenedFileHandle;
 
-    /// Open a provided `path` with the specified `OpenOptions`.
-    fn open_path(&mut self, path: &Path, opts: &OpenOptions) -> io::Result<Self::OpenedFileHandle>;
-    /// Create a new `Pipe` pair.
-    fn open_pipe(&mut self) -> io::Result<Pipe<Self::OpenedFileHandle>>;
+    /// Open a provided `path` with the specified `OpenOptions`, with the
+    /// close-on-exec flag set.
+    fn open_path(&mut self, path: &Path, opts: &OpenOptions) -> io::Result<Self::OpenedFileHandle> {
+        self.open_path_with_cloexec(path, opts, true)
+    }
+
+    /// Open a provided `path` with the specified `OpenOptions`, and set its
+    /// close-on-exec flag to the desired state.
+    fn open_path_with_cloexec(
+        &mut self,
+        path: &Path,
+        opts: &OpenOptions,
+        cloexec: bool,
+    ) -> io::Result<Self::OpenedFileHandle>;
+
+    /// Open a provided `path` as described by `flags`, and set its close-on-exec
+    /// flag to the desired state.
+    ///
+    /// Unlike `OpenOptions`, the flags can be inspected by implementations (e.g.
+    /// for auditing purposes), though by default they are simply converted to
+    /// `OpenOptions`.
+    fn open_path_with_flags(
+        &mut self,
+        path: &Path,
+        flags: OpenFlags,
+        cloexec: bool,
+    ) -> io::Result<Self::OpenedFileHandle> {
+        self.open_path_with_cloexec(path, &flags.into(), cloexec)
+    }
+
+    /// Create a new `Pipe` pair, with the close-on-exec flag set on both ends.
+    fn open_pipe(&mut self) -> io::Result<Pipe<Self::OpenedFileHandle>> {
+        self.open_pipe_with_cloexec(true)
+    }
+
+    /// Create a new `Pipe` pair, and set the close-on-exec flag
+    /// of both ends to the desired state.
+    fn open_pipe_with_cloexec(&mut self, cloexec: bool)
+        -> io::Result<Pipe<Self::OpenedFileHandle>>;
 }
 
 impl<'a, T: ?Sized + FileDescOpener> FileDescOpener for &'a mut T {
     type OpenedFileHandle = T::OpenedFileHandle;
 
-    fn open_path(&mut self, path: &Path, opts: &OpenOptions) -> io::Result<Self::OpenedFileHandle> {
-        (**self).open_path(path, opts)
+    fn open_path_with_cloexec(
+        &mut self,
+        path: &Path,
+        opts: &OpenOptions,
+        cloexec: bool,
+    ) -> io::Result<Self::OpenedFileHandle> {
+        (**self).open_path_with_cloexec(path, opts, cloexec)
     }
 
-    fn open_pipe(&mut self) -> io::Result<Pipe<Self::OpenedFileHandle>> {
-        (**self).open_pipe()
+    fn open_path_with_flags(
+        &mut self,
+        path: &Path,
+        flags: OpenFlags,
+        cloexec: bool,
+    ) -> io::Result<Self::OpenedFileHandle> {
+        (**self).open_path_with_flags(path, flags, cloexec)
+    }
+
+    fn open_pipe_with_cloexec(
+        &mut self,
+        cloexec: bool,
+    ) -> io::Result<Pipe<Self::OpenedFileHandle>> {
+        (**self).open_pipe_with_cloexec(cloexec)
     }
 }
 
@@ -57,51 +172,366 @@ impl SubEnvironment for FileDescOpenerEnv {
 impl FileDescOpener for FileDescOpenerEnv {
     type OpenedFileHandle = FileDesc;
 
-    fn open_path(&mut self, path: &Path, opts: &OpenOptions) -> io::Result<Self::OpenedFileHandle> {
-        opts.open(path).map(FileDesc::from)
+    fn open_path_with_cloexec(
+        &mut self,
+        path: &Path,
+        opts: &OpenOptions,
+        cloexec: bool,
+    ) -> io::Result<Self::OpenedFileHandle> {
+        let fd = opts.open(path).map(FileDesc::from)?;
+        if !cloexec {
+            fd.set_cloexec(false)?;
+        }
+        Ok(fd)
     }
 
-    fn open_pipe(&mut self) -> io::Result<Pipe<Self::OpenedFileHandle>> {
-        OsPipe::new().map(|pipe| Pipe {
+    fn open_pipe_with_cloexec(
+        &mut self,
+        cloexec: bool,
+    ) -> io::Result<Pipe<Self::OpenedFileHandle>> {
+        let pipe = OsPipe::new()?;
+        if !cloexec {
+            pipe.reader.set_cloexec(false)?;
+            pipe.writer.set_cloexec(false)?;
+        }
+
+        Ok(Pipe {
             reader: pipe.reader,
             writer: pipe.writer,
         })
     }
 }
 
+/// A shared handle for monitoring (and optionally limiting) the number of file
+/// descriptors which were opened through an environment and are still open.
+///
+/// This includes any files opened by redirects, as well as any pipes or
+/// temporary files created for here-documents or command substitutions, which
+/// can be useful for detecting leaks in long-running hosts. Any clones of this
+/// handle (and sub-environments of the environment it belongs to) share the
+/// same counters.
+#[derive(Default, Debug, Clone)]
+pub struct FdUsageTracker {
+    inner: Arc<Mutex<FdUsage>>,
+}
+
+#[derive(Default, Debug)]
+struct FdUsage {
+    handles: Vec<Weak<dyn Any + Send + Sync>>,
+    limit: Option<usize>,
+}
+
+impl FdUsage {
+    fn open_fds(&mut self) -> usize {
+        self.handles.retain(|handle| handle.strong_count() > 0);
+        self.handles.len()
+    }
+
+    fn reserve(&mut self, additional: usize) -> io::Result<()> {
+        let open_fds = self.open_fds();
+
+        match self.limit {
+            Some(limit) if open_fds + additional > limit => Err(io::Error::new(
+                io::ErrorKind::Other,
+                FdLimitExceededError { limit },
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn track<T: Any + Send + Sync>(&mut self, handle: T) -> Arc<T> {
+        let handle = Arc::new(handle);
+        let weak: Weak<T> = Arc::downgrade(&handle);
+        self.handles.push(weak);
+        handle
+    }
+}
+
+impl FdUsageTracker {
+    /// Creates a new tracker with no open file descriptors and no limit.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, FdUsage> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns the number of tracked file descriptors which are still open.
+    pub fn open_fds(&self) -> usize {
+        self.lock().open_fds()
+    }
+
+    /// Returns the maximum number of file descriptors which may be open at once, if any.
+    pub fn limit(&self) -> Option<usize> {
+        self.lock().limit
+    }
+
+    /// Sets (or clears) the maximum number of file descriptors which may be open at once.
+    ///
+    /// Attempting to open any file descriptors past this limit will result in an
+    /// `io::Error` wrapping a `FdLimitExceededError`. Lowering the limit below
+    /// the number of currently open file descriptors does not close any of them.
+    pub fn set_limit(&self, limit: Option<usize>) {
+        self.lock().limit = limit;
+    }
+}
+
 /// A `FileDescOpener` implementation which delegates to another implementation,
 /// but wraps any returned handles with in an `Arc`.
-#[derive(Default, Debug, Clone, PartialEq, Eq)]
+///
+/// The number of opened handles which are still alive is tracked via an
+/// `FdUsageTracker`, which is shared with all sub-environments.
+#[derive(Default, Debug, Clone)]
 pub struct ArcFileDescOpenerEnv<O> {
     opener: O,
+    usage: FdUsageTracker,
 }
 
 impl<O> ArcFileDescOpenerEnv<O> {
     /// Create a new wrapper instance around some other `FileDescOpener` implementation.
     pub fn new(opener: O) -> Self {
-        Self { opener }
+        Self {
+            opener,
+            usage: FdUsageTracker::new(),
+        }
+    }
+
+    /// Returns a handle for monitoring the file descriptors opened by this environment.
+    pub fn fd_usage(&self) -> &FdUsageTracker {
+        &self.usage
     }
 }
 
+impl<O: PartialEq> PartialEq for ArcFileDescOpenerEnv<O> {
+    fn eq(&self, other: &Self) -> bool {
+        self.opener == other.opener && Arc::ptr_eq(&self.usage.inner, &other.usage.inner)
+    }
+}
+
+impl<O: Eq> Eq for ArcFileDescOpenerEnv<O> {}
+
 impl<O: SubEnvironment> SubEnvironment for ArcFileDescOpenerEnv<O> {
     fn sub_env(&self) -> Self {
         Self {
             opener: self.opener.sub_env(),
+            usage: self.usage.clone(),
         }
     }
 }
 
-impl<O: FileDescOpener> FileDescOpener for ArcFileDescOpenerEnv<O> {
+impl<O> FileDescOpener for ArcFileDescOpenerEnv<O>
+where
+    O: FileDescOpener,
+    O::OpenedFileHandle: Send + Sync + 'static,
+{
     type OpenedFileHandle = Arc<O::OpenedFileHandle>;
 
-    fn open_path(&mut self, path: &Path, opts: &OpenOptions) -> io::Result<Self::OpenedFileHandle> {
-        self.opener.open_path(path, opts).map(Arc::new)
+    fn open_path_with_cloexec(
+        &mut self,
+        path: &Path,
+        opts: &OpenOptions,
+        cloexec: bool,
+    ) -> io::Result<Self::OpenedFileHandle> {
+        let mut usage = self.usage.lock();
+        usage.reserve(1)?;
+
+        self.opener
+            .open_path_with_cloexec(path, opts, cloexec)
+            .map(|handle| usage.track(handle))
     }
 
-    fn open_pipe(&mut self) -> io::Result<Pipe<Self::OpenedFileHandle>> {
-        self.opener.open_pipe().map(|pipe| Pipe {
-            reader: Arc::new(pipe.reader),
-            writer: Arc::new(pipe.writer),
+    fn open_path_with_flags(
+        &mut self,
+        path: &Path,
+        flags: OpenFlags,
+        cloexec: bool,
+    ) -> io::Result<Self::OpenedFileHandle> {
+        let mut usage = self.usage.lock();
+        usage.reserve(1)?;
+
+        self.opener
+            .open_path_with_flags(path, flags, cloexec)
+            .map(|handle| usage.track(handle))
+    }
+
+    fn open_pipe_with_cloexec(
+        &mut self,
+        cloexec: bool,
+    ) -> io::Result<Pipe<Self::OpenedFileHandle>> {
+        let mut usage = self.usage.lock();
+        usage.reserve(2)?;
+
+        self.opener
+            .open_pipe_with_cloexec(cloexec)
+            .map(|pipe| Pipe {
+                reader: usage.track(pipe.reader),
+                writer: usage.track(pipe.writer),
+            })
+    }
+}
+
+/// Describes an attempt to open a path through an `AuditFileDescOpenerEnv`.
+#[derive(Debug)]
+pub struct FileOpenEvent<'a> {
+    /// The path which was opened.
+    pub path: &'a Path,
+    /// The flags the path was opened with, if known (i.e. `None` if the path
+    /// was opened via an opaque `OpenOptions` instance).
+    pub flags: Option<OpenFlags>,
+    /// Whether the close-on-exec flag was requested on the descriptor.
+    pub cloexec: bool,
+    /// The resulting descriptor, or the reason the path could not be opened.
+    pub result: Result<&'a FileDesc, &'a io::Error>,
+}
+
+/// An interface for auditing (and optionally vetoing) any paths opened
+/// through an `AuditFileDescOpenerEnv`, e.g. by redirects.
+pub trait FileOpenAuditor {
+    /// Invoked before a path is opened, where returning an error prevents it
+    /// from being opened (e.g. to implement `noclobber`-like policies).
+    ///
+    /// By default all paths are permitted.
+    fn check_open(&self, path: &Path, flags: Option<OpenFlags>) -> io::Result<()> {
+        let _ = (path, flags);
+        Ok(())
+    }
+
+    /// Invoked after every attempt to open a path, including any
+    /// which were denied by `check_open`.
+    fn record_open(&self, event: &FileOpenEvent<'_>);
+}
+
+impl<'a, T: ?Sized + FileOpenAuditor> FileOpenAuditor for &'a T {
+    fn check_open(&self, path: &Path, flags: Option<OpenFlags>) -> io::Result<()> {
+        (**self).check_open(path, flags)
+    }
+
+    fn record_open(&self, event: &FileOpenEvent<'_>) {
+        (**self).record_open(event)
+    }
+}
+
+impl<T: ?Sized + FileOpenAuditor> FileOpenAuditor for Arc<T> {
+    fn check_open(&self, path: &Path, flags: Option<OpenFlags>) -> io::Result<()> {
+        (**self).check_open(path, flags)
+    }
+
+    fn record_open(&self, event: &FileOpenEvent<'_>) {
+        (**self).record_open(event)
+    }
+}
+
+/// A `FileDescOpener` implementation which delegates to another implementation,
+/// but reports every path it opens to a `FileOpenAuditor`.
+///
+/// The auditor is shared with all sub-environments.
+#[derive(Clone)]
+pub struct AuditFileDescOpenerEnv<O> {
+    opener: O,
+    auditor: Arc<dyn FileOpenAuditor + Send + Sync>,
+}
+
+impl<O> AuditFileDescOpenerEnv<O> {
+    /// Create a new wrapper instance around some other `FileDescOpener`
+    /// implementation, which reports to the provided auditor.
+    pub fn new<A>(opener: O, auditor: A) -> Self
+    where
+        A: 'static + FileOpenAuditor + Send + Sync,
+    {
+        Self {
+            opener,
+            auditor: Arc::new(auditor),
+        }
+    }
+
+    /// Returns a reference to the wrapped `FileDescOpener` implementation.
+    pub fn opener(&self) -> &O {
+        &self.opener
+    }
+
+    fn audit<H: Borrow<FileDesc>>(
+        &mut self,
+        path: &Path,
+        flags: Option<OpenFlags>,
+        cloexec: bool,
+        open: impl FnOnce(&mut O) -> io::Result<H>,
+    ) -> io::Result<H> {
+        let result = self
+            .auditor
+            .check_open(path, flags)
+            .and_then(|()| open(&mut self.opener));
+
+        self.auditor.record_open(&FileOpenEvent {
+            path,
+            flags,
+            cloexec,
+            result: result.as_ref().map(Borrow::borrow),
+        });
+
+        result
+    }
+}
+
+impl<O: fmt::Debug> fmt::Debug for AuditFileDescOpenerEnv<O> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct(stringify!(AuditFileDescOpenerEnv))
+            .field("opener", &self.opener)
+            .finish()
+    }
+}
+
+impl<O: PartialEq> PartialEq for AuditFileDescOpenerEnv<O> {
+    fn eq(&self, other: &Self) -> bool {
+        self.opener == other.opener
+            && Arc::as_ptr(&self.auditor) as *const u8 == Arc::as_ptr(&other.auditor) as *const u8
+    }
+}
+
+impl<O: Eq> Eq for AuditFileDescOpenerEnv<O> {}
+
+impl<O: SubEnvironment> SubEnvironment for AuditFileDescOpenerEnv<O> {
+    fn sub_env(&self) -> Self {
+        Self {
+            opener: self.opener.sub_env(),
+            auditor: self.auditor.clone(),
+        }
+    }
+}
+
+impl<O> FileDescOpener for AuditFileDescOpenerEnv<O>
+where
+    O: FileDescOpener,
+    O::OpenedFileHandle: Borrow<FileDesc>,
+{
+    type OpenedFileHandle = O::OpenedFileHandle;
+
+    fn open_path_with_cloexec(
+        &mut self,
+        path: &Path,
+        opts: &OpenOptions,
+        cloexec: bool,
+    ) -> io::Result<Self::OpenedFileHandle> {
+        self.audit(path, None, cloexec, |opener| {
+            opener.open_path_with_cloexec(path, opts, cloexec)
         })
     }
+
+    fn open_path_with_flags(
+        &mut self,
+        path: &Path,
+        flags: OpenFlags,
+        cloexec: bool,
+    ) -> io::Result<Self::OpenedFileHandle> {
+        self.audit(path, Some(flags), cloexec, |opener| {
+            opener.open_path_with_flags(path, flags, cloexec)
+        })
+    }
+
+    fn open_pipe_with_cloexec(
+        &mut self,
+        cloexec: bool,
+    ) -> io::Result<Pipe<Self::OpenedFileHandle>> {
+        self.opener.open_pipe_with_cloexec(cloexec)
+    }
 }
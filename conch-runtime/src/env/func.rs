@@ -1,4 +1,5 @@
 use crate::env::SubEnvironment;
+use crate::{ExitStatus, EXIT_SUCCESS};
 use std::collections::HashMap;
 use std::fmt;
 use std::hash::Hash;
@@ -20,6 +21,13 @@ pub trait FunctionEnvironment {
     fn has_function(&self, name: &Self::FnName) -> bool {
         self.function(name).is_some()
     }
+
+    /// List the names of all registered functions, in no particular order.
+    ///
+    /// Defaults to an empty list for implementations which cannot enumerate their functions.
+    fn function_names(&self) -> Vec<&Self::FnName> {
+        Vec::new()
+    }
 }
 
 impl<'a, T: ?Sized + FunctionEnvironment> FunctionEnvironment for &'a mut T {
@@ -37,6 +45,10 @@ impl<'a, T: ?Sized + FunctionEnvironment> FunctionEnvironment for &'a mut T {
     fn has_function(&self, name: &Self::FnName) -> bool {
         (**self).has_function(name)
     }
+
+    fn function_names(&self) -> Vec<&Self::FnName> {
+        (**self).function_names()
+    }
 }
 
 /// An interface for unsetting shell functions.
@@ -75,16 +87,89 @@ impl<'a, T: ?Sized + FunctionFrameEnvironment> FunctionFrameEnvironment for &'a
     }
 }
 
-/// An implementation of `FunctionFrameEnvironment`
+/// A request to unwind out of the currently executing commands,
+/// e.g. via the `return`, `exit`, `break`, or `continue` builtins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlFlow {
+    /// Return from the currently executing function with the provided status.
+    Return(ExitStatus),
+    /// Exit the current shell (or subshell) with the provided status.
+    Exit(ExitStatus),
+    /// Break out of the provided number of enclosing loops.
+    Break(usize),
+    /// Skip to the next iteration of the loop which is the provided
+    /// number of levels out (i.e. `1` being the innermost loop).
+    Continue(usize),
+}
+
+impl ControlFlow {
+    /// Get the status which the function, shell, or loop should complete with.
+    pub fn status(self) -> ExitStatus {
+        match self {
+            ControlFlow::Return(status) | ControlFlow::Exit(status) => status,
+            ControlFlow::Break(_) | ControlFlow::Continue(_) => EXIT_SUCCESS,
+        }
+    }
+}
+
+/// An interface for requesting changes to the flow of execution.
+///
+/// Since builtin utilities cannot directly fail the command which invoked them,
+/// they may instead request that the flow of execution be changed, in which case
+/// the invoking command will complete with a `CommandError::Return`,
+/// `CommandError::Exit`, `CommandError::Break`, or `CommandError::Continue` error,
+/// which unwind all the way to the function, (sub)shell, or loop being affected.
+pub trait ControlFlowEnvironment {
+    /// Request that the flow of execution be changed once the current command completes.
+    fn request_control_flow(&mut self, flow: ControlFlow);
+    /// Take any pending request, leaving none in its place.
+    fn take_control_flow(&mut self) -> Option<ControlFlow>;
+    /// Denote that a new loop has started executing.
+    fn push_loop_frame(&mut self);
+    /// Denote that the innermost loop has finished executing.
+    fn pop_loop_frame(&mut self);
+    /// Get the number of loops which are currently executing.
+    fn loop_depth(&self) -> usize;
+}
+
+impl<'a, T: ?Sized + ControlFlowEnvironment> ControlFlowEnvironment for &'a mut T {
+    fn request_control_flow(&mut self, flow: ControlFlow) {
+        (**self).request_control_flow(flow);
+    }
+
+    fn take_control_flow(&mut self) -> Option<ControlFlow> {
+        (**self).take_control_flow()
+    }
+
+    fn push_loop_frame(&mut self) {
+        (**self).push_loop_frame();
+    }
+
+    fn pop_loop_frame(&mut self) {
+        (**self).pop_loop_frame();
+    }
+
+    fn loop_depth(&self) -> usize {
+        (**self).loop_depth()
+    }
+}
+
+/// An implementation of `FunctionFrameEnvironment` and `ControlFlowEnvironment`.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct FnFrameEnv {
     num_frames: usize,
+    num_loops: usize,
+    control_flow: Option<ControlFlow>,
 }
 
 impl FnFrameEnv {
     /// Create a new environment instance.
     pub fn new() -> Self {
-        Self { num_frames: 0 }
+        Self {
+            num_frames: 0,
+            num_loops: 0,
+            control_flow: None,
+        }
     }
 }
 
@@ -110,9 +195,40 @@ impl FunctionFrameEnvironment for FnFrameEnv {
     }
 }
 
+impl ControlFlowEnvironment for FnFrameEnv {
+    fn request_control_flow(&mut self, flow: ControlFlow) {
+        self.control_flow = Some(flow);
+    }
+
+    fn take_control_flow(&mut self) -> Option<ControlFlow> {
+        self.control_flow.take()
+    }
+
+    /// Denote that a new loop has started executing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of pushed loops overflows a `usize`.
+    fn push_loop_frame(&mut self) {
+        self.num_loops = self.num_loops.checked_add(1).expect("loop frame overflow");
+    }
+
+    fn pop_loop_frame(&mut self) {
+        self.num_loops = self.num_loops.saturating_sub(1);
+    }
+
+    fn loop_depth(&self) -> usize {
+        self.num_loops
+    }
+}
+
 impl SubEnvironment for FnFrameEnv {
     fn sub_env(&self) -> Self {
-        *self
+        Self {
+            num_frames: self.num_frames,
+            num_loops: self.num_loops,
+            control_flow: None,
+        }
     }
 }
 
@@ -185,6 +301,10 @@ where
     fn set_function(&mut self, name: Self::FnName, func: Self::Fn) {
         Arc::make_mut(&mut self.functions).insert(name, func);
     }
+
+    fn function_names(&self) -> Vec<&Self::FnName> {
+        self.functions.keys().collect()
+    }
 }
 
 impl<N, F> UnsetFunctionEnvironment for FnEnv<N, F>
@@ -0,0 +1,180 @@
+use crate::env::{FunctionFrameEnvironment, SubEnvironment};
+
+/// The parsing state of the `getopts` utility which cannot be represented
+/// by the `$OPTIND` variable alone.
+///
+/// Namely, when several options are grouped into a single argument (e.g. `-abc`),
+/// `getopts` must remember which character of the argument to inspect next, even
+/// though `$OPTIND` continues to point at the same argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GetoptsState {
+    /// The value of `$OPTIND` as it was last set by `getopts`.
+    pub optind: usize,
+    /// The offset of the next option character within the argument
+    /// at `optind`, or zero if the next argument has yet to be inspected.
+    pub char_offset: usize,
+}
+
+impl GetoptsState {
+    /// Creates a new state for starting to parse options at `optind`.
+    pub fn new(optind: usize) -> Self {
+        Self {
+            optind,
+            char_offset: 0,
+        }
+    }
+
+    /// Resolves the state to resume parsing from, given the current value of `$OPTIND`.
+    ///
+    /// If `$OPTIND` no longer matches the value `getopts` last set (e.g. the script
+    /// reset it to 1), any saved character offset is stale and is discarded.
+    pub fn resume_at(state: Option<Self>, optind: usize) -> Self {
+        match state {
+            Some(state) if state.optind == optind => state,
+            _ => Self::new(optind),
+        }
+    }
+}
+
+impl Default for GetoptsState {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+/// An interface for tracking the parsing state of the `getopts` utility.
+///
+/// The state is tracked per function frame: invoking a function starts it off
+/// without any state, and the caller's state is restored once the function returns.
+/// The `$OPTIND` and `$OPTARG` variables themselves remain regular shell variables.
+pub trait GetoptsEnvironment {
+    /// Get the parsing state of the current function frame, if any.
+    fn getopts_state(&self) -> Option<GetoptsState>;
+    /// Set the parsing state of the current function frame.
+    fn set_getopts_state(&mut self, state: Option<GetoptsState>);
+    /// Reset all parsing state of the current function frame, such that the next
+    /// `getopts` invocation starts from the first positional parameter.
+    ///
+    /// Implementations which also manage shell variables should set `$OPTIND`
+    /// to 1 and unset `$OPTARG`.
+    fn reset_getopts(&mut self);
+}
+
+impl<'a, T: ?Sized + GetoptsEnvironment> GetoptsEnvironment for &'a mut T {
+    fn getopts_state(&self) -> Option<GetoptsState> {
+        (**self).getopts_state()
+    }
+
+    fn set_getopts_state(&mut self, state: Option<GetoptsState>) {
+        (**self).set_getopts_state(state)
+    }
+
+    fn reset_getopts(&mut self) {
+        (**self).reset_getopts()
+    }
+}
+
+/// An implementation of `GetoptsEnvironment` which also implements
+/// `FunctionFrameEnvironment` to save and restore the state of callers.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GetoptsEnv {
+    state: Option<GetoptsState>,
+    outer: Vec<Option<GetoptsState>>,
+}
+
+impl GetoptsEnv {
+    /// Create a new environment instance.
+    pub fn new() -> Self {
+        Self {
+            state: None,
+            outer: Vec::new(),
+        }
+    }
+}
+
+impl GetoptsEnvironment for GetoptsEnv {
+    fn getopts_state(&self) -> Option<GetoptsState> {
+        self.state
+    }
+
+    fn set_getopts_state(&mut self, state: Option<GetoptsState>) {
+        self.state = state;
+    }
+
+    fn reset_getopts(&mut self) {
+        self.state = None;
+    }
+}
+
+impl FunctionFrameEnvironment for GetoptsEnv {
+    fn push_fn_frame(&mut self) {
+        self.outer.push(self.state.take());
+    }
+
+    fn pop_fn_frame(&mut self) {
+        if let Some(state) = self.outer.pop() {
+            self.state = state;
+        }
+    }
+
+    fn is_fn_running(&self) -> bool {
+        !self.outer.is_empty()
+    }
+}
+
+impl SubEnvironment for GetoptsEnv {
+    fn sub_env(&self) -> Self {
+        self.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resume_at_discards_stale_offset() {
+        let state = GetoptsState {
+            optind: 2,
+            char_offset: 3,
+        };
+
+        assert_eq!(GetoptsState::resume_at(Some(state), 2), state);
+        assert_eq!(
+            GetoptsState::resume_at(Some(state), 1),
+            GetoptsState::new(1)
+        );
+        assert_eq!(GetoptsState::resume_at(None, 2), GetoptsState::new(2));
+    }
+
+    #[test]
+    fn test_state_is_saved_across_fn_frames() {
+        let outer = GetoptsState {
+            optind: 2,
+            char_offset: 1,
+        };
+
+        let mut env = GetoptsEnv::new();
+        env.set_getopts_state(Some(outer));
+
+        env.push_fn_frame();
+        assert!(env.is_fn_running());
+        assert_eq!(env.getopts_state(), None);
+        env.set_getopts_state(Some(GetoptsState::new(5)));
+
+        env.push_fn_frame();
+        env.pop_fn_frame();
+        assert_eq!(env.getopts_state(), Some(GetoptsState::new(5)));
+
+        env.pop_fn_frame();
+        assert!(!env.is_fn_running());
+        assert_eq!(env.getopts_state(), Some(outer));
+
+        // Popping too many frames should not clobber the current state
+        env.pop_fn_frame();
+        assert_eq!(env.getopts_state(), Some(outer));
+
+        env.reset_getopts();
+        assert_eq!(env.getopts_state(), None);
+    }
+}
@@ -0,0 +1,23 @@
+use std::borrow::Cow;
+
+/// An interface for resolving the home directory of the current user,
+/// e.g. for performing tilde expansions or changing to the home directory.
+///
+/// Typically this is the value of `$HOME`, but implementations are free to
+/// resolve it from elsewhere (e.g. to pin it to a fixed path within a sandbox).
+pub trait HomeDirEnvironment {
+    /// Get the home directory of the current user, if it can be determined.
+    fn home_dir(&self) -> Option<Cow<'_, str>>;
+}
+
+impl<'a, T: ?Sized + HomeDirEnvironment> HomeDirEnvironment for &'a T {
+    fn home_dir(&self) -> Option<Cow<'_, str>> {
+        (**self).home_dir()
+    }
+}
+
+impl<'a, T: ?Sized + HomeDirEnvironment> HomeDirEnvironment for &'a mut T {
+    fn home_dir(&self) -> Option<Cow<'_, str>> {
+        (**self).home_dir()
+    }
+}
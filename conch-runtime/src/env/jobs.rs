@@ -0,0 +1,255 @@
+use crate::env::{ResourceUsage, ResourceUsageTracker, SubEnvironment};
+use crate::{ExitStatus, EXIT_ERROR};
+use futures_core::future::BoxFuture;
+use futures_util::future::{select_all, FutureExt, Shared};
+use std::fmt;
+
+/// An identifier of an asynchronous (background) command spawned via
+/// `JobEnvironment::spawn_job`, which is also the value of `$!`.
+///
+/// Since subshells are emulated (rather than forked), job ids do not
+/// correspond to any actual process ids.
+pub type JobId = usize;
+
+/// An interface for running asynchronous (background) commands, e.g. `cmd &`,
+/// and later waiting for their completion.
+pub trait JobEnvironment {
+    /// Starts running a job in the background, and returns its id.
+    ///
+    /// The job will make progress regardless of whether it is ever waited for.
+    fn spawn_job(&mut self, job: BoxFuture<'static, ExitStatus>) -> JobId;
+    /// Get the id of the most recently spawned job, if any (i.e. the value of `$!`).
+    fn last_job(&self) -> Option<JobId>;
+    /// Get the ids of all jobs which have yet to be waited for, in the order
+    /// in which they were spawned.
+    fn job_ids(&self) -> Vec<JobId>;
+    /// Stop tracking a job and get a future which resolves to its exit status,
+    /// or `None` if the job is not known (e.g. it has already been waited for).
+    fn wait_job(&mut self, id: JobId) -> Option<BoxFuture<'static, ExitStatus>>;
+
+    /// Get a future which resolves once any job which is still running completes,
+    /// or `None` if no jobs are running. Unlike `wait_job`, the job continues to be
+    /// tracked, so that its status can still be waited for later.
+    ///
+    /// This is useful for reclaiming any resources held by jobs (e.g. file
+    /// descriptors) once they are exhausted.
+    ///
+    /// Defaults to `None` for implementations which cannot track running jobs.
+    fn any_job_done(&self) -> Option<BoxFuture<'static, ()>> {
+        None
+    }
+
+    /// Starts running a job in the background exactly like `spawn_job`, while
+    /// remembering the tracker which records the resources used by its executables.
+    ///
+    /// Defaults to `spawn_job` (without remembering the tracker) for
+    /// implementations which cannot report the usage of their jobs.
+    fn spawn_job_with_usage(
+        &mut self,
+        job: BoxFuture<'static, ExitStatus>,
+        _usage: ResourceUsageTracker,
+    ) -> JobId {
+        self.spawn_job(job)
+    }
+
+    /// Get the resources used so far by the executables of a job which has yet
+    /// to be waited for, or `None` if the job is not known or its usage is not tracked.
+    ///
+    /// Defaults to `None` for implementations which cannot report the usage of their jobs.
+    fn job_usage(&self, _id: JobId) -> Option<ResourceUsage> {
+        None
+    }
+}
+
+impl<'a, T: ?Sized + JobEnvironment> JobEnvironment for &'a mut T {
+    fn spawn_job(&mut self, job: BoxFuture<'static, ExitStatus>) -> JobId {
+        (**self).spawn_job(job)
+    }
+
+    fn last_job(&self) -> Option<JobId> {
+        (**self).last_job()
+    }
+
+    fn job_ids(&self) -> Vec<JobId> {
+        (**self).job_ids()
+    }
+
+    fn wait_job(&mut self, id: JobId) -> Option<BoxFuture<'static, ExitStatus>> {
+        (**self).wait_job(id)
+    }
+
+    fn any_job_done(&self) -> Option<BoxFuture<'static, ()>> {
+        (**self).any_job_done()
+    }
+
+    fn spawn_job_with_usage(
+        &mut self,
+        job: BoxFuture<'static, ExitStatus>,
+        usage: ResourceUsageTracker,
+    ) -> JobId {
+        (**self).spawn_job_with_usage(job, usage)
+    }
+
+    fn job_usage(&self, id: JobId) -> Option<ResourceUsage> {
+        (**self).job_usage(id)
+    }
+}
+
+type JobFuture = Shared<BoxFuture<'static, ExitStatus>>;
+
+/// A running job, along with the tracker of its resource usage (if any).
+#[derive(Clone)]
+struct Job {
+    id: JobId,
+    future: JobFuture,
+    usage: Option<ResourceUsageTracker>,
+}
+
+/// An implementation of `JobEnvironment` which runs each job as a `tokio` task.
+///
+/// Jobs are not inherited by sub-environments (just as a subshell cannot wait
+/// for its parent's jobs), although the value of `$!` is.
+///
+/// Once more than `JobEnv::CHILD_MAX` jobs have yet to be waited for, the
+/// oldest jobs which have already completed are forgotten (as POSIX permits),
+/// so that long-lived environments which never wait for their jobs do not
+/// grow without bound. Jobs which are still running are never forgotten.
+#[derive(Clone)]
+pub struct JobEnv {
+    next_id: JobId,
+    last_job: Option<JobId>,
+    jobs: Vec<Job>,
+}
+
+impl JobEnv {
+    /// The number of jobs whose status is remembered until they are waited
+    /// for, before any completed jobs are forgotten.
+    pub const CHILD_MAX: usize = 1024;
+
+    /// Create a new environment instance.
+    pub fn new() -> Self {
+        Self {
+            next_id: 1,
+            last_job: None,
+            jobs: Vec::new(),
+        }
+    }
+}
+
+impl Default for JobEnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for JobEnv {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct(stringify!(JobEnv))
+            .field("next_id", &self.next_id)
+            .field("last_job", &self.last_job)
+            .field("jobs", &self.job_ids())
+            .finish()
+    }
+}
+
+impl JobEnv {
+    fn push_job(
+        &mut self,
+        job: BoxFuture<'static, ExitStatus>,
+        usage: Option<ResourceUsageTracker>,
+    ) -> JobId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.last_job = Some(id);
+
+        let handle = tokio::spawn(job);
+        let future: BoxFuture<'static, _> =
+            Box::pin(async move { handle.await.unwrap_or(EXIT_ERROR) });
+
+        self.jobs.push(Job {
+            id,
+            future: future.shared(),
+            usage,
+        });
+        self.forget_completed_jobs();
+        id
+    }
+
+    /// Forgets the oldest completed jobs while more than `CHILD_MAX` are tracked.
+    fn forget_completed_jobs(&mut self) {
+        let mut excess = self.jobs.len().saturating_sub(Self::CHILD_MAX);
+        if excess == 0 {
+            return;
+        }
+
+        self.jobs.retain(|job| {
+            if excess > 0 && job.future.clone().now_or_never().is_some() {
+                excess -= 1;
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
+
+impl JobEnvironment for JobEnv {
+    fn spawn_job(&mut self, job: BoxFuture<'static, ExitStatus>) -> JobId {
+        self.push_job(job, None)
+    }
+
+    fn last_job(&self) -> Option<JobId> {
+        self.last_job
+    }
+
+    fn job_ids(&self) -> Vec<JobId> {
+        self.jobs.iter().map(|job| job.id).collect()
+    }
+
+    fn wait_job(&mut self, id: JobId) -> Option<BoxFuture<'static, ExitStatus>> {
+        let idx = self.jobs.iter().position(|job| job.id == id)?;
+        let job = self.jobs.remove(idx);
+        Some(Box::pin(job.future))
+    }
+
+    fn any_job_done(&self) -> Option<BoxFuture<'static, ()>> {
+        let running = self
+            .jobs
+            .iter()
+            .filter(|job| job.future.peek().is_none())
+            .map(|job| job.future.clone().map(drop))
+            .collect::<Vec<_>>();
+
+        if running.is_empty() {
+            None
+        } else {
+            Some(Box::pin(select_all(running).map(drop)))
+        }
+    }
+
+    fn spawn_job_with_usage(
+        &mut self,
+        job: BoxFuture<'static, ExitStatus>,
+        usage: ResourceUsageTracker,
+    ) -> JobId {
+        self.push_job(job, Some(usage))
+    }
+
+    fn job_usage(&self, id: JobId) -> Option<ResourceUsage> {
+        self.jobs
+            .iter()
+            .find(|job| job.id == id)
+            .and_then(|job| job.usage.as_ref())
+            .map(ResourceUsageTracker::usage)
+    }
+}
+
+impl SubEnvironment for JobEnv {
+    fn sub_env(&self) -> Self {
+        Self {
+            next_id: self.next_id,
+            last_job: self.last_job,
+            jobs: Vec::new(),
+        }
+    }
+}
@@ -20,12 +20,38 @@ impl<'a, T: ?Sized + LastStatusEnvironment> LastStatusEnvironment for &'a mut T
     }
 }
 
-/// An environment module for setting and getting
-/// the exit status of the last command to run.
+/// An interface for recording the exit status of the last command substitution
+/// performed while evaluating a command.
+///
+/// Per POSIX, a command which has no command name (i.e. one which only consists
+/// of variable assignments and/or redirections) completes with the status of the
+/// last command substitution it performed, or zero if it did not perform any.
+pub trait SubstitutionStatusEnvironment {
+    /// Get the exit status of the last command substitution performed since
+    /// the status was last cleared, if any.
+    fn last_substitution_status(&self) -> Option<ExitStatus>;
+    /// Record (or clear) the exit status of the last command substitution.
+    fn set_last_substitution_status(&mut self, status: Option<ExitStatus>);
+}
+
+impl<'a, T: ?Sized + SubstitutionStatusEnvironment> SubstitutionStatusEnvironment for &'a mut T {
+    fn last_substitution_status(&self) -> Option<ExitStatus> {
+        (**self).last_substitution_status()
+    }
+
+    fn set_last_substitution_status(&mut self, status: Option<ExitStatus>) {
+        (**self).set_last_substitution_status(status);
+    }
+}
+
+/// An environment module for setting and getting the exit status
+/// of the last command (and command substitution) to run.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct LastStatusEnv {
     /// The exit status of the last command that was executed.
     last_status: ExitStatus,
+    /// The exit status of the last command substitution that was performed.
+    last_substitution_status: Option<ExitStatus>,
 }
 
 impl LastStatusEnv {
@@ -38,6 +64,7 @@ impl LastStatusEnv {
     pub fn with_status(status: ExitStatus) -> Self {
         LastStatusEnv {
             last_status: status,
+            last_substitution_status: None,
         }
     }
 }
@@ -52,6 +79,16 @@ impl LastStatusEnvironment for LastStatusEnv {
     }
 }
 
+impl SubstitutionStatusEnvironment for LastStatusEnv {
+    fn last_substitution_status(&self) -> Option<ExitStatus> {
+        self.last_substitution_status
+    }
+
+    fn set_last_substitution_status(&mut self, status: Option<ExitStatus>) {
+        self.last_substitution_status = status;
+    }
+}
+
 impl Default for LastStatusEnv {
     fn default() -> Self {
         Self::new()
@@ -78,6 +115,20 @@ mod tests {
         assert_eq!(env.last_status(), exit);
     }
 
+    #[test]
+    fn test_env_set_and_get_last_substitution_status() {
+        let exit = ExitStatus::Code(42);
+        let mut env = LastStatusEnv::new();
+        assert_eq!(env.last_substitution_status(), None);
+
+        env.set_last_substitution_status(Some(exit));
+        assert_eq!(env.last_substitution_status(), Some(exit));
+        assert_eq!(env.last_status(), EXIT_SUCCESS);
+
+        env.set_last_substitution_status(None);
+        assert_eq!(env.last_substitution_status(), None);
+    }
+
     #[test]
     fn test_set_last_status_in_child_env_should_not_affect_parent() {
         let parent_exit = ExitStatus::Signal(9);
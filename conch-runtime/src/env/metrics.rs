@@ -0,0 +1,137 @@
+//! Metrics (e.g. for Prometheus-style exporters) about the commands an environment
+//! executes, recorded through a pluggable `MetricsSink`.
+
+use crate::env::{NextSpawn, SpawnContext, SpawnMiddleware, SpawnMiddlewareEnvironment};
+use crate::ExitStatus;
+use futures_core::future::BoxFuture;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// The counter of commands which were spawned, labeled by `command`.
+pub const COMMANDS_EXECUTED: &str = "conch_commands_executed_total";
+/// The counter of commands which exited unsuccessfully, labeled by `command`.
+pub const COMMAND_FAILURES: &str = "conch_command_failures_total";
+/// The histogram of how long it took to spawn commands (in seconds),
+/// labeled by `command`.
+pub const SPAWN_LATENCY_SECONDS: &str = "conch_spawn_latency_seconds";
+/// The counter of bytes written by the shell itself (i.e. by builtin utilities,
+/// as well as any reported errors or `xtrace` output) rather than by executables.
+pub const SHELL_OUTPUT_BYTES: &str = "conch_shell_output_bytes_total";
+
+/// A destination for the metrics recorded by an environment, e.g. an adapter
+/// which exports them to Prometheus (or any other metrics system).
+///
+/// Metrics are identified by the names defined in this module (e.g.
+/// `COMMANDS_EXECUTED`), along with any labels which further describe them.
+pub trait MetricsSink: Send + Sync {
+    /// Increments the counter `name` by `value`.
+    fn increment_counter(&self, name: &'static str, labels: &[(&'static str, &str)], value: u64);
+
+    /// Records an observation of the histogram `name`.
+    fn record_histogram(&self, name: &'static str, labels: &[(&'static str, &str)], value: f64);
+}
+
+impl<'a, T: ?Sized + MetricsSink> MetricsSink for &'a T {
+    fn increment_counter(&self, name: &'static str, labels: &[(&'static str, &str)], value: u64) {
+        (**self).increment_counter(name, labels, value)
+    }
+
+    fn record_histogram(&self, name: &'static str, labels: &[(&'static str, &str)], value: f64) {
+        (**self).record_histogram(name, labels, value)
+    }
+}
+
+impl<T: ?Sized + MetricsSink> MetricsSink for Arc<T> {
+    fn increment_counter(&self, name: &'static str, labels: &[(&'static str, &str)], value: u64) {
+        (**self).increment_counter(name, labels, value)
+    }
+
+    fn record_histogram(&self, name: &'static str, labels: &[(&'static str, &str)], value: f64) {
+        (**self).record_histogram(name, labels, value)
+    }
+}
+
+/// An interface for the `MetricsSink` which records any metrics about
+/// the output an environment writes (see `SHELL_OUTPUT_BYTES`).
+pub trait MetricsEnvironment {
+    /// Get the sink which records the environment's metrics, if any.
+    fn metrics_sink(&self) -> Option<&Arc<dyn MetricsSink>>;
+
+    /// Change the sink which records the environment's metrics. Any sub-environments
+    /// inherit the sink which was installed at the time they are created.
+    fn set_metrics_sink(&mut self, sink: Option<Arc<dyn MetricsSink>>);
+}
+
+impl<'a, T: ?Sized + MetricsEnvironment> MetricsEnvironment for &'a mut T {
+    fn metrics_sink(&self) -> Option<&Arc<dyn MetricsSink>> {
+        (**self).metrics_sink()
+    }
+
+    fn set_metrics_sink(&mut self, sink: Option<Arc<dyn MetricsSink>>) {
+        (**self).set_metrics_sink(sink)
+    }
+}
+
+/// A `SpawnMiddleware` which records how many commands are spawned (see
+/// `COMMANDS_EXECUTED`), how many of them fail (see `COMMAND_FAILURES`),
+/// and how long it takes to spawn them (see `SPAWN_LATENCY_SECONDS`).
+#[derive(Clone)]
+pub struct MetricsMiddleware {
+    sink: Arc<dyn MetricsSink>,
+}
+
+impl MetricsMiddleware {
+    /// Creates a middleware which records its metrics into the provided sink.
+    pub fn new(sink: Arc<dyn MetricsSink>) -> Self {
+        Self { sink }
+    }
+
+    async fn record(
+        &self,
+        cmd: &SpawnContext,
+        next: NextSpawn<'_>,
+    ) -> BoxFuture<'static, ExitStatus> {
+        let command = cmd.argv.first().cloned().unwrap_or_default();
+        let started = Instant::now();
+        let future = next.await;
+
+        let labels = [("command", command.as_str())];
+        let latency = started.elapsed().as_secs_f64();
+        self.sink
+            .record_histogram(SPAWN_LATENCY_SECONDS, &labels, latency);
+        self.sink.increment_counter(COMMANDS_EXECUTED, &labels, 1);
+
+        let sink = self.sink.clone();
+        Box::pin(async move {
+            let status = future.await;
+            if !status.success() {
+                let labels = [("command", command.as_str())];
+                sink.increment_counter(COMMAND_FAILURES, &labels, 1);
+            }
+            status
+        })
+    }
+}
+
+impl fmt::Debug for MetricsMiddleware {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct(stringify!(MetricsMiddleware)).finish()
+    }
+}
+
+impl SpawnMiddleware for MetricsMiddleware {
+    fn wrap<'a>(&'a self, cmd: &'a SpawnContext, next: NextSpawn<'a>) -> NextSpawn<'a> {
+        Box::pin(self.record(cmd, next))
+    }
+}
+
+/// Installs the sink into the environment, and registers a `MetricsMiddleware`
+/// which records into it, so that all metrics defined in this module are recorded.
+pub fn install_metrics<E>(sink: Arc<dyn MetricsSink>, env: &mut E)
+where
+    E: ?Sized + MetricsEnvironment + SpawnMiddlewareEnvironment,
+{
+    env.add_spawn_middleware(Arc::new(MetricsMiddleware::new(sink.clone())));
+    env.set_metrics_sink(Some(sink));
+}
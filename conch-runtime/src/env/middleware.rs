@@ -0,0 +1,76 @@
+use crate::ExitStatus;
+use futures_core::future::BoxFuture;
+use std::sync::Arc;
+
+/// A command which is about to be spawned, as seen by any `SpawnMiddleware`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpawnContext {
+    /// The fully expanded command name and its arguments.
+    pub argv: Vec<String>,
+}
+
+/// The (type-erased) spawning of a command, which resolves to a future
+/// of the command's exit status once the command has been spawned.
+///
+/// Nothing is spawned until this future is first polled, so a middleware
+/// can prevent a command from running by never polling it.
+pub type NextSpawn<'a> = BoxFuture<'a, BoxFuture<'static, ExitStatus>>;
+
+/// A layer which can be wrapped around every command an environment spawns,
+/// allowing cross-cutting concerns (e.g. tracing, timeouts, policy checks, or
+/// metrics) to be implemented without changing how each command is spawned.
+///
+/// Middleware applies to every simple command (including function invocations
+/// and builtin utilities), after all of its words have been expanded and its
+/// redirects have been applied. Compound commands are not wrapped themselves,
+/// although any simple commands they run are.
+pub trait SpawnMiddleware: Send + Sync {
+    /// Wraps the spawning of a command, returning a future which should
+    /// (eventually) spawn the command, e.g. by awaiting `next`.
+    ///
+    /// For example, a middleware may do some work before or after awaiting
+    /// `next`, wrap the status future it resolves to, or skip running the
+    /// command altogether by resolving to a status of its own.
+    ///
+    /// Should `next` fail to spawn the command, it will resolve to a future
+    /// of `EXIT_ERROR`, and the actual error will be propagated to the caller
+    /// once all middleware has completed.
+    fn wrap<'a>(&'a self, cmd: &'a SpawnContext, next: NextSpawn<'a>) -> NextSpawn<'a>;
+}
+
+impl<'b, T: ?Sized + SpawnMiddleware> SpawnMiddleware for &'b T {
+    fn wrap<'a>(&'a self, cmd: &'a SpawnContext, next: NextSpawn<'a>) -> NextSpawn<'a> {
+        (**self).wrap(cmd, next)
+    }
+}
+
+impl<T: ?Sized + SpawnMiddleware> SpawnMiddleware for Arc<T> {
+    fn wrap<'a>(&'a self, cmd: &'a SpawnContext, next: NextSpawn<'a>) -> NextSpawn<'a> {
+        (**self).wrap(cmd, next)
+    }
+}
+
+/// An interface for registering `SpawnMiddleware` to be wrapped
+/// around every command an environment spawns.
+pub trait SpawnMiddlewareEnvironment {
+    /// Get all registered middleware, in the order they were registered.
+    fn spawn_middleware(&self) -> &[Arc<dyn SpawnMiddleware>];
+
+    /// Registers a middleware to be wrapped around every spawned command.
+    ///
+    /// Middleware is applied in the order it is registered, that is, the
+    /// first registered middleware is the outermost layer around a command.
+    /// Any sub-environments inherit all middleware registered at the time
+    /// they are created.
+    fn add_spawn_middleware(&mut self, middleware: Arc<dyn SpawnMiddleware>);
+}
+
+impl<'a, T: ?Sized + SpawnMiddlewareEnvironment> SpawnMiddlewareEnvironment for &'a mut T {
+    fn spawn_middleware(&self) -> &[Arc<dyn SpawnMiddleware>] {
+        (**self).spawn_middleware()
+    }
+
+    fn add_spawn_middleware(&mut self, middleware: Arc<dyn SpawnMiddleware>) {
+        (**self).add_spawn_middleware(middleware)
+    }
+}
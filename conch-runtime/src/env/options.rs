@@ -0,0 +1,152 @@
+/// The set of shell options which alter how commands are executed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ShellOptions {
+    /// Mark any variable which is assigned a value as exported (i.e. `set -a`).
+    ///
+    /// Variables which are restored to their previous value (e.g. after a
+    /// command with temporary assignments completes) retain their previous
+    /// exported status.
+    pub allexport: bool,
+    /// Abort the script if a command exits unsuccessfully (i.e. `set -e`).
+    ///
+    /// The status of each command of a list (or of an entire pipeline) is checked
+    /// as soon as it completes, and a failure results in a (fatal)
+    /// `CommandError::ErrExit`. The option is ignored while evaluating the
+    /// condition of an `if`, `while`, or `until` command, any command of an
+    /// `&&` or `||` list other than the last, and any negated pipeline.
+    pub errexit: bool,
+    /// Treat the expansion of any unset parameter (other than `$@` or `$*`)
+    /// as an error (i.e. `set -u`).
+    pub nounset: bool,
+    /// Report the status of a pipeline as that of its last command which exited
+    /// unsuccessfully, or success if all commands succeeded (i.e. `set -o pipefail`).
+    pub pipefail: bool,
+    /// Write a trace of each command to standard error before it is executed (i.e. `set -x`).
+    pub xtrace: bool,
+    /// Treat a failure of a special builtin utility (e.g. `shift`) as a fatal error
+    /// which aborts the script, as POSIX requires of non-interactive shells.
+    pub fatal_special_builtin_errors: bool,
+    /// If a pipeline cannot be created because too many files are open, wait for
+    /// a background job to complete (thus releasing any file descriptors it holds)
+    /// and try again, rather than failing right away with
+    /// `CommandError::TooManyOpenFiles`.
+    pub reap_jobs_on_fd_exhaustion: bool,
+    /// Keep remembering the locations of previously found commands (see
+    /// `CommandSearchEnvironment`) even after `$PATH` changes, until they are
+    /// explicitly forgotten (e.g. via `hash -r`), rather than forgetting them
+    /// automatically.
+    pub manual_rehash: bool,
+}
+
+impl ShellOptions {
+    /// The names of all options which can be changed with `set -o NAME`,
+    /// along with the single letter flag of the option, if it has one.
+    pub const NAMES: &'static [(&'static str, Option<char>)] = &[
+        ("allexport", Some('a')),
+        ("errexit", Some('e')),
+        ("nounset", Some('u')),
+        ("pipefail", None),
+        ("xtrace", Some('x')),
+    ];
+
+    /// Get the value of an option by its name (e.g. `errexit`), if it exists.
+    pub fn get(&self, name: &str) -> Option<bool> {
+        match name {
+            "allexport" => Some(self.allexport),
+            "errexit" => Some(self.errexit),
+            "nounset" => Some(self.nounset),
+            "pipefail" => Some(self.pipefail),
+            "xtrace" => Some(self.xtrace),
+            _ => None,
+        }
+    }
+
+    /// Get a mutable reference to an option by its name (e.g. `errexit`), if it exists.
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut bool> {
+        match name {
+            "allexport" => Some(&mut self.allexport),
+            "errexit" => Some(&mut self.errexit),
+            "nounset" => Some(&mut self.nounset),
+            "pipefail" => Some(&mut self.pipefail),
+            "xtrace" => Some(&mut self.xtrace),
+            _ => None,
+        }
+    }
+
+    /// Get the name of the option with a single letter flag (e.g. `e` for `errexit`).
+    pub fn name_of_flag(flag: char) -> Option<&'static str> {
+        Self::NAMES
+            .iter()
+            .find(|&&(_, f)| f == Some(flag))
+            .map(|&(name, _)| name)
+    }
+
+    /// Renders the single letter flags of all enabled options (e.g. as `$-` would).
+    pub fn flags(&self) -> String {
+        Self::NAMES
+            .iter()
+            .filter_map(|&(name, flag)| flag.filter(|_| self.get(name) == Some(true)))
+            .collect()
+    }
+}
+
+/// An interface for querying and changing the current shell options.
+pub trait ShellOptionsEnvironment {
+    /// Get the currently set shell options.
+    fn options(&self) -> &ShellOptions;
+    /// Get a mutable reference to the currently set shell options.
+    fn options_mut(&mut self) -> &mut ShellOptions;
+
+    /// Checks if the `errexit` option is set and is not currently being ignored
+    /// (e.g. while evaluating the condition of an `if` command).
+    fn is_errexit_enforced(&self) -> bool;
+    /// Starts ignoring the `errexit` option until a matching call to
+    /// `end_ignoring_errexit`, without changing the value of the option itself
+    /// (thus any changes made to it in the meantime are retained).
+    ///
+    /// Calls may be nested, and any sub-environments created in the meantime
+    /// ignore the option as well.
+    fn begin_ignoring_errexit(&mut self);
+    /// Stops ignoring the `errexit` option, as previously requested by a
+    /// call to `begin_ignoring_errexit`.
+    fn end_ignoring_errexit(&mut self);
+
+    /// Switches the environment between interactive and non-interactive mode,
+    /// as later reported by `IsInteractiveEnvironment::is_interactive`.
+    ///
+    /// While interactive, a `Session` reports any fatal errors and moves on to the
+    /// next command instead of returning them, and `sequence` does not read ahead
+    /// (e.g. so a REPL is not blocked waiting on further input). Entering
+    /// interactive mode also clears `ShellOptions::fatal_special_builtin_errors`,
+    /// as an interactive shell must not exit on such errors; leaving it does not
+    /// change any options.
+    ///
+    /// Any sub-environments created afterwards inherit the new mode.
+    fn set_interactive(&mut self, interactive: bool);
+}
+
+impl<'a, T: ?Sized + ShellOptionsEnvironment> ShellOptionsEnvironment for &'a mut T {
+    fn options(&self) -> &ShellOptions {
+        (**self).options()
+    }
+
+    fn options_mut(&mut self) -> &mut ShellOptions {
+        (**self).options_mut()
+    }
+
+    fn is_errexit_enforced(&self) -> bool {
+        (**self).is_errexit_enforced()
+    }
+
+    fn begin_ignoring_errexit(&mut self) {
+        (**self).begin_ignoring_errexit()
+    }
+
+    fn end_ignoring_errexit(&mut self) {
+        (**self).end_ignoring_errexit()
+    }
+
+    fn set_interactive(&mut self, interactive: bool) {
+        (**self).set_interactive(interactive)
+    }
+}
@@ -0,0 +1,171 @@
+use crate::env::{ControlFlow, LastStatusEnvironment, ReportErrorEnvironment};
+use crate::error::{control_flow_for_error, exit_status_for_error, IsFatalError};
+use crate::spawn::{sequence_exact, Spawn};
+use crate::{ExitStatus, EXIT_ERROR};
+use futures_core::future::BoxFuture;
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+/// An error which arises while parsing shell source text at runtime.
+pub type ScriptParseError = Box<dyn Error + Send + Sync>;
+
+/// An interface for parsing shell source text into commands at runtime,
+/// e.g. to run the actions installed via `trap`.
+pub trait ParseScriptEnvironment {
+    /// The type of the commands which source text is parsed into.
+    type Command;
+
+    /// Parses all commands out of some shell source text.
+    fn parse_script(&self, source: &str) -> Result<Vec<Self::Command>, ScriptParseError>;
+}
+
+impl<'a, T: ?Sized + ParseScriptEnvironment> ParseScriptEnvironment for &'a mut T {
+    type Command = T::Command;
+
+    fn parse_script(&self, source: &str) -> Result<Vec<Self::Command>, ScriptParseError> {
+        (**self).parse_script(source)
+    }
+}
+
+/// The signature of a function which parses and executes shell source text.
+pub type RunScriptFn<E> =
+    for<'a> fn(&'a str, &'a mut E) -> BoxFuture<'a, Result<ExitStatus, ControlFlow>>;
+
+/// A hook for parsing and executing shell source text within an environment
+/// at runtime, e.g. for the `.` builtin.
+///
+/// Builtin utilities cannot require that the commands an environment parses are
+/// themselves spawnable within it (spawning those commands may in turn require
+/// spawning builtins), so a runner is created wherever this is known (see
+/// `ScriptRunner::new`) and installed via `ScriptRunnerEnvironment`.
+pub struct ScriptRunner<E: ?Sized> {
+    run: RunScriptFn<E>,
+}
+
+impl<E: ?Sized> ScriptRunner<E> {
+    /// Creates a runner which parses source text via `ParseScriptEnvironment`,
+    /// and executes the resulting commands sequentially.
+    ///
+    /// Any errors (including syntax errors) are reported, while any requests
+    /// to `break`, `continue`, `return` or `exit` are returned to the caller.
+    pub fn new() -> Self
+    where
+        E: Send + LastStatusEnvironment + ParseScriptEnvironment + ReportErrorEnvironment,
+        E::Command: Send + Sync + Spawn<E>,
+        <E::Command as Spawn<E>>::Error: IsFatalError,
+    {
+        Self::from_fn(run_script::<E>)
+    }
+
+    /// Creates a runner out of a custom function, e.g. one which
+    /// parses the source text with a different parser.
+    pub fn from_fn(run: RunScriptFn<E>) -> Self {
+        Self { run }
+    }
+
+    /// Parses and executes some shell source text within an environment.
+    pub fn run<'a>(
+        &self,
+        source: &'a str,
+        env: &'a mut E,
+    ) -> BoxFuture<'a, Result<ExitStatus, ControlFlow>> {
+        (self.run)(source, env)
+    }
+}
+
+impl<E> Default for ScriptRunner<E>
+where
+    E: ?Sized + Send + LastStatusEnvironment + ParseScriptEnvironment + ReportErrorEnvironment,
+    E::Command: Send + Sync + Spawn<E>,
+    <E::Command as Spawn<E>>::Error: IsFatalError,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E: ?Sized> Clone for ScriptRunner<E> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<E: ?Sized> Copy for ScriptRunner<E> {}
+
+impl<E: ?Sized> fmt::Debug for ScriptRunner<E> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct(stringify!(ScriptRunner)).finish()
+    }
+}
+
+fn run_script<'a, E>(
+    source: &'a str,
+    env: &'a mut E,
+) -> BoxFuture<'a, Result<ExitStatus, ControlFlow>>
+where
+    E: ?Sized + Send + LastStatusEnvironment + ParseScriptEnvironment + ReportErrorEnvironment,
+    E::Command: Send + Sync + Spawn<E>,
+    <E::Command as Spawn<E>>::Error: IsFatalError,
+{
+    Box::pin(async move {
+        let cmds = match env.parse_script(source) {
+            Ok(cmds) => cmds,
+            Err(e) => {
+                env.report_error(&*e).await;
+                return Ok(EXIT_ERROR);
+            }
+        };
+
+        match sequence_exact(cmds, env).await {
+            Ok(future) => Ok(future.await),
+            Err(e) => match control_flow_for_error(&e) {
+                Some(flow) => Err(flow),
+                None => {
+                    env.report_error(&e).await;
+                    Ok(exit_status_for_error(&e))
+                }
+            },
+        }
+    })
+}
+
+/// An interface for installing a `ScriptRunner` into an environment.
+pub trait ScriptRunnerEnvironment {
+    /// Get the runner installed into the environment, if any.
+    fn script_runner(&self) -> Option<ScriptRunner<Self>>;
+    /// Installs a runner into the environment, replacing any previous one.
+    fn set_script_runner(&mut self, runner: ScriptRunner<Self>);
+}
+
+/// An interface for tracking the files which are currently being sourced
+/// (e.g. via the `.` builtin), so that nested files can be resolved relative
+/// to the file which includes them.
+pub trait SourceStackEnvironment {
+    /// Get the paths of all files currently being sourced, outermost first.
+    fn source_stack(&self) -> &[Arc<Path>];
+    /// Records that a file has started to be sourced.
+    fn push_source(&mut self, path: Arc<Path>);
+    /// Records that the innermost file has finished being sourced.
+    fn pop_source(&mut self) -> Option<Arc<Path>>;
+
+    /// Get the path of the innermost file currently being sourced, if any.
+    fn current_source(&self) -> Option<&Path> {
+        self.source_stack().last().map(|path| &**path)
+    }
+}
+
+impl<'a, T: ?Sized + SourceStackEnvironment> SourceStackEnvironment for &'a mut T {
+    fn source_stack(&self) -> &[Arc<Path>] {
+        (**self).source_stack()
+    }
+
+    fn push_source(&mut self, path: Arc<Path>) {
+        (**self).push_source(path)
+    }
+
+    fn pop_source(&mut self) -> Option<Arc<Path>> {
+        (**self).pop_source()
+    }
+}
@@ -0,0 +1,35 @@
+use std::io;
+use std::time::Duration;
+
+/// The CPU time used by the shell, and by any child processes it has reaped.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProcessTimes {
+    /// The CPU time the shell spent executing in user mode.
+    pub user_time: Duration,
+    /// The CPU time the shell spent executing in kernel mode.
+    pub system_time: Duration,
+    /// The CPU time all reaped children spent executing in user mode.
+    pub children_user_time: Duration,
+    /// The CPU time all reaped children spent executing in kernel mode.
+    pub children_system_time: Duration,
+}
+
+/// An interface for reporting the CPU time used by the shell and its
+/// children, e.g. for the `times` builtin.
+pub trait ProcessTimesEnvironment {
+    /// Get the CPU time used by the shell and any children it has reaped so far.
+    fn process_times(&self) -> io::Result<ProcessTimes>;
+}
+
+impl<'a, T: ?Sized + ProcessTimesEnvironment> ProcessTimesEnvironment for &'a T {
+    fn process_times(&self) -> io::Result<ProcessTimes> {
+        (**self).process_times()
+    }
+}
+
+impl<'a, T: ?Sized + ProcessTimesEnvironment> ProcessTimesEnvironment for &'a mut T {
+    fn process_times(&self) -> io::Result<ProcessTimes> {
+        (**self).process_times()
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// An interface for generating (pseudo) random numbers, so that anything
+/// which relies on randomness can be seeded or mocked by the embedder.
+///
+/// Implementations are *not* expected to be cryptographically secure.
+pub trait RandomEnvironment {
+    /// Generates the next random number.
+    fn next_u32(&self) -> u32;
+
+    /// Generates the next random number which is less than `bound`,
+    /// or zero if `bound` is zero.
+    fn next_below(&self, bound: u32) -> u32 {
+        // NB: scale the value instead of taking its remainder,
+        // since the low bits of some generators are weaker.
+        ((u64::from(self.next_u32()) * u64::from(bound)) >> 32) as u32
+    }
+}
+
+impl<'a, T: ?Sized + RandomEnvironment> RandomEnvironment for &'a T {
+    fn next_u32(&self) -> u32 {
+        (**self).next_u32()
+    }
+
+    fn next_below(&self, bound: u32) -> u32 {
+        (**self).next_below(bound)
+    }
+}
+
+impl<'a, T: ?Sized + RandomEnvironment> RandomEnvironment for &'a mut T {
+    fn next_u32(&self) -> u32 {
+        (**self).next_u32()
+    }
+
+    fn next_below(&self, bound: u32) -> u32 {
+        (**self).next_below(bound)
+    }
+}
+
+/// A fast, non-cryptographic pseudo random number generator (based on wyrand).
+///
+/// Generators created with the same seed always produce the same sequence of
+/// numbers, while the default generator is seeded from the randomness the
+/// operating system provides to the standard library.
+#[derive(Debug)]
+pub struct FastRng {
+    state: AtomicU64,
+}
+
+impl FastRng {
+    /// Creates a generator with a fixed seed.
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            state: AtomicU64::new(seed),
+        }
+    }
+
+    /// Creates a generator seeded by the operating system.
+    pub fn from_os_seed() -> Self {
+        Self::with_seed(RandomState::new().hash_one(SystemTime::now()))
+    }
+}
+
+impl Default for FastRng {
+    fn default() -> Self {
+        Self::from_os_seed()
+    }
+}
+
+impl RandomEnvironment for FastRng {
+    fn next_u32(&self) -> u32 {
+        const INCREMENT: u64 = 0xa076_1d64_78bd_642f;
+        const XOR: u64 = 0xe703_7ed1_a0b4_28db;
+
+        let state = self
+            .state
+            .fetch_add(INCREMENT, Ordering::Relaxed)
+            .wrapping_add(INCREMENT);
+        let t = u128::from(state) * u128::from(state ^ XOR);
+        ((t >> 64) as u64 ^ t as u64) as u32
+    }
+}
+
+/// A cheaply clonable handle to the random number generator used by an environment.
+///
+/// By default an OS seeded `FastRng` is used, but any other `RandomEnvironment`
+/// implementation can be provided instead. Any clones of a handle (and any
+/// sub-environments of the environment it belongs to) share the same generator,
+/// and two handles are considered equal if they share the same generator.
+#[derive(Clone)]
+pub struct Rng {
+    inner: Arc<dyn RandomEnvironment + Send + Sync>,
+}
+
+impl Rng {
+    /// Creates a handle which uses the provided generator.
+    pub fn new<R>(rng: R) -> Self
+    where
+        R: 'static + RandomEnvironment + Send + Sync,
+    {
+        Self {
+            inner: Arc::new(rng),
+        }
+    }
+
+    /// Creates a handle which uses a `FastRng` with a fixed seed.
+    pub fn with_seed(seed: u64) -> Self {
+        Self::new(FastRng::with_seed(seed))
+    }
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Self::new(FastRng::from_os_seed())
+    }
+}
+
+impl RandomEnvironment for Rng {
+    fn next_u32(&self) -> u32 {
+        self.inner.next_u32()
+    }
+
+    fn next_below(&self, bound: u32) -> u32 {
+        self.inner.next_below(bound)
+    }
+}
+
+impl PartialEq for Rng {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::as_ptr(&self.inner) as *const u8 == Arc::as_ptr(&other.inner) as *const u8
+    }
+}
+
+impl Eq for Rng {}
+
+impl fmt::Debug for Rng {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct(stringify!(Rng)).finish()
+    }
+}
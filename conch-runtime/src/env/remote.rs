@@ -0,0 +1,273 @@
+//! A reference implementation of an `ExecutableEnvironment` which proxies process
+//! execution to a remote agent (e.g. one running within a container or jail),
+//! while all other shell logic (expansions, redirections, control flow, etc.)
+//! continues to be evaluated locally.
+//!
+//! The environment converts the `ExecutableData` of each command into an owned
+//! `RemoteExecRequest` (which is serializable with the `serde` feature), and hands
+//! it off to a `RemoteAgent`, which is responsible for delivering it over whatever
+//! transport is appropriate (e.g. gRPC, or some other IPC mechanism). Since file
+//! descriptors cannot be sent over the wire, the local ends of any piped standard
+//! streams are handed to the agent separately, and it is the agent's responsibility
+//! to shuttle data between them and the remote process.
+//!
+//! ```
+//! # use conch_runtime::env::remote::*;
+//! # use conch_runtime::ExitStatus;
+//! # use futures_core::future::BoxFuture;
+//! /// An agent which pretends that every command exits with its number of arguments.
+//! struct CountingAgent;
+//!
+//! impl RemoteAgent for CountingAgent {
+//!     fn execute(
+//!         &self,
+//!         request: RemoteExecRequest,
+//!         _stdio: RemoteStdio,
+//!     ) -> BoxFuture<'static, RemoteExecResponse> {
+//!         // A real agent would serialize the request and send it somewhere
+//!         let status = ExitStatus::Code(request.args.len() as i32);
+//!         Box::pin(async move { RemoteExecResponse::Exited(status) })
+//!     }
+//! }
+//!
+//! let exec_env = RemoteExecEnv::new(CountingAgent);
+//! ```
+
+use crate::env::executable::exit_with_status;
+use crate::env::{
+    ExecReplaceEnvironment, ExecutableData, ExecutableEnvironment, ExecutableStdio, ProcessTimes,
+    ProcessTimesEnvironment, Resource, ResourceLimit, ResourceLimitEnvironment, SubEnvironment,
+};
+use crate::error::CommandError;
+use crate::io::FileDesc;
+use crate::{ExitStatus, EXIT_CMD_NOT_EXECUTABLE, EXIT_CMD_NOT_FOUND, EXIT_ERROR};
+use futures_core::future::BoxFuture;
+use std::convert::Infallible;
+use std::ffi::OsString;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// An owned description of an executable which should be spawned remotely.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteExecRequest {
+    /// The name/path to the executable.
+    pub name: OsString,
+    /// Arguments to be provided to the executable.
+    pub args: Vec<OsString>,
+    /// The (only) environment variables to be provided to the executable.
+    pub env_vars: Vec<(OsString, OsString)>,
+    /// The current working directory the executable should start out with.
+    pub current_dir: PathBuf,
+    /// How the executable's standard input should be set up.
+    pub stdin: RemoteStdioKind,
+    /// How the executable's standard output should be set up.
+    pub stdout: RemoteStdioKind,
+    /// How the executable's standard error should be set up.
+    pub stderr: RemoteStdioKind,
+}
+
+/// Describes how a standard stream of a remote executable should be set up.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemoteStdioKind {
+    /// Inherit the corresponding stream of the remote agent.
+    Inherit,
+    /// Redirect the stream to the equivalent of `/dev/null`.
+    Null,
+    /// The stream should be connected to the corresponding local descriptor
+    /// provided in `RemoteStdio`.
+    Piped,
+}
+
+/// The local ends of any standard streams of a remote executable which are
+/// marked as `RemoteStdioKind::Piped` in the corresponding request.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct RemoteStdio {
+    /// The local descriptor the executable's standard input should be read from.
+    pub stdin: Option<FileDesc>,
+    /// The local descriptor the executable's standard output should be written to.
+    pub stdout: Option<FileDesc>,
+    /// The local descriptor the executable's standard error should be written to.
+    pub stderr: Option<FileDesc>,
+}
+
+/// The outcome of executing a `RemoteExecRequest`.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RemoteExecResponse {
+    /// The executable was spawned and has exited with the provided status.
+    Exited(ExitStatus),
+    /// The executable could not be found.
+    NotFound,
+    /// The executable was found, but could not be executed.
+    NotExecutable,
+    /// The executable could not be spawned (or the agent could not be reached),
+    /// with a description of the failure.
+    Failed(String),
+}
+
+impl RemoteExecResponse {
+    /// Get the exit status which the shell should observe for this response,
+    /// following the same conventions as failing to spawn a local executable.
+    pub fn exit_status(&self) -> ExitStatus {
+        match *self {
+            RemoteExecResponse::Exited(status) => status,
+            RemoteExecResponse::NotFound => EXIT_CMD_NOT_FOUND,
+            RemoteExecResponse::NotExecutable => EXIT_CMD_NOT_EXECUTABLE,
+            RemoteExecResponse::Failed(_) => EXIT_ERROR,
+        }
+    }
+}
+
+/// An interface for delivering requests to a remote agent which executes them.
+pub trait RemoteAgent {
+    /// Deliver the request to the remote agent, resolving to its response once
+    /// the remote executable has exited (or could not be spawned).
+    ///
+    /// The agent takes ownership of the local ends of any piped standard streams,
+    /// and should close them once the remote executable has exited.
+    fn execute(
+        &self,
+        request: RemoteExecRequest,
+        stdio: RemoteStdio,
+    ) -> BoxFuture<'static, RemoteExecResponse>;
+}
+
+impl<'a, T: ?Sized + RemoteAgent> RemoteAgent for &'a T {
+    fn execute(
+        &self,
+        request: RemoteExecRequest,
+        stdio: RemoteStdio,
+    ) -> BoxFuture<'static, RemoteExecResponse> {
+        (**self).execute(request, stdio)
+    }
+}
+
+impl<T: ?Sized + RemoteAgent> RemoteAgent for Arc<T> {
+    fn execute(
+        &self,
+        request: RemoteExecRequest,
+        stdio: RemoteStdio,
+    ) -> BoxFuture<'static, RemoteExecResponse> {
+        (**self).execute(request, stdio)
+    }
+}
+
+/// An `ExecutableEnvironment` implementation which proxies all process
+/// execution to a `RemoteAgent`.
+///
+/// Note that since spawning a remote executable is asynchronous, failing to spawn
+/// it cannot be reported as an error, and is instead only observable through
+/// the exit status of the command (e.g. `127` if the command was not found).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RemoteExecEnv<A> {
+    agent: A,
+}
+
+impl<A> RemoteExecEnv<A> {
+    /// Construct a new environment which delivers requests to the provided agent.
+    pub fn new(agent: A) -> Self {
+        Self { agent }
+    }
+
+    /// Get a reference to the underlying agent.
+    pub fn agent(&self) -> &A {
+        &self.agent
+    }
+}
+
+impl<A: Clone> SubEnvironment for RemoteExecEnv<A> {
+    fn sub_env(&self) -> Self {
+        self.clone()
+    }
+}
+
+impl<A: RemoteAgent> ExecutableEnvironment for RemoteExecEnv<A> {
+    fn spawn_executable(
+        &self,
+        data: ExecutableData<'_>,
+    ) -> Result<BoxFuture<'static, ExitStatus>, CommandError> {
+        let (request, stdio) = into_request(data);
+        let response = self.agent.execute(request, stdio);
+
+        Ok(Box::pin(async move { response.await.exit_status() }))
+    }
+}
+
+/// Since the current process cannot be replaced by a remote executable,
+/// it instead exits with the same status once the executable completes.
+impl<A: RemoteAgent> ExecReplaceEnvironment for RemoteExecEnv<A> {
+    fn exec_replace(
+        &self,
+        data: ExecutableData<'_>,
+    ) -> Result<BoxFuture<'static, Infallible>, CommandError> {
+        let child = self.spawn_executable(data)?;
+        Ok(Box::pin(async move { exit_with_status(child.await) }))
+    }
+}
+
+/// The times of remote executables cannot (currently) be determined,
+/// thus process times cannot be reported.
+impl<A> ProcessTimesEnvironment for RemoteExecEnv<A> {
+    fn process_times(&self) -> io::Result<ProcessTimes> {
+        let msg = "process times are not supported by remote executables";
+        Err(io::Error::new(io::ErrorKind::Other, msg))
+    }
+}
+
+/// Resource limits cannot (currently) be communicated to remote agents,
+/// thus they can be neither inspected nor changed.
+impl<A> ResourceLimitEnvironment for RemoteExecEnv<A> {
+    fn resource_limit(&self, _resource: Resource) -> io::Result<ResourceLimit> {
+        Err(unsupported_resource_limits())
+    }
+
+    fn set_resource_limit(&mut self, _resource: Resource, _limit: ResourceLimit) -> io::Result<()> {
+        Err(unsupported_resource_limits())
+    }
+}
+
+fn unsupported_resource_limits() -> io::Error {
+    let msg = "resource limits are not supported by remote executables";
+    io::Error::new(io::ErrorKind::Other, msg)
+}
+
+/// Split up the data for executing a local executable into a request for
+/// a remote agent and the local ends of its standard streams.
+pub fn into_request(data: ExecutableData<'_>) -> (RemoteExecRequest, RemoteStdio) {
+    fn split(stdio: ExecutableStdio) -> (RemoteStdioKind, Option<FileDesc>) {
+        match stdio {
+            ExecutableStdio::Inherit => (RemoteStdioKind::Inherit, None),
+            ExecutableStdio::Null => (RemoteStdioKind::Null, None),
+            ExecutableStdio::Piped(fdes) => (RemoteStdioKind::Piped, Some(fdes)),
+        }
+    }
+
+    let (stdin_kind, stdin) = split(data.stdin);
+    let (stdout_kind, stdout) = split(data.stdout);
+    let (stderr_kind, stderr) = split(data.stderr);
+
+    let request = RemoteExecRequest {
+        name: data.name.to_owned(),
+        args: data.args.iter().map(|&arg| arg.to_owned()).collect(),
+        env_vars: data
+            .env_vars
+            .iter()
+            .map(|&(key, val)| (key.to_owned(), val.to_owned()))
+            .collect(),
+        current_dir: data.current_dir.to_owned(),
+        stdin: stdin_kind,
+        stdout: stdout_kind,
+        stderr: stderr_kind,
+    };
+
+    let stdio = RemoteStdio {
+        stdin,
+        stdout,
+        stderr,
+    };
+
+    (request, stdio)
+}
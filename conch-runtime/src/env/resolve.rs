@@ -0,0 +1,144 @@
+use crate::env::{BuiltinEnvironment, FunctionEnvironment};
+use std::any::Any;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+
+/// The utility a command name resolves to when it is invoked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResolvedCommand<F, B> {
+    /// A shell function.
+    Function(F),
+    /// A builtin utility.
+    Builtin(B),
+    /// Neither a function nor a builtin, thus the command
+    /// (if it exists at all) is an external executable.
+    External,
+}
+
+/// An interface for resolving command names to the functions or builtin
+/// utilities they would invoke. Functions always take precedence over builtins.
+///
+/// Since the same commands are usually invoked over and over again,
+/// implementations are free to cache any resolutions, as long as they are
+/// invalidated whenever the function (or builtin) definitions change.
+pub trait ResolveCommandEnvironment:
+    FunctionEnvironment + BuiltinEnvironment<BuiltinName = <Self as FunctionEnvironment>::FnName>
+{
+    /// Resolves a command name to the utility it would invoke.
+    fn resolve_command(&mut self, name: &Self::FnName) -> ResolvedCommand<Self::Fn, Self::Builtin>;
+}
+
+impl<'a, T: ?Sized + ResolveCommandEnvironment> ResolveCommandEnvironment for &'a mut T {
+    fn resolve_command(&mut self, name: &Self::FnName) -> ResolvedCommand<Self::Fn, Self::Builtin> {
+        (**self).resolve_command(name)
+    }
+}
+
+/// The maximum number of cached resolutions of external commands.
+///
+/// Functions and builtins are always cached since they are bounded by their
+/// definitions, but scripts may generate arbitrarily many other command names.
+const MAX_CACHED_EXTERNAL: usize = 256;
+
+/// A cache of resolved command names, used by `Env`.
+///
+/// A cached resolution still costs a hash of the name, but skips the separate
+/// function and builtin lookups (the `resolve_command` benchmark of the
+/// `conch-runtime-tests` crate compares the two).
+///
+/// Builtins are type erased since `Env` does not otherwise require that its
+/// builtin environment implement `BuiltinEnvironment`, and thus cannot name the
+/// type of the builtins it produces.
+#[derive(Debug)]
+pub(crate) struct CommandCache<N: Eq + Hash, F> {
+    entries: HashMap<N, ResolvedCommand<F, Arc<dyn Any + Send + Sync>>>,
+    /// How many of the cached entries are external commands.
+    external: usize,
+}
+
+impl<N: Eq + Hash, F: Clone> CommandCache<N, F> {
+    pub(crate) fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+            external: 0,
+        }
+    }
+
+    /// Looks up a previous resolution of a command, or resolves
+    /// (and caches) it via the provided closure.
+    pub(crate) fn resolve<B, R>(&mut self, name: &N, resolve: R) -> ResolvedCommand<F, B>
+    where
+        N: Clone,
+        B: 'static + Send + Sync + Clone,
+        R: FnOnce() -> ResolvedCommand<F, B>,
+    {
+        let cached = match self.entries.get(name) {
+            Some(ResolvedCommand::Function(func)) => Some(ResolvedCommand::Function(func.clone())),
+            Some(ResolvedCommand::Builtin(builtin)) => builtin
+                .downcast_ref::<B>()
+                .map(|builtin| ResolvedCommand::Builtin(builtin.clone())),
+            Some(ResolvedCommand::External) => Some(ResolvedCommand::External),
+            None => None,
+        };
+
+        if let Some(cached) = cached {
+            return cached;
+        }
+
+        let resolved = resolve();
+        let entry = match &resolved {
+            ResolvedCommand::Function(func) => ResolvedCommand::Function(func.clone()),
+            ResolvedCommand::Builtin(builtin) => {
+                let builtin: Arc<dyn Any + Send + Sync> = Arc::new(builtin.clone());
+                ResolvedCommand::Builtin(builtin)
+            }
+            ResolvedCommand::External if self.external >= MAX_CACHED_EXTERNAL => return resolved,
+            ResolvedCommand::External => {
+                self.external += 1;
+                ResolvedCommand::External
+            }
+        };
+
+        self.entries.insert(name.clone(), entry);
+        resolved
+    }
+
+    /// Forgets any resolution of a command whose definition has changed.
+    pub(crate) fn invalidate(&mut self, name: &N) {
+        if let Some(ResolvedCommand::External) = self.entries.remove(name) {
+            self.external -= 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cached_external_commands_are_bounded() {
+        let mut cache = CommandCache::<String, ()>::new();
+        let external = || ResolvedCommand::<(), ()>::External;
+
+        for i in 0..MAX_CACHED_EXTERNAL * 2 {
+            cache.resolve(&format!("cmd{}", i), external);
+        }
+        assert_eq!(cache.entries.len(), MAX_CACHED_EXTERNAL);
+
+        // Functions and builtins are still cached
+        cache.resolve(&"func".to_owned(), || {
+            ResolvedCommand::<(), ()>::Function(())
+        });
+        cache.resolve(&"builtin".to_owned(), || {
+            ResolvedCommand::<(), ()>::Builtin(())
+        });
+        assert_eq!(cache.entries.len(), MAX_CACHED_EXTERNAL + 2);
+
+        // Forgetting a cached external command makes room for another
+        cache.invalidate(&"cmd0".to_owned());
+        cache.resolve(&"other".to_owned(), external);
+        assert!(cache.entries.contains_key("other"));
+        assert_eq!(cache.entries.len(), MAX_CACHED_EXTERNAL + 2);
+    }
+}
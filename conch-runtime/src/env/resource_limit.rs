@@ -0,0 +1,94 @@
+use std::io;
+
+/// A resource whose consumption by executables can be limited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Resource {
+    /// The maximum size (in bytes) of any core files which are created.
+    CoreFileSize,
+    /// The maximum size (in bytes) of a process' data segment.
+    DataSize,
+    /// The maximum size (in bytes) of any files which are written.
+    FileSize,
+    /// The maximum number of files a process may have open at once.
+    OpenFiles,
+    /// The maximum size (in bytes) of a process' stack.
+    StackSize,
+    /// The maximum amount of CPU time (in seconds) a process may use.
+    CpuTime,
+    /// The maximum number of processes the user may run at once.
+    Processes,
+    /// The maximum amount of virtual memory (in bytes) a process may use.
+    VirtualMemory,
+}
+
+impl Resource {
+    /// All resources, in the order `ulimit -a` reports them.
+    pub const ALL: &'static [Resource] = &[
+        Resource::CoreFileSize,
+        Resource::DataSize,
+        Resource::FileSize,
+        Resource::OpenFiles,
+        Resource::StackSize,
+        Resource::CpuTime,
+        Resource::Processes,
+        Resource::VirtualMemory,
+    ];
+}
+
+/// The soft (i.e. currently enforced) and hard (i.e. the ceiling to which
+/// the soft limit may be raised) limits of a resource.
+///
+/// A limit of `None` indicates that the resource is unlimited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ResourceLimit {
+    /// The currently enforced limit.
+    pub soft: Option<u64>,
+    /// The ceiling to which the soft limit may be raised.
+    pub hard: Option<u64>,
+}
+
+impl ResourceLimit {
+    /// Checks if the soft limit does not exceed the hard limit.
+    pub fn is_valid(&self) -> bool {
+        match (self.soft, self.hard) {
+            (_, None) => true,
+            (None, Some(_)) => false,
+            (Some(soft), Some(hard)) => soft <= hard,
+        }
+    }
+
+    /// Lowers both the soft and hard limits so that neither exceeds `max`.
+    pub fn capped(self, max: u64) -> Self {
+        let cap = |limit: Option<u64>| Some(limit.map_or(max, |limit| limit.min(max)));
+        Self {
+            soft: cap(self.soft),
+            hard: cap(self.hard),
+        }
+    }
+}
+
+/// An interface for inspecting and changing the resource limits
+/// which are applied to any executables an environment spawns.
+///
+/// Note that the limits of the current process are not affected.
+pub trait ResourceLimitEnvironment {
+    /// Get the limit of a resource which spawned executables are subject to.
+    fn resource_limit(&self, resource: Resource) -> io::Result<ResourceLimit>;
+
+    /// Change the limit of a resource which spawned executables are subject to.
+    ///
+    /// Just like with `setrlimit(2)`, the soft limit may not exceed the hard
+    /// limit, and (unprivileged) hard limits may only ever be lowered.
+    fn set_resource_limit(&mut self, resource: Resource, limit: ResourceLimit) -> io::Result<()>;
+}
+
+impl<'a, T: ?Sized + ResourceLimitEnvironment> ResourceLimitEnvironment for &'a mut T {
+    fn resource_limit(&self, resource: Resource) -> io::Result<ResourceLimit> {
+        (**self).resource_limit(resource)
+    }
+
+    fn set_resource_limit(&mut self, resource: Resource, limit: ResourceLimit) -> io::Result<()> {
+        (**self).set_resource_limit(resource, limit)
+    }
+}
@@ -1,16 +1,23 @@
 use crate::env::{
-    AsyncIoEnvironment, ExportedVariableEnvironment, FileDescEnvironment, FileDescOpener, Pipe,
-    UnsetVariableEnvironment, VariableEnvironment,
+    AsyncIoEnvironment, BoxAsyncRead, ChangeWorkingDirectoryEnvironment,
+    ExportedVariableEnvironment, FileDescAuditEnvironment, FileDescEnvironment, FileDescOpener,
+    LastStatusEnvironment, OpenFlags, Pipe, UnsetVariableEnvironment, VariableEnvironment,
+    WorkingDirectoryEnvironment,
 };
 use crate::io::Permissions;
-use crate::Fd;
+use crate::{ExitStatus, Fd};
 use futures_core::future::BoxFuture;
 use std::borrow::{Borrow, Cow};
 use std::collections::HashMap;
+use std::fmt;
 use std::fs::OpenOptions;
 use std::hash::Hash;
 use std::io;
-use std::path::Path;
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::thread;
 
 /// A base interface for any environment wrappers which track changes
 /// such that they can be undone later.
@@ -39,6 +46,151 @@ where
     }
 }
 
+/// An interface for restorers which can group their backups into nested frames,
+/// such that the changes made within a frame can be undone without disturbing
+/// the backups held by any enclosing frames.
+///
+/// Each frame captures the original value of anything it modifies at the time
+/// of its first modification *within that frame*. Any operations which restore
+/// or clear backups (e.g. `VarEnvRestorer::restore_vars`) only apply to the
+/// innermost frame; clearing a frame's backups does not affect the backups
+/// already held by any enclosing frames.
+///
+/// # Re-entrancy
+///
+/// A restorer is commonly handed down to nested commands (which may in turn
+/// push frames of their own) while an enclosing command still has a frame
+/// active. Thus, whoever pushes a frame owns it: it must pop exactly that frame
+/// (and no other) once it is done, and must leave any frames pushed by others
+/// untouched. Popping an enclosing frame early would restore (and discard) the
+/// backups it holds while its owner still relies on them, and any changes made
+/// afterwards would be silently attributed to the wrong frame.
+///
+/// `ScopedFrame` upholds this policy for its owner, and will panic (in debug
+/// builds) if any nested code leaves the frames unbalanced.
+pub trait ScopedRestorer<'a, E: 'a + ?Sized>: Restorer<'a, E> {
+    /// Begin a new frame, nested within the current one.
+    fn push_frame(&mut self);
+
+    /// Restore all changes made within the innermost frame, and discard it.
+    ///
+    /// If there is no nested frame, this is equivalent to restoring all
+    /// changes that have been backed up.
+    fn pop_frame(&mut self);
+
+    /// The number of nested frames which have been pushed and not yet popped.
+    fn frame_depth(&self) -> usize;
+}
+
+impl<'a, 'b, E, T> ScopedRestorer<'a, E> for &'b mut T
+where
+    T: 'b + ?Sized + ScopedRestorer<'a, E>,
+    E: 'a + ?Sized,
+{
+    fn push_frame(&mut self) {
+        (**self).push_frame()
+    }
+
+    fn pop_frame(&mut self) {
+        (**self).pop_frame()
+    }
+
+    fn frame_depth(&self) -> usize {
+        (**self).frame_depth()
+    }
+}
+
+/// A frame of a `ScopedRestorer` which is pushed on creation, and popped on drop
+/// (even if the future holding it is cancelled before it completes).
+///
+/// The restorer remains accessible (via `Deref` and `DerefMut`) while the frame
+/// is active. In debug builds, dropping the frame will panic if it is no longer
+/// the innermost frame of the restorer (i.e. if any frames pushed within it were
+/// not popped, or if a frame pushed before it was popped in its place), rather
+/// than silently restoring backups which belong to another frame.
+pub struct ScopedFrame<'r, 'a, RR, E>
+where
+    RR: ?Sized + ScopedRestorer<'a, E>,
+    E: ?Sized,
+{
+    restorer: &'r mut RR,
+    depth: usize,
+    env: PhantomData<fn() -> &'a E>,
+}
+
+impl<'r, 'a, RR, E> ScopedFrame<'r, 'a, RR, E>
+where
+    RR: ?Sized + ScopedRestorer<'a, E>,
+    E: ?Sized,
+{
+    /// Push a new frame onto the restorer, which will be popped on drop.
+    pub fn new(restorer: &'r mut RR) -> Self {
+        restorer.push_frame();
+        let depth = restorer.frame_depth();
+
+        Self {
+            restorer,
+            depth,
+            env: PhantomData,
+        }
+    }
+}
+
+impl<'r, 'a, RR, E> fmt::Debug for ScopedFrame<'r, 'a, RR, E>
+where
+    RR: ?Sized + ScopedRestorer<'a, E>,
+    E: ?Sized,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct(stringify!(ScopedFrame))
+            .field("depth", &self.depth)
+            .finish()
+    }
+}
+
+impl<'r, 'a, RR, E> Deref for ScopedFrame<'r, 'a, RR, E>
+where
+    RR: ?Sized + ScopedRestorer<'a, E>,
+    E: ?Sized,
+{
+    type Target = RR;
+
+    fn deref(&self) -> &Self::Target {
+        &*self.restorer
+    }
+}
+
+impl<'r, 'a, RR, E> DerefMut for ScopedFrame<'r, 'a, RR, E>
+where
+    RR: ?Sized + ScopedRestorer<'a, E>,
+    E: ?Sized,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut *self.restorer
+    }
+}
+
+impl<'r, 'a, RR, E> Drop for ScopedFrame<'r, 'a, RR, E>
+where
+    RR: ?Sized + ScopedRestorer<'a, E>,
+    E: ?Sized,
+{
+    fn drop(&mut self) {
+        let depth = self.restorer.frame_depth();
+
+        // NB: avoid a double panic (and thus an abort) if we're already unwinding
+        if cfg!(debug_assertions) && depth != self.depth && !thread::panicking() {
+            panic!(
+                "restorer frames were left unbalanced: expected to pop the frame at depth {}, \
+                 but the innermost frame is at depth {}",
+                self.depth, depth
+            );
+        }
+
+        self.restorer.pop_frame();
+    }
+}
+
 /// An interface for wrapping an environment and maintaining a state of all variable
 /// definitions that have been modified so that they can be restored later.
 pub trait VarEnvRestorer<'a, E: 'a + ?Sized + VariableEnvironment>:
@@ -105,6 +257,9 @@ pub trait RedirectEnvRestorer<'a, E: 'a + ?Sized + FileDescEnvironment>:
 
     /// Forget any redirects backed up to this point.
     fn clear_redirects(&mut self);
+
+    /// Get the (sorted) file descriptors whose original values are currently backed up.
+    fn redirected_fds(&self) -> Vec<Fd>;
 }
 
 impl<'a, 'b, E, T> RedirectEnvRestorer<'a, E> for &'b mut T
@@ -127,10 +282,18 @@ where
     fn clear_redirects(&mut self) {
         (**self).clear_redirects();
     }
+
+    fn redirected_fds(&self) -> Vec<Fd> {
+        (**self).redirected_fds()
+    }
 }
 
 /// Maintains a state of environment modifications so that
 /// they can be restored later, either on drop or on demand.
+///
+/// Modifications can be grouped into nested frames via the
+/// `ScopedRestorer` trait. Any frames still active on drop are
+/// restored innermost first.
 #[derive(Debug, PartialEq)]
 pub struct EnvRestorer<'a, E>
 where
@@ -142,8 +305,16 @@ where
     env: &'a mut E,
     var_overrides: HashMap<E::VarName, Option<(E::Var, bool)>>,
     redirect_overrides: HashMap<Fd, Option<(E::FileHandle, Permissions)>>,
+    /// Backups held by any enclosing frames, outermost first.
+    outer_frames: Vec<Frame<E>>,
 }
 
+/// The variable and redirect backups held by a single frame.
+type Frame<E> = (
+    HashMap<<E as VariableEnvironment>::VarName, Option<(<E as VariableEnvironment>::Var, bool)>>,
+    HashMap<Fd, Option<(<E as FileDescEnvironment>::FileHandle, Permissions)>>,
+);
+
 impl<'a, E> EnvRestorer<'a, E>
 where
     E: ?Sized + ExportedVariableEnvironment + FileDescEnvironment + UnsetVariableEnvironment,
@@ -157,6 +328,7 @@ where
             env,
             var_overrides: HashMap::new(),
             redirect_overrides: HashMap::new(),
+            outer_frames: Vec::new(),
         }
     }
 }
@@ -185,8 +357,39 @@ where
     E::Var: Clone,
 {
     fn drop(&mut self) {
+        while !self.outer_frames.is_empty() {
+            self.pop_frame();
+        }
+
+        self.pop_frame();
+    }
+}
+
+impl<'a, E> ScopedRestorer<'a, E> for EnvRestorer<'a, E>
+where
+    E: ?Sized + ExportedVariableEnvironment + FileDescEnvironment + UnsetVariableEnvironment,
+    E::FileHandle: Clone,
+    E::VarName: Clone,
+    E::Var: Clone,
+{
+    fn push_frame(&mut self) {
+        let vars = mem::take(&mut self.var_overrides);
+        let redirects = mem::take(&mut self.redirect_overrides);
+        self.outer_frames.push((vars, redirects));
+    }
+
+    fn pop_frame(&mut self) {
         self.restore_vars();
         self.restore_redirects();
+
+        if let Some((vars, redirects)) = self.outer_frames.pop() {
+            self.var_overrides = vars;
+            self.redirect_overrides = redirects;
+        }
+    }
+
+    fn frame_depth(&self) -> usize {
+        self.outer_frames.len()
     }
 }
 
@@ -209,11 +412,22 @@ where
     }
 
     fn restore_vars(&mut self) {
-        for (key, val) in self.var_overrides.drain() {
-            match val {
-                Some((val, exported)) => self.env.set_exported_var(key, val, exported),
-                None => self.env.unset_var(&key),
-            }
+        let mut unset = Vec::new();
+        let restored = self
+            .var_overrides
+            .drain()
+            .filter_map(|(key, val)| match val {
+                Some((val, exported)) => Some((key, val, exported)),
+                None => {
+                    unset.push(key);
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+
+        self.env.extend_exported(restored);
+        for key in unset {
+            self.env.unset_var(&key);
         }
     }
 
@@ -249,6 +463,18 @@ where
         self.env.set_var(name, val);
     }
 
+    fn set_vars<I>(&mut self, vars: I)
+    where
+        I: IntoIterator<Item = (Self::VarName, Self::Var)>,
+    {
+        let vars = vars
+            .into_iter()
+            .inspect(|(name, _)| self.backup_var(name))
+            .collect::<Vec<_>>();
+
+        self.env.set_vars(vars);
+    }
+
     fn env_vars(&self) -> Cow<'_, [(&Self::VarName, &Self::Var)]> {
         self.env.env_vars()
     }
@@ -273,6 +499,18 @@ where
         self.backup_var(&name);
         self.env.set_exported_var(name, val, exported)
     }
+
+    fn extend_exported<I>(&mut self, vars: I)
+    where
+        I: IntoIterator<Item = (Self::VarName, Self::Var, bool)>,
+    {
+        let vars = vars
+            .into_iter()
+            .inspect(|(name, _, _)| self.backup_var(name))
+            .collect::<Vec<_>>();
+
+        self.env.extend_exported(vars);
+    }
 }
 
 impl<'a, E> UnsetVariableEnvironment for EnvRestorer<'a, E>
@@ -316,6 +554,22 @@ where
     }
 }
 
+impl<'a, E> FileDescAuditEnvironment for EnvRestorer<'a, E>
+where
+    E: ?Sized
+        + ExportedVariableEnvironment
+        + FileDescAuditEnvironment
+        + FileDescEnvironment
+        + UnsetVariableEnvironment,
+    E::FileHandle: Clone,
+    E::VarName: Clone,
+    E::Var: Clone,
+{
+    fn inheritable_fds(&self) -> io::Result<Vec<Fd>> {
+        self.env.inheritable_fds()
+    }
+}
+
 impl<'a, E> FileDescOpener for EnvRestorer<'a, E>
 where
     E: ?Sized
@@ -329,12 +583,29 @@ where
 {
     type OpenedFileHandle = E::OpenedFileHandle;
 
-    fn open_path(&mut self, path: &Path, opts: &OpenOptions) -> io::Result<Self::OpenedFileHandle> {
-        self.env.open_path(path, opts)
+    fn open_path_with_cloexec(
+        &mut self,
+        path: &Path,
+        opts: &OpenOptions,
+        cloexec: bool,
+    ) -> io::Result<Self::OpenedFileHandle> {
+        self.env.open_path_with_cloexec(path, opts, cloexec)
+    }
+
+    fn open_path_with_flags(
+        &mut self,
+        path: &Path,
+        flags: OpenFlags,
+        cloexec: bool,
+    ) -> io::Result<Self::OpenedFileHandle> {
+        self.env.open_path_with_flags(path, flags, cloexec)
     }
 
-    fn open_pipe(&mut self) -> io::Result<Pipe<Self::OpenedFileHandle>> {
-        self.env.open_pipe()
+    fn open_pipe_with_cloexec(
+        &mut self,
+        cloexec: bool,
+    ) -> io::Result<Pipe<Self::OpenedFileHandle>> {
+        self.env.open_pipe_with_cloexec(cloexec)
     }
 }
 
@@ -355,6 +626,10 @@ where
         self.env.read_all(fd)
     }
 
+    fn read_async(&mut self, fd: Self::IoHandle) -> io::Result<BoxAsyncRead> {
+        self.env.read_async(fd)
+    }
+
     fn write_all<'a>(
         &mut self,
         fd: Self::IoHandle,
@@ -403,4 +678,187 @@ where
     fn clear_redirects(&mut self) {
         self.redirect_overrides.clear();
     }
+
+    fn redirected_fds(&self) -> Vec<Fd> {
+        let mut fds = self.redirect_overrides.keys().copied().collect::<Vec<_>>();
+        fds.sort_unstable();
+        fds
+    }
+}
+
+/// Temporarily overrides the last status of an environment, restoring the
+/// original status either on drop or on demand.
+///
+/// Since restoration happens on drop, the original status is restored even if
+/// a future holding the restorer is cancelled before it completes.
+#[derive(Debug, PartialEq)]
+pub struct LastStatusRestorer<'a, E: ?Sized + LastStatusEnvironment> {
+    env: &'a mut E,
+    original: Option<ExitStatus>,
+}
+
+impl<'a, E: ?Sized + LastStatusEnvironment> LastStatusRestorer<'a, E> {
+    /// Create a new restorer which backs up the current last status.
+    pub fn new(env: &'a mut E) -> Self {
+        let original = Some(env.last_status());
+        Self { env, original }
+    }
+
+    /// Create a new restorer which backs up the current last status,
+    /// before overriding it with the specified `status`.
+    pub fn with_status(env: &'a mut E, status: ExitStatus) -> Self {
+        let restorer = Self::new(env);
+        restorer.env.set_last_status(status);
+        restorer
+    }
+
+    /// Restore the last status to its original value.
+    ///
+    /// Subsequent changes will *not* be restored unless the status is backed up again.
+    pub fn restore_last_status(&mut self) {
+        if let Some(status) = self.original.take() {
+            self.env.set_last_status(status);
+        }
+    }
+
+    /// Back up the current last status, unless it is already backed up.
+    pub fn backup_last_status(&mut self) {
+        if self.original.is_none() {
+            self.original = Some(self.env.last_status());
+        }
+    }
+
+    /// Forget the backed up last status, such that any changes are retained.
+    pub fn clear_last_status(&mut self) {
+        self.original = None;
+    }
+}
+
+impl<'a, E: ?Sized + LastStatusEnvironment> Restorer<'a, E> for LastStatusRestorer<'a, E> {
+    fn get(&self) -> &E {
+        &*self.env
+    }
+
+    fn get_mut(&mut self) -> &mut E {
+        &mut self.env
+    }
+}
+
+impl<'a, E: ?Sized + LastStatusEnvironment> LastStatusEnvironment for LastStatusRestorer<'a, E> {
+    fn last_status(&self) -> ExitStatus {
+        self.env.last_status()
+    }
+
+    fn set_last_status(&mut self, status: ExitStatus) {
+        self.backup_last_status();
+        self.env.set_last_status(status);
+    }
+}
+
+impl<'a, E: ?Sized + LastStatusEnvironment> Drop for LastStatusRestorer<'a, E> {
+    fn drop(&mut self) {
+        self.restore_last_status();
+    }
+}
+
+/// Temporarily overrides the working directory of an environment, restoring the
+/// original directory either on drop or on demand.
+///
+/// Since restoration happens on drop, the original directory is restored even if
+/// a future holding the restorer is cancelled before it completes. Note that
+/// restoring on drop is best effort: any errors (e.g. if the original directory
+/// no longer exists) are ignored, use `restore_working_dir` to observe them.
+#[derive(Debug, PartialEq)]
+pub struct WorkingDirRestorer<'a, E: ?Sized + ChangeWorkingDirectoryEnvironment> {
+    env: &'a mut E,
+    original: Option<PathBuf>,
+}
+
+impl<'a, E: ?Sized + ChangeWorkingDirectoryEnvironment> WorkingDirRestorer<'a, E> {
+    /// Create a new restorer which backs up the current working directory.
+    pub fn new(env: &'a mut E) -> Self {
+        let original = Some(env.current_working_dir().to_path_buf());
+        Self { env, original }
+    }
+
+    /// Create a new restorer which backs up the current working directory,
+    /// before changing it to the specified `path`.
+    ///
+    /// If the directory cannot be changed, the error is returned and
+    /// the environment is left unmodified.
+    pub fn with_working_dir(env: &'a mut E, path: Cow<'_, Path>) -> io::Result<Self> {
+        let mut restorer = Self::new(env);
+        if let Err(e) = restorer.env.change_working_dir(path) {
+            restorer.clear_working_dir();
+            return Err(e);
+        }
+        Ok(restorer)
+    }
+
+    /// Restore the working directory to its original value.
+    ///
+    /// Subsequent changes will *not* be restored unless the directory is backed up again.
+    pub fn restore_working_dir(&mut self) -> io::Result<()> {
+        match self.original.take() {
+            Some(path) => self.env.change_working_dir(Cow::Owned(path)),
+            None => Ok(()),
+        }
+    }
+
+    /// Back up the current working directory, unless it is already backed up.
+    pub fn backup_working_dir(&mut self) {
+        if self.original.is_none() {
+            self.original = Some(self.env.current_working_dir().to_path_buf());
+        }
+    }
+
+    /// Forget the backed up working directory, such that any changes are retained.
+    pub fn clear_working_dir(&mut self) {
+        self.original = None;
+    }
+}
+
+impl<'a, E> Restorer<'a, E> for WorkingDirRestorer<'a, E>
+where
+    E: ?Sized + ChangeWorkingDirectoryEnvironment,
+{
+    fn get(&self) -> &E {
+        &*self.env
+    }
+
+    fn get_mut(&mut self) -> &mut E {
+        &mut self.env
+    }
+}
+
+impl<'a, E> WorkingDirectoryEnvironment for WorkingDirRestorer<'a, E>
+where
+    E: ?Sized + ChangeWorkingDirectoryEnvironment,
+{
+    fn path_relative_to_working_dir<'b>(&self, path: Cow<'b, Path>) -> Cow<'b, Path> {
+        self.env.path_relative_to_working_dir(path)
+    }
+
+    fn current_working_dir(&self) -> &Path {
+        self.env.current_working_dir()
+    }
+}
+
+impl<'a, E> ChangeWorkingDirectoryEnvironment for WorkingDirRestorer<'a, E>
+where
+    E: ?Sized + ChangeWorkingDirectoryEnvironment,
+{
+    fn change_working_dir<'b>(&mut self, path: Cow<'b, Path>) -> io::Result<()> {
+        self.backup_working_dir();
+        self.env.change_working_dir(path)
+    }
+}
+
+impl<'a, E> Drop for WorkingDirRestorer<'a, E>
+where
+    E: ?Sized + ChangeWorkingDirectoryEnvironment,
+{
+    fn drop(&mut self) {
+        let _ = self.restore_working_dir();
+    }
 }
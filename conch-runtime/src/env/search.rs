@@ -0,0 +1,177 @@
+use crate::env::{VarChange, WatchVariableEnvironment};
+use std::borrow::Borrow;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// An interface for finding executables within the `$PATH` directories.
+///
+/// Implementations may remember the locations of any commands they find (as
+/// reported by the `hash` builtin), so that scripts which repeatedly invoke the
+/// same utilities do not need to search for them every time. Any remembered
+/// locations are forgotten as soon as the value of `$PATH` changes, unless
+/// `ShellOptions::manual_rehash` is set.
+pub trait CommandSearchEnvironment {
+    /// Searches the `$PATH` directories (in order) for an executable
+    /// named `name`, and returns its location if it is found.
+    ///
+    /// Names which contain a path separator are never searched for.
+    fn search_command(&mut self, name: &str) -> Option<PathBuf>;
+    /// Get the names and locations of all remembered commands, sorted by name.
+    fn hashed_commands(&self) -> Vec<(String, PathBuf)>;
+    /// Forget the locations of all remembered commands (e.g. `hash -r`).
+    fn forget_hashed_commands(&mut self);
+}
+
+impl<'a, T: ?Sized + CommandSearchEnvironment> CommandSearchEnvironment for &'a mut T {
+    fn search_command(&mut self, name: &str) -> Option<PathBuf> {
+        (**self).search_command(name)
+    }
+
+    fn hashed_commands(&self) -> Vec<(String, PathBuf)> {
+        (**self).hashed_commands()
+    }
+
+    fn forget_hashed_commands(&mut self) {
+        (**self).forget_hashed_commands()
+    }
+}
+
+/// A cache of the locations of executables found within the `$PATH`
+/// directories, used by `Env`.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct CommandSearchCache {
+    /// How many times `$PATH` has changed, as reported by the
+    /// variable watcher registered via `watch`.
+    path_changes: Arc<AtomicUsize>,
+    /// The value of `path_changes` the remembered locations were found with.
+    seen_path_changes: usize,
+    entries: BTreeMap<String, PathBuf>,
+}
+
+impl CommandSearchCache {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a cache which is notified of any `$PATH` changes made to `var_env`.
+    pub(crate) fn watching<V>(var_env: &mut V) -> Self
+    where
+        V: ?Sized + WatchVariableEnvironment,
+        V::VarName: Borrow<String>,
+    {
+        let mut cache = Self::new();
+        cache.watch(var_env);
+        cache
+    }
+
+    /// Starts watching `var_env` for any changes to `$PATH` (instead of
+    /// any previously watched environment), while keeping any remembered
+    /// locations, e.g. for a sub-environment whose variables start off
+    /// identical to its parent's (since watchers are not inherited).
+    pub(crate) fn watch<V>(&mut self, var_env: &mut V)
+    where
+        V: ?Sized + WatchVariableEnvironment,
+        V::VarName: Borrow<String>,
+    {
+        // NB: carry over the current count so that any changes
+        // which have yet to be acted on are not forgotten
+        let path_changes = self.path_changes.load(Ordering::Relaxed);
+        let path_changes = Arc::new(AtomicUsize::new(path_changes));
+        self.path_changes = path_changes.clone();
+
+        var_env.watch(move |change| {
+            let name = match change {
+                VarChange::Set { name, .. } | VarChange::Unset { name } => name,
+            };
+
+            if name.borrow() == "PATH" {
+                path_changes.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+    }
+
+    /// Looks up a previously found location of a command, or searches
+    /// the `path` directories for it (resolving any relative ones via
+    /// `resolve_dir`), remembering where it was found.
+    ///
+    /// Unless `manual_rehash` is set, all remembered locations are forgotten
+    /// if `$PATH` has changed since they were found. Locations found within
+    /// relative directories are never remembered, since they depend on the
+    /// current working directory.
+    pub(crate) fn search<R>(
+        &mut self,
+        name: &str,
+        path: Option<&str>,
+        manual_rehash: bool,
+        resolve_dir: R,
+    ) -> Option<PathBuf>
+    where
+        R: Fn(PathBuf) -> PathBuf,
+    {
+        if name.is_empty() || name.contains(std::path::is_separator) {
+            return None;
+        }
+
+        if self.is_stale(manual_rehash) {
+            self.clear();
+        }
+
+        if let Some(found) = self.entries.get(name) {
+            return Some(found.clone());
+        }
+
+        for dir in std::env::split_paths(path?) {
+            let is_relative = dir.is_relative();
+            let candidate = resolve_dir(dir).join(name);
+
+            let is_executable_file = fs::metadata(&candidate)
+                .map(|metadata| metadata.is_file() && is_executable(&metadata))
+                .unwrap_or(false);
+
+            if is_executable_file {
+                if !is_relative {
+                    self.entries.insert(name.to_owned(), candidate.clone());
+                }
+                return Some(candidate);
+            }
+        }
+
+        None
+    }
+
+    /// Lists all remembered locations which are still valid.
+    pub(crate) fn entries(&self, manual_rehash: bool) -> Vec<(String, PathBuf)> {
+        if self.is_stale(manual_rehash) {
+            return Vec::new();
+        }
+
+        self.entries
+            .iter()
+            .map(|(name, path)| (name.clone(), path.clone()))
+            .collect()
+    }
+
+    fn is_stale(&self, manual_rehash: bool) -> bool {
+        !manual_rehash && self.path_changes.load(Ordering::Relaxed) != self.seen_path_changes
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.seen_path_changes = self.path_changes.load(Ordering::Relaxed);
+    }
+}
+
+#[cfg(unix)]
+pub(crate) fn is_executable(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+pub(crate) fn is_executable(_: &fs::Metadata) -> bool {
+    true
+}
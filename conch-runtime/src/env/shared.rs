@@ -0,0 +1,114 @@
+use crate::env::SubEnvironment;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+/// A handle for deliberately sharing a single environment (and any of its global
+/// state, such as variables, functions, or the working directory) between multiple
+/// concurrent tasks, e.g. several interactive sessions or parallel jobs.
+///
+/// Since all environment operations require mutable (or otherwise exclusive) access,
+/// a task must first `lock` the environment, which waits (asynchronously) until no
+/// other task holds the lock. The returned guard can be used anywhere an environment
+/// is expected (e.g. `&mut *guard`), and releases the lock when dropped.
+///
+/// Note that any task holding the lock while running commands will prevent all other
+/// tasks from making progress until the commands complete. Tasks which only need to
+/// share an initial state should prefer using a `sub_env` instead.
+pub struct SharedEnv<E> {
+    inner: Arc<Mutex<E>>,
+}
+
+impl<E> SharedEnv<E> {
+    /// Wraps an environment so that it can be shared.
+    pub fn new(env: E) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(env)),
+        }
+    }
+
+    /// Waits until no other task holds the lock, and acquires it.
+    pub async fn lock(&self) -> SharedEnvGuard<E> {
+        SharedEnvGuard {
+            guard: self.inner.clone().lock_owned().await,
+        }
+    }
+
+    /// Attempts to acquire the lock without waiting, returning `None`
+    /// if another task is currently holding it.
+    pub fn try_lock(&self) -> Option<SharedEnvGuard<E>> {
+        self.inner
+            .clone()
+            .try_lock_owned()
+            .ok()
+            .map(|guard| SharedEnvGuard { guard })
+    }
+
+    /// Checks if two handles share the same environment.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+
+    /// Unwraps the environment if this is the only remaining handle to it,
+    /// otherwise the handle is returned back.
+    pub fn try_unwrap(self) -> Result<E, Self> {
+        Arc::try_unwrap(self.inner)
+            .map(Mutex::into_inner)
+            .map_err(|inner| Self { inner })
+    }
+}
+
+impl<E: SubEnvironment> SharedEnv<E> {
+    /// Waits until the lock can be acquired, and creates an independent
+    /// sub-environment from the current state of the shared environment.
+    pub async fn sub_env(&self) -> E {
+        self.lock().await.sub_env()
+    }
+}
+
+impl<E> Clone for SharedEnv<E> {
+    /// Creates another handle to the *same* environment.
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<E> fmt::Debug for SharedEnv<E> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct(stringify!(SharedEnv))
+            .field("strong_count", &Arc::strong_count(&self.inner))
+            .finish()
+    }
+}
+
+/// Exclusive access to an environment shared via `SharedEnv`.
+///
+/// The lock is released when the guard is dropped.
+pub struct SharedEnvGuard<E> {
+    guard: OwnedMutexGuard<E>,
+}
+
+impl<E> Deref for SharedEnvGuard<E> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<E> DerefMut for SharedEnvGuard<E> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.guard
+    }
+}
+
+impl<E: fmt::Debug> fmt::Debug for SharedEnvGuard<E> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_tuple(stringify!(SharedEnvGuard))
+            .field(&*self.guard)
+            .finish()
+    }
+}
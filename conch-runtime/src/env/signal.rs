@@ -0,0 +1,226 @@
+use crate::env::SubEnvironment;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+/// The conditions for which a `trap` action can be installed.
+///
+/// Only the signals whose numbers are fixed by POSIX are supported, along with
+/// the `EXIT` pseudo-signal (i.e. signal 0), which denotes the shell exiting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Signal {
+    /// The shell is exiting.
+    Exit,
+    /// Hangup (`SIGHUP`).
+    Hup,
+    /// Interrupt (`SIGINT`).
+    Int,
+    /// Quit (`SIGQUIT`).
+    Quit,
+    /// Abort (`SIGABRT`).
+    Abrt,
+    /// Alarm clock (`SIGALRM`).
+    Alrm,
+    /// Termination (`SIGTERM`).
+    Term,
+}
+
+impl Signal {
+    /// All supported signals, ordered by their numbers.
+    pub const ALL: &'static [Signal] = &[
+        Signal::Exit,
+        Signal::Hup,
+        Signal::Int,
+        Signal::Quit,
+        Signal::Abrt,
+        Signal::Alrm,
+        Signal::Term,
+    ];
+
+    /// The name of the signal, without any `SIG` prefix (e.g. `INT`).
+    pub fn name(self) -> &'static str {
+        match self {
+            Signal::Exit => "EXIT",
+            Signal::Hup => "HUP",
+            Signal::Int => "INT",
+            Signal::Quit => "QUIT",
+            Signal::Abrt => "ABRT",
+            Signal::Alrm => "ALRM",
+            Signal::Term => "TERM",
+        }
+    }
+
+    /// The number of the signal, as specified by POSIX.
+    pub fn number(self) -> u8 {
+        match self {
+            Signal::Exit => 0,
+            Signal::Hup => 1,
+            Signal::Int => 2,
+            Signal::Quit => 3,
+            Signal::Abrt => 6,
+            Signal::Alrm => 14,
+            Signal::Term => 15,
+        }
+    }
+}
+
+impl fmt::Display for Signal {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str(self.name())
+    }
+}
+
+/// An error for a string which does not denote a supported signal.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{0}: invalid signal specification")]
+pub struct UnknownSignalError(pub String);
+
+impl FromStr for Signal {
+    type Err = UnknownSignalError;
+
+    /// Parses a signal from its name (with or without a `SIG` prefix),
+    /// or from its number, e.g. `INT`, `SIGINT`, or `2`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.strip_prefix("SIG").unwrap_or(s);
+
+        Signal::ALL
+            .iter()
+            .copied()
+            .find(|sig| sig.name() == name || s.parse() == Ok(sig.number()))
+            .ok_or_else(|| UnknownSignalError(s.to_owned()))
+    }
+}
+
+/// The action to take when a signal is delivered to the shell.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum TrapAction {
+    /// Take the default action for the signal.
+    #[default]
+    Default,
+    /// Ignore the signal.
+    Ignore,
+    /// Run the specified shell commands.
+    Command(String),
+}
+
+/// A handle for delivering signals to an environment, e.g. from a task which
+/// listens for signals sent to the process.
+///
+/// Signals are only queued when they are sent; it is up to whoever is driving
+/// the environment (e.g. `Session`) to take any pending signals and run any
+/// actions which have been installed via `trap`.
+#[derive(Debug, Default, Clone)]
+pub struct SignalSender {
+    pending: Arc<Mutex<Vec<Signal>>>,
+}
+
+impl SignalSender {
+    /// Delivers a signal to the environment.
+    pub fn send(&self, signal: Signal) {
+        self.pending.lock().unwrap().push(signal);
+    }
+
+    fn take(&self) -> Vec<Signal> {
+        std::mem::take(&mut *self.pending.lock().unwrap())
+    }
+}
+
+impl PartialEq for SignalSender {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.pending, &other.pending)
+    }
+}
+
+impl Eq for SignalSender {}
+
+/// An interface for installing `trap` actions and tracking delivered signals.
+pub trait SignalEnvironment {
+    /// Get the action to take when a signal is delivered.
+    fn trap(&self, signal: Signal) -> &TrapAction;
+    /// Set the action to take when a signal is delivered.
+    fn set_trap(&mut self, signal: Signal, action: TrapAction);
+    /// Get a handle through which signals can be delivered to this environment.
+    fn signal_sender(&self) -> SignalSender;
+    /// Take all signals which have been delivered since this method was last
+    /// called, in the order in which they were delivered.
+    fn take_pending_signals(&mut self) -> Vec<Signal>;
+}
+
+impl<'a, T: ?Sized + SignalEnvironment> SignalEnvironment for &'a mut T {
+    fn trap(&self, signal: Signal) -> &TrapAction {
+        (**self).trap(signal)
+    }
+
+    fn set_trap(&mut self, signal: Signal, action: TrapAction) {
+        (**self).set_trap(signal, action)
+    }
+
+    fn signal_sender(&self) -> SignalSender {
+        (**self).signal_sender()
+    }
+
+    fn take_pending_signals(&mut self) -> Vec<Signal> {
+        (**self).take_pending_signals()
+    }
+}
+
+/// An implementation of `SignalEnvironment`.
+///
+/// As POSIX requires of subshells, any traps which are not ignored are reset
+/// to their default actions in sub-environments, and signals delivered to the
+/// parent are not observed by its sub-environments.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SignalEnv {
+    traps: BTreeMap<Signal, TrapAction>,
+    sender: SignalSender,
+}
+
+impl SignalEnv {
+    /// Create a new environment without any traps.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SignalEnvironment for SignalEnv {
+    fn trap(&self, signal: Signal) -> &TrapAction {
+        const DEFAULT: &TrapAction = &TrapAction::Default;
+        self.traps.get(&signal).unwrap_or(DEFAULT)
+    }
+
+    fn set_trap(&mut self, signal: Signal, action: TrapAction) {
+        match action {
+            TrapAction::Default => {
+                self.traps.remove(&signal);
+            }
+            action => {
+                self.traps.insert(signal, action);
+            }
+        }
+    }
+
+    fn signal_sender(&self) -> SignalSender {
+        self.sender.clone()
+    }
+
+    fn take_pending_signals(&mut self) -> Vec<Signal> {
+        self.sender.take()
+    }
+}
+
+impl SubEnvironment for SignalEnv {
+    fn sub_env(&self) -> Self {
+        let traps = self
+            .traps
+            .iter()
+            .filter(|&(_, action)| *action == TrapAction::Ignore)
+            .map(|(&signal, action)| (signal, action.clone()))
+            .collect();
+
+        Self {
+            traps,
+            sender: SignalSender::default(),
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use crate::env::{
+    AsyncIoEnvironment, BoxAsyncRead, ChangeWorkingDirectoryEnvironment, FileDescEnvironment,
+    LastStatusEnvironment, UnsetVariableEnvironment, VariableEnvironment,
+    WorkingDirectoryEnvironment,
+};
+use crate::io::Permissions;
+use crate::{ExitStatus, Fd};
+use futures_core::future::BoxFuture;
+use std::borrow::{Borrow, Cow};
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+/// A reduced, object-safe interface over the most commonly used parts of an
+/// environment: variables, file descriptors, the last exit status, and the
+/// current working directory.
+///
+/// Builtins and other extensions can be written against a
+/// `&mut dyn SimpleEnvironment` instead of repeating a large number of generic
+/// bounds, at the cost of working with concrete `String` variables. Any
+/// environment implementing the full set of respective traits can be adapted
+/// via `SimpleEnvAdapter`.
+///
+/// This interface is intentionally kept small so that it can remain stable even
+/// as the more granular environment traits evolve.
+pub trait SimpleEnvironment {
+    /// Get the value of some variable.
+    fn var(&self, name: &str) -> Option<&str>;
+    /// Set the value of some variable, maintaining its status as an
+    /// environment variable if previously set as such.
+    fn set_var(&mut self, name: String, val: String);
+    /// Unset the value of some variable (including environment variables).
+    fn unset_var(&mut self, name: &str);
+    /// Get all current pairs of environment variables and their values.
+    fn env_vars(&self) -> Vec<(&str, &str)>;
+
+    /// Get the exit status of the previous command.
+    fn last_status(&self) -> ExitStatus;
+    /// Set the exit status of the previously run command.
+    fn set_last_status(&mut self, status: ExitStatus);
+
+    /// Retrieves the current working directory of this environment.
+    fn current_working_dir(&self) -> &Path;
+    /// Changes the environment's current working directory to the following path,
+    /// which may be relative to the current working directory.
+    fn change_working_dir(&mut self, path: &Path) -> io::Result<()>;
+
+    /// Get the permissions of a file descriptor, if it is open.
+    fn file_desc_permissions(&self, fd: Fd) -> Option<Permissions>;
+    /// Closes a file descriptor, if it is open.
+    fn close_file_desc(&mut self, fd: Fd);
+    /// Begins reading from a readable file descriptor, as if
+    /// by `AsyncIoEnvironment::read_async`.
+    fn read_async(&mut self, fd: Fd) -> io::Result<BoxAsyncRead>;
+    /// Asynchronously write `data` into a writable file descriptor.
+    fn write_all(&mut self, fd: Fd, data: Vec<u8>) -> BoxFuture<'static, io::Result<()>>;
+}
+
+impl<'a, T: ?Sized + SimpleEnvironment> SimpleEnvironment for &'a mut T {
+    fn var(&self, name: &str) -> Option<&str> {
+        (**self).var(name)
+    }
+
+    fn set_var(&mut self, name: String, val: String) {
+        (**self).set_var(name, val)
+    }
+
+    fn unset_var(&mut self, name: &str) {
+        (**self).unset_var(name)
+    }
+
+    fn env_vars(&self) -> Vec<(&str, &str)> {
+        (**self).env_vars()
+    }
+
+    fn last_status(&self) -> ExitStatus {
+        (**self).last_status()
+    }
+
+    fn set_last_status(&mut self, status: ExitStatus) {
+        (**self).set_last_status(status)
+    }
+
+    fn current_working_dir(&self) -> &Path {
+        (**self).current_working_dir()
+    }
+
+    fn change_working_dir(&mut self, path: &Path) -> io::Result<()> {
+        (**self).change_working_dir(path)
+    }
+
+    fn file_desc_permissions(&self, fd: Fd) -> Option<Permissions> {
+        (**self).file_desc_permissions(fd)
+    }
+
+    fn close_file_desc(&mut self, fd: Fd) {
+        (**self).close_file_desc(fd)
+    }
+
+    fn read_async(&mut self, fd: Fd) -> io::Result<BoxAsyncRead> {
+        (**self).read_async(fd)
+    }
+
+    fn write_all(&mut self, fd: Fd, data: Vec<u8>) -> BoxFuture<'static, io::Result<()>> {
+        (**self).write_all(fd, data)
+    }
+}
+
+/// Adapts a full environment into a `SimpleEnvironment`.
+pub struct SimpleEnvAdapter<'a, E: ?Sized> {
+    env: &'a mut E,
+}
+
+impl<'a, E: ?Sized> SimpleEnvAdapter<'a, E> {
+    /// Wraps the provided environment.
+    pub fn new(env: &'a mut E) -> Self {
+        Self { env }
+    }
+}
+
+impl<'a, E: ?Sized> fmt::Debug for SimpleEnvAdapter<'a, E> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct(stringify!(SimpleEnvAdapter)).finish()
+    }
+}
+
+impl<'a, E> SimpleEnvAdapter<'a, E>
+where
+    E: ?Sized + FileDescEnvironment,
+    E::FileHandle: Clone,
+{
+    fn handle(&self, fd: Fd, needs: fn(Permissions) -> bool) -> io::Result<E::FileHandle> {
+        match self.env.file_desc(fd) {
+            Some((handle, perms)) if needs(perms) => Ok(handle.clone()),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{}: bad file descriptor", fd),
+            )),
+        }
+    }
+}
+
+impl<'a, E> SimpleEnvironment for SimpleEnvAdapter<'a, E>
+where
+    E: ?Sized
+        + AsyncIoEnvironment
+        + ChangeWorkingDirectoryEnvironment
+        + FileDescEnvironment
+        + LastStatusEnvironment
+        + UnsetVariableEnvironment
+        + VariableEnvironment
+        + WorkingDirectoryEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+    E::VarName: Borrow<String> + From<String>,
+    E::Var: Borrow<String> + From<String>,
+{
+    fn var(&self, name: &str) -> Option<&str> {
+        self.env
+            .var(&name.to_owned())
+            .map(|val| val.borrow().as_str())
+    }
+
+    fn set_var(&mut self, name: String, val: String) {
+        self.env.set_var(name.into(), val.into())
+    }
+
+    fn unset_var(&mut self, name: &str) {
+        self.env.unset_var(&name.to_owned().into())
+    }
+
+    fn env_vars(&self) -> Vec<(&str, &str)> {
+        self.env
+            .env_vars()
+            .iter()
+            .map(|&(name, val)| (name.borrow().as_str(), val.borrow().as_str()))
+            .collect()
+    }
+
+    fn last_status(&self) -> ExitStatus {
+        self.env.last_status()
+    }
+
+    fn set_last_status(&mut self, status: ExitStatus) {
+        self.env.set_last_status(status)
+    }
+
+    fn current_working_dir(&self) -> &Path {
+        self.env.current_working_dir()
+    }
+
+    fn change_working_dir(&mut self, path: &Path) -> io::Result<()> {
+        self.env.change_working_dir(Cow::Borrowed(path))
+    }
+
+    fn file_desc_permissions(&self, fd: Fd) -> Option<Permissions> {
+        self.env.file_desc(fd).map(|(_, perms)| perms)
+    }
+
+    fn close_file_desc(&mut self, fd: Fd) {
+        self.env.close_file_desc(fd)
+    }
+
+    fn read_async(&mut self, fd: Fd) -> io::Result<BoxAsyncRead> {
+        let handle = self.handle(fd, Permissions::readable)?;
+        self.env.read_async(handle.into())
+    }
+
+    fn write_all(&mut self, fd: Fd, data: Vec<u8>) -> BoxFuture<'static, io::Result<()>> {
+        match self.handle(fd, Permissions::writable) {
+            Ok(handle) => self.env.write_all(handle.into(), Cow::Owned(data)),
+            Err(e) => Box::pin(async move { Err(e) }),
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use crate::env::ExecutableData;
+use crate::io::Pipe;
+use std::io::{self, Read};
+use std::os::unix::io::AsRawFd;
+use std::os::unix::process::CommandExt;
+use std::process::Command;
+use std::sync::Arc;
+
+/// A function which installs a policy (e.g. seccomp filters or Landlock rules)
+/// within a spawned child process, right before it executes the command.
+///
+/// Since the function runs after the child process has been forked, it must
+/// only use async-signal-safe operations (in particular, it must not allocate
+/// memory or acquire any locks), and it should instead do any such work while
+/// it is being prepared via `SpawnPolicy::prepare`.
+pub type PolicyInstaller = Box<dyn FnMut() -> io::Result<()> + Send + Sync>;
+
+/// An interface for attaching a policy to every executable spawned by a
+/// `TokioExecEnv`, giving sandboxing embedders a single place to restrict
+/// anything the shell executes.
+///
+/// Any failures to prepare or install the policy will fail to spawn the
+/// executable with a `CommandError::SpawnPolicy` error.
+pub trait SpawnPolicy {
+    /// Prepare the policy for an executable which is about to be spawned.
+    fn prepare(&self, data: &ExecutableData<'_>) -> io::Result<PolicyInstaller>;
+}
+
+impl<'a, T: ?Sized + SpawnPolicy> SpawnPolicy for &'a T {
+    fn prepare(&self, data: &ExecutableData<'_>) -> io::Result<PolicyInstaller> {
+        (**self).prepare(data)
+    }
+}
+
+impl<T: ?Sized + SpawnPolicy> SpawnPolicy for Arc<T> {
+    fn prepare(&self, data: &ExecutableData<'_>) -> io::Result<PolicyInstaller> {
+        (**self).prepare(data)
+    }
+}
+
+/// A `SpawnPolicy` which prevents spawned executables (and all of their
+/// descendants) from gaining any privileges they did not start with, e.g.
+/// by executing setuid binaries (via `PR_SET_NO_NEW_PRIVS`).
+#[cfg(target_os = "linux")]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct NoNewPrivilegesPolicy;
+
+#[cfg(target_os = "linux")]
+impl SpawnPolicy for NoNewPrivilegesPolicy {
+    fn prepare(&self, _data: &ExecutableData<'_>) -> io::Result<PolicyInstaller> {
+        Ok(Box::new(|| {
+            let ret = unsafe { libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) };
+            if ret == 0 {
+                Ok(())
+            } else {
+                Err(io::Error::last_os_error())
+            }
+        }))
+    }
+}
+
+/// Tracks whether the installation of a policy has failed within the child.
+///
+/// The standard library only reports the OS error code of a child which
+/// failed to spawn, so the child additionally signals any installation
+/// failures over a (close-on-exec) pipe in order to distinguish them
+/// from failures to execute the command itself.
+pub(crate) struct PolicyFailures {
+    pipe: Pipe,
+}
+
+impl PolicyFailures {
+    /// Registers the installer as a pre-exec hook on the command.
+    pub(crate) fn install(cmd: &mut Command, mut installer: PolicyInstaller) -> io::Result<Self> {
+        let pipe = Pipe::new()?;
+        let writer = pipe.writer.as_raw_fd();
+
+        let pre_exec = move || {
+            installer().inspect_err(|_| {
+                let byte = [1u8];
+                unsafe {
+                    libc::write(writer, byte.as_ptr().cast(), byte.len());
+                }
+            })
+        };
+
+        // Safety: the installer is documented as being required to be
+        // async-signal-safe, and writing to a pipe is as well.
+        unsafe {
+            cmd.pre_exec(pre_exec);
+        }
+
+        Ok(Self { pipe })
+    }
+
+    /// Checks if the child failed to install the policy, assuming
+    /// it has already failed to spawn.
+    pub(crate) fn has_failed(self) -> bool {
+        let Pipe { mut reader, writer } = self.pipe;
+        drop(writer);
+
+        let mut buf = [0];
+        matches!(reader.read(&mut buf), Ok(1))
+    }
+}
@@ -0,0 +1,160 @@
+use crate::env::VariableEnvironment;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// A counter of how many times the values of tainted variables have been
+/// looked up through an environment, which allows determining whether an
+/// evaluation was (conservatively) derived from any tainted input.
+///
+/// Each environment owns its own tracker, so that evaluations made by other
+/// environments (e.g. concurrently running jobs) cannot affect its count.
+/// A tracker may be nested within another one (e.g. that of a sub-environment
+/// within its parent's), in which case any expansions it records are also
+/// recorded by the outer tracker (and any trackers it is nested within), so that
+/// tainted variables expanded within a command substitution also taint the
+/// outer expansion.
+///
+/// A tracker may also be marked as tainted, in which case everything evaluated
+/// with it is considered tainted, regardless of which variables are expanded
+/// (e.g. while sourcing a script whose name was derived from tainted input).
+///
+/// Clones of a tracker share the same count.
+#[derive(Debug, Clone)]
+pub struct TaintTracker {
+    /// The count of this tracker, followed by those of any outer trackers.
+    expansions: Vec<Arc<AtomicUsize>>,
+    tainted: bool,
+}
+
+impl TaintTracker {
+    /// Creates a new tracker which has not recorded any expansions.
+    pub fn new() -> Self {
+        Self {
+            expansions: vec![Arc::new(AtomicUsize::new(0))],
+            tainted: false,
+        }
+    }
+
+    /// Creates a new tracker which has not recorded any expansions, but whose
+    /// recorded expansions are also recorded by this one.
+    ///
+    /// The new tracker is tainted if this one is.
+    pub fn nested(&self) -> Self {
+        let mut expansions = Vec::with_capacity(self.expansions.len() + 1);
+        expansions.push(Arc::new(AtomicUsize::new(0)));
+        expansions.extend(self.expansions.iter().cloned());
+
+        Self {
+            expansions,
+            tainted: self.tainted,
+        }
+    }
+
+    /// Creates a new tracker which is nested within this one (see `nested`),
+    /// and considers everything evaluated with it as tainted.
+    pub fn nested_tainted(&self) -> Self {
+        Self {
+            tainted: true,
+            ..self.nested()
+        }
+    }
+
+    /// Creates a new tracker which is tainted if this one is, but which is
+    /// otherwise independent of it, e.g. for an environment which is evaluated
+    /// concurrently with this tracker's (such as that of a background job).
+    pub fn detached(&self) -> Self {
+        Self {
+            tainted: self.tainted,
+            ..Self::new()
+        }
+    }
+
+    /// Records that the value of a tainted variable has been looked up.
+    pub fn record_expansion(&self) {
+        for expansions in &self.expansions {
+            expansions.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns the total number of expansions of tainted variables recorded so far.
+    ///
+    /// Comparing the count before and after some evaluation indicates whether
+    /// any tainted variables were expanded in the meantime.
+    pub fn expansions(&self) -> usize {
+        self.expansions[0].load(Ordering::Relaxed)
+    }
+
+    /// Checks if everything evaluated with this tracker is considered tainted.
+    pub fn is_tainted(&self) -> bool {
+        self.tainted
+    }
+
+    /// Checks if an evaluation which started when the tracker's count was
+    /// `before` should be considered tainted.
+    pub fn is_tainted_since(&self, before: usize) -> bool {
+        self.tainted || self.expansions() != before
+    }
+}
+
+impl Default for TaintTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PartialEq for TaintTracker {
+    fn eq(&self, other: &Self) -> bool {
+        self.tainted == other.tainted && Arc::ptr_eq(&self.expansions[0], &other.expansions[0])
+    }
+}
+
+impl Eq for TaintTracker {}
+
+/// An interface for marking variables as tainted (e.g. because they hold
+/// untrusted input), and tracking whenever their values are expanded.
+///
+/// Any values derived from tainted variables are conservatively considered
+/// tainted themselves, thus embedders can detect whenever untrusted input
+/// reaches a command line via `ExecutableData::is_tainted`. Namely:
+///
+/// * assigning a value derived from tainted variables (e.g. `y=$UNTRUSTED`)
+///   marks the assigned variable as tainted,
+/// * any builtin whose words or redirects were derived from tainted variables
+///   runs with a tainted tracker (see `TaintTracker::nested_tainted`), thus any
+///   variables assigned by `read`, and anything executed by `.` (e.g.
+///   `. "$UNTRUSTED"`), are considered tainted.
+///
+/// Note that variables remain tainted until they are unset (even if they are
+/// reassigned), and that taint is not tracked through anything but variables,
+/// e.g. through positional parameters, or data written to (and later read from)
+/// files or pipes.
+pub trait TaintEnvironment: VariableEnvironment {
+    /// Checks if a variable has been marked as tainted.
+    fn is_tainted(&self, name: &Self::VarName) -> bool;
+    /// Marks (or unmarks) a variable as tainted, regardless of its current value.
+    fn set_tainted(&mut self, name: Self::VarName, tainted: bool);
+    /// Returns the tracker which records any expansions of tainted variables
+    /// made through this environment.
+    fn taint_tracker(&self) -> &TaintTracker;
+    /// Changes the tracker which records any expansions of tainted variables,
+    /// returning the previous one so that it can be restored later.
+    fn set_taint_tracker(&mut self, tracker: TaintTracker) -> TaintTracker;
+}
+
+impl<'a, T: ?Sized + TaintEnvironment> TaintEnvironment for &'a mut T {
+    fn is_tainted(&self, name: &Self::VarName) -> bool {
+        (**self).is_tainted(name)
+    }
+
+    fn set_tainted(&mut self, name: Self::VarName, tainted: bool) {
+        (**self).set_tainted(name, tainted)
+    }
+
+    fn taint_tracker(&self) -> &TaintTracker {
+        (**self).taint_tracker()
+    }
+
+    fn set_taint_tracker(&mut self, tracker: TaintTracker) -> TaintTracker {
+        (**self).set_taint_tracker(tracker)
+    }
+}
@@ -0,0 +1,75 @@
+use crate::env::VariableEnvironment;
+use crate::io::{TerminalMode, WindowSize};
+use crate::Fd;
+use std::io;
+
+lazy_static::lazy_static! {
+    static ref COLUMNS: String = String::from("COLUMNS");
+    static ref LINES: String = String::from("LINES");
+}
+
+/// An interface for querying and manipulating any terminals
+/// which are attached to shell file descriptors.
+pub trait TerminalEnvironment {
+    /// Indicates if the specified file descriptor is open and refers to a terminal.
+    fn is_terminal(&self, fd: Fd) -> bool;
+
+    /// Queries the current mode of the terminal attached to a file descriptor.
+    fn terminal_mode(&self, fd: Fd) -> io::Result<TerminalMode>;
+
+    /// Changes the mode of the terminal attached to a file descriptor.
+    ///
+    /// Note that a terminal's mode is shared by all processes using it,
+    /// thus callers are responsible for restoring the original mode
+    /// once they are done (e.g. when `read -s` finishes).
+    fn set_terminal_mode(&mut self, fd: Fd, mode: TerminalMode) -> io::Result<()>;
+
+    /// Queries the window size of the terminal attached to a file descriptor.
+    fn window_size(&self, fd: Fd) -> io::Result<WindowSize>;
+}
+
+impl<'a, T: ?Sized + TerminalEnvironment> TerminalEnvironment for &'a mut T {
+    fn is_terminal(&self, fd: Fd) -> bool {
+        (**self).is_terminal(fd)
+    }
+
+    fn terminal_mode(&self, fd: Fd) -> io::Result<TerminalMode> {
+        (**self).terminal_mode(fd)
+    }
+
+    fn set_terminal_mode(&mut self, fd: Fd, mode: TerminalMode) -> io::Result<()> {
+        (**self).set_terminal_mode(fd, mode)
+    }
+
+    fn window_size(&self, fd: Fd) -> io::Result<WindowSize> {
+        (**self).window_size(fd)
+    }
+}
+
+/// Queries the window size of the terminal attached to `fd` and updates
+/// the `$COLUMNS` and `$LINES` variables to match it.
+///
+/// Since terminal windows can be resized at any time, these variables are
+/// "dynamic" and should be refreshed whenever the caller is about to rely on
+/// them, e.g. before printing a prompt or after a foreground command exits.
+/// If the window size cannot be determined, the variables are left untouched.
+pub fn update_window_size_vars<E>(env: &mut E, fd: Fd) -> io::Result<WindowSize>
+where
+    E: ?Sized + TerminalEnvironment + VariableEnvironment,
+    E::VarName: From<String>,
+    E::Var: From<String>,
+{
+    let size = env.window_size(fd)?;
+
+    env.set_var(COLUMNS.clone().into(), size.columns.to_string().into());
+    env.set_var(LINES.clone().into(), size.lines.to_string().into());
+
+    Ok(size)
+}
+
+pub(crate) fn not_a_terminal(fd: Fd) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Other,
+        format!("file descriptor {} is not a terminal", fd),
+    )
+}
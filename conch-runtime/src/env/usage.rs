@@ -0,0 +1,125 @@
+use crate::env::ResourceUsage;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// A shared accumulator of the resources used by any executables which have
+/// exited, e.g. for reporting the usage of a background job or a timed command.
+///
+/// Clones of a tracker accumulate into the same total. A tracker may also be
+/// nested within another one, in which case any usage it records is also
+/// recorded by the outer tracker (and any trackers it is nested within).
+#[derive(Debug, Clone)]
+pub struct ResourceUsageTracker {
+    /// The total of this tracker, followed by those of any outer trackers.
+    totals: Vec<Arc<Mutex<ResourceUsage>>>,
+}
+
+impl ResourceUsageTracker {
+    /// Creates a new tracker which has not recorded any usage.
+    pub fn new() -> Self {
+        Self {
+            totals: vec![Arc::new(Mutex::new(ResourceUsage::default()))],
+        }
+    }
+
+    /// Creates a new tracker which has not recorded any usage, but
+    /// whose recorded usage is also recorded by this one.
+    pub fn nested(&self) -> Self {
+        let mut totals = Vec::with_capacity(self.totals.len() + 1);
+        totals.push(Arc::new(Mutex::new(ResourceUsage::default())));
+        totals.extend(self.totals.iter().cloned());
+
+        Self { totals }
+    }
+
+    /// Records the resources used by an executable which has exited.
+    ///
+    /// The times are added to the total, while the maximum resident set
+    /// size becomes the largest one recorded so far.
+    pub fn record(&self, usage: ResourceUsage) {
+        for total in &self.totals {
+            let mut total = total.lock().unwrap_or_else(PoisonError::into_inner);
+            total.max_rss = total.max_rss.max(usage.max_rss);
+            total.user_time += usage.user_time;
+            total.system_time += usage.system_time;
+        }
+    }
+
+    /// Returns the total usage recorded so far.
+    pub fn usage(&self) -> ResourceUsage {
+        *self.totals[0]
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Default for ResourceUsageTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PartialEq for ResourceUsageTracker {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.totals[0], &other.totals[0])
+    }
+}
+
+impl Eq for ResourceUsageTracker {}
+
+/// An interface for accessing the tracker which accumulates the resources
+/// used by any executables spawned by an environment, if any.
+///
+/// Resource usage is only collected (see `ExecutableEnvironment::spawn_executable_with_usage`)
+/// while a tracker (or an `ExecutionReporter`) is installed.
+pub trait ResourceUsageEnvironment {
+    /// Get the tracker which should record the usage of any newly spawned executables, if any.
+    fn usage_tracker(&self) -> Option<&ResourceUsageTracker>;
+
+    /// Change the tracker which should record the usage of any newly spawned
+    /// executables, returning the previous one so that it can be restored later.
+    fn set_usage_tracker(
+        &mut self,
+        tracker: Option<ResourceUsageTracker>,
+    ) -> Option<ResourceUsageTracker>;
+}
+
+impl<'a, T: ?Sized + ResourceUsageEnvironment> ResourceUsageEnvironment for &'a mut T {
+    fn usage_tracker(&self) -> Option<&ResourceUsageTracker> {
+        (**self).usage_tracker()
+    }
+
+    fn set_usage_tracker(
+        &mut self,
+        tracker: Option<ResourceUsageTracker>,
+    ) -> Option<ResourceUsageTracker> {
+        (**self).set_usage_tracker(tracker)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn usage(max_rss: u64, secs: u64) -> ResourceUsage {
+        ResourceUsage {
+            max_rss,
+            user_time: Duration::from_secs(secs),
+            system_time: Duration::from_secs(secs * 2),
+        }
+    }
+
+    #[test]
+    fn test_nested_usage_is_recorded_by_outer_trackers() {
+        let outer = ResourceUsageTracker::new();
+        let inner = outer.nested();
+
+        outer.record(usage(10, 1));
+        inner.record(usage(5, 2));
+
+        assert_eq!(inner.usage(), usage(5, 2));
+        assert_eq!(outer.usage(), usage(10, 3));
+        assert_eq!(inner.clone(), inner);
+        assert_ne!(inner, outer);
+    }
+}
@@ -1,10 +1,16 @@
-use crate::env::SubEnvironment;
+use crate::env::{HomeDirEnvironment, SubEnvironment, TaintEnvironment, TaintTracker};
+use once_cell::sync::OnceCell;
 use std::borrow::{Borrow, Cow};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::hash::Hash;
+use std::mem;
 use std::sync::Arc;
 
+lazy_static::lazy_static! {
+    static ref HOME: String = String::from("HOME");
+}
+
 /// An interface for setting and getting shell and environment variables.
 pub trait VariableEnvironment {
     /// The type of the name this environment associates for a variable.
@@ -20,6 +26,19 @@ pub trait VariableEnvironment {
     /// Set the value of some variable, maintaining its status as an
     /// environment variable if previously set as such.
     fn set_var(&mut self, name: Self::VarName, val: Self::Var);
+    /// Set the values of several variables at once, maintaining their status
+    /// as environment variables if previously set as such.
+    ///
+    /// Implementations may override this to perform the updates more
+    /// efficiently than setting each variable one at a time.
+    fn set_vars<I>(&mut self, vars: I)
+    where
+        I: IntoIterator<Item = (Self::VarName, Self::Var)>,
+    {
+        for (name, val) in vars {
+            self.set_var(name, val);
+        }
+    }
     /// Unset the value of some variable (including environment variables).
     /// Get all current pairs of environment variables and their values.
     fn env_vars(&self) -> Cow<'_, [(&Self::VarName, &Self::Var)]>;
@@ -41,6 +60,13 @@ impl<'a, T: ?Sized + VariableEnvironment> VariableEnvironment for &'a mut T {
         (**self).set_var(name, val);
     }
 
+    fn set_vars<I>(&mut self, vars: I)
+    where
+        I: IntoIterator<Item = (Self::VarName, Self::Var)>,
+    {
+        (**self).set_vars(vars);
+    }
+
     fn env_vars(&self) -> Cow<'_, [(&Self::VarName, &Self::Var)]> {
         (**self).env_vars()
     }
@@ -54,6 +80,18 @@ pub trait ExportedVariableEnvironment: VariableEnvironment {
     fn exported_var(&self, name: &Self::VarName) -> Option<(&Self::Var, bool)>;
     /// Set the value of some variable, and set it's exported status as specified.
     fn set_exported_var(&mut self, name: Self::VarName, val: Self::Var, exported: bool);
+    /// Set the values of several variables at once, along with their exported status.
+    ///
+    /// Implementations may override this to perform the updates more
+    /// efficiently than setting each variable one at a time.
+    fn extend_exported<I>(&mut self, vars: I)
+    where
+        I: IntoIterator<Item = (Self::VarName, Self::Var, bool)>,
+    {
+        for (name, val, exported) in vars {
+            self.set_exported_var(name, val, exported);
+        }
+    }
 }
 
 impl<'a, T: ?Sized + ExportedVariableEnvironment> ExportedVariableEnvironment for &'a mut T {
@@ -64,6 +102,13 @@ impl<'a, T: ?Sized + ExportedVariableEnvironment> ExportedVariableEnvironment fo
     fn set_exported_var(&mut self, name: Self::VarName, val: Self::Var, exported: bool) {
         (**self).set_exported_var(name, val, exported)
     }
+
+    fn extend_exported<I>(&mut self, vars: I)
+    where
+        I: IntoIterator<Item = (Self::VarName, Self::Var, bool)>,
+    {
+        (**self).extend_exported(vars)
+    }
 }
 
 /// An interface for unsetting shell and envrironment variables.
@@ -78,13 +123,147 @@ impl<'a, T: ?Sized + UnsetVariableEnvironment> UnsetVariableEnvironment for &'a
     }
 }
 
+/// An interface for marking variables as read-only.
+///
+/// Attempting to set or unset a read-only variable has no effect, thus it is up
+/// to any callers (e.g. the `unset` builtin) to report such attempts as errors.
+pub trait ReadonlyVariableEnvironment: VariableEnvironment {
+    /// Checks if a variable has been marked as read-only.
+    fn is_readonly(&self, name: &Self::VarName) -> bool;
+    /// Marks a variable as read-only, after which its value can no longer change.
+    fn set_readonly(&mut self, name: Self::VarName);
+}
+
+impl<'a, T: ?Sized + ReadonlyVariableEnvironment> ReadonlyVariableEnvironment for &'a mut T {
+    fn is_readonly(&self, name: &Self::VarName) -> bool {
+        (**self).is_readonly(name)
+    }
+
+    fn set_readonly(&mut self, name: Self::VarName) {
+        (**self).set_readonly(name)
+    }
+}
+
+/// An interface for reading and writing variables as integers, such as
+/// during arithmetic expansions (e.g. `$(( i += 1 ))`).
+///
+/// Implementations may cache the numeric values of variables so that repeated
+/// arithmetic on them does not need to parse and format their values each time,
+/// so long as the (string) values returned by `VariableEnvironment` agree.
+pub trait ArithVariableEnvironment: VariableEnvironment {
+    /// Get the numeric value of some variable, where any variables which are
+    /// unset or do not hold a valid number evaluate to 0.
+    fn arith_var<Q: ?Sized>(&self, name: &Q) -> isize
+    where
+        Self::VarName: Borrow<Q>,
+        Q: Hash + Eq;
+    /// Set the value of some variable to a number, maintaining its status as
+    /// an environment variable if previously set as such.
+    fn set_arith_var(&mut self, name: Self::VarName, val: isize);
+}
+
+impl<'a, T: ?Sized + ArithVariableEnvironment> ArithVariableEnvironment for &'a mut T {
+    fn arith_var<Q: ?Sized>(&self, name: &Q) -> isize
+    where
+        Self::VarName: Borrow<Q>,
+        Q: Hash + Eq,
+    {
+        (**self).arith_var(name)
+    }
+
+    fn set_arith_var(&mut self, name: Self::VarName, val: isize) {
+        (**self).set_arith_var(name, val);
+    }
+}
+
+/// An interface for registering callbacks which are notified of any variable changes.
+pub trait WatchVariableEnvironment: VariableEnvironment {
+    /// Registers a callback which will be notified whenever a variable is
+    /// set, unset, or has its exported status changed.
+    fn watch<F>(&mut self, watcher: F)
+    where
+        F: Fn(VarChange<'_, Self::VarName, Self::Var>) + Send + Sync + 'static;
+}
+
+impl<'a, T: ?Sized + WatchVariableEnvironment> WatchVariableEnvironment for &'a mut T {
+    fn watch<F>(&mut self, watcher: F)
+    where
+        F: Fn(VarChange<'_, Self::VarName, Self::Var>) + Send + Sync + 'static,
+    {
+        (**self).watch(watcher)
+    }
+}
+
+/// A change to a variable which is reported to any `VarEnv` watchers.
+#[derive(Debug, PartialEq, Eq)]
+pub enum VarChange<'a, N, V> {
+    /// A variable was set to a new value, or its exported status has changed.
+    Set {
+        /// The name of the variable.
+        name: &'a N,
+        /// The new value of the variable.
+        value: &'a V,
+        /// Indicates if the variable is exported to other commands.
+        exported: bool,
+    },
+    /// A previously set variable was unset.
+    Unset {
+        /// The name of the variable.
+        name: &'a N,
+    },
+}
+
+impl<'a, N, V> Copy for VarChange<'a, N, V> {}
+
+impl<'a, N, V> Clone for VarChange<'a, N, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+type VarWatcher<N, V> = Arc<dyn Fn(VarChange<'_, N, V>) + Send + Sync>;
+
+/// The value of a variable held by a `VarEnv`.
+#[derive(Clone)]
+enum VarValue<V> {
+    String(V),
+    /// A value set via `set_arith_var`, whose string form is only built
+    /// once it is first read.
+    Number(isize, OnceCell<V>, fn(isize) -> V),
+}
+
+impl<V> VarValue<V> {
+    fn get(&self) -> &V {
+        match *self {
+            VarValue::String(ref val) => val,
+            VarValue::Number(val, ref string, to_string) => string.get_or_init(|| to_string(val)),
+        }
+    }
+}
+
+impl<V: PartialEq> PartialEq for VarValue<V> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (VarValue::Number(a, ..), VarValue::Number(b, ..)) => a == b,
+            _ => self.get() == other.get(),
+        }
+    }
+}
+
 /// An environment module for setting, getting, and exporting shell variables.
-#[derive(PartialEq, Eq)]
 pub struct VarEnv<N: Eq + Hash, V> {
     /// A mapping of variable names to their values.
     ///
     /// The tupled boolean indicates if a variable should be exported to other commands.
-    vars: Arc<HashMap<N, (V, bool)>>,
+    vars: Arc<HashMap<N, (VarValue<V>, bool)>>,
+    /// Callbacks to be notified whenever a variable is changed.
+    watchers: Vec<VarWatcher<N, V>>,
+    /// The names of any variables which have been marked as tainted.
+    tainted: Arc<HashSet<N>>,
+    /// The names of any variables which have been marked as read-only.
+    readonly: Arc<HashSet<N>>,
+    /// Records any lookups of tainted variables.
+    taint_tracker: TaintTracker,
 }
 
 impl<N, V> VarEnv<N, V>
@@ -95,6 +274,10 @@ where
     pub fn new() -> Self {
         Self {
             vars: Arc::new(HashMap::new()),
+            watchers: Vec::new(),
+            tainted: Arc::new(HashSet::new()),
+            readonly: Arc::new(HashSet::new()),
+            taint_tracker: TaintTracker::new(),
         }
     }
 
@@ -115,9 +298,101 @@ where
         Self {
             vars: Arc::new(
                 iter.into_iter()
-                    .map(|(k, v)| (k, (v, true)))
+                    .map(|(k, v)| (k, (VarValue::String(v), true)))
                     .collect::<HashMap<_, _>>(),
             ),
+            watchers: Vec::new(),
+            tainted: Arc::new(HashSet::new()),
+            readonly: Arc::new(HashSet::new()),
+            taint_tracker: TaintTracker::new(),
+        }
+    }
+
+    /// Sets all provided variables, where an `exported` status of `None`
+    /// maintains the variable's current status (or defaults to not exported).
+    ///
+    /// The underlying map is only cloned (if shared) if any variable
+    /// actually changes, and at most once for the entire batch.
+    fn insert_all<I>(&mut self, vars: I)
+    where
+        I: IntoIterator<Item = (N, V, Option<bool>)>,
+        N: Clone,
+        V: Eq + Clone,
+    {
+        let vars = vars.into_iter();
+        let (lower, _) = vars.size_hint();
+        let mut reserved = false;
+
+        for (name, val, exported) in vars {
+            if !self.readonly.is_empty() && self.readonly.contains(&name) {
+                continue;
+            }
+
+            let exported = match self.vars.get(&name) {
+                Some(&(ref existing_val, existing_exported)) => {
+                    let exported = exported.unwrap_or(existing_exported);
+                    if *existing_val.get() == val && exported == existing_exported {
+                        continue;
+                    }
+                    exported
+                }
+                None => exported.unwrap_or(false),
+            };
+
+            if !reserved {
+                Arc::make_mut(&mut self.vars).reserve(lower);
+                reserved = true;
+            }
+
+            self.insert(name, VarValue::String(val), exported);
+        }
+    }
+
+    /// Registers a callback which will be notified whenever a variable is
+    /// set, unset, or has its exported status changed (but not when a variable
+    /// is set to the value it already has).
+    ///
+    /// This allows embedders to mirror the shell's state (e.g. `$PWD`) into
+    /// their application without needing to poll for changes. Watchers are
+    /// shared with any clones of this environment, but are *not* inherited
+    /// by sub-environments, since changes there are not visible to the parent.
+    ///
+    /// Note that watchers are invoked synchronously while the environment is
+    /// being modified, so they should avoid doing any expensive work (e.g. by
+    /// forwarding changes over a channel instead).
+    pub fn watch<F>(&mut self, watcher: F)
+    where
+        F: Fn(VarChange<'_, N, V>) + Send + Sync + 'static,
+    {
+        self.watchers.push(Arc::new(watcher));
+    }
+
+    /// Removes all callbacks previously registered via `watch`.
+    pub fn clear_watchers(&mut self) {
+        self.watchers.clear();
+    }
+
+    fn notify(&self, change: VarChange<'_, N, V>) {
+        for watcher in &self.watchers {
+            watcher(change);
+        }
+    }
+
+    fn insert(&mut self, name: N, val: VarValue<V>, exported: bool)
+    where
+        N: Clone,
+        V: Clone,
+    {
+        Arc::make_mut(&mut self.vars).insert(name.clone(), (val, exported));
+
+        if !self.watchers.is_empty() {
+            if let Some((value, exported)) = self.vars.get(&name) {
+                self.notify(VarChange::Set {
+                    name: &name,
+                    value: value.get(),
+                    exported: *exported,
+                });
+            }
         }
     }
 }
@@ -135,25 +410,39 @@ where
         Self::VarName: Borrow<Q>,
         Q: Hash + Eq,
     {
-        self.vars.get(name).map(|&(ref val, _)| val)
+        let ret = self.vars.get(name).map(|&(ref val, _)| val.get());
+
+        if ret.is_some() && !self.tainted.is_empty() && self.tainted.contains(name) {
+            self.taint_tracker.record_expansion();
+        }
+
+        ret
     }
 
     fn set_var(&mut self, name: Self::VarName, val: Self::Var) {
-        let (needs_insert, exported) = match self.vars.get(&name) {
-            Some(&(ref existing_val, exported)) => (&val != existing_val, exported),
-            None => (true, false),
-        };
+        self.insert_all(Some((name, val, None)));
+    }
 
-        if needs_insert {
-            Arc::make_mut(&mut self.vars).insert(name, (val, exported));
-        }
+    fn set_vars<I>(&mut self, vars: I)
+    where
+        I: IntoIterator<Item = (Self::VarName, Self::Var)>,
+    {
+        self.insert_all(vars.into_iter().map(|(name, val)| (name, val, None)));
     }
 
     fn env_vars(&self) -> Cow<'_, [(&Self::VarName, &Self::Var)]> {
         let ret: Vec<_> = self
             .vars
             .iter()
-            .filter_map(|(k, &(ref v, exported))| if exported { Some((k, v)) } else { None })
+            .filter_map(
+                |(k, &(ref v, exported))| {
+                    if exported {
+                        Some((k, v.get()))
+                    } else {
+                        None
+                    }
+                },
+            )
             .collect();
 
         Cow::Owned(ret)
@@ -168,19 +457,70 @@ where
     fn exported_var(&self, name: &Self::VarName) -> Option<(&Self::Var, bool)> {
         self.vars
             .get(name)
-            .map(|&(ref val, exported)| (val, exported))
+            .map(|&(ref val, exported)| (val.get(), exported))
     }
 
     fn set_exported_var(&mut self, name: Self::VarName, val: Self::Var, exported: bool) {
-        let needs_insert = match self.vars.get(&name) {
-            Some(&(ref existing_val, _)) => val != *existing_val,
-            None => true,
+        self.insert_all(Some((name, val, Some(exported))));
+    }
+
+    fn extend_exported<I>(&mut self, vars: I)
+    where
+        I: IntoIterator<Item = (Self::VarName, Self::Var, bool)>,
+    {
+        self.insert_all(
+            vars.into_iter()
+                .map(|(name, val, exported)| (name, val, Some(exported))),
+        );
+    }
+}
+
+impl<N, V> ArithVariableEnvironment for VarEnv<N, V>
+where
+    N: Eq + Clone + Hash,
+    V: Eq + Clone + Borrow<String> + From<String>,
+{
+    fn arith_var<Q: ?Sized>(&self, name: &Q) -> isize
+    where
+        Self::VarName: Borrow<Q>,
+        Q: Hash + Eq,
+    {
+        let val = match self.vars.get(name) {
+            Some(&(ref val, _)) => val,
+            None => return 0,
         };
 
-        if needs_insert {
-            Arc::make_mut(&mut self.vars).insert(name, (val, exported));
+        if !self.tainted.is_empty() && self.tainted.contains(name) {
+            self.taint_tracker.record_expansion();
+        }
+
+        match *val {
+            VarValue::Number(val, ..) => val,
+            VarValue::String(ref val) => val.borrow().parse().unwrap_or(0),
         }
     }
+
+    fn set_arith_var(&mut self, name: Self::VarName, val: isize) {
+        if self.readonly.contains(&name) {
+            return;
+        }
+
+        let exported = match self.vars.get(&name) {
+            Some(&(VarValue::Number(existing_val, ..), _)) if existing_val == val => return,
+            Some(&(VarValue::String(ref existing_val), _))
+                if *existing_val.borrow() == val.to_string() =>
+            {
+                return;
+            }
+            Some(&(_, exported)) => exported,
+            None => false,
+        };
+
+        // NB: only build the string form of the value if it is actually read,
+        // since arithmetic (e.g. a loop counter) may update it many times before then
+        let val = VarValue::Number(val, OnceCell::new(), |val| val.to_string().into());
+        self.insert(name, val, exported);
+    }
 }
 
 impl<N, V> UnsetVariableEnvironment for VarEnv<N, V>
@@ -189,8 +529,64 @@ where
     V: Eq + Clone,
 {
     fn unset_var(&mut self, name: &N) {
+        if self.readonly.contains(name) {
+            return;
+        }
+
         if self.vars.contains_key(name) {
             Arc::make_mut(&mut self.vars).remove(name);
+            self.notify(VarChange::Unset { name });
+        }
+
+        if self.tainted.contains(name) {
+            Arc::make_mut(&mut self.tainted).remove(name);
+        }
+    }
+}
+
+impl<N, V> TaintEnvironment for VarEnv<N, V>
+where
+    N: Eq + Clone + Hash,
+    V: Eq + Clone,
+{
+    fn is_tainted(&self, name: &N) -> bool {
+        self.tainted.contains(name)
+    }
+
+    fn set_tainted(&mut self, name: N, tainted: bool) {
+        if tainted == self.tainted.contains(&name) {
+            return;
+        }
+
+        let set = Arc::make_mut(&mut self.tainted);
+        if tainted {
+            set.insert(name);
+        } else {
+            set.remove(&name);
+        }
+    }
+
+    fn taint_tracker(&self) -> &TaintTracker {
+        &self.taint_tracker
+    }
+
+    fn set_taint_tracker(&mut self, tracker: TaintTracker) -> TaintTracker {
+        mem::replace(&mut self.taint_tracker, tracker)
+    }
+}
+
+impl<N, V> ReadonlyVariableEnvironment for VarEnv<N, V>
+where
+    N: Eq + Clone + Hash,
+    V: Eq + Clone,
+{
+    fn is_readonly(&self, name: &N) -> bool {
+        self.readonly.contains(name)
+    }
+
+    fn set_readonly(&mut self, name: N) {
+        if !self.readonly.contains(&name) {
+            Arc::make_mut(&mut self.readonly).insert(name);
         }
     }
 }
@@ -201,12 +597,13 @@ where
     V: fmt::Debug,
 {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
-        use std::collections::BTreeMap;
+        use std::collections::{BTreeMap, BTreeSet};
 
         let mut vars = BTreeMap::new();
         let mut env_vars = BTreeMap::new();
 
         for (name, &(ref val, is_env)) in &*self.vars {
+            let val = val.get();
             if is_env {
                 env_vars.insert(name, val);
             } else {
@@ -217,10 +614,30 @@ where
         fmt.debug_struct(stringify!(VarEnv))
             .field("env_vars", &env_vars)
             .field("vars", &vars)
+            .field("watchers", &self.watchers.len())
+            .field("tainted", &self.tainted.iter().collect::<BTreeSet<_>>())
+            .field("readonly", &self.readonly.iter().collect::<BTreeSet<_>>())
             .finish()
     }
 }
 
+impl<N, V> PartialEq for VarEnv<N, V>
+where
+    N: Eq + Hash,
+    V: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.vars == other.vars && self.tainted == other.tainted && self.readonly == other.readonly
+    }
+}
+
+impl<N, V> Eq for VarEnv<N, V>
+where
+    N: Eq + Hash,
+    V: Eq,
+{
+}
+
 impl<N, V> Default for VarEnv<N, V>
 where
     N: Eq + Hash,
@@ -237,16 +654,51 @@ where
     fn clone(&self) -> Self {
         Self {
             vars: self.vars.clone(),
+            watchers: self.watchers.clone(),
+            tainted: self.tainted.clone(),
+            readonly: self.readonly.clone(),
+            taint_tracker: self.taint_tracker.nested(),
         }
     }
 }
 
+impl<N, V> WatchVariableEnvironment for VarEnv<N, V>
+where
+    N: Eq + Clone + Hash,
+    V: Eq + Clone,
+{
+    fn watch<F>(&mut self, watcher: F)
+    where
+        F: Fn(VarChange<'_, Self::VarName, Self::Var>) + Send + Sync + 'static,
+    {
+        VarEnv::watch(self, watcher)
+    }
+}
+
 impl<N, V> SubEnvironment for VarEnv<N, V>
 where
     N: Eq + Hash,
 {
     fn sub_env(&self) -> Self {
-        self.clone()
+        Self {
+            vars: self.vars.clone(),
+            watchers: Vec::new(),
+            tainted: self.tainted.clone(),
+            readonly: self.readonly.clone(),
+            taint_tracker: self.taint_tracker.nested(),
+        }
+    }
+}
+
+impl<N, V> HomeDirEnvironment for VarEnv<N, V>
+where
+    N: Eq + Hash + Borrow<String>,
+    V: Borrow<String>,
+{
+    fn home_dir(&self) -> Option<Cow<'_, str>> {
+        self.vars
+            .get(&*HOME)
+            .map(|&(ref home, _)| Cow::Borrowed(home.get().borrow().as_str()))
     }
 }
 
@@ -357,6 +809,130 @@ mod tests {
         assert_eq!(parent.var(child_name), None);
     }
 
+    #[test]
+    fn test_export_existing_var_with_same_value() {
+        let name = "var";
+        let value = "value";
+
+        let mut env = VarEnv::new();
+        env.set_var(name, value);
+        env.set_exported_var(name, value, true);
+        assert_eq!(env.exported_var(&name), Some((&value, true)));
+    }
+
+    #[test]
+    fn test_set_vars_and_extend_exported() {
+        let mut env = VarEnv::with_env_vars(vec![("exported", "old")]);
+
+        env.set_vars(vec![("exported", "new"), ("var", "value")]);
+        assert_eq!(env.exported_var(&"exported"), Some((&"new", true)));
+        assert_eq!(env.exported_var(&"var"), Some((&"value", false)));
+
+        env.extend_exported(vec![("exported", "new", false), ("var", "value", true)]);
+        assert_eq!(env.exported_var(&"exported"), Some((&"new", false)));
+        assert_eq!(env.exported_var(&"var"), Some((&"value", true)));
+    }
+
+    #[test]
+    fn test_arith_var_formatted_lazily_and_invalidated() {
+        let name = "var".to_owned();
+        let mut env = VarEnv::with_env_vars(vec![(name.clone(), "5".to_owned())]);
+        assert_eq!(env.arith_var(&name), 5);
+
+        env.set_arith_var(name.clone(), 42);
+        assert_eq!(env.arith_var(&name), 42);
+        match env.vars[&name].0 {
+            VarValue::Number(42, ref string, _) => assert_eq!(string.get(), None),
+            _ => panic!("value was not set as a number"),
+        }
+        assert_eq!(env.exported_var(&name), Some((&"42".to_owned(), true)));
+
+        env.set_var(name.clone(), "foo".to_owned());
+        assert_eq!(env.arith_var(&name), 0);
+
+        env.set_arith_var(name.clone(), 7);
+        env.unset_var(&name);
+        assert_eq!(env.arith_var(&name), 0);
+        assert_eq!(env.var(&name), None);
+    }
+
+    #[test]
+    fn test_arith_var_in_child_env_should_not_affect_parent() {
+        let name = "var".to_owned();
+        let mut parent = VarEnv::new();
+        parent.set_arith_var(name.clone(), 1);
+
+        let mut child = parent.sub_env();
+        assert_eq!(child.arith_var(&name), 1);
+        child.set_arith_var(name.clone(), 2);
+        assert_eq!(child.arith_var(&name), 2);
+
+        assert_eq!(parent.arith_var(&name), 1);
+        assert_eq!(parent.var(&name), Some(&"1".to_owned()));
+    }
+
+    #[test]
+    fn test_batch_no_needless_clone() {
+        let mut env = VarEnv::new();
+        env.set_vars(vec![("var", "value")]);
+        env.extend_exported(vec![("exported", "value", true)]);
+
+        let mut env = env.sub_env();
+        env.set_vars(vec![("var", "value")]);
+        env.extend_exported(vec![("exported", "value", true)]);
+        if Arc::get_mut(&mut env.vars).is_some() {
+            panic!("needles clone!");
+        }
+    }
+
+    #[test]
+    fn test_watchers_notified_of_changes() {
+        use std::sync::Mutex;
+
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let mut env = VarEnv::new();
+
+        let changes_clone = changes.clone();
+        env.watch(move |change: VarChange<'_, &str, &str>| {
+            let change = match change {
+                VarChange::Set {
+                    name,
+                    value,
+                    exported,
+                } => (*name, Some(*value), exported),
+                VarChange::Unset { name } => (*name, None, false),
+            };
+            changes_clone.lock().unwrap().push(change);
+        });
+
+        env.set_var("var", "value");
+        env.set_var("var", "value");
+        env.set_exported_var("var", "value", true);
+        env.set_var("var", "new");
+        env.unset_var(&"var");
+        env.unset_var(&"var");
+
+        let mut child = env.sub_env();
+        child.set_var("child", "value");
+
+        let mut clone = env.clone();
+        clone.set_var("clone", "value");
+
+        env.clear_watchers();
+        env.set_var("cleared", "value");
+
+        assert_eq!(
+            *changes.lock().unwrap(),
+            vec![
+                ("var", Some("value"), false),
+                ("var", Some("value"), true),
+                ("var", Some("new"), true),
+                ("var", None, false),
+                ("clone", Some("value"), false),
+            ]
+        );
+    }
+
     #[test]
     fn test_get_env_vars_visible_in_parent_and_child() {
         use std::collections::HashSet;
@@ -378,4 +954,72 @@ mod tests {
         let vars: HashSet<(_, _)> = HashSet::from_iter(child.env_vars().into_owned());
         assert_eq!(vars, env_vars);
     }
+
+    #[test]
+    fn test_tainted_vars_record_expansions() {
+        let mut env = VarEnv::new();
+        env.set_var("safe", "value");
+        env.set_var("untrusted", "value");
+        env.set_tainted("untrusted", true);
+        env.set_tainted("unset", true);
+
+        assert!(env.is_tainted(&"untrusted"));
+        assert!(!env.is_tainted(&"safe"));
+
+        let tracker = env.taint_tracker().clone();
+        env.var("safe");
+        env.var("unset");
+        assert_eq!(tracker.expansions(), 0);
+        env.var("untrusted");
+        assert_eq!(tracker.expansions(), 1);
+
+        // Sub environments inherit taints and also report to the parent's tracker
+        let mut child = env.sub_env();
+        assert!(child.is_tainted(&"untrusted"));
+        child.var("untrusted");
+        assert_eq!(child.taint_tracker().expansions(), 1);
+        assert_eq!(tracker.expansions(), 2);
+
+        // ...but the parent's own lookups are not reported to its children
+        env.var("untrusted");
+        assert_eq!(child.taint_tracker().expansions(), 1);
+        assert_eq!(tracker.expansions(), 3);
+
+        let detached = child.taint_tracker().detached();
+        child.set_taint_tracker(detached);
+        child.var("untrusted");
+        assert_eq!(tracker.expansions(), 3);
+
+        env.set_var("untrusted", "new");
+        assert!(env.is_tainted(&"untrusted"));
+        env.unset_var(&"untrusted");
+        assert!(!env.is_tainted(&"untrusted"));
+        env.set_tainted("safe", true);
+        env.set_tainted("safe", false);
+        assert!(!env.is_tainted(&"safe"));
+    }
+
+    #[test]
+    fn test_readonly_vars_cannot_change() {
+        let mut env = VarEnv::new();
+        env.set_var("var", "value");
+        env.set_readonly("var");
+        env.set_readonly("unset");
+
+        assert!(env.is_readonly(&"var"));
+        assert!(!env.is_readonly(&"other"));
+
+        env.set_var("var", "new");
+        env.set_exported_var("var", "new", true);
+        env.unset_var(&"var");
+        assert_eq!(env.exported_var(&"var"), Some((&"value", false)));
+
+        env.set_var("unset", "new");
+        assert_eq!(env.var("unset"), None);
+
+        // Sub environments inherit read-only variables
+        let mut child = env.sub_env();
+        child.unset_var(&"var");
+        assert_eq!(child.var("var"), Some(&"value"));
+    }
 }
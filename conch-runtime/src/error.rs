@@ -2,12 +2,15 @@
 //! while executing commands.
 #![allow(unused_qualifications)] // False positives with thiserror derive
 
+use crate::env::ControlFlow;
 use crate::io::Permissions;
-use crate::Fd;
+use crate::{
+    ExitStatus, Fd, EXIT_CMD_NOT_EXECUTABLE, EXIT_CMD_NOT_FOUND, EXIT_ERROR, EXIT_SUCCESS,
+};
 use std::convert::From;
 use std::error::Error;
 use std::fmt::{self, Display, Formatter};
-use std::io::Error as IoError;
+use std::io::{Error as IoError, ErrorKind as IoErrorKind};
 
 /// Determines whether an error should be treated as "fatal".
 ///
@@ -30,6 +33,28 @@ impl IsFatalError for void::Void {
     }
 }
 
+/// A frame of execution context within which an error may be reported,
+/// e.g. "in function f" or "in command substitution".
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum ErrorContext {
+    /// Executing the body of the named function.
+    Function(String),
+    /// Executing within a subshell.
+    Subshell,
+    /// Executing within a command substitution.
+    CommandSubstitution,
+}
+
+impl Display for ErrorContext {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        match *self {
+            ErrorContext::Function(ref name) => write!(fmt, "in function {}", name),
+            ErrorContext::Subshell => write!(fmt, "in subshell"),
+            ErrorContext::CommandSubstitution => write!(fmt, "in command substitution"),
+        }
+    }
+}
+
 /// An error which may arise during parameter expansion.
 #[derive(PartialEq, Eq, Clone, Debug, thiserror::Error)]
 pub enum ExpansionError {
@@ -39,12 +64,20 @@ pub enum ExpansionError {
     /// Attempted to raise to a negative power in an arithmetic subsitution.
     #[error("attempted to raise to a negative power")]
     NegativeExponent,
+    /// The result of an arithmetic subsitution (or any of its intermediate
+    /// values) cannot be represented, or a value was shifted by a negative
+    /// amount or by more bits than it has.
+    #[error("arithmetic overflow")]
+    Overflow,
     /// Attempted to assign a special parameter, e.g. `${!:=value}`.
     #[error("{0}: cannot assign in this way")]
     BadAssig(String),
     /// Attempted to evaluate a null or unset parameter, i.e. `${var:?msg}`.
     #[error("{0}: {1}")]
     EmptyParameter(String /* var */, String /* msg */),
+    /// Attempted to expand an unset parameter while `ShellOptions::nounset` was set.
+    #[error("{0}: parameter not set")]
+    UnsetParameter(String /* var */),
 }
 
 impl IsFatalError for ExpansionError {
@@ -53,8 +86,10 @@ impl IsFatalError for ExpansionError {
         match *self {
             ExpansionError::DivideByZero
             | ExpansionError::NegativeExponent
+            | ExpansionError::Overflow
             | ExpansionError::BadAssig(_)
-            | ExpansionError::EmptyParameter(_, _) => true,
+            | ExpansionError::EmptyParameter(_, _)
+            | ExpansionError::UnsetParameter(_) => true,
         }
     }
 }
@@ -74,6 +109,13 @@ pub enum RedirectionError {
     Io(#[source] IoError, Option<String>),
 }
 
+impl RedirectionError {
+    /// Creates an I/O error which was caused while manipulating the specified file descriptor.
+    pub(crate) fn fd_io(err: IoError, fd: Fd) -> Self {
+        RedirectionError::Io(err, Some(format!("file descriptor {}", fd)))
+    }
+}
+
 impl Eq for RedirectionError {}
 impl PartialEq for RedirectionError {
     fn eq(&self, other: &Self) -> bool {
@@ -120,8 +162,7 @@ impl Display for RedirectionError {
                 )
             }
 
-            RedirectionError::Io(ref e, None) => write!(fmt, "{}", e),
-            RedirectionError::Io(ref e, Some(ref path)) => write!(fmt, "{}: {}", e, path),
+            RedirectionError::Io(ref e, ref path) => fmt_io_error(fmt, e, path.as_deref()),
         }
     }
 }
@@ -147,6 +188,30 @@ pub enum CommandError {
     /// Any I/O error returned by the OS during execution and the
     /// file that caused the error if applicable.
     Io(#[source] IoError, Option<String>),
+    /// A special builtin utility failed while `ShellOptions::fatal_special_builtin_errors`
+    /// was set, along with the status it exited with.
+    SpecialBuiltin(String, ExitStatus),
+    /// A command exited unsuccessfully while `ShellOptions::errexit` was set,
+    /// along with the status it exited with.
+    ErrExit(ExitStatus),
+    /// Unable to prepare or install the `SpawnPolicy` of an executable,
+    /// along with the name of the executable.
+    SpawnPolicy(#[source] IoError, String),
+    /// Unable to create the pipes of a pipeline because too many files are open
+    /// (e.g. `EMFILE`, or the limit configured via `FdUsageTracker::set_limit`).
+    TooManyOpenFiles(#[source] IoError),
+    /// The `return` builtin requested that the currently executing function
+    /// return with the provided status.
+    Return(ExitStatus),
+    /// The `exit` builtin requested that the current shell (or subshell)
+    /// exit with the provided status.
+    Exit(ExitStatus),
+    /// The `break` builtin requested to break out of the provided number of
+    /// enclosing loops.
+    Break(usize),
+    /// The `continue` builtin requested to skip to the next iteration of the
+    /// loop which is the provided number of levels out.
+    Continue(usize),
 }
 
 impl Eq for CommandError {}
@@ -158,6 +223,14 @@ impl PartialEq for CommandError {
             (&NotFound(ref a), &NotFound(ref b))
             | (&NotExecutable(ref a), &NotExecutable(ref b)) => a == b,
             (&Io(ref e1, ref a), &Io(ref e2, ref b)) => e1.kind() == e2.kind() && a == b,
+            (&SpecialBuiltin(ref a, sa), &SpecialBuiltin(ref b, sb)) => a == b && sa == sb,
+            (&ErrExit(a), &ErrExit(b)) => a == b,
+            (&SpawnPolicy(ref e1, ref a), &SpawnPolicy(ref e2, ref b)) => {
+                e1.kind() == e2.kind() && a == b
+            }
+            (&TooManyOpenFiles(ref e1), &TooManyOpenFiles(ref e2)) => e1.kind() == e2.kind(),
+            (&Return(a), &Return(b)) | (&Exit(a), &Exit(b)) => a == b,
+            (&Break(a), &Break(b)) | (&Continue(a), &Continue(b)) => a == b,
             _ => false,
         }
     }
@@ -166,10 +239,79 @@ impl PartialEq for CommandError {
 impl Display for CommandError {
     fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
         match *self {
+            // NB: commands with a path are never looked up, they simply do not exist
+            CommandError::NotFound(ref c) if c.contains(std::path::is_separator) => {
+                write!(fmt, "{}: No such file or directory", c)
+            }
             CommandError::NotFound(ref c) => write!(fmt, "{}: command not found", c),
             CommandError::NotExecutable(ref c) => write!(fmt, "{}: command not executable", c),
-            CommandError::Io(ref e, None) => write!(fmt, "{}", e),
-            CommandError::Io(ref e, Some(ref path)) => write!(fmt, "{}: {}", e, path),
+            CommandError::Io(ref e, ref path) => fmt_io_error(fmt, e, path.as_deref()),
+            CommandError::SpecialBuiltin(ref c, status) => {
+                write!(fmt, "{}: special builtin utility failed ({:?})", c, status)
+            }
+            CommandError::ErrExit(status) => {
+                write!(fmt, "command failed while errexit was set ({:?})", status)
+            }
+            CommandError::SpawnPolicy(ref e, ref c) => write!(
+                fmt,
+                "{}: failed to apply spawn policy: {}",
+                c,
+                describe_io_error(e)
+            ),
+            CommandError::TooManyOpenFiles(ref e) => {
+                write!(fmt, "cannot create pipeline: {}", describe_io_error(e))
+            }
+            CommandError::Return(status) => {
+                write!(
+                    fmt,
+                    "return: can only be used within a function ({:?})",
+                    status
+                )
+            }
+            CommandError::Exit(status) => write!(fmt, "exit requested ({:?})", status),
+            CommandError::Break(n) => write!(fmt, "break: only meaningful in a loop ({})", n),
+            CommandError::Continue(n) => {
+                write!(fmt, "continue: only meaningful in a loop ({})", n)
+            }
+        }
+    }
+}
+
+impl CommandError {
+    /// Determines the exit status of a command which failed with this error,
+    /// following the same conventions as bash and dash:
+    ///
+    /// * `EXIT_CMD_NOT_FOUND` (127) if the command could not be found (`ENOENT`)
+    /// * `EXIT_CMD_NOT_EXECUTABLE` (126) if the command was found but could not be
+    ///   executed (e.g. `EACCES`, `EISDIR`, or `ENOEXEC`)
+    /// * `EXIT_CMD_NOT_EXECUTABLE` (126) if the `SpawnPolicy` of the command could
+    ///   not be applied
+    /// * the status the utility exited with, if a special builtin utility failed
+    /// * the status the command exited with, if it failed while `errexit` was set
+    /// * the requested status, if the command was `return` or `exit`
+    /// * `EXIT_SUCCESS` (0) if the command was `break` or `continue`
+    /// * `EXIT_ERROR` (1) for all other errors
+    pub fn exit_status(&self) -> ExitStatus {
+        match *self {
+            CommandError::NotFound(_) => EXIT_CMD_NOT_FOUND,
+            CommandError::NotExecutable(_) | CommandError::SpawnPolicy(_, _) => {
+                EXIT_CMD_NOT_EXECUTABLE
+            }
+            CommandError::Io(ref e, _) => {
+                if e.kind() == IoErrorKind::NotFound {
+                    EXIT_CMD_NOT_FOUND
+                } else if e.kind() == IoErrorKind::PermissionDenied || is_eisdir(e) {
+                    EXIT_CMD_NOT_EXECUTABLE
+                } else {
+                    EXIT_ERROR
+                }
+            }
+            CommandError::SpecialBuiltin(_, status)
+            | CommandError::ErrExit(status)
+            | CommandError::Return(status)
+            | CommandError::Exit(status) => status,
+            CommandError::Break(_) | CommandError::Continue(_) => EXIT_SUCCESS,
+            CommandError::TooManyOpenFiles(_) => EXIT_ERROR,
         }
     }
 }
@@ -177,13 +319,32 @@ impl Display for CommandError {
 impl IsFatalError for CommandError {
     fn is_fatal(&self) -> bool {
         match *self {
-            CommandError::NotFound(_) | CommandError::NotExecutable(_) | CommandError::Io(_, _) => {
-                false
-            }
+            CommandError::NotFound(_)
+            | CommandError::NotExecutable(_)
+            | CommandError::Io(_, _)
+            | CommandError::SpawnPolicy(_, _)
+            | CommandError::TooManyOpenFiles(_) => false,
+            CommandError::SpecialBuiltin(_, _)
+            | CommandError::ErrExit(_)
+            | CommandError::Return(_)
+            | CommandError::Exit(_)
+            | CommandError::Break(_)
+            | CommandError::Continue(_) => true,
         }
     }
 }
 
+/// An error which arises when opening a file descriptor would exceed the
+/// limit configured via `FdUsageTracker::set_limit`.
+///
+/// It is surfaced as the inner error of an `io::Error`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, thiserror::Error)]
+#[error("too many open file descriptors (limit: {limit})")]
+pub struct FdLimitExceededError {
+    /// The configured limit which would have been exceeded.
+    pub limit: usize,
+}
+
 /// An error which may arise while executing commands.
 #[derive(Debug, thiserror::Error)]
 pub enum RuntimeError {
@@ -223,8 +384,7 @@ impl Display for RuntimeError {
             RuntimeError::Redirection(ref e) => write!(fmt, "{}", e),
             RuntimeError::Command(ref e) => write!(fmt, "{}", e),
             RuntimeError::Unimplemented(e) => write!(fmt, "{}", e),
-            RuntimeError::Io(ref e, None) => write!(fmt, "{}", e),
-            RuntimeError::Io(ref e, Some(ref path)) => write!(fmt, "{}: {}", e, path),
+            RuntimeError::Io(ref e, ref path) => fmt_io_error(fmt, e, path.as_deref()),
         }
     }
 }
@@ -240,6 +400,123 @@ impl IsFatalError for RuntimeError {
     }
 }
 
+/// Determines the exit status of a command which failed with the provided error
+/// (e.g. as observed by scripts via `$?`), following the same conventions as bash and dash.
+///
+/// The error's chain of sources is searched for a `CommandError`, in which case
+/// `CommandError::exit_status` determines the status. Any other errors, including
+/// failed redirections (e.g. opening a file which is missing, inaccessible, or is
+/// a directory), result in `EXIT_ERROR`.
+pub fn exit_status_for_error(err: &(dyn Error + 'static)) -> ExitStatus {
+    let mut cur = Some(err);
+    while let Some(err) = cur {
+        if let Some(e) = err.downcast_ref::<CommandError>() {
+            return e.exit_status();
+        } else if err.is::<RedirectionError>() {
+            break;
+        }
+
+        cur = err.source();
+    }
+
+    EXIT_ERROR
+}
+
+impl From<ControlFlow> for CommandError {
+    fn from(flow: ControlFlow) -> Self {
+        match flow {
+            ControlFlow::Return(status) => CommandError::Return(status),
+            ControlFlow::Exit(status) => CommandError::Exit(status),
+            ControlFlow::Break(n) => CommandError::Break(n),
+            ControlFlow::Continue(n) => CommandError::Continue(n),
+        }
+    }
+}
+
+/// Determines if a command failed because it requested a change to the flow of
+/// execution (i.e. it failed with a `CommandError::Return`, `CommandError::Exit`,
+/// `CommandError::Break`, or `CommandError::Continue`), in which case the function
+/// being returned from, the (sub)shell being exited, or the loop being broken out
+/// of, should silently complete with the requested status.
+///
+/// The error's chain of sources is searched for a `CommandError`, much like
+/// `exit_status_for_error` does.
+pub fn control_flow_for_error(err: &(dyn Error + 'static)) -> Option<ControlFlow> {
+    let mut cur = Some(err);
+    while let Some(err) = cur {
+        match err.downcast_ref::<CommandError>() {
+            Some(CommandError::Return(status)) => return Some(ControlFlow::Return(*status)),
+            Some(CommandError::Exit(status)) => return Some(ControlFlow::Exit(*status)),
+            Some(CommandError::Break(n)) => return Some(ControlFlow::Break(*n)),
+            Some(CommandError::Continue(n)) => return Some(ControlFlow::Continue(*n)),
+            Some(_) => break,
+            None => cur = err.source(),
+        }
+    }
+
+    None
+}
+
+/// Describes an I/O error the same way as shells like bash and dash would,
+/// i.e. only with the system's description of the error (e.g.
+/// `No such file or directory`), without any raw OS error codes.
+pub fn describe_io_error(err: &IoError) -> String {
+    let msg = err.to_string();
+
+    match err.raw_os_error() {
+        Some(code) => {
+            let suffix = format!(" (os error {})", code);
+            match msg.strip_suffix(&suffix) {
+                Some(msg) => msg.to_owned(),
+                None => msg,
+            }
+        }
+        None => msg,
+    }
+}
+
+fn fmt_io_error(fmt: &mut Formatter<'_>, err: &IoError, path: Option<&str>) -> fmt::Result {
+    match path {
+        Some(path) => write!(fmt, "{}: {}", path, describe_io_error(err)),
+        None => write!(fmt, "{}", describe_io_error(err)),
+    }
+}
+
+#[cfg(unix)]
+fn is_eisdir(err: &IoError) -> bool {
+    Some(libc::EISDIR) == err.raw_os_error()
+}
+
+#[cfg(windows)]
+fn is_eisdir(_err: &IoError) -> bool {
+    false
+}
+
+/// Checks if an error arose because too many files are open, either by the
+/// process or the entire system, or because the limit configured via
+/// `FdUsageTracker::set_limit` would have been exceeded.
+pub(crate) fn is_too_many_open_files(err: &IoError) -> bool {
+    let limit_exceeded = err
+        .get_ref()
+        .is_some_and(|e| e.is::<FdLimitExceededError>());
+
+    limit_exceeded || is_emfile(err)
+}
+
+#[cfg(unix)]
+fn is_emfile(err: &IoError) -> bool {
+    match err.raw_os_error() {
+        Some(code) => code == libc::EMFILE || code == libc::ENFILE,
+        None => false,
+    }
+}
+
+#[cfg(windows)]
+fn is_emfile(err: &IoError) -> bool {
+    const ERROR_TOO_MANY_OPEN_FILES: i32 = 4;
+    Some(ERROR_TOO_MANY_OPEN_FILES) == err.raw_os_error()
+}
+
 impl From<IoError> for RuntimeError {
     fn from(err: IoError) -> Self {
         RuntimeError::Io(err, None)
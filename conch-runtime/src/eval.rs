@@ -10,9 +10,11 @@ mod concat;
 mod double_quoted;
 mod fields;
 mod param_subst;
+mod quote;
 mod redirect;
 mod redirect_or_cmd_word;
 mod redirect_or_var_assig;
+mod tainted;
 
 #[cfg(feature = "conch-parser")]
 pub mod ast_impl;
@@ -20,22 +22,25 @@ pub mod ast_impl;
 pub use self::assignment::eval_as_assignment;
 pub use self::concat::concat;
 pub use self::double_quoted::double_quoted;
-pub use self::fields::Fields;
+pub use self::fields::{join_fields, Fields};
 pub use self::param_subst::{alternative, assign, default, error, len};
 pub use self::param_subst::{
     remove_largest_prefix, remove_largest_suffix, remove_smallest_prefix, remove_smallest_suffix,
 };
+pub use self::quote::{quote_bytes_for_shell, quote_for_shell};
 pub use self::redirect::{
     redirect_append, redirect_clobber, redirect_dup_read, redirect_dup_write, redirect_heredoc,
     redirect_read, redirect_readwrite, redirect_write, RedirectAction, RedirectEval,
 };
 pub use self::redirect_or_cmd_word::{
-    eval_redirects_or_cmd_words_with_restorer, EvalRedirectOrCmdWordError, RedirectOrCmdWord,
+    eval_redirects_or_cmd_words_with_restorer, eval_redirects_or_cmd_words_with_taint,
+    EvalRedirectOrCmdWordError, RedirectOrCmdWord,
 };
 pub use self::redirect_or_var_assig::{
-    eval_redirects_or_var_assignments_with_restorer, EvalRedirectOrVarAssigError,
-    RedirectOrVarAssig,
+    eval_redirects_or_var_assignments_staged, eval_redirects_or_var_assignments_with_restorer,
+    EvalRedirectOrVarAssigError, RedirectOrVarAssig,
 };
+pub use self::tainted::{eval_with_taint, TaintedFields};
 
 /// A trait for evaluating parameters.
 pub trait ParamEval<E: ?Sized> {
@@ -283,8 +288,16 @@ where
     // need to move into its own trait (right now WordEval *must* return a
     // Pattern future).
     let pat = future.await?.await.join();
-    let pat = glob::Pattern::new(pat.as_str())
-        .or_else(|_| glob::Pattern::new(&glob::Pattern::escape(pat.as_str())))
+
+    // Consecutive `*` are equivalent to a single one in shell patterns, but the glob
+    // crate only accepts `**` as a standalone (recursive) path component.
+    let mut pat = pat.as_str().to_owned();
+    while pat.contains("**") {
+        pat = pat.replace("**", "*");
+    }
+
+    let pat = glob::Pattern::new(&pat)
+        .or_else(|_| glob::Pattern::new(&glob::Pattern::escape(&pat)))
         .expect("pattern compilation unexpectedly failed");
     Ok(pat)
 }
@@ -1,25 +1,21 @@
-use crate::env::VariableEnvironment;
+use crate::env::ArithVariableEnvironment;
 use crate::error::ExpansionError;
 use crate::eval::ArithEval;
 use conch_parser::ast::Arithmetic;
 use conch_parser::ast::Arithmetic::*;
 use std::borrow::Borrow;
+use std::convert::TryFrom;
 
 impl<T, E: ?Sized> ArithEval<E> for Arithmetic<T>
 where
     T: Borrow<String> + Clone,
-    E: VariableEnvironment,
+    E: ArithVariableEnvironment,
     E::VarName: Borrow<String> + From<T>,
-    E::Var: Borrow<String> + From<String>,
 {
     fn eval(&self, env: &mut E) -> Result<isize, ExpansionError> {
         // FIXME: interesting observation: bash and zsh seem to recursively expand vars to other vars
         // FIXME: e.g. x=3, y=x, z=y, $(( $z *= 5 ))
-        let get_var = |env: &E, var: &T| {
-            env.var(var.borrow())
-                .and_then(|s| s.borrow().as_str().parse().ok())
-                .unwrap_or(0)
-        };
+        let get_var = |env: &E, var: &T| env.arith_var(var.borrow());
 
         let ret = match *self {
             Literal(lit) => lit,
@@ -27,30 +23,30 @@ where
 
             PostIncr(ref var) => {
                 let value = get_var(env, var);
-                env.set_var(var.clone().into(), (value + 1).to_string().into());
+                env.set_arith_var(var.clone().into(), checked(value.checked_add(1))?);
                 value
             }
 
             PostDecr(ref var) => {
                 let value = get_var(env, var);
-                env.set_var(var.clone().into(), (value - 1).to_string().into());
+                env.set_arith_var(var.clone().into(), checked(value.checked_sub(1))?);
                 value
             }
 
             PreIncr(ref var) => {
-                let value = get_var(env, var) + 1;
-                env.set_var(var.clone().into(), value.to_string().into());
+                let value = checked(get_var(env, var).checked_add(1))?;
+                env.set_arith_var(var.clone().into(), value);
                 value
             }
 
             PreDecr(ref var) => {
-                let value = get_var(env, var) - 1;
-                env.set_var(var.clone().into(), value.to_string().into());
+                let value = checked(get_var(env, var).checked_sub(1))?;
+                env.set_arith_var(var.clone().into(), value);
                 value
             }
 
-            UnaryPlus(ref expr) => expr.eval(env)?.abs(),
-            UnaryMinus(ref expr) => -expr.eval(env)?,
+            UnaryPlus(ref expr) => expr.eval(env)?,
+            UnaryMinus(ref expr) => checked(expr.eval(env)?.checked_neg())?,
             BitwiseNot(ref expr) => expr.eval(env)? ^ !0,
             LogicalNot(ref expr) => {
                 if expr.eval(env)? == 0 {
@@ -108,7 +104,9 @@ where
                 if right.is_negative() {
                     return Err(ExpansionError::NegativeExponent);
                 } else {
-                    left.eval(env)?.pow(right as u32)
+                    let left = left.eval(env)?;
+                    let right = u32::try_from(right).map_err(|_| ExpansionError::Overflow)?;
+                    checked(left.checked_pow(right))?
                 }
             }
 
@@ -117,7 +115,7 @@ where
                 if right == 0 {
                     return Err(ExpansionError::DivideByZero);
                 } else {
-                    left.eval(env)? / right
+                    checked(left.eval(env)?.checked_div(right))?
                 }
             }
 
@@ -126,15 +124,21 @@ where
                 if right == 0 {
                     return Err(ExpansionError::DivideByZero);
                 } else {
-                    left.eval(env)? % right
+                    checked(left.eval(env)?.checked_rem(right))?
                 }
             }
 
-            Mult(ref left, ref right) => left.eval(env)? * right.eval(env)?,
-            Add(ref left, ref right) => left.eval(env)? + right.eval(env)?,
-            Sub(ref left, ref right) => left.eval(env)? - right.eval(env)?,
-            ShiftLeft(ref left, ref right) => left.eval(env)? << right.eval(env)?,
-            ShiftRight(ref left, ref right) => left.eval(env)? >> right.eval(env)?,
+            Mult(ref left, ref right) => checked(left.eval(env)?.checked_mul(right.eval(env)?))?,
+            Add(ref left, ref right) => checked(left.eval(env)?.checked_add(right.eval(env)?))?,
+            Sub(ref left, ref right) => checked(left.eval(env)?.checked_sub(right.eval(env)?))?,
+            ShiftLeft(ref left, ref right) => {
+                let left = left.eval(env)?;
+                checked(shift_amount(right.eval(env)?).and_then(|n| left.checked_shl(n)))?
+            }
+            ShiftRight(ref left, ref right) => {
+                let left = left.eval(env)?;
+                checked(shift_amount(right.eval(env)?).and_then(|n| left.checked_shr(n)))?
+            }
             BitwiseAnd(ref left, ref right) => left.eval(env)? & right.eval(env)?,
             BitwiseXor(ref left, ref right) => left.eval(env)? ^ right.eval(env)?,
             BitwiseOr(ref left, ref right) => left.eval(env)? | right.eval(env)?,
@@ -173,7 +177,7 @@ where
 
             Assign(ref var, ref value) => {
                 let value = value.eval(env)?;
-                env.set_var(var.clone().into(), value.to_string().into());
+                env.set_arith_var(var.clone().into(), value);
                 value
             }
 
@@ -189,3 +193,14 @@ where
         Ok(ret)
     }
 }
+
+/// Converts the result of a checked operation into an error if it overflowed.
+fn checked(result: Option<isize>) -> Result<isize, ExpansionError> {
+    result.ok_or(ExpansionError::Overflow)
+}
+
+/// Converts the amount to shift a value by, provided it is not negative
+/// (shifting by more bits than a value has is checked by the shift itself).
+fn shift_amount(amount: isize) -> Option<u32> {
+    u32::try_from(amount).ok()
+}
@@ -1,6 +1,7 @@
 use crate::env::{
     AsyncIoEnvironment, FileDescEnvironment, FileDescOpener, IsInteractiveEnvironment,
-    LastStatusEnvironment, ReportErrorEnvironment, SubEnvironment, VariableEnvironment,
+    LastStatusEnvironment, ReportErrorEnvironment, ShellOptionsEnvironment, SubEnvironment,
+    SubstitutionStatusEnvironment, VariableEnvironment,
 };
 use crate::error::{ExpansionError, IsFatalError};
 use crate::eval::{
@@ -8,7 +9,7 @@ use crate::eval::{
     remove_smallest_prefix, remove_smallest_suffix, ArithEval, Fields, ParamEval, WordEval,
     WordEvalConfig, WordEvalResult,
 };
-use crate::spawn::{sequence_slice, substitution, Spawn};
+use crate::spawn::{sequence_slice, substitution_with_status, Spawn};
 use conch_parser::ast;
 use conch_parser::ast::ParameterSubstitution::*;
 use std::fmt;
@@ -32,7 +33,9 @@ where
         + IsInteractiveEnvironment
         + LastStatusEnvironment
         + ReportErrorEnvironment
+        + ShellOptionsEnvironment
         + SubEnvironment
+        + SubstitutionStatusEnvironment
         + VariableEnvironment<VarName = W::EvalResult, Var = W::EvalResult>,
     E::FileHandle: Send + From<E::OpenedFileHandle>,
     E::OpenedFileHandle: Send,
@@ -53,9 +56,20 @@ where
     ) -> WordEvalResult<Self::EvalResult, W::Error> {
         let te = cfg.tilde_expansion;
 
+        // NB: the other substitutions define their own behavior for unset parameters
+        match self {
+            Len(p)
+            | RemoveSmallestSuffix(p, _)
+            | RemoveLargestSuffix(p, _)
+            | RemoveSmallestPrefix(p, _)
+            | RemoveLargestPrefix(p, _) => check_set(p, env)?,
+            _ => {}
+        }
+
         let fields = match self {
             Command(body) => {
-                let ret = substitution(sequence_slice(body), env).await?;
+                let (ret, status) = substitution_with_status(sequence_slice(body), env).await?;
+                env.set_last_substitution_status(Some(status));
                 Fields::Single(W::EvalResult::from(ret))
             }
             Len(ref p) => Fields::Single(len(p, env)),
@@ -88,3 +102,16 @@ where
         Ok(Box::pin(async move { ret }))
     }
 }
+
+/// Ensures a parameter is set if `ShellOptions::nounset` is enabled.
+fn check_set<P, E>(param: &P, env: &E) -> Result<(), ExpansionError>
+where
+    P: ParamEval<E> + fmt::Display,
+    E: ?Sized + ShellOptionsEnvironment,
+{
+    if env.options().nounset && param.eval(false, env).is_none() {
+        Err(ExpansionError::UnsetParameter(param.to_string()))
+    } else {
+        Ok(())
+    }
+}
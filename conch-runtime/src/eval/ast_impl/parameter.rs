@@ -1,4 +1,7 @@
-use crate::env::{ArgumentsEnvironment, LastStatusEnvironment, StringWrapper, VariableEnvironment};
+use crate::env::{
+    ArgumentsEnvironment, IsInteractiveEnvironment, JobEnvironment, LastStatusEnvironment,
+    ShellOptionsEnvironment, StringWrapper, VariableEnvironment,
+};
 use crate::eval::{Fields, ParamEval};
 use crate::io::getpid;
 use crate::ExitStatus;
@@ -10,7 +13,12 @@ const EXIT_SIGNAL_OFFSET: u32 = 128;
 impl<T, E: ?Sized> ParamEval<E> for Parameter<T>
 where
     T: StringWrapper,
-    E: ArgumentsEnvironment<Arg = T> + LastStatusEnvironment + VariableEnvironment<Var = T>,
+    E: ArgumentsEnvironment<Arg = T>
+        + IsInteractiveEnvironment
+        + JobEnvironment
+        + LastStatusEnvironment
+        + ShellOptionsEnvironment
+        + VariableEnvironment<Var = T>,
     E::VarName: Borrow<String>,
 {
     type EvalResult = T;
@@ -26,22 +34,37 @@ where
         };
 
         let ret = match *self {
-            Parameter::At   => Some(get_args().map_or(Fields::Zero, Fields::At)),
+            Parameter::At => Some(get_args().map_or(Fields::Zero, Fields::At)),
             Parameter::Star => Some(get_args().map_or(Fields::Zero, Fields::Star)),
 
-            Parameter::Pound  => Some(Fields::Single(env.args_len().to_string().into())),
+            Parameter::Pound => Some(Fields::Single(env.args_len().to_string().into())),
             Parameter::Dollar => Some(Fields::Single(getpid().to_string().into())),
-            Parameter::Dash   |        // FIXME: implement properly
-            Parameter::Bang   => None, // FIXME: eventual job control would be nice
+            Parameter::Dash => {
+                let mut flags = env.options().flags();
+                if env.is_interactive() {
+                    flags.push('i');
+                }
+                Some(Fields::Single(flags.into()))
+            }
+            Parameter::Bang => env
+                .last_job()
+                .map(|id| Fields::Single(id.to_string().into())),
 
-            Parameter::Question => Some(Fields::Single(match env.last_status() {
-                ExitStatus::Code(c)   => c as u32,
-                ExitStatus::Signal(c) => c as u32 + EXIT_SIGNAL_OFFSET,
-            }.to_string().into())),
+            Parameter::Question => Some(Fields::Single(
+                match env.last_status() {
+                    ExitStatus::Code(c) => c as u32,
+                    ExitStatus::Signal(c) => c as u32 + EXIT_SIGNAL_OFFSET,
+                }
+                .to_string()
+                .into(),
+            )),
 
-            Parameter::Positional(0) => Some(Fields::Single(env.name().clone())),
+            Parameter::Positional(0) => {
+                let arg0 = env.arg(0).unwrap_or_else(|| env.name());
+                Some(Fields::Single(arg0.clone()))
+            }
             Parameter::Positional(p) => env.arg(p as usize).cloned().map(Fields::Single),
-            Parameter::Var(ref var)  => env.var(var.borrow()).cloned().map(Fields::Single),
+            Parameter::Var(ref var) => env.var(var.borrow()).cloned().map(Fields::Single),
         };
 
         ret.map(|f| {
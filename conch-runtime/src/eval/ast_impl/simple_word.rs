@@ -1,17 +1,19 @@
-use crate::env::{StringWrapper, VariableEnvironment};
+use crate::env::{HomeDirEnvironment, ShellOptionsEnvironment, StringWrapper, VariableEnvironment};
+use crate::error::ExpansionError;
 use crate::eval::{Fields, ParamEval, TildeExpansion, WordEval, WordEvalConfig, WordEvalResult};
-use crate::HOME;
 use conch_parser::ast::SimpleWord;
 use conch_parser::ast::SimpleWord::*;
 use std::borrow::Borrow;
+use std::fmt;
 
 #[async_trait::async_trait]
 impl<T, P, S, E> WordEval<E> for SimpleWord<T, P, S>
 where
     T: 'static + Send + Sync + StringWrapper,
-    P: Send + Sync + ParamEval<E, EvalResult = T>,
+    P: Send + Sync + ParamEval<E, EvalResult = T> + fmt::Display,
     S: Send + Sync + WordEval<E, EvalResult = T>,
-    E: ?Sized + Send + VariableEnvironment<Var = T>,
+    S::Error: From<ExpansionError>,
+    E: ?Sized + Send + HomeDirEnvironment + ShellOptionsEnvironment + VariableEnvironment<Var = T>,
     E::VarName: Borrow<String>,
 {
     type EvalResult = T;
@@ -38,14 +40,19 @@ where
                     // Note: even though we are expanding the equivalent of `$HOME`, a tilde
                     // expansion is NOT considered a parameter expansion, and therefore
                     // should not be subjected to field splitting.
-                    env.var(&HOME)
-                        .map_or(Fields::Zero, |f| Fields::Single(f.clone()))
+                    env.home_dir().map_or(Fields::Zero, |home| {
+                        Fields::Single(home.into_owned().into())
+                    })
                 }
             },
 
-            Param(p) => p
-                .eval(cfg.split_fields_further, env)
-                .unwrap_or(Fields::Zero),
+            Param(p) => match p.eval(cfg.split_fields_further, env) {
+                Some(fields) => fields,
+                None if env.options().nounset => {
+                    return Err(ExpansionError::UnsetParameter(p.to_string()).into());
+                }
+                None => Fields::Zero,
+            },
 
             Subst(s) => return s.eval_with_config(env, cfg).await,
         };
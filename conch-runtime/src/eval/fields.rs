@@ -1,6 +1,7 @@
 use crate::env::{StringWrapper, VariableEnvironment};
 use crate::IFS_DEFAULT;
 use std::borrow::Borrow;
+use std::iter::Peekable;
 use std::vec;
 
 lazy_static::lazy_static! {
@@ -74,19 +75,8 @@ impl<T: StringWrapper> Fields<T> {
             Fields::Zero => String::new().into(),
             Fields::Single(s) => s,
             Fields::At(v) | Fields::Star(v) | Fields::Split(v) => {
-                let sep = env.var(&IFS).map(|s| s.borrow().as_str()).map_or(" ", |s| {
-                    if s.is_empty() {
-                        ""
-                    } else {
-                        &s[0..1]
-                    }
-                });
-
-                v.iter()
-                    .map(StringWrapper::as_str)
-                    .collect::<Vec<_>>()
-                    .join(sep)
-                    .into()
+                let ifs = env.var(&IFS).map(|s| s.borrow().as_str());
+                join_fields(v.iter().map(StringWrapper::as_str), ifs).into()
             }
         }
     }
@@ -144,6 +134,33 @@ impl<T> IntoIterator for Fields<T> {
     }
 }
 
+/// Joins fields as is done when expanding `$*` within double quotes,
+/// given the value of `$IFS` (or `None` if it is unset).
+///
+/// Fields are separated by the first character of `$IFS` (which may span
+/// multiple bytes), by a space if `$IFS` is unset, or are concatenated
+/// if `$IFS` is set but empty. Any empty fields are preserved.
+pub fn join_fields<I>(fields: I, ifs: Option<&str>) -> String
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    let sep = match ifs {
+        None => " ",
+        Some(ifs) => ifs.chars().next().map_or("", |c| &ifs[..c.len_utf8()]),
+    };
+
+    let mut ret = String::new();
+    for (i, field) in fields.into_iter().enumerate() {
+        if i > 0 {
+            ret.push_str(sep);
+        }
+        ret.push_str(field.as_ref());
+    }
+
+    ret
+}
+
 /// Actual implementation of `split_fields`.
 fn split_fields_internal<T, E: ?Sized>(words: Vec<T>, env: &E) -> Vec<T>
 where
@@ -166,7 +183,7 @@ where
             continue;
         }
 
-        let mut iter = word.chars().enumerate().peekable();
+        let mut iter = word.char_indices().peekable();
         loop {
             let start;
             loop {
@@ -201,7 +218,7 @@ where
                     }
                     Some((idx, c)) => {
                         if ifs.contains(c) {
-                            end = Some(idx);
+                            end = Some((idx, c));
                             break;
                         }
                     }
@@ -209,7 +226,7 @@ where
             }
 
             let field = match end {
-                Some(end) => &word[start..end],
+                Some((end, _)) => &word[start..end],
                 None => &word[start..],
             };
 
@@ -218,13 +235,27 @@ where
             // Since now we've hit an IFS character, we need to also skip past
             // any adjacent IFS whitespace as well. This also conveniently
             // ignores any trailing IFS whitespace in the input as well.
-            loop {
+            let skip_whitespace = |iter: &mut Peekable<_>| loop {
                 match iter.peek() {
                     Some(&(_, c)) if whitespace.contains(&c) => {
                         iter.next();
                     }
                     Some(_) | None => break,
                 }
+            };
+            skip_whitespace(&mut iter);
+
+            // If the field was terminated by IFS whitespace, a single non-whitespace
+            // IFS character (and any whitespace around it) is part of the same delimiter.
+            if let Some((_, c)) = end {
+                if whitespace.contains(&c) {
+                    if let Some(&(_, c)) = iter.peek() {
+                        if ifs.contains(c) {
+                            iter.next();
+                            skip_whitespace(&mut iter);
+                        }
+                    }
+                }
             }
         }
     }
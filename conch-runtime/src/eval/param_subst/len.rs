@@ -14,7 +14,7 @@ where
     let len = match param.eval(false, env).unwrap_or(Fields::Zero) {
         Fields::Zero => 0,
 
-        Fields::Single(s) => s.as_str().len(),
+        Fields::Single(s) => s.as_str().chars().count(),
 
         Fields::At(v) | Fields::Star(v) => v.len(),
 
@@ -22,7 +22,7 @@ where
         // this variant should never occur, but since we cannot control
         // external implementations, we'll fallback somewhat gracefully
         // rather than panicking.
-        Fields::Split(v) => v.into_iter().fold(0, |l, s| l + s.as_str().len()),
+        Fields::Split(v) => v.into_iter().fold(0, |l, s| l + s.as_str().chars().count()),
     };
 
     len.to_string().into()
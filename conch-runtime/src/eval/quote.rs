@@ -0,0 +1,48 @@
+use std::borrow::Cow;
+
+/// Checks if a byte can appear unquoted in a shell word without
+/// being interpreted specially by the shell.
+fn is_safe(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric()
+        || matches!(
+            byte,
+            b'_' | b'-' | b'+' | b'=' | b'.' | b',' | b'/' | b':' | b'@' | b'%'
+        )
+}
+
+/// Quotes a string such that the shell will interpret it as a single,
+/// literal word (i.e. the equivalent of `printf %q`).
+///
+/// Strings which do not contain any special characters are returned as is,
+/// otherwise the string is wrapped in single quotes (with any single quotes
+/// within it escaped as `'\''`), which is portable across all POSIX shells.
+pub fn quote_for_shell(s: &str) -> Cow<'_, str> {
+    match quote_bytes_for_shell(s.as_bytes()) {
+        Cow::Borrowed(_) => Cow::Borrowed(s),
+        // NB: the quoted string only ever has ASCII bytes
+        // inserted at character boundaries of the original
+        Cow::Owned(quoted) => Cow::Owned(String::from_utf8(quoted).expect("invalid utf8")),
+    }
+}
+
+/// Quotes arbitrary bytes such that the shell will interpret them as a
+/// single, literal word (see `quote_for_shell` for more details).
+pub fn quote_bytes_for_shell(bytes: &[u8]) -> Cow<'_, [u8]> {
+    if !bytes.is_empty() && bytes.iter().copied().all(is_safe) {
+        return Cow::Borrowed(bytes);
+    }
+
+    let mut quoted = Vec::with_capacity(bytes.len() + 2);
+    quoted.push(b'\'');
+
+    for &byte in bytes {
+        if byte == b'\'' {
+            quoted.extend_from_slice(b"'\\''");
+        } else {
+            quoted.push(byte);
+        }
+    }
+
+    quoted.push(b'\'');
+    Cow::Owned(quoted)
+}
@@ -1,7 +1,7 @@
 //! A module which defines evaluating any kind of redirection.
 
 use crate::env::{
-    AsyncIoEnvironment, FileDescEnvironment, FileDescOpener, IsInteractiveEnvironment,
+    AsyncIoEnvironment, FileDescEnvironment, FileDescOpener, IsInteractiveEnvironment, OpenFlags,
     StringWrapper, WorkingDirectoryEnvironment,
 };
 use crate::error::RedirectionError;
@@ -10,7 +10,6 @@ use crate::io::Permissions;
 use crate::{Fd, STDIN_FILENO, STDOUT_FILENO};
 use futures_core::future::BoxFuture;
 use std::borrow::Cow;
-use std::fs::OpenOptions;
 use std::io;
 use std::path::Path;
 
@@ -30,6 +29,15 @@ pub enum RedirectAction<T> {
 }
 
 impl<T> RedirectAction<T> {
+    /// Returns the file descriptor affected by this action.
+    pub fn fd(&self) -> Fd {
+        match *self {
+            RedirectAction::Close(fd)
+            | RedirectAction::Open(fd, _, _)
+            | RedirectAction::HereDoc(fd, _) => fd,
+        }
+    }
+
     /// Applies changes to a given environment as appropriate.
     pub fn apply<E>(self, env: &mut E) -> io::Result<()>
     where
@@ -125,7 +133,7 @@ macro_rules! join_path {
 async fn redirect<W, E>(
     fd: Fd,
     path: W,
-    opts: &OpenOptions,
+    flags: OpenFlags,
     perms: Permissions,
     env: &mut E,
 ) -> Result<RedirectAction<E::FileHandle>, W::Error>
@@ -145,7 +153,7 @@ where
 
     let ret = env
         // FIXME: on unix set file permission bits based on umask
-        .open_path(&*actual_path, &opts)
+        .open_path_with_flags(&*actual_path, flags, true)
         .map(|fdesc| RedirectAction::Open(fd, E::FileHandle::from(fdesc), perms))
         .map_err(|err| RedirectionError::Io(err, Some(requested_path.into_owned())));
 
@@ -173,7 +181,7 @@ where
     let fd = fd.unwrap_or(STDIN_FILENO);
     let perms = Permissions::Read;
 
-    redirect(fd, path, &perms.into(), perms, env).await
+    redirect(fd, path, perms.into(), perms, env).await
 }
 
 /// Evaluate a redirect which will open a file for writing, failing if the
@@ -222,7 +230,7 @@ where
     let fd = fd.unwrap_or(STDIN_FILENO);
     let perms = Permissions::ReadWrite;
 
-    redirect(fd, path, &perms.into(), perms, env).await
+    redirect(fd, path, perms.into(), perms, env).await
 }
 
 /// Evaluate a redirect which will open a file for writing, regardless if the
@@ -247,7 +255,7 @@ where
     let fd = fd.unwrap_or(STDOUT_FILENO);
     let perms = Permissions::Write;
 
-    redirect(fd, path, &perms.into(), perms, env).await
+    redirect(fd, path, perms.into(), perms, env).await
 }
 
 /// Evaluate a redirect which will open a file in append mode.
@@ -269,10 +277,12 @@ where
     E::FileHandle: From<E::OpenedFileHandle>,
 {
     let fd = fd.unwrap_or(STDOUT_FILENO);
-    let mut opts = OpenOptions::new();
-    opts.append(true);
+    let flags = OpenFlags {
+        append: true,
+        ..OpenFlags::default()
+    };
 
-    redirect(fd, path, &opts, Permissions::Write, env).await
+    redirect(fd, path, flags, Permissions::Write, env).await
 }
 
 async fn redirect_dup<W, E>(
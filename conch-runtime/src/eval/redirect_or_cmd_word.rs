@@ -1,8 +1,11 @@
 #![allow(unused_qualifications)] // False positives with thiserror derive
 
-use crate::env::{AsyncIoEnvironment, FileDescEnvironment, FileDescOpener, RedirectEnvRestorer};
+use crate::env::{
+    AsyncIoEnvironment, FileDescEnvironment, FileDescOpener, RedirectEnvRestorer, TaintEnvironment,
+    TaintTracker,
+};
 use crate::error::{IsFatalError, RedirectionError};
-use crate::eval::{RedirectEval, WordEval};
+use crate::eval::{RedirectEval, TaintedFields, WordEval};
 use std::error::Error;
 
 /// Represents a redirect or a command word.
@@ -53,6 +56,49 @@ pub async fn eval_redirects_or_cmd_words_with_restorer<'a, R, W, I, E, RR>(
     restorer: &mut RR,
     words: I,
 ) -> Result<Vec<W::EvalResult>, EvalRedirectOrCmdWordError<R::Error, W::Error>>
+where
+    I: Iterator<Item = RedirectOrCmdWord<R, W>>,
+    R: RedirectEval<E, Handle = E::FileHandle>,
+    R::Error: 'static + Error + From<RedirectionError>,
+    W: WordEval<E>,
+    W::Error: 'static + Error,
+    E: 'a + ?Sized + Send + Sync + FileDescEnvironment,
+    RR: ?Sized + Send + Sync + AsyncIoEnvironment + FileDescOpener + RedirectEnvRestorer<'a, E>,
+    RR::FileHandle: From<RR::OpenedFileHandle>,
+    RR::IoHandle: Send + From<RR::FileHandle>,
+{
+    do_eval(restorer, words, None)
+        .await
+        .map(TaintedFields::into_fields)
+}
+
+/// Evaluate a series of redirections and shell words (just like
+/// `eval_redirects_or_cmd_words_with_restorer`), while also tracking which
+/// of the resulting fields were derived from any tainted variables.
+pub async fn eval_redirects_or_cmd_words_with_taint<'a, R, W, I, E, RR>(
+    restorer: &mut RR,
+    words: I,
+) -> Result<TaintedFields<W::EvalResult>, EvalRedirectOrCmdWordError<R::Error, W::Error>>
+where
+    I: Iterator<Item = RedirectOrCmdWord<R, W>>,
+    R: RedirectEval<E, Handle = E::FileHandle>,
+    R::Error: 'static + Error + From<RedirectionError>,
+    W: WordEval<E>,
+    W::Error: 'static + Error,
+    E: 'a + ?Sized + Send + Sync + FileDescEnvironment + TaintEnvironment,
+    RR: ?Sized + Send + Sync + AsyncIoEnvironment + FileDescOpener + RedirectEnvRestorer<'a, E>,
+    RR::FileHandle: From<RR::OpenedFileHandle>,
+    RR::IoHandle: Send + From<RR::FileHandle>,
+{
+    let tracker = restorer.get().taint_tracker().clone();
+    do_eval(restorer, words, Some(tracker)).await
+}
+
+async fn do_eval<'a, R, W, I, E, RR>(
+    restorer: &mut RR,
+    words: I,
+    tracker: Option<TaintTracker>,
+) -> Result<TaintedFields<W::EvalResult>, EvalRedirectOrCmdWordError<R::Error, W::Error>>
 where
     I: Iterator<Item = RedirectOrCmdWord<R, W>>,
     R: RedirectEval<E, Handle = E::FileHandle>,
@@ -67,10 +113,10 @@ where
     let (lo, hi) = words.size_hint();
     let size_hint = hi.unwrap_or(lo);
 
-    let mut results = Vec::with_capacity(size_hint);
+    let mut results = TaintedFields::with_capacity(size_hint);
 
     for w in words {
-        if let Err(e) = eval(restorer, w, &mut results).await {
+        if let Err(e) = eval(restorer, w, tracker.as_ref(), &mut results).await {
             restorer.restore_redirects();
             return Err(e);
         }
@@ -82,7 +128,8 @@ where
 async fn eval<'r, 'a: 'r, W, R, E, RR>(
     restorer: &'r mut RR,
     candidate: RedirectOrCmdWord<R, W>,
-    results: &mut Vec<W::EvalResult>,
+    tracker: Option<&TaintTracker>,
+    results: &mut TaintedFields<W::EvalResult>,
 ) -> Result<(), EvalRedirectOrCmdWordError<R::Error, W::Error>>
 where
     R: RedirectEval<E, Handle = E::FileHandle>,
@@ -96,11 +143,18 @@ where
 {
     match candidate {
         RedirectOrCmdWord::CmdWord(w) => {
+            let before = tracker.map(TaintTracker::expansions);
             let fields = w
                 .eval(restorer.get_mut())
                 .await
-                .map_err(EvalRedirectOrCmdWordError::CmdWord)?;
-            results.extend(fields.await);
+                .map_err(EvalRedirectOrCmdWordError::CmdWord)?
+                .await;
+
+            let tainted = match (tracker, before) {
+                (Some(tracker), Some(before)) => tracker.is_tainted_since(before),
+                _ => false,
+            };
+            results.extend(fields, tainted);
         }
         RedirectOrCmdWord::Redirect(r) => {
             let action = r
@@ -108,8 +162,9 @@ where
                 .await
                 .map_err(EvalRedirectOrCmdWordError::Redirect)?;
 
+            let fd = action.fd();
             if let Err(e) = action.apply(restorer) {
-                let err = R::Error::from(RedirectionError::Io(e, None));
+                let err = R::Error::from(RedirectionError::fd_io(e, fd));
                 return Err(EvalRedirectOrCmdWordError::Redirect(err));
             }
         }
@@ -1,11 +1,13 @@
 #![allow(unused_qualifications)] // False positives with thiserror derive
 
 use crate::env::{
-    AsyncIoEnvironment, ExportedVariableEnvironment, FileDescEnvironment, FileDescOpener,
-    RedirectEnvRestorer, VarEnvRestorer, VariableEnvironment,
+    AsyncIoEnvironment, EnvChangeSet, ExportedVariableEnvironment, FileDescEnvironment,
+    FileDescOpener, RedirectEnvRestorer, ScopedFrame, ScopedRestorer, TaintEnvironment,
+    VarEnvRestorer, VariableEnvironment,
 };
 use crate::error::{IsFatalError, RedirectionError};
 use crate::eval::{eval_as_assignment, RedirectEval, WordEval};
+use crate::Fd;
 use std::borrow::Borrow;
 use std::error::Error;
 
@@ -98,6 +100,100 @@ where
     Ok(())
 }
 
+/// Evaluate a series of redirections and variable assignments, staging their
+/// changes instead of leaving them applied to the environment.
+///
+/// Each redirection and assignment is temporarily applied (within a new frame of
+/// the provided restorer) while the remainder are evaluated, such that later
+/// redirections and assignments can observe the effects of earlier ones (e.g.
+/// `foo=bar baz=$foo` or `>file 2>&1`). Once evaluation completes, all of the
+/// changes are undone, and the resulting values of all affected variables and
+/// file descriptors are returned as an `EnvChangeSet`, which the caller can
+/// apply (e.g. to the same restorer) all at once.
+///
+/// Unlike `eval_redirects_or_var_assignments_with_restorer`, the environment is
+/// never left partially modified, even if the returned future is cancelled
+/// before it resolves. If `export_vars` is specified, any variables to be inserted
+/// or updated will have their exported status set as specified. Otherwise,
+/// variables will use their existing exported status.
+///
+/// Any variables whose values were derived from tainted variables (see
+/// `TaintEnvironment`) are staged to be marked as tainted as well.
+#[allow(clippy::type_complexity)]
+pub async fn eval_redirects_or_var_assignments_staged<'a, R, V, W, I, E, RR>(
+    export_vars: Option<bool>,
+    vars: I,
+    restorer: &mut RR,
+) -> Result<
+    EnvChangeSet<E::VarName, E::Var, E::FileHandle>,
+    EvalRedirectOrVarAssigError<R::Error, W::Error>,
+>
+where
+    I: Iterator<Item = RedirectOrVarAssig<R, V, W>>,
+    R: RedirectEval<E, Handle = E::FileHandle>,
+    R::Error: 'static + Error + From<RedirectionError>,
+    W: WordEval<E>,
+    W::Error: 'static + Error,
+    E: 'a + ?Sized + Send + Sync + FileDescEnvironment + TaintEnvironment,
+    E::FileHandle: Clone,
+    E::VarName: Clone + Borrow<String> + From<V>,
+    E::Var: Clone + Borrow<String> + From<W::EvalResult>,
+    RR: ?Sized
+        + Send
+        + AsyncIoEnvironment
+        + FileDescOpener
+        + ExportedVariableEnvironment
+        + RedirectEnvRestorer<'a, E>
+        + ScopedRestorer<'a, E>
+        + VarEnvRestorer<'a, E>,
+    RR::FileHandle: From<RR::OpenedFileHandle>,
+    RR::IoHandle: Send + From<RR::FileHandle>,
+{
+    let (lo, hi) = vars.size_hint();
+    let mut changes = EnvChangeSet::with_capacity(hi.unwrap_or(lo));
+
+    // NB: the frame is popped (and thus all changes undone) when the scope is
+    // dropped, regardless of whether we complete, fail, or get cancelled
+    let mut scope = ScopedFrame::new(restorer);
+    let restorer = &mut *scope;
+
+    for var in vars {
+        match var {
+            RedirectOrVarAssig::VarAssig(key, val) => {
+                let tracker = restorer.get().taint_tracker().clone();
+                let before = tracker.expansions();
+
+                let (key, val) = eval_var_assig(key, val, restorer.get_mut()).await?;
+                if tracker.is_tainted_since(before) {
+                    changes.set_tainted(key.clone());
+                }
+
+                match export_vars {
+                    Some(export) => restorer.set_exported_var(key.clone(), val, export),
+                    None => restorer.set_var(key.clone(), val),
+                };
+
+                // NB: stage whatever the environment actually ended up with
+                // (e.g. readonly variables may have refused the assignment)
+                if let Some((val, exported)) = restorer.exported_var(&key) {
+                    let val = val.clone();
+                    changes.set_exported_var(key, val, exported);
+                }
+            }
+            RedirectOrVarAssig::Redirect(r) => {
+                let fd = eval_redirect(r, restorer).await?;
+                match restorer.file_desc(fd) {
+                    Some((handle, perms)) => changes.set_file_desc(fd, handle.clone(), perms),
+                    None => changes.close_file_desc(fd),
+                }
+            }
+        }
+    }
+
+    drop(scope);
+    Ok(changes)
+}
+
 async fn eval<'r, 'a: 'r, R, V, W, E, RR>(
     export_vars: Option<bool>,
     restorer: &'r mut RR,
@@ -122,32 +218,69 @@ where
 {
     match candidate {
         RedirectOrVarAssig::VarAssig(key, val) => {
-            let val = match val {
-                None => W::EvalResult::from(String::new()),
-                Some(val) => eval_as_assignment(val, restorer.get_mut())
-                    .await
-                    .map_err(EvalRedirectOrVarAssigError::VarAssig)?,
-            };
-
-            let key = E::VarName::from(key);
-            let val = E::Var::from(val);
+            let (key, val) = eval_var_assig(key, val, restorer.get_mut()).await?;
             match export_vars {
                 Some(export) => restorer.set_exported_var(key, val, export),
                 None => restorer.set_var(key, val),
             };
         }
         RedirectOrVarAssig::Redirect(r) => {
-            let action = r
-                .eval(restorer.get_mut())
-                .await
-                .map_err(EvalRedirectOrVarAssigError::Redirect)?;
-
-            if let Err(e) = action.apply(restorer) {
-                let err = R::Error::from(RedirectionError::Io(e, None));
-                return Err(EvalRedirectOrVarAssigError::Redirect(err));
-            }
+            eval_redirect(r, restorer).await?;
         }
     }
 
     Ok(())
 }
+
+/// Evaluates the name and value of a variable assignment (without assigning it).
+async fn eval_var_assig<R, V, W, E>(
+    key: V,
+    val: Option<W>,
+    env: &mut E,
+) -> Result<(E::VarName, E::Var), EvalRedirectOrVarAssigError<R, W::Error>>
+where
+    R: 'static + Error,
+    W: WordEval<E>,
+    W::Error: 'static + Error,
+    E: ?Sized + VariableEnvironment,
+    E::VarName: Borrow<String> + From<V>,
+    E::Var: Borrow<String> + From<W::EvalResult>,
+{
+    let val = match val {
+        None => W::EvalResult::from(String::new()),
+        Some(val) => eval_as_assignment(val, env)
+            .await
+            .map_err(EvalRedirectOrVarAssigError::VarAssig)?,
+    };
+
+    Ok((E::VarName::from(key), E::Var::from(val)))
+}
+
+/// Evaluates a redirect and applies it to the restorer,
+/// returning the file descriptor it affected.
+async fn eval_redirect<'a, R, W, E, RR>(
+    r: R,
+    restorer: &mut RR,
+) -> Result<Fd, EvalRedirectOrVarAssigError<R::Error, W>>
+where
+    R: RedirectEval<E, Handle = E::FileHandle>,
+    R::Error: 'static + Error + From<RedirectionError>,
+    W: 'static + Error,
+    E: 'a + ?Sized + FileDescEnvironment,
+    RR: ?Sized + AsyncIoEnvironment + FileDescOpener + RedirectEnvRestorer<'a, E>,
+    RR::FileHandle: From<RR::OpenedFileHandle>,
+    RR::IoHandle: From<RR::FileHandle>,
+{
+    let action = r
+        .eval(restorer.get_mut())
+        .await
+        .map_err(EvalRedirectOrVarAssigError::Redirect)?;
+
+    let fd = action.fd();
+    if let Err(e) = action.apply(restorer) {
+        let err = R::Error::from(RedirectionError::fd_io(e, fd));
+        return Err(EvalRedirectOrVarAssigError::Redirect(err));
+    }
+
+    Ok(fd)
+}
@@ -0,0 +1,125 @@
+use crate::env::TaintEnvironment;
+use crate::eval::WordEval;
+use std::slice;
+
+/// The fields resulting from evaluating one or more words, along with whether
+/// each field was derived from the values of any tainted variables.
+///
+/// A field is considered tainted if any tainted variables were expanded while
+/// evaluating the word it resulted from, or if the word was evaluated with a
+/// tainted `TaintTracker` (see `TaintEnvironment`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaintedFields<T> {
+    fields: Vec<T>,
+    tainted: Vec<bool>,
+}
+
+impl<T> TaintedFields<T> {
+    /// Creates an empty collection of fields.
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// Creates an empty collection of fields with the specified capacity.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            fields: Vec::with_capacity(capacity),
+            tainted: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Appends the fields resulting from evaluating a single word.
+    pub fn extend<I: IntoIterator<Item = T>>(&mut self, fields: I, tainted: bool) {
+        self.fields.extend(fields);
+        self.tainted.resize(self.fields.len(), tainted);
+    }
+
+    /// Returns the number of fields.
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Checks if there are no fields.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Returns all fields, regardless of whether they are tainted.
+    pub fn fields(&self) -> &[T] {
+        &self.fields
+    }
+
+    /// Returns whether each field is tainted, in the same order as `fields`.
+    pub fn taint(&self) -> &[bool] {
+        &self.tainted
+    }
+
+    /// Checks if any of the fields are tainted.
+    pub fn is_tainted(&self) -> bool {
+        self.tainted.contains(&true)
+    }
+
+    /// Checks if the field at the specified index is tainted.
+    ///
+    /// Out of bounds indices are never considered tainted.
+    pub fn is_field_tainted(&self, index: usize) -> bool {
+        self.tainted.get(index).copied().unwrap_or(false)
+    }
+
+    /// Returns an iterator over the tainted fields only.
+    pub fn tainted_fields(&self) -> impl Iterator<Item = &T> {
+        self.iter()
+            .filter_map(|(field, tainted)| if tainted { Some(field) } else { None })
+    }
+
+    /// Returns an iterator over all fields, along with whether each is tainted.
+    pub fn iter(&self) -> impl Iterator<Item = (&T, bool)> {
+        self.fields.iter().zip(self.tainted.iter().copied())
+    }
+
+    /// Unwraps the fields along with whether each is tainted.
+    pub fn into_parts(self) -> (Vec<T>, Vec<bool>) {
+        (self.fields, self.tainted)
+    }
+
+    /// Unwraps the fields, discarding whether they are tainted.
+    pub fn into_fields(self) -> Vec<T> {
+        self.fields
+    }
+}
+
+impl<T> Default for TaintedFields<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a TaintedFields<T> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.fields.iter()
+    }
+}
+
+/// Evaluates a word (see `WordEval::eval`) and tracks whether the resulting
+/// fields were derived from the values of any tainted variables.
+pub async fn eval_with_taint<W, E>(
+    word: W,
+    env: &mut E,
+) -> Result<TaintedFields<W::EvalResult>, W::Error>
+where
+    W: WordEval<E>,
+    E: ?Sized + TaintEnvironment,
+{
+    let tracker = env.taint_tracker().clone();
+    let before = tracker.expansions();
+
+    let future = word.eval(env).await?;
+    let fields = future.await;
+
+    let mut ret = TaintedFields::new();
+    ret.extend(fields, tracker.is_tainted_since(before));
+    Ok(ret)
+}
@@ -11,6 +11,7 @@ pub const EXIT_CMD_NOT_EXECUTABLE: ExitStatus = ExitStatus::Code(126);
 pub const EXIT_CMD_NOT_FOUND: ExitStatus = ExitStatus::Code(127);
 
 /// Describes the result of a process after it has terminated.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
 pub enum ExitStatus {
     /// Normal termination with an exit code.
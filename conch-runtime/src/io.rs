@@ -1,17 +1,24 @@
 //! Defines interfaces and methods for doing OS agnostic file IO operations.
 
+mod async_file_desc;
 mod file_desc_wrapper;
+mod line_reader;
 mod permissions;
 mod pipe;
+mod terminal;
 
 use crate::sys;
 use crate::IntoInner;
 use std::io::{Read, Result, Seek, SeekFrom, Write};
 use std::process::Stdio;
 
+pub(crate) use self::async_file_desc::supports_evented_io;
+pub use self::async_file_desc::AsyncFileDesc;
 pub use self::file_desc_wrapper::FileDescWrapper;
+pub use self::line_reader::LineReader;
 pub use self::permissions::Permissions;
-pub use self::pipe::Pipe;
+pub use self::pipe::{Pipe, PipeOptions};
+pub use self::terminal::{TerminalMode, WindowSize};
 pub use crate::sys::io::getpid;
 
 /// A wrapper around an owned OS file primitive. The wrapper
@@ -62,6 +69,20 @@ impl FileDesc {
         Ok(Self::from_inner(self.inner().duplicate()?))
     }
 
+    /// Sets the close-on-exec flag of the descriptor to the desired state.
+    ///
+    /// On Unix systems this is the `FD_CLOEXEC` flag, while on Windows it is
+    /// the inverse of the handle's inheritance flag.
+    pub fn set_cloexec(&self, set: bool) -> Result<()> {
+        self.inner().set_cloexec(set)
+    }
+
+    /// Checks whether the close-on-exec flag of the descriptor is set, i.e. whether
+    /// the descriptor will *not* be inherited by spawned child processes.
+    pub fn is_cloexec(&self) -> Result<bool> {
+        self.inner().is_cloexec()
+    }
+
     /// Sets the `O_NONBLOCK` flag on the descriptor to the desired state.
     ///
     /// Specifiying `true` will set the file descriptor in non-blocking mode,
@@ -71,6 +92,33 @@ impl FileDesc {
         self.inner_mut().set_nonblock(set)
     }
 
+    /// Checks whether the underlying OS file primitive refers to a terminal.
+    pub fn is_terminal(&self) -> bool {
+        self.inner().is_terminal()
+    }
+
+    /// Queries the current mode of the terminal this descriptor refers to.
+    ///
+    /// An error is returned if the descriptor does not refer to a terminal.
+    pub fn terminal_mode(&self) -> Result<TerminalMode> {
+        let (canonical, echo) = self.inner().terminal_mode()?;
+        Ok(TerminalMode { canonical, echo })
+    }
+
+    /// Changes the mode of the terminal this descriptor refers to.
+    ///
+    /// Note that the terminal mode is shared by *all* handles to the same
+    /// terminal, including those held by other processes.
+    pub fn set_terminal_mode(&self, mode: TerminalMode) -> Result<()> {
+        self.inner().set_terminal_mode(mode.canonical, mode.echo)
+    }
+
+    /// Queries the window size of the terminal this descriptor refers to.
+    pub fn window_size(&self) -> Result<WindowSize> {
+        let (columns, lines) = self.inner().window_size()?;
+        Ok(WindowSize { columns, lines })
+    }
+
     fn read(&self, buf: &mut [u8]) -> Result<usize> {
         self.inner().read_inner(buf)
     }
@@ -158,6 +206,23 @@ impl<'a> Seek for &'a FileDesc {
     }
 }
 
+/// Copies all remaining data from one file descriptor to another,
+/// returning the number of bytes copied.
+///
+/// On Linux the data is copied within the kernel (via `splice` or `sendfile`)
+/// whenever the descriptors support it, otherwise (and on other platforms) the
+/// data is copied through an intermediate buffer. Both descriptors are expected
+/// to be in blocking mode.
+pub fn copy_fd(from: &FileDesc, to: &FileDesc) -> Result<u64> {
+    let (copied, done) = sys::io::copy_in_kernel(from.inner(), to.inner())?;
+    if done {
+        return Ok(copied);
+    }
+
+    let (mut from, mut to) = (from, to);
+    Ok(copied + std::io::copy(&mut from, &mut to)?)
+}
+
 /// Duplicates handles for (stdin, stdout, stderr) and returns them in that order.
 pub(crate) fn dup_stdio() -> Result<(FileDesc, FileDesc, FileDesc)> {
     let (stdin, stdout, stderr) = sys::io::dup_stdio()?;
@@ -0,0 +1,137 @@
+use crate::io::FileDesc;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// An adapter which allows reading from and writing to a `FileDesc` via
+/// Tokio's `AsyncRead` and `AsyncWrite` traits, e.g. so that pipes created
+/// by the shell can be used with arbitrary async code.
+///
+/// On Unix systems, file descriptors which support evented IO (e.g. pipes) are
+/// registered with Tokio's reactor. Any others (e.g. regular files, or any handle
+/// on Windows) fall back to performing blocking operations on a background thread.
+#[derive(Debug)]
+pub struct AsyncFileDesc(AsyncIo);
+
+#[derive(Debug)]
+enum AsyncIo {
+    /// An evented file descriptor registered with tokio.
+    #[cfg(unix)]
+    PollEvented(tokio::io::PollEvented<FileDesc>),
+    /// Evented IO not supported, use a blocking operation
+    File(tokio::fs::File),
+}
+
+impl AsyncFileDesc {
+    /// Wraps a file descriptor so that it can be used asynchronously.
+    ///
+    /// Must be called within the context of a Tokio runtime.
+    pub fn new(fd: FileDesc) -> Self {
+        let io = match try_as_evented(&fd) {
+            Some(io) => io,
+            None => AsyncIo::File(tokio::fs::File::from_std(convert_to_file(fd))),
+        };
+
+        AsyncFileDesc(io)
+    }
+}
+
+#[cfg(not(unix))]
+fn try_as_evented(_: &FileDesc) -> Option<AsyncIo> {
+    None
+}
+
+#[cfg(unix)]
+fn try_as_evented(fd: &FileDesc) -> Option<AsyncIo> {
+    if !supports_evented_io(fd) {
+        return None;
+    }
+
+    fd.duplicate()
+        .and_then(|mut fd| {
+            fd.set_nonblock(true)?;
+            tokio::io::PollEvented::new(fd)
+        })
+        .map(AsyncIo::PollEvented)
+        .ok()
+}
+
+/// Checks whether an `AsyncFileDesc` wrapping this descriptor would register
+/// it with Tokio's reactor, or fall back to a blocking background thread.
+#[cfg(not(unix))]
+pub(crate) fn supports_evented_io(_: &FileDesc) -> bool {
+    false
+}
+
+/// Checks whether an `AsyncFileDesc` wrapping this descriptor would register
+/// it with Tokio's reactor, or fall back to a blocking background thread.
+#[cfg(unix)]
+pub(crate) fn supports_evented_io(fd: &FileDesc) -> bool {
+    use crate::sys::cvt_r;
+    use std::mem;
+    use std::os::unix::io::AsRawFd;
+
+    fn get_mode(fd: &FileDesc) -> io::Result<libc::mode_t> {
+        unsafe {
+            let mut stat: libc::stat = mem::zeroed();
+            cvt_r(|| libc::fstat(fd.as_raw_fd(), &mut stat)).map(|_| stat.st_mode)
+        }
+    }
+
+    get_mode(fd)
+        .map(|mode| mode & libc::S_IFMT != libc::S_IFREG)
+        .unwrap_or(false)
+}
+
+#[cfg(unix)]
+fn convert_to_file(fd: FileDesc) -> std::fs::File {
+    use std::os::unix::io::{FromRawFd, IntoRawFd};
+
+    unsafe { FromRawFd::from_raw_fd(fd.into_raw_fd()) }
+}
+
+#[cfg(windows)]
+fn convert_to_file(fd: FileDesc) -> std::fs::File {
+    use std::os::windows::io::{FromRawHandle, IntoRawHandle};
+
+    unsafe { FromRawHandle::from_raw_handle(fd.into_raw_handle()) }
+}
+
+macro_rules! delegate {
+    ($self:ident.$method:ident($($arg:expr),*)) => {
+        match $self.get_mut().0 {
+            #[cfg(unix)]
+            AsyncIo::PollEvented(ref mut io) => Pin::new(io).$method($($arg),*),
+            AsyncIo::File(ref mut io) => Pin::new(io).$method($($arg),*),
+        }
+    };
+}
+
+impl AsyncRead for AsyncFileDesc {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        delegate!(self.poll_read(cx, buf))
+    }
+}
+
+impl AsyncWrite for AsyncFileDesc {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        delegate!(self.poll_write(cx, buf))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        delegate!(self.poll_flush(cx))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        delegate!(self.poll_shutdown(cx))
+    }
+}
@@ -1,4 +1,5 @@
 use crate::io::FileDesc;
+use crate::RefCounted;
 use std::io;
 use std::rc::Rc;
 use std::sync::Arc;
@@ -6,6 +7,10 @@ use std::sync::Arc;
 /// An interface for any wrapper which can be unwrapped into a `FileDesc`.
 pub trait FileDescWrapper: Sized {
     /// Unwrap to an owned `FileDesc` handle.
+    ///
+    /// Shared wrappers (e.g. `Rc` or `Arc`) are unwrapped without any copies if they
+    /// hold the only reference to the handle, otherwise the handle is duplicated
+    /// (see `RefCounted::unwrap_or_duplicate`).
     fn try_unwrap(self) -> io::Result<FileDesc>;
 }
 
@@ -23,12 +28,12 @@ impl FileDescWrapper for Box<FileDesc> {
 
 impl FileDescWrapper for Rc<FileDesc> {
     fn try_unwrap(self) -> io::Result<FileDesc> {
-        Rc::try_unwrap(self).or_else(|rc| rc.duplicate())
+        RefCounted::unwrap_or_duplicate(self, FileDesc::duplicate)
     }
 }
 
 impl FileDescWrapper for Arc<FileDesc> {
     fn try_unwrap(self) -> io::Result<FileDesc> {
-        Arc::try_unwrap(self).or_else(|arc| arc.duplicate())
+        RefCounted::unwrap_or_duplicate(self, FileDesc::duplicate)
     }
 }
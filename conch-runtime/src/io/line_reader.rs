@@ -0,0 +1,163 @@
+use std::io::Result;
+use std::mem;
+use std::str;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// The largest amount of data a `LineReader` will attempt to read at once.
+const MAX_CHUNK_SIZE: usize = 4096;
+
+/// An adapter which reads delimited lines from an async reader.
+///
+/// Any data which has been consumed from the underlying reader, but has not
+/// been yielded yet (e.g. a partially read line), is retained by the reader,
+/// thus it is safe to cancel any read operation (e.g. due to a timeout) and
+/// retry it later (or retrieve the partial data via `take_partial_line`).
+///
+/// By default, data is consumed from the underlying reader one byte at a time,
+/// so that nothing beyond the end of the line is read. This is crucial when
+/// reading from handles which are shared with other commands (e.g. a script's
+/// standard input), as any consumed data would otherwise be lost once the
+/// reader is dropped. If the reader is exclusively owned, a buffer capacity
+/// can be specified via `with_capacity` to read data more efficiently.
+#[derive(Debug)]
+pub struct LineReader<R> {
+    reader: R,
+    delim: u8,
+    chunk_size: usize,
+    /// Data which has been read but not yet inspected for delimiters.
+    pending: Vec<u8>,
+    /// The contents of the line currently being read.
+    line: Vec<u8>,
+    eof: bool,
+}
+
+impl<R> LineReader<R> {
+    /// Creates a new reader which yields lines delimited by `\n` and
+    /// reads from the underlying reader one byte at a time.
+    pub fn new(reader: R) -> Self {
+        Self::with_capacity(reader, 1)
+    }
+
+    /// Creates a new reader which yields lines delimited by `\n` and will
+    /// read up to `capacity` bytes from the underlying reader at a time.
+    pub fn with_capacity(reader: R, capacity: usize) -> Self {
+        LineReader {
+            reader,
+            delim: b'\n',
+            chunk_size: capacity.clamp(1, MAX_CHUNK_SIZE),
+            pending: Vec::new(),
+            line: Vec::new(),
+            eof: false,
+        }
+    }
+
+    /// Changes the delimiter used to separate lines (e.g. for `read -d`).
+    pub fn set_delimiter(&mut self, delim: u8) {
+        self.delim = delim;
+    }
+
+    /// Returns the delimiter which separates lines.
+    pub fn delimiter(&self) -> u8 {
+        self.delim
+    }
+
+    /// Indicates if the underlying reader has reached EOF.
+    pub fn is_eof(&self) -> bool {
+        self.eof
+    }
+
+    /// Returns the data read for the current line so far, which
+    /// will be completed by the next call to `read_line`.
+    pub fn partial_line(&self) -> &[u8] {
+        &self.line
+    }
+
+    /// Takes ownership of the data read for the current line so far,
+    /// as if a full line had been read.
+    pub fn take_partial_line(&mut self) -> Vec<u8> {
+        mem::take(&mut self.line)
+    }
+
+    /// Unwraps the underlying reader.
+    ///
+    /// Note that any buffered data which has not yet been yielded will be lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: AsyncRead + Unpin> LineReader<R> {
+    /// Reads the next line (without its delimiter).
+    ///
+    /// If EOF is reached before a delimiter is found, any data read before it
+    /// is returned as the last line (`is_eof` can be used to differentiate
+    /// this case). `None` is returned once no more data remains.
+    pub async fn read_line(&mut self) -> Result<Option<Vec<u8>>> {
+        self.read_chars(None).await
+    }
+
+    /// Reads the next line (without its delimiter), or until `max_chars`
+    /// UTF-8 characters have been read, whichever comes first.
+    ///
+    /// Invalid UTF-8 sequences are counted as single characters.
+    pub async fn read_line_max_chars(&mut self, max_chars: usize) -> Result<Option<Vec<u8>>> {
+        self.read_chars(Some(max_chars)).await
+    }
+
+    async fn read_chars(&mut self, max_chars: Option<usize>) -> Result<Option<Vec<u8>>> {
+        let mut chars_read = 0;
+        let mut char_start = 0;
+
+        loop {
+            let mut consumed = 0;
+
+            for &byte in &self.pending {
+                if Some(chars_read) == max_chars {
+                    break;
+                }
+
+                consumed += 1;
+
+                if byte == self.delim {
+                    self.pending.drain(..consumed);
+                    return Ok(Some(self.take_partial_line()));
+                }
+
+                self.line.push(byte);
+                if is_complete_char(&self.line[char_start..]) {
+                    chars_read += 1;
+                    char_start = self.line.len();
+                }
+            }
+
+            self.pending.drain(..consumed);
+
+            if Some(chars_read) == max_chars {
+                return Ok(Some(self.take_partial_line()));
+            }
+
+            if self.eof {
+                return if self.line.is_empty() {
+                    Ok(None)
+                } else {
+                    Ok(Some(self.take_partial_line()))
+                };
+            }
+
+            let mut chunk = [0; MAX_CHUNK_SIZE];
+            match self.reader.read(&mut chunk[..self.chunk_size]).await? {
+                0 => self.eof = true,
+                n => self.pending.extend_from_slice(&chunk[..n]),
+            }
+        }
+    }
+}
+
+/// Checks if `bytes` form a complete character. Invalid UTF-8 sequences are
+/// treated as complete, so that they do not swallow any subsequent input.
+fn is_complete_char(bytes: &[u8]) -> bool {
+    match str::from_utf8(bytes) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_some(),
+    }
+}
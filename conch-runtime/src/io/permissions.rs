@@ -4,6 +4,7 @@ use std::io;
 use std::path::Path;
 
 /// An indicator of the read/write permissions of an OS file primitive.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Permissions {
     /// A file was opened for reading only.
@@ -18,19 +18,87 @@ impl Pipe {
     /// however, note that the setting of the flags is nonatomic on BSD systems.
     pub fn new() -> IoResult<Pipe> {
         let (reader, writer) = sys::io::pipe()?;
-        Ok(Pipe {
+        Ok(Pipe::from_inner(reader, writer))
+    }
+
+    /// Creates and returns a new pipe pair whose buffer can hold at least `bytes` bytes.
+    ///
+    /// Larger buffers can improve the throughput of pipelines which move a lot of data.
+    /// On Linux this adjusts the kernel buffer via `F_SETPIPE_SZ`, though if the request
+    /// cannot be satisfied (e.g. it exceeds the system limit) the pipe silently retains
+    /// the default capacity.
+    pub fn with_capacity(bytes: usize) -> IoResult<Pipe> {
+        Pipe::with_options(PipeOptions {
+            capacity: Some(bytes),
+            ..PipeOptions::default()
+        })
+    }
+
+    /// Creates and returns a new pipe pair configured with the specified options.
+    ///
+    /// Any options unsupported by the current platform are silently ignored.
+    pub fn with_options(options: PipeOptions) -> IoResult<Pipe> {
+        let (reader, writer) = sys::io::pipe_with_options(options.capacity, options.packet_mode)?;
+        Ok(Pipe::from_inner(reader, writer))
+    }
+
+    fn from_inner(reader: sys::io::RawIo, writer: sys::io::RawIo) -> Self {
+        Pipe {
             reader: FileDesc::from_inner(reader),
             writer: FileDesc::from_inner(writer),
-        })
+        }
     }
 }
 
+/// Options for configuring a newly created `Pipe`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PipeOptions {
+    /// The minimum number of bytes the pipe's buffer should be able to hold,
+    /// or `None` to use the platform default.
+    pub capacity: Option<usize>,
+    /// Whether the pipe should operate in "packet mode" (`O_DIRECT` on Linux),
+    /// where each write is read back as a separate packet instead of a continuous
+    /// byte stream.
+    pub packet_mode: bool,
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Pipe;
+    use super::{Pipe, PipeOptions};
     use std::io::{Read, Write};
     use std::thread;
 
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn with_capacity() {
+        use std::os::unix::io::AsRawFd;
+
+        let capacity = 256 * 1024;
+        let pipe = Pipe::with_capacity(capacity).unwrap();
+        let actual = unsafe { libc::fcntl(pipe.writer.as_raw_fd(), libc::F_GETPIPE_SZ) };
+        assert!(actual as usize >= capacity);
+
+        // Requests exceeding the system limit should silently fall back
+        Pipe::with_capacity(usize::MAX).unwrap();
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn packet_mode() {
+        let Pipe { reader, mut writer } = Pipe::with_options(PipeOptions {
+            capacity: None,
+            packet_mode: true,
+        })
+        .unwrap();
+
+        writer.write_all(b"foo").unwrap();
+        writer.write_all(b"bar").unwrap();
+
+        let mut buf = [0; 16];
+        assert_eq!(reader.read(&mut buf).unwrap(), 3);
+        assert_eq!(&buf[..3], b"foo");
+    }
+
     #[test]
     fn smoke() {
         let msg = "pipe message";
@@ -0,0 +1,38 @@
+/// A simplified description of a terminal's line discipline, covering
+/// the handful of settings the shell itself needs to toggle (a la `stty`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TerminalMode {
+    /// Input is made available one line at a time, with line editing
+    /// handled by the terminal (i.e. `stty icanon`).
+    pub canonical: bool,
+    /// Characters typed into the terminal are echoed back (i.e. `stty echo`).
+    pub echo: bool,
+}
+
+impl TerminalMode {
+    /// The mode a terminal is normally in: canonical input with echo enabled.
+    pub fn cooked() -> Self {
+        TerminalMode {
+            canonical: true,
+            echo: true,
+        }
+    }
+
+    /// A mode where input is made available one character at a time
+    /// without being echoed back.
+    pub fn raw() -> Self {
+        TerminalMode {
+            canonical: false,
+            echo: false,
+        }
+    }
+}
+
+/// The dimensions of a terminal window, measured in characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WindowSize {
+    /// The number of columns in the terminal window.
+    pub columns: u16,
+    /// The number of lines in the terminal window.
+    pub lines: u16,
+}
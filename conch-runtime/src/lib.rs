@@ -23,7 +23,17 @@
 //! # Supported Cargo Features
 //!
 //! * `conch-parser`: enable implementations on the default AST types provided
-//! by the `conch-parser` crate
+//! by the `conch-parser` crate, as well as the simplified `easy` interface
+//! * `cgroups`: enable `env::Cgroup` for placing spawned executables into a
+//! (Linux) cgroup v2 with optional resource limits
+//! * `metrics`: enable the `env::metrics` module for recording metrics about
+//! executed commands through a pluggable sink
+//! * `spawn-policy`: enable `env::SpawnPolicy` for installing policies (e.g. seccomp
+//! or Landlock rules) within spawned executables on Unix systems, along with
+//! `env::NoNewPrivilegesPolicy` on Linux
+//! * `serde`: implement `Serialize` and `Deserialize` for plain data types such as
+//! `ExitStatus`, execution reports, resource limits and usage, and remote
+//! execution requests
 
 #![doc(html_root_url = "https://docs.rs/conch-runtime/0.1")]
 #![cfg_attr(not(test), deny(clippy::print_stdout))]
@@ -36,12 +46,15 @@
 #![deny(unused_qualifications)]
 #![deny(rust_2018_idioms)]
 
+#[cfg(feature = "conch-parser")]
+pub mod easy;
 pub mod env;
 pub mod error;
 pub mod eval;
 pub mod io;
 pub mod path;
 pub mod spawn;
+pub mod validate;
 
 mod exit_status;
 mod ref_counted;
@@ -74,10 +87,6 @@ pub const STDOUT_FILENO: Fd = 1;
 /// File descriptor for standard error.
 pub const STDERR_FILENO: Fd = 2;
 
-lazy_static::lazy_static! {
-    static ref HOME: String = String::from("HOME");
-}
-
 /// The type that represents a file descriptor within shell scripts.
 pub type Fd = u16;
 
@@ -1,3 +1,4 @@
+use std::io;
 use std::ops::Deref;
 use std::rc::Rc;
 use std::sync::Arc;
@@ -18,6 +19,23 @@ pub trait RefCounted<T>: Sized + Clone + Deref<Target = T> + From<T> {
     fn make_mut(&mut self) -> &mut T
     where
         T: Clone;
+
+    /// Returns the contained value, if the wrapper has exactly one strong reference.
+    ///
+    /// Otherwise, the same wrapper is returned back.
+    fn try_unwrap(this: Self) -> Result<T, Self>;
+
+    /// Returns the contained value if the wrapper has exactly one strong reference,
+    /// otherwise an owned copy is created via `duplicate` (e.g. `FileDesc::duplicate`).
+    ///
+    /// Useful for values which are expensive (or fallible) to copy, such as OS handles,
+    /// where the copy should be avoided whenever possible.
+    fn unwrap_or_duplicate<F>(this: Self, duplicate: F) -> io::Result<T>
+    where
+        F: FnOnce(&T) -> io::Result<T>,
+    {
+        Self::try_unwrap(this).or_else(|shared| duplicate(&shared))
+    }
 }
 
 impl<T> RefCounted<T> for Rc<T> {
@@ -31,6 +49,10 @@ impl<T> RefCounted<T> for Rc<T> {
     {
         Rc::make_mut(self)
     }
+
+    fn try_unwrap(this: Self) -> Result<T, Self> {
+        Rc::try_unwrap(this)
+    }
 }
 
 impl<T> RefCounted<T> for Arc<T> {
@@ -44,4 +66,8 @@ impl<T> RefCounted<T> for Arc<T> {
     {
         Arc::make_mut(self)
     }
+
+    fn try_unwrap(this: Self) -> Result<T, Self> {
+        Arc::try_unwrap(this)
+    }
 }
@@ -5,37 +5,55 @@ use async_trait::async_trait;
 use futures_core::future::BoxFuture;
 
 mod and_or;
+mod autoload;
+mod background;
 mod case;
+mod errexit;
 mod for_cmd;
 mod func_exec;
 mod if_cmd;
 mod local_redirections;
 mod loop_cmd;
+mod parallel;
 mod pipeline;
+mod ready;
+mod retry;
 mod sequence;
+mod session;
 mod simple;
 mod subshell;
 mod substitution;
 mod swallow_non_fatal;
+mod trap;
+mod xtrace;
 
 #[cfg(feature = "conch-parser")]
 pub mod ast_impl;
 pub mod builtin;
 
 // Pub reexports
-pub use self::and_or::{and_or_list, AndOr};
-pub use self::case::{case, PatternBodyPair};
-pub use self::for_cmd::{for_args, for_loop, for_with_args};
+pub use self::and_or::{and_or_list, and_or_list_with_observer, AndOr, AndOrEvent};
+pub use self::autoload::{autoload_functions, AutoloadFunction};
+pub use self::background::{background, redirect_background_stdin};
+pub use self::case::{case, case_with_terminators, CaseArmTerminator, PatternBodyPair};
+pub use self::for_cmd::{for_args, for_loop, for_loop_lazy, for_stream, for_with_args};
 pub use self::func_exec::{function, function_body};
 pub use self::if_cmd::if_cmd;
 pub use self::local_redirections::spawn_with_local_redirections_and_restorer;
 pub use self::loop_cmd::loop_cmd;
-pub use self::pipeline::pipeline;
+pub use self::parallel::{parallel, ParallelAggregate, ParallelStatus};
+pub use self::pipeline::{pipeline, pipeline_with_status, PipelineStatus};
+pub use self::ready::ready_status;
+pub use self::retry::{retry, RetryPolicy};
 pub use self::sequence::{sequence, sequence_exact, sequence_slice, SequenceSlice};
+pub use self::session::{Session, SessionState};
 pub use self::simple::{simple_command, simple_command_with_restorer};
-pub use self::subshell::subshell;
-pub use self::substitution::substitution;
+pub use self::subshell::{subshell, subshell_with_merge, MergeVars, SubshellMerge};
+pub use self::substitution::{
+    substitution, substitution_bytes, substitution_bytes_with_status, substitution_with_status,
+};
 pub use self::swallow_non_fatal::swallow_non_fatal_errors;
+pub use self::trap::{run_pending_traps, run_trap};
 
 /// A trait for spawning commands.
 ///
@@ -134,6 +152,40 @@ where
     }
 }
 
+impl<E: ?Sized> Spawn<E> for void::Void {
+    type Error = void::Void;
+
+    fn spawn<'life0, 'life1, 'async_trait>(
+        &'life0 self,
+        _env: &'life1 mut E,
+    ) -> BoxFuture<'async_trait, Result<BoxFuture<'static, ExitStatus>, Self::Error>>
+    where
+        'life0: 'async_trait,
+        'life1: 'async_trait,
+        Self: 'async_trait,
+    {
+        void::unreachable(*self)
+    }
+}
+
+/// A marker for `Spawn` implementations which are safe to cancel.
+///
+/// Dropping the future returned by `Spawn::spawn` before it resolves (e.g. in
+/// the middle of evaluating the guard of an `if` command) will leave the
+/// environment as if the command had completed or failed at that point: any
+/// local redirects or variable assignments which were applied will be restored,
+/// as will the function frames, positional arguments, and error contexts of any
+/// functions which were executing. Any other side effects (such as variables
+/// assigned by an already completed command) remain in effect.
+///
+/// All `Spawn` implementations provided by this crate uphold this guarantee,
+/// so long as the commands they are composed of do as well.
+pub trait CancelSafe {}
+
+impl<'a, T: ?Sized + CancelSafe> CancelSafe for &'a T {}
+impl<T: ?Sized + CancelSafe> CancelSafe for Box<T> {}
+impl<T: ?Sized + CancelSafe> CancelSafe for std::sync::Arc<T> {}
+
 /// A grouping of guard and body commands.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct GuardBodyPair<T> {
@@ -1,5 +1,6 @@
-use crate::env::{LastStatusEnvironment, ReportErrorEnvironment};
+use crate::env::{LastStatusEnvironment, ReportErrorEnvironment, ShellOptionsEnvironment};
 use crate::error::IsFatalError;
+use crate::spawn::errexit::IgnoreErrExit;
 use crate::spawn::swallow_non_fatal_errors;
 use crate::{ExitStatus, Spawn};
 use futures_core::future::BoxFuture;
@@ -14,7 +15,32 @@ pub enum AndOr<T> {
     Or(T),
 }
 
+/// Describes the progress of an `And`/`Or` list, as reported to the observer
+/// provided to `and_or_list_with_observer`.
+///
+/// Commands are identified by their position in the list, where the initial
+/// command is at position `0`.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum AndOrEvent {
+    /// A command (which is not the last one to run) has completed.
+    Completed {
+        /// The position of the command in the list.
+        index: usize,
+        /// The exit status of the command.
+        status: ExitStatus,
+    },
+    /// A command was skipped due to the exit status of the previously run command.
+    Skipped {
+        /// The position of the command in the list.
+        index: usize,
+        /// The exit status of the previously run command.
+        status: ExitStatus,
+    },
+}
+
 /// Spawns an `And`/`Or` list of commands from an initial command and an iterator.
+///
+/// The `errexit` option is ignored while running any command other than the last.
 pub async fn and_or_list<T, I, E>(
     first: T,
     rest: I,
@@ -24,35 +50,65 @@ where
     T: Spawn<E>,
     T::Error: IsFatalError,
     I: IntoIterator<Item = AndOr<T>>,
-    E: ?Sized + LastStatusEnvironment + ReportErrorEnvironment,
+    E: ?Sized + LastStatusEnvironment + ReportErrorEnvironment + ShellOptionsEnvironment,
+{
+    and_or_list_with_observer(first, rest, env, |_| {}).await
+}
+
+/// Spawns an `And`/`Or` list of commands from an initial command and an iterator,
+/// notifying the `observer` as each command completes or is skipped.
+///
+/// Note that the observer is not notified of the completion of the last command
+/// which runs, since its exit status is that of the entire list, and is only
+/// available once the returned future resolves.
+pub async fn and_or_list_with_observer<T, I, E, F>(
+    first: T,
+    rest: I,
+    env: &mut E,
+    observer: F,
+) -> Result<BoxFuture<'static, ExitStatus>, T::Error>
+where
+    T: Spawn<E>,
+    T::Error: IsFatalError,
+    I: IntoIterator<Item = AndOr<T>>,
+    E: ?Sized + LastStatusEnvironment + ReportErrorEnvironment + ShellOptionsEnvironment,
+    F: FnMut(AndOrEvent),
 {
-    do_and_or_list(first, rest.into_iter().peekable(), env).await
+    do_and_or_list(first, rest.into_iter().peekable(), env, observer).await
 }
 
-async fn do_and_or_list<T, I, E>(
+async fn do_and_or_list<T, I, E, F>(
     mut next: T,
     mut rest: Peekable<I>,
     env: &mut E,
+    mut observer: F,
 ) -> Result<BoxFuture<'static, ExitStatus>, T::Error>
 where
     T: Spawn<E>,
     T::Error: IsFatalError,
     I: Iterator<Item = AndOr<T>>,
-    E: ?Sized + LastStatusEnvironment + ReportErrorEnvironment,
+    E: ?Sized + LastStatusEnvironment + ReportErrorEnvironment + ShellOptionsEnvironment,
+    F: FnMut(AndOrEvent),
 {
-    loop {
-        let future = swallow_non_fatal_errors(&next, env).await?;
+    let mut index = 0;
 
+    loop {
         // If we have no further commands to process, we can return the
         // current command's future (so the caller may drop the environment)
         if rest.peek().is_none() {
-            return Ok(future);
+            return swallow_non_fatal_errors(&next, env).await;
         }
 
-        let status = future.await;
+        let status = {
+            let mut env = IgnoreErrExit::new(&mut *env);
+            swallow_non_fatal_errors(&next, &mut *env).await?.await
+        };
         env.set_last_status(status);
+        observer(AndOrEvent::Completed { index, status });
 
         'find_next: loop {
+            index += 1;
+
             match (rest.next(), status.success()) {
                 (None, _) => return Ok(Box::pin(async move { status })),
 
@@ -62,7 +118,7 @@ where
                 }
 
                 // Keep looping until we find a command we can spawn
-                _ => {}
+                _ => observer(AndOrEvent::Skipped { index, status }),
             }
         }
     }
@@ -12,6 +12,8 @@ mod pipeable;
 mod simple;
 mod top_level_impl;
 
+pub use self::top_level_impl::spawn_top_level;
+
 impl<T> From<ast::GuardBodyPair<T>> for GuardBodyPair<Vec<T>> {
     fn from(guard_body_pair: ast::GuardBodyPair<T>) -> Self {
         GuardBodyPair {
@@ -1,6 +1,6 @@
-use crate::env::{LastStatusEnvironment, ReportErrorEnvironment};
+use crate::env::{LastStatusEnvironment, ReportErrorEnvironment, ShellOptionsEnvironment};
 use crate::error::IsFatalError;
-use crate::spawn::{and_or_list, AndOr, ExitStatus, Spawn};
+use crate::spawn::{and_or_list, AndOr, CancelSafe, ExitStatus, Spawn};
 use conch_parser::ast;
 use futures_core::future::BoxFuture;
 
@@ -13,11 +13,13 @@ impl<T> From<ast::AndOr<T>> for AndOr<T> {
     }
 }
 
+impl<T: CancelSafe> CancelSafe for ast::AndOrList<T> {}
+
 impl<T, E> Spawn<E> for ast::AndOrList<T>
 where
     T: Sync + Spawn<E>,
     T::Error: IsFatalError,
-    E: Send + ?Sized + LastStatusEnvironment + ReportErrorEnvironment,
+    E: Send + ?Sized + LastStatusEnvironment + ReportErrorEnvironment + ShellOptionsEnvironment,
 {
     type Error = T::Error;
 
@@ -1,14 +1,31 @@
-use crate::env::LastStatusEnvironment;
+use crate::env::{
+    CapabilityEnvironment, FileDescEnvironment, FileDescOpener, JobEnvironment,
+    LastStatusEnvironment, ReportErrorEnvironment, ShellOptionsEnvironment, SubEnvironment,
+};
 use crate::error::RuntimeError;
-use crate::{ExitStatus, Spawn, EXIT_ERROR};
+use crate::spawn::{background, CancelSafe};
+use crate::{ExitStatus, Spawn, EXIT_ERROR, EXIT_SUCCESS};
 use conch_parser::ast;
 use futures_core::future::BoxFuture;
+use std::error::Error;
+
+impl<T: CancelSafe> CancelSafe for ast::Command<T> {}
 
 impl<T, E> Spawn<E> for ast::Command<T>
 where
-    T: Spawn<E>,
-    T::Error: From<RuntimeError>,
-    E: Send + ?Sized + LastStatusEnvironment,
+    T: 'static + Clone + Send + Sync + Spawn<E>,
+    T::Error: 'static + Send + Sync + Error + From<RuntimeError>,
+    E: 'static
+        + Send
+        + CapabilityEnvironment
+        + FileDescEnvironment
+        + FileDescOpener
+        + JobEnvironment
+        + LastStatusEnvironment
+        + ReportErrorEnvironment
+        + ShellOptionsEnvironment
+        + SubEnvironment,
+    E::FileHandle: From<E::OpenedFileHandle>,
 {
     type Error = T::Error;
 
@@ -23,15 +40,19 @@ where
     {
         match self {
             ast::Command::List(list) => list.spawn(env),
-            ast::Command::Job(_) => {
-                Box::pin(async move {
-                    // FIXME: eventual job control would be nice
-                    env.set_last_status(EXIT_ERROR);
-                    Err(T::Error::from(RuntimeError::Unimplemented(
-                        "job control is not currently supported",
-                    )))
-                })
-            }
+            ast::Command::Job(list) => Box::pin(async move {
+                match background(list.clone(), env) {
+                    Ok(_) => {
+                        env.set_last_status(EXIT_SUCCESS);
+                        let future: BoxFuture<'static, _> = Box::pin(async { EXIT_SUCCESS });
+                        Ok(future)
+                    }
+                    Err(e) => {
+                        env.set_last_status(EXIT_ERROR);
+                        Err(T::Error::from(RuntimeError::from(e)))
+                    }
+                }
+            }),
         }
     }
 }
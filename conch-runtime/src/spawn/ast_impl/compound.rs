@@ -1,17 +1,22 @@
 use crate::env::{
-    ArgumentsEnvironment, AsyncIoEnvironment, EnvRestorer, ExportedVariableEnvironment,
-    FileDescEnvironment, FileDescOpener, LastStatusEnvironment, ReportErrorEnvironment,
-    SubEnvironment, UnsetVariableEnvironment, VariableEnvironment,
+    ArgumentsEnvironment, AsyncIoEnvironment, ControlFlowEnvironment, EnvRestorer,
+    ExportedVariableEnvironment, FileDescEnvironment, FileDescOpener, LastStatusEnvironment,
+    ReportErrorEnvironment, ShellOptionsEnvironment, SubEnvironment, TaintEnvironment,
+    UnsetVariableEnvironment, VariableEnvironment,
 };
-use crate::error::{IsFatalError, RedirectionError};
+use crate::error::{CommandError, IsFatalError, RedirectionError};
 use crate::eval::{RedirectEval, WordEval};
 use crate::spawn::{
     case, for_args, for_loop, if_cmd, loop_cmd, sequence_exact, sequence_slice,
-    spawn_with_local_redirections_and_restorer, subshell, GuardBodyPair, PatternBodyPair, Spawn,
+    spawn_with_local_redirections_and_restorer, subshell, CancelSafe, GuardBodyPair,
+    PatternBodyPair, Spawn,
 };
 use crate::{ExitStatus, EXIT_SUCCESS};
 use conch_parser::ast;
 use futures_core::future::BoxFuture;
+use std::borrow::Borrow;
+
+impl<S: CancelSafe, R> CancelSafe for ast::CompoundCommand<S, R> {}
 
 #[async_trait::async_trait]
 impl<S, R, E> Spawn<E> for ast::CompoundCommand<S, R>
@@ -40,6 +45,8 @@ where
     }
 }
 
+impl<V, W, S: CancelSafe> CancelSafe for ast::CompoundCommandKind<V, W, S> {}
+
 #[async_trait::async_trait]
 impl<V, W, S, E> Spawn<E> for ast::CompoundCommandKind<V, W, S>
 where
@@ -47,17 +54,24 @@ where
     W: Sync + WordEval<E>,
     W::Error: Send + IsFatalError,
     S: Send + Sync + Spawn<E>,
-    S::Error: From<W::Error> + IsFatalError,
+    S::Error: From<CommandError> + From<W::Error> + IsFatalError,
     E: ?Sized
         + Send
         + Sync
         + ArgumentsEnvironment
+        + AsyncIoEnvironment
+        + ControlFlowEnvironment
+        + FileDescEnvironment
         + LastStatusEnvironment
         + ReportErrorEnvironment
+        + ShellOptionsEnvironment
+        + TaintEnvironment
         + SubEnvironment
         + VariableEnvironment,
-    E::Var: Send + From<E::Arg> + From<W::EvalResult>,
-    E::VarName: Send + Clone + From<V>,
+    E::FileHandle: Send + Sync + Clone,
+    E::IoHandle: From<E::FileHandle>,
+    E::Var: Send + Borrow<String> + From<E::Arg> + From<W::EvalResult>,
+    E::VarName: Send + Borrow<String> + Clone + From<V>,
 {
     type Error = S::Error;
 
@@ -117,8 +131,14 @@ async fn spawn_loop<S, E>(
 ) -> Result<BoxFuture<'static, ExitStatus>, S::Error>
 where
     S: Send + Sync + Spawn<E>,
-    S::Error: IsFatalError,
-    E: ?Sized + Send + Sync + LastStatusEnvironment + ReportErrorEnvironment,
+    S::Error: From<CommandError> + IsFatalError,
+    E: ?Sized
+        + Send
+        + Sync
+        + ControlFlowEnvironment
+        + LastStatusEnvironment
+        + ReportErrorEnvironment
+        + ShellOptionsEnvironment,
 {
     let ret = if guard.is_empty() && body.is_empty() {
         // Not a well formed command, rather than burning CPU and spinning
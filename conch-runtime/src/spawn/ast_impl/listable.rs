@@ -1,21 +1,29 @@
-use crate::env::{FileDescEnvironment, FileDescOpener, ReportErrorEnvironment, SubEnvironment};
-use crate::error::IsFatalError;
-use crate::spawn::{pipeline, ExitStatus, Spawn};
+use crate::env::{
+    FileDescEnvironment, FileDescOpener, JobEnvironment, ReportErrorEnvironment,
+    ShellOptionsEnvironment, SubEnvironment,
+};
+use crate::error::{CommandError, IsFatalError};
+use crate::spawn::errexit::IgnoreErrExit;
+use crate::spawn::{pipeline, ready_status, CancelSafe, ExitStatus, Spawn};
 use crate::{EXIT_ERROR, EXIT_SUCCESS};
 use conch_parser::ast;
 use futures_core::future::BoxFuture;
 use std::io;
 
+impl<S: CancelSafe> CancelSafe for ast::ListableCommand<S> {}
+
 impl<S, E> Spawn<E> for ast::ListableCommand<S>
 where
     S: Send + Sync + Spawn<E>,
-    S::Error: From<io::Error> + IsFatalError,
+    S::Error: From<io::Error> + From<CommandError> + IsFatalError,
     E: ?Sized
         + Send
         + Sync
         + FileDescEnvironment
         + FileDescOpener
+        + JobEnvironment
         + ReportErrorEnvironment
+        + ShellOptionsEnvironment
         + SubEnvironment,
     E::FileHandle: From<E::OpenedFileHandle>,
     E::OpenedFileHandle: Send,
@@ -31,15 +39,27 @@ where
         'life1: 'async_trait,
         Self: 'async_trait,
     {
+        let errexit = env.is_errexit_enforced();
+
         match self {
-            ast::ListableCommand::Single(cmd) => cmd.spawn(env),
+            ast::ListableCommand::Single(cmd) if !errexit => cmd.spawn(env),
+            ast::ListableCommand::Single(cmd) => Box::pin(async move {
+                let future = cmd.spawn(env).await?;
+                Ok(check_errexit(env.is_errexit_enforced(), future).await?)
+            }),
             ast::ListableCommand::Pipe(invert, cmds) => {
                 match cmds.as_slice() {
                     // Malformed command, just treat it as a successfull command
                     [] => Box::pin(async move { Ok(dummy(*invert)) }),
-                    [first, rest @ ..] => {
-                        Box::pin(async move { Ok(pipeline(*invert, first, rest, env).await?) })
-                    }
+                    // NB: errexit never applies to negated pipelines
+                    [first, rest @ ..] if *invert => Box::pin(async move {
+                        let mut env = IgnoreErrExit::new(env);
+                        Ok(pipeline(true, first, rest, &mut *env).await?)
+                    }),
+                    [first, rest @ ..] => Box::pin(async move {
+                        let future = pipeline(false, first, rest, env).await?;
+                        Ok(check_errexit(env.is_errexit_enforced(), future).await?)
+                    }),
                 }
             }
         }
@@ -50,3 +70,22 @@ fn dummy(invert: bool) -> BoxFuture<'static, ExitStatus> {
     let ret = if invert { EXIT_ERROR } else { EXIT_SUCCESS };
     Box::pin(async move { ret })
 }
+
+/// Waits for a command to complete if `errexit` is enforced, so that an unsuccessful
+/// status can be turned into a `CommandError::ErrExit`. Otherwise the command's
+/// future is returned as is.
+async fn check_errexit(
+    errexit: bool,
+    future: BoxFuture<'static, ExitStatus>,
+) -> Result<BoxFuture<'static, ExitStatus>, CommandError> {
+    if !errexit {
+        return Ok(future);
+    }
+
+    let status = future.await;
+    if status.success() {
+        Ok(ready_status(status))
+    } else {
+        Err(CommandError::ErrExit(status))
+    }
+}
@@ -1,10 +1,17 @@
 use crate::env::FunctionEnvironment;
-use crate::spawn::{ExitStatus, Spawn};
+use crate::spawn::{CancelSafe, ExitStatus, Spawn};
 use crate::EXIT_SUCCESS;
 use conch_parser::ast;
 use futures_core::future::BoxFuture;
 use std::sync::Arc;
 
+impl<N, S, C, F> CancelSafe for ast::PipeableCommand<N, S, C, Arc<F>>
+where
+    S: CancelSafe,
+    C: CancelSafe,
+{
+}
+
 impl<N, S, C, F, E> Spawn<E> for ast::PipeableCommand<N, S, C, Arc<F>>
 where
     S: Spawn<E>,
@@ -1,13 +1,16 @@
 use crate::env::builtin::{BuiltinEnvironment, BuiltinUtility};
 use crate::env::{
-    AsyncIoEnvironment, EnvRestorer, ExecutableEnvironment, ExportedVariableEnvironment,
+    AsyncIoEnvironment, CapabilityEnvironment, CommandSearchEnvironment, CommandTextEnvironment,
+    ControlFlowEnvironment, EnvRestorer, ExecutableEnvironment, ExportedVariableEnvironment,
     FileDescEnvironment, FileDescOpener, FunctionEnvironment, FunctionFrameEnvironment,
-    SetArgumentsEnvironment, UnsetVariableEnvironment, WorkingDirectoryEnvironment,
+    ReportErrorEnvironment, ResolveCommandEnvironment, SetArgumentsEnvironment,
+    ShellOptionsEnvironment, SpawnMiddlewareEnvironment, SubstitutionStatusEnvironment,
+    UnsetVariableEnvironment, WorkingDirectoryEnvironment,
 };
 use crate::error::{CommandError, RedirectionError};
 use crate::eval::{RedirectEval, RedirectOrCmdWord, RedirectOrVarAssig, WordEval};
 use crate::io::FileDescWrapper;
-use crate::spawn::{simple_command, Spawn};
+use crate::spawn::{simple_command, CancelSafe, Spawn};
 use crate::ExitStatus;
 use conch_parser::ast;
 use futures_core::future::BoxFuture;
@@ -15,6 +18,8 @@ use std::borrow::Borrow;
 use std::collections::VecDeque;
 use std::error::Error;
 
+impl<V, W, R> CancelSafe for ast::SimpleCommand<V, W, R> {}
+
 #[async_trait::async_trait]
 impl<V, W, R, E> Spawn<E> for ast::SimpleCommand<V, W, R>
 where
@@ -29,13 +34,22 @@ where
         + Sync
         + AsyncIoEnvironment
         + BuiltinEnvironment<BuiltinName = <E as FunctionEnvironment>::FnName>
+        + CapabilityEnvironment
+        + CommandSearchEnvironment
+        + CommandTextEnvironment
+        + ControlFlowEnvironment
         + ExecutableEnvironment
         + ExportedVariableEnvironment
         + FileDescEnvironment
         + FileDescOpener
         + FunctionEnvironment
         + FunctionFrameEnvironment
+        + ReportErrorEnvironment
+        + ResolveCommandEnvironment
         + SetArgumentsEnvironment
+        + ShellOptionsEnvironment
+        + SpawnMiddlewareEnvironment
+        + SubstitutionStatusEnvironment
         + UnsetVariableEnvironment
         + WorkingDirectoryEnvironment,
     E::Arg: Send + From<W::EvalResult>,
@@ -45,8 +59,13 @@ where
     E::FileHandle: Send + Sync + Clone + FileDescWrapper + From<E::OpenedFileHandle>,
     E::FnName: Send + Sync + From<W::EvalResult>,
     E::Fn: Send + Sync + Clone + Spawn<E>,
-    <E::Fn as Spawn<E>>::Error:
-        From<CommandError> + From<RedirectionError> + From<R::Error> + From<W::Error>,
+    <E::Fn as Spawn<E>>::Error: 'static
+        + Send
+        + Error
+        + From<CommandError>
+        + From<RedirectionError>
+        + From<R::Error>
+        + From<W::Error>,
     E::IoHandle: Send + Sync + From<E::FileHandle>,
     E::VarName: Send + Sync + Clone + Borrow<String> + From<V>,
     E::Var: Send + Sync + Clone + Borrow<String> + From<W::EvalResult>,
@@ -1,15 +1,18 @@
 use crate::env::builtin::{BuiltinEnvironment, BuiltinUtility};
 use crate::env::{
-    ArgumentsEnvironment, AsyncIoEnvironment, EnvRestorer, ExecutableEnvironment,
-    ExportedVariableEnvironment, FileDescEnvironment, FileDescOpener, FunctionEnvironment,
-    FunctionFrameEnvironment, IsInteractiveEnvironment, LastStatusEnvironment,
-    ReportErrorEnvironment, SetArgumentsEnvironment, StringWrapper, SubEnvironment,
-    UnsetVariableEnvironment, WorkingDirectoryEnvironment,
+    ArgumentsEnvironment, ArithVariableEnvironment, AsyncIoEnvironment, CapabilityEnvironment,
+    CommandSearchEnvironment, CommandTextEnvironment, ControlFlowEnvironment, EnvRestorer,
+    ExecutableEnvironment, ExportedVariableEnvironment, FileDescEnvironment, FileDescOpener,
+    FunctionEnvironment, FunctionFrameEnvironment, HomeDirEnvironment, IsInteractiveEnvironment,
+    JobEnvironment, LastStatusEnvironment, ReportErrorEnvironment, ResolveCommandEnvironment,
+    SetArgumentsEnvironment, ShellOptionsEnvironment, SpawnMiddlewareEnvironment, StringWrapper,
+    SubEnvironment, SubstitutionStatusEnvironment, UnsetVariableEnvironment,
+    WorkingDirectoryEnvironment,
 };
 use crate::error::RuntimeError;
 use crate::eval::{WordEval, WordEvalConfig, WordEvalResult};
 use crate::io::FileDescWrapper;
-use crate::spawn::Spawn;
+use crate::spawn::{CancelSafe, Spawn};
 use crate::ExitStatus;
 use conch_parser::ast::{AtomicTopLevelCommand, AtomicTopLevelWord};
 use futures_core::future::BoxFuture;
@@ -17,26 +20,39 @@ use std::collections::VecDeque;
 use std::fmt::Display;
 use std::sync::Arc;
 
+impl<T> CancelSafe for AtomicTopLevelCommand<T> {}
+
 impl<T, E> Spawn<E> for AtomicTopLevelCommand<T>
 where
     T: 'static + StringWrapper + Display + Send + Sync,
-    E: ?Sized
+    E: 'static
         + Send
         + Sync
         + AsyncIoEnvironment
         + ArgumentsEnvironment<Arg = T>
+        + ArithVariableEnvironment
         + BuiltinEnvironment<BuiltinName = <E as FunctionEnvironment>::FnName>
+        + CapabilityEnvironment
+        + CommandSearchEnvironment
+        + CommandTextEnvironment
+        + ControlFlowEnvironment
         + ExecutableEnvironment
         + ExportedVariableEnvironment<VarName = T, Var = T>
         + FileDescEnvironment
         + FileDescOpener
         + FunctionEnvironment
         + FunctionFrameEnvironment
+        + HomeDirEnvironment
         + IsInteractiveEnvironment
+        + JobEnvironment
         + LastStatusEnvironment
         + ReportErrorEnvironment
+        + ResolveCommandEnvironment
         + SetArgumentsEnvironment
+        + ShellOptionsEnvironment
+        + SpawnMiddlewareEnvironment
         + SubEnvironment
+        + SubstitutionStatusEnvironment
         + UnsetVariableEnvironment
         + WorkingDirectoryEnvironment,
     E::Args: Send + From<VecDeque<E::Arg>>,
@@ -67,26 +83,97 @@ where
     }
 }
 
+/// Spawns a top-level command, exactly like `Spawn::spawn`, but through a
+/// type-erased (i.e. `dyn Spawn`) reference to the command.
+///
+/// Each layer of the AST implementations already boxes its futures, so erasing the
+/// command itself means all callers spawning commands against the same environment
+/// share a single instantiation of the entire AST implementation, instead of each
+/// (potentially wrapped or referenced) command type generating its own copy.
+pub fn spawn_top_level<'cmd, T, E>(
+    cmd: &'cmd AtomicTopLevelCommand<T>,
+    env: &'cmd mut E,
+) -> BoxFuture<'cmd, Result<BoxFuture<'static, ExitStatus>, RuntimeError>>
+where
+    T: 'static + StringWrapper + Display + Send + Sync,
+    E: 'static
+        + Send
+        + Sync
+        + AsyncIoEnvironment
+        + ArgumentsEnvironment<Arg = T>
+        + ArithVariableEnvironment
+        + BuiltinEnvironment<BuiltinName = <E as FunctionEnvironment>::FnName>
+        + CapabilityEnvironment
+        + CommandSearchEnvironment
+        + CommandTextEnvironment
+        + ControlFlowEnvironment
+        + ExecutableEnvironment
+        + ExportedVariableEnvironment<VarName = T, Var = T>
+        + FileDescEnvironment
+        + FileDescOpener
+        + FunctionEnvironment
+        + FunctionFrameEnvironment
+        + HomeDirEnvironment
+        + IsInteractiveEnvironment
+        + JobEnvironment
+        + LastStatusEnvironment
+        + ReportErrorEnvironment
+        + ResolveCommandEnvironment
+        + SetArgumentsEnvironment
+        + ShellOptionsEnvironment
+        + SpawnMiddlewareEnvironment
+        + SubEnvironment
+        + SubstitutionStatusEnvironment
+        + UnsetVariableEnvironment
+        + WorkingDirectoryEnvironment,
+    E::Args: Send + From<VecDeque<E::Arg>>,
+    E::Builtin: Send + Sync,
+    for<'a> E::Builtin: BuiltinUtility<'a, Vec<T>, EnvRestorer<'a, E>, E>,
+    E::FileHandle: Send + Sync + Clone + FileDescWrapper + From<E::OpenedFileHandle>,
+    E::OpenedFileHandle: Send,
+    E::FnName: Send + Sync + From<T>,
+    E::Fn: Send
+        + Sync
+        + Clone
+        + From<Arc<dyn Spawn<E, Error = RuntimeError> + 'static + Send + Sync>>
+        + Spawn<E, Error = RuntimeError>,
+    E::IoHandle: Send + Sync + From<E::FileHandle> + From<E::OpenedFileHandle>,
+{
+    let cmd: &'cmd (dyn Spawn<E, Error = RuntimeError> + Send + Sync) = cmd;
+    cmd.spawn(env)
+}
+
 impl<T, E> WordEval<E> for AtomicTopLevelWord<T>
 where
     T: 'static + StringWrapper + Display + Send + Sync,
-    E: ?Sized
+    E: 'static
         + Send
         + Sync
         + AsyncIoEnvironment
         + ArgumentsEnvironment<Arg = T>
+        + ArithVariableEnvironment
         + BuiltinEnvironment<BuiltinName = <E as FunctionEnvironment>::FnName>
+        + CapabilityEnvironment
+        + CommandSearchEnvironment
+        + CommandTextEnvironment
+        + ControlFlowEnvironment
         + ExecutableEnvironment
         + ExportedVariableEnvironment<VarName = T, Var = T>
         + FileDescEnvironment
         + FileDescOpener
         + FunctionEnvironment
         + FunctionFrameEnvironment
+        + HomeDirEnvironment
         + IsInteractiveEnvironment
+        + JobEnvironment
         + LastStatusEnvironment
         + ReportErrorEnvironment
+        + ResolveCommandEnvironment
         + SetArgumentsEnvironment
+        + ShellOptionsEnvironment
+        + SpawnMiddlewareEnvironment
         + SubEnvironment
+        + SubstitutionStatusEnvironment
         + UnsetVariableEnvironment
         + WorkingDirectoryEnvironment,
     E::Args: Send + From<VecDeque<E::Arg>>,
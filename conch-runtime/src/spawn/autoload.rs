@@ -0,0 +1,168 @@
+use crate::env::{
+    FunctionEnvironment, LastStatusEnvironment, ParseScriptEnvironment, ReportErrorEnvironment,
+    UnsetFunctionEnvironment,
+};
+use crate::error::{CommandError, IsFatalError, RuntimeError};
+use crate::spawn::{sequence_exact, CancelSafe, Spawn};
+use crate::ExitStatus;
+use futures_core::future::BoxFuture;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// A placeholder for a function whose definition is loaded from a file
+/// the first time the function is called.
+///
+/// When spawned, the file is parsed and run in the current environment, which
+/// is expected to (re)define the function (along with any helpers it may need),
+/// after which the newly defined function is invoked in place of the placeholder.
+/// Thus the cost of reading and parsing the file is only paid if the function is
+/// ever used.
+///
+/// If the file does not define the function, the placeholder is restored, and
+/// the function is treated as if it was not found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AutoloadFunction {
+    name: String,
+    path: PathBuf,
+}
+
+impl AutoloadFunction {
+    /// Creates a placeholder for the function `name`, defined in the file at `path`.
+    pub fn new<N: Into<String>, P: Into<PathBuf>>(name: N, path: P) -> Self {
+        Self {
+            name: name.into(),
+            path: path.into(),
+        }
+    }
+
+    /// Get the name of the function this placeholder will load.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the path of the file which defines the function.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn io_error(&self, err: io::Error) -> RuntimeError {
+        RuntimeError::Io(err, Some(self.path.display().to_string()))
+    }
+}
+
+impl CancelSafe for AutoloadFunction {}
+
+#[async_trait::async_trait]
+impl<E> Spawn<E> for AutoloadFunction
+where
+    E: ?Sized
+        + Send
+        + Sync
+        + FunctionEnvironment
+        + LastStatusEnvironment
+        + ParseScriptEnvironment
+        + ReportErrorEnvironment
+        + UnsetFunctionEnvironment,
+    E::Command: Send + Sync + Spawn<E, Error = <E::Fn as Spawn<E>>::Error>,
+    E::FnName: Send + Sync + From<String>,
+    E::Fn: Send + Sync + Clone + Spawn<E>,
+    <E::Fn as Spawn<E>>::Error: From<RuntimeError> + IsFatalError,
+{
+    type Error = <E::Fn as Spawn<E>>::Error;
+
+    async fn spawn(&self, env: &mut E) -> Result<BoxFuture<'static, ExitStatus>, Self::Error> {
+        let source = tokio::fs::read_to_string(&self.path)
+            .await
+            .map_err(|e| self.io_error(e))?;
+
+        let cmds = env
+            .parse_script(&source)
+            .map_err(|e| self.io_error(io::Error::new(io::ErrorKind::InvalidData, e)))?;
+
+        let name = E::FnName::from(self.name.clone());
+        let placeholder = env.function(&name).cloned();
+        env.unset_function(&name);
+
+        let result = match sequence_exact(cmds, env).await {
+            Ok(future) => {
+                future.await;
+                env.function(&name).cloned().ok_or(None)
+            }
+            Err(e) => Err(Some(e)),
+        };
+
+        match result {
+            Ok(func) => func.spawn(env).await,
+            Err(e) => {
+                if let Some(placeholder) = placeholder {
+                    env.set_function(name, placeholder);
+                }
+
+                Err(e.unwrap_or_else(|| {
+                    RuntimeError::from(CommandError::NotFound(self.name.clone())).into()
+                }))
+            }
+        }
+    }
+}
+
+/// Registers an `AutoloadFunction` for each file within a directory, thus
+/// making an entire library of shell functions available to an environment,
+/// while only loading those which are actually used.
+///
+/// Each file is expected to define a function named after the file, without
+/// any `.sh` extension (e.g. `greet` or `greet.sh` should define `greet`).
+/// Any hidden files (i.e. those whose names start with a `.`), subdirectories,
+/// or files whose names are not valid UTF-8 are ignored. Any existing functions
+/// with the same name are replaced.
+///
+/// The names of all registered functions are returned, sorted by name.
+pub fn autoload_functions<E, P>(dir: P, env: &mut E) -> io::Result<Vec<String>>
+where
+    P: AsRef<Path>,
+    E: ?Sized + FunctionEnvironment,
+    E::FnName: From<String>,
+    E::Fn: From<Arc<dyn Spawn<E, Error = <AutoloadFunction as Spawn<E>>::Error> + Send + Sync>>,
+    AutoloadFunction: Spawn<E>,
+{
+    let mut functions = Vec::new();
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if !entry.path().is_file() {
+            continue;
+        }
+
+        let file_name = match entry.file_name().into_string() {
+            Ok(file_name) => file_name,
+            Err(_) => continue,
+        };
+
+        let name = file_name.strip_suffix(".sh").unwrap_or(&file_name);
+        if name.is_empty() || name.starts_with('.') {
+            continue;
+        }
+
+        functions.push(AutoloadFunction::new(name, entry.path()));
+    }
+
+    // NB: sort the functions so that any conflicts (e.g. `f` and `f.sh`)
+    // are resolved the same way regardless of the order of the entries
+    functions.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut names = functions
+        .into_iter()
+        .map(|func| {
+            let name = func.name.clone();
+            let func: Arc<dyn Spawn<E, Error = _> + Send + Sync> = Arc::new(func);
+            env.set_function(E::FnName::from(name.clone()), E::Fn::from(func));
+            name
+        })
+        .collect::<Vec<_>>();
+
+    names.sort();
+    names.dedup();
+    Ok(names)
+}
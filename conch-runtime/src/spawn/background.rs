@@ -0,0 +1,83 @@
+use crate::env::{
+    FileDescEnvironment, FileDescOpener, JobEnvironment, JobId, ReportErrorEnvironment,
+    ResourceUsageEnvironment, ResourceUsageTracker, SubEnvironment, TaintEnvironment,
+};
+use crate::error::ErrorContext;
+use crate::io::Permissions;
+use crate::spawn::subshell::subshell_with_env;
+use crate::{Spawn, STDIN_FILENO};
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io;
+use std::path::Path;
+
+#[cfg(unix)]
+const DEV_NULL: &str = "/dev/null";
+#[cfg(windows)]
+const DEV_NULL: &str = "NUL";
+
+/// Redirects the standard input of an environment in which an asynchronous
+/// (background) command will run to the equivalent of `/dev/null`.
+///
+/// POSIX specifies that (when job control is disabled) the standard input of an
+/// asynchronous command is considered to be the null device *before* any of the
+/// command's own redirections are applied. This prevents background commands from
+/// competing with the shell for input, while still allowing something like
+/// `cmd < file &` to read from `file`.
+///
+/// Any executables spawned from such an environment will observe a null standard
+/// input, same as if `ExecutableStdio::Null` was explicitly requested.
+pub fn redirect_background_stdin<E>(env: &mut E) -> io::Result<()>
+where
+    E: ?Sized + FileDescEnvironment + FileDescOpener,
+    E::FileHandle: From<E::OpenedFileHandle>,
+{
+    let null = env.open_path(Path::new(DEV_NULL), OpenOptions::new().read(true))?;
+    env.set_file_desc(STDIN_FILENO, null.into(), Permissions::Read);
+    Ok(())
+}
+
+/// Spawns anything as an asynchronous (background) job, e.g. `cmd &`.
+///
+/// The command runs in its own sub-environment (much like a subshell) whose
+/// standard input is redirected via `redirect_background_stdin`. The job is
+/// tracked by the `JobEnvironment` of `env`, and the returned id can later be
+/// used to wait for its completion.
+///
+/// Any shell options (e.g. `errexit`) apply to the commands within the job
+/// just as they would in a subshell, so with `set -e` the job stops at the
+/// first command which fails, and completes with its status.
+///
+/// The resources used by any executables the job spawns are recorded by a new
+/// `ResourceUsageTracker`, which is available via `JobEnvironment::job_usage`.
+/// Any expansions of tainted variables made by the job are recorded by a new
+/// `TaintTracker` (see `TaintTracker::detached`).
+pub fn background<S, E>(spawn: S, env: &mut E) -> io::Result<JobId>
+where
+    S: 'static + Send + Sync + Spawn<E>,
+    S::Error: 'static + Send + Sync + Error,
+    E: 'static
+        + Send
+        + FileDescEnvironment
+        + FileDescOpener
+        + JobEnvironment
+        + ReportErrorEnvironment
+        + ResourceUsageEnvironment
+        + SubEnvironment
+        + TaintEnvironment,
+    E::FileHandle: From<E::OpenedFileHandle>,
+{
+    let mut sub_env = env.sub_env();
+    redirect_background_stdin(&mut sub_env)?;
+
+    // NB: the job runs concurrently with the shell, so any variables it expands
+    // must not taint whatever the shell happens to be evaluating in the meantime
+    let tracker = sub_env.taint_tracker().detached();
+    sub_env.set_taint_tracker(tracker);
+
+    let usage = ResourceUsageTracker::new();
+    sub_env.set_usage_tracker(Some(usage.clone()));
+
+    let job = subshell_with_env(spawn, sub_env, ErrorContext::Subshell);
+    Ok(env.spawn_job_with_usage(Box::pin(job), usage))
+}
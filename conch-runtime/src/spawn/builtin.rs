@@ -23,6 +23,18 @@ macro_rules! try_and_report {
     };
 }
 
+macro_rules! try_or_usage {
+    ($builtin_name:expr, $usage:expr, $result:expr, $env:ident) => {
+        match $result {
+            Ok(val) => val,
+            Err(e) => {
+                return $crate::spawn::builtin::report_usage_err($builtin_name, $usage, $env, e)
+                    .await;
+            }
+        }
+    };
+}
+
 pub(crate) async fn report_err<E, ERR>(
     builtin_name: &str,
     env: &mut E,
@@ -44,17 +56,54 @@ where
     .await
 }
 
+mod alias;
 mod cd;
+mod conch_info;
+mod control_flow;
+mod describe;
 mod echo;
+mod exec;
+mod hash;
+mod opts;
+mod printf;
 mod pwd;
+mod read;
+mod set;
 mod shift;
+mod source;
+mod test;
+mod time;
+mod times;
+mod trap;
 mod trivial;
+mod ulimit;
+mod unset;
+mod wait;
 
+pub use self::alias::{alias, unalias};
 pub use self::cd::cd;
+pub use self::conch_info::conch_info;
+pub use self::control_flow::{break_cmd, continue_cmd, exit, return_cmd};
+pub use self::describe::{command, type_cmd};
 pub use self::echo::echo;
+pub use self::exec::exec;
+pub use self::hash::hash;
+pub use self::opts::{report_usage_err, BuiltinArgs, UsageError};
+pub use self::printf::printf;
 pub use self::pwd::pwd;
+pub use self::read::read;
+pub use self::set::set;
 pub use self::shift::shift;
+pub use self::source::source;
+pub use self::test::{bracket, test_cmd};
+pub(crate) use self::time::format_times;
+pub use self::time::time;
+pub use self::times::times;
+pub use self::trap::trap;
 pub use self::trivial::{colon, false_cmd, true_cmd};
+pub use self::ulimit::ulimit;
+pub use self::unset::unset;
+pub use self::wait::wait;
 
 pub(crate) async fn generate_and_print_output<E, F, ERR>(
     builtin_name: &str,
@@ -0,0 +1,153 @@
+use super::{generate_and_print_output, report_err, report_usage_err, BuiltinArgs, UsageError};
+use crate::env::{AliasEnvironment, AsyncIoEnvironment, FileDescEnvironment, StringWrapper};
+use crate::eval::quote_for_shell;
+use crate::{ExitStatus, EXIT_ERROR, EXIT_SUCCESS};
+use futures_util::future::BoxFuture;
+use void::Void;
+
+const ALIAS: &str = "alias";
+const ALIAS_USAGE: &str = "[name[=value] ...]";
+const UNALIAS: &str = "unalias";
+const UNALIAS_USAGE: &str = "-a | name ...";
+const ARG_ALL: char = 'a';
+
+#[derive(Debug, thiserror::Error)]
+enum AliasError {
+    #[error("{0}: not found")]
+    NotFound(String),
+    #[error("{0}: invalid alias name")]
+    InvalidName(String),
+}
+
+/// The `alias` builtin command defines aliases, or prints their definitions.
+///
+/// Each operand of the form `name=value` defines an alias, while any other
+/// operand prints the definition of the named alias. Without any operands, the
+/// definitions of all aliases are printed. Definitions are printed in a form
+/// which can be reinput to the shell (i.e. `alias name='value'`).
+///
+/// Aliases which are not defined (or cannot be defined) are reported, and
+/// result in an exit status of 1.
+pub async fn alias<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized + AliasEnvironment + AsyncIoEnvironment + FileDescEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    let args = try_or_usage!(ALIAS, ALIAS_USAGE, BuiltinArgs::parse(args, ""), env);
+
+    if args.operands().is_empty() {
+        return generate_and_print_output(ALIAS, env, |env| -> Result<_, Void> {
+            let mut out = String::new();
+            for (name, value) in env.aliases() {
+                out.push_str(&format_alias(name, value));
+            }
+            Ok(out.into_bytes())
+        })
+        .await;
+    }
+
+    let mut out = String::new();
+    let mut errors = Vec::new();
+    for operand in args.into_operands() {
+        match operand.find('=') {
+            Some(idx) => {
+                let name = &operand[..idx];
+                if is_valid_name(name) {
+                    env.set_alias(name.to_owned(), operand[idx + 1..].to_owned());
+                } else {
+                    errors.push(AliasError::InvalidName(name.to_owned()));
+                }
+            }
+            None => match env.alias(&operand) {
+                Some(value) => out.push_str(&format_alias(&operand, value)),
+                None => errors.push(AliasError::NotFound(operand)),
+            },
+        }
+    }
+
+    if !out.is_empty() {
+        generate_and_print_output(ALIAS, env, |_| -> Result<_, Void> { Ok(out.into_bytes()) })
+            .await
+            .await;
+    }
+
+    if errors.is_empty() {
+        return Box::pin(async { EXIT_SUCCESS });
+    }
+
+    for err in errors {
+        report_err(ALIAS, env, err).await.await;
+    }
+
+    Box::pin(async { EXIT_ERROR })
+}
+
+/// The `unalias` builtin command removes the definitions of the named aliases,
+/// or of all aliases if `-a` is specified.
+///
+/// Names which are not defined as aliases are reported, and result in an exit
+/// status of 1.
+pub async fn unalias<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized + AliasEnvironment + AsyncIoEnvironment + FileDescEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    let args = try_or_usage!(UNALIAS, UNALIAS_USAGE, BuiltinArgs::parse(args, "a"), env);
+
+    if args.is_present(ARG_ALL) {
+        env.unalias_all();
+        return Box::pin(async { EXIT_SUCCESS });
+    }
+
+    if args.operands().is_empty() {
+        let err = UsageError::TooFewOperands;
+        return report_usage_err(UNALIAS, UNALIAS_USAGE, env, err).await;
+    }
+
+    let mut status = EXIT_SUCCESS;
+    for name in args.into_operands() {
+        if env.unalias(&name).is_none() {
+            report_err(UNALIAS, env, AliasError::NotFound(name))
+                .await
+                .await;
+            status = EXIT_ERROR;
+        }
+    }
+
+    Box::pin(async move { status })
+}
+
+fn format_alias(name: &str, value: &str) -> String {
+    format!("alias {}={}\n", name, quote_for_shell(value))
+}
+
+/// Checks if a name can be defined as an alias, i.e. it is not empty, and it
+/// does not contain any characters which the shell would interpret specially.
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.chars().any(|c| {
+            c.is_whitespace()
+                || matches!(
+                    c,
+                    '=' | '/'
+                        | '$'
+                        | '`'
+                        | '\''
+                        | '"'
+                        | '\\'
+                        | '|'
+                        | '&'
+                        | ';'
+                        | '<'
+                        | '>'
+                        | '('
+                        | ')'
+                )
+        })
+}
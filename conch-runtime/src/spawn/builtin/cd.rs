@@ -1,11 +1,10 @@
-use super::{generate_and_print_output, report_err};
+use super::{generate_and_print_output, report_err, BuiltinArgs};
 use crate::env::{
-    AsyncIoEnvironment, ChangeWorkingDirectoryEnvironment, FileDescEnvironment, StringWrapper,
-    VariableEnvironment, WorkingDirectoryEnvironment,
+    AsyncIoEnvironment, ChangeWorkingDirectoryEnvironment, FileDescEnvironment, HomeDirEnvironment,
+    StringWrapper, VariableEnvironment, WorkingDirectoryEnvironment,
 };
 use crate::path::{NormalizationError, NormalizedPath};
-use crate::{ExitStatus, EXIT_SUCCESS, HOME};
-use clap::{App, AppSettings, Arg, ArgMatches, Result as ClapResult};
+use crate::{ExitStatus, EXIT_SUCCESS};
 use futures_util::future::BoxFuture;
 use std::borrow::{Borrow, Cow};
 use std::io;
@@ -13,24 +12,7 @@ use std::path::{Component, Path, PathBuf};
 use void::{self, Void};
 
 const CD: &str = "cd";
-const ARG_LOGICAL: &str = "L";
-const ARG_PHYSICAL: &str = "P";
-const ARG_DIR: &str = "dir";
-
-const LONG_ABOUT: &str = "Changes the current working directory to the specified
-argument, provided the argument points to a valid directory. If the operation is
-successful, $PWD will be updated with the new working directory, and $OLDPWD
-will be set to the previous working directory.
-
-If no argument is specified, the value of $HOME will be used as the new working
-directory. If `-` is specified as an argument, the value of $OLDPWD will be used
-instead, and the new working directory will be printed to standard output.
-
-If the specified argument is neither an absolute path, nor begins with ./ or
-../, the value of $CDPATH will be searched for alternative directory names
-(seprated by `:`) to use as a prefix for the argument. If a valid directory is
-discovered using an alternative directory name from $CDPATH, the new working
-directory will be printed to standard output.";
+const USAGE: &str = "[-L | -P] [dir]";
 
 lazy_static::lazy_static! {
     static ref CDPATH: String = String::from("CDPATH");
@@ -66,6 +48,20 @@ impl From<NormalizationError> for CdError {
 }
 
 /// The `cd` builtin command will change the current working directory.
+///
+/// If the operation is successful, `$PWD` will be updated with the new working
+/// directory, and `$OLDPWD` will be set to the previous working directory.
+///
+/// If no directory is specified, the home directory (usually `$HOME`) will be used.
+/// If `-` is specified, the value of `$OLDPWD` will be used instead, and the new
+/// working directory will be printed to standard output.
+///
+/// If the specified directory is neither an absolute path, nor begins with `./` or
+/// `../`, the value of `$CDPATH` will be searched for alternative directory names
+/// (separated by `:`) to use as a prefix for it. If a valid directory is discovered
+/// this way, the new working directory will be printed to standard output.
+///
+/// By default paths are handled logically (`-L`), but `-P` will resolve any symbolic links.
 pub async fn cd<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
 where
     I: IntoIterator,
@@ -74,6 +70,7 @@ where
         + AsyncIoEnvironment
         + ChangeWorkingDirectoryEnvironment
         + FileDescEnvironment
+        + HomeDirEnvironment
         + VariableEnvironment
         + WorkingDirectoryEnvironment,
     E::FileHandle: Clone,
@@ -81,8 +78,9 @@ where
     E::VarName: Borrow<String> + From<String>,
     E::Var: Borrow<String> + From<String>,
 {
-    let matches = try_and_report!(CD, get_matches(args.into_iter()), env);
-    let flags = get_flags(&matches);
+    let args = try_or_usage!(CD, USAGE, BuiltinArgs::parse(args, "LP"), env);
+    try_or_usage!(CD, USAGE, args.check_max_operands(1), env);
+    let flags = get_flags(&args);
 
     let (new_working_dir, should_print_pwd) = match get_new_working_dir(&flags, env) {
         Ok(ret) => ret,
@@ -105,48 +103,16 @@ where
     }
 }
 
-fn get_matches<I>(args: I) -> ClapResult<ArgMatches<'static>>
-where
-    I: Iterator,
-    I::Item: StringWrapper,
-{
-    let app = App::new(CD)
-        .setting(AppSettings::NoBinaryName)
-        .setting(AppSettings::DisableVersion)
-        .about("Changes the current working directory of the shell")
-        .long_about(LONG_ABOUT)
-        .arg(
-            Arg::with_name(ARG_LOGICAL)
-                .short(ARG_LOGICAL)
-                .multiple(true)
-                .overrides_with(ARG_PHYSICAL)
-                .help("Handle paths logically (symbolic links will not be resolved)"),
-        )
-        .arg(
-            Arg::with_name(ARG_PHYSICAL)
-                .short(ARG_PHYSICAL)
-                .multiple(true)
-                .overrides_with(ARG_LOGICAL)
-                .help("Handle paths physically (all symbolic links resolved)"),
-        )
-        .arg(Arg::with_name(ARG_DIR).help(
-            "An absolute or relative path for the what shall become the new working directory",
-        ));
-
-    let app_args = args.map(StringWrapper::into_owned);
-    app.get_matches_from_safe(app_args)
-}
-
 #[derive(Debug)]
 struct Flags<'a> {
     resolve_symlinks: bool,
     dir: Option<&'a str>,
 }
 
-fn get_flags<'a>(matches: &'a ArgMatches<'a>) -> Flags<'a> {
+fn get_flags(args: &BuiltinArgs) -> Flags<'_> {
     Flags {
-        resolve_symlinks: matches.is_present(ARG_PHYSICAL),
-        dir: matches.value_of(ARG_DIR),
+        resolve_symlinks: args.last_of(&['L', 'P']) == Some('P'),
+        dir: args.operands().first().map(String::as_str),
     }
 }
 
@@ -155,7 +121,7 @@ fn get_new_working_dir<E: ?Sized>(
     env: &E,
 ) -> Result<(NormalizedPath, bool), CdError>
 where
-    E: VariableEnvironment + WorkingDirectoryEnvironment,
+    E: HomeDirEnvironment + VariableEnvironment + WorkingDirectoryEnvironment,
     E::VarName: Borrow<String>,
     E::Var: Borrow<String>,
 {
@@ -188,14 +154,18 @@ fn get_dir_arg<'a, E: ?Sized>(
     env: &'a E,
 ) -> Result<(Cow<'a, Path>, bool), VarNotDefinedError>
 where
-    E: VariableEnvironment + WorkingDirectoryEnvironment,
+    E: HomeDirEnvironment + VariableEnvironment + WorkingDirectoryEnvironment,
     E::VarName: Borrow<String>,
     E::Var: Borrow<String>,
 {
     let mut should_print_pwd = false;
     let dir = match dir {
-        None => match env.var(&HOME) {
-            Some(home) => Path::new((*home).borrow()),
+        None => match env.home_dir() {
+            Some(Cow::Borrowed(home)) => Path::new(home),
+            Some(Cow::Owned(home)) => {
+                let (dir, via_cdpath) = resolve_dir(Path::new(&home), env);
+                return Ok((Cow::Owned(dir.into_owned()), via_cdpath));
+            }
             None => return Err(VarNotDefinedError::Home),
         },
         Some("-") => match env.var(&OLDPWD) {
@@ -208,6 +178,18 @@ where
         Some(d) => Path::new(d),
     };
 
+    let (dir, via_cdpath) = resolve_dir(dir, env);
+    Ok((dir, should_print_pwd || via_cdpath))
+}
+
+/// Resolves a directory relative to the working directory or `$CDPATH`,
+/// indicating if the latter was used.
+fn resolve_dir<'a, E: ?Sized>(dir: &'a Path, env: &'a E) -> (Cow<'a, Path>, bool)
+where
+    E: VariableEnvironment + WorkingDirectoryEnvironment,
+    E::VarName: Borrow<String>,
+    E::Var: Borrow<String>,
+{
     let candidate = if is_cdpath_candidate(dir) {
         env.var(&CDPATH)
             .and_then(|cdpath| cdpath_candidate(dir, cdpath.borrow().as_str(), env))
@@ -215,15 +197,10 @@ where
         None
     };
 
-    let dir = match candidate {
-        Some(c) => {
-            should_print_pwd = true;
-            c
-        }
-        None => env.path_relative_to_working_dir(Cow::Borrowed(dir)),
-    };
-
-    Ok((dir, should_print_pwd))
+    match candidate {
+        Some(c) => (c, true),
+        None => (env.path_relative_to_working_dir(Cow::Borrowed(dir)), false),
+    }
 }
 
 fn is_cdpath_candidate(path: &Path) -> bool {
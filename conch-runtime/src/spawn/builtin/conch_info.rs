@@ -0,0 +1,29 @@
+use super::{generate_and_print_output, BuiltinArgs};
+use crate::env::{AsyncIoEnvironment, FileDescEnvironment, RuntimeInfoEnvironment, StringWrapper};
+use crate::spawn::ExitStatus;
+use futures_util::future::BoxFuture;
+
+const CONCH_INFO: &str = "conch-info";
+const USAGE: &str = "";
+
+/// The `conch-info` builtin command prints a diagnostic report of the runtime's
+/// capabilities, I/O backends, open file descriptors, and usage counters.
+///
+/// This is not a standard shell utility, but can be useful for debugging
+/// misbehaving environment configurations.
+pub async fn conch_info<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized + AsyncIoEnvironment + FileDescEnvironment + RuntimeInfoEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    let args = try_or_usage!(CONCH_INFO, USAGE, BuiltinArgs::parse(args, ""), env);
+    try_or_usage!(CONCH_INFO, USAGE, args.check_max_operands(0), env);
+
+    generate_and_print_output(CONCH_INFO, env, |env| {
+        env.runtime_info().map(|info| info.to_string().into_bytes())
+    })
+    .await
+}
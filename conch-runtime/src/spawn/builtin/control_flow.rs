@@ -0,0 +1,195 @@
+use super::{report_err, BuiltinArgs, UsageError};
+use crate::env::{
+    AsyncIoEnvironment, ControlFlow, ControlFlowEnvironment, FileDescEnvironment,
+    FunctionFrameEnvironment, LastStatusEnvironment, StringWrapper,
+};
+use crate::{ExitStatus, EXIT_SUCCESS};
+use futures_util::future::BoxFuture;
+
+const BREAK: &str = "break";
+const CONTINUE: &str = "continue";
+const EXIT: &str = "exit";
+const RETURN: &str = "return";
+const USAGE: &str = "[n]";
+
+#[derive(Debug, thiserror::Error)]
+#[error("numeric argument required")]
+struct NumericArgumentRequiredError;
+
+#[derive(Debug, thiserror::Error)]
+#[error("can only return from a function")]
+struct NotInFunctionError;
+
+#[derive(Debug, thiserror::Error)]
+#[error("only meaningful in a loop")]
+struct NotInLoopError;
+
+#[derive(Debug, thiserror::Error)]
+enum LoopCountError {
+    #[error("numeric argument required")]
+    NotNumeric,
+    #[error("{0}: loop count out of range")]
+    OutOfRange(String),
+}
+
+/// The `exit` builtin command will exit the current shell (or subshell) with
+/// the specified status, or the status of the previous command if omitted.
+///
+/// Since only the least significant 8 bits of a status are observable by a
+/// parent process, the specified status is truncated accordingly.
+pub async fn exit<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized
+        + AsyncIoEnvironment
+        + ControlFlowEnvironment
+        + FileDescEnvironment
+        + LastStatusEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    let args = try_or_usage!(EXIT, USAGE, parse_status(args), env);
+    let status = try_and_report!(EXIT, args, env).unwrap_or_else(|| env.last_status());
+
+    env.request_control_flow(ControlFlow::Exit(status));
+    Box::pin(async move { status })
+}
+
+/// The `return` builtin command will return from the currently executing
+/// function with the specified status, or the status of the previous command
+/// if omitted.
+///
+/// Since only the least significant 8 bits of a status are observable by a
+/// parent process, the specified status is truncated accordingly.
+///
+/// Attempting to return while no function is executing reports an error
+/// and exits with a status of 1.
+pub async fn return_cmd<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized
+        + AsyncIoEnvironment
+        + ControlFlowEnvironment
+        + FileDescEnvironment
+        + FunctionFrameEnvironment
+        + LastStatusEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    let args = try_or_usage!(RETURN, USAGE, parse_status(args), env);
+    let status = try_and_report!(RETURN, args, env).unwrap_or_else(|| env.last_status());
+
+    if !env.is_fn_running() {
+        return report_err(RETURN, env, NotInFunctionError).await;
+    }
+
+    env.request_control_flow(ControlFlow::Return(status));
+    Box::pin(async move { status })
+}
+
+/// Parses the optional status operand of `exit` or `return`.
+#[allow(clippy::type_complexity)]
+fn parse_status<I>(
+    args: I,
+) -> Result<Result<Option<ExitStatus>, NumericArgumentRequiredError>, UsageError>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+{
+    let args = BuiltinArgs::parse(args, "")?;
+    args.check_max_operands(1)?;
+
+    let status = match args.operands().first() {
+        Some(status) => match status.parse::<u32>() {
+            Ok(status) => Some(ExitStatus::Code((status & 0xff) as i32)),
+            Err(_) => return Ok(Err(NumericArgumentRequiredError)),
+        },
+        None => None,
+    };
+
+    Ok(Ok(status))
+}
+
+/// The `break` builtin command will break out of the specified number of
+/// enclosing loops (or all of them if there are fewer), or the innermost
+/// loop if omitted.
+///
+/// Attempting to break while no loop is executing reports an error, but
+/// otherwise has no effect (i.e. the command still exits successfully).
+pub async fn break_cmd<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized + AsyncIoEnvironment + ControlFlowEnvironment + FileDescEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    loop_control(BREAK, ControlFlow::Break, args, env).await
+}
+
+/// The `continue` builtin command will skip to the next iteration of the
+/// loop which is the specified number of levels out (or the outermost loop
+/// if there are fewer), or of the innermost loop if omitted.
+///
+/// Attempting to continue while no loop is executing reports an error, but
+/// otherwise has no effect (i.e. the command still exits successfully).
+pub async fn continue_cmd<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized + AsyncIoEnvironment + ControlFlowEnvironment + FileDescEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    loop_control(CONTINUE, ControlFlow::Continue, args, env).await
+}
+
+async fn loop_control<I, E>(
+    name: &'static str,
+    flow: fn(usize) -> ControlFlow,
+    args: I,
+    env: &mut E,
+) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized + AsyncIoEnvironment + ControlFlowEnvironment + FileDescEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    let args = try_or_usage!(name, USAGE, parse_loop_count(args), env);
+    let levels = try_and_report!(name, args, env);
+
+    let depth = env.loop_depth();
+    if depth == 0 {
+        report_err(name, env, NotInLoopError).await.await;
+        return Box::pin(async { EXIT_SUCCESS });
+    }
+
+    env.request_control_flow(flow(levels.min(depth)));
+    Box::pin(async { EXIT_SUCCESS })
+}
+
+/// Parses the optional loop count operand of `break` or `continue`.
+#[allow(clippy::type_complexity)]
+fn parse_loop_count<I>(args: I) -> Result<Result<usize, LoopCountError>, UsageError>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+{
+    let args = BuiltinArgs::parse(args, "")?;
+    args.check_max_operands(1)?;
+
+    let levels = match args.operands().first() {
+        Some(levels) => match levels.parse::<usize>() {
+            Ok(0) => return Ok(Err(LoopCountError::OutOfRange(levels.clone()))),
+            Ok(levels) => levels,
+            Err(_) => return Ok(Err(LoopCountError::NotNumeric)),
+        },
+        None => 1,
+    };
+
+    Ok(Ok(levels))
+}
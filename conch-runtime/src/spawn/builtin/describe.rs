@@ -0,0 +1,210 @@
+use super::{generate_and_print_output, report_err, BuiltinArgs};
+use crate::env::{
+    is_executable, AsyncIoEnvironment, BuiltinEnvironment, CommandSearchEnvironment,
+    FileDescEnvironment, FunctionEnvironment, StringWrapper, WorkingDirectoryEnvironment,
+};
+use crate::{ExitStatus, EXIT_ERROR, EXIT_SUCCESS};
+use futures_util::future::BoxFuture;
+use std::borrow::Cow;
+use std::fs;
+use std::path::{Path, PathBuf};
+use void::Void;
+
+const COMMAND: &str = "command";
+const COMMAND_USAGE: &str = "[-v | -V] name [argument ...]";
+const TYPE: &str = "type";
+const TYPE_USAGE: &str = "name ...";
+const ARG_DESCRIBE: char = 'v';
+const ARG_DESCRIBE_VERBOSE: char = 'V';
+
+/// The reserved words of the shell grammar.
+const KEYWORDS: &[&str] = &[
+    "!", "{", "}", "case", "do", "done", "elif", "else", "esac", "fi", "for", "if", "in", "then",
+    "until", "while",
+];
+
+#[derive(Debug, thiserror::Error)]
+#[error("{0}: not found")]
+struct NotFoundError(String);
+
+#[derive(Debug, thiserror::Error)]
+#[error("commands can only be executed as part of a simple command")]
+struct UnsupportedError;
+
+/// What a command name would invoke if it were executed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum CommandKind {
+    Keyword,
+    Function,
+    Builtin,
+    External(PathBuf),
+}
+
+/// The `type` builtin command describes what each of the named commands would
+/// invoke if it were executed: a reserved word, a shell function, a builtin
+/// utility, or an executable (along with its location).
+///
+/// Names which cannot be found are reported, and result in an exit status of 1.
+pub async fn type_cmd<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized
+        + AsyncIoEnvironment
+        + BuiltinEnvironment<BuiltinName = <E as FunctionEnvironment>::FnName>
+        + CommandSearchEnvironment
+        + FileDescEnvironment
+        + FunctionEnvironment
+        + WorkingDirectoryEnvironment,
+    E::FileHandle: Clone,
+    E::FnName: From<String>,
+    E::IoHandle: From<E::FileHandle>,
+{
+    let args = try_or_usage!(TYPE, TYPE_USAGE, BuiltinArgs::parse(args, ""), env);
+    describe(TYPE, args.into_operands(), true, true, env).await
+}
+
+/// The `command` builtin command executes a command while bypassing any shell
+/// functions, or describes what each of the named commands would invoke.
+///
+/// With `-v`, the location of an executable is printed, while any other command
+/// is printed as is. With `-V`, a description like that of the `type` builtin is
+/// printed instead. Names which cannot be found result in an exit status of 1,
+/// but are only reported with `-V`.
+///
+/// If neither option is specified, the named command is executed in place of
+/// the builtin while bypassing any functions of the same name. This is handled
+/// by `simple_command` itself (see `BuiltinUtility::bypasses_functions`), thus
+/// this function will only report an error if given a command to execute.
+pub async fn command<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized
+        + AsyncIoEnvironment
+        + BuiltinEnvironment<BuiltinName = <E as FunctionEnvironment>::FnName>
+        + CommandSearchEnvironment
+        + FileDescEnvironment
+        + FunctionEnvironment
+        + WorkingDirectoryEnvironment,
+    E::FileHandle: Clone,
+    E::FnName: From<String>,
+    E::IoHandle: From<E::FileHandle>,
+{
+    let args = try_or_usage!(COMMAND, COMMAND_USAGE, BuiltinArgs::parse(args, "vV"), env);
+
+    let verbose = match args.last_of(&[ARG_DESCRIBE, ARG_DESCRIBE_VERBOSE]) {
+        Some(ARG_DESCRIBE) => false,
+        Some(_) => true,
+        None if args.operands().is_empty() => return Box::pin(async { EXIT_SUCCESS }),
+        None => return report_err(COMMAND, env, UnsupportedError).await,
+    };
+
+    describe(COMMAND, args.into_operands(), verbose, verbose, env).await
+}
+
+async fn describe<E>(
+    builtin_name: &str,
+    names: Vec<String>,
+    verbose: bool,
+    report_missing: bool,
+    env: &mut E,
+) -> BoxFuture<'static, ExitStatus>
+where
+    E: ?Sized
+        + AsyncIoEnvironment
+        + BuiltinEnvironment<BuiltinName = <E as FunctionEnvironment>::FnName>
+        + CommandSearchEnvironment
+        + FileDescEnvironment
+        + FunctionEnvironment
+        + WorkingDirectoryEnvironment,
+    E::FileHandle: Clone,
+    E::FnName: From<String>,
+    E::IoHandle: From<E::FileHandle>,
+{
+    let mut out = String::new();
+    let mut missing = Vec::new();
+
+    for name in names {
+        let kind = match command_kind(&name, env) {
+            Some(kind) => kind,
+            None => {
+                missing.push(name);
+                continue;
+            }
+        };
+
+        let line = match (kind, verbose) {
+            (CommandKind::External(path), false) => path.to_string_lossy().into_owned(),
+            (_, false) => name,
+            (CommandKind::Keyword, true) => format!("{} is a shell keyword", name),
+            (CommandKind::Function, true) => format!("{} is a shell function", name),
+            (CommandKind::Builtin, true) => format!("{} is a shell builtin", name),
+            (CommandKind::External(path), true) => format!("{} is {}", name, path.display()),
+        };
+
+        out.push_str(&line);
+        out.push('\n');
+    }
+
+    let status = if out.is_empty() {
+        EXIT_SUCCESS
+    } else {
+        generate_and_print_output(builtin_name, env, |_| -> Result<_, Void> {
+            Ok(out.into_bytes())
+        })
+        .await
+        .await
+    };
+
+    if missing.is_empty() {
+        return Box::pin(async move { status });
+    }
+
+    if report_missing {
+        for name in missing {
+            report_err(builtin_name, env, NotFoundError(name))
+                .await
+                .await;
+        }
+    }
+
+    Box::pin(async { EXIT_ERROR })
+}
+
+/// Determines what a command name would invoke, if anything.
+fn command_kind<E>(name: &str, env: &mut E) -> Option<CommandKind>
+where
+    E: ?Sized
+        + BuiltinEnvironment<BuiltinName = <E as FunctionEnvironment>::FnName>
+        + CommandSearchEnvironment
+        + FunctionEnvironment
+        + WorkingDirectoryEnvironment,
+    E::FnName: From<String>,
+{
+    if name.contains(std::path::is_separator) {
+        let path = env.path_relative_to_working_dir(Cow::Borrowed(Path::new(name)));
+        let is_executable_file = fs::metadata(&path)
+            .map(|metadata| metadata.is_file() && is_executable(&metadata))
+            .unwrap_or(false);
+
+        return if is_executable_file {
+            Some(CommandKind::External(PathBuf::from(name)))
+        } else {
+            None
+        };
+    }
+
+    if KEYWORDS.contains(&name) {
+        return Some(CommandKind::Keyword);
+    }
+
+    let fn_name = E::FnName::from(name.to_owned());
+    if env.has_function(&fn_name) {
+        Some(CommandKind::Function)
+    } else if env.builtin(&fn_name).is_some() {
+        Some(CommandKind::Builtin)
+    } else {
+        env.search_command(name).map(CommandKind::External)
+    }
+}
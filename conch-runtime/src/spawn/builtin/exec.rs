@@ -0,0 +1,107 @@
+use super::{report_err, BuiltinArgs};
+use crate::env::{
+    AsyncIoEnvironment, ExecReplaceEnvironment, ExecutableData, ExecutableStdio,
+    FileDescEnvironment, RedirectEnvRestorer, StringWrapper, VariableEnvironment,
+    WorkingDirectoryEnvironment,
+};
+use crate::error::CommandError;
+use crate::io::FileDescWrapper;
+use crate::{ExitStatus, Fd, EXIT_SUCCESS, STDERR_FILENO, STDIN_FILENO, STDOUT_FILENO};
+use futures_util::future::BoxFuture;
+use std::borrow::Borrow;
+use std::convert::Infallible;
+use std::ffi::OsStr;
+
+const EXEC: &str = "exec";
+const USAGE: &str = "[command [argument ...]]";
+
+/// The `exec` builtin command will replace the current shell with the
+/// specified command.
+///
+/// If no command is specified, any redirections applied to the builtin are
+/// not restored once it completes, and instead remain in effect for the rest
+/// of the script. Otherwise the command (which is always treated as an
+/// executable, even if a function or builtin with the same name is defined)
+/// replaces the current process via `ExecReplaceEnvironment`, inheriting the
+/// shell's standard streams and exported variables.
+///
+/// Since builtins are not aware of which of their arguments were derived
+/// from tainted variables, the executed command is always considered tainted.
+pub async fn exec<'a, I, R, E>(args: I, restorer: &mut R) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    R: ?Sized + RedirectEnvRestorer<'a, E>,
+    E: 'a
+        + ?Sized
+        + AsyncIoEnvironment
+        + ExecReplaceEnvironment
+        + FileDescEnvironment
+        + VariableEnvironment
+        + WorkingDirectoryEnvironment,
+    E::FileHandle: Clone + FileDescWrapper,
+    E::IoHandle: From<E::FileHandle>,
+    E::VarName: Borrow<String>,
+    E::Var: Borrow<String>,
+{
+    let env = restorer.get_mut();
+    let args = try_or_usage!(EXEC, USAGE, BuiltinArgs::parse(args, ""), env);
+    let argv = args.into_operands();
+
+    if argv.is_empty() {
+        // NB: forget about the original file descriptors so they aren't restored
+        restorer.clear_redirects();
+        return Box::pin(async { EXIT_SUCCESS });
+    }
+
+    let env = restorer.get_mut();
+    match exec_replace(&argv, env) {
+        Ok(future) => Box::pin(async move { match future.await {} }),
+        Err(e) => {
+            let status = e.exit_status();
+            report_err(EXEC, env, e).await.await;
+            Box::pin(async move { status })
+        }
+    }
+}
+
+fn exec_replace<E>(argv: &[String], env: &E) -> Result<BoxFuture<'static, Infallible>, CommandError>
+where
+    E: ?Sized
+        + ExecReplaceEnvironment
+        + FileDescEnvironment
+        + VariableEnvironment
+        + WorkingDirectoryEnvironment,
+    E::FileHandle: Clone + FileDescWrapper,
+    E::VarName: Borrow<String>,
+    E::Var: Borrow<String>,
+{
+    let get_io = |fd: Fd| -> Result<_, CommandError> {
+        match env.file_desc(fd) {
+            None => Ok(ExecutableStdio::Null),
+            Some((fdes, _)) => fdes
+                .clone()
+                .try_unwrap()
+                .map(ExecutableStdio::Piped)
+                .map_err(|err| CommandError::Io(err, Some(format!("file descriptor {}", fd)))),
+        }
+    };
+
+    let args = argv[1..].iter().map(OsStr::new).collect::<Vec<_>>();
+    let env_vars = env
+        .env_vars()
+        .iter()
+        .map(|&(key, val)| (OsStr::new(key.borrow()), OsStr::new(val.borrow())))
+        .collect::<Vec<_>>();
+
+    let data = ExecutableData::builder(OsStr::new(&argv[0]), env.current_working_dir())
+        .args(&args)
+        .env_vars(&env_vars)
+        .stdin(get_io(STDIN_FILENO)?)
+        .stdout(get_io(STDOUT_FILENO)?)
+        .stderr(get_io(STDERR_FILENO)?)
+        .name_tainted(true)
+        .build();
+
+    env.exec_replace(data)
+}
@@ -0,0 +1,67 @@
+use super::{generate_and_print_output, report_err, BuiltinArgs};
+use crate::env::{
+    AsyncIoEnvironment, CommandSearchEnvironment, FileDescEnvironment, StringWrapper,
+};
+use crate::{ExitStatus, EXIT_ERROR, EXIT_SUCCESS};
+use futures_util::future::BoxFuture;
+use void::Void;
+
+const HASH: &str = "hash";
+const USAGE: &str = "[-r] [name ...]";
+const ARG_FORGET: char = 'r';
+
+#[derive(Debug, thiserror::Error)]
+#[error("{0}: not found")]
+struct NotFoundError(String);
+
+/// The `hash` builtin command remembers the locations of each of the named
+/// commands by searching for them within the `$PATH` directories, or prints
+/// the locations of all remembered commands if no names are specified.
+///
+/// If `-r` is specified, all remembered locations are forgotten first. Names
+/// which cannot be found are reported, and result in an exit status of 1,
+/// while names containing a path separator are silently ignored.
+pub async fn hash<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized + AsyncIoEnvironment + CommandSearchEnvironment + FileDescEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    let args = try_or_usage!(HASH, USAGE, BuiltinArgs::parse(args, "r"), env);
+
+    if args.is_present(ARG_FORGET) {
+        env.forget_hashed_commands();
+    }
+
+    if args.operands().is_empty() {
+        if args.is_present(ARG_FORGET) {
+            return Box::pin(async { EXIT_SUCCESS });
+        }
+
+        return generate_and_print_output(HASH, env, |env| -> Result<_, Void> {
+            let mut out = String::new();
+            for (_, path) in env.hashed_commands() {
+                out.push_str(&path.to_string_lossy());
+                out.push('\n');
+            }
+            Ok(out.into_bytes())
+        })
+        .await;
+    }
+
+    let mut status = EXIT_SUCCESS;
+    for name in args.into_operands() {
+        if name.contains(std::path::is_separator) {
+            continue;
+        }
+
+        if env.search_command(&name).is_none() {
+            report_err(HASH, env, NotFoundError(name)).await.await;
+            status = EXIT_ERROR;
+        }
+    }
+
+    Box::pin(async move { status })
+}
@@ -0,0 +1,187 @@
+use super::generate_and_write_bytes_to_fd_if_present;
+use crate::env::{AsyncIoEnvironment, FileDescEnvironment, StringWrapper};
+use crate::{ExitStatus, EXIT_ERROR, STDERR_FILENO};
+use futures_util::future::BoxFuture;
+use std::str::FromStr;
+use void::Void;
+
+/// An error describing an incorrect invocation of a builtin utility.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum UsageError {
+    /// An option which the utility does not recognize was specified.
+    #[error("-{0}: invalid option")]
+    InvalidOption(char),
+    /// An option which requires a value was specified without one.
+    #[error("-{0}: option requires an argument")]
+    MissingValue(char),
+    /// The value of an option could not be parsed.
+    #[error("-{0}: {1}: invalid argument")]
+    InvalidValue(char, String),
+    /// More operands were specified than the utility accepts.
+    #[error("too many arguments")]
+    TooManyOperands,
+    /// Fewer operands were specified than the utility requires.
+    #[error("not enough arguments")]
+    TooFewOperands,
+}
+
+/// The options and operands of a builtin utility, parsed according to
+/// the POSIX utility syntax guidelines.
+///
+/// Options are single characters which may be grouped together (e.g. `-ab`
+/// is the same as `-a -b`), and the value of an option can either be attached
+/// to it (e.g. `-tvalue`) or be the following argument (e.g. `-t value`).
+/// Option parsing stops at the first argument which does not start with `-`,
+/// is exactly `-`, or is exactly `--` (which is itself discarded).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BuiltinArgs {
+    opts: Vec<(char, Option<String>)>,
+    operands: Vec<String>,
+}
+
+impl BuiltinArgs {
+    /// Parses the arguments of a builtin utility.
+    ///
+    /// Much like the `getopts` utility, `optstring` lists all recognized options,
+    /// where any option which requires a value is followed by a `:` (e.g. `"Lt:"`).
+    pub fn parse<I>(args: I, optstring: &str) -> Result<Self, UsageError>
+    where
+        I: IntoIterator,
+        I::Item: StringWrapper,
+    {
+        let mut args = args.into_iter().map(StringWrapper::into_owned);
+        let mut opts = Vec::new();
+
+        let mut operands = Vec::new();
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                break;
+            } else if arg == "-" || !arg.starts_with('-') {
+                operands.push(arg);
+                break;
+            }
+
+            for (idx, opt) in arg.char_indices().skip(1) {
+                let takes_value = match opt_spec(optstring, opt) {
+                    Some(takes_value) => takes_value,
+                    None => return Err(UsageError::InvalidOption(opt)),
+                };
+
+                if !takes_value {
+                    opts.push((opt, None));
+                    continue;
+                }
+
+                let rest = &arg[idx + opt.len_utf8()..];
+                let value = if rest.is_empty() {
+                    args.next().ok_or(UsageError::MissingValue(opt))?
+                } else {
+                    rest.to_owned()
+                };
+
+                opts.push((opt, Some(value)));
+                break;
+            }
+        }
+
+        operands.extend(args);
+        Ok(Self { opts, operands })
+    }
+
+    /// Checks if an option was specified at least once.
+    pub fn is_present(&self, opt: char) -> bool {
+        self.opts.iter().any(|&(o, _)| o == opt)
+    }
+
+    /// Returns whichever of the specified (mutually exclusive) options was
+    /// specified last, if any (e.g. `-L` and `-P` override each other).
+    pub fn last_of(&self, opts: &[char]) -> Option<char> {
+        self.opts
+            .iter()
+            .rev()
+            .map(|&(o, _)| o)
+            .find(|o| opts.contains(o))
+    }
+
+    /// Returns the value of an option, if it was specified.
+    ///
+    /// If an option was specified multiple times, its last value is returned.
+    pub fn value(&self, opt: char) -> Option<&str> {
+        self.opts
+            .iter()
+            .rev()
+            .find(|&&(o, _)| o == opt)
+            .and_then(|(_, value)| value.as_deref())
+    }
+
+    /// Parses the value of an option, if it was specified.
+    pub fn parse_value<T: FromStr>(&self, opt: char) -> Result<Option<T>, UsageError> {
+        match self.value(opt) {
+            Some(value) => value
+                .parse()
+                .map(Some)
+                .map_err(|_| UsageError::InvalidValue(opt, value.to_owned())),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns all operands which followed the options.
+    pub fn operands(&self) -> &[String] {
+        &self.operands
+    }
+
+    /// Ensures that no more than `max` operands were specified.
+    pub fn check_max_operands(&self, max: usize) -> Result<(), UsageError> {
+        if self.operands.len() > max {
+            Err(UsageError::TooManyOperands)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Unwraps all operands which followed the options.
+    pub fn into_operands(self) -> Vec<String> {
+        self.operands
+    }
+}
+
+/// Looks up an option in a `getopts` style `optstring`,
+/// returning whether it takes a value, if it is present at all.
+fn opt_spec(optstring: &str, opt: char) -> Option<bool> {
+    if opt == ':' {
+        return None;
+    }
+
+    let idx = optstring.find(opt)?;
+    Some(optstring[idx + opt.len_utf8()..].starts_with(':'))
+}
+
+/// Reports an incorrect invocation of a builtin utility to standard error,
+/// followed by a synopsis of its usage (e.g. `[-LP] [dir]`), and exits with an error.
+pub async fn report_usage_err<E>(
+    builtin_name: &str,
+    usage: &str,
+    env: &mut E,
+    err: UsageError,
+) -> BoxFuture<'static, ExitStatus>
+where
+    E: ?Sized + AsyncIoEnvironment + FileDescEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    generate_and_write_bytes_to_fd_if_present(
+        builtin_name,
+        env,
+        STDERR_FILENO,
+        EXIT_ERROR,
+        |_| -> Result<_, Void> {
+            let mut msg = format_err!(builtin_name, err);
+            msg.extend(format_err!(
+                builtin_name,
+                format!("usage: {} {}", builtin_name, usage)
+            ));
+            Ok(msg)
+        },
+    )
+    .await
+}
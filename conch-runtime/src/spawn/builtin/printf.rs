@@ -0,0 +1,490 @@
+use super::{generate_and_print_output, report_err, report_usage_err, UsageError};
+use crate::env::{AsyncIoEnvironment, FileDescEnvironment, StringWrapper};
+use crate::eval::quote_for_shell;
+use crate::{ExitStatus, EXIT_ERROR};
+use futures_util::future::BoxFuture;
+use std::str::Chars;
+use void::Void;
+
+const PRINTF: &str = "printf";
+const USAGE: &str = "format [argument ...]";
+/// The largest field width or precision which may be specified, just like C.
+const MAX_FIELD_WIDTH: i64 = i32::MAX as i64;
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+enum PrintfError {
+    #[error("{0}: invalid number")]
+    InvalidNumber(String),
+    #[error("%{0}: invalid conversion specification")]
+    InvalidConversion(String),
+    #[error("missing format character")]
+    MissingConversion,
+}
+
+/// The `printf` builtin command writes its arguments as described by a format.
+///
+/// The format is printed as is, except for any escape sequences (e.g. `\n`,
+/// `\t`, or `\NNN` for an octal byte), and conversion specifications which
+/// consume the next argument: `%s` (a string), `%b` (a string with any escape
+/// sequences interpreted), `%q` (a string quoted for reuse as shell input),
+/// `%c` (the first character of a string), `%d` or `%i` (a signed decimal),
+/// `%u` (an unsigned decimal), `%o` (an octal), `%x` or `%X` (a hexadecimal),
+/// and `%%` (a literal `%`). Conversions may specify the usual flags (`-`, `+`,
+/// ` `, `#`, `0`), field width, and precision, where `*` consumes an argument.
+///
+/// Just like POSIX requires, the format is reused until all arguments are
+/// consumed, and any missing arguments are treated as empty strings (or zero).
+/// Invalid numbers (including any field widths or precisions which exceed
+/// `i32::MAX`, just like C) are reported (and treated as zero), but do not stop
+/// the output, although they will result in an exit status of 1.
+pub async fn printf<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized + AsyncIoEnvironment + FileDescEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    let mut args = args
+        .into_iter()
+        .map(StringWrapper::into_owned)
+        .collect::<Vec<_>>();
+
+    // NB: the format may begin with a `-`, so we only skip the `--` delimiter
+    if args.first().map(String::as_str) == Some("--") {
+        args.remove(0);
+    }
+
+    if args.is_empty() {
+        return report_usage_err(PRINTF, USAGE, env, UsageError::TooFewOperands).await;
+    }
+
+    let format = args.remove(0);
+    let mut formatter = Formatter {
+        args: &args,
+        next_arg: 0,
+        out: Vec::new(),
+        errors: Vec::new(),
+    };
+    formatter.format(&format);
+
+    let Formatter { out, errors, .. } = formatter;
+
+    let status = generate_and_print_output(PRINTF, env, |_| -> Result<_, Void> { Ok(out) })
+        .await
+        .await;
+
+    if errors.is_empty() {
+        return Box::pin(async move { status });
+    }
+
+    for e in errors {
+        report_err(PRINTF, env, e).await.await;
+    }
+
+    Box::pin(async { EXIT_ERROR })
+}
+
+/// The flags, field width, and precision of a conversion specification.
+#[derive(Debug, Default, Clone, Copy)]
+struct Spec {
+    left_justify: bool,
+    plus_sign: bool,
+    space_sign: bool,
+    alternate: bool,
+    zero_pad: bool,
+    width: usize,
+    precision: Option<usize>,
+}
+
+struct Formatter<'a> {
+    args: &'a [String],
+    next_arg: usize,
+    out: Vec<u8>,
+    errors: Vec<PrintfError>,
+}
+
+impl<'a> Formatter<'a> {
+    /// Formats the arguments, reusing the format until all of them are consumed.
+    fn format(&mut self, format: &str) {
+        loop {
+            let consumed_before = self.next_arg;
+            if !self.format_once(format) {
+                break;
+            }
+
+            // NB: a format without any conversions should only be printed once
+            if self.next_arg >= self.args.len() || self.next_arg == consumed_before {
+                break;
+            }
+        }
+    }
+
+    /// Formats a single pass over the format, returning whether
+    /// output should continue (i.e. a `\c` was not encountered).
+    fn format_once(&mut self, format: &str) -> bool {
+        let mut chars = format.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => {
+                    if !push_escape(&mut self.out, &mut chars, false) {
+                        return false;
+                    }
+                }
+                '%' => {
+                    if !self.convert(&mut chars) {
+                        return false;
+                    }
+                }
+                c => push_char(&mut self.out, c),
+            }
+        }
+
+        true
+    }
+
+    fn next_arg(&mut self) -> Option<&'a str> {
+        let args = self.args;
+        let arg = args.get(self.next_arg).map(String::as_str);
+        if arg.is_some() {
+            self.next_arg += 1;
+        }
+        arg
+    }
+
+    fn next_number(&mut self) -> i64 {
+        match self.next_arg() {
+            Some(arg) => self.parse_number(arg),
+            None => 0,
+        }
+    }
+
+    /// Consumes the next argument as a field width (or precision), which is
+    /// reported (and treated as zero) if it exceeds `MAX_FIELD_WIDTH`.
+    fn next_field_width(&mut self) -> i64 {
+        let arg = match self.next_arg() {
+            Some(arg) => arg,
+            None => return 0,
+        };
+
+        let width = self.parse_number(arg);
+        if width.unsigned_abs() > MAX_FIELD_WIDTH as u64 {
+            self.errors.push(PrintfError::InvalidNumber(arg.to_owned()));
+            0
+        } else {
+            width
+        }
+    }
+
+    /// Parses the digits of a field width (or precision) within the format, which
+    /// is reported (and treated as zero) if it exceeds `MAX_FIELD_WIDTH`.
+    fn parse_field_width(&mut self, chars: &mut Chars<'_>) -> usize {
+        let digits = chars.as_str();
+        let mut value = 0i64;
+        let mut len = 0;
+        while let Some(digit) = peek(chars).and_then(|c| c.to_digit(10)) {
+            chars.next();
+            len += 1;
+            value = value.saturating_mul(10).saturating_add(i64::from(digit));
+        }
+
+        if value > MAX_FIELD_WIDTH {
+            let digits = digits[..len].to_owned();
+            self.errors.push(PrintfError::InvalidNumber(digits));
+            0
+        } else {
+            value as usize
+        }
+    }
+
+    /// Parses a numeric argument like a C integer constant, where a leading
+    /// quote instead yields the code point of the character that follows it.
+    fn parse_number(&mut self, arg: &str) -> i64 {
+        let mut chars = arg.chars();
+        if let Some('\'') | Some('"') = chars.next() {
+            return chars.next().map_or(0, |c| i64::from(u32::from(c)));
+        }
+
+        let trimmed = arg.trim_start();
+        if trimmed.is_empty() {
+            return 0;
+        }
+
+        let (negative, unsigned) = match trimmed.as_bytes()[0] {
+            b'-' => (true, &trimmed[1..]),
+            b'+' => (false, &trimmed[1..]),
+            _ => (false, trimmed),
+        };
+
+        let (radix, digits) = if unsigned.starts_with("0x") || unsigned.starts_with("0X") {
+            (16, &unsigned[2..])
+        } else if unsigned.len() > 1 && unsigned.starts_with('0') {
+            (8, &unsigned[1..])
+        } else {
+            (10, unsigned)
+        };
+
+        let valid = !digits.is_empty() && digits.chars().all(|c| c.is_digit(radix));
+        match u64::from_str_radix(digits, radix) {
+            Ok(value) if valid => {
+                let value = value as i64;
+                if negative {
+                    value.wrapping_neg()
+                } else {
+                    value
+                }
+            }
+            _ => {
+                self.errors.push(PrintfError::InvalidNumber(arg.to_owned()));
+                0
+            }
+        }
+    }
+
+    /// Parses a conversion specification (following a `%`), and formats
+    /// the next argument with it, returning whether output should continue.
+    fn convert(&mut self, chars: &mut Chars<'_>) -> bool {
+        let spec_text = chars.as_str();
+        let mut spec = Spec::default();
+
+        loop {
+            match peek(chars) {
+                Some('-') => spec.left_justify = true,
+                Some('+') => spec.plus_sign = true,
+                Some(' ') => spec.space_sign = true,
+                Some('#') => spec.alternate = true,
+                Some('0') => spec.zero_pad = true,
+                _ => break,
+            }
+            chars.next();
+        }
+
+        if peek(chars) == Some('*') {
+            chars.next();
+            let width = self.next_field_width();
+            spec.left_justify |= width < 0;
+            spec.width = width.unsigned_abs() as usize;
+        } else {
+            spec.width = self.parse_field_width(chars);
+        }
+
+        if peek(chars) == Some('.') {
+            chars.next();
+            spec.precision = if peek(chars) == Some('*') {
+                chars.next();
+                let precision = self.next_field_width();
+                // NB: a negative precision is taken as if it were omitted
+                if precision < 0 {
+                    None
+                } else {
+                    Some(precision as usize)
+                }
+            } else {
+                Some(self.parse_field_width(chars))
+            };
+        }
+
+        let conversion = match chars.next() {
+            Some(c) => c,
+            None => {
+                self.errors.push(PrintfError::MissingConversion);
+                return false;
+            }
+        };
+
+        match conversion {
+            '%' => self.out.push(b'%'),
+            's' => {
+                let arg = self.next_arg().unwrap_or("");
+                let arg = match spec.precision {
+                    Some(precision) => arg
+                        .char_indices()
+                        .nth(precision)
+                        .map_or(arg, |(i, _)| &arg[..i]),
+                    None => arg,
+                };
+                pad(&mut self.out, spec, arg.as_bytes());
+            }
+            'b' => {
+                let arg = self.next_arg().unwrap_or("");
+                let mut expanded = Vec::new();
+                let mut arg_chars = arg.chars();
+                let mut more = true;
+
+                while let Some(c) = arg_chars.next() {
+                    if c != '\\' {
+                        push_char(&mut expanded, c);
+                    } else if !push_escape(&mut expanded, &mut arg_chars, true) {
+                        more = false;
+                        break;
+                    }
+                }
+
+                if let Some(precision) = spec.precision {
+                    expanded.truncate(precision);
+                }
+
+                pad(&mut self.out, spec, &expanded);
+                return more;
+            }
+            'q' => {
+                let arg = self.next_arg().unwrap_or("");
+                pad(&mut self.out, spec, quote_for_shell(arg).as_bytes());
+            }
+            'c' => {
+                let arg = self.next_arg().unwrap_or("");
+                let end = arg.chars().next().map_or(0, char::len_utf8);
+                pad(&mut self.out, spec, &arg.as_bytes()[..end]);
+            }
+            'd' | 'i' => {
+                let value = self.next_number();
+                let sign = if value < 0 {
+                    "-"
+                } else if spec.plus_sign {
+                    "+"
+                } else if spec.space_sign {
+                    " "
+                } else {
+                    ""
+                };
+
+                let digits = value.unsigned_abs().to_string();
+                pad_integer(&mut self.out, spec, sign, digits, value == 0);
+            }
+            'u' | 'o' | 'x' | 'X' => {
+                let value = self.next_number() as u64;
+                let digits = match conversion {
+                    'u' => value.to_string(),
+                    'o' => format!("{:o}", value),
+                    'x' => format!("{:x}", value),
+                    _ => format!("{:X}", value),
+                };
+
+                let prefix = match conversion {
+                    'x' if spec.alternate && value != 0 => "0x",
+                    'X' if spec.alternate && value != 0 => "0X",
+                    _ => "",
+                };
+
+                // NB: the alternate form of an octal always has a leading zero
+                let digits = if conversion == 'o' && spec.alternate && !digits.starts_with('0') {
+                    format!("0{}", digits)
+                } else {
+                    digits
+                };
+
+                pad_integer(&mut self.out, spec, prefix, digits, value == 0);
+            }
+            _ => {
+                let len = spec_text.len() - chars.as_str().len();
+                let spec_text = spec_text[..len].to_owned();
+                self.errors.push(PrintfError::InvalidConversion(spec_text));
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+fn peek(chars: &Chars<'_>) -> Option<char> {
+    chars.as_str().chars().next()
+}
+
+fn push_char(out: &mut Vec<u8>, c: char) {
+    let mut buf = [0; 4];
+    out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+}
+
+/// Pads the bytes with spaces up to the field width.
+fn pad(out: &mut Vec<u8>, spec: Spec, bytes: &[u8]) {
+    let padding = spec.width.saturating_sub(bytes.len());
+
+    if !spec.left_justify {
+        out.resize(out.len() + padding, b' ');
+    }
+
+    out.extend_from_slice(bytes);
+
+    if spec.left_justify {
+        out.resize(out.len() + padding, b' ');
+    }
+}
+
+/// Pads the digits of an integer with zeros up to the precision (or field width
+/// if zero padding was requested), and places the prefix (e.g. sign) before them.
+fn pad_integer(out: &mut Vec<u8>, spec: Spec, prefix: &str, digits: String, is_zero: bool) {
+    let digits = match spec.precision {
+        // NB: an explicit precision of zero omits a zero value entirely
+        Some(0) if is_zero => String::new(),
+        Some(precision) => format!("{:0>width$}", digits, width = precision),
+        None if spec.zero_pad && !spec.left_justify => {
+            let width = spec.width.saturating_sub(prefix.len());
+            format!("{:0>width$}", digits, width = width)
+        }
+        None => digits,
+    };
+
+    pad(out, spec, format!("{}{}", prefix, digits).as_bytes());
+}
+
+/// Interprets an escape sequence (following a `\`), returning whether
+/// output should continue (i.e. the sequence was not `\c`).
+///
+/// Octal escapes have the form `\NNN`, but within the argument of a `%b`
+/// conversion the form `\0NNN` is also supported (just like `echo -e`).
+fn push_escape(out: &mut Vec<u8>, chars: &mut Chars<'_>, in_arg: bool) -> bool {
+    let c = match chars.next() {
+        Some(c) => c,
+        None => {
+            // NB: treat an incomplete escape as a literal
+            out.push(b'\\');
+            return true;
+        }
+    };
+
+    let byte = match c {
+        'a' => b'\x07',
+        'b' => b'\x08',
+        'c' => return false,
+        'e' => b'\x1B',
+        'f' => b'\x0C',
+        'n' => b'\n',
+        'r' => b'\r',
+        't' => b'\t',
+        'v' => b'\x0B',
+        '\\' => b'\\',
+        '"' if !in_arg => b'"',
+        '\'' if !in_arg => b'\'',
+        '0'..='7' => {
+            let (first, max_digits) = if in_arg && c == '0' {
+                (0, 3)
+            } else {
+                (c.to_digit(8).unwrap(), 2)
+            };
+
+            let mut value = first;
+            for _ in 0..max_digits {
+                match peek(chars).and_then(|c| c.to_digit(8)) {
+                    Some(digit) => {
+                        chars.next();
+                        value = value * 8 + digit;
+                    }
+                    None => break,
+                }
+            }
+
+            value as u8
+        }
+        c => {
+            // NB: treat unrecognized escapes as literals
+            out.push(b'\\');
+            push_char(out, c);
+            return true;
+        }
+    };
+
+    out.push(byte);
+    true
+}
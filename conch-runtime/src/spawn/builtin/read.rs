@@ -0,0 +1,268 @@
+use super::BuiltinArgs;
+use crate::env::{
+    AsyncIoEnvironment, BoxAsyncRead, FileDescEnvironment, StringWrapper, TaintEnvironment,
+};
+use crate::io::LineReader;
+use crate::{ExitStatus, EXIT_ERROR, EXIT_SUCCESS, IFS_DEFAULT, STDIN_FILENO};
+use futures_util::future::BoxFuture;
+use std::borrow::Borrow;
+use std::io;
+use std::time::Duration;
+
+const READ: &str = "read";
+const USAGE: &str = "[-r] [-t timeout] [-n nchars] [-d delim] [name ...]";
+const ARG_RAW: char = 'r';
+const ARG_TIMEOUT: char = 't';
+const ARG_NCHARS: char = 'n';
+const ARG_DELIM: char = 'd';
+
+const DEFAULT_VAR_NAME: &str = "REPLY";
+const DEFAULT_DELIMITER: u8 = b'\n';
+
+/// The status `read` exits with if it times out, which (like bash)
+/// is greater than 128 (specifically, `128 + SIGALRM`).
+const EXIT_TIMEOUT: ExitStatus = ExitStatus::Code(142);
+
+#[derive(Debug, thiserror::Error)]
+enum InvalidFlagError {
+    #[error("{0}: invalid timeout specification")]
+    Timeout(String),
+    #[error("{0}: delimiter must be a single-byte character")]
+    Delimiter(String),
+}
+
+/// The `read` builtin command will read a line from standard input
+/// and assign it to the specified variables (or `$REPLY` if no name is given).
+///
+/// The line is split into fields based on the contents of `$IFS`, and each
+/// field is assigned to the respective variable, with the last variable
+/// receiving the remainder of the line (minus any leading or trailing `$IFS`
+/// whitespace). Any variables without a corresponding field are set to the
+/// empty string. If no names are given, the entire line is assigned to
+/// `$REPLY` without any splitting.
+///
+/// Unless `-r` is specified, a backslash will escape the character which
+/// follows it (i.e. it will not be used to split fields), and a backslash
+/// at the end of a line will continue reading the next line (unless `-n`
+/// is specified).
+///
+/// If `-t timeout` is specified, `read` will give up if a full line is not
+/// available within `timeout` seconds, and exit with a status greater than 128.
+/// If `-n nchars` is specified, `read` will return after reading `nchars`
+/// characters rather than waiting for a full line (unless a newline is read first).
+/// If `-d delim` is specified, the first character of `delim` (or NUL if it is
+/// empty) is used to terminate the line instead of a newline. Since input is
+/// split on bytes, this character must be ASCII (i.e. a single byte).
+///
+/// In both cases, any partial input is still assigned to the variable.
+///
+/// If the environment's taint tracker is tainted (e.g. because `read` was
+/// invoked with words derived from tainted variables), the assigned variables
+/// are marked as tainted as well.
+pub async fn read<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized + AsyncIoEnvironment + FileDescEnvironment + TaintEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+    E::VarName: Borrow<String> + From<String>,
+    E::Var: Borrow<String> + From<String>,
+{
+    let args = try_or_usage!(READ, USAGE, BuiltinArgs::parse(args, "rt:n:d:"), env);
+    let nchars = try_or_usage!(READ, USAGE, args.parse_value(ARG_NCHARS), env);
+    let flags = try_and_report!(READ, get_flags(&args, nchars), env);
+
+    let names = args.into_operands();
+
+    let stdin = match env.file_desc(STDIN_FILENO) {
+        Some((fdes, _)) => E::IoHandle::from(fdes.clone()),
+        None => return Box::pin(async { EXIT_ERROR }),
+    };
+
+    let reader = try_and_report!(READ, env.read_async(stdin), env);
+    let mut reader = LineReader::new(reader);
+    reader.set_delimiter(flags.delim);
+
+    let mut buf = Vec::new();
+    let result = match flags.timeout {
+        Some(timeout) => {
+            let read = read_input(&mut reader, &mut buf, flags);
+            match tokio::time::timeout(timeout, read).await {
+                Ok(result) => result,
+                Err(_) => {
+                    buf.extend(reader.take_partial_line());
+                    Ok(Outcome::TimedOut)
+                }
+            }
+        }
+        None => read_input(&mut reader, &mut buf, flags).await,
+    };
+
+    let outcome = try_and_report!(READ, result, env);
+    let line = unescape(&String::from_utf8_lossy(&buf), flags.raw);
+    let tainted = env.taint_tracker().is_tainted();
+
+    if names.is_empty() {
+        let line = line.into_iter().map(|(c, _)| c).collect::<String>();
+        env.set_var(DEFAULT_VAR_NAME.to_owned().into(), line.into());
+        if tainted {
+            env.set_tainted(DEFAULT_VAR_NAME.to_owned().into(), true);
+        }
+    } else {
+        let ifs = env
+            .var(&"IFS".to_owned())
+            .map_or(IFS_DEFAULT, |ifs| ifs.borrow().as_str());
+
+        let mut fields = split_fields(&line, ifs, names.len()).into_iter();
+        for name in names {
+            let name = name.into_owned();
+            let field = fields.next().unwrap_or_default();
+            env.set_var(name.clone().into(), field.into());
+            if tainted {
+                env.set_tainted(name.into(), true);
+            }
+        }
+    }
+
+    let ret = match outcome {
+        Outcome::Complete => EXIT_SUCCESS,
+        Outcome::Eof => EXIT_ERROR,
+        Outcome::TimedOut => EXIT_TIMEOUT,
+    };
+
+    Box::pin(async move { ret })
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Flags {
+    raw: bool,
+    timeout: Option<Duration>,
+    nchars: Option<usize>,
+    delim: u8,
+}
+
+fn get_flags(args: &BuiltinArgs, nchars: Option<usize>) -> Result<Flags, InvalidFlagError> {
+    let timeout = match args.value(ARG_TIMEOUT) {
+        Some(t) => {
+            let secs = t
+                .parse::<f64>()
+                .ok()
+                .filter(|secs| secs.is_finite() && *secs >= 0.0)
+                .ok_or_else(|| InvalidFlagError::Timeout(t.to_owned()))?;
+
+            Some(Duration::from_secs_f64(secs))
+        }
+        None => None,
+    };
+
+    let delim = match args.value(ARG_DELIM).map(|d| (d, d.chars().next())) {
+        Some((_, Some(c))) if c.is_ascii() => c as u8,
+        Some((d, Some(_))) => return Err(InvalidFlagError::Delimiter(d.to_owned())),
+        Some((_, None)) => b'\0',
+        None => DEFAULT_DELIMITER,
+    };
+
+    Ok(Flags {
+        raw: args.is_present(ARG_RAW),
+        timeout,
+        nchars,
+        delim,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    /// A full line (or the requested number of characters) was read.
+    Complete,
+    /// EOF was reached before a full line could be read.
+    Eof,
+    /// The timeout expired before a full line could be read.
+    TimedOut,
+}
+
+/// Reads a line (or `nchars` characters) from `reader` into `buf`, noting
+/// whether EOF was reached before it could be completed.
+///
+/// Unless reading raw input (or a fixed number of characters), any lines which
+/// end with an (unescaped) backslash are joined with the line which follows.
+async fn read_input(
+    reader: &mut LineReader<BoxAsyncRead>,
+    buf: &mut Vec<u8>,
+    flags: Flags,
+) -> io::Result<Outcome> {
+    loop {
+        let line = match flags.nchars {
+            Some(nchars) => reader.read_line_max_chars(nchars).await?,
+            None => reader.read_line().await?,
+        };
+
+        buf.extend(line.unwrap_or_default());
+
+        // NB: the reader only hits EOF if the line could not be completed
+        if reader.is_eof() {
+            return Ok(Outcome::Eof);
+        }
+
+        let trailing_backslashes = buf.iter().rev().take_while(|&&b| b == b'\\').count();
+        if flags.raw || flags.nchars.is_some() || trailing_backslashes % 2 == 0 {
+            return Ok(Outcome::Complete);
+        }
+
+        // Drop the backslash, the reader has already dropped the delimiter
+        buf.pop();
+    }
+}
+
+/// Splits `line` into the characters it contains, each paired with a flag
+/// indicating whether it was escaped by a backslash (and thus should not
+/// be used for splitting fields).
+fn unescape(line: &str, raw: bool) -> Vec<(char, bool)> {
+    if raw {
+        return line.chars().map(|c| (c, false)).collect();
+    }
+
+    let mut ret = Vec::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            ret.push((c, false));
+        } else if let Some(escaped) = chars.next() {
+            ret.push((escaped, true));
+        }
+    }
+
+    ret
+}
+
+/// Splits `line` into (at most) `max_fields` fields based on the characters
+/// within `ifs`, with the last field holding the remainder of the line.
+///
+/// Like regular field splitting, any whitespace within `ifs` is collapsed, and
+/// each non-whitespace character within `ifs` delimits a (possibly empty) field.
+/// Any leading or trailing `ifs` whitespace is always ignored.
+fn split_fields(line: &[(char, bool)], ifs: &str, max_fields: usize) -> Vec<String> {
+    let is_ifs = |&(c, escaped): &(char, bool)| !escaped && ifs.contains(c);
+    let is_whitespace = |ch: &(char, bool)| is_ifs(ch) && ch.0.is_whitespace();
+    let collect = |chars: &[(char, bool)]| chars.iter().map(|&(c, _)| c).collect::<String>();
+
+    let mut fields = Vec::with_capacity(max_fields);
+    let mut line = &line[line.iter().take_while(|c| is_whitespace(c)).count()..];
+
+    while !line.is_empty() && fields.len() + 1 < max_fields {
+        let len = line.iter().take_while(|c| !is_ifs(c)).count();
+        fields.push(collect(&line[..len]));
+        line = &line[len..];
+
+        // Skip the delimiter along with any whitespace around it
+        line = &line[line.iter().take_while(|c| is_whitespace(c)).count()..];
+        if line.first().is_some_and(is_ifs) {
+            line = &line[1..];
+            line = &line[line.iter().take_while(|c| is_whitespace(c)).count()..];
+        }
+    }
+
+    let trailing = line.iter().rev().take_while(|c| is_whitespace(c)).count();
+    fields.push(collect(&line[..line.len() - trailing]));
+    fields
+}
@@ -0,0 +1,140 @@
+use super::{generate_and_print_output, report_err, report_usage_err, UsageError};
+use crate::env::{
+    AsyncIoEnvironment, FileDescEnvironment, SetArgumentsEnvironment, ShellOptions,
+    ShellOptionsEnvironment, StringWrapper,
+};
+use crate::{ExitStatus, EXIT_SUCCESS};
+use futures_util::future::BoxFuture;
+use std::collections::VecDeque;
+use void::Void;
+
+const SET: &str = "set";
+const USAGE: &str = "[-aeux] [-o option] [--] [arg ...]";
+
+#[derive(Debug, thiserror::Error)]
+#[error("{0}: invalid option name")]
+struct InvalidOptionNameError(String);
+
+/// The `set` builtin command will enable (`-`) or disable (`+`) shell options,
+/// either via their single letter flags (e.g. `-e` or `+x`), or via their names
+/// (e.g. `-o pipefail`), and sets any remaining operands as the positional
+/// parameters.
+///
+/// Specifying `-o` (or `+o`) without an option name prints the current options,
+/// either in a human readable form, or as commands which can be reinput to the
+/// shell to restore them, respectively.
+///
+/// Option parsing stops at the first argument which does not start with `-` or `+`.
+/// An argument of `--` is discarded, and the positional parameters are set even if
+/// no operands follow it (i.e. `set --` unsets them). An argument of `-` disables
+/// the `xtrace` option. Without any arguments, `set` has no effect.
+///
+/// Any invalid options are reported, and the command exits with a status of 1
+/// without changing any options or positional parameters.
+pub async fn set<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized
+        + AsyncIoEnvironment
+        + FileDescEnvironment
+        + SetArgumentsEnvironment
+        + ShellOptionsEnvironment,
+    E::Arg: From<String>,
+    E::Args: From<VecDeque<E::Arg>>,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    let mut args = args.into_iter().map(StringWrapper::into_owned);
+    let mut options = *env.options();
+    let mut print = None;
+    let mut set_args = false;
+    let mut operands = Vec::new();
+
+    while let Some(arg) = args.next() {
+        let enable = if arg.starts_with('-') {
+            true
+        } else if arg.starts_with('+') {
+            false
+        } else {
+            operands.push(arg);
+            break;
+        };
+
+        match &*arg {
+            "--" => {
+                set_args = true;
+                break;
+            }
+            "-" => {
+                options.xtrace = false;
+                break;
+            }
+            _ => {}
+        }
+
+        for flag in arg.chars().skip(1) {
+            let name = if flag == 'o' {
+                match args.next() {
+                    Some(name) => name,
+                    None => {
+                        print = Some(enable);
+                        continue;
+                    }
+                }
+            } else {
+                match ShellOptions::name_of_flag(flag) {
+                    Some(name) => name.to_owned(),
+                    None => {
+                        let err = UsageError::InvalidOption(flag);
+                        return report_usage_err(SET, USAGE, env, err).await;
+                    }
+                }
+            };
+
+            match options.get_mut(&name) {
+                Some(option) => *option = enable,
+                None => return report_err(SET, env, InvalidOptionNameError(name)).await,
+            }
+        }
+    }
+
+    operands.extend(args);
+
+    *env.options_mut() = options;
+    if set_args || !operands.is_empty() {
+        let args = operands
+            .into_iter()
+            .map(E::Arg::from)
+            .collect::<VecDeque<_>>();
+        env.set_args(args.into());
+    }
+
+    match print {
+        Some(human_readable) => {
+            generate_and_print_output(SET, env, |_| -> Result<_, Void> {
+                Ok(print_options(&options, human_readable))
+            })
+            .await
+        }
+        None => Box::pin(async { EXIT_SUCCESS }),
+    }
+}
+
+fn print_options(options: &ShellOptions, human_readable: bool) -> Vec<u8> {
+    let mut out = String::new();
+
+    for &(name, _) in ShellOptions::NAMES {
+        let enabled = options.get(name) == Some(true);
+
+        if human_readable {
+            let state = if enabled { "on" } else { "off" };
+            out.push_str(&format!("{:<15} {}\n", name, state));
+        } else {
+            let flag = if enabled { '-' } else { '+' };
+            out.push_str(&format!("set {}o {}\n", flag, name));
+        }
+    }
+
+    out.into_bytes()
+}
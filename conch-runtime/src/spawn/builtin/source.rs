@@ -0,0 +1,199 @@
+use super::{report_err, report_usage_err, BuiltinArgs, UsageError};
+use crate::env::{
+    AsyncIoEnvironment, ControlFlow, ControlFlowEnvironment, FileDescEnvironment,
+    FunctionFrameEnvironment, ScriptRunnerEnvironment, SetArgumentsEnvironment,
+    SourceStackEnvironment, StringWrapper, UnsetVariableEnvironment, VariableEnvironment,
+    WorkingDirectoryEnvironment,
+};
+use crate::error::CommandError;
+use crate::ExitStatus;
+use futures_util::future::BoxFuture;
+use std::borrow::{Borrow, Cow};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const SOURCE: &str = "source";
+const USAGE: &str = "file [argument ...]";
+
+lazy_static::lazy_static! {
+    static ref PATH: String = String::from("PATH");
+    static ref BASH_SOURCE: String = String::from("BASH_SOURCE");
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("{0}: file not found")]
+struct NotFoundError(String);
+
+#[derive(Debug, thiserror::Error)]
+#[error("executing scripts is not supported by this environment")]
+struct UnsupportedError;
+
+/// The `.` (or `source`) builtin command reads and executes the commands of a
+/// file in the current environment, thus any variables or functions it defines
+/// remain in effect once it completes.
+///
+/// A file name which does not contain a path separator is searched for within
+/// the `$PATH` directories (the file need not be executable). If any arguments
+/// are specified, they become the positional parameters while the file executes.
+/// The file is parsed and executed via the environment's `ScriptRunner`, and
+/// executing `return` within it stops executing the file with the requested status.
+///
+/// While a file is sourced, its path is tracked by the environment's
+/// `SourceStackEnvironment` and is available as `$BASH_SOURCE` (whose previous
+/// value is restored afterwards). Thus any relative paths sourced by the file
+/// are resolved relative to its own directory (falling back to the working
+/// directory or `$PATH` search if no such file exists), rather than depending
+/// on the working directory of whoever executes the outermost script.
+///
+/// The builtin exits with the status of the last command executed, or with a
+/// status of 1 if the file could not be found, read, or parsed.
+pub async fn source<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized
+        + AsyncIoEnvironment
+        + ControlFlowEnvironment
+        + FileDescEnvironment
+        + FunctionFrameEnvironment
+        + ScriptRunnerEnvironment
+        + SetArgumentsEnvironment
+        + SourceStackEnvironment
+        + UnsetVariableEnvironment
+        + WorkingDirectoryEnvironment,
+    E::Arg: From<String>,
+    E::Args: From<VecDeque<E::Arg>>,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+    E::VarName: Borrow<String> + From<String>,
+    E::Var: Borrow<String> + From<String>,
+{
+    let args = try_or_usage!(SOURCE, USAGE, BuiltinArgs::parse(args, ""), env);
+    let mut operands = args.into_operands().into_iter();
+    let name = match operands.next() {
+        Some(name) => name,
+        None => return report_usage_err(SOURCE, USAGE, env, UsageError::TooFewOperands).await,
+    };
+
+    let runner = match env.script_runner() {
+        Some(runner) => runner,
+        None => return report_err(SOURCE, env, UnsupportedError).await,
+    };
+
+    let path = match find_script(&name, env) {
+        Some(path) => path,
+        None => return report_err(SOURCE, env, NotFoundError(name)).await,
+    };
+
+    let source = match tokio::fs::read_to_string(&path).await {
+        Ok(source) => source,
+        Err(e) => return report_err(SOURCE, env, CommandError::Io(e, Some(name))).await,
+    };
+
+    let args = operands.map(E::Arg::from).collect::<VecDeque<_>>();
+    let old_args = if args.is_empty() {
+        None
+    } else {
+        Some(env.set_args(args.into()))
+    };
+
+    let old_source = env.var(&BASH_SOURCE).map(|val| val.borrow().clone());
+    let path_str = path.to_string_lossy().into_owned();
+    env.set_var(BASH_SOURCE.clone().into(), path_str.into());
+    env.push_source(Arc::from(path));
+
+    // NB: allow the file to `return`, and restore its positional
+    // parameters (and source tracking) even if we get cancelled
+    env.push_fn_frame();
+    let frame = SourceFrame {
+        env,
+        old_args,
+        old_source,
+    };
+
+    let status = match runner.run(&source, &mut *frame.env).await {
+        Ok(status) => status,
+        Err(ControlFlow::Return(status)) => status,
+        // NB: let the caller unwind any other requests on our behalf
+        Err(flow) => {
+            frame.env.request_control_flow(flow);
+            flow.status()
+        }
+    };
+
+    Box::pin(async move { status })
+}
+
+/// Resolves a file name relative to the working directory if it contains
+/// a path separator, or searches for it within the `$PATH` directories.
+///
+/// Relative names are first resolved relative to the directory of the file
+/// currently being sourced (if any), provided such a file exists.
+fn find_script<E>(name: &str, env: &E) -> Option<PathBuf>
+where
+    E: ?Sized + SourceStackEnvironment + VariableEnvironment + WorkingDirectoryEnvironment,
+    E::VarName: Borrow<String>,
+    E::Var: Borrow<String>,
+{
+    let including_dir = env.current_source().and_then(Path::parent);
+    if let Some(dir) = including_dir {
+        let candidate = dir.join(name);
+        if Path::new(name).is_relative() && candidate.is_file() {
+            return Some(candidate);
+        }
+    }
+
+    if name.contains(std::path::is_separator) {
+        let path = env.path_relative_to_working_dir(Cow::Borrowed(Path::new(name)));
+        return Some(path.into_owned());
+    }
+
+    let path = env.var(&PATH)?;
+    std::env::split_paths(path.borrow().as_str())
+        .map(|dir| env.path_relative_to_working_dir(Cow::Owned(dir)).join(name))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Restores the previous positional parameters (and `$BASH_SOURCE`), and pops
+/// the function frame and source path pushed for the sourced file when dropped.
+struct SourceFrame<'a, E>
+where
+    E: ?Sized
+        + FunctionFrameEnvironment
+        + SetArgumentsEnvironment
+        + SourceStackEnvironment
+        + UnsetVariableEnvironment,
+    E::VarName: From<String>,
+    E::Var: From<String>,
+{
+    env: &'a mut E,
+    old_args: Option<E::Args>,
+    old_source: Option<String>,
+}
+
+impl<'a, E> Drop for SourceFrame<'a, E>
+where
+    E: ?Sized
+        + FunctionFrameEnvironment
+        + SetArgumentsEnvironment
+        + SourceStackEnvironment
+        + UnsetVariableEnvironment,
+    E::VarName: From<String>,
+    E::Var: From<String>,
+{
+    fn drop(&mut self) {
+        if let Some(old_args) = self.old_args.take() {
+            self.env.set_args(old_args);
+        }
+
+        let name = E::VarName::from(BASH_SOURCE.clone());
+        match self.old_source.take() {
+            Some(old_source) => self.env.set_var(name, old_source.into()),
+            None => self.env.unset_var(&name),
+        }
+
+        self.env.pop_source();
+        self.env.pop_fn_frame();
+    }
+}
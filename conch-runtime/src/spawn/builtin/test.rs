@@ -0,0 +1,420 @@
+use super::report_err;
+use crate::env::{
+    AsyncIoEnvironment, FileDescEnvironment, StringWrapper, TerminalEnvironment,
+    WorkingDirectoryEnvironment,
+};
+use crate::{ExitStatus, Fd, EXIT_ERROR, EXIT_SUCCESS};
+use futures_util::future::BoxFuture;
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::fs::{self, Metadata};
+use std::path::Path;
+
+const TEST: &str = "test";
+const BRACKET: &str = "[";
+
+/// The status `test` exits with if its expression is invalid, which (per POSIX)
+/// is distinct from the status of an expression which evaluates to false.
+const EXIT_INVALID: ExitStatus = ExitStatus::Code(2);
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+enum TestError {
+    #[error("missing `]`")]
+    MissingBracket,
+    #[error("missing `)`")]
+    MissingParen,
+    #[error("argument expected")]
+    ArgumentExpected,
+    #[error("{0}: unary operator expected")]
+    UnaryOperatorExpected(String),
+    #[error("{0}: binary operator expected")]
+    BinaryOperatorExpected(String),
+    #[error("{0}: integer expression expected")]
+    IntegerExpected(String),
+    #[error("{0}: unexpected argument")]
+    UnexpectedArgument(String),
+}
+
+/// The `test` builtin command evaluates a conditional expression, exiting with
+/// a status of 0 if it is true, or 1 if it is false (or if it is missing).
+///
+/// The full POSIX expression grammar is supported: string comparisons (`=`, `!=`,
+/// `-n`, `-z`), integer comparisons (`-eq`, `-ne`, `-lt`, `-le`, `-gt`, `-ge`),
+/// file predicates (`-e`, `-f`, `-d`, `-b`, `-c`, `-p`, `-S`, `-h`, `-L`, `-g`,
+/// `-u`, `-r`, `-w`, `-x`, `-s`), checking if a file descriptor refers to a
+/// terminal (`-t`), as well as `!`, `-a`, `-o` and parentheses. Just like POSIX requires, expressions
+/// of up to four arguments are disambiguated based on their number of arguments
+/// (e.g. `test -n` tests whether the string `-n` is non-empty).
+///
+/// Any relative paths are resolved against the environment's working directory.
+/// An invalid expression is reported, and results in an exit status of 2.
+pub async fn test_cmd<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized
+        + AsyncIoEnvironment
+        + FileDescEnvironment
+        + TerminalEnvironment
+        + WorkingDirectoryEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    let args = args
+        .into_iter()
+        .map(StringWrapper::into_owned)
+        .collect::<Vec<_>>();
+
+    let result = evaluate(&args, env);
+    finish(TEST, result, env).await
+}
+
+/// The `[` builtin command is equivalent to `test`, except that its last
+/// argument must be `]` (which is not considered part of the expression).
+pub async fn bracket<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized
+        + AsyncIoEnvironment
+        + FileDescEnvironment
+        + TerminalEnvironment
+        + WorkingDirectoryEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    let mut args = args
+        .into_iter()
+        .map(StringWrapper::into_owned)
+        .collect::<Vec<_>>();
+
+    let result = match args.pop() {
+        Some(ref last) if last == "]" => evaluate(&args, env),
+        _ => Err(TestError::MissingBracket),
+    };
+
+    finish(BRACKET, result, env).await
+}
+
+async fn finish<E>(
+    builtin_name: &str,
+    result: Result<bool, TestError>,
+    env: &mut E,
+) -> BoxFuture<'static, ExitStatus>
+where
+    E: ?Sized + AsyncIoEnvironment + FileDescEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    let status = match result {
+        Ok(true) => EXIT_SUCCESS,
+        Ok(false) => EXIT_ERROR,
+        Err(e) => {
+            report_err(builtin_name, env, e).await.await;
+            EXIT_INVALID
+        }
+    };
+
+    Box::pin(async move { status })
+}
+
+/// Evaluates an expression, disambiguating it based on its number
+/// of arguments (as POSIX requires) if it has four or fewer.
+fn evaluate<E>(args: &[String], env: &E) -> Result<bool, TestError>
+where
+    E: ?Sized + TerminalEnvironment + WorkingDirectoryEnvironment,
+{
+    let arg = |i: usize| args[i].as_str();
+
+    match args.len() {
+        0 => Ok(false),
+        1 => Ok(!arg(0).is_empty()),
+
+        2 if arg(0) == "!" => Ok(arg(1).is_empty()),
+        2 if is_unary_op(arg(0)) => unary(arg(0), arg(1), env),
+        2 => Err(TestError::UnaryOperatorExpected(args[0].clone())),
+
+        3 if is_binary_op(arg(1)) => binary(arg(0), arg(1), arg(2)),
+        3 if arg(0) == "!" => evaluate(&args[1..], env).map(|result| !result),
+        3 if arg(0) == "(" && arg(2) == ")" => Ok(!arg(1).is_empty()),
+        3 => Err(TestError::BinaryOperatorExpected(args[1].clone())),
+
+        4 if arg(0) == "!" => evaluate(&args[1..], env).map(|result| !result),
+        4 if arg(0) == "(" && arg(3) == ")" => evaluate(&args[1..3], env),
+
+        _ => Parser { args, pos: 0, env }.parse(),
+    }
+}
+
+fn is_unary_op(op: &str) -> bool {
+    matches!(
+        op,
+        "-n" | "-z"
+            | "-e"
+            | "-f"
+            | "-d"
+            | "-b"
+            | "-c"
+            | "-p"
+            | "-S"
+            | "-h"
+            | "-L"
+            | "-g"
+            | "-u"
+            | "-r"
+            | "-w"
+            | "-x"
+            | "-s"
+            | "-t"
+    )
+}
+
+fn is_binary_op(op: &str) -> bool {
+    matches!(
+        op,
+        "=" | "!=" | "-eq" | "-ne" | "-lt" | "-le" | "-gt" | "-ge" | "-a" | "-o"
+    )
+}
+
+/// A recursive descent parser (and evaluator) of the expression grammar:
+///
+/// ```text
+/// or      := and ( "-o" and )*
+/// and     := not ( "-a" not )*
+/// not     := "!" not | primary
+/// primary := "(" or ")" | string binary_op string | unary_op string | string
+/// ```
+struct Parser<'a, E: ?Sized> {
+    args: &'a [String],
+    pos: usize,
+    env: &'a E,
+}
+
+impl<'a, E> Parser<'a, E>
+where
+    E: ?Sized + TerminalEnvironment + WorkingDirectoryEnvironment,
+{
+    fn parse(mut self) -> Result<bool, TestError> {
+        let result = self.or()?;
+
+        match self.peek() {
+            Some(arg) => Err(TestError::UnexpectedArgument(arg.to_owned())),
+            None => Ok(result),
+        }
+    }
+
+    fn peek(&self) -> Option<&'a str> {
+        self.args.get(self.pos).map(String::as_str)
+    }
+
+    fn next(&mut self) -> Option<&'a str> {
+        let arg = self.peek();
+        if arg.is_some() {
+            self.pos += 1;
+        }
+        arg
+    }
+
+    fn eat(&mut self, expected: &str) -> bool {
+        let found = self.peek() == Some(expected);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn or(&mut self) -> Result<bool, TestError> {
+        let mut result = self.and()?;
+        while self.eat("-o") {
+            // NB: always evaluate the rhs so that any errors are reported
+            let rhs = self.and()?;
+            result = result || rhs;
+        }
+        Ok(result)
+    }
+
+    fn and(&mut self) -> Result<bool, TestError> {
+        let mut result = self.not()?;
+        while self.eat("-a") {
+            let rhs = self.not()?;
+            result = result && rhs;
+        }
+        Ok(result)
+    }
+
+    fn not(&mut self) -> Result<bool, TestError> {
+        if self.eat("!") {
+            self.not().map(|result| !result)
+        } else {
+            self.primary()
+        }
+    }
+
+    fn primary(&mut self) -> Result<bool, TestError> {
+        let arg = self.next().ok_or(TestError::ArgumentExpected)?;
+
+        // NB: a binary operator takes precedence over treating
+        // the current argument as an operator or a parenthesis
+        if let (Some(op), Some(rhs)) = (self.peek(), self.args.get(self.pos + 1)) {
+            if op != "-a" && op != "-o" && is_binary_op(op) {
+                self.pos += 2;
+                return binary(arg, op, rhs);
+            }
+        }
+
+        if arg == "(" {
+            let result = self.or()?;
+            return if self.eat(")") {
+                Ok(result)
+            } else {
+                Err(TestError::MissingParen)
+            };
+        }
+
+        if is_unary_op(arg) {
+            let operand = self.next().ok_or(TestError::ArgumentExpected)?;
+            return unary(arg, operand, self.env);
+        }
+
+        Ok(!arg.is_empty())
+    }
+}
+
+fn unary<E>(op: &str, operand: &str, env: &E) -> Result<bool, TestError>
+where
+    E: ?Sized + TerminalEnvironment + WorkingDirectoryEnvironment,
+{
+    if op == "-n" {
+        return Ok(!operand.is_empty());
+    } else if op == "-z" {
+        return Ok(operand.is_empty());
+    } else if op == "-t" {
+        let fd = parse_integer(operand)?;
+        return Ok(Fd::try_from(fd).is_ok_and(|fd| env.is_terminal(fd)));
+    } else if operand.is_empty() {
+        return Ok(false);
+    }
+
+    let path = env.path_relative_to_working_dir(Cow::Borrowed(Path::new(operand)));
+    let path = &*path;
+
+    let result = match op {
+        "-h" | "-L" => fs::symlink_metadata(path)
+            .map(|metadata| metadata.file_type().is_symlink())
+            .unwrap_or(false),
+        "-r" => is_accessible(path, Access::Read),
+        "-w" => is_accessible(path, Access::Write),
+        "-x" => is_accessible(path, Access::Execute),
+        _ => match fs::metadata(path) {
+            Ok(metadata) => match op {
+                "-e" => true,
+                "-f" => metadata.is_file(),
+                "-d" => metadata.is_dir(),
+                "-s" => metadata.len() > 0,
+                _ => match file_kind(&metadata, op) {
+                    Some(result) => result,
+                    None => return Err(TestError::UnaryOperatorExpected(op.to_owned())),
+                },
+            },
+            Err(_) => false,
+        },
+    };
+
+    Ok(result)
+}
+
+/// Evaluates the file predicates which inspect a file's type or mode bits,
+/// returning `None` if `op` is not one of them.
+#[cfg(unix)]
+fn file_kind(metadata: &Metadata, op: &str) -> Option<bool> {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+    let file_type = metadata.file_type();
+    let mode = metadata.permissions().mode();
+
+    let result = match op {
+        "-b" => file_type.is_block_device(),
+        "-c" => file_type.is_char_device(),
+        "-p" => file_type.is_fifo(),
+        "-S" => file_type.is_socket(),
+        "-g" => mode & 0o2000 != 0, // S_ISGID
+        "-u" => mode & 0o4000 != 0, // S_ISUID
+        _ => return None,
+    };
+
+    Some(result)
+}
+
+#[cfg(not(unix))]
+fn file_kind(_metadata: &Metadata, op: &str) -> Option<bool> {
+    match op {
+        "-b" | "-c" | "-p" | "-S" | "-g" | "-u" => Some(false),
+        _ => None,
+    }
+}
+
+fn binary(lhs: &str, op: &str, rhs: &str) -> Result<bool, TestError> {
+    let result = match op {
+        "=" => lhs == rhs,
+        "!=" => lhs != rhs,
+        "-a" => !lhs.is_empty() && !rhs.is_empty(),
+        "-o" => !lhs.is_empty() || !rhs.is_empty(),
+        _ => {
+            let lhs = parse_integer(lhs)?;
+            let rhs = parse_integer(rhs)?;
+
+            match op {
+                "-eq" => lhs == rhs,
+                "-ne" => lhs != rhs,
+                "-lt" => lhs < rhs,
+                "-le" => lhs <= rhs,
+                "-gt" => lhs > rhs,
+                "-ge" => lhs >= rhs,
+                _ => return Err(TestError::BinaryOperatorExpected(op.to_owned())),
+            }
+        }
+    };
+
+    Ok(result)
+}
+
+fn parse_integer(s: &str) -> Result<i64, TestError> {
+    s.trim()
+        .parse()
+        .map_err(|_| TestError::IntegerExpected(s.to_owned()))
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Access {
+    Read,
+    Write,
+    Execute,
+}
+
+#[cfg(unix)]
+fn is_accessible(path: &Path, access: Access) -> bool {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = match CString::new(path.as_os_str().as_bytes()) {
+        Ok(path) => path,
+        Err(_) => return false,
+    };
+
+    let mode = match access {
+        Access::Read => libc::R_OK,
+        Access::Write => libc::W_OK,
+        Access::Execute => libc::X_OK,
+    };
+
+    unsafe { libc::access(path.as_ptr(), mode) == 0 }
+}
+
+#[cfg(not(unix))]
+fn is_accessible(path: &Path, access: Access) -> bool {
+    match (fs::metadata(path), access) {
+        (Ok(metadata), Access::Write) => !metadata.permissions().readonly(),
+        (Ok(_), _) => true,
+        (Err(_), _) => false,
+    }
+}
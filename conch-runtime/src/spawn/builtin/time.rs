@@ -0,0 +1,58 @@
+use super::{generate_and_write_bytes_to_fd_if_present, BuiltinArgs};
+use crate::env::{AsyncIoEnvironment, FileDescEnvironment, ResourceUsage, StringWrapper};
+use crate::spawn::ExitStatus;
+use crate::{EXIT_SUCCESS, STDERR_FILENO};
+use futures_util::future::BoxFuture;
+use std::time::Duration;
+use void::Void;
+
+const TIME: &str = "time";
+const USAGE: &str = "[-p] [utility [argument...]]";
+
+/// The `time` builtin command executes a command, and then writes the real time
+/// it took to complete, along with the user and system time used by any
+/// executables it spawned, to standard error.
+///
+/// The times are written in the format specified by POSIX for `time -p`, whether
+/// or not `-p` is specified, e.g. `real 1.50`, `user 0.25` and `sys 0.10` each on
+/// their own line.
+///
+/// The named command is executed in place of the builtin, which is handled
+/// by `simple_command` itself (see `BuiltinUtility::times_command`), thus
+/// this function only reports zero times when given no command to execute.
+pub async fn time<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized + AsyncIoEnvironment + FileDescEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    let args = try_or_usage!(TIME, USAGE, BuiltinArgs::parse(args, "p"), env);
+    try_or_usage!(TIME, USAGE, args.check_max_operands(0), env);
+
+    let times = format_times(Duration::default(), &ResourceUsage::default());
+    generate_and_write_bytes_to_fd_if_present(
+        TIME,
+        env,
+        STDERR_FILENO,
+        EXIT_SUCCESS,
+        |_| -> Result<_, Void> { Ok(times.into_bytes()) },
+    )
+    .await
+}
+
+/// Formats the times reported by the `time` builtin for a completed command.
+pub(crate) fn format_times(real: Duration, usage: &ResourceUsage) -> String {
+    format!(
+        "real {}\nuser {}\nsys {}\n",
+        format_time(real),
+        format_time(usage.user_time),
+        format_time(usage.system_time),
+    )
+}
+
+fn format_time(time: Duration) -> String {
+    let centis = time.as_millis() / 10;
+    format!("{}.{:02}", centis / 100, centis % 100)
+}
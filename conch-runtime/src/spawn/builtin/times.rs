@@ -0,0 +1,45 @@
+use super::{generate_and_print_output, BuiltinArgs};
+use crate::env::{AsyncIoEnvironment, FileDescEnvironment, ProcessTimesEnvironment, StringWrapper};
+use crate::spawn::ExitStatus;
+use futures_util::future::BoxFuture;
+use std::time::Duration;
+
+const TIMES: &str = "times";
+const USAGE: &str = "";
+
+/// The `times` builtin command prints the CPU time used by the shell and
+/// by any of its children which have exited.
+///
+/// Two lines are printed: the user and system time of the shell itself, followed
+/// by the user and system time of its children, each formatted as `XmY.ZZZs`.
+pub async fn times<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized + AsyncIoEnvironment + FileDescEnvironment + ProcessTimesEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    let args = try_or_usage!(TIMES, USAGE, BuiltinArgs::parse(args, ""), env);
+    try_or_usage!(TIMES, USAGE, args.check_max_operands(0), env);
+
+    generate_and_print_output(TIMES, env, |env| {
+        env.process_times().map(|times| {
+            format!(
+                "{} {}\n{} {}\n",
+                format_time(times.user_time),
+                format_time(times.system_time),
+                format_time(times.children_user_time),
+                format_time(times.children_system_time),
+            )
+            .into_bytes()
+        })
+    })
+    .await
+}
+
+fn format_time(time: Duration) -> String {
+    let millis = time.as_millis();
+    let secs = millis / 1000;
+    format!("{}m{}.{:03}s", secs / 60, secs % 60, millis % 1000)
+}
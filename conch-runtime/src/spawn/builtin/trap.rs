@@ -0,0 +1,103 @@
+use super::{
+    generate_and_print_output, generate_and_write_bytes_to_fd_if_present, report_usage_err,
+    BuiltinArgs, UsageError,
+};
+use crate::env::{
+    AsyncIoEnvironment, FileDescEnvironment, Signal, SignalEnvironment, StringWrapper, TrapAction,
+};
+use crate::eval::quote_for_shell;
+use crate::{ExitStatus, EXIT_ERROR, EXIT_SUCCESS, STDERR_FILENO};
+use futures_util::future::BoxFuture;
+use void::Void;
+
+const TRAP: &str = "trap";
+const USAGE: &str = "[action condition...]";
+
+/// The `trap` builtin command will set the action to take when the shell
+/// receives any of the specified signals (or conditions, like `EXIT`).
+///
+/// An action of `-` resets each condition to its default action, and an empty
+/// action causes each condition to be ignored. If the first operand is a signal
+/// number, all operands are treated as conditions to be reset. Any other action
+/// is parsed and run as shell commands whenever the condition arises.
+///
+/// If no operands are specified, all traps which have been set are printed in
+/// a format which can be reinput to the shell to restore them.
+///
+/// Any invalid conditions are reported, and the command exits with a status of 1,
+/// though the action is still applied to all other conditions.
+pub async fn trap<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized + AsyncIoEnvironment + FileDescEnvironment + SignalEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    let args = try_or_usage!(TRAP, USAGE, BuiltinArgs::parse(args, ""), env);
+    let mut operands = args.into_operands().into_iter();
+
+    let first = match operands.next() {
+        Some(first) => first,
+        None => {
+            return generate_and_print_output(TRAP, env, |env| -> Result<_, Void> {
+                Ok(print_traps(env))
+            })
+            .await
+        }
+    };
+
+    let (action, conditions) = if first.parse::<u8>().is_ok() {
+        let conditions = Some(first).into_iter().chain(operands).collect();
+        (TrapAction::Default, conditions)
+    } else {
+        let action = match &*first {
+            "-" => TrapAction::Default,
+            "" => TrapAction::Ignore,
+            _ => TrapAction::Command(first),
+        };
+
+        (action, operands.collect::<Vec<_>>())
+    };
+
+    if conditions.is_empty() {
+        return report_usage_err(TRAP, USAGE, env, UsageError::TooFewOperands).await;
+    }
+
+    let mut err_bytes = Vec::new();
+    for condition in conditions {
+        match condition.parse::<Signal>() {
+            Ok(signal) => env.set_trap(signal, action.clone()),
+            Err(e) => err_bytes.extend(format_err!(TRAP, e)),
+        }
+    }
+
+    if err_bytes.is_empty() {
+        Box::pin(async { EXIT_SUCCESS })
+    } else {
+        generate_and_write_bytes_to_fd_if_present(
+            TRAP,
+            env,
+            STDERR_FILENO,
+            EXIT_ERROR,
+            |_| -> Result<_, Void> { Ok(err_bytes) },
+        )
+        .await
+    }
+}
+
+fn print_traps<E: ?Sized + SignalEnvironment>(env: &E) -> Vec<u8> {
+    let mut out = String::new();
+
+    for &signal in Signal::ALL {
+        let action = match env.trap(signal) {
+            TrapAction::Default => continue,
+            TrapAction::Ignore => "''".into(),
+            TrapAction::Command(cmd) => quote_for_shell(cmd),
+        };
+
+        out.push_str(&format!("trap -- {} {}\n", action, signal));
+    }
+
+    out.into_bytes()
+}
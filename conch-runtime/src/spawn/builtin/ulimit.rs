@@ -0,0 +1,216 @@
+use super::{generate_and_print_output, report_err, BuiltinArgs};
+use crate::env::{
+    AsyncIoEnvironment, FileDescEnvironment, Resource, ResourceLimitEnvironment, StringWrapper,
+};
+use crate::{ExitStatus, EXIT_ERROR, EXIT_SUCCESS};
+use futures_util::future::BoxFuture;
+use std::io;
+use void::Void;
+
+const ULIMIT: &str = "ulimit";
+const USAGE: &str = "[-H | -S] [-a | -c | -d | -f | -n | -s | -t | -u | -v] [limit]";
+const ARG_HARD: char = 'H';
+const ARG_SOFT: char = 'S';
+const ARG_ALL: char = 'a';
+const RESOURCE_ARGS: &[char] = &['c', 'd', 'f', 'n', 's', 't', 'u', 'v'];
+const UNLIMITED: &str = "unlimited";
+
+#[derive(Debug, thiserror::Error)]
+#[error("{0}: invalid limit")]
+struct InvalidLimitError(String);
+
+#[derive(Debug, thiserror::Error)]
+#[error("{resource}: cannot {action} limit: {err}")]
+struct LimitError {
+    resource: &'static str,
+    action: &'static str,
+    err: io::Error,
+}
+
+/// The `ulimit` builtin command prints or changes the resource limits which
+/// any executables spawned by the shell are subject to.
+///
+/// A single resource is selected via one of the `-cdfnstuv` options (or `-f`
+/// if none are specified), while `-a` prints the limits of all resources.
+/// Limits are printed (and specified) as a number of 512-byte blocks for `-c`
+/// and `-f`, kibibytes for `-d`, `-s`, and `-v`, seconds for `-t`, or simply
+/// as a count for `-n` and `-u`, or `unlimited`.
+///
+/// If `-H` is specified, the hard limit is printed or changed, while `-S`
+/// selects the soft limit. By default the soft limit is printed, and both
+/// limits are changed.
+pub async fn ulimit<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized + AsyncIoEnvironment + FileDescEnvironment + ResourceLimitEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    let args = try_or_usage!(ULIMIT, USAGE, BuiltinArgs::parse(args, "HSacdfnstuv"), env);
+
+    let max_operands = if args.is_present(ARG_ALL) { 0 } else { 1 };
+    try_or_usage!(ULIMIT, USAGE, args.check_max_operands(max_operands), env);
+
+    let hard = args.is_present(ARG_HARD);
+    let soft = args.is_present(ARG_SOFT);
+
+    if args.is_present(ARG_ALL) {
+        let mut out = String::new();
+        let mut errors = Vec::new();
+        for &resource in Resource::ALL {
+            let info = ResourceInfo::new(resource);
+            match env.resource_limit(resource) {
+                Ok(limit) => {
+                    let limit = if hard && !soft {
+                        limit.hard
+                    } else {
+                        limit.soft
+                    };
+                    let unit = match info.unit {
+                        Some(unit) => format!("({}, -{})", unit, info.flag),
+                        None => format!("(-{})", info.flag),
+                    };
+                    out.push_str(&format!(
+                        "{:<24}{:>16} {}\n",
+                        info.name,
+                        unit,
+                        info.display(limit)
+                    ));
+                }
+                Err(err) => errors.push(LimitError {
+                    resource: info.name,
+                    action: "get",
+                    err,
+                }),
+            }
+        }
+
+        let status =
+            generate_and_print_output(ULIMIT, env, |_| -> Result<_, Void> { Ok(out.into_bytes()) })
+                .await
+                .await;
+
+        if errors.is_empty() {
+            return Box::pin(async move { status });
+        }
+
+        for err in errors {
+            report_err(ULIMIT, env, err).await.await;
+        }
+
+        return Box::pin(async { EXIT_ERROR });
+    }
+
+    let resource = match args.last_of(RESOURCE_ARGS) {
+        Some('c') => Resource::CoreFileSize,
+        Some('d') => Resource::DataSize,
+        Some('n') => Resource::OpenFiles,
+        Some('s') => Resource::StackSize,
+        Some('t') => Resource::CpuTime,
+        Some('u') => Resource::Processes,
+        Some('v') => Resource::VirtualMemory,
+        _ => Resource::FileSize,
+    };
+
+    let info = ResourceInfo::new(resource);
+    let current = try_and_report!(
+        ULIMIT,
+        env.resource_limit(resource).map_err(|err| LimitError {
+            resource: info.name,
+            action: "get",
+            err,
+        }),
+        env
+    );
+
+    let value = match args.operands().first() {
+        Some(value) => value,
+        None => {
+            let limit = if hard && !soft {
+                current.hard
+            } else {
+                current.soft
+            };
+
+            let out = format!("{}\n", info.display(limit));
+            return generate_and_print_output(ULIMIT, env, |_| -> Result<_, Void> {
+                Ok(out.into_bytes())
+            })
+            .await;
+        }
+    };
+
+    let value = try_and_report!(ULIMIT, info.parse(value), env);
+
+    let mut limit = current;
+    if hard || !soft {
+        limit.hard = value;
+    }
+    if soft || !hard {
+        limit.soft = value;
+    }
+
+    let result = env.set_resource_limit(resource, limit);
+    try_and_report!(
+        ULIMIT,
+        result.map_err(|err| LimitError {
+            resource: info.name,
+            action: "modify",
+            err,
+        }),
+        env
+    );
+
+    Box::pin(async { EXIT_SUCCESS })
+}
+
+/// How a resource is described by (and its limits are specified to) `ulimit`.
+struct ResourceInfo {
+    name: &'static str,
+    flag: char,
+    unit: Option<&'static str>,
+    multiplier: u64,
+}
+
+impl ResourceInfo {
+    fn new(resource: Resource) -> Self {
+        let (name, flag, unit, multiplier) = match resource {
+            Resource::CoreFileSize => ("core file size", 'c', Some("blocks"), 512),
+            Resource::DataSize => ("data seg size", 'd', Some("kbytes"), 1024),
+            Resource::FileSize => ("file size", 'f', Some("blocks"), 512),
+            Resource::OpenFiles => ("open files", 'n', None, 1),
+            Resource::StackSize => ("stack size", 's', Some("kbytes"), 1024),
+            Resource::CpuTime => ("cpu time", 't', Some("seconds"), 1),
+            Resource::Processes => ("max user processes", 'u', None, 1),
+            Resource::VirtualMemory => ("virtual memory", 'v', Some("kbytes"), 1024),
+        };
+
+        Self {
+            name,
+            flag,
+            unit,
+            multiplier,
+        }
+    }
+
+    fn display(&self, limit: Option<u64>) -> String {
+        match limit {
+            Some(limit) => (limit / self.multiplier).to_string(),
+            None => UNLIMITED.to_owned(),
+        }
+    }
+
+    fn parse(&self, value: &str) -> Result<Option<u64>, InvalidLimitError> {
+        if value == UNLIMITED {
+            return Ok(None);
+        }
+
+        value
+            .parse::<u64>()
+            .ok()
+            .and_then(|limit| limit.checked_mul(self.multiplier))
+            .map(Some)
+            .ok_or_else(|| InvalidLimitError(value.to_owned()))
+    }
+}
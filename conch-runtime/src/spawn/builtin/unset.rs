@@ -0,0 +1,74 @@
+use super::{report_err, BuiltinArgs};
+use crate::env::{
+    AsyncIoEnvironment, FileDescEnvironment, ReadonlyVariableEnvironment, StringWrapper,
+    UnsetFunctionEnvironment, UnsetVariableEnvironment,
+};
+use crate::{ExitStatus, EXIT_ERROR, EXIT_SUCCESS};
+use futures_util::future::BoxFuture;
+
+const UNSET: &str = "unset";
+const USAGE: &str = "[-f | -v] name ...";
+const ARG_FUNCTIONS: char = 'f';
+const ARG_VARIABLES: char = 'v';
+
+#[derive(Debug, thiserror::Error)]
+#[error("{0}: cannot unset: readonly variable")]
+struct ReadonlyVariableError(String);
+
+/// The `unset` builtin command will remove each of the named variables
+/// (or functions if `-f` is specified) from the environment.
+///
+/// Names which are not currently defined are silently ignored. Attempting to
+/// unset a read-only variable reports an error and exits with a status of 1,
+/// though any other names are still unset.
+pub async fn unset<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized
+        + AsyncIoEnvironment
+        + FileDescEnvironment
+        + ReadonlyVariableEnvironment
+        + UnsetFunctionEnvironment
+        + UnsetVariableEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+    E::FnName: From<String>,
+    E::VarName: From<String>,
+{
+    let args = try_or_usage!(UNSET, USAGE, BuiltinArgs::parse(args, "fv"), env);
+    let functions = args.last_of(&[ARG_FUNCTIONS, ARG_VARIABLES]) == Some(ARG_FUNCTIONS);
+
+    let mut status = EXIT_SUCCESS;
+    for name in args.into_operands() {
+        if functions {
+            env.unset_function(&E::FnName::from(name));
+            continue;
+        }
+
+        if !unset_var(env, &name) {
+            report_err(UNSET, env, ReadonlyVariableError(name))
+                .await
+                .await;
+            status = EXIT_ERROR;
+        }
+    }
+
+    Box::pin(async move { status })
+}
+
+/// Unsets the named variable, unless it is read-only, in which
+/// case `false` is returned.
+fn unset_var<E>(env: &mut E, name: &str) -> bool
+where
+    E: ?Sized + ReadonlyVariableEnvironment + UnsetVariableEnvironment,
+    E::VarName: From<String>,
+{
+    let name = E::VarName::from(name.to_owned());
+    if env.is_readonly(&name) {
+        return false;
+    }
+
+    env.unset_var(&name);
+    true
+}
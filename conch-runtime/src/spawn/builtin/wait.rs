@@ -0,0 +1,110 @@
+use super::{generate_and_write_bytes_to_fd_if_present, BuiltinArgs};
+use crate::env::{AsyncIoEnvironment, FileDescEnvironment, JobEnvironment, JobId, StringWrapper};
+use crate::{ExitStatus, EXIT_CMD_NOT_FOUND, EXIT_SUCCESS, STDERR_FILENO};
+use futures_util::future::{join_all, BoxFuture};
+use void::Void;
+
+const WAIT: &str = "wait";
+const USAGE: &str = "[id...]";
+
+#[derive(Debug, thiserror::Error)]
+#[error("{0}: not a valid job id")]
+struct InvalidJobIdError(String);
+
+#[derive(Debug, thiserror::Error)]
+#[error("{0}: no such job")]
+struct NoSuchJobError(JobId);
+
+/// The `wait` builtin command will wait for asynchronous (background) jobs
+/// to complete.
+///
+/// If no operands are specified, all known jobs are waited for, and the
+/// command exits with a status of 0. Otherwise, each operand identifies a job
+/// (e.g. via the value of `$!`, optionally prefixed with `%`), and the command
+/// exits with the status of the job identified by the last operand, or 127
+/// if that job is not known (e.g. it has already been waited for).
+///
+/// Since jobs are not backed by actual processes, there are no process ids
+/// to wait for: an operand of `N` and a job spec of `%N` both identify the job
+/// whose id is `N` (i.e. the value `$!` had after spawning it), unlike in other
+/// shells where `%N` refers to a job number and `N` to a process id. A job may
+/// also be unknown if it completed long ago and was forgotten by the environment
+/// (e.g. see `JobEnv::CHILD_MAX`).
+pub async fn wait<I, E>(args: I, env: &mut E) -> BoxFuture<'static, ExitStatus>
+where
+    I: IntoIterator,
+    I::Item: StringWrapper,
+    E: ?Sized + AsyncIoEnvironment + FileDescEnvironment + JobEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+{
+    let args = try_or_usage!(WAIT, USAGE, BuiltinArgs::parse(args, ""), env);
+
+    if args.operands().is_empty() {
+        let jobs = env
+            .job_ids()
+            .into_iter()
+            .filter_map(|id| env.wait_job(id))
+            .collect::<Vec<_>>();
+
+        return Box::pin(async move {
+            join_all(jobs).await;
+            EXIT_SUCCESS
+        });
+    }
+
+    let ids = try_and_report!(
+        WAIT,
+        args.operands()
+            .iter()
+            .map(|operand| parse_job_id(operand))
+            .collect::<Result<Vec<_>, _>>(),
+        env
+    );
+
+    let mut err_bytes = Vec::new();
+    let jobs = ids
+        .into_iter()
+        .map(|id| {
+            let job = env.wait_job(id);
+            if job.is_none() {
+                err_bytes.extend(format_err!(WAIT, NoSuchJobError(id)));
+            }
+            job
+        })
+        .collect::<Vec<_>>();
+
+    if !err_bytes.is_empty() {
+        // NB: unknown jobs are not fatal, and the status of the last
+        // operand is reported regardless of the other operands
+        generate_and_write_bytes_to_fd_if_present(
+            WAIT,
+            env,
+            STDERR_FILENO,
+            EXIT_SUCCESS,
+            |_| -> Result<_, Void> { Ok(err_bytes) },
+        )
+        .await
+        .await;
+    }
+
+    Box::pin(async move {
+        let mut status = EXIT_SUCCESS;
+        for job in jobs {
+            status = match job {
+                Some(job) => job.await,
+                None => EXIT_CMD_NOT_FOUND,
+            };
+        }
+
+        status
+    })
+}
+
+fn parse_job_id(operand: &str) -> Result<JobId, InvalidJobIdError> {
+    operand
+        .strip_prefix('%')
+        .unwrap_or(operand)
+        .parse()
+        .map_err(|_| InvalidJobIdError(operand.to_owned()))
+}
@@ -1,10 +1,15 @@
-use crate::env::{LastStatusEnvironment, ReportErrorEnvironment, StringWrapper};
+use crate::env::{
+    AsyncIoEnvironment, FileDescEnvironment, LastStatusEnvironment, ReportErrorEnvironment,
+    ShellOptionsEnvironment, StringWrapper, VariableEnvironment,
+};
 use crate::error::IsFatalError;
 use crate::eval::{eval_as_pattern, TildeExpansion, WordEval, WordEvalConfig};
+use crate::spawn::xtrace::{render_xtrace, write_xtrace, xtrace_fd};
 use crate::spawn::ExitStatus;
 use crate::{Spawn, EXIT_ERROR, EXIT_SUCCESS};
 use futures_core::future::BoxFuture;
 use glob::MatchOptions;
+use std::borrow::Borrow;
 
 /// A grouping of patterns and body commands.
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -15,6 +20,18 @@ pub struct PatternBodyPair<W, C> {
     pub body: C,
 }
 
+/// Describes how a `case` command proceeds after the body of a matched arm has run.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub enum CaseArmTerminator {
+    /// Stop after running the arm's body (i.e. `;;`).
+    #[default]
+    Break,
+    /// Run the body of the next arm without checking its patterns (i.e. `;&`).
+    FallThrough,
+    /// Continue checking the patterns of any subsequent arms (i.e. `;;&`).
+    Continue,
+}
+
 /// Spawns a `case` commands from a word to match number of case arms.
 ///
 /// First the provided `word` will be evaluated and compared to each
@@ -34,19 +51,62 @@ where
     P::Error: IsFatalError,
     S: Spawn<E>,
     S::Error: From<W::Error> + From<P::Error>,
-    E: ?Sized + LastStatusEnvironment + ReportErrorEnvironment,
+    E: ?Sized
+        + AsyncIoEnvironment
+        + FileDescEnvironment
+        + LastStatusEnvironment
+        + ReportErrorEnvironment
+        + ShellOptionsEnvironment
+        + VariableEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+    E::VarName: Borrow<String>,
+    E::Var: Borrow<String>,
+{
+    let arms = arms.map(|arm| (arm, CaseArmTerminator::Break));
+    case_with_terminators(word, arms, env).await
+}
+
+/// Spawns a `case` commands from a word to match number of case arms,
+/// each of which specifies how to proceed once its body has run.
+///
+/// First the provided `word` will be evaluated and compared to each
+/// pattern of each case arm. Once an arm with a matching pattern is found,
+/// its body is evaluated, after which its `CaseArmTerminator` determines
+/// whether the `case` command exits, runs the body of the next arm
+/// unconditionally, or resumes matching against the subsequent arms.
+///
+/// The exit status of the `case` command will be that of the last body
+/// which was run, or success if no arms are matched.
+pub async fn case_with_terminators<'a, I, W, P, S, E>(
+    word: W,
+    arms: I,
+    env: &mut E,
+) -> Result<BoxFuture<'static, ExitStatus>, S::Error>
+where
+    I: Iterator<Item = (PatternBodyPair<&'a [P], S>, CaseArmTerminator)>,
+    W: WordEval<E>,
+    P: 'a + WordEval<E>,
+    P::Error: IsFatalError,
+    S: Spawn<E>,
+    S::Error: From<W::Error> + From<P::Error>,
+    E: ?Sized
+        + AsyncIoEnvironment
+        + FileDescEnvironment
+        + LastStatusEnvironment
+        + ReportErrorEnvironment
+        + ShellOptionsEnvironment
+        + VariableEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+    E::VarName: Borrow<String>,
+    E::Var: Borrow<String>,
 {
     let cfg = WordEvalConfig {
         tilde_expansion: TildeExpansion::First,
         split_fields_further: false,
     };
 
-    let match_opts = MatchOptions {
-        case_sensitive: true,
-        require_literal_separator: false,
-        require_literal_leading_dot: false,
-    };
-
     let word = match word.eval_with_config(env, cfg).await {
         Ok(w) => w.await.join().into_owned(),
         Err(e) => {
@@ -55,25 +115,63 @@ where
         }
     };
 
-    for arm in arms {
-        for pat in arm.patterns {
-            let pat = match eval_as_pattern(pat, env).await {
-                Ok(pat) => pat,
-                Err(e) => {
-                    if e.is_fatal() {
-                        return Err(S::Error::from(e));
-                    } else {
-                        env.report_error(&e).await;
-                        continue;
-                    }
-                }
-            };
+    if let Some(fd) = xtrace_fd(env) {
+        let line = render_xtrace(&["case", &word, "in"], env);
+        write_xtrace(fd, line, env).await;
+    }
+
+    let mut fall_through = false;
+    let mut status = EXIT_SUCCESS;
+
+    for (arm, terminator) in arms {
+        if !fall_through && !arm_matches(&word, arm.patterns, env).await? {
+            continue;
+        }
+
+        let future = arm.body.spawn(env).await?;
+
+        fall_through = match terminator {
+            CaseArmTerminator::Break => return Ok(future),
+            CaseArmTerminator::FallThrough => true,
+            CaseArmTerminator::Continue => false,
+        };
+
+        status = future.await;
+        env.set_last_status(status);
+    }
+
+    Ok(Box::pin(async move { status }))
+}
 
-            if pat.matches_with(&word, match_opts) {
-                return arm.body.spawn(env).await;
+async fn arm_matches<P, E>(word: &str, patterns: &[P], env: &mut E) -> Result<bool, P::Error>
+where
+    P: WordEval<E>,
+    P::Error: IsFatalError,
+    E: ?Sized + ReportErrorEnvironment,
+{
+    let match_opts = MatchOptions {
+        case_sensitive: true,
+        require_literal_separator: false,
+        require_literal_leading_dot: false,
+    };
+
+    for pat in patterns {
+        let pat = match eval_as_pattern(pat, env).await {
+            Ok(pat) => pat,
+            Err(e) => {
+                if e.is_fatal() {
+                    return Err(e);
+                } else {
+                    env.report_error(&e).await;
+                    continue;
+                }
             }
+        };
+
+        if pat.matches_with(word, match_opts) {
+            return Ok(true);
         }
     }
 
-    Ok(Box::pin(async { EXIT_SUCCESS }))
+    Ok(false)
 }
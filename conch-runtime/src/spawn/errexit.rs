@@ -0,0 +1,38 @@
+use crate::env::ShellOptionsEnvironment;
+use std::ops::{Deref, DerefMut};
+
+/// Ignores the `errexit` option of an environment for as long as it is held
+/// (e.g. while evaluating the condition of an `if` command).
+///
+/// The option itself is left untouched, so any changes made to it in the
+/// meantime (e.g. via `set -e`) are retained once the guard is dropped.
+pub(crate) struct IgnoreErrExit<'a, E: ?Sized + ShellOptionsEnvironment> {
+    env: &'a mut E,
+}
+
+impl<'a, E: ?Sized + ShellOptionsEnvironment> IgnoreErrExit<'a, E> {
+    pub(crate) fn new(env: &'a mut E) -> Self {
+        env.begin_ignoring_errexit();
+        Self { env }
+    }
+}
+
+impl<'a, E: ?Sized + ShellOptionsEnvironment> Deref for IgnoreErrExit<'a, E> {
+    type Target = E;
+
+    fn deref(&self) -> &E {
+        self.env
+    }
+}
+
+impl<'a, E: ?Sized + ShellOptionsEnvironment> DerefMut for IgnoreErrExit<'a, E> {
+    fn deref_mut(&mut self) -> &mut E {
+        self.env
+    }
+}
+
+impl<'a, E: ?Sized + ShellOptionsEnvironment> Drop for IgnoreErrExit<'a, E> {
+    fn drop(&mut self) {
+        self.env.end_ignoring_errexit();
+    }
+}
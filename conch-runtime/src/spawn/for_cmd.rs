@@ -1,8 +1,19 @@
-use crate::env::{ArgumentsEnvironment, LastStatusEnvironment, VariableEnvironment};
+use crate::env::{
+    ArgumentsEnvironment, AsyncIoEnvironment, ControlFlowEnvironment, FileDescEnvironment,
+    LastStatusEnvironment, ShellOptionsEnvironment, VariableEnvironment,
+};
+use crate::error::CommandError;
 use crate::eval::WordEval;
-use crate::spawn::{ExitStatus, Spawn};
+use crate::spawn::loop_cmd::{loop_step_for_error, spawn_loop_step, LoopScope, LoopStep};
+use crate::spawn::xtrace::{render_xtrace, write_xtrace, xtrace_fd};
+use crate::spawn::{ready_status, ExitStatus, Spawn};
 use crate::EXIT_SUCCESS;
 use futures_core::future::BoxFuture;
+use futures_core::stream::Stream;
+use futures_util::pin_mut;
+use futures_util::stream::StreamExt;
+use std::borrow::Borrow;
+use std::error::Error;
 
 /// Spawns a `for` loop with all the fields when `words` are evaluated.
 ///
@@ -18,10 +29,18 @@ where
     I: IntoIterator<Item = W>,
     W: WordEval<E>,
     S: Spawn<E>,
-    S::Error: From<W::Error>,
-    E: ?Sized + LastStatusEnvironment + VariableEnvironment,
-    E::VarName: Clone,
-    E::Var: From<W::EvalResult>,
+    S::Error: 'static + Error + From<CommandError> + From<W::Error>,
+    E: ?Sized
+        + AsyncIoEnvironment
+        + ControlFlowEnvironment
+        + FileDescEnvironment
+        + LastStatusEnvironment
+        + ShellOptionsEnvironment
+        + VariableEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+    E::VarName: Borrow<String> + Clone,
+    E::Var: Borrow<String> + From<W::EvalResult>,
 {
     do_for_loop(name, words.into_iter(), body, env).await
 }
@@ -36,10 +55,18 @@ where
     I: Iterator<Item = W>,
     W: WordEval<E>,
     S: Spawn<E>,
-    S::Error: From<W::Error>,
-    E: ?Sized + LastStatusEnvironment + VariableEnvironment,
-    E::VarName: Clone,
-    E::Var: From<W::EvalResult>,
+    S::Error: 'static + Error + From<CommandError> + From<W::Error>,
+    E: ?Sized
+        + AsyncIoEnvironment
+        + ControlFlowEnvironment
+        + FileDescEnvironment
+        + LastStatusEnvironment
+        + ShellOptionsEnvironment
+        + VariableEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+    E::VarName: Borrow<String> + Clone,
+    E::Var: Borrow<String> + From<W::EvalResult>,
 {
     let (lo, hi) = words.size_hint();
     let mut values = Vec::with_capacity(hi.unwrap_or(lo));
@@ -59,6 +86,145 @@ where
     do_for_with_args(name, values.into_iter(), body, env).await
 }
 
+/// Spawns a `for` loop over `words`, evaluating each word only once
+/// the loop is ready to iterate over its fields.
+///
+/// Unlike `for_loop`, which evaluates all words before running the body even
+/// once, this variant only holds on to the fields of a single word at a time,
+/// which keeps memory usage low when iterating over huge expansions (e.g. the
+/// output of a command substitution). Note that this means that any side effects
+/// of the body (e.g. changing a variable) *will* be observed when evaluating any
+/// subsequent words.
+///
+/// Dropping the returned future cleanly cancels the loop: no further words are
+/// evaluated and the body is not run again.
+///
+/// When `xtrace` is enabled, each iteration is traced as `for NAME in VALUE`,
+/// since the remaining values are not known ahead of time.
+pub async fn for_loop_lazy<W, I, S, E>(
+    name: E::VarName,
+    words: I,
+    body: S,
+    env: &mut E,
+) -> Result<BoxFuture<'static, ExitStatus>, S::Error>
+where
+    I: IntoIterator<Item = W>,
+    W: WordEval<E>,
+    S: Spawn<E>,
+    S::Error: 'static + Error + From<CommandError> + From<W::Error>,
+    E: ?Sized
+        + AsyncIoEnvironment
+        + ControlFlowEnvironment
+        + FileDescEnvironment
+        + LastStatusEnvironment
+        + ShellOptionsEnvironment
+        + VariableEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+    E::VarName: Borrow<String> + Clone,
+    E::Var: Borrow<String> + From<W::EvalResult>,
+{
+    let mut scope = LoopScope::new(env);
+    let env = &mut *scope;
+    let mut status = EXIT_SUCCESS;
+
+    for word in words {
+        let fields = word.eval(env).await.map_err(S::Error::from)?.await;
+
+        for field in fields {
+            match run_iteration(&name, E::Var::from(field), &body, env).await? {
+                LoopStep::Next(s) => status = s,
+                LoopStep::Break => return Ok(ready_status(EXIT_SUCCESS)),
+            }
+        }
+    }
+
+    Ok(Box::pin(async move { status }))
+}
+
+/// Spawns a `for` loop over the values yielded by a (potentially unbounded) stream.
+///
+/// Values are only pulled from `args` once the loop is ready to iterate over them,
+/// thus only a single value is held in memory at a time.
+///
+/// Dropping the returned future cleanly cancels the loop: no further values are
+/// pulled from the stream and the body is not run again.
+///
+/// When `xtrace` is enabled, each iteration is traced as `for NAME in VALUE`,
+/// since the remaining values are not known ahead of time.
+pub async fn for_stream<St, S, E>(
+    name: E::VarName,
+    args: St,
+    body: S,
+    env: &mut E,
+) -> Result<BoxFuture<'static, ExitStatus>, S::Error>
+where
+    St: Stream<Item = E::Var>,
+    S: Spawn<E>,
+    S::Error: 'static + Error + From<CommandError>,
+    E: ?Sized
+        + AsyncIoEnvironment
+        + ControlFlowEnvironment
+        + FileDescEnvironment
+        + LastStatusEnvironment
+        + ShellOptionsEnvironment
+        + VariableEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+    E::VarName: Borrow<String> + Clone,
+    E::Var: Borrow<String>,
+{
+    pin_mut!(args);
+
+    let mut scope = LoopScope::new(env);
+    let env = &mut *scope;
+    let mut status = EXIT_SUCCESS;
+
+    while let Some(arg) = args.next().await {
+        match run_iteration(&name, arg, &body, env).await? {
+            LoopStep::Next(s) => status = s,
+            LoopStep::Break => return Ok(ready_status(EXIT_SUCCESS)),
+        }
+    }
+
+    Ok(Box::pin(async move { status }))
+}
+
+async fn run_iteration<S, E>(
+    name: &E::VarName,
+    arg: E::Var,
+    body: &S,
+    env: &mut E,
+) -> Result<LoopStep, S::Error>
+where
+    S: Spawn<E>,
+    S::Error: 'static + Error + From<CommandError>,
+    E: ?Sized
+        + AsyncIoEnvironment
+        + ControlFlowEnvironment
+        + FileDescEnvironment
+        + LastStatusEnvironment
+        + ShellOptionsEnvironment
+        + VariableEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+    E::VarName: Borrow<String> + Clone,
+    E::Var: Borrow<String>,
+{
+    if let Some(fd) = xtrace_fd(env) {
+        let line = render_xtrace(&["for", name.borrow(), "in", arg.borrow()], env);
+        write_xtrace(fd, line, env).await;
+    }
+
+    env.set_var(name.clone(), arg);
+    let step = spawn_loop_step(body, env).await?;
+    match step {
+        LoopStep::Next(status) => env.set_last_status(status),
+        LoopStep::Break => env.set_last_status(EXIT_SUCCESS),
+    }
+    Ok(step)
+}
+
 /// Spawns a `for` loop with the environment's currently set arguments.
 ///
 /// For each element in the environment's arguments, `name` will be assigned
@@ -70,9 +236,19 @@ pub async fn for_args<S, E>(
 ) -> Result<BoxFuture<'static, ExitStatus>, S::Error>
 where
     S: Spawn<E>,
-    E: ?Sized + ArgumentsEnvironment + LastStatusEnvironment + VariableEnvironment,
-    E::VarName: Clone,
-    E::Var: From<E::Arg>,
+    S::Error: 'static + Error + From<CommandError>,
+    E: ?Sized
+        + ArgumentsEnvironment
+        + AsyncIoEnvironment
+        + ControlFlowEnvironment
+        + FileDescEnvironment
+        + LastStatusEnvironment
+        + ShellOptionsEnvironment
+        + VariableEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+    E::VarName: Borrow<String> + Clone,
+    E::Var: Borrow<String> + From<E::Arg>,
 {
     let args = env
         .args()
@@ -97,22 +273,80 @@ pub async fn for_with_args<I, S, E>(
 where
     I: IntoIterator<Item = E::Var>,
     S: Spawn<E>,
-    E: ?Sized + LastStatusEnvironment + VariableEnvironment,
-    E::VarName: Clone,
+    S::Error: 'static + Error + From<CommandError>,
+    E: ?Sized
+        + AsyncIoEnvironment
+        + ControlFlowEnvironment
+        + FileDescEnvironment
+        + LastStatusEnvironment
+        + ShellOptionsEnvironment
+        + VariableEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+    E::VarName: Borrow<String> + Clone,
+    E::Var: Borrow<String>,
 {
     do_for_with_args(name, args.into_iter(), body, env).await
 }
 
 async fn do_for_with_args<I, S, E>(
+    name: E::VarName,
+    args: I,
+    body: S,
+    env: &mut E,
+) -> Result<BoxFuture<'static, ExitStatus>, S::Error>
+where
+    I: Iterator<Item = E::Var>,
+    S: Spawn<E>,
+    S::Error: 'static + Error + From<CommandError>,
+    E: ?Sized
+        + AsyncIoEnvironment
+        + ControlFlowEnvironment
+        + FileDescEnvironment
+        + LastStatusEnvironment
+        + ShellOptionsEnvironment
+        + VariableEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
+    E::VarName: Borrow<String> + Clone,
+    E::Var: Borrow<String>,
+{
+    let fd = match xtrace_fd(env) {
+        Some(fd) => fd,
+        None => return run_for_with_args(name, args, body, None, env).await,
+    };
+
+    // Each iteration is traced with the full list of words, thus
+    // we need to hold on to them while the loop is running
+    let args = args.collect::<Vec<_>>();
+    let line = {
+        let mut words = vec!["for", name.borrow().as_str(), "in"];
+        words.extend(args.iter().map(|arg| arg.borrow().as_str()));
+        render_xtrace(&words, env)
+    };
+
+    run_for_with_args(name, args.into_iter(), body, Some((fd, line)), env).await
+}
+
+async fn run_for_with_args<I, S, E>(
     name: E::VarName,
     mut args: I,
     body: S,
+    xtrace: Option<(E::FileHandle, String)>,
     env: &mut E,
 ) -> Result<BoxFuture<'static, ExitStatus>, S::Error>
 where
     I: Iterator<Item = E::Var>,
     S: Spawn<E>,
-    E: ?Sized + LastStatusEnvironment + VariableEnvironment,
+    S::Error: 'static + Error + From<CommandError>,
+    E: ?Sized
+        + AsyncIoEnvironment
+        + ControlFlowEnvironment
+        + FileDescEnvironment
+        + LastStatusEnvironment
+        + VariableEnvironment,
+    E::FileHandle: Clone,
+    E::IoHandle: From<E::FileHandle>,
     E::VarName: Clone,
 {
     let mut cur_arg = match args.next() {
@@ -120,13 +354,30 @@ where
         None => return Ok(Box::pin(async { EXIT_SUCCESS })),
     };
 
+    let mut scope = LoopScope::new(env);
+    let env = &mut *scope;
+
     for next in args {
+        if let Some((fd, line)) = &xtrace {
+            write_xtrace(fd.clone(), line.clone(), env).await;
+        }
+
         env.set_var(name.clone(), cur_arg);
-        let status = body.spawn(env).await?.await;
-        env.set_last_status(status);
+        match spawn_loop_step(&body, env).await? {
+            LoopStep::Next(status) => env.set_last_status(status),
+            LoopStep::Break => return Ok(ready_status(EXIT_SUCCESS)),
+        }
         cur_arg = next;
     }
 
+    if let Some((fd, line)) = xtrace {
+        write_xtrace(fd, line, env).await;
+    }
+
     env.set_var(name, cur_arg);
-    body.spawn(env).await
+    match body.spawn(env).await {
+        Ok(future) => Ok(future),
+        // NB: the last iteration has nothing left to skip to
+        Err(e) => loop_step_for_error(e).map(|_| ready_status(EXIT_SUCCESS)),
+    }
 }
@@ -1,6 +1,11 @@
-use crate::env::{FunctionEnvironment, FunctionFrameEnvironment, SetArgumentsEnvironment};
+use crate::env::{
+    ControlFlow, FunctionEnvironment, FunctionFrameEnvironment, SetArgumentsEnvironment,
+};
+use crate::error::control_flow_for_error;
+use crate::spawn::ready_status;
 use crate::{ExitStatus, Spawn};
 use futures_core::future::BoxFuture;
+use std::error::Error;
 
 /// Creates a future adapter that will attempt to execute a function (if it has
 /// been defined) with a given set of arguments.
@@ -13,6 +18,7 @@ where
     E: FunctionEnvironment<Fn = S> + FunctionFrameEnvironment + SetArgumentsEnvironment,
     E::Args: From<A>,
     S: Clone + Spawn<E>,
+    S::Error: 'static + Error,
 {
     match env.function(name).cloned() {
         Some(func) => Some(function_body(func, args, env).await),
@@ -21,6 +27,9 @@ where
 }
 
 /// Creates a future adapter that will execute a function body with the given set of arguments.
+///
+/// If the body requests to `return` (i.e. it fails with a `CommandError::Return`
+/// error), the function completes with the requested status.
 pub async fn function_body<S, A, E: ?Sized>(
     body: S,
     args: A,
@@ -28,6 +37,7 @@ pub async fn function_body<S, A, E: ?Sized>(
 ) -> Result<BoxFuture<'static, ExitStatus>, S::Error>
 where
     S: Spawn<E>,
+    S::Error: 'static + Error,
     E: FunctionFrameEnvironment + SetArgumentsEnvironment,
     E::Args: From<A>,
 {
@@ -41,14 +51,42 @@ async fn do_function_body<S, E: ?Sized>(
 ) -> Result<BoxFuture<'static, ExitStatus>, S::Error>
 where
     S: Spawn<E>,
+    S::Error: 'static + Error,
     E: FunctionFrameEnvironment + SetArgumentsEnvironment,
 {
     env.push_fn_frame();
     let old_args = env.set_args(args);
 
-    let ret = body.spawn(env).await;
+    // NB: restore the previous frame even if we get cancelled
+    let frame = FnFrame {
+        env,
+        old_args: Some(old_args),
+    };
 
-    env.set_args(old_args);
-    env.pop_fn_frame();
-    ret
+    match body.spawn(&mut *frame.env).await {
+        Err(e) => match control_flow_for_error(&e) {
+            Some(ControlFlow::Return(status)) => Ok(ready_status(status)),
+            _ => Err(e),
+        },
+        ret => ret,
+    }
+}
+
+/// Restores the previous function frame and arguments when dropped.
+struct FnFrame<'a, E: ?Sized + FunctionFrameEnvironment + SetArgumentsEnvironment> {
+    env: &'a mut E,
+    old_args: Option<E::Args>,
+}
+
+impl<'a, E> Drop for FnFrame<'a, E>
+where
+    E: ?Sized + FunctionFrameEnvironment + SetArgumentsEnvironment,
+{
+    fn drop(&mut self) {
+        if let Some(old_args) = self.old_args.take() {
+            self.env.set_args(old_args);
+        }
+
+        self.env.pop_fn_frame();
+    }
 }
@@ -1,5 +1,6 @@
-use crate::env::LastStatusEnvironment;
+use crate::env::{LastStatusEnvironment, ShellOptionsEnvironment};
 use crate::error::IsFatalError;
+use crate::spawn::errexit::IgnoreErrExit;
 use crate::spawn::GuardBodyPair;
 use crate::{ExitStatus, Spawn, EXIT_SUCCESS};
 use futures_core::future::BoxFuture;
@@ -10,6 +11,8 @@ use futures_core::future::BoxFuture;
 /// corresponding body will be evaluated. If no guard exits successfully,
 /// the `else` branch will be run, if present. Otherwise, the `If` command
 /// will exit successfully.
+///
+/// The `errexit` option is ignored while any guard is evaluated.
 pub async fn if_cmd<S, ELS, I, E>(
     conditionals: I,
     else_branch: Option<ELS>,
@@ -20,10 +23,13 @@ where
     S::Error: IsFatalError,
     ELS: Spawn<E, Error = S::Error>,
     I: Iterator<Item = GuardBodyPair<S>>,
-    E: ?Sized + LastStatusEnvironment,
+    E: ?Sized + LastStatusEnvironment + ShellOptionsEnvironment,
 {
     for gbp in conditionals {
-        let status = gbp.guard.spawn(env).await?.await;
+        let status = {
+            let mut env = IgnoreErrExit::new(&mut *env);
+            gbp.guard.spawn(&mut *env).await?.await
+        };
         env.set_last_status(status);
 
         if status.success() {
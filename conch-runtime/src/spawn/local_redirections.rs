@@ -56,9 +56,10 @@ where
 
     for redirect in redirects {
         let action = redirect.eval(restorer.get_mut()).await?;
+        let fd = action.fd();
         action
             .apply(restorer)
-            .map_err(|e| RedirectionError::Io(e, None))?;
+            .map_err(|e| RedirectionError::fd_io(e, fd))?;
     }
 
     cmd.spawn(restorer.get_mut()).await
@@ -1,7 +1,13 @@
-use crate::env::LastStatusEnvironment;
+use crate::env::{
+    ControlFlow, ControlFlowEnvironment, LastStatusEnvironment, ShellOptionsEnvironment,
+};
+use crate::error::{control_flow_for_error, CommandError};
+use crate::spawn::errexit::IgnoreErrExit;
 use crate::spawn::Spawn;
 use crate::{ExitStatus, EXIT_SUCCESS};
+use std::error::Error;
 use std::future::Future;
+use std::ops::{Deref, DerefMut};
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
@@ -12,7 +18,13 @@ use std::task::{Context, Poll};
 /// `invert_guard_status == false`, the loop will continue as long as the guard
 /// exits successfully. If `invert_guard_status == true`, the loop will continue
 /// **until** the guard exits successfully.
-// FIXME: implement a `break` built in command to break loops
+///
+/// The `errexit` option is ignored while the guard is evaluated.
+///
+/// Any requests to `break` out of (or `continue`) this loop, made by either
+/// the guard or the body, are honored, while requests to affect any outer
+/// loops are propagated (as a `CommandError::Break` or `CommandError::Continue`
+/// error) with one less level.
 pub async fn loop_cmd<G, B, E>(
     invert_guard_status: bool,
     guard: G,
@@ -21,9 +33,12 @@ pub async fn loop_cmd<G, B, E>(
 ) -> Result<ExitStatus, G::Error>
 where
     G: Spawn<E>,
+    G::Error: 'static + Error + From<CommandError>,
     B: Spawn<E, Error = G::Error>,
-    E: ?Sized + LastStatusEnvironment,
+    E: ?Sized + ControlFlowEnvironment + LastStatusEnvironment + ShellOptionsEnvironment,
 {
+    let mut env = LoopScope::new(env);
+
     // bash/zsh will exit loops with a successful status if
     // loop breaks out of the first round without running the body,
     // so if it hasn't yet run, consider it a success
@@ -35,7 +50,18 @@ where
         // readiness) every once in a while so that other futures running on
         // the same thread get a chance to make some progress too.
         for _ in 0..20usize {
-            let guard_status = guard.spawn(env).await?.await;
+            let guard_step = {
+                let mut env = IgnoreErrExit::new(&mut *env);
+                spawn_loop_step(&guard, &mut *env).await?
+            };
+
+            let guard_status = match guard_step {
+                LoopStep::Next(status) => status,
+                LoopStep::Break => {
+                    env.set_last_status(EXIT_SUCCESS);
+                    return Ok(EXIT_SUCCESS);
+                }
+            };
             let should_continue = guard_status.success() ^ invert_guard_status;
 
             if !should_continue {
@@ -48,7 +74,13 @@ where
             // Set the guard status so that the body can access it if needed
             env.set_last_status(guard_status);
 
-            last_body_status = body.spawn(env).await?.await;
+            last_body_status = match spawn_loop_step(&body, &mut *env).await? {
+                LoopStep::Next(status) => status,
+                LoopStep::Break => {
+                    env.set_last_status(EXIT_SUCCESS);
+                    return Ok(EXIT_SUCCESS);
+                }
+            };
             env.set_last_status(last_body_status);
         }
 
@@ -56,8 +88,83 @@ where
     }
 }
 
+/// Keeps track of a loop which is currently executing (so that the `break` and
+/// `continue` builtins know how many loops they may affect) for as long as it
+/// is held.
+pub(crate) struct LoopScope<'a, E: ?Sized + ControlFlowEnvironment> {
+    env: &'a mut E,
+}
+
+impl<'a, E: ?Sized + ControlFlowEnvironment> LoopScope<'a, E> {
+    pub(crate) fn new(env: &'a mut E) -> Self {
+        env.push_loop_frame();
+        Self { env }
+    }
+}
+
+impl<'a, E: ?Sized + ControlFlowEnvironment> Deref for LoopScope<'a, E> {
+    type Target = E;
+
+    fn deref(&self) -> &E {
+        self.env
+    }
+}
+
+impl<'a, E: ?Sized + ControlFlowEnvironment> DerefMut for LoopScope<'a, E> {
+    fn deref_mut(&mut self) -> &mut E {
+        self.env
+    }
+}
+
+impl<'a, E: ?Sized + ControlFlowEnvironment> Drop for LoopScope<'a, E> {
+    fn drop(&mut self) {
+        self.env.pop_loop_frame();
+    }
+}
+
+/// What a loop should do after running one of its commands.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum LoopStep {
+    /// Carry on with the loop, using the status of the command.
+    Next(ExitStatus),
+    /// Stop the loop altogether.
+    Break,
+}
+
+/// Determines how a loop should proceed after one of its commands failed.
+///
+/// Requests to `break` out of (or `continue`) the innermost loop are handled
+/// here, while requests which affect any outer loops are propagated with one
+/// less level. All other errors are returned as is.
+pub(crate) fn loop_step_for_error<ERR>(err: ERR) -> Result<LoopStep, ERR>
+where
+    ERR: 'static + Error + From<CommandError>,
+{
+    match control_flow_for_error(&err) {
+        Some(ControlFlow::Break(n)) if n > 1 => Err(CommandError::Break(n - 1).into()),
+        Some(ControlFlow::Continue(n)) if n > 1 => Err(CommandError::Continue(n - 1).into()),
+        Some(ControlFlow::Break(_)) => Ok(LoopStep::Break),
+        Some(ControlFlow::Continue(_)) => Ok(LoopStep::Next(EXIT_SUCCESS)),
+        _ => Err(err),
+    }
+}
+
+/// Spawns a command of a loop and waits for it to complete.
+pub(crate) async fn spawn_loop_step<S, E>(cmd: &S, env: &mut E) -> Result<LoopStep, S::Error>
+where
+    S: Spawn<E>,
+    S::Error: 'static + Error + From<CommandError>,
+    E: ?Sized,
+{
+    match cmd.spawn(env).await {
+        Ok(future) => Ok(LoopStep::Next(future.await)),
+        Err(e) => loop_step_for_error(e),
+    }
+}
+
 /// A future which yields once and resolves.
 #[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
 struct YieldOnce {
     yielded: bool,
 }
@@ -0,0 +1,96 @@
+use crate::env::{ReportErrorEnvironment, SubEnvironment};
+use crate::error::ErrorContext;
+use crate::spawn::subshell::subshell_with_env;
+use crate::{ExitStatus, Spawn, EXIT_ERROR, EXIT_SUCCESS};
+use futures_util::future::join_all;
+use std::error::Error;
+use std::future::Future;
+
+/// Spawns a set of independent commands concurrently, each in its own
+/// sub-environment (i.e. as if each were run as `( cmd ) &`), and resolves
+/// once *all* of them have completed.
+///
+/// Much like a subshell, any side effects of the commands (e.g. setting variables)
+/// are not reflected in the parent environment, and any errors are reported and
+/// treated as if the command exited with the status `exit_status_for_error` derives.
+///
+/// See `ParallelAggregate` for how the status of the entire set is derived.
+pub fn parallel<S, I, E>(
+    aggregate: ParallelAggregate,
+    cmds: I,
+    env: &E,
+) -> impl Future<Output = ParallelStatus>
+where
+    I: IntoIterator<Item = S>,
+    S: Spawn<E>,
+    S::Error: 'static + Send + Sync + Error,
+    E: ReportErrorEnvironment + SubEnvironment,
+{
+    let futures = cmds
+        .into_iter()
+        .map(|cmd| subshell_with_env(cmd, env.sub_env(), ErrorContext::Subshell))
+        .collect::<Vec<_>>();
+
+    async move {
+        ParallelStatus {
+            statuses: join_all(futures).await,
+            aggregate,
+        }
+    }
+}
+
+/// Determines how the exit statuses of commands spawned via `parallel`
+/// are combined into the status of the entire set.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ParallelAggregate {
+    /// The status of the first unsuccessful command (in the order the commands
+    /// were provided, not the order they finished), or `EXIT_SUCCESS` if all
+    /// commands succeeded (e.g. like `make`).
+    #[default]
+    FirstFailure,
+    /// The status of the last command (in the order the commands were provided).
+    Last,
+    /// `EXIT_SUCCESS` if any of the commands succeeded, otherwise `EXIT_ERROR`.
+    AnySuccess,
+}
+
+/// The exit statuses of all commands spawned via `parallel`.
+///
+/// An empty set of commands is always considered successful.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParallelStatus {
+    statuses: Vec<ExitStatus>,
+    aggregate: ParallelAggregate,
+}
+
+impl ParallelStatus {
+    /// The exit statuses of every command, in the order the commands were
+    /// provided (i.e. *not* the order in which they finished).
+    pub fn statuses(&self) -> &[ExitStatus] {
+        &self.statuses
+    }
+
+    /// The policy used to derive the status of the entire set.
+    pub fn aggregate(&self) -> ParallelAggregate {
+        self.aggregate
+    }
+
+    /// The exit status of the entire set, according to its `ParallelAggregate` policy.
+    pub fn status(&self) -> ExitStatus {
+        let mut statuses = self.statuses.iter().copied();
+
+        match self.aggregate {
+            ParallelAggregate::FirstFailure => statuses
+                .find(|status| !status.success())
+                .unwrap_or(EXIT_SUCCESS),
+            ParallelAggregate::Last => statuses.last().unwrap_or(EXIT_SUCCESS),
+            ParallelAggregate::AnySuccess => {
+                if self.statuses.is_empty() || statuses.any(|status| status.success()) {
+                    EXIT_SUCCESS
+                } else {
+                    EXIT_ERROR
+                }
+            }
+        }
+    }
+}
@@ -1,5 +1,11 @@
-use crate::env::{FileDescEnvironment, FileDescOpener, ReportErrorEnvironment, SubEnvironment};
-use crate::error::IsFatalError;
+use crate::env::{
+    FileDescEnvironment, FileDescOpener, JobEnvironment, Pipe, ReportErrorEnvironment,
+    ShellOptionsEnvironment, SubEnvironment,
+};
+use crate::error::{
+    control_flow_for_error, exit_status_for_error, is_too_many_open_files, CommandError,
+    IsFatalError,
+};
 use crate::io::Permissions;
 use crate::spawn::swallow_non_fatal_errors;
 use crate::{ExitStatus, Spawn, EXIT_ERROR, EXIT_SUCCESS, STDIN_FILENO, STDOUT_FILENO};
@@ -8,6 +14,7 @@ use futures_core::stream::Stream;
 use futures_util::future::poll_fn;
 use futures_util::stream::{FuturesUnordered, StreamExt};
 use std::error::Error;
+use std::fmt;
 use std::future::Future;
 use std::io;
 use std::pin::Pin;
@@ -22,6 +29,14 @@ use std::task::{Context, Poll};
 /// If `invert_last_status` is set to `false`, the pipeline will fully resolve
 /// to the last command's exit status. Otherwise, `EXIT_ERROR` will be returned
 /// if the last command succeeds, and `EXIT_SUCCESS` will be returned otherwise.
+///
+/// See `PipelineStatus` for the precise policy of which status is reported.
+///
+/// All pipes are created before any command is spawned. If too many files are
+/// open to create them, any pipes which were already created are closed, and
+/// `CommandError::TooManyOpenFiles` is returned (unless the
+/// `ShellOptions::reap_jobs_on_fd_exhaustion` option is set, in which case the
+/// pipes are created again once a background job completes).
 pub async fn pipeline<S, I, E>(
     invert_last_status: bool,
     first: S,
@@ -31,26 +46,147 @@ pub async fn pipeline<S, I, E>(
 where
     I: IntoIterator<Item = S>,
     S: Send + Sync + Spawn<E>,
-    S::Error: From<io::Error> + IsFatalError,
-    E: Send + FileDescEnvironment + FileDescOpener + ReportErrorEnvironment + SubEnvironment,
+    S::Error: From<io::Error> + From<CommandError> + IsFatalError,
+    E: Send
+        + FileDescEnvironment
+        + FileDescOpener
+        + JobEnvironment
+        + ReportErrorEnvironment
+        + ShellOptionsEnvironment
+        + SubEnvironment,
+    E::FileHandle: From<E::OpenedFileHandle>,
+{
+    let future = do_pipeline(invert_last_status, first, rest.into_iter(), env).await?;
+    Ok(Box::pin(async move { future.await.status() }))
+}
+
+/// Spawns a pipeline of commands, exactly like `pipeline`, but resolves to the
+/// exit statuses of *all* commands in the pipeline.
+pub async fn pipeline_with_status<S, I, E>(
+    invert_last_status: bool,
+    first: S,
+    rest: I,
+    env: &mut E,
+) -> Result<BoxFuture<'static, PipelineStatus>, S::Error>
+where
+    I: IntoIterator<Item = S>,
+    S: Send + Sync + Spawn<E>,
+    S::Error: From<io::Error> + From<CommandError> + IsFatalError,
+    E: Send
+        + FileDescEnvironment
+        + FileDescOpener
+        + JobEnvironment
+        + ReportErrorEnvironment
+        + ShellOptionsEnvironment
+        + SubEnvironment,
     E::FileHandle: From<E::OpenedFileHandle>,
 {
     do_pipeline(invert_last_status, first, rest.into_iter(), env).await
 }
 
+/// The exit statuses of all commands which ran as part of a pipeline.
+///
+/// The commands of a pipeline run concurrently and may finish in any order
+/// (e.g. a builtin at the end of the pipeline may finish well before the
+/// commands feeding it input), but the pipeline as a whole only completes
+/// once *all* of its commands have completed. Regardless of the order in
+/// which they finish, the status of the pipeline is always derived from
+/// its *last* command:
+///
+/// * `last_status` is the exit status of the last command
+/// * `status` is the status of the entire pipeline as observed by `!`, `&&`,
+///   `||` and `$?`, which is `last_status`, inverted if the pipeline was negated
+///
+/// Unless the `pipefail` option was set when the pipeline was spawned, in which
+/// case the status of the pipeline is derived from its last command which exited
+/// unsuccessfully (if any) instead.
+///
+/// Any command which could not be spawned (after reporting the error) is
+/// considered to have exited with the status `exit_status_for_error` derives.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PipelineStatus {
+    statuses: Vec<ExitStatus>,
+    inverted: bool,
+    pipefail: bool,
+}
+
+impl PipelineStatus {
+    /// The exit statuses of every command in the pipeline, in pipeline order
+    /// (i.e. *not* the order in which they finished).
+    pub fn statuses(&self) -> &[ExitStatus] {
+        &self.statuses
+    }
+
+    /// The exit status of the last command in the pipeline.
+    pub fn last_status(&self) -> ExitStatus {
+        *self
+            .statuses
+            .last()
+            .expect("pipelines always have at least one command")
+    }
+
+    /// Indicates whether the status of the pipeline is inverted (i.e. `! cmd`).
+    pub fn is_inverted(&self) -> bool {
+        self.inverted
+    }
+
+    /// Indicates whether the `pipefail` option was set when the pipeline was spawned.
+    pub fn is_pipefail(&self) -> bool {
+        self.pipefail
+    }
+
+    /// The exit status of the entire pipeline.
+    ///
+    /// If the pipeline is inverted, this is `EXIT_ERROR` if the last command
+    /// succeeded, and `EXIT_SUCCESS` otherwise. Otherwise it is the exit status
+    /// of the last command.
+    ///
+    /// If `pipefail` was set, the status of the last command which exited
+    /// unsuccessfully is used in place of the last command's status (or
+    /// `EXIT_SUCCESS` if all commands succeeded).
+    pub fn status(&self) -> ExitStatus {
+        let last_status = if self.pipefail {
+            self.statuses
+                .iter()
+                .rev()
+                .find(|status| !status.success())
+                .copied()
+                .unwrap_or(EXIT_SUCCESS)
+        } else {
+            self.last_status()
+        };
+
+        if !self.inverted {
+            last_status
+        } else if last_status.success() {
+            EXIT_ERROR
+        } else {
+            EXIT_SUCCESS
+        }
+    }
+}
+
 async fn do_pipeline<S, I, E>(
     invert_last_status: bool,
     first: S,
     mut rest: I,
     orig_env: &mut E,
-) -> Result<BoxFuture<'static, ExitStatus>, S::Error>
+) -> Result<BoxFuture<'static, PipelineStatus>, S::Error>
 where
     I: Iterator<Item = S>,
     S: Send + Sync + Spawn<E>,
-    S::Error: From<io::Error> + IsFatalError,
-    E: Send + FileDescEnvironment + FileDescOpener + ReportErrorEnvironment + SubEnvironment,
+    S::Error: From<io::Error> + From<CommandError> + IsFatalError,
+    E: Send
+        + FileDescEnvironment
+        + FileDescOpener
+        + JobEnvironment
+        + ReportErrorEnvironment
+        + ShellOptionsEnvironment
+        + SubEnvironment,
     E::FileHandle: From<E::OpenedFileHandle>,
 {
+    let pipefail = orig_env.options().pipefail;
+
     // When we spawn each command in the pipeline, we'll pins them to their own
     // (sub) environments.
     //
@@ -75,17 +211,26 @@ where
     // Futures which are still holding an environment or a reference to the command being
     // spawned and as such they cannot be treated as static (well, without imposing that
     // bound on the caller).
+    //
+    // Each of the non-final commands is tagged with its position in the pipeline
+    // so that its status can be recorded, even though they can finish in any order.
     let env_futures = FuturesUnordered::new();
+    let mut num_cmds = 1;
 
     let final_cmd_env_future: BoxFuture<'_, _> = if let Some(second) = rest.next() {
+        let rest = rest.collect::<Vec<_>>();
+        let pipes = open_pipes::<_, S::Error>(rest.len() + 1, orig_env).await?;
+        let mut pipes = pipes.into_iter();
+        let mut next_pipe = || pipes.next().expect("missing pipe");
+
         let mut next_in = {
             // First command will automatically inherit the stdin of the
             // parent environment, so no need to manually set it
             let mut env = orig_env.sub_env();
-            let pipe = env.open_pipe()?;
+            let pipe = next_pipe();
 
             env.set_file_desc(STDOUT_FILENO, pipe.writer.into(), Permissions::Write);
-            env_futures.push(spawn_and_swallow_errors(first, env));
+            env_futures.push(spawn_and_swallow_errors(0, first, env));
 
             pipe.reader
         };
@@ -93,15 +238,17 @@ where
         let mut last = second;
         for next in rest {
             let mut env = orig_env.sub_env();
-            let pipe = env.open_pipe()?;
+            let pipe = next_pipe();
 
             env.set_file_desc(STDIN_FILENO, next_in.into(), Permissions::Read);
             env.set_file_desc(STDOUT_FILENO, pipe.writer.into(), Permissions::Write);
             next_in = pipe.reader;
 
-            env_futures.push(spawn_and_swallow_errors(last, env));
+            env_futures.push(spawn_and_swallow_errors(num_cmds, last, env));
+            num_cmds += 1;
             last = next;
         }
+        num_cmds += 1;
 
         let mut env = orig_env.sub_env();
         env.set_file_desc(STDIN_FILENO, next_in.into(), Permissions::Read);
@@ -132,46 +279,59 @@ where
     // at which point we can move into the second "static future" phase. But this requires
     // doing some extra book keeping which happens below.
 
+    //
+    // Note that the final command may well finish (e.g. if it is a builtin) before
+    // the other commands have even been spawned, so its status must be retained
+    // until everything else completes.
+
     let mut env_futures = Box::pin(env_futures);
     let mut static_futures = Box::pin(FuturesUnordered::new());
     let mut final_cmd_state = FinalCmdState::EnvFuture(final_cmd_env_future);
+    let mut statuses = vec![EXIT_ERROR; num_cmds];
 
     poll_fn(|cx| {
         let env_futures_done = loop {
             match env_futures.as_mut().poll_next(cx) {
-                Poll::Ready(Some(sf)) => sf.map(|sf| static_futures.push(sf)),
+                Poll::Ready(Some((idx, sf))) => {
+                    static_futures.push(async move { (idx, sf.await) });
+                }
                 Poll::Ready(None) => break true,
                 Poll::Pending => break false,
             };
         };
 
-        loop {
-            match &mut final_cmd_state {
-                FinalCmdState::EnvFuture(ef) => {
-                    final_cmd_state = match ef.as_mut().poll(cx) {
-                        Poll::Pending => break,
-                        Poll::Ready(Ok(f)) => FinalCmdState::Maybe(MaybeDone::Future(f)),
-                        Poll::Ready(Err(e)) => FinalCmdState::Error(e),
-                    };
+        let final_cmd_spawned = match &mut final_cmd_state {
+            FinalCmdState::EnvFuture(ef) => match ef.as_mut().poll(cx) {
+                Poll::Pending => false,
+                Poll::Ready(Ok(f)) => {
+                    final_cmd_state = FinalCmdState::Maybe(MaybeDone::Future(f));
+                    true
                 }
-
-                FinalCmdState::Error(_) => {}
-
-                FinalCmdState::Maybe(f) => {
-                    let _ = Pin::new(f).poll(cx);
+                Poll::Ready(Err(e)) => {
+                    final_cmd_state = FinalCmdState::Error(e);
+                    true
                 }
-            }
+            },
+            FinalCmdState::Error(_) | FinalCmdState::Maybe(_) => true,
+        };
 
-            // Don't need references to any environments
-            // or commands any more, so bail!
-            if env_futures_done {
-                return Poll::Ready(());
-            }
+        // NB: the final command may complete before any of the other commands,
+        // so we must keep polling it until then to avoid starving it
+        if let FinalCmdState::Maybe(f) = &mut final_cmd_state {
+            let _ = Pin::new(f).poll(cx);
+        }
+
+        // Don't need references to any environments
+        // or commands any more, so bail!
+        if final_cmd_spawned && env_futures_done {
+            return Poll::Ready(());
         }
 
         // Still have pending futures, keep polling any static_futures so they
         // can make progress.
-        while let Poll::Ready(Some(_exit)) = static_futures.as_mut().poll_next(cx) {}
+        while let Poll::Ready(Some((idx, status))) = static_futures.as_mut().poll_next(cx) {
+            statuses[idx] = status;
+        }
 
         Poll::Pending
     })
@@ -192,38 +352,89 @@ where
 
     Ok(Box::pin(async move {
         let (_, final_status) = futures_util::join!(
-            async move { while let Some(_status) = static_futures.next().await {} },
+            async {
+                while let Some((idx, status)) = static_futures.next().await {
+                    statuses[idx] = status;
+                }
+            },
             final_cmd,
         );
 
-        if invert_last_status {
-            if final_status.success() {
-                EXIT_ERROR
-            } else {
-                EXIT_SUCCESS
+        statuses[num_cmds - 1] = final_status;
+
+        PipelineStatus {
+            statuses,
+            inverted: invert_last_status,
+            pipefail,
+        }
+    }))
+}
+
+/// Opens all `count` pipes of a pipeline at once, so that a failure
+/// does not leave behind a partially constructed pipeline.
+async fn open_pipes<E, ERR>(
+    count: usize,
+    env: &mut E,
+) -> Result<Vec<Pipe<E::OpenedFileHandle>>, ERR>
+where
+    E: FileDescOpener + JobEnvironment + ShellOptionsEnvironment,
+    ERR: From<io::Error> + From<CommandError>,
+{
+    loop {
+        let mut pipes = Vec::with_capacity(count);
+        let err = loop {
+            if pipes.len() == count {
+                return Ok(pipes);
             }
+
+            match env.open_pipe() {
+                Ok(pipe) => pipes.push(pipe),
+                Err(e) => break e,
+            }
+        };
+
+        // Close any pipes we did manage to open before retrying (or bailing)
+        drop(pipes);
+
+        if !is_too_many_open_files(&err) {
+            return Err(err.into());
+        }
+
+        let job_done = if env.options().reap_jobs_on_fd_exhaustion {
+            env.any_job_done()
         } else {
-            final_status
+            None
+        };
+
+        match job_done {
+            Some(job_done) => job_done.await,
+            None => return Err(CommandError::TooManyOpenFiles(err).into()),
         }
-    }))
+    }
 }
 
 async fn spawn_and_swallow_errors<S, E>(
+    idx: usize,
     cmd: S,
     mut env: E,
-) -> Option<BoxFuture<'static, ExitStatus>>
+) -> (usize, BoxFuture<'static, ExitStatus>)
 where
     S: Spawn<E>,
     S::Error: 'static + Send + Sync + Error,
     E: ReportErrorEnvironment,
 {
-    match cmd.spawn(&mut env).await {
-        Ok(f) => Some(f),
-        Err(e) => {
-            env.report_error(&e).await;
-            None
-        }
-    }
+    let status = match cmd.spawn(&mut env).await {
+        Ok(f) => return (idx, f),
+        Err(e) => match control_flow_for_error(&e) {
+            Some(flow) => flow.status(),
+            None => {
+                env.report_error(&e).await;
+                exit_status_for_error(&e)
+            }
+        },
+    };
+
+    (idx, Box::pin(async move { status }))
 }
 
 enum FinalCmdState<EF, ERR> {
@@ -243,6 +454,25 @@ enum MaybeDone {
     Done(ExitStatus),
 }
 
+impl<EF, ERR: fmt::Debug> fmt::Debug for FinalCmdState<EF, ERR> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FinalCmdState::EnvFuture(_) => fmt.write_str("EnvFuture(..)"),
+            FinalCmdState::Error(e) => fmt.debug_tuple("Error").field(e).finish(),
+            FinalCmdState::Maybe(m) => fmt.debug_tuple("Maybe").field(m).finish(),
+        }
+    }
+}
+
+impl fmt::Debug for MaybeDone {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MaybeDone::Future(_) => fmt.write_str("Future(..)"),
+            MaybeDone::Done(status) => fmt.debug_tuple("Done").field(status).finish(),
+        }
+    }
+}
+
 impl Future for MaybeDone {
     type Output = ExitStatus;
 
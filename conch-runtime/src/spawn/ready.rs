@@ -0,0 +1,44 @@
+use crate::{ExitStatus, EXIT_ERROR, EXIT_SUCCESS};
+use futures_core::future::BoxFuture;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A zero sized future which immediately resolves to `EXIT_SUCCESS`.
+#[derive(Debug)]
+struct Success;
+
+impl Future for Success {
+    type Output = ExitStatus;
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Poll::Ready(EXIT_SUCCESS)
+    }
+}
+
+/// A zero sized future which immediately resolves to `EXIT_ERROR`.
+#[derive(Debug)]
+struct Error;
+
+impl Future for Error {
+    type Output = ExitStatus;
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Poll::Ready(EXIT_ERROR)
+    }
+}
+
+/// Creates a future which immediately resolves to the provided status.
+///
+/// Since boxing a zero sized future does not allocate, the most common statuses
+/// (i.e. `EXIT_SUCCESS` and `EXIT_ERROR`) can be returned without allocating,
+/// which makes this suitable for commands which run in tight loops, like `:`.
+pub fn ready_status(status: ExitStatus) -> BoxFuture<'static, ExitStatus> {
+    if status == EXIT_SUCCESS {
+        Box::pin(Success)
+    } else if status == EXIT_ERROR {
+        Box::pin(Error)
+    } else {
+        Box::pin(futures_util::future::ready(status))
+    }
+}
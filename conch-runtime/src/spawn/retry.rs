@@ -0,0 +1,98 @@
+use crate::env::{FileDescEnvironment, RedirectEnvRestorer, VarEnvRestorer, VariableEnvironment};
+use crate::spawn::Spawn;
+use crate::ExitStatus;
+use std::convert::TryFrom;
+use std::time::Duration;
+
+/// Determines how many times, and how often, a command spawned via `retry`
+/// is attempted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// The maximum number of times the command will be spawned. The command
+    /// is always spawned at least once, even if this is zero.
+    pub max_attempts: usize,
+    /// How long to wait before retrying the command for the first time.
+    pub delay: Duration,
+    /// The factor by which the delay grows after every retry (e.g. a factor
+    /// of 2 doubles the delay each time, while 1 keeps it constant).
+    pub backoff_factor: u32,
+    /// The upper bound of the delay between attempts, if any.
+    pub max_delay: Option<Duration>,
+}
+
+impl RetryPolicy {
+    /// Create a policy which attempts a command up to `max_attempts` times,
+    /// waiting a constant `delay` between each attempt.
+    pub fn new(max_attempts: usize, delay: Duration) -> Self {
+        Self {
+            max_attempts,
+            delay,
+            backoff_factor: 1,
+            max_delay: None,
+        }
+    }
+
+    /// The delay to wait before making the `retry`-th retry (counting from 1).
+    pub fn delay_before(&self, retry: usize) -> Duration {
+        let exponent = u32::try_from(retry.saturating_sub(1)).unwrap_or(u32::MAX);
+
+        let delay = self
+            .backoff_factor
+            .checked_pow(exponent)
+            .and_then(|factor| self.delay.checked_mul(factor));
+
+        match (delay, self.max_delay) {
+            (Some(delay), Some(max)) => delay.min(max),
+            (Some(delay), None) => delay,
+            (None, Some(max)) => max,
+            (None, None) => Duration::from_secs(u64::MAX),
+        }
+    }
+}
+
+/// Spawns the commands produced by `cmd_factory` until one of them exits
+/// successfully, or until the `RetryPolicy` runs out of attempts, resolving
+/// with the exit status of the last attempt.
+///
+/// Before every attempt, `cmd_factory` is given the restorer so that it can
+/// apply any local variables or redirects the attempt should run with. Once an
+/// attempt completes (either successfully or with an error), these are restored
+/// via the provided restorer, so that each attempt starts from the same
+/// environment. Any errors that arise while spawning a command are returned
+/// immediately without further retrying.
+pub async fn retry<'a, F, S, E, R>(
+    mut cmd_factory: F,
+    policy: RetryPolicy,
+    restorer: &mut R,
+) -> Result<ExitStatus, S::Error>
+where
+    F: FnMut(&mut R) -> S,
+    S: Spawn<E>,
+    E: 'a + ?Sized + FileDescEnvironment + VariableEnvironment,
+    R: ?Sized + RedirectEnvRestorer<'a, E> + VarEnvRestorer<'a, E>,
+{
+    let mut attempt = 1;
+
+    loop {
+        let cmd = cmd_factory(restorer);
+        let ret = match cmd.spawn(restorer.get_mut()).await {
+            Ok(future) => Ok(future.await),
+            Err(e) => Err(e),
+        };
+
+        restorer.restore_vars();
+        restorer.restore_redirects();
+
+        let status = ret?;
+        if status.success() || attempt >= policy.max_attempts {
+            return Ok(status);
+        }
+
+        let delay = policy.delay_before(attempt);
+        if delay > Duration::from_secs(0) {
+            tokio::time::delay_for(delay).await;
+        }
+
+        attempt += 1;
+    }
+}
@@ -1,6 +1,6 @@
 use crate::env::{IsInteractiveEnvironment, LastStatusEnvironment, ReportErrorEnvironment};
 use crate::error::IsFatalError;
-use crate::spawn::swallow_non_fatal_errors;
+use crate::spawn::{swallow_non_fatal_errors, CancelSafe};
 use crate::{ExitStatus, Spawn, EXIT_SUCCESS};
 use futures_core::future::BoxFuture;
 
@@ -62,6 +62,8 @@ pub struct SequenceSlice<'a, S> {
     cmds: &'a [S],
 }
 
+impl<'a, S: CancelSafe> CancelSafe for SequenceSlice<'a, S> {}
+
 impl<'a, S, E> Spawn<E> for SequenceSlice<'a, S>
 where
     S: Send + Sync + Spawn<E>,
@@ -0,0 +1,318 @@
+use crate::env::{
+    ControlFlow, IsInteractiveEnvironment, JobEnvironment, JobId, LastStatusEnvironment,
+    ParseScriptEnvironment, ReportErrorEnvironment, ScriptRunner, ScriptRunnerEnvironment,
+    ShellOptionsEnvironment, Signal, SignalEnvironment, TrapAction,
+};
+use crate::error::{control_flow_for_error, exit_status_for_error, IsFatalError};
+use crate::spawn::{run_pending_traps, run_trap, sequence_exact, Spawn};
+use crate::{ExitStatus, EXIT_SUCCESS};
+use std::fmt;
+use std::iter;
+
+/// A driver for executing batches of commands, one after the other, against
+/// a single environment, e.g. as they are read from a REPL or a network protocol.
+///
+/// Each batch runs just as if all commands were part of the same script: any
+/// changes to the environment (e.g. variables or functions) persist across
+/// batches, and the status of the previous batch is available as `$?`.
+///
+/// When the `conch-parser` feature is enabled, the session can also parse the
+/// source text as it arrives in arbitrary chunks, holding on to any input which
+/// ends in the middle of a command (e.g. an `if` which has yet to see its `fi`)
+/// until the rest of it is fed in.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Session<E> {
+    env: E,
+    /// Any input which does not (yet) form a complete command.
+    pending: String,
+    /// The position (within its batch) of the command currently being executed.
+    executing: Option<usize>,
+}
+
+/// A snapshot of what a `Session` is doing, produced by `Session::dump_state`.
+///
+/// The `Display` implementation renders a single line summary, suitable
+/// for logging when a session appears to be stuck.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionState {
+    /// Whether the session is running in interactive mode.
+    pub interactive: bool,
+    /// The position (within its batch) of the command which was being executed
+    /// when the last call to `Session::execute` was dropped before completing,
+    /// or `None` if the session is idle.
+    pub executing: Option<usize>,
+    /// The number of bytes of pending input which do not yet form a complete command.
+    pub pending_input_len: usize,
+    /// The exit status of the last command the session completed.
+    pub last_status: ExitStatus,
+    /// The ids of any background jobs which have yet to be waited for.
+    pub jobs: Vec<JobId>,
+}
+
+impl fmt::Display for SessionState {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mode = if self.interactive {
+            "interactive"
+        } else {
+            "non-interactive"
+        };
+
+        match self.executing {
+            Some(idx) => write!(fmt, "{} session executing command #{}", mode, idx)?,
+            None => write!(fmt, "idle {} session", mode)?,
+        }
+
+        write!(
+            fmt,
+            ", last status: {}, pending input: {} bytes, jobs: {:?}",
+            self.last_status, self.pending_input_len, self.jobs
+        )
+    }
+}
+
+impl<E> Session<E> {
+    /// Creates a new session which executes commands in the provided environment.
+    pub fn new(env: E) -> Self {
+        Self {
+            env,
+            pending: String::new(),
+            executing: None,
+        }
+    }
+
+    /// Get a reference to the session's environment.
+    pub fn env(&self) -> &E {
+        &self.env
+    }
+
+    /// Get a mutable reference to the session's environment.
+    pub fn env_mut(&mut self) -> &mut E {
+        &mut self.env
+    }
+
+    /// Unwraps the session's environment, discarding any pending input.
+    pub fn into_env(self) -> E {
+        self.env
+    }
+
+    /// Checks if the session's environment is running in interactive mode.
+    pub fn is_interactive(&self) -> bool
+    where
+        E: IsInteractiveEnvironment,
+    {
+        self.env.is_interactive()
+    }
+
+    /// Switches the session between interactive and non-interactive mode,
+    /// e.g. when an embedder hands a session over from a script to a user.
+    ///
+    /// Any pending input is retained, and takes effect with the next batch
+    /// which is executed. An interactive session reports fatal errors instead
+    /// of returning them (see `execute`), and should be prompted for input
+    /// using `$PS1` (or `$PS2` while `is_incomplete`), whereas a non-interactive
+    /// one should not display any prompts. See `ShellOptionsEnvironment::set_interactive`
+    /// for how the environment's options are affected.
+    pub fn set_interactive(&mut self, interactive: bool)
+    where
+        E: ShellOptionsEnvironment,
+    {
+        self.env.set_interactive(interactive)
+    }
+
+    /// Checks if the previously fed input ended in the middle of a command,
+    /// i.e. more input is needed before it can be executed (e.g. a REPL may
+    /// wish to display a continuation prompt like `$PS2`).
+    pub fn is_incomplete(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Get any previously fed input which does not (yet) form a complete command.
+    pub fn pending_input(&self) -> &str {
+        &self.pending
+    }
+
+    /// Discards any pending input (e.g. if the user cancels a multi-line command),
+    /// returning it to the caller.
+    pub fn discard_pending_input(&mut self) -> String {
+        std::mem::take(&mut self.pending)
+    }
+
+    /// Takes a snapshot of what the session is doing, e.g. to diagnose which
+    /// command was hanging after an `execute` call was cancelled (such as by
+    /// a timeout), or which background jobs are still outstanding.
+    pub fn dump_state(&self) -> SessionState
+    where
+        E: IsInteractiveEnvironment + JobEnvironment + LastStatusEnvironment,
+    {
+        SessionState {
+            interactive: self.env.is_interactive(),
+            executing: self.executing,
+            pending_input_len: self.pending.len(),
+            last_status: self.env.last_status(),
+            jobs: self.env.job_ids(),
+        }
+    }
+
+    /// Executes a batch of commands sequentially, and waits for all of them to finish.
+    ///
+    /// All non-fatal errors are reported and swallowed, however, any fatal errors
+    /// are returned to the caller (who may then choose to continue using the session).
+    /// If the session is interactive, fatal errors are also reported and swallowed
+    /// (with the rest of the batch still being executed), just like an interactive
+    /// shell which does not exit on such errors. Otherwise, the environment's last status is updated with the status of the
+    /// batch (i.e. of its last command) before it is returned.
+    ///
+    /// If a command requests to `exit` (i.e. it fails with a `CommandError::Exit`
+    /// error), the rest of the batch is skipped, and the error is returned even if
+    /// the session is interactive, after the environment's last status is updated
+    /// with the requested status (which `exit_status_for_error` also derives).
+    ///
+    /// Any signals delivered to the environment are handled (i.e. their `trap`
+    /// actions are run) after each command of the batch completes.
+    ///
+    /// If the environment does not have a `ScriptRunner` installed, a default one
+    /// (see `ScriptRunner::new`) is installed first, so that the batch can execute
+    /// other scripts (e.g. via the `.` builtin).
+    pub async fn execute<I>(&mut self, batch: I) -> Result<ExitStatus, <I::Item as Spawn<E>>::Error>
+    where
+        I: IntoIterator,
+        I::Item: Spawn<E>,
+        <I::Item as Spawn<E>>::Error: IsFatalError,
+        E: Send
+            + IsInteractiveEnvironment
+            + LastStatusEnvironment
+            + ParseScriptEnvironment
+            + ReportErrorEnvironment
+            + ScriptRunnerEnvironment
+            + SignalEnvironment,
+        E::Command: Send + Sync + Spawn<E>,
+        <E::Command as Spawn<E>>::Error: IsFatalError,
+    {
+        if self.env.script_runner().is_none() {
+            self.env.set_script_runner(ScriptRunner::new());
+        }
+
+        let mut status = EXIT_SUCCESS;
+        for (idx, cmd) in batch.into_iter().enumerate() {
+            self.executing = Some(idx);
+            status = match sequence_exact(iter::once(cmd), &mut self.env).await {
+                Ok(future) => future.await,
+                Err(e) => {
+                    if let Some(ControlFlow::Exit(status)) = control_flow_for_error(&e) {
+                        self.executing = None;
+                        self.env.set_last_status(status);
+                        return Err(e);
+                    }
+
+                    if !self.env.is_interactive() {
+                        self.executing = None;
+                        return Err(e);
+                    }
+
+                    self.env.report_error(&e).await;
+                    exit_status_for_error(&e)
+                }
+            };
+            self.env.set_last_status(status);
+
+            run_pending_traps(&mut self.env).await;
+        }
+
+        self.executing = None;
+        Ok(status)
+    }
+
+    /// Runs the action of the `EXIT` trap (if any), e.g. right before the session
+    /// is discarded, and resets the trap so that its action is only ever run once.
+    ///
+    /// Any signals which were delivered but not yet handled are handled first.
+    pub async fn run_exit_trap(&mut self)
+    where
+        E: LastStatusEnvironment
+            + ParseScriptEnvironment
+            + ReportErrorEnvironment
+            + SignalEnvironment,
+        E::Command: Spawn<E>,
+        <E::Command as Spawn<E>>::Error: IsFatalError,
+    {
+        run_pending_traps(&mut self.env).await;
+        run_trap(Signal::Exit, &mut self.env).await;
+        self.env.set_trap(Signal::Exit, TrapAction::Default);
+    }
+}
+
+#[cfg(feature = "conch-parser")]
+impl<E> Session<E> {
+    /// Feeds another chunk of source text to the session, and parses all commands
+    /// which have become complete (using a default instance of the builder `B`).
+    ///
+    /// Any trailing input which ends in the middle of a command is retained until
+    /// the next chunk is fed in (see `is_incomplete`). On the other hand, if the input
+    /// has a syntax error, it is discarded along with any pending input, and the
+    /// error is returned.
+    ///
+    /// Note that a here-document body is considered complete at the end of the
+    /// input, even if its delimiter has yet to be seen.
+    pub fn parse<B>(
+        &mut self,
+        chunk: &str,
+    ) -> Result<Vec<B::Command>, conch_parser::parse::ParseError<B::Error>>
+    where
+        B: conch_parser::ast::builder::Builder + Default,
+    {
+        use conch_parser::lexer::Lexer;
+        use conch_parser::parse::Parser;
+
+        self.pending.push_str(chunk);
+
+        let mut cmds = Vec::new();
+        let mut consumed = 0;
+        let result = {
+            let lexer = Lexer::new(self.pending.chars());
+            let mut parser = Parser::with_builder(lexer, B::default());
+
+            loop {
+                match parser.complete_command() {
+                    Ok(Some(cmd)) => {
+                        cmds.push(cmd);
+                        consumed = parser.pos().byte;
+                    }
+                    Ok(None) => break Ok(()),
+                    Err(e) => break Err(e),
+                }
+            }
+        };
+
+        match result {
+            Ok(()) => {
+                self.pending.clear();
+                Ok(cmds)
+            }
+            Err(ref e) if is_incomplete(e, self.pending.len()) => {
+                self.pending.drain(..consumed);
+                Ok(cmds)
+            }
+            Err(e) => {
+                self.pending.clear();
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Checks if a parse error was caused by reaching the end of the input,
+/// which could be resolved by appending further input.
+#[cfg(feature = "conch-parser")]
+fn is_incomplete<T>(err: &conch_parser::parse::ParseError<T>, input_len: usize) -> bool {
+    use conch_parser::parse::ParseError;
+
+    match *err {
+        ParseError::UnexpectedEOF | ParseError::Unmatched(_, _) => true,
+        ParseError::IncompleteCmd(_, _, _, keyword_pos) => keyword_pos.byte >= input_len,
+        ParseError::BadFd(_, _)
+        | ParseError::BadIdent(_, _)
+        | ParseError::BadSubst(_, _)
+        | ParseError::Unexpected(_, _)
+        | ParseError::Custom(_) => false,
+    }
+}
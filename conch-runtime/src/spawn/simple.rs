@@ -1,27 +1,35 @@
 use crate::env::builtin::{BuiltinEnvironment, BuiltinUtility};
 use crate::env::{
-    AsyncIoEnvironment, EnvRestorer, ExecutableData, ExecutableEnvironment,
-    ExportedVariableEnvironment, FileDescEnvironment, FileDescOpener, FunctionEnvironment,
-    FunctionFrameEnvironment, RedirectEnvRestorer, SetArgumentsEnvironment,
+    AsyncIoEnvironment, CapabilityEnvironment, ClockEnvironment, CommandReportHandle,
+    CommandSearchEnvironment, CommandTextEnvironment, ControlFlowEnvironment, EnvRestorer,
+    ExecutableData, ExecutableEnvironment, ExecutableStdio, ExecutionReportEnvironment,
+    ExecutionReporter, ExportedVariableEnvironment, FileDescEnvironment, FileDescOpener,
+    FunctionEnvironment, FunctionFrameEnvironment, NextSpawn, RedirectEnvRestorer, RedirectReport,
+    ReportErrorEnvironment, ResolveCommandEnvironment, ResolvedCommand, ResourceUsage,
+    ResourceUsageEnvironment, ResourceUsageTracker, Restorer, ScopedFrame, ScopedRestorer,
+    SetArgumentsEnvironment, ShellOptionsEnvironment, SpawnContext, SpawnMiddlewareEnvironment,
+    StringWrapper, SubstitutionStatusEnvironment, TaintEnvironment, TaintTracker,
     UnsetVariableEnvironment, VarEnvRestorer, WorkingDirectoryEnvironment,
 };
-use crate::error::{CommandError, RedirectionError};
+use crate::error::{CommandError, ErrorContext, RedirectionError};
 use crate::eval::{
-    eval_redirects_or_cmd_words_with_restorer, eval_redirects_or_var_assignments_with_restorer,
+    eval_redirects_or_cmd_words_with_taint, eval_redirects_or_var_assignments_staged,
     EvalRedirectOrCmdWordError, EvalRedirectOrVarAssigError, RedirectEval, RedirectOrCmdWord,
     RedirectOrVarAssig, WordEval,
 };
 use crate::io::FileDescWrapper;
-use crate::spawn::{function_body, Spawn};
-use crate::{
-    ExitStatus, EXIT_CMD_NOT_EXECUTABLE, EXIT_CMD_NOT_FOUND, EXIT_ERROR, EXIT_SUCCESS,
-    STDERR_FILENO, STDIN_FILENO, STDOUT_FILENO,
-};
+use crate::spawn::builtin::format_times;
+use crate::spawn::xtrace::{render_xtrace, write_xtrace, xtrace_fd};
+use crate::spawn::{function_body, ready_status, Spawn};
+use crate::{ExitStatus, EXIT_ERROR, EXIT_SUCCESS, STDERR_FILENO, STDIN_FILENO, STDOUT_FILENO};
 use futures_core::future::BoxFuture;
-use std::borrow::Borrow;
+use std::borrow::{Borrow, Cow};
 use std::collections::VecDeque;
 use std::error::Error;
 use std::ffi::OsStr;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::time::Instant;
 
 /// Spawns a shell command (or function) after applying any redirects and
 /// environment variable assignments.
@@ -36,37 +44,57 @@ where
     R: RedirectEval<E, Handle = E::FileHandle>,
     R::Error: 'static + Error + From<RedirectionError>,
     W: WordEval<E>,
+    W::EvalResult: Send,
     W::Error: 'static + Error,
     E: ?Sized
         + Send
         + Sync
         + AsyncIoEnvironment
         + BuiltinEnvironment<BuiltinName = <E as FunctionEnvironment>::FnName>
+        + CapabilityEnvironment
+        + CommandSearchEnvironment
+        + CommandTextEnvironment
+        + ControlFlowEnvironment
         + ExecutableEnvironment
         + ExportedVariableEnvironment
         + FileDescEnvironment
         + FileDescOpener
         + FunctionEnvironment<Fn = S>
         + FunctionFrameEnvironment
+        + ReportErrorEnvironment
+        + ResolveCommandEnvironment
         + SetArgumentsEnvironment
+        + ShellOptionsEnvironment
+        + SpawnMiddlewareEnvironment
+        + SubstitutionStatusEnvironment
         + UnsetVariableEnvironment
         + WorkingDirectoryEnvironment,
-    E::Builtin: BuiltinUtility<'a, Vec<W::EvalResult>, EnvRestorer<'a, E>, E>,
-    E::Arg: From<W::EvalResult>,
-    E::Args: From<VecDeque<E::Arg>>,
+    E::Builtin: Send + Sync + BuiltinUtility<'a, Vec<W::EvalResult>, EnvRestorer<'a, E>, E>,
+    E::Arg: Send + From<W::EvalResult>,
+    E::Args: Send + From<VecDeque<E::Arg>>,
     E::FileHandle: Send + Sync + Clone + FileDescWrapper + From<E::OpenedFileHandle>,
-    E::FnName: From<W::EvalResult>,
+    E::FnName: Send + Sync + From<W::EvalResult>,
     E::IoHandle: Send + Sync + From<E::FileHandle>,
     E::VarName: Send + Sync + Clone + Borrow<String> + From<V>,
     E::Var: Send + Sync + Clone + Borrow<String> + From<W::EvalResult>,
-    S: Spawn<E> + Clone,
-    S::Error: From<R::Error> + From<W::Error> + From<CommandError> + From<RedirectionError>,
+    S: Send + Sync + Spawn<E> + Clone,
+    S::Error: 'static
+        + Send
+        + Error
+        + From<R::Error>
+        + From<W::Error>
+        + From<CommandError>
+        + From<RedirectionError>,
 {
     simple_command_with_restorer(vars, words, &mut EnvRestorer::new(env)).await
 }
 
 /// Spawns a shell command (or function) after applying any redirects and
 /// environment variable assignments.
+///
+/// All changes are tracked within a new frame of the provided restorer,
+/// thus any changes the restorer has already backed up will be left
+/// intact for the caller to restore.
 pub async fn simple_command_with_restorer<'a, R, V, W, IV, IW, RR, S, E>(
     vars: IV,
     words: IW,
@@ -78,6 +106,7 @@ where
     R: RedirectEval<E, Handle = E::FileHandle>,
     R::Error: 'static + Error + From<RedirectionError>,
     W: WordEval<E>,
+    W::EvalResult: Send,
     W::Error: 'static + Error,
     RR: ?Sized
         + Send
@@ -86,6 +115,7 @@ where
         + FileDescOpener
         + ExportedVariableEnvironment
         + RedirectEnvRestorer<'a, E>
+        + ScopedRestorer<'a, E>
         + VarEnvRestorer<'a, E>,
     RR::FileHandle: From<RR::OpenedFileHandle>,
     RR::IoHandle: Send + From<RR::FileHandle>,
@@ -94,33 +124,279 @@ where
         + Send
         + Sync
         + BuiltinEnvironment<BuiltinName = <E as FunctionEnvironment>::FnName>
+        + CapabilityEnvironment
+        + CommandSearchEnvironment
+        + CommandTextEnvironment
+        + ControlFlowEnvironment
         + ExecutableEnvironment
         + ExportedVariableEnvironment
         + FileDescEnvironment
         + FunctionEnvironment<Fn = S>
         + FunctionFrameEnvironment
+        + ReportErrorEnvironment
+        + ResolveCommandEnvironment
         + SetArgumentsEnvironment
+        + ShellOptionsEnvironment
+        + SpawnMiddlewareEnvironment
+        + SubstitutionStatusEnvironment
         + WorkingDirectoryEnvironment,
-    E::Builtin: BuiltinUtility<'a, Vec<W::EvalResult>, RR, E>,
-    E::Arg: From<W::EvalResult>,
-    E::Args: From<VecDeque<E::Arg>>,
+    E::Builtin: Send + Sync + BuiltinUtility<'a, Vec<W::EvalResult>, RR, E>,
+    E::Arg: Send + From<W::EvalResult>,
+    E::Args: Send + From<VecDeque<E::Arg>>,
     E::FileHandle: Clone + FileDescWrapper,
-    E::FnName: From<W::EvalResult>,
-    E::VarName: Borrow<String> + From<V>,
-    E::Var: Borrow<String> + From<W::EvalResult>,
-    S: Spawn<E> + Clone,
-    S::Error: From<R::Error> + From<W::Error> + From<CommandError> + From<RedirectionError>,
+    E::FnName: Send + Sync + From<W::EvalResult>,
+    E::VarName: Clone + Borrow<String> + From<V>,
+    E::Var: Clone + Borrow<String> + From<W::EvalResult>,
+    S: Send + Sync + Spawn<E> + Clone,
+    S::Error: 'static
+        + Send
+        + Error
+        + From<R::Error>
+        + From<W::Error>
+        + From<CommandError>
+        + From<RedirectionError>,
+{
+    let mut frame = ScopedFrame::new(restorer);
+
+    // NB: the outer command is no longer the one executing while we
+    // evaluate (and run) this one, and it should be restored even if
+    // we get cancelled
+    let prev_text = frame.get_mut().set_command_text(None);
+    let mut scope = CommandScope {
+        restorer: frame,
+        prev_text,
+        timer: None,
+    };
+
+    let ret =
+        do_simple_command_with_restorer(vars, words, &mut *scope.restorer, &mut scope.timer).await;
+
+    // NB: the timed command has been spawned, so the outer tracker should
+    // record the usage of any commands which follow it
+    let timer = scope.timer.take().map(|mut timer| {
+        timer.restore_usage_tracker(scope.restorer.get_mut());
+        timer
+    });
+
+    // NB: pop the command's frame so that its times are reported
+    // to the shell's standard error, not wherever it redirected its own
+    drop(scope);
+
+    let timer = match timer {
+        Some(timer) => timer,
+        None => return ret,
+    };
+
+    let status = ret?.await;
+    let times = timer.format_times(restorer.get().monotonic_now());
+
+    let stderr = restorer
+        .get()
+        .file_desc(STDERR_FILENO)
+        .filter(|(_, perms)| perms.writable())
+        .map(|(fdes, _)| fdes.clone());
+
+    if let Some(fdes) = stderr {
+        let _ = restorer
+            .write_all(fdes.into(), Cow::Owned(times.into_bytes()))
+            .await;
+    }
+
+    Ok(ready_status(status))
+}
+
+/// Restores the previous command text and resource usage tracker
+/// (and then pops the restorer's frame) when dropped.
+struct CommandScope<'r, 'a, RR, E>
+where
+    RR: ?Sized + ScopedRestorer<'a, E>,
+    E: ?Sized + CommandTextEnvironment + ResourceUsageEnvironment,
+{
+    restorer: ScopedFrame<'r, 'a, RR, E>,
+    prev_text: Option<String>,
+    timer: Option<CommandTimer>,
+}
+
+impl<'r, 'a, RR, E> Drop for CommandScope<'r, 'a, RR, E>
+where
+    RR: ?Sized + ScopedRestorer<'a, E>,
+    E: ?Sized + CommandTextEnvironment + ResourceUsageEnvironment,
+{
+    fn drop(&mut self) {
+        let prev_text = self.prev_text.take();
+        let env = self.restorer.get_mut();
+        env.set_command_text(prev_text);
+
+        if let Some(mut timer) = self.timer.take() {
+            timer.restore_usage_tracker(env);
+        }
+    }
+}
+
+/// Times a command executed in place of a builtin like `time`, so that the time
+/// and resources it used can be reported once it completes.
+struct CommandTimer {
+    started: Instant,
+    usage: ResourceUsageTracker,
+    /// The tracker which should be restored once the command has been spawned.
+    prev_usage: Option<ResourceUsageTracker>,
+}
+
+impl CommandTimer {
+    /// Starts timing a command (using the environment's clock), and installs a new
+    /// tracker (nested within the environment's current one, if any) to record
+    /// the resources it uses.
+    fn start<E: ?Sized + ClockEnvironment + ResourceUsageEnvironment>(env: &mut E) -> Self {
+        let usage = match env.usage_tracker() {
+            Some(outer) => outer.nested(),
+            None => ResourceUsageTracker::new(),
+        };
+
+        Self {
+            started: env.monotonic_now(),
+            prev_usage: env.set_usage_tracker(Some(usage.clone())),
+            usage,
+        }
+    }
+
+    fn restore_usage_tracker<E: ?Sized + ResourceUsageEnvironment>(&mut self, env: &mut E) {
+        env.set_usage_tracker(self.prev_usage.take());
+    }
+
+    /// Formats the times of the command, assuming it completed at `now`.
+    fn format_times(&self, now: Instant) -> String {
+        format_times(
+            now.saturating_duration_since(self.started),
+            &self.usage.usage(),
+        )
+    }
+}
+
+/// Pops the environment's innermost error context and restores any
+/// replaced execution reporter when dropped.
+struct FunctionScope<'a, E: ?Sized + ExecutionReportEnvironment + ReportErrorEnvironment> {
+    env: &'a mut E,
+    prev_reporter: Option<Option<ExecutionReporter>>,
+}
+
+impl<'a, E> Drop for FunctionScope<'a, E>
+where
+    E: ?Sized + ExecutionReportEnvironment + ReportErrorEnvironment,
+{
+    fn drop(&mut self) {
+        self.env.pop_error_context();
+        if let Some(prev) = self.prev_reporter.take() {
+            self.env.set_execution_reporter(prev);
+        }
+    }
+}
+
+/// Restores the environment's previous taint tracker (if it was replaced) when dropped.
+struct TaintScope<'r, 'a, RR, E>
+where
+    RR: ?Sized + Restorer<'a, E>,
+    E: ?Sized + TaintEnvironment,
+{
+    restorer: &'r mut RR,
+    prev_tracker: Option<TaintTracker>,
+    env: PhantomData<&'a E>,
+}
+
+impl<'r, 'a, RR, E> Drop for TaintScope<'r, 'a, RR, E>
+where
+    RR: ?Sized + Restorer<'a, E>,
+    E: ?Sized + TaintEnvironment,
+{
+    fn drop(&mut self) {
+        if let Some(prev) = self.prev_tracker.take() {
+            self.restorer.get_mut().set_taint_tracker(prev);
+        }
+    }
+}
+
+/// Finds the index of the command name a builtin like `command` should execute
+/// in its place, if it was invoked with one (and without any options).
+fn command_operand_index<W: StringWrapper>(words: &[W]) -> Option<usize> {
+    match words.first().map(StringWrapper::as_str) {
+        Some("--") if words.len() > 1 => Some(1),
+        Some(word) if word.starts_with('-') => None,
+        Some(_) => Some(0),
+        None => None,
+    }
+}
+
+/// Finds the index of the command name a builtin like `time` should execute
+/// in its place, if it was invoked with one (and without any options but `-p`).
+fn time_operand_index<W: StringWrapper>(words: &[W]) -> Option<usize> {
+    let skip = match words.first().map(StringWrapper::as_str) {
+        Some("-p") => 1,
+        _ => 0,
+    };
+
+    command_operand_index(&words[skip..]).map(|index| index + skip)
+}
+
+/// Records the completion of the spawned command (if it is being reported).
+fn finish_report(
+    future: BoxFuture<'static, ExitStatus>,
+    report: Option<CommandReportHandle>,
+) -> BoxFuture<'static, ExitStatus> {
+    match report {
+        None => future,
+        Some(report) => Box::pin(async move {
+            let status = future.await;
+            report.finish(status);
+            status
+        }),
+    }
+}
+
+/// Records the completion of the spawned executable (if it is being reported),
+/// along with the resources it used (if they are being tracked).
+fn finish_with_usage(
+    future: BoxFuture<'static, (ExitStatus, Option<ResourceUsage>)>,
+    report: Option<CommandReportHandle>,
+    tracker: Option<ResourceUsageTracker>,
+) -> BoxFuture<'static, ExitStatus> {
+    Box::pin(async move {
+        let (status, usage) = future.await;
+
+        if let (Some(tracker), Some(usage)) = (tracker, usage) {
+            tracker.record(usage);
+        }
+
+        if let Some(report) = report {
+            report.finish_with_usage(status, usage);
+        }
+
+        status
+    })
+}
+
+/// Resolves to the spawned command's status future (or that of `EXIT_ERROR`
+/// if it could not be spawned), so that it can be passed through any
+/// middleware, while holding on to any error for the caller to return.
+async fn capture_spawn_error<F, ERR>(
+    spawn: F,
+    error: &mut Option<ERR>,
+) -> BoxFuture<'static, ExitStatus>
+where
+    F: Future<Output = Result<BoxFuture<'static, ExitStatus>, ERR>>,
 {
-    let ret = do_simple_command_with_restorer(vars, words, restorer).await;
-    restorer.restore_vars();
-    restorer.restore_redirects();
-    ret
+    match spawn.await {
+        Ok(future) => future,
+        Err(e) => {
+            *error = Some(e);
+            ready_status(EXIT_ERROR)
+        }
+    }
 }
 
 async fn do_simple_command_with_restorer<'a, R, V, W, IV, IW, RR, S, E>(
     vars: IV,
     mut words: IW,
     restorer: &mut RR,
+    timer: &mut Option<CommandTimer>,
 ) -> Result<BoxFuture<'static, ExitStatus>, S::Error>
 where
     IV: Iterator<Item = RedirectOrVarAssig<R, V, W>>,
@@ -128,6 +404,7 @@ where
     R: RedirectEval<E, Handle = E::FileHandle>,
     R::Error: 'static + Error + From<RedirectionError>,
     W: WordEval<E>,
+    W::EvalResult: Send,
     W::Error: 'static + Error,
     RR: ?Sized
         + Send
@@ -136,6 +413,7 @@ where
         + FileDescOpener
         + ExportedVariableEnvironment
         + RedirectEnvRestorer<'a, E>
+        + ScopedRestorer<'a, E>
         + VarEnvRestorer<'a, E>,
     RR::FileHandle: From<RR::OpenedFileHandle>,
     RR::IoHandle: Send + From<RR::FileHandle>,
@@ -144,23 +422,46 @@ where
         + Send
         + Sync
         + BuiltinEnvironment<BuiltinName = <E as FunctionEnvironment>::FnName>
+        + CapabilityEnvironment
+        + CommandSearchEnvironment
+        + CommandTextEnvironment
+        + ControlFlowEnvironment
         + ExecutableEnvironment
         + ExportedVariableEnvironment
         + FileDescEnvironment
         + FunctionEnvironment<Fn = S>
         + FunctionFrameEnvironment
+        + ReportErrorEnvironment
+        + ResolveCommandEnvironment
         + SetArgumentsEnvironment
+        + ShellOptionsEnvironment
+        + SpawnMiddlewareEnvironment
+        + SubstitutionStatusEnvironment
         + WorkingDirectoryEnvironment,
-    E::Builtin: BuiltinUtility<'a, Vec<W::EvalResult>, RR, E>,
-    E::Arg: From<W::EvalResult>,
-    E::Args: From<VecDeque<E::Arg>>,
+    E::Builtin: Send + Sync + BuiltinUtility<'a, Vec<W::EvalResult>, RR, E>,
+    E::Arg: Send + From<W::EvalResult>,
+    E::Args: Send + From<VecDeque<E::Arg>>,
     E::FileHandle: Clone + FileDescWrapper,
-    E::FnName: From<W::EvalResult>,
-    E::VarName: Borrow<String> + From<V>,
-    E::Var: Borrow<String> + From<W::EvalResult>,
-    S: Spawn<E> + Clone,
-    S::Error: From<R::Error> + From<W::Error> + From<CommandError> + From<RedirectionError>,
+    E::FnName: Send + Sync + From<W::EvalResult>,
+    E::VarName: Clone + Borrow<String> + From<V>,
+    E::Var: Clone + Borrow<String> + From<W::EvalResult>,
+    S: Send + Sync + Spawn<E> + Clone,
+    S::Error: 'static
+        + Send
+        + Error
+        + From<R::Error>
+        + From<W::Error>
+        + From<CommandError>
+        + From<RedirectionError>,
 {
+    // NB: trace the command to the shell's standard error,
+    // not wherever the command redirects its own
+    let xtrace_fd = xtrace_fd(restorer.get());
+
+    // NB: only substitutions performed by this command should determine its
+    // status if it turns out to have no command name
+    restorer.get_mut().set_last_substitution_status(None);
+
     // Any other redirects encountered before we found a command word
     let mut other_redirects = Vec::new();
     let mut first_word = None;
@@ -186,126 +487,318 @@ where
     let vars = vars.chain(other_redirects.into_iter());
     let words = first_word.into_iter().chain(words);
 
-    eval_redirects_or_var_assignments_with_restorer(export_vars, vars, restorer)
+    // NB: builtins run with a tainted tracker if anything they were
+    // invoked with (including their assignments) was derived from tainted input
+    let taint_tracker = restorer.get().taint_tracker().clone();
+    let taint_before = taint_tracker.expansions();
+
+    // NB: stage the changes so that they are only applied if all of them
+    // are successfully evaluated, even if we get cancelled part way through
+    let changes = eval_redirects_or_var_assignments_staged(export_vars, vars, restorer)
         .await
         .map_err(|e| match e {
             EvalRedirectOrVarAssigError::Redirect(e) => S::Error::from(e),
             EvalRedirectOrVarAssigError::VarAssig(e) => S::Error::from(e),
         })?;
+    changes.apply_taint(restorer.get_mut());
+    changes.apply(restorer);
 
-    let mut words = eval_redirects_or_cmd_words_with_restorer(restorer, words)
+    let (mut words, mut tainted) = eval_redirects_or_cmd_words_with_taint(restorer, words)
         .await
         .map_err(|e| match e {
             EvalRedirectOrCmdWordError::Redirect(e) => S::Error::from(e),
             EvalRedirectOrCmdWordError::CmdWord(e) => S::Error::from(e),
-        })?;
+        })?
+        .into_parts();
+    let command_tainted = taint_tracker.is_tainted_since(taint_before);
 
-    let cmd_name = if words.is_empty() {
+    let mut cmd_name = if words.is_empty() {
         // "Empty" command which is probably just assigning variables.
         // Any redirect side effects have already been applied, but ensure
         // we keep the actual variable values.
         restorer.clear_vars();
-        return Ok(Box::pin(async { EXIT_SUCCESS }));
+
+        // Per POSIX, the command completes with the status of the
+        // last command substitution it performed (if any)
+        let status = restorer
+            .get()
+            .last_substitution_status()
+            .unwrap_or(EXIT_SUCCESS);
+        return Ok(Box::pin(async move { status }));
     } else {
         words.remove(0)
     };
+    let mut name_tainted = tainted.remove(0);
 
-    {
-        let cmd_name = cmd_name.clone().into();
-        let env = restorer.get_mut();
+    let text = {
+        let words = Some(&cmd_name)
+            .into_iter()
+            .chain(&words)
+            .map(StringWrapper::as_str)
+            .collect::<Vec<_>>();
 
-        if let Some(func) = env.function(&cmd_name).cloned() {
-            let args = words.into_iter().map(Into::into).collect();
-            return Ok(function_body(func, args, env).await?);
-        } else if let Some(builtin) = env.builtin(&cmd_name) {
-            return Ok(builtin.spawn_builtin(words, restorer).await);
+        if let Some(fd) = xtrace_fd {
+            let line = render_xtrace(&words, restorer.get());
+            write_xtrace(fd, line, restorer).await;
         }
-    }
 
-    // FIXME: inherit all open file descriptors on UNIX systems
-    let (stdin, stdout, stderr) = {
-        let env = restorer.get();
-        (
-            env.file_desc(STDIN_FILENO).map(|(fdes, _)| fdes).cloned(),
-            env.file_desc(STDOUT_FILENO).map(|(fdes, _)| fdes).cloned(),
-            env.file_desc(STDERR_FILENO).map(|(fdes, _)| fdes).cloned(),
-        )
+        words.join(" ")
     };
+    restorer.get_mut().set_command_text(Some(text));
 
-    // Now that we've got all the redirections we care about having the
-    // child inherit, we can do the environment cleanup right now.
-    restorer.restore_redirects();
-
-    // Now that we've restore the environment's redirects, hopefully most of
-    // the Rc/Arc counts should be just one here and we can cheaply unwrap
-    // the handles. Otherwise, we're forced to duplicate the actual handle
-    // (which is a pretty unfortunate "limitation" of std::process::Command)
-    let get_io = move |fd, fdes: Option<E::FileHandle>| match fdes {
-        None => Ok(None),
-        Some(fdes_wrapper) => match fdes_wrapper.try_unwrap() {
-            Ok(fdes) => Ok(Some(fdes)),
-            Err(err) => {
-                let msg = format!("file descriptor {}", fd);
-                Err(RedirectionError::Io(err, Some(msg)))
-            }
-        },
-    };
+    let report = restorer.get().execution_reporter().map(|reporter| {
+        let argv = Some(&cmd_name)
+            .into_iter()
+            .chain(&words)
+            .map(|word| word.as_str().to_owned())
+            .collect();
 
-    let env = restorer.get();
-    let args = words
-        .iter()
-        .map(|a| OsStr::new(a.borrow()))
-        .collect::<Vec<_>>();
-    let env_vars = env
-        .env_vars()
-        .iter()
-        .map(|&(ref key, ref val)| {
-            let key = OsStr::new((*key).borrow());
-            let val = OsStr::new((*val).borrow());
-            (key, val)
-        })
-        .collect::<Vec<_>>();
-
-    let cur_dir = env.current_working_dir().to_path_buf();
-
-    let data = ExecutableData {
-        name: OsStr::new(cmd_name.borrow()),
-        args: &args,
-        env_vars: &env_vars,
-        current_dir: &cur_dir,
-        stdin: get_io(STDIN_FILENO, stdin)?,
-        stdout: get_io(STDOUT_FILENO, stdout)?,
-        stderr: get_io(STDERR_FILENO, stderr)?,
+        let env = restorer.get();
+        let redirects = restorer
+            .redirected_fds()
+            .into_iter()
+            .map(|fd| RedirectReport {
+                fd,
+                permissions: env.file_desc(fd).map(|(_, perms)| perms),
+            })
+            .collect();
+
+        reporter.start_command(argv, redirects)
+    });
+
+    // NB: middleware only sees the command after all of its words have been expanded
+    let middleware = restorer.get().spawn_middleware().to_vec();
+    let cmd = if middleware.is_empty() {
+        None
+    } else {
+        let argv = Some(&cmd_name)
+            .into_iter()
+            .chain(&words)
+            .map(|word| word.as_str().to_owned())
+            .collect();
+
+        Some(SpawnContext { argv })
     };
 
-    let child = env.spawn_executable(data);
+    let spawn = async move {
+        {
+            // NB: something like `command name args...` executes `name` in place
+            // of the builtin, but without considering any shell functions, while
+            // something like `time name args...` executes `name` and times it
+            let mut bypass_functions = false;
+            let resolved = loop {
+                let name = cmd_name.clone().into();
+                let env = restorer.get_mut();
 
-    // Once the child is fully bootstrapped (and we are no longer borrowing
-    // env vars) we can do the var cleanup.
-    restorer.restore_vars();
+                let resolved = match env.resolve_command(&name) {
+                    ResolvedCommand::Function(_) if bypass_functions => match env.builtin(&name) {
+                        Some(builtin) => ResolvedCommand::Builtin(builtin),
+                        None => ResolvedCommand::External,
+                    },
+                    resolved => resolved,
+                };
 
-    match child {
-        Ok(ret) => Ok(ret),
-        Err(e) => {
-            if let Some(e) = find_root_cause(&e).downcast_ref::<CommandError>() {
-                let status = match e {
-                    CommandError::NotExecutable(_) => EXIT_CMD_NOT_EXECUTABLE,
-                    CommandError::NotFound(_) => EXIT_CMD_NOT_FOUND,
-                    CommandError::Io(_, _) => EXIT_ERROR,
+                let times = match resolved {
+                    ResolvedCommand::Builtin(ref builtin) => builtin.times_command(),
+                    _ => false,
+                };
+
+                let operand = match resolved {
+                    ResolvedCommand::Builtin(ref builtin) if builtin.bypasses_functions() => {
+                        command_operand_index(&words)
+                    }
+                    ResolvedCommand::Builtin(_) if times => time_operand_index(&words),
+                    _ => None,
                 };
 
-                Ok(Box::pin(async move { status }))
-            } else {
-                Err(S::Error::from(e))
+                match operand {
+                    Some(index) => {
+                        cmd_name = words.drain(..=index).next_back().expect("missing operand");
+                        name_tainted = tainted
+                            .drain(..=index)
+                            .next_back()
+                            .expect("missing operand");
+
+                        if !times {
+                            bypass_functions = true;
+                        } else if timer.is_none() {
+                            *timer = Some(CommandTimer::start(env));
+                        }
+                    }
+                    None => break resolved,
+                }
+            };
+
+            let cmd_name_str = cmd_name.as_str().to_owned();
+            let env = restorer.get_mut();
+
+            match resolved {
+                ResolvedCommand::Function(func) => {
+                    let context = ErrorContext::Function(cmd_name_str);
+                    let args = words.into_iter().map(Into::into).collect();
+
+                    // Any commands the function runs are reported as nested within this one
+                    let prev_reporter = report
+                        .as_ref()
+                        .map(|report| env.set_execution_reporter(Some(report.nested_reporter())));
+
+                    env.push_error_context(context);
+                    let scope = FunctionScope { env, prev_reporter };
+                    let ret = function_body(func, args, &mut *scope.env).await;
+                    drop(scope);
+
+                    return Ok(finish_report(ret?, report));
+                }
+                ResolvedCommand::Builtin(builtin) => {
+                    if let Some(status) = builtin.trivial_status() {
+                        restorer.restore_vars();
+                        restorer.restore_redirects();
+                        return Ok(finish_report(ready_status(status), report));
+                    }
+
+                    // NB: `command` prevents errors in special builtins from being fatal
+                    let fatal = builtin.is_special()
+                        && !bypass_functions
+                        && env.options().fatal_special_builtin_errors;
+                    let prev_tracker = if command_tainted {
+                        Some(env.set_taint_tracker(env.taint_tracker().nested_tainted()))
+                    } else {
+                        None
+                    };
+                    let scope = TaintScope {
+                        restorer: &mut *restorer,
+                        prev_tracker,
+                        env: PhantomData,
+                    };
+                    let ret = builtin.spawn_builtin(words, &mut *scope.restorer).await;
+                    drop(scope);
+
+                    // NB: builtins like `return` and `exit` cannot fail the command
+                    // themselves, so we unwind on their behalf
+                    if let Some(flow) = restorer.get_mut().take_control_flow() {
+                        finish_report(ret, report).await;
+                        return Err(CommandError::from(flow).into());
+                    }
+
+                    if !fatal {
+                        return Ok(finish_report(ret, report));
+                    }
+
+                    // NB: the builtin has already reported its error by now,
+                    // we just need to make sure the script does not continue
+                    let status = finish_report(ret, report).await;
+                    return if status.success() {
+                        Ok(Box::pin(async move { status }))
+                    } else {
+                        Err(CommandError::SpecialBuiltin(cmd_name_str, status).into())
+                    };
+                }
+                ResolvedCommand::External => {}
             }
         }
-    }
-}
 
-fn find_root_cause<'a>(mut err: &'a (dyn Error + 'static)) -> &'a (dyn Error + 'static) {
-    while let Some(e) = err.source() {
-        err = e;
-    }
+        // FIXME: inherit all open file descriptors on UNIX systems
+        let (stdin, stdout, stderr) = {
+            let env = restorer.get();
+            (
+                env.file_desc(STDIN_FILENO).map(|(fdes, _)| fdes).cloned(),
+                env.file_desc(STDOUT_FILENO).map(|(fdes, _)| fdes).cloned(),
+                env.file_desc(STDERR_FILENO).map(|(fdes, _)| fdes).cloned(),
+            )
+        };
+
+        // Now that we've got all the redirections we care about having the
+        // child inherit, we can do the environment cleanup right now.
+        restorer.restore_redirects();
+
+        // Now that we've restore the environment's redirects, hopefully most of
+        // the Rc/Arc counts should be just one here and we can cheaply unwrap
+        // the handles. Otherwise, we're forced to duplicate the actual handle
+        // (which is a pretty unfortunate "limitation" of std::process::Command)
+        let get_io = move |fd, fdes: Option<E::FileHandle>| match fdes {
+            None => Ok(ExecutableStdio::Null),
+            Some(fdes_wrapper) => fdes_wrapper
+                .try_unwrap()
+                .map(ExecutableStdio::Piped)
+                .map_err(|err| RedirectionError::fd_io(err, fd)),
+        };
+
+        // NB: search with any `$PATH` the command itself may have assigned
+        let path = restorer.get_mut().search_command(cmd_name.as_str());
 
-    err
+        let env = restorer.get();
+        let args = words
+            .iter()
+            .map(|a| OsStr::new(a.borrow()))
+            .collect::<Vec<_>>();
+        let env_vars = env
+            .env_vars()
+            .iter()
+            .map(|&(ref key, ref val)| {
+                let key = OsStr::new((*key).borrow());
+                let val = OsStr::new((*val).borrow());
+                (key, val)
+            })
+            .collect::<Vec<_>>();
+
+        let cur_dir = env.current_working_dir().to_path_buf();
+
+        let data = ExecutableData {
+            name: OsStr::new(cmd_name.borrow()),
+            path: path.as_deref(),
+            args: &args,
+            env_vars: &env_vars,
+            current_dir: &cur_dir,
+            stdin: get_io(STDIN_FILENO, stdin)?,
+            stdout: get_io(STDOUT_FILENO, stdout)?,
+            stderr: get_io(STDERR_FILENO, stderr)?,
+            name_tainted,
+            args_tainted: &tainted,
+        };
+
+        // NB: only collect resource usage if anyone is around to record it
+        let tracker = env.usage_tracker().cloned();
+        let (child, report) = if report.is_none() && tracker.is_none() {
+            (env.spawn_executable(data), None)
+        } else {
+            match env.spawn_executable_with_usage(data) {
+                Ok(future) => (Ok(finish_with_usage(future, report, tracker)), None),
+                Err(e) => (Err(e), report),
+            }
+        };
+
+        // Once the child is fully bootstrapped (and we are no longer borrowing
+        // env vars) we can do the var cleanup.
+        restorer.restore_vars();
+
+        match child {
+            Ok(ret) => Ok(finish_report(ret, report)),
+            Err(e @ CommandError::NotFound(_)) | Err(e @ CommandError::NotExecutable(_)) => {
+                let status = e.exit_status();
+                Ok(finish_report(Box::pin(async move { status }), report))
+            }
+            // NB: any other errors are reported by the caller, which should
+            // determine the command's exit status via `exit_status_for_error`
+            Err(e) => Err(S::Error::from(e)),
+        }
+    };
+
+    let cmd = match cmd {
+        Some(cmd) => cmd,
+        None => return spawn.await,
+    };
+
+    let mut spawn_error = None;
+    let next: NextSpawn<'_> = Box::pin(capture_spawn_error(spawn, &mut spawn_error));
+
+    let future = middleware
+        .iter()
+        .rev()
+        .fold(next, |next, middleware| middleware.wrap(&cmd, next))
+        .await;
+
+    match spawn_error {
+        Some(e) => Err(e),
+        None => Ok(future),
+    }
 }
@@ -1,32 +1,137 @@
-use crate::env::{ReportErrorEnvironment, SubEnvironment};
-use crate::{ExitStatus, Spawn, EXIT_ERROR};
+use crate::env::{
+    ExportedVariableEnvironment, ReportErrorEnvironment, SubEnvironment, UnsetVariableEnvironment,
+};
+use crate::error::{control_flow_for_error, exit_status_for_error, ErrorContext};
+use crate::{ExitStatus, Spawn};
 use std::error::Error;
 use std::future::Future;
 
 /// Spawns anything as if running in a subshell environment.
 ///
 /// The `env` parameter will be copied as a `SubEnvironment`, in whose context
-/// the commands will be executed.
+/// the commands will be executed. Any requests to `return` or `exit` only
+/// complete the subshell (with the requested status), instead of the
+/// function or shell which spawned it.
 pub fn subshell<S, E>(spawn: S, env: &E) -> impl Future<Output = ExitStatus>
 where
     S: Spawn<E>,
     S::Error: 'static + Send + Sync + Error,
     E: ReportErrorEnvironment + SubEnvironment,
 {
-    subshell_with_env(spawn, env.sub_env())
+    subshell_with_env(spawn, env.sub_env(), ErrorContext::Subshell)
 }
 
-pub(crate) async fn subshell_with_env<S, E>(spawn: S, mut env: E) -> ExitStatus
+/// Spawns anything as if running in a subshell environment, exactly like `subshell`,
+/// but once the subshell completes, any changes chosen by `merge` are merged from
+/// the subshell environment back into `env`.
+///
+/// Since subshells are emulated (rather than forked), this allows capturing select
+/// state (e.g. a specific variable) without exposing the parent environment to any
+/// other side effects of the subshell.
+pub async fn subshell_with_merge<S, E, M>(spawn: S, env: &mut E, merge: M) -> ExitStatus
+where
+    S: Spawn<E>,
+    S::Error: 'static + Send + Sync + Error,
+    E: ReportErrorEnvironment + SubEnvironment,
+    M: SubshellMerge<E>,
+{
+    let mut sub_env = env.sub_env();
+    let status = spawn_in_env(spawn, &mut sub_env, ErrorContext::Subshell).await;
+
+    merge.merge(env, &sub_env);
+    status
+}
+
+/// An interface for merging chosen changes from a (completed) subshell
+/// environment back into its parent environment.
+///
+/// Implemented for any `FnOnce(&mut E, &E)` closure, which receives the
+/// parent and subshell environments, respectively.
+pub trait SubshellMerge<E: ?Sized> {
+    /// Merges any chosen changes from `subshell` into `parent`.
+    fn merge(self, parent: &mut E, subshell: &E);
+}
+
+impl<E, F> SubshellMerge<E> for F
+where
+    E: ?Sized,
+    F: FnOnce(&mut E, &E),
+{
+    fn merge(self, parent: &mut E, subshell: &E) {
+        self(parent, subshell)
+    }
+}
+
+/// A `SubshellMerge` implementation which merges the values (and exported status)
+/// of specific variables from a subshell back into its parent.
+///
+/// Any variables which were unset by the subshell are also unset in the parent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeVars<N> {
+    names: Vec<N>,
+}
+
+impl<N> MergeVars<N> {
+    /// Creates a new instance which merges the specified variables.
+    pub fn new<I: IntoIterator<Item = N>>(names: I) -> Self {
+        Self {
+            names: names.into_iter().collect(),
+        }
+    }
+}
+
+impl<E> SubshellMerge<E> for MergeVars<E::VarName>
+where
+    E: ?Sized + ExportedVariableEnvironment + UnsetVariableEnvironment,
+    E::Var: Clone + PartialEq,
+{
+    fn merge(self, parent: &mut E, subshell: &E) {
+        for name in self.names {
+            match subshell.exported_var(&name) {
+                Some((val, exported)) => {
+                    if parent.exported_var(&name) != Some((val, exported)) {
+                        parent.set_exported_var(name, val.clone(), exported);
+                    }
+                }
+                None => {
+                    if parent.exported_var(&name).is_some() {
+                        parent.unset_var(&name);
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub(crate) async fn subshell_with_env<S, E>(
+    spawn: S,
+    mut env: E,
+    context: ErrorContext,
+) -> ExitStatus
 where
     S: Spawn<E>,
     S::Error: 'static + Send + Sync + Error,
     E: ReportErrorEnvironment,
 {
-    match spawn.spawn(&mut env).await {
+    spawn_in_env(spawn, &mut env, context).await
+}
+
+async fn spawn_in_env<S, E>(spawn: S, env: &mut E, context: ErrorContext) -> ExitStatus
+where
+    S: Spawn<E>,
+    S::Error: 'static + Send + Sync + Error,
+    E: ReportErrorEnvironment,
+{
+    env.push_error_context(context);
+
+    match spawn.spawn(env).await {
         Ok(future) => future.await,
-        Err(e) => {
-            env.report_error(&e).await;
-            EXIT_ERROR
-        }
+        Err(e) => match control_flow_for_error(&e) {
+            Some(flow) => flow.status(),
+            None => {
+                env.report_error(&e).await;
+                exit_status_for_error(&e)
+            }
+        },
     }
 }
@@ -2,16 +2,89 @@ use crate::env::{
     AsyncIoEnvironment, FileDescEnvironment, FileDescOpener, Pipe, ReportErrorEnvironment,
     SubEnvironment,
 };
+use crate::error::ErrorContext;
 use crate::io::Permissions;
 use crate::spawn::subshell::subshell_with_env;
-use crate::{Spawn, STDOUT_FILENO};
-use std::borrow::Cow;
+use crate::{ExitStatus, Spawn, STDOUT_FILENO};
 use std::error::Error;
 use std::future::Future;
 use std::io;
 
 /// Spawns something whose standard output will be captured (and trailing newlines trimmed).
 pub fn substitution<S, E>(spawn: S, env: &E) -> impl Future<Output = Result<String, S::Error>>
+where
+    S: Spawn<E>,
+    S::Error: 'static + Send + Sync + From<io::Error> + Error,
+    E: AsyncIoEnvironment
+        + FileDescEnvironment
+        + FileDescOpener
+        + ReportErrorEnvironment
+        + SubEnvironment,
+    E::FileHandle: From<E::OpenedFileHandle>,
+    E::IoHandle: From<E::OpenedFileHandle>,
+{
+    let future = substitution_with_status(spawn, env);
+    async move { future.await.map(|(output, _)| output) }
+}
+
+/// Spawns something whose standard output will be captured (and trailing newlines trimmed),
+/// along with the exit status it completed with.
+///
+/// Any output which is not valid UTF-8 is converted lossily (i.e. invalid sequences are
+/// replaced with `U+FFFD REPLACEMENT CHARACTER`), see `substitution_bytes_with_status`
+/// for capturing the output as is.
+pub fn substitution_with_status<S, E>(
+    spawn: S,
+    env: &E,
+) -> impl Future<Output = Result<(String, ExitStatus), S::Error>>
+where
+    S: Spawn<E>,
+    S::Error: 'static + Send + Sync + From<io::Error> + Error,
+    E: AsyncIoEnvironment
+        + FileDescEnvironment
+        + FileDescOpener
+        + ReportErrorEnvironment
+        + SubEnvironment,
+    E::FileHandle: From<E::OpenedFileHandle>,
+    E::IoHandle: From<E::OpenedFileHandle>,
+{
+    let future = substitution_bytes_with_status(spawn, env);
+    async move {
+        let (buf, status) = future.await?;
+        let ret = String::from_utf8(buf)
+            .unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned());
+
+        Ok((ret, status))
+    }
+}
+
+/// Spawns something whose standard output will be captured as raw bytes (and trailing
+/// newlines trimmed), e.g. for commands which may emit output that is not valid UTF-8.
+pub fn substitution_bytes<S, E>(
+    spawn: S,
+    env: &E,
+) -> impl Future<Output = Result<Vec<u8>, S::Error>>
+where
+    S: Spawn<E>,
+    S::Error: 'static + Send + Sync + From<io::Error> + Error,
+    E: AsyncIoEnvironment
+        + FileDescEnvironment
+        + FileDescOpener
+        + ReportErrorEnvironment
+        + SubEnvironment,
+    E::FileHandle: From<E::OpenedFileHandle>,
+    E::IoHandle: From<E::OpenedFileHandle>,
+{
+    let future = substitution_bytes_with_status(spawn, env);
+    async move { future.await.map(|(output, _)| output) }
+}
+
+/// Spawns something whose standard output will be captured as raw bytes (and trailing
+/// newlines trimmed), along with the exit status it completed with.
+pub fn substitution_bytes_with_status<S, E>(
+    spawn: S,
+    env: &E,
+) -> impl Future<Output = Result<(Vec<u8>, ExitStatus), S::Error>>
 where
     S: Spawn<E>,
     S::Error: 'static + Send + Sync + From<io::Error> + Error,
@@ -34,9 +107,9 @@ where
         env.set_file_desc(STDOUT_FILENO, cmd_stdout_fd, Permissions::Write);
 
         let output = env.read_all(cmd_output.into());
-        let cmd = subshell_with_env(spawn, env);
+        let cmd = subshell_with_env(spawn, env, ErrorContext::CommandSubstitution);
 
-        let (buf, _) = futures_util::join!(output, cmd);
+        let (buf, status) = futures_util::join!(output, cmd);
         let mut buf = buf?;
 
         while Some(&b'\n') == buf.last() {
@@ -46,11 +119,6 @@ where
             }
         }
 
-        let ret = match String::from_utf8_lossy(&buf) {
-            Cow::Owned(s) => s,
-            Cow::Borrowed(_) => unsafe { String::from_utf8_unchecked(buf) },
-        };
-
-        Ok(ret)
+        Ok((buf, status))
     }
 }
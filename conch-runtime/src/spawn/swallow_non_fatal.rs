@@ -1,10 +1,11 @@
 use crate::env::ReportErrorEnvironment;
-use crate::error::IsFatalError;
-use crate::{ExitStatus, Spawn, EXIT_ERROR};
+use crate::error::{exit_status_for_error, IsFatalError};
+use crate::{ExitStatus, Spawn};
 use futures_core::future::BoxFuture;
 
 /// Spawns a command and swallow (and report) all non-fatal errors
-/// and resolve to `EXIT_ERROR` if they arise.
+/// and resolve to an appropriate exit status (see `exit_status_for_error`)
+/// if they arise.
 ///
 /// All other responses are propagated through as is.
 pub async fn swallow_non_fatal_errors<S, E>(
@@ -16,12 +17,13 @@ where
     S::Error: IsFatalError,
     E: ?Sized + ReportErrorEnvironment,
 {
-    cmd.spawn(env).await.or_else(|e| {
-        if e.is_fatal() {
-            Err(e)
-        } else {
-            env.report_error(&e);
-            Ok(Box::pin(async { EXIT_ERROR }))
+    match cmd.spawn(env).await {
+        Ok(future) => Ok(future),
+        Err(e) if e.is_fatal() => Err(e),
+        Err(e) => {
+            env.report_error(&e).await;
+            let status = exit_status_for_error(&e);
+            Ok(Box::pin(async move { status }))
         }
-    })
+    }
 }
@@ -0,0 +1,69 @@
+use crate::env::{
+    LastStatusEnvironment, ParseScriptEnvironment, ReportErrorEnvironment, Signal,
+    SignalEnvironment, TrapAction,
+};
+use crate::error::IsFatalError;
+use crate::spawn::sequence_exact;
+use crate::Spawn;
+
+/// Runs the actions of any traps whose signals have been delivered to the
+/// environment (e.g. via a `SignalSender`) since this was last called.
+///
+/// Signals whose traps have not been set to run a command are discarded.
+/// See `run_trap` for how each action is run.
+pub async fn run_pending_traps<E>(env: &mut E)
+where
+    E: ?Sized
+        + LastStatusEnvironment
+        + ParseScriptEnvironment
+        + ReportErrorEnvironment
+        + SignalEnvironment,
+    E::Command: Spawn<E>,
+    <E::Command as Spawn<E>>::Error: IsFatalError,
+{
+    for signal in env.take_pending_signals() {
+        run_trap(signal, env).await;
+    }
+}
+
+/// Runs the action of the trap for a signal, if it has been set to run a command.
+///
+/// The action is parsed and run in the current environment (e.g. any variables
+/// it sets remain in effect), although the value of `$?` is restored once it
+/// completes. Any errors (including parsing the action) are reported and swallowed.
+///
+/// For example, the `EXIT` trap can be run just before the shell exits via
+/// `run_trap(Signal::Exit, env)`.
+pub async fn run_trap<E>(signal: Signal, env: &mut E)
+where
+    E: ?Sized
+        + LastStatusEnvironment
+        + ParseScriptEnvironment
+        + ReportErrorEnvironment
+        + SignalEnvironment,
+    E::Command: Spawn<E>,
+    <E::Command as Spawn<E>>::Error: IsFatalError,
+{
+    let cmds = match env.trap(signal) {
+        TrapAction::Command(action) => env.parse_script(action),
+        TrapAction::Default | TrapAction::Ignore => return,
+    };
+
+    let cmds = match cmds {
+        Ok(cmds) => cmds,
+        Err(e) => {
+            env.report_error(&*e).await;
+            return;
+        }
+    };
+
+    let last_status = env.last_status();
+    match sequence_exact(cmds, env).await {
+        Ok(future) => {
+            future.await;
+        }
+        Err(e) => env.report_error(&e).await,
+    }
+
+    env.set_last_status(last_status);
+}
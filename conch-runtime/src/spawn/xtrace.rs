@@ -0,0 +1,126 @@
+use crate::env::{
+    AsyncIoEnvironment, FileDescEnvironment, ShellOptionsEnvironment, VariableEnvironment,
+};
+use crate::eval::quote_for_shell;
+use crate::STDERR_FILENO;
+use std::borrow::{Borrow, Cow};
+
+/// The prefix of `xtrace` output if `$PS4` is unset.
+const PS4_DEFAULT: &str = "+ ";
+
+/// Get the handle to which `xtrace` output should be written, or `None` if
+/// the `xtrace` option is disabled (or standard error is not writable).
+///
+/// The handle should be retrieved before applying any redirects for the
+/// traced command so that its trace is written to the shell's standard error.
+pub(crate) fn xtrace_fd<E>(env: &E) -> Option<E::FileHandle>
+where
+    E: ?Sized + FileDescEnvironment + ShellOptionsEnvironment,
+    E::FileHandle: Clone,
+{
+    if !env.options().xtrace {
+        return None;
+    }
+
+    match env.file_desc(STDERR_FILENO) {
+        Some((fdes, perms)) if perms.writable() => Some(fdes.clone()),
+        _ => None,
+    }
+}
+
+/// Renders a trace of the provided (already expanded) words prefixed by the expansion of `$PS4`.
+///
+/// Only variable references (e.g. `$VAR` or `${VAR}`) within `$PS4` are expanded,
+/// and any words with special characters are quoted so the trace can be re-run.
+pub(crate) fn render_xtrace<E>(words: &[&str], env: &E) -> String
+where
+    E: ?Sized + VariableEnvironment,
+    E::VarName: Borrow<String>,
+    E::Var: Borrow<String>,
+{
+    let lookup = |name: &str| env.var(&name.to_owned()).map(|val| val.borrow().clone());
+
+    let mut line = match lookup("PS4") {
+        Some(ps4) => expand_vars(&ps4, lookup),
+        None => PS4_DEFAULT.to_owned(),
+    };
+
+    let words = words
+        .iter()
+        .copied()
+        .map(quote_for_shell)
+        .collect::<Vec<_>>();
+
+    line.push_str(&words.join(" "));
+    line.push('\n');
+    line
+}
+
+/// Writes a rendered trace to the provided handle, ignoring any errors.
+pub(crate) async fn write_xtrace<E>(fd: E::FileHandle, line: String, env: &mut E)
+where
+    E: ?Sized + AsyncIoEnvironment + FileDescEnvironment,
+    E::IoHandle: From<E::FileHandle>,
+{
+    let _ = env
+        .write_all(fd.into(), Cow::Owned(line.into_bytes()))
+        .await;
+}
+
+fn expand_vars<F>(s: &str, lookup: F) -> String
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let is_name_char = |c: char| c == '_' || c.is_ascii_alphanumeric();
+
+    let mut ret = String::with_capacity(s.len());
+    let mut rest = s;
+
+    while let Some(idx) = rest.find('$') {
+        ret.push_str(&rest[..idx]);
+        rest = &rest[idx + 1..];
+
+        let (name, remaining) = if rest.starts_with('{') {
+            match rest.find('}') {
+                Some(end) => (&rest[1..end], &rest[end + 1..]),
+                None => ("", rest),
+            }
+        } else {
+            let end = rest.find(|c| !is_name_char(c)).unwrap_or(rest.len());
+            (&rest[..end], &rest[end..])
+        };
+
+        if name.is_empty() || !name.chars().all(is_name_char) {
+            // Not a variable reference, keep the literal dollar sign around
+            ret.push('$');
+            continue;
+        }
+
+        ret.push_str(&lookup(name).unwrap_or_default());
+        rest = remaining;
+    }
+
+    ret.push_str(rest);
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::expand_vars;
+
+    #[test]
+    fn test_expand_vars() {
+        let lookup = |name: &str| match name {
+            "foo" => Some("FOO".to_owned()),
+            "bar_1" => Some("BAR".to_owned()),
+            _ => None,
+        };
+
+        assert_eq!(expand_vars("+ ", lookup), "+ ");
+        assert_eq!(expand_vars("$foo+ ", lookup), "FOO+ ");
+        assert_eq!(expand_vars("${bar_1}x$foo.", lookup), "BARxFOO.");
+        assert_eq!(expand_vars("$missing!", lookup), "!");
+        assert_eq!(expand_vars("$ $} ${} ${foo", lookup), "$ $} ${} ${foo");
+        assert_eq!(expand_vars("trailing $", lookup), "trailing $");
+    }
+}
@@ -3,6 +3,8 @@
 use std::io::{Error, ErrorKind, Result};
 
 pub mod io;
+pub(crate) mod rlimit;
+pub(crate) mod rusage;
 
 pub(crate) trait IsMinusOne {
     fn is_minus_one(&self) -> bool;
@@ -138,12 +138,6 @@ impl RawIo {
         Ok(n as u64)
     }
 
-    // NB: Linux platforms which support opening a file with O_CLOEXEC won't
-    // use this function, so we can suppress the dead_code lint
-    #[cfg_attr(
-        any(target_os = "linux", target_os = "android", target_os = "emscripten"),
-        allow(dead_code)
-    )]
     /// Sets the `CLOEXEC` flag on the descriptor to the desired state
     pub fn set_cloexec(&self, set: bool) -> Result<()> {
         unsafe {
@@ -157,6 +151,12 @@ impl RawIo {
         }
     }
 
+    /// Checks whether the `CLOEXEC` flag is set on the descriptor.
+    pub fn is_cloexec(&self) -> Result<bool> {
+        let flags = cvt_r(|| unsafe { libc::fcntl(self.fd, libc::F_GETFD) })?;
+        Ok(flags & libc::FD_CLOEXEC != 0)
+    }
+
     /// Sets the `O_NONBLOCK` flag on the descriptor to the desired state.
     ///
     /// Requires a mutable handle so that blocking state is not unexpectedly
@@ -172,6 +172,65 @@ impl RawIo {
             cvt_r(|| libc::fcntl(self.fd, libc::F_SETFL, new_flags)).map(|_| ())
         }
     }
+
+    /// Checks whether the underlying file descriptor refers to a terminal.
+    pub fn is_terminal(&self) -> bool {
+        unsafe { libc::isatty(self.fd) == 1 }
+    }
+
+    fn termios(&self) -> Result<libc::termios> {
+        unsafe {
+            let mut termios = mem::zeroed();
+            cvt_r(|| libc::tcgetattr(self.fd, &mut termios))?;
+            Ok(termios)
+        }
+    }
+
+    /// Returns the `(canonical, echo)` state of the terminal.
+    pub fn terminal_mode(&self) -> Result<(bool, bool)> {
+        let termios = self.termios()?;
+        let canonical = termios.c_lflag & libc::ICANON != 0;
+        let echo = termios.c_lflag & libc::ECHO != 0;
+        Ok((canonical, echo))
+    }
+
+    /// Sets the canonical (line buffered) and echo state of the terminal.
+    pub fn set_terminal_mode(&self, canonical: bool, echo: bool) -> Result<()> {
+        let mut termios = self.termios()?;
+
+        for &(flag, set) in &[(libc::ICANON, canonical), (libc::ECHO, echo)] {
+            if set {
+                termios.c_lflag |= flag;
+            } else {
+                termios.c_lflag &= !flag;
+            }
+        }
+
+        if !canonical {
+            // Make reads return as soon as a single byte is available
+            termios.c_cc[libc::VMIN] = 1;
+            termios.c_cc[libc::VTIME] = 0;
+        }
+
+        cvt_r(|| unsafe { libc::tcsetattr(self.fd, libc::TCSANOW, &termios) }).map(|_| ())
+    }
+
+    /// Queries the type of the file the descriptor refers to (e.g. `S_IFIFO`).
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn file_type(&self) -> Result<libc::mode_t> {
+        let mut stat: libc::stat = unsafe { mem::zeroed() };
+        cvt_r(|| unsafe { libc::fstat(self.fd, &mut stat) })?;
+        Ok(stat.st_mode & libc::S_IFMT)
+    }
+
+    /// Returns the `(columns, lines)` of the terminal window.
+    pub fn window_size(&self) -> Result<(u16, u16)> {
+        unsafe {
+            let mut size: libc::winsize = mem::zeroed();
+            cvt_r(|| libc::ioctl(self.fd, libc::TIOCGWINSZ, &mut size))?;
+            Ok((size.ws_col, size.ws_row))
+        }
+    }
 }
 
 impl Drop for RawIo {
@@ -229,6 +288,52 @@ pub fn pipe() -> Result<(RawIo, RawIo)> {
     }
 }
 
+/// Creates and returns a `(reader, writer)` pipe pair whose kernel buffer can hold
+/// at least `capacity` bytes, and which operates in "packet mode" (where each write
+/// is read back as a separate packet) if requested.
+///
+/// Either option is silently ignored if unsupported by the platform (or the running kernel).
+pub fn pipe_with_options(capacity: Option<usize>, packet_mode: bool) -> Result<(RawIo, RawIo)> {
+    let (reader, writer) = if packet_mode { packet_pipe()? } else { pipe()? };
+
+    if let Some(capacity) = capacity {
+        set_pipe_capacity(&writer, capacity);
+    }
+
+    Ok((reader, writer))
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn packet_pipe() -> Result<(RawIo, RawIo)> {
+    unsafe {
+        let mut fds = [0; 2];
+        match cvt_r(|| libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC | libc::O_DIRECT)) {
+            Ok(_) => Ok((RawIo::new(fds[0]), RawIo::new(fds[1]))),
+            // Kernels prior to 3.4 do not support packet mode
+            Err(ref e) if e.raw_os_error() == Some(libc::EINVAL) => pipe(),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn packet_pipe() -> Result<(RawIo, RawIo)> {
+    pipe()
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn set_pipe_capacity(pipe: &RawIo, capacity: usize) {
+    use std::convert::TryFrom;
+
+    let capacity = libc::c_int::try_from(capacity).unwrap_or(libc::c_int::MAX);
+    // Failures (e.g. exceeding the limit for unprivileged users) are deliberately
+    // ignored, the pipe simply retains its current capacity.
+    let _ = cvt_r(|| unsafe { libc::fcntl(pipe.fd, libc::F_SETPIPE_SZ, capacity) });
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn set_pipe_capacity(_pipe: &RawIo, _capacity: usize) {}
+
 /// Duplicates file descriptors for (stdin, stdout, stderr) and returns them in that order.
 pub fn dup_stdio() -> Result<(RawIo, RawIo, RawIo)> {
     unsafe {
@@ -240,6 +345,64 @@ pub fn dup_stdio() -> Result<(RawIo, RawIo, RawIo)> {
     }
 }
 
+/// Copies data between two file descriptors without passing it through userspace,
+/// using `splice(2)` if either descriptor is a pipe, or `sendfile(2)` if reading
+/// from a regular file.
+///
+/// Returns the number of bytes copied, and whether the end of the input was reached.
+/// If the kernel is unable to copy between the descriptors, the copy stops early so
+/// that the caller can finish it (from the current offsets) through regular IO.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn copy_in_kernel(from: &RawIo, to: &RawIo) -> Result<(u64, bool)> {
+    use std::ptr;
+
+    const CHUNK_SIZE: usize = 64 * 1024;
+
+    let use_splice = from.file_type()? == libc::S_IFIFO || to.file_type()? == libc::S_IFIFO;
+    if !use_splice && from.file_type()? != libc::S_IFREG {
+        return Ok((0, false));
+    }
+
+    let mut copied = 0;
+    loop {
+        let ret = cvt_r(|| unsafe {
+            if use_splice {
+                libc::splice(
+                    from.fd,
+                    ptr::null_mut(),
+                    to.fd,
+                    ptr::null_mut(),
+                    CHUNK_SIZE,
+                    0,
+                )
+            } else {
+                libc::sendfile(to.fd, from.fd, ptr::null_mut(), CHUNK_SIZE)
+            }
+        });
+
+        match ret {
+            Ok(0) => return Ok((copied, true)),
+            Ok(n) => copied += n as u64,
+            Err(e) => match e.raw_os_error() {
+                Some(libc::EINVAL)
+                | Some(libc::ENOSYS)
+                | Some(libc::EXDEV)
+                | Some(libc::EOPNOTSUPP) => return Ok((copied, false)),
+                _ => return Err(e),
+            },
+        }
+    }
+}
+
+/// Copies data between two file descriptors without passing it through userspace.
+///
+/// This platform offers no such facility, so nothing is copied and the caller
+/// is expected to perform the entire copy through regular IO.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub fn copy_in_kernel(_from: &RawIo, _to: &RawIo) -> Result<(u64, bool)> {
+    Ok((0, false))
+}
+
 /// Returns the process ID of the calling process
 pub fn getpid() -> libc::pid_t {
     unsafe { libc::getpid() }
@@ -0,0 +1,80 @@
+//! Wrappers around `getrlimit(2)` and `setrlimit(2)`.
+
+use super::cvt_r;
+use crate::env::{Resource, ResourceLimit};
+use std::io::Result;
+
+/// Get the limit of a resource for the current process.
+pub(crate) fn get_rlimit(resource: Resource) -> Result<ResourceLimit> {
+    let mut rlim = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+
+    let resource = raw_resource(resource)?;
+    cvt_r(|| unsafe { libc::getrlimit(resource, &mut rlim) })?;
+
+    Ok(ResourceLimit {
+        soft: from_raw_limit(rlim.rlim_cur),
+        hard: from_raw_limit(rlim.rlim_max),
+    })
+}
+
+/// Change the limit of a resource for the current process.
+///
+/// Does not allocate, thus it is safe to call between forking and exec-ing a child.
+pub(crate) fn set_rlimit(resource: Resource, limit: ResourceLimit) -> Result<()> {
+    let rlim = libc::rlimit {
+        rlim_cur: to_raw_limit(limit.soft),
+        rlim_max: to_raw_limit(limit.hard),
+    };
+
+    let resource = raw_resource(resource)?;
+    cvt_r(|| unsafe { libc::setrlimit(resource, &rlim) })?;
+    Ok(())
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+type RawResource = libc::__rlimit_resource_t;
+#[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+type RawResource = libc::c_int;
+
+fn raw_resource(resource: Resource) -> Result<RawResource> {
+    let raw = match resource {
+        Resource::CoreFileSize => libc::RLIMIT_CORE,
+        Resource::DataSize => libc::RLIMIT_DATA,
+        Resource::FileSize => libc::RLIMIT_FSIZE,
+        Resource::OpenFiles => libc::RLIMIT_NOFILE,
+        Resource::StackSize => libc::RLIMIT_STACK,
+        Resource::CpuTime => libc::RLIMIT_CPU,
+        Resource::VirtualMemory => libc::RLIMIT_AS,
+        #[cfg(not(any(target_os = "solaris", target_os = "illumos")))]
+        Resource::Processes => libc::RLIMIT_NPROC,
+        #[cfg(any(target_os = "solaris", target_os = "illumos"))]
+        Resource::Processes => {
+            let msg = "limiting the number of processes is not supported on this platform";
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, msg));
+        }
+    };
+
+    Ok(raw)
+}
+
+// NB: `rlim_t` is not a `u64` on all platforms
+#[allow(clippy::unnecessary_cast)]
+fn from_raw_limit(raw: libc::rlim_t) -> Option<u64> {
+    if raw == libc::RLIM_INFINITY {
+        None
+    } else {
+        Some(raw as u64)
+    }
+}
+
+#[allow(clippy::unnecessary_cast)]
+fn to_raw_limit(limit: Option<u64>) -> libc::rlim_t {
+    match limit {
+        // NB: clamp any limits which cannot be represented to "unlimited"
+        Some(limit) if limit < libc::RLIM_INFINITY as u64 => limit as libc::rlim_t,
+        _ => libc::RLIM_INFINITY,
+    }
+}
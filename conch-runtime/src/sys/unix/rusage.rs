@@ -0,0 +1,31 @@
+//! Wrappers around `getrusage(2)`.
+
+use super::cvt_r;
+use crate::env::ProcessTimes;
+use std::io::Result;
+use std::mem::MaybeUninit;
+use std::time::Duration;
+
+/// Get the CPU time used by the current process, and by all of its children
+/// which have been reaped.
+pub(crate) fn process_times() -> Result<ProcessTimes> {
+    let shell = getrusage(libc::RUSAGE_SELF)?;
+    let children = getrusage(libc::RUSAGE_CHILDREN)?;
+
+    Ok(ProcessTimes {
+        user_time: timeval_to_duration(shell.ru_utime),
+        system_time: timeval_to_duration(shell.ru_stime),
+        children_user_time: timeval_to_duration(children.ru_utime),
+        children_system_time: timeval_to_duration(children.ru_stime),
+    })
+}
+
+fn getrusage(who: libc::c_int) -> Result<libc::rusage> {
+    let mut usage = MaybeUninit::<libc::rusage>::zeroed();
+    cvt_r(|| unsafe { libc::getrusage(who, usage.as_mut_ptr()) })?;
+    Ok(unsafe { usage.assume_init() })
+}
+
+pub(crate) fn timeval_to_duration(time: libc::timeval) -> Duration {
+    Duration::from_secs(time.tv_sec as u64) + Duration::from_micros(time.tv_usec as u64)
+}
@@ -3,6 +3,7 @@
 use std::io::{Error, Result};
 
 pub mod io;
+pub mod job;
 
 pub(crate) trait IsZero {
     fn is_zero(&self) -> bool;
@@ -3,6 +3,7 @@
 use crate::io::FileDesc;
 use crate::sys::cvt;
 use crate::IntoInner;
+use std::convert::TryFrom;
 use std::fs::File;
 use std::io::{ErrorKind, Result, SeekFrom};
 use std::mem;
@@ -10,13 +11,20 @@ use std::os::windows::io::{AsRawHandle, FromRawHandle, IntoRawHandle, RawHandle}
 use std::process::Stdio;
 use std::ptr;
 use winapi::shared::minwindef::{DWORD, FALSE, LPVOID};
+use winapi::um::consoleapi::{GetConsoleMode, SetConsoleMode};
 use winapi::um::fileapi::{ReadFile, SetFilePointerEx, WriteFile};
-use winapi::um::handleapi::{CloseHandle, DuplicateHandle, INVALID_HANDLE_VALUE};
+use winapi::um::handleapi::{
+    CloseHandle, DuplicateHandle, GetHandleInformation, SetHandleInformation, INVALID_HANDLE_VALUE,
+};
 use winapi::um::namedpipeapi::CreatePipe;
 use winapi::um::processenv::GetStdHandle;
 use winapi::um::processthreadsapi::{GetCurrentProcess, GetCurrentProcessId};
 use winapi::um::winbase::{
-    FILE_BEGIN, FILE_CURRENT, FILE_END, STD_ERROR_HANDLE, STD_INPUT_HANDLE, STD_OUTPUT_HANDLE,
+    FILE_BEGIN, FILE_CURRENT, FILE_END, HANDLE_FLAG_INHERIT, STD_ERROR_HANDLE, STD_INPUT_HANDLE,
+    STD_OUTPUT_HANDLE,
+};
+use winapi::um::wincon::{
+    GetConsoleScreenBufferInfo, CONSOLE_SCREEN_BUFFER_INFO, ENABLE_ECHO_INPUT, ENABLE_LINE_INPUT,
 };
 use winapi::um::winnt::{DUPLICATE_SAME_ACCESS, LARGE_INTEGER};
 
@@ -110,6 +118,28 @@ impl RawIo {
         }
     }
 
+    /// Sets the HANDLE to be inherited by child processes if `set` is `false`.
+    pub fn set_cloexec(&self, set: bool) -> Result<()> {
+        let flags = if set { 0 } else { HANDLE_FLAG_INHERIT };
+        unsafe {
+            cvt(SetHandleInformation(
+                self.inner(),
+                HANDLE_FLAG_INHERIT,
+                flags,
+            ))
+            .map(|_| ())
+        }
+    }
+
+    /// Checks whether the HANDLE will *not* be inherited by child processes.
+    pub fn is_cloexec(&self) -> Result<bool> {
+        let mut flags = 0;
+        unsafe {
+            cvt(GetHandleInformation(self.inner(), &mut flags))?;
+        }
+        Ok(flags & HANDLE_FLAG_INHERIT == 0)
+    }
+
     /// Reads from the underlying HANDLE.
     // Taken from rust: libstd/sys/windows/handle.rs
     pub fn read_inner(&self, buf: &mut [u8]) -> Result<usize> {
@@ -174,6 +204,51 @@ impl RawIo {
             Ok(*newpos.QuadPart() as u64)
         }
     }
+
+    /// Checks whether the underlying HANDLE refers to a console.
+    pub fn is_terminal(&self) -> bool {
+        let mut mode = 0;
+        unsafe { GetConsoleMode(self.inner(), &mut mode) != 0 }
+    }
+
+    /// Returns the `(canonical, echo)` state of the console.
+    pub fn terminal_mode(&self) -> Result<(bool, bool)> {
+        let mut mode = 0;
+        cvt(unsafe { GetConsoleMode(self.inner(), &mut mode) })?;
+
+        let canonical = mode & ENABLE_LINE_INPUT != 0;
+        let echo = mode & ENABLE_ECHO_INPUT != 0;
+        Ok((canonical, echo))
+    }
+
+    /// Sets the line input and echo state of the console.
+    pub fn set_terminal_mode(&self, canonical: bool, echo: bool) -> Result<()> {
+        let mut mode = 0;
+        cvt(unsafe { GetConsoleMode(self.inner(), &mut mode) })?;
+
+        for &(flag, set) in &[(ENABLE_LINE_INPUT, canonical), (ENABLE_ECHO_INPUT, echo)] {
+            if set {
+                mode |= flag;
+            } else {
+                mode &= !flag;
+            }
+        }
+
+        cvt(unsafe { SetConsoleMode(self.inner(), mode) }).map(|_| ())
+    }
+
+    /// Returns the `(columns, lines)` of the visible console window.
+    pub fn window_size(&self) -> Result<(u16, u16)> {
+        unsafe {
+            let mut info: CONSOLE_SCREEN_BUFFER_INFO = mem::zeroed();
+            cvt(GetConsoleScreenBufferInfo(self.inner(), &mut info))?;
+
+            let window = info.srWindow;
+            let columns = window.Right - window.Left + 1;
+            let lines = window.Bottom - window.Top + 1;
+            Ok((columns as u16, lines as u16))
+        }
+    }
 }
 
 impl Drop for RawIo {
@@ -195,6 +270,23 @@ pub fn pipe() -> Result<(RawIo, RawIo)> {
     }
 }
 
+/// Creates and returns a `(reader, writer)` pipe pair whose buffer can hold
+/// at least `capacity` bytes.
+///
+/// Packet mode is not supported on this platform and is silently ignored.
+pub fn pipe_with_options(capacity: Option<usize>, _packet_mode: bool) -> Result<(RawIo, RawIo)> {
+    let size = capacity.map_or(0, |capacity| {
+        DWORD::try_from(capacity).unwrap_or(DWORD::MAX)
+    });
+
+    unsafe {
+        let mut reader = INVALID_HANDLE_VALUE;
+        let mut writer = INVALID_HANDLE_VALUE;
+        cvt(CreatePipe(&mut reader, &mut writer, ptr::null_mut(), size))?;
+        Ok((RawIo::new(reader), RawIo::new(writer)))
+    }
+}
+
 /// Duplicates file HANDLES for (stdin, stdout, stderr) and returns them in that order.
 pub fn dup_stdio() -> Result<(RawIo, RawIo, RawIo)> {
     fn dup_handle(handle: DWORD) -> Result<RawIo> {
@@ -223,6 +315,14 @@ pub fn dup_stdio() -> Result<(RawIo, RawIo, RawIo)> {
     ))
 }
 
+/// Copies data between two handles without passing it through userspace.
+///
+/// This platform offers no such facility, so nothing is copied and the caller
+/// is expected to perform the entire copy through regular IO.
+pub fn copy_in_kernel(_from: &RawIo, _to: &RawIo) -> Result<(u64, bool)> {
+    Ok((0, false))
+}
+
 /// Retrieves the process identifier of the calling process.
 pub fn getpid() -> DWORD {
     unsafe { GetCurrentProcessId() }
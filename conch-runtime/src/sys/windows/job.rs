@@ -0,0 +1,144 @@
+//! Defines a wrapper around Windows Job Objects for grouping process trees.
+
+use crate::sys::cvt;
+use crate::sys::io::RawIo;
+use std::io::{Error, ErrorKind, Result};
+use std::mem;
+use std::ptr;
+use winapi::shared::minwindef::{DWORD, FALSE, LPVOID};
+use winapi::um::jobapi2::{
+    AssignProcessToJobObject, CreateJobObjectW, SetInformationJobObject, TerminateJobObject,
+};
+use winapi::um::processthreadsapi::OpenProcess;
+use winapi::um::winnt::{
+    JobObjectCpuRateControlInformation, JobObjectExtendedLimitInformation,
+    JOBOBJECT_CPU_RATE_CONTROL_INFORMATION, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+    JOB_OBJECT_CPU_RATE_CONTROL_ENABLE, JOB_OBJECT_CPU_RATE_CONTROL_HARD_CAP,
+    JOB_OBJECT_LIMIT_JOB_MEMORY, JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+    JOB_OBJECT_LIMIT_PROCESS_MEMORY, PROCESS_SET_QUOTA, PROCESS_TERMINATE,
+};
+
+/// Resource limits which can be applied to all processes within a `JobObject`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct JobLimits {
+    /// The maximum amount of memory (in bytes) which can be committed by
+    /// all processes in the job combined.
+    pub job_memory: Option<usize>,
+    /// The maximum amount of memory (in bytes) which can be committed by
+    /// any single process in the job.
+    pub process_memory: Option<usize>,
+    /// The maximum percentage of CPU time (between 1 and 100, inclusive) which
+    /// can be used by all processes in the job combined.
+    pub cpu_rate_percent: Option<u8>,
+}
+
+/// A Windows Job Object which groups spawned processes (and all of their
+/// descendants) so that entire process trees can be terminated together.
+///
+/// All processes in the job are terminated once the job is dropped (i.e. once
+/// the last environment sharing it has been dropped), or explicitly via
+/// `JobObject::terminate`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct JobObject {
+    handle: RawIo,
+}
+
+impl JobObject {
+    /// Creates a new (anonymous) job without any resource limits.
+    pub fn new() -> Result<Self> {
+        let job = unsafe {
+            let handle = cvt(CreateJobObjectW(ptr::null_mut(), ptr::null()))?;
+            JobObject {
+                handle: RawIo::new(handle),
+            }
+        };
+
+        job.set_extended_limits(&JobLimits::default())?;
+        Ok(job)
+    }
+
+    /// Replaces the resource limits of all processes in the job.
+    pub fn set_limits(&self, limits: &JobLimits) -> Result<()> {
+        self.set_extended_limits(limits)?;
+        self.set_cpu_rate(limits.cpu_rate_percent)
+    }
+
+    fn set_extended_limits(&self, limits: &JobLimits) -> Result<()> {
+        unsafe {
+            let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = mem::zeroed();
+            // NB: always kill the entire process tree once the job is closed
+            info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+
+            if let Some(memory) = limits.job_memory {
+                info.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_JOB_MEMORY;
+                info.JobMemoryLimit = memory;
+            }
+
+            if let Some(memory) = limits.process_memory {
+                info.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_PROCESS_MEMORY;
+                info.ProcessMemoryLimit = memory;
+            }
+
+            cvt(SetInformationJobObject(
+                self.handle.inner(),
+                JobObjectExtendedLimitInformation,
+                &mut info as *mut _ as LPVOID,
+                mem::size_of_val(&info) as DWORD,
+            ))
+            .map(|_| ())
+        }
+    }
+
+    fn set_cpu_rate(&self, percent: Option<u8>) -> Result<()> {
+        unsafe {
+            let mut info: JOBOBJECT_CPU_RATE_CONTROL_INFORMATION = mem::zeroed();
+
+            if let Some(percent) = percent {
+                if percent == 0 || percent > 100 {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        "CPU rate must be between 1 and 100 percent",
+                    ));
+                }
+
+                info.ControlFlags =
+                    JOB_OBJECT_CPU_RATE_CONTROL_ENABLE | JOB_OBJECT_CPU_RATE_CONTROL_HARD_CAP;
+                // NB: the rate is specified in hundredths of a percent
+                *info.u.CpuRate_mut() = DWORD::from(percent) * 100;
+            }
+
+            cvt(SetInformationJobObject(
+                self.handle.inner(),
+                JobObjectCpuRateControlInformation,
+                &mut info as *mut _ as LPVOID,
+                mem::size_of_val(&info) as DWORD,
+            ))
+            .map(|_| ())
+        }
+    }
+
+    /// Adds the process with the specified ID to the job.
+    ///
+    /// Any processes it subsequently spawns will also become part of the job.
+    pub fn assign(&self, pid: u32) -> Result<()> {
+        unsafe {
+            let process = cvt(OpenProcess(
+                PROCESS_SET_QUOTA | PROCESS_TERMINATE,
+                FALSE,
+                pid,
+            ))?;
+            let process = RawIo::new(process);
+
+            cvt(AssignProcessToJobObject(
+                self.handle.inner(),
+                process.inner(),
+            ))
+            .map(|_| ())
+        }
+    }
+
+    /// Terminates all processes in the job with the specified exit code.
+    pub fn terminate(&self, exit_code: u32) -> Result<()> {
+        unsafe { cvt(TerminateJobObject(self.handle.inner(), exit_code)).map(|_| ()) }
+    }
+}
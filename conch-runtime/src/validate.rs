@@ -0,0 +1,303 @@
+//! Defines methods for statically validating commands before executing them.
+//!
+//! Validating a script walks its entire command tree without evaluating any words
+//! or running any commands (and thus without any side effects), checking for
+//! issues which are sure to cause problems once the script is executed, for
+//! example, invoking commands which do not exist. This allows embedders to
+//! pre-flight scripts before handing them off to be spawned.
+//!
+//! Since the actual behavior of a script can only be known by running it, any
+//! checks err on the side of caution: an issue is only reported if nothing in
+//! the script could possibly avoid it (e.g. by assigning a variable or defining
+//! a function, no matter where that happens in the script).
+
+use crate::env::{
+    complete_command, BuiltinEnvironment, FileDescEnvironment, FunctionEnvironment,
+    ShellOptionsEnvironment, StringWrapper, VariableEnvironment, WorkingDirectoryEnvironment,
+};
+use crate::io::Permissions;
+use crate::Fd;
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::rc::Rc;
+use std::sync::Arc;
+
+#[cfg(feature = "conch-parser")]
+pub mod ast_impl;
+
+/// An issue discovered while validating a command, which would (likely)
+/// result in an error if the command were to be executed.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ValidationIssue {
+    /// A parameter is used while the `nounset` option is enabled, but it is not
+    /// set in the environment, nor is it ever assigned by the script.
+    #[error("{0}: parameter not set")]
+    UnsetParameter(String),
+    /// Input is redirected from a file descriptor which is not open for reading.
+    #[error("{0}: file descriptor not open for reading")]
+    UnreadableFileDesc(Fd),
+    /// Output is redirected to a file descriptor which is not open for writing.
+    #[error("{0}: file descriptor not open for writing")]
+    UnwritableFileDesc(Fd),
+    /// A command is neither a function, a builtin utility, nor an executable
+    /// found in any of the `$PATH` directories.
+    #[error("{0}: command not found")]
+    UnknownCommand(String),
+}
+
+/// The names of any builtin utilities whose effects on the environment cannot be
+/// determined without running them.
+const OPAQUE_COMMANDS: &[&str] = &[".", "eval", "source"];
+
+/// Tracks the state of validating a script against an environment,
+/// and collects any issues discovered along the way.
+///
+/// A script is walked twice: first to collect anything the script may define
+/// (e.g. variables, functions, or file descriptors) from *anywhere* within it,
+/// and then once more to report any issues. Thus implementations of `Validate`
+/// should always record definitions, and leave it up to the `check_*` methods
+/// to determine whether an issue should be reported.
+#[derive(Debug)]
+pub struct Validator<'a, E: ?Sized> {
+    env: &'a E,
+    collecting: bool,
+    opaque: bool,
+    vars: HashSet<String>,
+    functions: HashSet<String>,
+    fds: HashSet<Fd>,
+    issues: Vec<ValidationIssue>,
+}
+
+impl<'a, E: ?Sized> Validator<'a, E> {
+    fn new(env: &'a E) -> Self {
+        Self {
+            env,
+            collecting: true,
+            opaque: false,
+            vars: HashSet::new(),
+            functions: HashSet::new(),
+            fds: HashSet::new(),
+            issues: Vec::new(),
+        }
+    }
+
+    /// Get a reference to the environment the script is validated against.
+    pub fn env(&self) -> &'a E {
+        self.env
+    }
+
+    /// Records that the script may assign a variable.
+    pub fn assign_var(&mut self, name: &str) {
+        if !self.vars.contains(name) {
+            self.vars.insert(name.to_owned());
+        }
+    }
+
+    /// Records that the script may define a function.
+    pub fn define_function(&mut self, name: &str) {
+        if !self.functions.contains(name) {
+            self.functions.insert(name.to_owned());
+        }
+    }
+
+    /// Records that the script may open a file descriptor (e.g. via a redirect).
+    pub fn open_fd(&mut self, fd: Fd) {
+        self.fds.insert(fd);
+    }
+
+    fn report(&mut self, issue: ValidationIssue) {
+        if !self.collecting {
+            self.issues.push(issue);
+        }
+    }
+}
+
+impl<'a, E> Validator<'a, E>
+where
+    E: ?Sized + ShellOptionsEnvironment + VariableEnvironment,
+    E::VarName: Borrow<String>,
+{
+    /// Checks a variable which is used in a way that would result in an error
+    /// if it were unset while the `nounset` option is enabled.
+    pub fn check_var(&mut self, name: &str) {
+        if self.collecting || self.opaque || !self.env.options().nounset {
+            return;
+        }
+
+        if !self.vars.contains(name) && self.env.var(&name.to_owned()).is_none() {
+            self.report(ValidationIssue::UnsetParameter(name.to_owned()));
+        }
+    }
+}
+
+impl<'a, E> Validator<'a, E>
+where
+    E: ?Sized + FileDescEnvironment,
+{
+    /// Checks that a file descriptor will be open for reading.
+    pub fn check_fd_readable(&mut self, fd: Fd) {
+        if !self.fd_allows(fd, |perms| perms.readable()) {
+            self.report(ValidationIssue::UnreadableFileDesc(fd));
+        }
+    }
+
+    /// Checks that a file descriptor will be open for writing.
+    pub fn check_fd_writable(&mut self, fd: Fd) {
+        if !self.fd_allows(fd, |perms| perms.writable()) {
+            self.report(ValidationIssue::UnwritableFileDesc(fd));
+        }
+    }
+
+    fn fd_allows<F>(&self, fd: Fd, f: F) -> bool
+    where
+        F: FnOnce(Permissions) -> bool,
+    {
+        if self.fds.contains(&fd) {
+            return true;
+        }
+
+        self.env.file_desc(fd).is_some_and(|(_, perms)| f(perms))
+    }
+}
+
+impl<'a, E> Validator<'a, E>
+where
+    E: ?Sized
+        + BuiltinEnvironment
+        + FunctionEnvironment
+        + VariableEnvironment
+        + WorkingDirectoryEnvironment,
+    E::BuiltinName: StringWrapper,
+    E::FnName: StringWrapper,
+    E::VarName: Borrow<String>,
+    E::Var: Borrow<String>,
+{
+    /// Checks that a command will resolve to a function, a builtin utility, or an
+    /// executable found in one of the `$PATH` directories.
+    ///
+    /// A name of `None` indicates that the command's name cannot be determined
+    /// statically (e.g. it is the result of a parameter expansion), in which case
+    /// the command is assumed to do anything (e.g. assign arbitrary variables).
+    /// Likewise for any functions defined by the environment, or any builtins which
+    /// run arbitrary commands (e.g. `eval`).
+    ///
+    /// Note that looking up executables performs blocking I/O.
+    pub fn check_command(&mut self, name: Option<&str>) {
+        let name = match name {
+            Some(name) => name,
+            None => {
+                self.opaque = true;
+                return;
+            }
+        };
+
+        if self.collecting {
+            let env_function = self
+                .env
+                .function_names()
+                .into_iter()
+                .any(|f| f.as_str() == name);
+
+            if env_function || OPAQUE_COMMANDS.contains(&name) {
+                self.opaque = true;
+            }
+
+            return;
+        }
+
+        if self.functions.contains(name) || name.contains(std::path::is_separator) {
+            return;
+        }
+
+        let found = complete_command(name, self.env)
+            .into_iter()
+            .any(|cmd| cmd.name == name);
+
+        if !found {
+            self.report(ValidationIssue::UnknownCommand(name.to_owned()));
+        }
+    }
+}
+
+/// A trait for statically validating a command (or any of its parts) against
+/// an environment, without evaluating or running anything.
+pub trait Validate<E: ?Sized> {
+    /// Validates this node (and any of its children), recording any definitions
+    /// and issues it encounters with the `validator`.
+    fn validate(&self, validator: &mut Validator<'_, E>);
+
+    /// Returns the value this node would always evaluate to, if it is made up
+    /// solely of literals (e.g. a word without any parameters or globs).
+    fn literal(&self) -> Option<String> {
+        None
+    }
+}
+
+impl<'a, T: ?Sized + Validate<E>, E: ?Sized> Validate<E> for &'a T {
+    fn validate(&self, validator: &mut Validator<'_, E>) {
+        (**self).validate(validator)
+    }
+
+    fn literal(&self) -> Option<String> {
+        (**self).literal()
+    }
+}
+
+impl<T: ?Sized + Validate<E>, E: ?Sized> Validate<E> for Box<T> {
+    fn validate(&self, validator: &mut Validator<'_, E>) {
+        (**self).validate(validator)
+    }
+
+    fn literal(&self) -> Option<String> {
+        (**self).literal()
+    }
+}
+
+impl<T: ?Sized + Validate<E>, E: ?Sized> Validate<E> for Rc<T> {
+    fn validate(&self, validator: &mut Validator<'_, E>) {
+        (**self).validate(validator)
+    }
+
+    fn literal(&self) -> Option<String> {
+        (**self).literal()
+    }
+}
+
+impl<T: ?Sized + Validate<E>, E: ?Sized> Validate<E> for Arc<T> {
+    fn validate(&self, validator: &mut Validator<'_, E>) {
+        (**self).validate(validator)
+    }
+
+    fn literal(&self) -> Option<String> {
+        (**self).literal()
+    }
+}
+
+/// Statically validates a script against an environment, returning any issues
+/// which are sure to arise if the script were executed in that environment, in
+/// the order they appear in the script.
+///
+/// Currently, the following issues are detected:
+/// * using parameters which are never set while the `nounset` option is enabled,
+/// * duplicating file descriptors which are not open (with the right permissions),
+/// * invoking commands which cannot be found.
+///
+/// Note that looking up executables performs blocking I/O.
+pub fn validate<T, E>(cmds: &[T], env: &E) -> Vec<ValidationIssue>
+where
+    T: Validate<E>,
+    E: ?Sized,
+{
+    let mut validator = Validator::new(env);
+
+    for cmd in cmds {
+        cmd.validate(&mut validator);
+    }
+
+    validator.collecting = false;
+    for cmd in cmds {
+        cmd.validate(&mut validator);
+    }
+
+    validator.issues
+}
@@ -0,0 +1,533 @@
+//! This module defines various `Validate` implementations on AST types defined by
+//! the `conch-parser` crate.
+
+use crate::env::{
+    BuiltinEnvironment, FileDescEnvironment, FunctionEnvironment, ShellOptionsEnvironment,
+    StringWrapper, VariableEnvironment, WorkingDirectoryEnvironment,
+};
+use crate::validate::{Validate, Validator};
+use crate::{Fd, STDIN_FILENO, STDOUT_FILENO};
+use conch_parser::ast;
+use std::borrow::Borrow;
+
+impl<T, E> Validate<E> for ast::TopLevelCommand<T>
+where
+    T: StringWrapper,
+    E: ?Sized
+        + BuiltinEnvironment
+        + FileDescEnvironment
+        + FunctionEnvironment
+        + ShellOptionsEnvironment
+        + VariableEnvironment
+        + WorkingDirectoryEnvironment,
+    E::BuiltinName: StringWrapper,
+    E::FnName: StringWrapper,
+    E::VarName: Borrow<String>,
+    E::Var: Borrow<String>,
+{
+    fn validate(&self, validator: &mut Validator<'_, E>) {
+        self.0.validate(validator)
+    }
+}
+
+impl<T, E> Validate<E> for ast::AtomicTopLevelCommand<T>
+where
+    T: StringWrapper,
+    E: ?Sized
+        + BuiltinEnvironment
+        + FileDescEnvironment
+        + FunctionEnvironment
+        + ShellOptionsEnvironment
+        + VariableEnvironment
+        + WorkingDirectoryEnvironment,
+    E::BuiltinName: StringWrapper,
+    E::FnName: StringWrapper,
+    E::VarName: Borrow<String>,
+    E::Var: Borrow<String>,
+{
+    fn validate(&self, validator: &mut Validator<'_, E>) {
+        self.0.validate(validator)
+    }
+}
+
+impl<T, E> Validate<E> for ast::TopLevelWord<T>
+where
+    T: StringWrapper,
+    E: ?Sized
+        + BuiltinEnvironment
+        + FileDescEnvironment
+        + FunctionEnvironment
+        + ShellOptionsEnvironment
+        + VariableEnvironment
+        + WorkingDirectoryEnvironment,
+    E::BuiltinName: StringWrapper,
+    E::FnName: StringWrapper,
+    E::VarName: Borrow<String>,
+    E::Var: Borrow<String>,
+{
+    fn validate(&self, validator: &mut Validator<'_, E>) {
+        self.0.validate(validator)
+    }
+
+    fn literal(&self) -> Option<String> {
+        Validate::<E>::literal(&self.0)
+    }
+}
+
+impl<T, E> Validate<E> for ast::AtomicTopLevelWord<T>
+where
+    T: StringWrapper,
+    E: ?Sized
+        + BuiltinEnvironment
+        + FileDescEnvironment
+        + FunctionEnvironment
+        + ShellOptionsEnvironment
+        + VariableEnvironment
+        + WorkingDirectoryEnvironment,
+    E::BuiltinName: StringWrapper,
+    E::FnName: StringWrapper,
+    E::VarName: Borrow<String>,
+    E::Var: Borrow<String>,
+{
+    fn validate(&self, validator: &mut Validator<'_, E>) {
+        self.0.validate(validator)
+    }
+
+    fn literal(&self) -> Option<String> {
+        Validate::<E>::literal(&self.0)
+    }
+}
+
+impl<T: Validate<E>, E: ?Sized> Validate<E> for ast::Command<T> {
+    fn validate(&self, validator: &mut Validator<'_, E>) {
+        match self {
+            ast::Command::Job(cmd) | ast::Command::List(cmd) => cmd.validate(validator),
+        }
+    }
+}
+
+impl<T: Validate<E>, E: ?Sized> Validate<E> for ast::AndOrList<T> {
+    fn validate(&self, validator: &mut Validator<'_, E>) {
+        self.first.validate(validator);
+
+        for cmd in &self.rest {
+            match cmd {
+                ast::AndOr::And(cmd) | ast::AndOr::Or(cmd) => cmd.validate(validator),
+            }
+        }
+    }
+}
+
+impl<T: Validate<E>, E: ?Sized> Validate<E> for ast::ListableCommand<T> {
+    fn validate(&self, validator: &mut Validator<'_, E>) {
+        match self {
+            ast::ListableCommand::Single(cmd) => cmd.validate(validator),
+            ast::ListableCommand::Pipe(_, cmds) => {
+                for cmd in cmds {
+                    cmd.validate(validator);
+                }
+            }
+        }
+    }
+}
+
+impl<N, S, C, F, E> Validate<E> for ast::PipeableCommand<N, S, C, F>
+where
+    N: StringWrapper,
+    S: Validate<E>,
+    C: Validate<E>,
+    F: Validate<E>,
+    E: ?Sized,
+{
+    fn validate(&self, validator: &mut Validator<'_, E>) {
+        match self {
+            ast::PipeableCommand::Simple(cmd) => cmd.validate(validator),
+            ast::PipeableCommand::Compound(cmd) => cmd.validate(validator),
+            ast::PipeableCommand::FunctionDef(name, body) => {
+                validator.define_function(name.as_str());
+                body.validate(validator);
+            }
+        }
+    }
+}
+
+impl<T: Validate<E>, R: Validate<E>, E: ?Sized> Validate<E> for ast::CompoundCommand<T, R> {
+    fn validate(&self, validator: &mut Validator<'_, E>) {
+        for redirect in &self.io {
+            redirect.validate(validator);
+        }
+
+        self.kind.validate(validator);
+    }
+}
+
+impl<V, W, C, E> Validate<E> for ast::CompoundCommandKind<V, W, C>
+where
+    V: StringWrapper,
+    W: Validate<E>,
+    C: Validate<E>,
+    E: ?Sized,
+{
+    fn validate(&self, validator: &mut Validator<'_, E>) {
+        use ast::CompoundCommandKind::*;
+
+        match self {
+            Brace(cmds) | Subshell(cmds) => validate_all(cmds, validator),
+
+            While(gbp) | Until(gbp) => {
+                validate_all(&gbp.guard, validator);
+                validate_all(&gbp.body, validator);
+            }
+
+            If {
+                conditionals,
+                else_branch,
+            } => {
+                for gbp in conditionals {
+                    validate_all(&gbp.guard, validator);
+                    validate_all(&gbp.body, validator);
+                }
+
+                if let Some(body) = else_branch {
+                    validate_all(body, validator);
+                }
+            }
+
+            For { var, words, body } => {
+                validator.assign_var(var.as_str());
+                if let Some(words) = words {
+                    validate_all(words, validator);
+                }
+                validate_all(body, validator);
+            }
+
+            Case { word, arms } => {
+                word.validate(validator);
+                for arm in arms {
+                    validate_all(&arm.patterns, validator);
+                    validate_all(&arm.body, validator);
+                }
+            }
+        }
+    }
+}
+
+impl<V, W, R, E> Validate<E> for ast::SimpleCommand<V, W, R>
+where
+    V: StringWrapper,
+    W: Validate<E>,
+    R: Validate<E>,
+    E: ?Sized
+        + BuiltinEnvironment
+        + FunctionEnvironment
+        + VariableEnvironment
+        + WorkingDirectoryEnvironment,
+    E::BuiltinName: StringWrapper,
+    E::FnName: StringWrapper,
+    E::VarName: Borrow<String>,
+    E::Var: Borrow<String>,
+{
+    fn validate(&self, validator: &mut Validator<'_, E>) {
+        for rova in &self.redirects_or_env_vars {
+            match rova {
+                ast::RedirectOrEnvVar::Redirect(r) => r.validate(validator),
+                ast::RedirectOrEnvVar::EnvVar(name, val) => {
+                    validator.assign_var(name.as_str());
+                    if let Some(val) = val {
+                        val.validate(validator);
+                    }
+                }
+            }
+        }
+
+        let mut words = Vec::new();
+        for rocw in &self.redirects_or_cmd_words {
+            match rocw {
+                ast::RedirectOrCmdWord::Redirect(r) => r.validate(validator),
+                ast::RedirectOrCmdWord::CmdWord(w) => {
+                    w.validate(validator);
+                    words.push(w);
+                }
+            }
+        }
+
+        let mut words = words.into_iter();
+        let name = match words.next() {
+            Some(name) => name.literal(),
+            None => return,
+        };
+
+        // NB: builtins like `read`, `export`, or `getopts` assign any
+        // variables named by their arguments, so we'll (conservatively)
+        // assume any argument which looks like a name might be assigned.
+        for word in words {
+            if let Some(arg) = word.literal() {
+                let name = arg.split('=').next().unwrap_or_default();
+                if is_name(name) {
+                    validator.assign_var(name);
+                }
+            }
+        }
+
+        validator.check_command(name.as_deref());
+    }
+}
+
+impl<W: Validate<E>, E: ?Sized + FileDescEnvironment> Validate<E> for ast::Redirect<W> {
+    fn validate(&self, validator: &mut Validator<'_, E>) {
+        use ast::Redirect::*;
+
+        match self {
+            Read(fd, path) | Heredoc(fd, path) => {
+                validator.open_fd(fd.unwrap_or(STDIN_FILENO));
+                path.validate(validator);
+            }
+            ReadWrite(fd, path) => {
+                validator.open_fd(fd.unwrap_or(STDIN_FILENO));
+                path.validate(validator);
+            }
+            Write(fd, path) | Append(fd, path) | Clobber(fd, path) => {
+                validator.open_fd(fd.unwrap_or(STDOUT_FILENO));
+                path.validate(validator);
+            }
+            DupRead(fd, src) => {
+                let fd = fd.unwrap_or(STDIN_FILENO);
+                src.validate(validator);
+                if let Some(src) = validate_dup::<_, E>(fd, src, validator) {
+                    validator.check_fd_readable(src);
+                }
+            }
+            DupWrite(fd, src) => {
+                let fd = fd.unwrap_or(STDOUT_FILENO);
+                src.validate(validator);
+                if let Some(src) = validate_dup::<_, E>(fd, src, validator) {
+                    validator.check_fd_writable(src);
+                }
+            }
+        }
+    }
+}
+
+impl<W: Validate<E>, E: ?Sized> Validate<E> for ast::ComplexWord<W> {
+    fn validate(&self, validator: &mut Validator<'_, E>) {
+        match self {
+            ast::ComplexWord::Single(w) => w.validate(validator),
+            ast::ComplexWord::Concat(words) => validate_all(words, validator),
+        }
+    }
+
+    fn literal(&self) -> Option<String> {
+        match self {
+            ast::ComplexWord::Single(w) => w.literal(),
+            ast::ComplexWord::Concat(words) => concat_literals::<_, E>(words),
+        }
+    }
+}
+
+impl<L: StringWrapper, W: Validate<E>, E: ?Sized> Validate<E> for ast::Word<L, W> {
+    fn validate(&self, validator: &mut Validator<'_, E>) {
+        match self {
+            ast::Word::Simple(w) => w.validate(validator),
+            ast::Word::DoubleQuoted(words) => validate_all(words, validator),
+            ast::Word::SingleQuoted(_) => {}
+        }
+    }
+
+    fn literal(&self) -> Option<String> {
+        match self {
+            ast::Word::Simple(w) => w.literal(),
+            ast::Word::DoubleQuoted(words) => concat_literals::<_, E>(words),
+            ast::Word::SingleQuoted(lit) => Some(lit.as_str().to_owned()),
+        }
+    }
+}
+
+impl<L, P, S, E> Validate<E> for ast::SimpleWord<L, P, S>
+where
+    L: StringWrapper,
+    P: Validate<E>,
+    S: Validate<E>,
+    E: ?Sized,
+{
+    fn validate(&self, validator: &mut Validator<'_, E>) {
+        match self {
+            ast::SimpleWord::Param(p) => p.validate(validator),
+            ast::SimpleWord::Subst(s) => s.validate(validator),
+            _ => {}
+        }
+    }
+
+    fn literal(&self) -> Option<String> {
+        match self {
+            ast::SimpleWord::Literal(lit) | ast::SimpleWord::Escaped(lit) => {
+                Some(lit.as_str().to_owned())
+            }
+            ast::SimpleWord::Colon => Some(String::from(":")),
+            _ => None,
+        }
+    }
+}
+
+impl<T, E> Validate<E> for ast::Parameter<T>
+where
+    T: StringWrapper,
+    E: ?Sized + ShellOptionsEnvironment + VariableEnvironment,
+    E::VarName: Borrow<String>,
+{
+    fn validate(&self, validator: &mut Validator<'_, E>) {
+        if let ast::Parameter::Var(name) = self {
+            validator.check_var(name.as_str());
+        }
+    }
+}
+
+impl<T, W, C, A, E> Validate<E> for ast::ParameterSubstitution<ast::Parameter<T>, W, C, A>
+where
+    T: StringWrapper,
+    W: Validate<E>,
+    C: Validate<E>,
+    A: Validate<E>,
+    E: ?Sized + ShellOptionsEnvironment + VariableEnvironment,
+    E::VarName: Borrow<String>,
+{
+    fn validate(&self, validator: &mut Validator<'_, E>) {
+        use ast::ParameterSubstitution::*;
+
+        match self {
+            Command(cmds) => validate_all(cmds, validator),
+            Len(p) => p.validate(validator),
+            Arith(a) => validate_opt(a, validator),
+
+            // NB: these substitutions handle unset parameters themselves
+            Default(_, _, word) | Error(_, _, word) | Alternative(_, _, word) => {
+                validate_opt(word, validator)
+            }
+            Assign(_, p, word) => {
+                if let ast::Parameter::Var(name) = p {
+                    validator.assign_var(name.as_str());
+                }
+                validate_opt(word, validator);
+            }
+
+            RemoveSmallestSuffix(p, word)
+            | RemoveLargestSuffix(p, word)
+            | RemoveSmallestPrefix(p, word)
+            | RemoveLargestPrefix(p, word) => {
+                p.validate(validator);
+                validate_opt(word, validator);
+            }
+        }
+    }
+}
+
+impl<T: StringWrapper, E: ?Sized> Validate<E> for ast::Arithmetic<T> {
+    fn validate(&self, validator: &mut Validator<'_, E>) {
+        use ast::Arithmetic::*;
+
+        match self {
+            Var(_) | Literal(_) => {}
+
+            PostIncr(var) | PostDecr(var) | PreIncr(var) | PreDecr(var) => {
+                validator.assign_var(var.as_str())
+            }
+
+            Assign(var, expr) => {
+                validator.assign_var(var.as_str());
+                expr.validate(validator);
+            }
+
+            UnaryPlus(expr) | UnaryMinus(expr) | LogicalNot(expr) | BitwiseNot(expr) => {
+                expr.validate(validator)
+            }
+
+            Pow(a, b)
+            | Mult(a, b)
+            | Div(a, b)
+            | Modulo(a, b)
+            | Add(a, b)
+            | Sub(a, b)
+            | ShiftLeft(a, b)
+            | ShiftRight(a, b)
+            | Less(a, b)
+            | LessEq(a, b)
+            | Great(a, b)
+            | GreatEq(a, b)
+            | Eq(a, b)
+            | NotEq(a, b)
+            | BitwiseAnd(a, b)
+            | BitwiseXor(a, b)
+            | BitwiseOr(a, b)
+            | LogicalAnd(a, b)
+            | LogicalOr(a, b) => {
+                a.validate(validator);
+                b.validate(validator);
+            }
+
+            Ternary(guard, a, b) => {
+                guard.validate(validator);
+                a.validate(validator);
+                b.validate(validator);
+            }
+
+            Sequence(exprs) => validate_all(exprs, validator),
+        }
+    }
+}
+
+fn validate_all<T: Validate<E>, E: ?Sized>(nodes: &[T], validator: &mut Validator<'_, E>) {
+    for node in nodes {
+        node.validate(validator);
+    }
+}
+
+fn validate_opt<T: Validate<E>, E: ?Sized>(node: &Option<T>, validator: &mut Validator<'_, E>) {
+    if let Some(node) = node {
+        node.validate(validator);
+    }
+}
+
+fn concat_literals<T: Validate<E>, E: ?Sized>(nodes: &[T]) -> Option<String> {
+    nodes.iter().map(|node| node.literal()).collect()
+}
+
+/// Records the file descriptor opened by duplicating `src` into `fd`, and returns
+/// the source descriptor which needs to be checked, if it is a literal number.
+fn validate_dup<W, E>(fd: Fd, src: &W, validator: &mut Validator<'_, E>) -> Option<Fd>
+where
+    W: Validate<E>,
+    E: ?Sized,
+{
+    let src = match src.literal() {
+        Some(src) => src,
+        None => {
+            validator.open_fd(fd);
+            return None;
+        }
+    };
+
+    // NB: closing a descriptor (e.g. `>&-`) never opens anything, and
+    // neither does duplicating a descriptor into itself (e.g. `>&1`)
+    match src.parse() {
+        Ok(src) if src == fd => Some(src),
+        Ok(src) => {
+            validator.open_fd(fd);
+            Some(src)
+        }
+        Err(_) if src == "-" => None,
+        Err(_) => {
+            validator.open_fd(fd);
+            None
+        }
+    }
+}
+
+/// Checks if a string is a valid variable name.
+fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c == '_' || c.is_ascii_alphabetic() => {}
+        _ => return false,
+    }
+
+    chars.all(|c| c == '_' || c.is_ascii_alphanumeric())
+}